@@ -1,6 +1,13 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use tracing::info;
+use shriven_q::core::error::ShrivenError;
+use shriven_q::core::replay::{
+    BacktestRecorder, DataSource, DateRange, HistoricalDataSource, ParallelBacktest, ReplayPacer,
+};
+use shriven_q::core::types::SymbolId;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(name = "shriven-backtest")]
@@ -14,33 +21,107 @@ struct Args {
     #[arg(long)]
     end_date: String,
 
-    /// Data source for historical data
+    /// Data source for historical data: a file (`.csv` or, with the `parquet`
+    /// feature, `.parquet`, either optionally gzipped as `.gz`), a directory
+    /// of such files, or a name resolved under `data/`
     #[arg(long, default_value = "binance")]
     data_source: String,
 
+    /// Replay speed multiplier against wall-clock time (omit to replay flat out)
+    #[arg(long)]
+    speed: Option<f64>,
+
     /// Strategy configuration file
     #[arg(long, default_value = "config/strategy.toml")]
     strategy: String,
 
-    /// Number of parallel workers
+    /// Number of parallel workers; symbols are backtested concurrently
     #[arg(long, default_value = "4")]
     workers: usize,
+
+    /// Comma-separated symbols that must be backtested together, e.g. a
+    /// pair; repeat for several groups
+    #[arg(long)]
+    group: Vec<String>,
+
+    /// Also write the results report as JSON to this file
+    #[arg(long)]
+    output: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt().init();
 
-    let args = Args::parse();
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => ShrivenError::from(e).report(),
+    }
+}
 
+async fn run(args: Args) -> Result<()> {
     info!("🔄 ShrivenQ Backtesting Engine");
     info!("├─ Period: {} to {}", args.start_date, args.end_date);
     info!("├─ Data Source: {}", args.data_source);
     info!("├─ Strategy: {}", args.strategy);
     info!("└─ Workers: {}", args.workers);
 
-    // TODO: Implement backtesting logic
-    info!("Backtesting not yet implemented");
+    let range = DateRange::parse(&args.start_date, &args.end_date)?;
+    let path = resolve_data_path(&args.data_source);
+    let mut source = HistoricalDataSource::open(&path, range)?;
+    info!(
+        "📂 Loaded {} events from {}",
+        source.remaining(),
+        path.display()
+    );
+
+    let report = match args.speed {
+        Some(speed) => {
+            // Wall-clock pacing needs the whole stream in order on one thread
+            if args.workers > 1 {
+                warn!("--speed replays on a single worker");
+            }
+            let mut pacer = ReplayPacer::new(speed)?;
+            let mut recorder = BacktestRecorder::new();
+            while let Some(event) = source.next_event() {
+                pacer.pace(event.timestamp_ns());
+                recorder.on_event(&event);
+            }
+            recorder.report()
+        }
+        None => {
+            let mut backtest = ParallelBacktest::new(args.workers);
+            for group in &args.group {
+                let symbols = group
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|symbol| !symbol.is_empty())
+                    .map(SymbolId::intern)
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("invalid symbol group '{}'", group))?;
+                backtest = backtest.group(symbols);
+            }
+            backtest.run(&source.into_events())
+        }
+    };
+    info!("📊 Replay complete");
+    println!("{report}");
+
+    if let Some(output) = &args.output {
+        let json = serde_json::to_string_pretty(&report.to_json())?;
+        std::fs::write(output, json)
+            .with_context(|| format!("failed to write report to {}", output.display()))?;
+        info!("💾 Report written to {}", output.display());
+    }
 
     Ok(())
 }
+
+fn resolve_data_path(data_source: &str) -> PathBuf {
+    let path = PathBuf::from(data_source);
+    if path.exists() {
+        path
+    } else {
+        PathBuf::from("data").join(data_source)
+    }
+}
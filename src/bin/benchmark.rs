@@ -1,5 +1,18 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use clap::Parser;
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use shriven_q::core::book::OrderBook;
+use shriven_q::core::events::Side;
+use shriven_q::core::memory::{MemoryAllocator, MemoryBackend, MemoryStats, SafePoolConfig};
+use shriven_q::core::orders::{Order, OrderKind, OrderSide};
+use shriven_q::core::portfolio::Portfolio;
+use shriven_q::core::risk::{RiskEngine, RiskLimits, WorkingOrders};
+use shriven_q::core::types::{Px, Qty, SymbolId, SymbolRegistry};
+use std::alloc::Layout;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use tracing::info;
 
 #[derive(Parser)]
@@ -10,7 +23,7 @@ struct Args {
     #[arg(long, default_value = "1000")]
     iterations: u32,
 
-    /// Benchmark type to run
+    /// Benchmark type to run (all, memory, book, risk, stats, symbols, ring)
     #[arg(long, default_value = "all")]
     benchmark_type: String,
 
@@ -21,6 +34,168 @@ struct Args {
     /// Enable verbose output
     #[arg(long)]
     verbose: bool,
+
+    /// Write results as JSON (read by `doc-tracker metrics --reconcile`)
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// Latency distribution of one operation, in nanoseconds
+#[derive(Debug, Serialize)]
+struct BenchmarkResult {
+    name: &'static str,
+    unit: &'static str,
+    samples: u64,
+    mean: f64,
+    p50: u64,
+    p99: u64,
+    max: u64,
+    throughput_per_sec: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchmarkReport {
+    iterations: u32,
+    results: Vec<BenchmarkResult>,
+}
+
+// Time `op` once per iteration and summarise the distribution
+fn measure(
+    name: &'static str,
+    iterations: u32,
+    mut op: impl FnMut(u32),
+) -> Result<BenchmarkResult> {
+    let mut histogram = Histogram::<u64>::new(3)?;
+    let started = Instant::now();
+    for i in 0..iterations {
+        let start = Instant::now();
+        op(i);
+        histogram.record(start.elapsed().as_nanos() as u64)?;
+    }
+    let total_secs = started.elapsed().as_secs_f64();
+
+    Ok(BenchmarkResult {
+        name,
+        unit: "ns",
+        samples: histogram.len(),
+        mean: histogram.mean(),
+        p50: histogram.value_at_quantile(0.50),
+        p99: histogram.value_at_quantile(0.99),
+        max: histogram.max(),
+        throughput_per_sec: if total_secs > 0.0 {
+            f64::from(iterations) / total_secs
+        } else {
+            0.0
+        },
+    })
+}
+
+fn bench_memory_allocation(iterations: u32) -> Result<BenchmarkResult> {
+    let backend = MemoryBackend::safe(SafePoolConfig::default())?;
+    let layout = Layout::from_size_align(64, 1)?;
+    measure("memory_allocation", iterations, |_| {
+        if let Ok(ptr) = backend.allocate(layout) {
+            backend.deallocate(ptr, layout);
+        }
+    })
+}
+
+fn bench_order_book_update(iterations: u32) -> Result<BenchmarkResult> {
+    let mut book = OrderBook::new(SymbolId::intern("BENCH")?);
+    measure("order_book_update", iterations, |i| {
+        let offset = i64::from(i % 64);
+        let (side, price) = if i % 2 == 0 {
+            (Side::Bid, Px::from_raw(10_000_000_000 - offset * 1_000_000))
+        } else {
+            (Side::Ask, Px::from_raw(10_001_000_000 + offset * 1_000_000))
+        };
+        let size = if i % 4 == 3 {
+            Qty::ZERO
+        } else {
+            Qty::from_raw(i64::from(i) + 1)
+        };
+        let _ = book.apply_level(side, price, size);
+    })
+}
+
+fn bench_risk_check(iterations: u32) -> Result<BenchmarkResult> {
+    let risk = RiskEngine::new(RiskLimits {
+        max_position: Qty::from_int(1_000),
+        max_order_size: Qty::from_int(10),
+    });
+    let portfolio = Portfolio::new();
+    let working = WorkingOrders::new();
+    let order = Order {
+        id: 1,
+        symbol: SymbolId::intern("BENCH")?,
+        side: OrderSide::Buy,
+        kind: OrderKind::Limit(Px::from_raw(10_000_000_000)),
+        qty: Qty::from_raw(100_000_000),
+        timestamp_ns: 0,
+    };
+    measure("risk_check", iterations, |_| {
+        let _ = risk.check(&order, &portfolio, &working);
+    })
+}
+
+/// Name-to-id lookup of an already interned symbol, as a feed parser does
+fn bench_symbol_lookup(iterations: u32) -> Result<BenchmarkResult> {
+    let registry = SymbolRegistry::global();
+    let names: Vec<String> = (0..64).map(|i| format!("BENCH{}", i)).collect();
+    registry.intern_all(&names)?;
+    measure("symbol_lookup", iterations, |i| {
+        let _ = registry.lookup(&names[i as usize % names.len()]);
+    })
+}
+
+// Push/pop round trip through the pool-backed SPSC ring and, for
+// comparison, crossbeam's bounded queue
+#[cfg(feature = "hft-unsafe")]
+fn bench_spsc_ring(iterations: u32) -> Result<Vec<BenchmarkResult>> {
+    use shriven_q::core::memory::{LockFreeMemoryPool, PoolConfig, SpscRing};
+    use std::sync::Arc;
+
+    // 512 u64 slots fill exactly one default 4 KiB chunk
+    const RING_CAPACITY: usize = 512;
+    let pool = Arc::new(LockFreeMemoryPool::new(PoolConfig::default())?);
+    let (mut producer, mut consumer) = SpscRing::<u64>::new(pool, RING_CAPACITY)?;
+    let ring = measure("spsc_ring_round_trip", iterations, |i| {
+        let _ = producer.push(u64::from(i));
+        let _ = consumer.pop();
+    })?;
+
+    let queue = crossbeam::queue::ArrayQueue::<u64>::new(RING_CAPACITY);
+    let crossbeam = measure("crossbeam_queue_round_trip", iterations, |i| {
+        let _ = queue.push(u64::from(i));
+        let _ = queue.pop();
+    })?;
+    Ok(vec![ring, crossbeam])
+}
+
+/// Sampled-stats recording rate compared against full recording
+const STATS_SAMPLE_EVERY: u64 = 64;
+
+// Per-call cost of `MemoryStats::record_allocation` while `threads - 1`
+// other threads record into the same stats, i.e. under lock contention
+fn bench_stats_recording(
+    name: &'static str,
+    stats: &MemoryStats,
+    iterations: u32,
+    threads: usize,
+) -> Result<BenchmarkResult> {
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    stats.record_allocation(64, 100);
+                }
+            });
+        }
+        let result = measure(name, iterations, |_| stats.record_allocation(64, 100));
+        done.store(true, Ordering::Relaxed);
+        result
+    })
 }
 
 #[tokio::main]
@@ -35,8 +210,63 @@ async fn main() -> Result<()> {
     info!("├─ Threads: {}", args.threads);
     info!("└─ Verbose: {}", args.verbose);
 
-    // TODO: Implement benchmark logic
-    info!("Benchmarking not yet implemented");
+    let run_all = args.benchmark_type == "all";
+    let mut results = Vec::new();
+    if run_all || args.benchmark_type == "memory" {
+        results.push(bench_memory_allocation(args.iterations)?);
+    }
+    if run_all || args.benchmark_type == "book" {
+        results.push(bench_order_book_update(args.iterations)?);
+    }
+    if run_all || args.benchmark_type == "risk" {
+        results.push(bench_risk_check(args.iterations)?);
+    }
+    if run_all || args.benchmark_type == "symbols" {
+        results.push(bench_symbol_lookup(args.iterations)?);
+    }
+    if run_all || args.benchmark_type == "stats" {
+        results.push(bench_stats_recording(
+            "stats_record_full",
+            &MemoryStats::new(),
+            args.iterations,
+            args.threads,
+        )?);
+        results.push(bench_stats_recording(
+            "stats_record_sampled",
+            &MemoryStats::with_sampling(STATS_SAMPLE_EVERY),
+            args.iterations,
+            args.threads,
+        )?);
+    }
+    #[cfg(feature = "hft-unsafe")]
+    if run_all || args.benchmark_type == "ring" {
+        results.extend(bench_spsc_ring(args.iterations)?);
+    }
+    if results.is_empty() {
+        bail!("Unknown benchmark type '{}'", args.benchmark_type);
+    }
+
+    for result in &results {
+        info!(
+            "{}: p50 {}ns, p99 {}ns, max {}ns ({:.0} ops/sec)",
+            result.name, result.p50, result.p99, result.max, result.throughput_per_sec
+        );
+        if args.verbose {
+            info!(
+                "   mean {:.1}ns over {} samples",
+                result.mean, result.samples
+            );
+        }
+    }
+
+    if let Some(path) = &args.output {
+        let report = BenchmarkReport {
+            iterations: args.iterations,
+            results,
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        info!("📄 Results written to {}", path.display());
+    }
 
     Ok(())
 }
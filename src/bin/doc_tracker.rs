@@ -4,10 +4,14 @@
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::fs;
 use std::error::Error;
 use std::fmt;
+use std::cell::OnceCell;
+use std::sync::mpsc;
+use std::time::Duration;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
 #[derive(Parser)]
 #[command(name = "doc-tracker")]
@@ -31,12 +35,21 @@ enum Commands {
         /// Include source code references
         #[arg(long)]
         include_source: bool,
+        /// Only reparse files whose checksum differs from the existing output graph
+        #[arg(long)]
+        incremental: bool,
     },
-    /// Watch for changes and auto-propagate (future implementation)
+    /// Watch for changes and auto-propagate
     Watch {
         /// Documentation root directory  
         #[arg(short, long, default_value = "docs")]
         docs_path: PathBuf,
+        /// Reference graph to diff against and keep updated
+        #[arg(short, long, default_value = "docs/.doc-graph.json")]
+        graph: PathBuf,
+        /// Quiet period before a burst of changes is processed (ms)
+        #[arg(long, default_value = "500")]
+        debounce_ms: u64,
         /// Dry run mode (show what would be changed)
         #[arg(long)]
         dry_run: bool,
@@ -64,6 +77,9 @@ enum Commands {
         /// Output format (json, markdown)
         #[arg(long, default_value = "markdown")]
         format: String,
+        /// Check performance claims against results from `shriven-benchmark --output`
+        #[arg(long)]
+        reconcile: Option<PathBuf>,
     },
 }
 
@@ -93,9 +109,18 @@ pub struct DocumentationGraph {
     pub references: Vec<DocReference>,
     pub files: HashMap<PathBuf, DocMetadata>,
     pub broken_links: Vec<DocReference>,
+    /// References whose target exists but is stale in some other way
+    #[serde(default)]
+    pub reference_warnings: Vec<ReferenceWarning>,
     pub metrics: GraphMetrics,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReferenceWarning {
+    pub reference: DocReference,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DocMetadata {
     pub title: String,
@@ -133,6 +158,12 @@ impl From<std::io::Error> for DocError {
     }
 }
 
+impl From<notify::Error> for DocError {
+    fn from(err: notify::Error) -> Self {
+        DocError { message: format!("Filesystem watch error: {}", err) }
+    }
+}
+
 impl From<serde_json::Error> for DocError {
     fn from(err: serde_json::Error) -> Self {
         DocError { message: err.to_string() }
@@ -142,37 +173,64 @@ impl From<serde_json::Error> for DocError {
 pub struct DocumentationScanner {
     #[allow(dead_code)]
     include_source: bool,
+    // Rust sources under `src/`, loaded on first symbol lookup
+    sources: OnceCell<Vec<String>>,
 }
 
 impl DocumentationScanner {
     pub fn new(include_source: bool) -> Self {
-        Self { include_source }
+        Self { include_source, sources: OnceCell::new() }
     }
 
     pub fn scan_directory(&self, docs_path: &Path) -> Result<DocumentationGraph, DocError> {
+        self.scan_incremental(docs_path, None).map(|(graph, _)| graph)
+    }
+
+    /// Scan `docs_path`, reusing the references `previous` recorded for files
+    /// whose checksum is unchanged. Every reference is still re-validated, so
+    /// links broken by moves elsewhere in the tree are caught. Returns the
+    /// graph and the number of files that were not reparsed.
+    pub fn scan_incremental(&self, docs_path: &Path, previous: Option<&DocumentationGraph>) -> Result<(DocumentationGraph, usize), DocError> {
         let mut references = Vec::new();
         let mut files = HashMap::new();
         let mut broken_links = Vec::new();
+        let mut reference_warnings = Vec::new();
 
         println!("🔍 Scanning documentation in {}", docs_path.display());
 
         // Find all markdown files
         let md_files = self.find_markdown_files(docs_path)?;
+        let mut reused = 0;
         
         for file_path in md_files {
             let content = fs::read_to_string(&file_path)
                 .map_err(|e| DocError { message: format!("Failed to read {}: {}", file_path.display(), e) })?;
-            
-            // Extract metadata
-            let metadata = self.extract_metadata(&file_path, &content);
-            files.insert(file_path.clone(), metadata);
+            let checksum = self.calculate_checksum(&content);
 
-            // Extract references
-            let file_refs = self.extract_references(&file_path, &content, docs_path);
+            let unchanged = previous.and_then(|graph| {
+                graph.files
+                    .get(&file_path)
+                    .filter(|metadata| metadata.checksum == checksum)
+                    .map(|metadata| (metadata.clone(), graph.references_from(&file_path)))
+            });
+            let (metadata, file_refs) = match unchanged {
+                Some(reused_scan) => {
+                    reused += 1;
+                    reused_scan
+                }
+                None => (
+                    self.extract_metadata(&file_path, &content),
+                    self.extract_references(&file_path, &content, docs_path),
+                ),
+            };
+            files.insert(file_path.clone(), metadata);
             
             // Validate references and categorize
             for reference in file_refs {
                 if self.validate_reference(&reference, docs_path) {
+                    if let Some(message) = self.reference_warning(&reference, docs_path) {
+                        reference_warnings.push(ReferenceWarning { reference: reference.clone(), message });
+                    }
                     references.push(reference);
                 } else {
                     broken_links.push(reference);
@@ -183,12 +241,24 @@ impl DocumentationScanner {
         // Calculate metrics
         let metrics = self.calculate_metrics(&references, &files, &broken_links);
 
-        Ok(DocumentationGraph {
+        let graph = DocumentationGraph {
             references,
             files,
             broken_links,
+            reference_warnings,
             metrics,
-        })
+        };
+        Ok((graph, reused))
+    }
+
+    /// Read one markdown file and extract its metadata and references
+    pub fn scan_file(&self, file_path: &Path, docs_path: &Path) -> Result<(DocMetadata, Vec<DocReference>), DocError> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| DocError { message: format!("Failed to read {}: {}", file_path.display(), e) })?;
+
+        let metadata = self.extract_metadata(file_path, &content);
+        let references = self.extract_references(file_path, &content, docs_path);
+        Ok((metadata, references))
     }
 
     fn find_markdown_files(&self, dir: &Path) -> Result<Vec<PathBuf>, DocError> {
@@ -225,6 +295,8 @@ impl DocumentationScanner {
         
         // Markdown links: [text](path)
         let link_regex = regex::Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap();
+        // Symbol paths: `MemoryStats::record_allocation`, `ReferenceType::DirectLink`
+        let symbol_regex = regex::Regex::new(r"`([A-Z][A-Za-z0-9_]*(?:::[A-Za-z_][A-Za-z0-9_]*)+)(?:\(\))?`").ok();
         
         for (line_num, line) in content.lines().enumerate() {
             for cap in link_regex.captures_iter(line) {
@@ -261,6 +333,21 @@ impl DocumentationScanner {
                 });
             }
 
+            for cap in symbol_regex.iter().flat_map(|re| re.captures_iter(line)) {
+                let Some(symbol) = cap.get(1) else {
+                    continue;
+                };
+
+                references.push(DocReference {
+                    source_file: file_path.to_path_buf(),
+                    target_path: format!("symbol:{}", symbol.as_str()),
+                    reference_type: ReferenceType::FunctionName,
+                    line_number: line_num + 1,
+                    context: line.to_string(),
+                    anchor: None,
+                });
+            }
+
             // Performance metrics: "< 100μs", "1000+ orders/second"
             let perf_regex = regex::Regex::new(r"([<>≤≥]?\s*\d+(?:\.\d+)?[+]?)\s*(μs|ms|ns|orders?/second|messages?/second)").unwrap();
             for cap in perf_regex.captures_iter(line) {
@@ -344,27 +431,7 @@ impl DocumentationScanner {
 
     fn validate_reference(&self, reference: &DocReference, docs_root: &Path) -> bool {
         match reference.reference_type {
-            ReferenceType::DirectLink => {
-                let target_path = if reference.target_path.starts_with("/") {
-                    docs_root.join(&reference.target_path[1..])
-                } else {
-                    reference.source_file.parent().unwrap().join(&reference.target_path)
-                };
-                
-                // Remove anchor for file existence check
-                let file_path = if let Some(pos) = reference.target_path.find('#') {
-                    let path_without_anchor = &reference.target_path[..pos];
-                    if path_without_anchor.starts_with("/") {
-                        docs_root.join(&path_without_anchor[1..])
-                    } else {
-                        reference.source_file.parent().unwrap().join(path_without_anchor)
-                    }
-                } else {
-                    target_path
-                };
-                
-                file_path.exists()
-            }
+            ReferenceType::DirectLink => resolve_link(reference, docs_root).exists(),
             ReferenceType::CodeReference => {
                 // For code references, check if the file exists in src/
                 let code_path = reference.target_path.split(':').next().unwrap();
@@ -375,6 +442,87 @@ impl DocumentationScanner {
         }
     }
 
+    /// Problems with a reference whose target exists, e.g. a link to a
+    /// heading that has since been renamed
+    fn reference_warning(&self, reference: &DocReference, docs_root: &Path) -> Option<String> {
+        match reference.reference_type {
+            ReferenceType::DirectLink => {
+                let anchor = reference.anchor.as_deref().filter(|a| !a.is_empty())?;
+                let target = resolve_link(reference, docs_root);
+                if target.extension().and_then(|e| e.to_str()) != Some("md") {
+                    return None;
+                }
+                let content = fs::read_to_string(&target).ok()?;
+                let anchors = heading_anchors(&content);
+                let wanted = anchor.to_lowercase();
+                if anchors.contains(&wanted) {
+                    return None;
+                }
+
+                let closest = anchors
+                    .iter()
+                    .map(|candidate| (candidate, edit_distance(&wanted, candidate)))
+                    .min_by_key(|(_, distance)| *distance)
+                    .filter(|(candidate, distance)| *distance * 3 <= candidate.chars().count().max(wanted.chars().count()));
+                Some(match closest {
+                    Some((candidate, _)) => format!("Missing anchor #{} in {} (did you mean #{}?)", anchor, target.display(), candidate),
+                    None => format!("Missing anchor #{} in {}", anchor, target.display()),
+                })
+            }
+            ReferenceType::CodeReference => {
+                // `src/file.rs:123` must still have a line 123
+                let (code_path, line) = reference.target_path.split_once(':')?;
+                let line: usize = line.parse().ok()?;
+                let src_path = docs_root.parent().unwrap_or(docs_root).join(code_path);
+                let line_count = fs::read_to_string(&src_path).ok()?.lines().count();
+                (line == 0 || line > line_count).then(|| {
+                    format!("Line {} is out of range for {} ({} lines)", line, code_path, line_count)
+                })
+            }
+            ReferenceType::FunctionName => {
+                let symbol = reference.target_path.strip_prefix("symbol:")?;
+                (!self.symbol_defined(symbol, docs_root)).then(|| format!("Symbol {} not found under src/", symbol))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `Owner::member` is defined somewhere under `src/`, judged by
+    /// a type definition for `Owner` plus a matching `fn` or item name
+    fn symbol_defined(&self, symbol: &str, docs_root: &Path) -> bool {
+        let Some((owner_path, member)) = symbol.rsplit_once("::") else {
+            return true;
+        };
+        let owner = owner_path.rsplit("::").next().unwrap_or(owner_path);
+        if EXTERNAL_TYPES.contains(&owner) {
+            return true;
+        }
+        let sources = self.sources.get_or_init(|| {
+            let src_root = docs_root.parent().unwrap_or(docs_root).join("src");
+            load_rust_sources(&src_root)
+        });
+
+        let owner = regex::escape(owner);
+        let member = regex::escape(member);
+        let definition = regex::Regex::new(&format!(r"\b(?:struct|enum|trait|type|union)\s+{}\b", owner));
+        let implementation = regex::Regex::new(&format!(r"\bimpl\b[^{{;]*\b{}\b", owner));
+        let item = if member.starts_with(char::is_uppercase) {
+            // Enum variants, associated consts and types
+            regex::Regex::new(&format!(r"\b{}\b", member))
+        } else {
+            regex::Regex::new(&format!(r"\bfn\s+{}\b", member))
+        };
+        let (Ok(definition), Ok(implementation), Ok(item)) = (definition, implementation, item) else {
+            return true;
+        };
+
+        sources.iter().any(|source| definition.is_match(source))
+            && sources
+                .iter()
+                .filter(|source| definition.is_match(source) || implementation.is_match(source))
+                .any(|source| item.is_match(source))
+    }
+
     fn calculate_metrics(&self, references: &[DocReference], files: &HashMap<PathBuf, DocMetadata>, broken_links: &[DocReference]) -> GraphMetrics {
         let mut reference_type_counts = HashMap::new();
         let mut file_reference_counts: HashMap<String, usize> = HashMap::new();
@@ -401,6 +549,488 @@ impl DocumentationScanner {
     }
 }
 
+/// File a `DirectLink` points at, without its anchor. Absolute links are
+/// rooted at the documentation directory; a bare `#anchor` is the source file.
+fn resolve_link(reference: &DocReference, docs_root: &Path) -> PathBuf {
+    let path = reference.target_path.split('#').next().unwrap_or_default();
+    if path.is_empty() {
+        return reference.source_file.clone();
+    }
+    match path.strip_prefix('/') {
+        Some(rooted) => docs_root.join(rooted),
+        None => reference.source_file.parent().unwrap_or(Path::new("")).join(path),
+    }
+}
+
+// Standard library types docs commonly mention; never defined under src/
+const EXTERNAL_TYPES: &[&str] = &[
+    "Arc", "AtomicBool", "AtomicU64", "AtomicUsize", "BTreeMap", "Box", "Duration", "HashMap",
+    "HashSet", "Instant", "Layout", "Mutex", "NonNull", "Option", "Rc", "Result", "RwLock",
+    "String", "Vec", "VecDeque",
+];
+
+/// Contents of every `.rs` file under `dir`; unreadable files are skipped
+fn load_rust_sources(dir: &Path) -> Vec<String> {
+    let mut sources = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "rs")
+                && let Ok(source) = fs::read_to_string(&path)
+            {
+                sources.push(source);
+            }
+        }
+    }
+    sources
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation dropped,
+/// spaces turned into hyphens
+fn slugify(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Anchors of every ATX heading outside fenced code blocks. Repeated
+/// headings get `-1`, `-2`, ... suffixes as on GitHub.
+fn heading_anchors(content: &str) -> HashSet<String> {
+    let mut anchors = HashSet::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level == 0 || level > 6 {
+            continue;
+        }
+        let Some(text) = trimmed[level..].strip_prefix(' ') else {
+            continue;
+        };
+
+        let slug = slugify(text.trim_end().trim_end_matches('#'));
+        let count = seen.entry(slug.clone()).or_insert(0);
+        anchors.insert(if *count == 0 { slug } else { format!("{}-{}", slug, count) });
+        *count += 1;
+    }
+
+    anchors
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Path to `target` relative to the directory `from_dir`
+fn relative_path(from_dir: &Path, target: &Path) -> PathBuf {
+    let from = normalize_path(from_dir);
+    let target = normalize_path(target);
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+impl DocumentationGraph {
+    pub fn load(path: &Path) -> Result<Self, DocError> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| DocError { message: format!("Failed to read {}: {}", path.display(), e) })?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DocError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Valid and broken references originating in `file`
+    fn references_from(&self, file: &Path) -> Vec<DocReference> {
+        self.references
+            .iter()
+            .chain(&self.broken_links)
+            .filter(|r| r.source_file == file)
+            .cloned()
+            .collect()
+    }
+
+    fn remove_file(&mut self, file: &Path) {
+        self.files.remove(file);
+        self.references.retain(|r| r.source_file != file);
+        self.broken_links.retain(|r| r.source_file != file);
+        self.reference_warnings.retain(|w| w.reference.source_file != file);
+    }
+
+    /// Re-check every reference against the current tree. A file that moved
+    /// or disappeared can break links in documents that did not change.
+    fn revalidate(&mut self, scanner: &DocumentationScanner, docs_path: &Path) {
+        let all: Vec<DocReference> = self.references.drain(..).chain(self.broken_links.drain(..)).collect();
+        self.reference_warnings.clear();
+        for reference in all {
+            if scanner.validate_reference(&reference, docs_path) {
+                if let Some(message) = scanner.reference_warning(&reference, docs_path) {
+                    self.reference_warnings.push(ReferenceWarning { reference: reference.clone(), message });
+                }
+                self.references.push(reference);
+            } else {
+                self.broken_links.push(reference);
+            }
+        }
+        self.metrics = scanner.calculate_metrics(&self.references, &self.files, &self.broken_links);
+    }
+}
+
+// Identity of a reference for diffing scans
+fn reference_key(reference: &DocReference) -> (PathBuf, usize, String) {
+    (reference.source_file.clone(), reference.line_number, reference.target_path.clone())
+}
+
+/// A link rewrite proposed after a document moved
+#[derive(Debug, Clone)]
+pub struct Propagation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub old_target: String,
+    pub new_target: String,
+    /// 1.0 for identical content, lower for a filename-only match
+    pub confidence: f32,
+}
+
+/// Outcome of processing one batch of filesystem changes
+#[derive(Debug, Default)]
+pub struct WatchReport {
+    pub changed_files: Vec<PathBuf>,
+    pub added: Vec<DocReference>,
+    pub removed: Vec<DocReference>,
+    pub new_broken: Vec<DocReference>,
+    pub propagations: Vec<Propagation>,
+    pub applied: usize,
+}
+
+impl WatchReport {
+    pub fn print(&self, dry_run: bool) {
+        println!("📝 {} file(s) changed", self.changed_files.len());
+        for reference in &self.added {
+            println!("  ➕ {}:{} → {}", reference.source_file.display(), reference.line_number, reference.target_path);
+        }
+        for reference in &self.removed {
+            println!("  ➖ {}:{} → {}", reference.source_file.display(), reference.line_number, reference.target_path);
+        }
+        for reference in &self.new_broken {
+            println!("  ❌ Broken link {}:{} → {}", reference.source_file.display(), reference.line_number, reference.target_path);
+        }
+        for propagation in &self.propagations {
+            println!("  🔁 {}{}:{} {} → {} (confidence {:.2})",
+                     if dry_run { "[dry-run] " } else { "" },
+                     propagation.file.display(),
+                     propagation.line,
+                     propagation.old_target,
+                     propagation.new_target,
+                     propagation.confidence);
+        }
+        if !dry_run && !self.propagations.is_empty() {
+            println!("  ✅ Applied {} of {} propagation(s)", self.applied, self.propagations.len());
+        }
+    }
+}
+
+/// Watches a documentation tree and keeps the stored reference graph current.
+///
+/// Changes arrive as filesystem notifications; bursts of events (editors
+/// writing temp files, `git checkout`) are coalesced until the tree has been
+/// quiet for the debounce interval.
+pub struct DocumentationWatcher {
+    docs_path: PathBuf,
+    // `docs_path` as the notification backend may report it
+    canonical_docs_path: PathBuf,
+    graph_path: PathBuf,
+    scanner: DocumentationScanner,
+    graph: DocumentationGraph,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    // Dropping it ends the watch
+    _watcher: RecommendedWatcher,
+    debounce: Duration,
+}
+
+impl DocumentationWatcher {
+    /// Start from the stored graph, or a fresh scan if there is none yet.
+    /// The watch is in place when this returns.
+    pub fn new(docs_path: &Path, graph_path: &Path, debounce: Duration) -> Result<Self, DocError> {
+        let scanner = DocumentationScanner::new(false);
+        let graph = match DocumentationGraph::load(graph_path) {
+            Ok(graph) => graph,
+            Err(_) => scanner.scan_directory(docs_path)?,
+        };
+
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(docs_path, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            docs_path: docs_path.to_path_buf(),
+            canonical_docs_path: fs::canonicalize(docs_path)?,
+            graph_path: graph_path.to_path_buf(),
+            scanner,
+            graph,
+            events,
+            _watcher: watcher,
+            debounce,
+        })
+    }
+
+    /// Block until markdown files change and then settle
+    pub fn wait_for_changes(&mut self) -> Result<Vec<PathBuf>, DocError> {
+        let mut changed: HashSet<PathBuf> = HashSet::new();
+        loop {
+            let event = if changed.is_empty() {
+                self.events.recv().map_err(|_| DocError { message: "Filesystem watcher stopped".to_string() })?
+            } else {
+                match self.events.recv_timeout(self.debounce) {
+                    Ok(event) => event,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        // Our own link rewrites notify too, but are already in the graph
+                        let mut changed: Vec<PathBuf> = changed.drain().filter(|path| !self.matches_graph(path)).collect();
+                        if changed.is_empty() {
+                            continue;
+                        }
+                        changed.sort();
+                        return Ok(changed);
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        return Err(DocError { message: "Filesystem watcher stopped".to_string() });
+                    }
+                }
+            };
+            let event = event?;
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in &event.paths {
+                changed.extend(self.affected_files(path));
+            }
+        }
+    }
+
+    // Whether `path` still holds the content the graph recorded for it
+    fn matches_graph(&self, path: &Path) -> bool {
+        let recorded = self.graph.files.get(path).map(|metadata| &metadata.checksum);
+        match fs::read_to_string(path) {
+            Ok(content) => recorded == Some(&self.scanner.calculate_checksum(&content)),
+            Err(_) => recorded.is_none(),
+        }
+    }
+
+    // Markdown files an event on `path` may have changed. A directory that
+    // moved or vanished takes the files the graph knew under it along.
+    fn affected_files(&self, path: &Path) -> Vec<PathBuf> {
+        let path = match path.strip_prefix(&self.canonical_docs_path) {
+            Ok(relative) if !path.starts_with(&self.docs_path) => self.docs_path.join(relative),
+            _ => path.to_path_buf(),
+        };
+        if path.extension().and_then(|e| e.to_str()) == Some("md") {
+            return vec![path];
+        }
+        let mut files: Vec<PathBuf> = self.graph.files.keys().filter(|file| file.starts_with(&path)).cloned().collect();
+        if path.is_dir() {
+            files.extend(self.scanner.find_markdown_files(&path).unwrap_or_default());
+        }
+        files
+    }
+
+    /// Rescan `changed` files, diff their references against the stored
+    /// graph, propose link rewrites for moved files and persist the result.
+    /// Rewrites at or above `auto_threshold` are applied unless `dry_run`.
+    pub fn process(&mut self, changed: &[PathBuf], dry_run: bool, auto_threshold: f32) -> Result<WatchReport, DocError> {
+        let mut report = WatchReport { changed_files: changed.to_vec(), ..WatchReport::default() };
+        let previously_broken: HashSet<_> = self.graph.broken_links.iter().map(reference_key).collect();
+        let mut vanished = Vec::new();
+        let mut appeared = Vec::new();
+
+        for path in changed {
+            let old_refs = self.graph.references_from(path);
+            let old_checksum = self.graph.files.get(path).map(|m| m.checksum.clone());
+            self.graph.remove_file(path);
+
+            if !path.exists() {
+                if let Some(checksum) = old_checksum {
+                    vanished.push((path.clone(), checksum));
+                }
+                report.removed.extend(old_refs);
+                continue;
+            }
+
+            let (metadata, new_refs) = self.scanner.scan_file(path, &self.docs_path)?;
+            if old_checksum.is_none() {
+                appeared.push((path.clone(), metadata.checksum.clone()));
+            }
+            self.graph.files.insert(path.clone(), metadata);
+
+            let old_keys: HashSet<_> = old_refs.iter().map(|r| (r.target_path.clone(), r.reference_type.clone())).collect();
+            let new_keys: HashSet<_> = new_refs.iter().map(|r| (r.target_path.clone(), r.reference_type.clone())).collect();
+            report.added.extend(new_refs.iter().filter(|r| !old_keys.contains(&(r.target_path.clone(), r.reference_type.clone()))).cloned());
+            report.removed.extend(old_refs.into_iter().filter(|r| !new_keys.contains(&(r.target_path.clone(), r.reference_type.clone()))));
+            self.graph.references.extend(new_refs);
+        }
+
+        self.graph.revalidate(&self.scanner, &self.docs_path);
+        report.propagations = self.propose_moves(&vanished, &appeared);
+
+        if !dry_run {
+            let accepted: Vec<&Propagation> = report.propagations.iter().filter(|p| p.confidence >= auto_threshold).collect();
+            report.applied = self.apply_propagations(&accepted)?;
+        }
+
+        report.new_broken = self.graph.broken_links
+            .iter()
+            .filter(|r| !previously_broken.contains(&reference_key(r)))
+            .cloned()
+            .collect();
+
+        if !dry_run {
+            self.graph.save(&self.graph_path)?;
+        }
+        Ok(report)
+    }
+
+    // Pair each vanished file with the new file it most likely became and
+    // propose rewriting every link that pointed at the old location
+    fn propose_moves(&self, vanished: &[(PathBuf, String)], appeared: &[(PathBuf, String)]) -> Vec<Propagation> {
+        let mut propagations = Vec::new();
+
+        for (old_path, old_checksum) in vanished {
+            let candidate = appeared
+                .iter()
+                .filter_map(|(new_path, new_checksum)| {
+                    let confidence: f32 = if new_checksum == old_checksum {
+                        1.0
+                    } else if new_path.file_name() == old_path.file_name() {
+                        0.7
+                    } else {
+                        return None;
+                    };
+                    Some((new_path, confidence))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((new_path, confidence)) = candidate else {
+                continue;
+            };
+
+            let old_normalized = normalize_path(old_path);
+            for reference in &self.graph.broken_links {
+                if reference.reference_type != ReferenceType::DirectLink
+                    || normalize_path(&resolve_link(reference, &self.docs_path)) != old_normalized
+                {
+                    continue;
+                }
+
+                let from_dir = reference.source_file.parent().unwrap_or(Path::new(""));
+                let mut new_target = relative_path(from_dir, new_path).to_string_lossy().into_owned();
+                if let Some(anchor) = &reference.anchor {
+                    new_target = format!("{}#{}", new_target, anchor);
+                }
+                propagations.push(Propagation {
+                    file: reference.source_file.clone(),
+                    line: reference.line_number,
+                    old_target: reference.target_path.clone(),
+                    new_target,
+                    confidence,
+                });
+            }
+        }
+
+        propagations
+    }
+
+    // Rewrite links in place, then rescan the edited files
+    fn apply_propagations(&mut self, propagations: &[&Propagation]) -> Result<usize, DocError> {
+        let mut applied = 0;
+        let mut edited = HashSet::new();
+
+        for propagation in propagations {
+            if rewrite_link(&propagation.file, propagation.line, &propagation.old_target, &propagation.new_target)? {
+                applied += 1;
+                edited.insert(propagation.file.clone());
+            }
+        }
+
+        for path in &edited {
+            self.graph.remove_file(path);
+            let (metadata, references) = self.scanner.scan_file(path, &self.docs_path)?;
+            self.graph.files.insert(path.clone(), metadata);
+            self.graph.references.extend(references);
+        }
+        if !edited.is_empty() {
+            self.graph.revalidate(&self.scanner, &self.docs_path);
+        }
+
+        Ok(applied)
+    }
+}
+
+/// Replace the `](old_target)` link on `line` (1-based) of `file`
+fn rewrite_link(file: &Path, line: usize, old_target: &str, new_target: &str) -> Result<bool, DocError> {
+    let content = fs::read_to_string(file)?;
+    let old_link = format!("]({})", old_target);
+    let new_link = format!("]({})", new_target);
+    let mut rewritten = false;
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if let Some(text) = line.checked_sub(1).and_then(|index| lines.get_mut(index))
+        && text.contains(&old_link)
+    {
+        *text = text.replacen(&old_link, &new_link, 1);
+        rewritten = true;
+    }
+
+    if rewritten {
+        let mut updated = lines.join("\n");
+        if content.ends_with('\n') {
+            updated.push('\n');
+        }
+        fs::write(file, updated)?;
+    }
+    Ok(rewritten)
+}
+
 pub struct DocumentationValidator;
 
 impl DocumentationValidator {
@@ -408,7 +1038,9 @@ impl DocumentationValidator {
         Self
     }
 
-    pub fn validate(&self, docs_path: &Path, verbose: bool) -> Result<Vec<ValidationIssue>, DocError> {
+    /// Check the tree for broken links and orphaned files. With `fix`, broken
+    /// links whose file name matches exactly one document are rewritten in place.
+    pub fn validate(&self, docs_path: &Path, verbose: bool, fix: bool) -> Result<Vec<ValidationIssue>, DocError> {
         let scanner = DocumentationScanner::new(false);
         let graph = scanner.scan_directory(docs_path)?;
         
@@ -416,11 +1048,47 @@ impl DocumentationValidator {
 
         // Check for broken links
         for broken_link in &graph.broken_links {
+            let repair = if broken_link.reference_type == ReferenceType::DirectLink {
+                self.find_repair(broken_link, &graph, docs_path)
+            } else {
+                LinkRepair::None
+            };
+
+            let suggestion = match repair {
+                LinkRepair::Unique(new_target) => {
+                    if fix && rewrite_link(&broken_link.source_file, broken_link.line_number, &broken_link.target_path, &new_target)? {
+                        issues.push(ValidationIssue {
+                            severity: IssueSeverity::Info,
+                            file: broken_link.source_file.clone(),
+                            line: broken_link.line_number,
+                            description: format!("Fixed link: {} → {}", broken_link.target_path, new_target),
+                            suggestion: None,
+                        });
+                        continue;
+                    }
+                    Some(format!("Link should be {} (run with --fix to rewrite)", new_target))
+                }
+                LinkRepair::Fuzzy(new_target) => Some(format!("Did you mean {}?", new_target)),
+                LinkRepair::Ambiguous(candidates) => Some(format!("Ambiguous target, candidates: {}", candidates.join(", "))),
+                LinkRepair::None => None,
+            };
+
             issues.push(ValidationIssue {
                 severity: IssueSeverity::Error,
                 file: broken_link.source_file.clone(),
                 line: broken_link.line_number,
                 description: format!("Broken link: {}", broken_link.target_path),
+                suggestion,
+            });
+        }
+
+        // Targets that exist but have drifted, e.g. renamed headings
+        for warning in &graph.reference_warnings {
+            issues.push(ValidationIssue {
+                severity: IssueSeverity::Warning,
+                file: warning.reference.source_file.clone(),
+                line: warning.reference.line_number,
+                description: warning.message.clone(),
                 suggestion: None,
             });
         }
@@ -462,6 +1130,208 @@ impl DocumentationValidator {
     }
 }
 
+/// Candidate correction for a broken link
+#[derive(Debug, PartialEq)]
+enum LinkRepair {
+    /// Exactly one document has the link's file name
+    Unique(String),
+    /// No name match; closest path by edit distance, offered but never applied
+    Fuzzy(String),
+    Ambiguous(Vec<String>),
+    None,
+}
+
+impl DocumentationValidator {
+    // Minimum similarity (1 - distance / length) for a fuzzy suggestion
+    const FUZZY_THRESHOLD: f32 = 0.6;
+
+    fn find_repair(&self, link: &DocReference, graph: &DocumentationGraph, docs_path: &Path) -> LinkRepair {
+        let (path, anchor) = match link.target_path.split_once('#') {
+            Some((path, anchor)) => (path, Some(anchor)),
+            None => (link.target_path.as_str(), None),
+        };
+        let Some(file_name) = Path::new(path).file_name() else {
+            return LinkRepair::None;
+        };
+        let from_dir = link.source_file.parent().unwrap_or(Path::new(""));
+        let target_for = |file: &Path| {
+            let relative = relative_path(from_dir, file).to_string_lossy().into_owned();
+            match anchor {
+                Some(anchor) => format!("{}#{}", relative, anchor),
+                None => relative,
+            }
+        };
+
+        let mut matches: Vec<&PathBuf> = graph.files.keys().filter(|f| f.file_name() == Some(file_name)).collect();
+        matches.sort();
+        match matches.as_slice() {
+            [only] => return LinkRepair::Unique(target_for(only)),
+            [] => {}
+            several => return LinkRepair::Ambiguous(several.iter().map(|f| target_for(f)).collect()),
+        }
+
+        // Nothing with that name: the file was likely renamed along with its directory
+        let wanted = normalize_path(&resolve_link(link, docs_path));
+        let wanted = wanted.strip_prefix(normalize_path(docs_path)).unwrap_or(&wanted).to_string_lossy().into_owned();
+        graph.files
+            .keys()
+            .map(|file| {
+                let normalized = normalize_path(file);
+                let candidate = normalized.strip_prefix(normalize_path(docs_path)).unwrap_or(&normalized).to_string_lossy().into_owned();
+                let longest = wanted.chars().count().max(candidate.chars().count()).max(1);
+                let similarity = 1.0 - edit_distance(&wanted, &candidate) as f32 / longest as f32;
+                (file, similarity)
+            })
+            .filter(|(_, similarity)| *similarity >= Self::FUZZY_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(LinkRepair::None, |(file, _)| LinkRepair::Fuzzy(target_for(file)))
+    }
+}
+
+/// Levenshtein distance over characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Output of `shriven-benchmark --output`
+#[derive(Debug, Deserialize)]
+pub struct BenchmarkResults {
+    pub results: Vec<MeasuredMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MeasuredMetric {
+    pub name: String,
+    /// Unit of the latency figures (`ns`, `μs` or `ms`)
+    pub unit: String,
+    pub p99: f64,
+    pub throughput_per_sec: f64,
+}
+
+/// A documented performance claim the measurements contradict
+#[derive(Debug, Serialize)]
+pub struct MetricMismatch {
+    pub file: PathBuf,
+    pub line: usize,
+    pub claim: String,
+    pub benchmark: String,
+    pub measured: String,
+}
+
+/// Bound stated by a `PerformanceMetric` reference
+#[derive(Debug, PartialEq)]
+enum PerformanceClaim {
+    /// Latency at most this many nanoseconds
+    MaxLatencyNs(f64),
+    /// Throughput at least this many per second
+    MinThroughput(f64),
+}
+
+impl PerformanceClaim {
+    /// Parse `< 100μs`, `500ns`, `1000+ orders/second`. Bare latencies are
+    /// targets and so read as ceilings; `>` on a latency states no bound.
+    fn parse(claim: &str) -> Option<Self> {
+        let claim = claim.trim();
+        let number_start = claim.find(|c: char| c.is_ascii_digit())?;
+        let comparator = claim[..number_start].trim();
+        let rest = &claim[number_start..];
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let value: f64 = rest[..number_end].parse().ok()?;
+        let unit = rest[number_end..].trim_start_matches('+').trim();
+
+        let to_ns = match unit {
+            "ns" => Some(1.0),
+            "μs" => Some(1_000.0),
+            "ms" => Some(1_000_000.0),
+            _ => None,
+        };
+        match to_ns {
+            Some(_) if matches!(comparator, ">" | "≥") => None,
+            Some(scale) => Some(PerformanceClaim::MaxLatencyNs(value * scale)),
+            None if matches!(comparator, "<" | "≤") => None,
+            None => Some(PerformanceClaim::MinThroughput(value)),
+        }
+    }
+}
+
+// Lowercase words of `text`, with a trailing plural `s` dropped
+fn claim_words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let w = w.to_lowercase();
+            match w.strip_suffix('s') {
+                Some(stem) if stem.len() > 2 => stem.to_string(),
+                _ => w,
+            }
+        })
+        .collect()
+}
+
+/// Compare every performance claim in `graph` with the benchmark whose name
+/// words (`order_book_update`) all appear on the claim's line
+pub fn reconcile_metrics(graph: &DocumentationGraph, results: &BenchmarkResults) -> Vec<MetricMismatch> {
+    let mut mismatches = Vec::new();
+
+    for reference in graph.references.iter().filter(|r| r.reference_type == ReferenceType::PerformanceMetric) {
+        let Some(claim_text) = reference.anchor.as_deref() else {
+            continue;
+        };
+        let Some(claim) = PerformanceClaim::parse(claim_text) else {
+            continue;
+        };
+        let words = claim_words(&reference.context);
+
+        for measured in &results.results {
+            if !claim_words(&measured.name.replace('_', " ")).is_subset(&words) {
+                continue;
+            }
+
+            let p99_ns = match measured.unit.as_str() {
+                "μs" | "us" => measured.p99 * 1_000.0,
+                "ms" => measured.p99 * 1_000_000.0,
+                _ => measured.p99,
+            };
+            let contradicted = match claim {
+                PerformanceClaim::MaxLatencyNs(limit) => (p99_ns > limit).then(|| format!("p99 {}", format_ns(p99_ns))),
+                PerformanceClaim::MinThroughput(floor) => (measured.throughput_per_sec < floor)
+                    .then(|| format!("{:.0}/second", measured.throughput_per_sec)),
+            };
+            if let Some(measured_value) = contradicted {
+                mismatches.push(MetricMismatch {
+                    file: reference.source_file.clone(),
+                    line: reference.line_number,
+                    claim: claim_text.to_string(),
+                    benchmark: measured.name.clone(),
+                    measured: measured_value,
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+fn format_ns(ns: f64) -> String {
+    if ns >= 1_000_000.0 {
+        format!("{:.1}ms", ns / 1_000_000.0)
+    } else if ns >= 1_000.0 {
+        format!("{:.1}μs", ns / 1_000.0)
+    } else {
+        format!("{:.0}ns", ns)
+    }
+}
+
 #[derive(Debug)]
 pub struct ValidationIssue {
     pub severity: IssueSeverity,
@@ -492,9 +1362,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Scan { docs_path, output, include_source } => {
+        Commands::Scan { docs_path, output, include_source, incremental } => {
             let scanner = DocumentationScanner::new(include_source);
-            let graph = scanner.scan_directory(&docs_path)?;
+            let previous = if incremental {
+                DocumentationGraph::load(&output).ok()
+            } else {
+                None
+            };
+            let (graph, reused) = scanner.scan_incremental(&docs_path, previous.as_ref())?;
+            if incremental {
+                println!("♻️  Reused {} unchanged files, reparsed {}", reused, graph.files.len() - reused);
+            }
             
             let json = serde_json::to_string_pretty(&graph)?;
             fs::write(&output, json)?;
@@ -509,15 +1387,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
         
-        Commands::Watch { docs_path: _, dry_run: _, auto_threshold: _ } => {
-            println!("🚧 Watch mode is not yet implemented");
-            println!("This feature will be added in a future release");
-            return Ok(());
+        Commands::Watch { docs_path, graph, debounce_ms, dry_run, auto_threshold } => {
+            let mut watcher = DocumentationWatcher::new(&docs_path, &graph, Duration::from_millis(debounce_ms))?;
+            println!("👀 Watching {} for changes (Ctrl+C to stop)", docs_path.display());
+            if dry_run {
+                println!("🔍 Dry run: proposed propagations will not be applied");
+            }
+
+            loop {
+                let changed = watcher.wait_for_changes()?;
+                let report = watcher.process(&changed, dry_run, auto_threshold)?;
+                report.print(dry_run);
+            }
         }
         
-        Commands::Validate { docs_path, fix: _, verbose } => {
+        Commands::Validate { docs_path, fix, verbose } => {
             let validator = DocumentationValidator::new();
-            let issues = validator.validate(&docs_path, verbose)?;
+            let issues = validator.validate(&docs_path, verbose, fix)?;
             
             if issues.is_empty() {
                 println!("✅ All documentation is consistent!");
@@ -545,9 +1431,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
         
-        Commands::Metrics { docs_path, format } => {
+        Commands::Metrics { docs_path, format, reconcile } => {
             let scanner = DocumentationScanner::new(false);
             let graph = scanner.scan_directory(&docs_path)?;
+
+            if let Some(results_path) = reconcile {
+                let results: BenchmarkResults = serde_json::from_str(&fs::read_to_string(&results_path)?)?;
+                let mismatches = reconcile_metrics(&graph, &results);
+
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(&mismatches)?),
+                    "markdown" => {
+                        println!("# Performance Claims vs {}
+", results_path.display());
+                        for mismatch in &mismatches {
+                            println!("- ❌ {}:{} claims `{}` but `{}` measured {}",
+                                     mismatch.file.display(),
+                                     mismatch.line,
+                                     mismatch.claim,
+                                     mismatch.benchmark,
+                                     mismatch.measured);
+                        }
+                        if mismatches.is_empty() {
+                            println!("✅ No documented claim is contradicted by the measurements");
+                        }
+                    }
+                    _ => return Err("Unsupported format. Use 'json' or 'markdown'".into()),
+                }
+
+                if !mismatches.is_empty() {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
             
             match format.as_str() {
                 "json" => {
@@ -579,4 +1495,200 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watcher_reports_a_new_broken_link() {
+        let root = std::env::temp_dir().join(format!("shriven-doc-tracker-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let docs = root.join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        let guide = docs.join("guide.md");
+        fs::write(&guide, "# Guide\n\nSee [the API](api.md).\n").unwrap();
+        fs::write(docs.join("api.md"), "# API\n").unwrap();
+        let graph_path = root.join("doc-graph.json");
+
+        let mut watcher = DocumentationWatcher::new(&docs, &graph_path, Duration::from_millis(50)).unwrap();
+        assert!(watcher.graph.broken_links.is_empty());
+
+        fs::write(&guide, "# Guide\n\nSee [the API](api.md) and [the FAQ](faq.md).\n").unwrap();
+        let changed = watcher.wait_for_changes().unwrap();
+        assert_eq!(changed, vec![guide.clone()]);
+
+        let report = watcher.process(&changed, false, 1.0).unwrap();
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.new_broken.len(), 1);
+        assert_eq!(report.new_broken[0].source_file, guide);
+        assert_eq!(report.new_broken[0].target_path, "faq.md");
+        assert_eq!(report.new_broken[0].line_number, 3);
+
+        let stored = DocumentationGraph::load(&graph_path).unwrap();
+        assert_eq!(stored.broken_links.len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn fix_rewrites_moved_links_and_leaves_dangling_ones() {
+        let docs = std::env::temp_dir().join(format!("shriven-doc-fix-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&docs);
+        fs::create_dir_all(docs.join("reference")).unwrap();
+        // api.md moved into reference/; missing.md never existed
+        let guide = docs.join("guide.md");
+        fs::write(&guide, "# Guide\n\nSee [the API](api.md).\nAnd [nothing](missing.md).\n").unwrap();
+        fs::write(docs.join("reference").join("api.md"), "# API\n").unwrap();
+
+        let issues = DocumentationValidator::new().validate(&docs, false, true).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&guide).unwrap(),
+            "# Guide\n\nSee [the API](reference/api.md).\nAnd [nothing](missing.md).\n"
+        );
+        let fixed: Vec<_> = issues.iter().filter(|i| matches!(i.severity, IssueSeverity::Info)).collect();
+        assert_eq!(fixed.len(), 1);
+        assert_eq!((fixed[0].file.as_path(), fixed[0].line), (guide.as_path(), 3));
+        let broken: Vec<_> = issues.iter().filter(|i| matches!(i.severity, IssueSeverity::Error)).collect();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].description, "Broken link: missing.md");
+        assert_eq!(broken[0].line, 4);
+        fs::remove_dir_all(&docs).unwrap();
+    }
+
+    #[test]
+    fn slugs_follow_github_case_and_punctuation_rules() {
+        assert_eq!(slugify("Getting Started"), "getting-started");
+        assert_eq!(slugify("  Hello, World!  "), "hello-world");
+        assert_eq!(slugify("API v2.0 (beta)"), "api-v20-beta");
+        assert_eq!(slugify("snake_case & kebab-case"), "snake_case--kebab-case");
+
+        let anchors = heading_anchors("# Setup\n## Setup\n```\n# not a heading\n```\n### Closed ###\n#NoSpace\n");
+        let expected: HashSet<String> = ["setup", "setup-1", "closed"].into_iter().map(String::from).collect();
+        assert_eq!(anchors, expected);
+    }
+
+    #[test]
+    fn stale_anchors_warn_and_valid_ones_pass() {
+        let docs = std::env::temp_dir().join(format!("shriven-doc-anchor-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&docs);
+        fs::create_dir_all(&docs).unwrap();
+        fs::write(docs.join("api.md"), "# API\n\n## Setup Steps\n").unwrap();
+        fs::write(
+            docs.join("README.md"),
+            "# Intro\n\n[ok](api.md#setup-steps)\n[stale](api.md#setup)\n[here](#intro)\n[gone](#outro)\n",
+        )
+        .unwrap();
+
+        let issues = DocumentationValidator::new().validate(&docs, false, false).unwrap();
+
+        let mut missing: Vec<_> = issues
+            .iter()
+            .filter(|i| matches!(i.severity, IssueSeverity::Warning) && i.description.starts_with("Missing anchor"))
+            .map(|i| (i.line, i.description.split(" in ").next().unwrap()))
+            .collect();
+        missing.sort();
+        assert_eq!(missing, [(4, "Missing anchor #setup"), (6, "Missing anchor #outro")]);
+        assert!(!issues.iter().any(|i| matches!(i.severity, IssueSeverity::Error)));
+        fs::remove_dir_all(&docs).unwrap();
+    }
+
+    #[test]
+    fn code_references_check_line_ranges_and_symbols() {
+        let root = std::env::temp_dir().join(format!("shriven-doc-code-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let docs = root.join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        // record_allocation was renamed to record_alloc
+        fs::write(
+            root.join("src").join("stats.rs"),
+            "pub struct MemoryStats;\n\nimpl MemoryStats {\n    pub fn record_alloc(&self) {}\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            docs.join("README.md"),
+            "# Stats\n\n`src/stats.rs:4`\n`src/stats.rs:40`\n`MemoryStats::record_alloc()`\n`MemoryStats::record_allocation`\n",
+        )
+        .unwrap();
+
+        let issues = DocumentationValidator::new().validate(&docs, false, false).unwrap();
+
+        let mut warnings: Vec<_> = issues
+            .iter()
+            .filter(|i| matches!(i.severity, IssueSeverity::Warning))
+            .map(|i| (i.line, i.description.as_str()))
+            .collect();
+        warnings.sort();
+        assert_eq!(
+            warnings,
+            [
+                (4, "Line 40 is out of range for src/stats.rs (5 lines)"),
+                (6, "Symbol MemoryStats::record_allocation not found under src/"),
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reconcile_flags_claims_the_benchmarks_contradict() {
+        let docs = std::env::temp_dir().join(format!("shriven-doc-metrics-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&docs);
+        fs::create_dir_all(&docs).unwrap();
+        let readme = docs.join("README.md");
+        fs::write(
+            &readme,
+            "# Performance\n\nOrder book update latency < 100μs\nOrder submit latency < 500μs\nOrder submit rate 1000+ orders/second\n",
+        )
+        .unwrap();
+        let results: BenchmarkResults = serde_json::from_str(
+            r#"{"results": [
+                {"name": "order_book_update", "unit": "μs", "p99": 140.0, "throughput_per_sec": 50000.0},
+                {"name": "order_submit", "unit": "ns", "p99": 200000.0, "throughput_per_sec": 5000.0}
+            ]}"#,
+        )
+        .unwrap();
+
+        let graph = DocumentationScanner::new(false).scan_directory(&docs).unwrap();
+        let mismatches = reconcile_metrics(&graph, &results);
+
+        assert_eq!(mismatches.len(), 1, "{:?}", mismatches);
+        assert_eq!((mismatches[0].file.as_path(), mismatches[0].line), (readme.as_path(), 3));
+        assert_eq!(mismatches[0].claim, "< 100μs");
+        assert_eq!(mismatches[0].benchmark, "order_book_update");
+        assert_eq!(mismatches[0].measured, "p99 140.0μs");
+        fs::remove_dir_all(&docs).unwrap();
+    }
+
+    #[test]
+    fn incremental_scan_skips_unchanged_files_but_revalidates_their_links() {
+        let root = std::env::temp_dir().join(format!("shriven-doc-incremental-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let docs = root.join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        fs::write(docs.join("README.md"), "# Index\n\n[API](api.md)\n").unwrap();
+        fs::write(docs.join("api.md"), "# API\n").unwrap();
+        fs::write(docs.join("faq.md"), "# FAQ\n").unwrap();
+        let graph_path = root.join("doc-graph.json");
+        let scanner = DocumentationScanner::new(false);
+
+        let (first, reused) = scanner.scan_incremental(&docs, None).unwrap();
+        assert_eq!(reused, 0);
+        first.save(&graph_path).unwrap();
+
+        fs::write(docs.join("faq.md"), "# FAQ\n\nUpdated.\n").unwrap();
+        let previous = DocumentationGraph::load(&graph_path).unwrap();
+        let (second, reused) = scanner.scan_incremental(&docs, Some(&previous)).unwrap();
+        assert_eq!(reused, 2);
+        assert_eq!(second.references.len(), first.references.len());
+        assert!(second.broken_links.is_empty());
+
+        // README.md is untouched, but the file it links to is gone
+        fs::remove_file(docs.join("api.md")).unwrap();
+        let (third, reused) = scanner.scan_incremental(&docs, Some(&second)).unwrap();
+        assert_eq!(reused, 2);
+        assert_eq!(third.broken_links.len(), 1);
+        assert_eq!(third.broken_links[0].target_path, "api.md");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
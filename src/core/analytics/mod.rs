@@ -0,0 +1,353 @@
+// Real-time analytics for ShrivenQ strategies
+// Windowed statistics over market data with O(1) updates
+
+use crate::core::events::MarketEvent;
+use crate::core::memory::Clock;
+use crate::core::types::{Px, Qty};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Mean, variance, min, max and VWAP of trade prices over the last `window`
+/// trades.
+///
+/// Mean and variance use Welford's update for adding and removing a sample;
+/// the running moments are re-derived from the window every `window`
+/// evictions so rounding from removals cannot accumulate. Min and max use
+/// monotonic deques and VWAP exact integer sums, so every update is O(1)
+/// amortized and every accessor O(1). This is the reference the
+/// `ComputeBackend` kernels are checked against.
+///
+/// With [`with_max_age`](Self::with_max_age) trades also leave the window
+/// once they are older than the age limit on the given clock; a `SimClock`
+/// makes that event time in backtests.
+#[derive(Debug, Clone)]
+pub struct RollingStats {
+    window: usize,
+    samples: VecDeque<(Px, Qty)>,
+    // When each sample arrived; only kept with an age limit
+    arrivals: VecDeque<Instant>,
+    max_age: Option<(Duration, Arc<dyn Clock>)>,
+    mean: f64,
+    m2: f64,
+    // Candidates for min/max, as (sequence, price), oldest first
+    minima: VecDeque<(u64, Px)>,
+    maxima: VecDeque<(u64, Px)>,
+    notional: i128,
+    volume: i128,
+    pushed: u64,
+    evictions_since_rebase: usize,
+}
+
+impl RollingStats {
+    /// A window of at least one trade
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window),
+            arrivals: VecDeque::new(),
+            max_age: None,
+            mean: 0.0,
+            m2: 0.0,
+            minima: VecDeque::with_capacity(window),
+            maxima: VecDeque::with_capacity(window),
+            notional: 0,
+            volume: 0,
+            pushed: 0,
+            evictions_since_rebase: 0,
+        }
+    }
+
+    /// Also drop trades older than `max_age` as measured by `clock`. Expiry
+    /// happens on [`push`](Self::push) and [`expire`](Self::expire); the
+    /// accessors report the window as of the last of those.
+    pub fn with_max_age(mut self, max_age: Duration, clock: Arc<dyn Clock>) -> Self {
+        self.arrivals = VecDeque::with_capacity(self.window);
+        self.max_age = Some((max_age, clock));
+        self
+    }
+
+    /// Drop trades past the age limit, if there is one; returns how many
+    pub fn expire(&mut self) -> usize {
+        let Some((max_age, clock)) = &self.max_age else {
+            return 0;
+        };
+        let now = clock.now();
+        let max_age = *max_age;
+        let mut expired = 0;
+        while self
+            .arrivals
+            .front()
+            .is_some_and(|&arrived| now.saturating_duration_since(arrived) > max_age)
+        {
+            self.evict();
+            expired += 1;
+        }
+        expired
+    }
+
+    /// Add trades; other events are ignored. Returns whether the event was used.
+    pub fn on_event(&mut self, event: &MarketEvent) -> bool {
+        match event {
+            MarketEvent::Trade { price, size, .. } => {
+                self.push(*price, *size);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn push(&mut self, price: Px, size: Qty) {
+        self.expire();
+        if self.samples.len() == self.window {
+            self.evict();
+        }
+        if let Some((_, clock)) = &self.max_age {
+            self.arrivals.push_back(clock.now());
+        }
+
+        let x = to_f64(price);
+        let n = (self.samples.len() + 1) as f64;
+        let delta = x - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (x - self.mean);
+
+        let seq = self.pushed;
+        self.pushed += 1;
+        while self.minima.back().is_some_and(|&(_, p)| p >= price) {
+            self.minima.pop_back();
+        }
+        self.minima.push_back((seq, price));
+        while self.maxima.back().is_some_and(|&(_, p)| p <= price) {
+            self.maxima.pop_back();
+        }
+        self.maxima.push_back((seq, price));
+
+        self.notional += i128::from(price.raw()) * i128::from(size.raw());
+        self.volume += i128::from(size.raw());
+        self.samples.push_back((price, size));
+    }
+
+    fn evict(&mut self) {
+        let Some((price, size)) = self.samples.pop_front() else {
+            return;
+        };
+        self.arrivals.pop_front();
+
+        let n = self.samples.len() as f64;
+        if n == 0.0 {
+            self.mean = 0.0;
+            self.m2 = 0.0;
+        } else {
+            let y = to_f64(price);
+            let delta = y - self.mean;
+            self.mean -= delta / n;
+            self.m2 -= delta * (y - self.mean);
+        }
+
+        let oldest = self.pushed - self.samples.len() as u64;
+        if self.minima.front().is_some_and(|&(seq, _)| seq < oldest) {
+            self.minima.pop_front();
+        }
+        if self.maxima.front().is_some_and(|&(seq, _)| seq < oldest) {
+            self.maxima.pop_front();
+        }
+
+        self.notional -= i128::from(price.raw()) * i128::from(size.raw());
+        self.volume -= i128::from(size.raw());
+
+        self.evictions_since_rebase += 1;
+        if self.evictions_since_rebase >= self.window {
+            self.rebase();
+        }
+    }
+
+    // Recompute the moments from the window with a fresh two-pass sum
+    fn rebase(&mut self) {
+        self.evictions_since_rebase = 0;
+        let n = self.samples.len() as f64;
+        if n == 0.0 {
+            return;
+        }
+        self.mean = self.samples.iter().map(|&(p, _)| to_f64(p)).sum::<f64>() / n;
+        self.m2 = self
+            .samples
+            .iter()
+            .map(|&(p, _)| (to_f64(p) - self.mean).powi(2))
+            .sum();
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Trades currently in the window
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.samples.len() == self.window
+    }
+
+    /// Arithmetic mean price, in price units
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.mean)
+    }
+
+    /// Sample variance of price, in squared price units; needs two trades
+    pub fn variance(&self) -> Option<f64> {
+        (self.samples.len() >= 2).then(|| (self.m2 / (self.samples.len() - 1) as f64).max(0.0))
+    }
+
+    pub fn stddev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Option<Px> {
+        self.minima.front().map(|&(_, p)| p)
+    }
+
+    pub fn max(&self) -> Option<Px> {
+        self.maxima.front().map(|&(_, p)| p)
+    }
+
+    /// Volume-weighted average price; `None` while the window has no volume
+    pub fn vwap(&self) -> Option<Px> {
+        if self.volume == 0 {
+            return None;
+        }
+        i64::try_from(self.notional / self.volume)
+            .ok()
+            .map(Px::from_raw)
+    }
+
+    pub fn clear(&mut self) {
+        let max_age = self.max_age.take();
+        *self = Self::new(self.window);
+        self.max_age = max_age;
+    }
+}
+
+fn to_f64(price: Px) -> f64 {
+    price.raw() as f64 / Px::<8>::SCALE as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::SymbolId;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // Full recompute over the window, for comparison
+    struct Naive {
+        mean: Option<f64>,
+        variance: Option<f64>,
+        min: Option<Px>,
+        max: Option<Px>,
+        vwap: Option<Px>,
+    }
+
+    fn naive(window: &[(Px, Qty)]) -> Naive {
+        let n = window.len() as f64;
+        let mean =
+            (!window.is_empty()).then(|| window.iter().map(|&(p, _)| to_f64(p)).sum::<f64>() / n);
+        let variance = (window.len() >= 2).then(|| {
+            let mean = mean.unwrap();
+            window
+                .iter()
+                .map(|&(p, _)| (to_f64(p) - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1.0)
+        });
+        let notional: i128 = window
+            .iter()
+            .map(|&(p, q)| i128::from(p.raw()) * i128::from(q.raw()))
+            .sum();
+        let volume: i128 = window.iter().map(|&(_, q)| i128::from(q.raw())).sum();
+        Naive {
+            mean,
+            variance,
+            min: window.iter().map(|&(p, _)| p).min(),
+            max: window.iter().map(|&(p, _)| p).max(),
+            vwap: (volume != 0).then(|| Px::from_raw((notional / volume) as i64)),
+        }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: Option<f64>, what: &str) {
+        match (actual, expected) {
+            (Some(a), Some(e)) => {
+                assert!(
+                    (a - e).abs() <= 1e-6 * e.abs().max(1.0),
+                    "{what}: {a} vs {e}"
+                )
+            }
+            (a, e) => assert_eq!(a, e, "{what}"),
+        }
+    }
+
+    #[test]
+    fn rolling_results_match_a_full_recompute_on_random_data() {
+        let mut rng = StdRng::seed_from_u64(581);
+        for window in [1, 2, 7, 64] {
+            let mut stats = RollingStats::new(window);
+            let mut trades = Vec::new();
+            let mut price = 30_000 * Px::<8>::SCALE;
+            for i in 0..5_000 {
+                price += rng.gen_range(-500_000_000..=500_000_000);
+                // Zero-size prints leave VWAP without volume now and then
+                let size = Qty::from_raw(rng.gen_range(0..=3) * rng.gen_range(1..1_000_000_000));
+                stats.push(Px::from_raw(price), size);
+                trades.push((Px::from_raw(price), size));
+
+                let start = trades.len().saturating_sub(window);
+                let expected = naive(&trades[start..]);
+                let at = format!("window {window}, trade {i}");
+                assert_eq!(stats.len(), trades.len() - start, "{at}");
+                assert_close(stats.mean(), expected.mean, &format!("mean at {at}"));
+                assert_close(
+                    stats.variance(),
+                    expected.variance,
+                    &format!("variance at {at}"),
+                );
+                assert_eq!(stats.min(), expected.min, "min at {at}");
+                assert_eq!(stats.max(), expected.max, "max at {at}");
+                assert_eq!(stats.vwap(), expected.vwap, "vwap at {at}");
+            }
+        }
+    }
+
+    #[test]
+    fn only_trades_enter_the_window() {
+        let mut stats = RollingStats::new(4);
+        let heartbeat = MarketEvent::Heartbeat {
+            timestamp_ns: 1,
+            normalized_ns: 1,
+        };
+        let trade = MarketEvent::Trade {
+            symbol: SymbolId::intern("BTCUSDT").unwrap(),
+            timestamp_ns: 2,
+            normalized_ns: 2,
+            price: Px::from_int(100).unwrap(),
+            size: Qty::from_int(2).unwrap(),
+        };
+
+        assert!(!stats.on_event(&heartbeat));
+        assert!(stats.is_empty());
+        assert!(stats.on_event(&trade));
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats.mean(), Some(100.0));
+        assert_eq!(stats.variance(), None);
+        assert_eq!(stats.vwap(), Px::from_int(100));
+
+        stats.clear();
+        assert!(stats.is_empty());
+        assert_eq!(stats.window(), 4);
+    }
+}
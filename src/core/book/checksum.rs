@@ -0,0 +1,259 @@
+// Order book checksums for ShrivenQ
+// Recomputes exchange-published CRC32 book checksums to catch a diverged local book
+
+use super::{BookError, OrderBook};
+use crate::core::events::Side;
+use crate::core::types::{Px, Qty};
+use std::fmt::Write;
+use tracing::warn;
+
+/// Levels per side covered by an OKX checksum
+pub const OKX_CHECKSUM_LEVELS: usize = 25;
+/// Levels per side covered by a Kraken checksum
+pub const KRAKEN_CHECKSUM_LEVELS: usize = 10;
+
+/// How a venue builds the string its CRC32 book checksum is taken over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumFormat {
+    /// Top 25 levels as `bid_px:bid_sz:ask_px:ask_sz:...`, bids and asks
+    /// interleaved best first, continuing with one side once the other runs
+    /// out. OKX hashes the strings exactly as it sent them, so levels should
+    /// be applied with [`OrderBook::apply_wire_level`]; any other level is
+    /// printed without trailing zeros, which only matches if OKX sent it that
+    /// way. OKX publishes the CRC as a signed 32-bit integer; compare it
+    /// `as u32`.
+    Okx,
+    /// Top 10 asks (ascending) then top 10 bids (descending), each price and
+    /// size printed with the pair's fixed decimals, the decimal point and
+    /// leading zeros removed, all concatenated without separators.
+    Kraken {
+        price_decimals: u32,
+        size_decimals: u32,
+    },
+}
+
+impl OrderBook {
+    /// Set a level from the venue's own price and size text, keeping the text
+    /// for [`ChecksumFormat::Okx`]. A later `apply_level` at the same price
+    /// drops it.
+    pub fn apply_wire_level(
+        &mut self,
+        side: Side,
+        price: &str,
+        size: &str,
+    ) -> Result<(), BookError> {
+        let bad_level = |reason| BookError::BadLevel {
+            price: price.to_string(),
+            size: size.to_string(),
+            reason,
+        };
+        let parsed_price: Px = price.parse().map_err(bad_level)?;
+        let parsed_size: Qty = size.parse().map_err(bad_level)?;
+        self.apply_level(side, parsed_price, parsed_size)?;
+        if !parsed_size.is_zero() {
+            self.wire
+                .insert((side, parsed_price), (price.into(), size.into()));
+        }
+        Ok(())
+    }
+
+    /// CRC32 of the top of the book in `format`. A `u32` rather than a wider
+    /// hash because that is what venues publish; comparing needs no
+    /// conversion beyond OKX's sign.
+    pub fn checksum(&self, format: ChecksumFormat) -> u32 {
+        let mut input = String::with_capacity(1024);
+        match format {
+            ChecksumFormat::Okx => {
+                let mut bids = self.levels(Side::Bid).take(OKX_CHECKSUM_LEVELS);
+                let mut asks = self.levels(Side::Ask).take(OKX_CHECKSUM_LEVELS);
+                loop {
+                    let (bid, ask) = (bids.next(), asks.next());
+                    if bid.is_none() && ask.is_none() {
+                        break;
+                    }
+                    let bid = bid.map(|level| (Side::Bid, level));
+                    let ask = ask.map(|level| (Side::Ask, level));
+                    for (side, (price, size)) in bid.into_iter().chain(ask) {
+                        if !input.is_empty() {
+                            input.push(':');
+                        }
+                        if let Some((price, size)) = self.wire.get(&(side, price)) {
+                            input.push_str(price);
+                            input.push(':');
+                            input.push_str(size);
+                            continue;
+                        }
+                        push_trimmed(&mut input, price.raw(), <Px>::DECIMALS);
+                        input.push(':');
+                        push_trimmed(&mut input, size.raw(), <Qty>::DECIMALS);
+                    }
+                }
+            }
+            ChecksumFormat::Kraken {
+                price_decimals,
+                size_decimals,
+            } => {
+                for side in [Side::Ask, Side::Bid] {
+                    for (price, size) in self.levels(side).take(KRAKEN_CHECKSUM_LEVELS) {
+                        push_digits(&mut input, price.raw(), <Px>::DECIMALS, price_decimals);
+                        push_digits(&mut input, size.raw(), <Qty>::DECIMALS, size_decimals);
+                    }
+                }
+            }
+        }
+        crc32fast::hash(input.as_bytes())
+    }
+
+    /// Compare against the venue's published checksum. A mismatch means the
+    /// local book has diverged; it is logged and returned so the caller can
+    /// resync from a fresh snapshot.
+    pub fn verify_checksum(&self, format: ChecksumFormat, expected: u32) -> Result<(), BookError> {
+        let actual = self.checksum(format);
+        if actual == expected {
+            return Ok(());
+        }
+        warn!(
+            "{} book checksum mismatch: expected {:#010x}, computed {:#010x}",
+            self.symbol, expected, actual
+        );
+        Err(BookError::ChecksumMismatch { expected, actual })
+    }
+}
+
+// `raw` at `scale` decimals with trailing zeros (and a bare point) removed
+fn push_trimmed(out: &mut String, raw: i64, scale: u32) {
+    let start = out.len();
+    push_fixed(out, raw, scale, scale);
+    if scale > 0 {
+        let trimmed = out[start..]
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .len();
+        out.truncate(start + trimmed);
+    }
+}
+
+// `raw` at exactly `decimals` places with the point and leading zeros removed
+fn push_digits(out: &mut String, raw: i64, scale: u32, decimals: u32) {
+    let start = out.len();
+    push_fixed(out, raw, scale, decimals);
+    let digits: String = out[start..]
+        .chars()
+        .filter(|c| *c != '.')
+        .skip_while(|c| *c == '0')
+        .collect();
+    out.truncate(start);
+    out.push_str(&digits);
+}
+
+// `raw` (a value at `scale` decimals) printed with `decimals` places,
+// truncating extra precision
+fn push_fixed(out: &mut String, raw: i64, scale: u32, decimals: u32) {
+    let sign = if raw < 0 { "-" } else { "" };
+    let magnitude = u128::from(raw.unsigned_abs());
+    let value = if decimals <= scale {
+        magnitude / 10u128.pow(scale - decimals)
+    } else {
+        magnitude * 10u128.pow(decimals - scale)
+    };
+    let unit = 10u128.pow(decimals);
+    let _ = if decimals == 0 {
+        write!(out, "{}{}", sign, value)
+    } else {
+        write!(
+            out,
+            "{}{}.{:0width$}",
+            sign,
+            value / unit,
+            value % unit,
+            width = decimals as usize
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::SymbolId;
+
+    // The worked example from OKX's order book checksum documentation
+    const OKX_EXAMPLE_CHECKSUM: i32 = -1881014294;
+
+    fn okx_example() -> OrderBook {
+        let mut book = OrderBook::new(SymbolId::intern("CHECKSUM-OKX").unwrap());
+        for (side, price, size) in [
+            (Side::Bid, "3366.1", "7"),
+            (Side::Bid, "3366", "6"),
+            (Side::Ask, "3366.8", "9"),
+            (Side::Ask, "3368", "8"),
+        ] {
+            book.apply_wire_level(side, price, size).unwrap();
+        }
+        book
+    }
+
+    #[test]
+    fn okx_checksum_matches_the_published_example() {
+        let book = okx_example();
+        assert_eq!(
+            book.checksum(ChecksumFormat::Okx) as i32,
+            OKX_EXAMPLE_CHECKSUM
+        );
+        assert_eq!(
+            book.verify_checksum(ChecksumFormat::Okx, OKX_EXAMPLE_CHECKSUM as u32),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn okx_hashes_the_text_as_sent() {
+        let mut book = okx_example();
+        let price: Px = "3366.1".parse().unwrap();
+        let size = Qty::from_int(7).unwrap();
+
+        // Same level, sent with a trailing zero: "3366.10:7:3366.8:9:..."
+        book.apply_wire_level(Side::Bid, "3366.10", "7").unwrap();
+        assert_eq!(book.size_at(Side::Bid, price), size);
+        assert_eq!(book.checksum(ChecksumFormat::Okx), 1_664_841_389);
+
+        // A numeric update drops the text and falls back to trimmed digits
+        book.apply_level(Side::Bid, price, size).unwrap();
+        assert_eq!(
+            book.checksum(ChecksumFormat::Okx) as i32,
+            OKX_EXAMPLE_CHECKSUM
+        );
+
+        book.apply_wire_level(Side::Bid, "3366.1", "0").unwrap();
+        assert_eq!(book.level_count(Side::Bid), 1);
+        assert!(matches!(
+            book.apply_wire_level(Side::Ask, "3366.8", "nine"),
+            Err(BookError::BadLevel { .. })
+        ));
+    }
+
+    #[test]
+    fn kraken_checksum_drops_points_and_leading_zeros() {
+        let mut book = OrderBook::new(SymbolId::intern("CHECKSUM-KRAKEN").unwrap());
+        for (side, price, size) in [
+            (Side::Ask, "0.05005", "0.00000500"),
+            (Side::Ask, "0.05010", "0.00001000"),
+            (Side::Bid, "0.05000", "0.00000500"),
+        ] {
+            book.apply_wire_level(side, price, size).unwrap();
+        }
+        let format = ChecksumFormat::Kraken {
+            price_decimals: 5,
+            size_decimals: 8,
+        };
+
+        // CRC32 of "5005" "500" "5010" "1000" "5000" "500"
+        assert_eq!(book.checksum(format), 1_957_218_791);
+        assert_eq!(
+            book.verify_checksum(format, 1),
+            Err(BookError::ChecksumMismatch {
+                expected: 1,
+                actual: 1_957_218_791
+            })
+        );
+    }
+}
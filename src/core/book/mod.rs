@@ -0,0 +1,318 @@
+// Local order book for ShrivenQ
+// Price-level aggregated book maintained from BookUpdate events
+
+pub mod checksum;
+pub mod sequence;
+pub mod sharded;
+
+pub use checksum::ChecksumFormat;
+pub use sequence::{DepthDiff, DepthSnapshot, DiffOutcome, SequencedBook, SyncState};
+pub use sharded::{ShardedBook, ShardedBookConfig, TopOfBook};
+
+use crate::core::events::{MarketEvent, Side};
+use crate::core::types::{FixedPointError, Px, Qty, SymbolId};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BookError {
+    #[error("Event is not a book update")]
+    NotABookUpdate,
+    #[error("Update for {got} applied to the {expected} book")]
+    SymbolMismatch { expected: SymbolId, got: SymbolId },
+    #[error("Negative size {size} at price {price}")]
+    NegativeSize { price: Px, size: Qty },
+    #[error("Unparseable level {price}@{size}: {reason}")]
+    BadLevel {
+        price: String,
+        size: String,
+        reason: FixedPointError,
+    },
+    #[error("Book checksum {actual:#010x} does not match the exchange's {expected:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+    #[error("Symbol {symbol} is past the {capacity}-symbol capacity of the sharded book")]
+    SymbolCapacity { symbol: SymbolId, capacity: usize },
+}
+
+/// Top `n` levels of each side, best price first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDepth {
+    pub bids: Vec<(Px, Qty)>,
+    pub asks: Vec<(Px, Qty)>,
+}
+
+/// Aggregated order book keyed by price level.
+///
+/// Levels live in `BTreeMap`s: std collections cannot take a custom allocator
+/// on stable Rust, so level nodes come from the global allocator rather than
+/// the memory pools. Insertion and removal are O(log levels); best bid/ask
+/// are O(log levels) lookups at the ends of each tree.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    symbol: SymbolId,
+    bids: BTreeMap<Px, Qty>,
+    asks: BTreeMap<Px, Qty>,
+    /// Levels' price and size as the venue sent them, for checksums over the
+    /// original text; only set through `apply_wire_level`
+    wire: HashMap<(Side, Px), (Box<str>, Box<str>)>,
+    last_update_ns: u64,
+}
+
+impl OrderBook {
+    pub fn new(symbol: SymbolId) -> Self {
+        Self {
+            symbol,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            wire: HashMap::new(),
+            last_update_ns: 0,
+        }
+    }
+
+    pub fn symbol(&self) -> SymbolId {
+        self.symbol
+    }
+
+    /// Apply a `MarketEvent::BookUpdate` for this book's symbol
+    pub fn apply(&mut self, event: &MarketEvent) -> Result<(), BookError> {
+        let MarketEvent::BookUpdate {
+            symbol,
+            timestamp_ns,
+            side,
+            price,
+            size,
+            ..
+        } = event
+        else {
+            return Err(BookError::NotABookUpdate);
+        };
+
+        if *symbol != self.symbol {
+            return Err(BookError::SymbolMismatch {
+                expected: self.symbol,
+                got: *symbol,
+            });
+        }
+
+        self.apply_level(*side, *price, *size)?;
+        self.last_update_ns = *timestamp_ns;
+        Ok(())
+    }
+
+    /// Set the aggregate size at one level; zero removes the level
+    pub fn apply_level(&mut self, side: Side, price: Px, size: Qty) -> Result<(), BookError> {
+        if size < Qty::ZERO {
+            return Err(BookError::NegativeSize { price, size });
+        }
+
+        let levels = match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        };
+        if size.is_zero() {
+            levels.remove(&price);
+        } else {
+            levels.insert(price, size);
+        }
+        if !self.wire.is_empty() {
+            self.wire.remove(&(side, price));
+        }
+        Ok(())
+    }
+
+    pub fn best_bid(&self) -> Option<(Px, Qty)> {
+        self.bids.iter().next_back().map(|(&px, &qty)| (px, qty))
+    }
+
+    pub fn best_ask(&self) -> Option<(Px, Qty)> {
+        self.asks.iter().next().map(|(&px, &qty)| (px, qty))
+    }
+
+    /// Best ask minus best bid; negative when the book is crossed
+    pub fn spread(&self) -> Option<Px> {
+        let (bid, _) = self.best_bid()?;
+        let (ask, _) = self.best_ask()?;
+        ask.checked_sub(bid)
+    }
+
+    /// Best bid at or above best ask. Incremental feeds can cross briefly
+    /// mid-update, so this is reported rather than rejected.
+    pub fn is_crossed(&self) -> bool {
+        matches!((self.best_bid(), self.best_ask()), (Some((bid, _)), Some((ask, _))) if bid >= ask)
+    }
+
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        BookDepth {
+            bids: self
+                .bids
+                .iter()
+                .rev()
+                .take(levels)
+                .map(|(&px, &qty)| (px, qty))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .take(levels)
+                .map(|(&px, &qty)| (px, qty))
+                .collect(),
+        }
+    }
+
+    /// Levels of one side, best price first
+    pub fn levels(&self, side: Side) -> impl Iterator<Item = (Px, Qty)> + '_ {
+        // Only one of the two chained iterators is ever populated
+        let (bids, asks) = match side {
+            Side::Bid => (Some(self.bids.iter().rev()), None),
+            Side::Ask => (None, Some(self.asks.iter())),
+        };
+        bids.into_iter()
+            .flatten()
+            .chain(asks.into_iter().flatten())
+            .map(|(&px, &qty)| (px, qty))
+    }
+
+    /// Aggregate size resting at `price`, zero if the level is empty
+    pub fn size_at(&self, side: Side, price: Px) -> Qty {
+        let levels = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        levels.get(&price).copied().unwrap_or(Qty::ZERO)
+    }
+
+    pub fn level_count(&self, side: Side) -> usize {
+        match side {
+            Side::Bid => self.bids.len(),
+            Side::Ask => self.asks.len(),
+        }
+    }
+
+    pub fn last_update_ns(&self) -> u64 {
+        self.last_update_ns
+    }
+
+    // Restoring from a snapshot sets levels directly, then the time they were current
+    pub(crate) fn set_last_update_ns(&mut self, timestamp_ns: u64) {
+        self.last_update_ns = timestamp_ns;
+    }
+
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.wire.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(size: i64) -> Qty {
+        Qty::from_int(size).unwrap()
+    }
+
+    fn update(symbol: SymbolId, side: Side, price: i64, size: i64) -> MarketEvent {
+        MarketEvent::BookUpdate {
+            symbol,
+            timestamp_ns: 7,
+            normalized_ns: 7,
+            side,
+            price: px(price),
+            size: qty(size),
+        }
+    }
+
+    #[test]
+    fn updates_aggregate_by_level_and_zero_removes() {
+        let symbol = SymbolId::intern("BOOK-LEVELS").unwrap();
+        let mut book = OrderBook::new(symbol);
+        for (side, price, size) in [
+            (Side::Bid, 99, 5),
+            (Side::Bid, 98, 3),
+            (Side::Bid, 97, 1),
+            (Side::Ask, 101, 4),
+            (Side::Ask, 102, 2),
+            (Side::Bid, 99, 6),
+        ] {
+            book.apply(&update(symbol, side, price, size)).unwrap();
+        }
+
+        assert_eq!(book.best_bid(), Some((px(99), qty(6))));
+        assert_eq!(book.best_ask(), Some((px(101), qty(4))));
+        assert_eq!(book.spread(), Some(px(2)));
+        assert_eq!(book.last_update_ns(), 7);
+        assert_eq!(
+            book.depth(2),
+            BookDepth {
+                bids: vec![(px(99), qty(6)), (px(98), qty(3))],
+                asks: vec![(px(101), qty(4)), (px(102), qty(2))],
+            }
+        );
+
+        book.apply(&update(symbol, Side::Bid, 99, 0)).unwrap();
+        book.apply(&update(symbol, Side::Ask, 105, 0)).unwrap();
+        assert_eq!(book.best_bid(), Some((px(98), qty(3))));
+        assert_eq!(book.size_at(Side::Bid, px(99)), Qty::ZERO);
+        assert_eq!(
+            (book.level_count(Side::Bid), book.level_count(Side::Ask)),
+            (2, 2)
+        );
+
+        book.clear();
+        assert_eq!((book.best_bid(), book.spread()), (None, None));
+    }
+
+    #[test]
+    fn crossed_books_are_reported_not_rejected() {
+        let symbol = SymbolId::intern("BOOK-CROSSED").unwrap();
+        let mut book = OrderBook::new(symbol);
+        book.apply_level(Side::Bid, px(100), qty(1)).unwrap();
+        book.apply_level(Side::Ask, px(101), qty(1)).unwrap();
+        assert!(!book.is_crossed());
+
+        book.apply_level(Side::Bid, px(101), qty(2)).unwrap();
+        assert!(book.is_crossed());
+        assert_eq!(book.spread(), Some(Px::ZERO));
+        book.apply_level(Side::Bid, px(102), qty(2)).unwrap();
+        assert_eq!(book.spread(), Some(px(-1)));
+
+        book.apply_level(Side::Bid, px(102), Qty::ZERO).unwrap();
+        book.apply_level(Side::Bid, px(101), Qty::ZERO).unwrap();
+        assert!(!book.is_crossed());
+    }
+
+    #[test]
+    fn invalid_updates_are_rejected() {
+        let symbol = SymbolId::intern("BOOK-INVALID").unwrap();
+        let other = SymbolId::intern("BOOK-OTHER").unwrap();
+        let mut book = OrderBook::new(symbol);
+
+        assert_eq!(
+            book.apply(&MarketEvent::Heartbeat {
+                timestamp_ns: 1,
+                normalized_ns: 1,
+            }),
+            Err(BookError::NotABookUpdate)
+        );
+        assert_eq!(
+            book.apply(&update(other, Side::Bid, 100, 1)),
+            Err(BookError::SymbolMismatch {
+                expected: symbol,
+                got: other,
+            })
+        );
+        assert_eq!(
+            book.apply(&update(symbol, Side::Ask, 100, -1)),
+            Err(BookError::NegativeSize {
+                price: px(100),
+                size: qty(-1),
+            })
+        );
+        assert_eq!(book.level_count(Side::Ask), 0);
+    }
+}
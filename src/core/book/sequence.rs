@@ -0,0 +1,374 @@
+// Sequenced depth for ShrivenQ
+// Detects gaps in update-id numbered depth diffs and resyncs the book from a snapshot
+
+use super::{BookError, ChecksumFormat, OrderBook};
+use crate::core::events::Side;
+use crate::core::types::{Px, Qty, SymbolId};
+use std::collections::VecDeque;
+use tracing::{info, warn};
+
+/// Diffs held while waiting for a snapshot; past this the oldest are dropped,
+/// which at worst forces another resync
+pub const DEFAULT_MAX_BUFFERED: usize = 10_000;
+
+/// One incremental depth message covering update ids
+/// `first_update_id..=final_update_id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthDiff {
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub timestamp_ns: u64,
+    /// New aggregate size per level; zero removes the level
+    pub levels: Vec<(Side, Px, Qty)>,
+}
+
+/// Full book as of `last_update_id`, usually fetched over REST
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    pub timestamp_ns: u64,
+    pub bids: Vec<(Px, Qty)>,
+    pub asks: Vec<(Px, Qty)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    /// Book contents are not trustworthy; diffs are buffered until a snapshot arrives
+    AwaitingSnapshot,
+    /// Every update up to and including `last_update_id` has been applied
+    Live { last_update_id: u64 },
+}
+
+/// What happened to a diff passed to [`SequencedBook::apply_diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOutcome {
+    Applied,
+    /// Entirely older than the book; ignored
+    Outdated,
+    /// Held for replay after the next snapshot
+    Buffered,
+    /// Updates `expected..got` were missed. The book is now awaiting a
+    /// snapshot and the diff has been buffered; fetch a snapshot.
+    GapDetected {
+        expected: u64,
+        got: u64,
+    },
+}
+
+/// [`OrderBook`] fed by sequence-numbered diffs, following the usual
+/// exchange resync procedure: buffer diffs, apply a snapshot, drop buffered
+/// diffs the snapshot already covers, then replay the rest. Any break in
+/// the id sequence sends the book back to awaiting a snapshot.
+#[derive(Debug, Clone)]
+pub struct SequencedBook {
+    book: OrderBook,
+    state: SyncState,
+    buffer: VecDeque<DepthDiff>,
+    max_buffered: usize,
+    resyncs: u64,
+}
+
+impl SequencedBook {
+    /// Empty book awaiting its first snapshot
+    pub fn new(symbol: SymbolId) -> Self {
+        Self {
+            book: OrderBook::new(symbol),
+            state: SyncState::AwaitingSnapshot,
+            buffer: VecDeque::new(),
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            resyncs: 0,
+        }
+    }
+
+    pub fn with_max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered.max(1);
+        self
+    }
+
+    /// The book itself; only reliable while [`is_live`](Self::is_live)
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn state(&self) -> SyncState {
+        self.state
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self.state, SyncState::Live { .. })
+    }
+
+    /// Diffs waiting for a snapshot
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Times the book fell out of sync after having been live
+    pub fn resyncs(&self) -> u64 {
+        self.resyncs
+    }
+
+    pub fn apply_diff(&mut self, diff: DepthDiff) -> Result<DiffOutcome, BookError> {
+        let SyncState::Live { last_update_id } = self.state else {
+            self.buffer_diff(diff);
+            return Ok(DiffOutcome::Buffered);
+        };
+
+        if diff.final_update_id <= last_update_id {
+            return Ok(DiffOutcome::Outdated);
+        }
+        let expected = last_update_id + 1;
+        if diff.first_update_id > expected {
+            let got = diff.first_update_id;
+            warn!(
+                "{} depth gap: expected update {}, got {}; resyncing",
+                self.book.symbol(),
+                expected,
+                got
+            );
+            self.invalidate();
+            self.buffer_diff(diff);
+            return Ok(DiffOutcome::GapDetected { expected, got });
+        }
+
+        // A rejected level leaves the book half-updated
+        self.apply_levels(&diff)
+            .inspect_err(|_| self.invalidate())?;
+        Ok(DiffOutcome::Applied)
+    }
+
+    /// Replace the book with `snapshot`, then replay buffered diffs that
+    /// continue from it. Returns how many were replayed. If the buffer does
+    /// not connect to the snapshot the book stays awaiting a (newer) one.
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> Result<usize, BookError> {
+        self.state = SyncState::AwaitingSnapshot;
+        self.book.clear();
+        for (side, levels) in [(Side::Bid, &snapshot.bids), (Side::Ask, &snapshot.asks)] {
+            for &(price, size) in levels {
+                self.book.apply_level(side, price, size)?;
+            }
+        }
+        self.book.last_update_ns = snapshot.timestamp_ns;
+        self.state = SyncState::Live {
+            last_update_id: snapshot.last_update_id,
+        };
+
+        let mut replayed = 0;
+        let mut last_update_id = snapshot.last_update_id;
+        while let Some(diff) = self.buffer.front() {
+            if diff.final_update_id <= last_update_id {
+                self.buffer.pop_front();
+                continue;
+            }
+            if diff.first_update_id > last_update_id + 1 {
+                // Keep the rest buffered for the next snapshot
+                warn!(
+                    "{} snapshot at update {} does not reach buffered update {}; resyncing",
+                    self.book.symbol(),
+                    last_update_id,
+                    diff.first_update_id
+                );
+                self.state = SyncState::AwaitingSnapshot;
+                return Ok(replayed);
+            }
+            if let Some(diff) = self.buffer.pop_front() {
+                self.apply_levels(&diff)
+                    .inspect_err(|_| self.state = SyncState::AwaitingSnapshot)?;
+                last_update_id = diff.final_update_id;
+                replayed += 1;
+            }
+        }
+        info!(
+            "{} book synced at update {} ({} buffered diffs replayed)",
+            self.book.symbol(),
+            snapshot.last_update_id,
+            replayed
+        );
+        Ok(replayed)
+    }
+
+    /// Treat the book as corrupt until the next snapshot, e.g. after a
+    /// checksum mismatch
+    pub fn invalidate(&mut self) {
+        if self.is_live() {
+            self.resyncs += 1;
+        }
+        self.state = SyncState::AwaitingSnapshot;
+    }
+
+    /// [`OrderBook::verify_checksum`] that also invalidates the book on a mismatch
+    pub fn verify_checksum(
+        &mut self,
+        format: ChecksumFormat,
+        expected: u32,
+    ) -> Result<(), BookError> {
+        self.book
+            .verify_checksum(format, expected)
+            .inspect_err(|_| self.invalidate())
+    }
+
+    fn apply_levels(&mut self, diff: &DepthDiff) -> Result<(), BookError> {
+        for &(side, price, size) in &diff.levels {
+            self.book.apply_level(side, price, size)?;
+        }
+        self.book.last_update_ns = diff.timestamp_ns;
+        self.state = SyncState::Live {
+            last_update_id: diff.final_update_id,
+        };
+        Ok(())
+    }
+
+    fn buffer_diff(&mut self, diff: DepthDiff) {
+        if self.buffer.len() >= self.max_buffered {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(diff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(size: i64) -> Qty {
+        Qty::from_int(size).unwrap()
+    }
+
+    fn diff(first_update_id: u64, final_update_id: u64, levels: &[(Side, i64, i64)]) -> DepthDiff {
+        DepthDiff {
+            first_update_id,
+            final_update_id,
+            timestamp_ns: final_update_id,
+            levels: levels
+                .iter()
+                .map(|&(side, price, size)| (side, px(price), qty(size)))
+                .collect(),
+        }
+    }
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot {
+            last_update_id,
+            timestamp_ns: last_update_id,
+            bids: vec![(px(99), qty(5)), (px(98), qty(5))],
+            asks: vec![(px(101), qty(5))],
+        }
+    }
+
+    fn book(name: &str) -> SequencedBook {
+        SequencedBook::new(SymbolId::intern(name).unwrap())
+    }
+
+    #[test]
+    fn snapshot_then_diffs_builds_the_book() {
+        let mut book = book("SEQ-SNAPSHOT");
+        assert_eq!(book.state(), SyncState::AwaitingSnapshot);
+
+        // Diffs that arrive before the snapshot wait for it; the first is
+        // already covered by it and the second straddles it
+        for update in [
+            diff(8, 9, &[(Side::Bid, 97, 1)]),
+            diff(10, 11, &[(Side::Bid, 99, 7)]),
+            diff(12, 12, &[(Side::Ask, 102, 3)]),
+        ] {
+            assert_eq!(book.apply_diff(update), Ok(DiffOutcome::Buffered));
+        }
+        assert_eq!(book.apply_snapshot(snapshot(10)), Ok(2));
+        assert_eq!(book.state(), SyncState::Live { last_update_id: 12 });
+        assert_eq!(book.buffered(), 0);
+
+        assert_eq!(
+            book.apply_diff(diff(13, 14, &[(Side::Bid, 98, 0), (Side::Ask, 101, 2)])),
+            Ok(DiffOutcome::Applied)
+        );
+        assert_eq!(book.state(), SyncState::Live { last_update_id: 14 });
+        let depth = book.book().depth(5);
+        assert_eq!(depth.bids, [(px(99), qty(7))]);
+        assert_eq!(depth.asks, [(px(101), qty(2)), (px(102), qty(3))]);
+        assert_eq!(book.book().level_count(Side::Bid), 1);
+        assert_eq!(book.book().last_update_ns(), 14);
+    }
+
+    #[test]
+    fn a_gap_forces_a_resync_from_a_fresh_snapshot() {
+        let mut book = book("SEQ-GAP");
+        book.apply_snapshot(snapshot(10)).unwrap();
+        assert_eq!(
+            book.apply_diff(diff(11, 11, &[(Side::Bid, 99, 6)])),
+            Ok(DiffOutcome::Applied)
+        );
+
+        assert_eq!(
+            book.apply_diff(diff(14, 15, &[(Side::Bid, 99, 9)])),
+            Ok(DiffOutcome::GapDetected {
+                expected: 12,
+                got: 14,
+            })
+        );
+        assert!(!book.is_live());
+        assert_eq!((book.resyncs(), book.buffered()), (1, 1));
+        assert_eq!(
+            book.apply_diff(diff(16, 16, &[(Side::Ask, 101, 1)])),
+            Ok(DiffOutcome::Buffered)
+        );
+
+        // The new snapshot covers 14..=15, so only 16 is replayed
+        assert_eq!(book.apply_snapshot(snapshot(15)), Ok(1));
+        assert_eq!(book.state(), SyncState::Live { last_update_id: 16 });
+        assert_eq!(book.book().best_bid(), Some((px(99), qty(5))));
+        assert_eq!(book.book().best_ask(), Some((px(101), qty(1))));
+        assert_eq!(book.resyncs(), 1);
+    }
+
+    #[test]
+    fn duplicate_and_out_of_order_diffs_are_ignored() {
+        let mut book = book("SEQ-ORDER");
+        book.apply_snapshot(snapshot(10)).unwrap();
+        let latest = diff(11, 13, &[(Side::Bid, 99, 8)]);
+        assert_eq!(book.apply_diff(latest.clone()), Ok(DiffOutcome::Applied));
+
+        // A resend and a late straggler both end at or before update 13
+        assert_eq!(book.apply_diff(latest), Ok(DiffOutcome::Outdated));
+        assert_eq!(
+            book.apply_diff(diff(12, 12, &[(Side::Bid, 99, 1)])),
+            Ok(DiffOutcome::Outdated)
+        );
+        assert_eq!(book.book().best_bid(), Some((px(99), qty(8))));
+
+        // Overlapping the last applied id still continues the sequence
+        assert_eq!(
+            book.apply_diff(diff(13, 14, &[(Side::Bid, 99, 2)])),
+            Ok(DiffOutcome::Applied)
+        );
+        assert_eq!(book.state(), SyncState::Live { last_update_id: 14 });
+        assert_eq!(book.book().best_bid(), Some((px(99), qty(2))));
+        assert_eq!(book.resyncs(), 0);
+    }
+
+    #[test]
+    fn a_snapshot_behind_the_buffer_waits_for_a_newer_one() {
+        let mut book = book("SEQ-BEHIND").with_max_buffered(2);
+        for update in [
+            diff(20, 20, &[(Side::Bid, 90, 1)]),
+            diff(21, 21, &[(Side::Bid, 91, 1)]),
+            diff(22, 22, &[(Side::Bid, 92, 1)]),
+        ] {
+            book.apply_diff(update).unwrap();
+        }
+        // The oldest diff was dropped to stay within the buffer
+        assert_eq!(book.buffered(), 2);
+
+        assert_eq!(book.apply_snapshot(snapshot(15)), Ok(0));
+        assert_eq!(book.state(), SyncState::AwaitingSnapshot);
+        assert_eq!(book.buffered(), 2);
+
+        assert_eq!(book.apply_snapshot(snapshot(20)), Ok(2));
+        assert_eq!(book.state(), SyncState::Live { last_update_id: 22 });
+        assert_eq!(book.book().best_bid(), Some((px(99), qty(5))));
+        assert_eq!(book.book().size_at(Side::Bid, px(92)), qty(1));
+    }
+}
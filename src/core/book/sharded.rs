@@ -0,0 +1,333 @@
+// Sharded order books for ShrivenQ
+// Symbols partitioned across independently locked shards, with lock-free top-of-book reads
+
+use super::{BookError, OrderBook};
+use crate::core::events::{MarketEvent, Side};
+use crate::core::types::symbol::DEFAULT_SYMBOL_CAPACITY;
+use crate::core::types::{Px, Qty, SymbolId};
+use crossbeam::utils::CachePadded;
+use parking_lot::Mutex;
+use rustc_hash::FxHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering, fence};
+
+const DEFAULT_SHARDS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardedBookConfig {
+    /// Independently locked partitions; symbols in different shards update in parallel
+    pub shards: usize,
+    /// Symbols whose index is at or past this are rejected. Sizes the
+    /// top-of-book table, which is allocated up front.
+    pub max_symbols: usize,
+}
+
+impl Default for ShardedBookConfig {
+    fn default() -> Self {
+        Self {
+            shards: DEFAULT_SHARDS,
+            max_symbols: DEFAULT_SYMBOL_CAPACITY,
+        }
+    }
+}
+
+/// Best bid and best ask of one symbol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopOfBook {
+    pub bid: Option<(Px, Qty)>,
+    pub ask: Option<(Px, Qty)>,
+}
+
+// Books of the symbols one shard owns, padded so shard locks never share a line
+type Shard = CachePadded<Mutex<HashMap<SymbolId, OrderBook>>>;
+
+// Best levels of one symbol, republished by the owning shard after every
+// update. `seq` is odd while a write is in progress; a zero size marks an
+// empty side.
+#[derive(Debug, Default)]
+struct Top {
+    seq: AtomicU64,
+    bid_px: AtomicI64,
+    bid_qty: AtomicI64,
+    ask_px: AtomicI64,
+    ask_qty: AtomicI64,
+}
+
+impl Top {
+    // Callers hold the owning shard's lock, so there is only ever one writer
+    fn publish(&self, book: &OrderBook) {
+        let (bid_px, bid_qty) = book
+            .best_bid()
+            .map_or((0, 0), |(px, qty)| (px.raw(), qty.raw()));
+        let (ask_px, ask_qty) = book
+            .best_ask()
+            .map_or((0, 0), |(px, qty)| (px.raw(), qty.raw()));
+
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.bid_px.store(bid_px, Ordering::Relaxed);
+        self.bid_qty.store(bid_qty, Ordering::Relaxed);
+        self.ask_px.store(ask_px, Ordering::Relaxed);
+        self.ask_qty.store(ask_qty, Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    // Retries while a write is in progress or lands mid-read
+    fn read(&self) -> TopOfBook {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before & 1 == 0 {
+                let bid = (
+                    self.bid_px.load(Ordering::Relaxed),
+                    self.bid_qty.load(Ordering::Relaxed),
+                );
+                let ask = (
+                    self.ask_px.load(Ordering::Relaxed),
+                    self.ask_qty.load(Ordering::Relaxed),
+                );
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == before {
+                    return TopOfBook {
+                        bid: level(bid),
+                        ask: level(ask),
+                    };
+                }
+            }
+            std::hint::spin_loop();
+        }
+    }
+}
+
+fn level((px, qty): (i64, i64)) -> Option<(Px, Qty)> {
+    (qty != 0).then(|| (Px::from_raw(px), Qty::from_raw(qty)))
+}
+
+/// [`OrderBook`]s for many symbols, partitioned across shards by a hash of
+/// the symbol.
+///
+/// Each shard has its own lock, so updates to symbols in different shards
+/// never wait on each other. Best bid and ask are published to a table
+/// indexed by [`SymbolId::index`] after every update and read from it without
+/// taking any lock; they always reflect a complete update, never a torn one.
+#[derive(Debug)]
+pub struct ShardedBook {
+    shards: Box<[Shard]>,
+    tops: Box<[Top]>,
+}
+
+impl ShardedBook {
+    pub fn new(config: ShardedBookConfig) -> Self {
+        Self {
+            shards: (0..config.shards.max(1))
+                .map(|_| CachePadded::new(Mutex::new(HashMap::new())))
+                .collect(),
+            tops: (0..config.max_symbols).map(|_| Top::default()).collect(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Shard that owns `symbol`'s book
+    pub fn shard_of(&self, symbol: SymbolId) -> usize {
+        let mut hasher = FxHasher::default();
+        symbol.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// Apply a `MarketEvent::BookUpdate` to its symbol's book, creating the
+    /// book on first sight
+    pub fn apply(&self, event: &MarketEvent) -> Result<(), BookError> {
+        let MarketEvent::BookUpdate { symbol, .. } = event else {
+            return Err(BookError::NotABookUpdate);
+        };
+        self.update(*symbol, |book| book.apply(event))
+    }
+
+    /// Set the aggregate size at one level of `symbol`'s book; zero removes the level
+    pub fn apply_level(
+        &self,
+        symbol: SymbolId,
+        side: Side,
+        price: Px,
+        size: Qty,
+    ) -> Result<(), BookError> {
+        self.update(symbol, |book| book.apply_level(side, price, size))
+    }
+
+    fn update(
+        &self,
+        symbol: SymbolId,
+        f: impl FnOnce(&mut OrderBook) -> Result<(), BookError>,
+    ) -> Result<(), BookError> {
+        let top = self
+            .tops
+            .get(symbol.index())
+            .ok_or(BookError::SymbolCapacity {
+                symbol,
+                capacity: self.tops.len(),
+            })?;
+
+        let mut books = self.shards[self.shard_of(symbol)].lock();
+        let book = books
+            .entry(symbol)
+            .or_insert_with(|| OrderBook::new(symbol));
+        f(book)?;
+        top.publish(book);
+        Ok(())
+    }
+
+    /// Best bid and best ask of `symbol`, read without locking its shard
+    pub fn top_of_book(&self, symbol: SymbolId) -> TopOfBook {
+        self.tops
+            .get(symbol.index())
+            .map_or(TopOfBook::default(), Top::read)
+    }
+
+    pub fn best_bid(&self, symbol: SymbolId) -> Option<(Px, Qty)> {
+        self.top_of_book(symbol).bid
+    }
+
+    pub fn best_ask(&self, symbol: SymbolId) -> Option<(Px, Qty)> {
+        self.top_of_book(symbol).ask
+    }
+
+    /// Run `f` on `symbol`'s book under its shard lock, for reads beyond the
+    /// top of book. `None` if the symbol has never been updated.
+    pub fn with_book<R>(&self, symbol: SymbolId, f: impl FnOnce(&OrderBook) -> R) -> Option<R> {
+        self.shards[self.shard_of(symbol)]
+            .lock()
+            .get(&symbol)
+            .map(f)
+    }
+
+    /// Symbols with a book, across all shards
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.lock().is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(prefix: &str, count: usize) -> Vec<SymbolId> {
+        (0..count)
+            .map(|i| SymbolId::intern(&format!("{prefix}{i}")).unwrap())
+            .collect()
+    }
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        const WRITERS: i64 = 4;
+        const ROUNDS: i64 = 200;
+        let book = ShardedBook::new(ShardedBookConfig {
+            shards: 4,
+            ..ShardedBookConfig::default()
+        });
+        let symbols = symbols("SHARDLOST", 64);
+
+        // Every writer adds a fresh level to every symbol each round, so a
+        // lost update leaves a level missing
+        std::thread::scope(|scope| {
+            for writer in 1..=WRITERS {
+                let (book, symbols) = (&book, &symbols);
+                scope.spawn(move || {
+                    for round in 1..=ROUNDS {
+                        let price = px((writer - 1) * ROUNDS + round);
+                        for &symbol in symbols {
+                            book.apply_level(symbol, Side::Bid, price, qty(writer))
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(book.len(), symbols.len());
+        let expected: Vec<_> = (1..=WRITERS)
+            .rev()
+            .flat_map(|writer| (1..=ROUNDS).rev().map(move |round| (writer, round)))
+            .map(|(writer, round)| (px((writer - 1) * ROUNDS + round), qty(writer)))
+            .collect();
+        for &symbol in &symbols {
+            assert_eq!(book.best_bid(symbol), expected.first().copied());
+            let levels: Vec<_> = book
+                .with_book(symbol, |b| b.levels(Side::Bid).collect())
+                .unwrap();
+            assert_eq!(levels, expected, "{symbol}");
+        }
+    }
+
+    #[test]
+    fn top_of_book_reads_are_never_torn() {
+        const UPDATES: i64 = 2_000;
+        const CEILING: i64 = UPDATES + 1;
+        let book = ShardedBook::new(ShardedBookConfig::default());
+        let symbols = symbols("SHARDTORN", 2);
+        let writers_done = AtomicU64::new(0);
+
+        std::thread::scope(|scope| {
+            // Update k publishes bid k x k, then ask (CEILING - k) x k
+            for &symbol in &symbols {
+                let (book, writers_done) = (&book, &writers_done);
+                scope.spawn(move || {
+                    for k in 1..=UPDATES {
+                        let (bid, ask, size) =
+                            (Px::from_raw(k), Px::from_raw(CEILING - k), Qty::from_raw(k));
+                        book.apply_level(symbol, Side::Bid, bid, size).unwrap();
+                        book.apply_level(symbol, Side::Ask, ask, size).unwrap();
+                        if k % 64 == 0 {
+                            // Let the readers in on a single core
+                            std::thread::yield_now();
+                        }
+                    }
+                    writers_done.fetch_add(1, Ordering::Release);
+                });
+            }
+            for _ in 0..2 {
+                let (book, symbols, writers_done) = (&book, &symbols, &writers_done);
+                scope.spawn(move || {
+                    let mut last = vec![(0, 0); symbols.len()];
+                    loop {
+                        let finished = writers_done.load(Ordering::Acquire) == 2;
+                        for (&symbol, last) in symbols.iter().zip(&mut last) {
+                            let top = book.top_of_book(symbol);
+                            let bid = top.bid.map_or(0, |(px, qty)| {
+                                assert_eq!(px.raw(), qty.raw(), "torn bid");
+                                qty.raw()
+                            });
+                            let ask = top.ask.map_or(0, |(px, qty)| {
+                                assert_eq!(px.raw(), CEILING - qty.raw(), "torn ask");
+                                qty.raw()
+                            });
+                            assert!(bid >= last.0 && ask >= last.1, "went backwards");
+                            assert!(bid == ask || bid == ask + 1, "bid {bid}, ask {ask}");
+                            *last = (bid, ask);
+                        }
+                        if finished {
+                            assert_eq!(last, [(UPDATES, UPDATES); 2]);
+                            return;
+                        }
+                        std::thread::yield_now();
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,40 @@
+// CUDA compute backend (stub)
+// Claims a device at init; kernels run on the host until device versions land
+
+use super::{ComputeBackend, ComputeError, CpuBackend};
+use crate::core::types::{Px, Qty};
+use cudarc::driver::CudaDevice;
+use std::sync::Arc;
+
+/// Holds CUDA device 0. Until device kernels exist every kernel delegates to
+/// `CpuBackend`, so results already match the reference exactly.
+#[derive(Debug)]
+pub struct CudaBackend {
+    _device: Arc<CudaDevice>,
+    host: CpuBackend,
+}
+
+impl CudaBackend {
+    pub fn init() -> Result<Self, ComputeError> {
+        let device = CudaDevice::new(0)
+            .map_err(|e| ComputeError::Unavailable(format!("CUDA device 0: {}", e)))?;
+        Ok(Self {
+            _device: device,
+            host: CpuBackend,
+        })
+    }
+}
+
+impl ComputeBackend for CudaBackend {
+    fn name(&self) -> &'static str {
+        "CUDA"
+    }
+
+    fn vwap(&self, prices: &[Px], sizes: &[Qty]) -> Result<Option<Px>, ComputeError> {
+        self.host.vwap(prices, sizes)
+    }
+
+    fn rolling_volatility(&self, prices: &[Px], window: usize) -> Result<Vec<f64>, ComputeError> {
+        self.host.rolling_volatility(prices, window)
+    }
+}
@@ -0,0 +1,196 @@
+// Compute backends for ShrivenQ analytics
+// CPU reference kernels, optional CUDA, and transparent fallback between them
+
+#[cfg(feature = "gpu-acceleration")]
+pub mod cuda;
+
+#[cfg(feature = "gpu-acceleration")]
+pub use cuda::CudaBackend;
+
+use crate::core::types::{Px, Qty};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ComputeError {
+    #[error("Compute backend unavailable: {0}")]
+    Unavailable(String),
+    #[error("{prices} prices but {sizes} sizes")]
+    LengthMismatch { prices: usize, sizes: usize },
+    #[error("Window of {0} returns is too small; need at least 2")]
+    InvalidWindow(usize),
+}
+
+/// Analytics kernels over price/size series.
+///
+/// Every backend must produce the same results as `CpuBackend`, which is the
+/// reference implementation.
+pub trait ComputeBackend: Debug + Send + Sync {
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// Volume-weighted average price; `None` when total size is zero
+    fn vwap(&self, prices: &[Px], sizes: &[Qty]) -> Result<Option<Px>, ComputeError>;
+
+    /// Sample standard deviation of simple returns over each run of `window`
+    /// consecutive returns, one value per window, oldest first
+    fn rolling_volatility(&self, prices: &[Px], window: usize) -> Result<Vec<f64>, ComputeError>;
+}
+
+/// Portable scalar kernels
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuBackend;
+
+impl ComputeBackend for CpuBackend {
+    fn name(&self) -> &'static str {
+        "CPU"
+    }
+
+    fn vwap(&self, prices: &[Px], sizes: &[Qty]) -> Result<Option<Px>, ComputeError> {
+        if prices.len() != sizes.len() {
+            return Err(ComputeError::LengthMismatch {
+                prices: prices.len(),
+                sizes: sizes.len(),
+            });
+        }
+
+        let (notional, volume) =
+            prices
+                .iter()
+                .zip(sizes)
+                .fold((0i128, 0i128), |(notional, volume), (price, size)| {
+                    (
+                        notional + i128::from(price.raw()) * i128::from(size.raw()),
+                        volume + i128::from(size.raw()),
+                    )
+                });
+        if volume == 0 {
+            return Ok(None);
+        }
+        Ok(i64::try_from(notional / volume).ok().map(Px::from_raw))
+    }
+
+    fn rolling_volatility(&self, prices: &[Px], window: usize) -> Result<Vec<f64>, ComputeError> {
+        if window < 2 {
+            return Err(ComputeError::InvalidWindow(window));
+        }
+
+        let returns: Vec<f64> = prices
+            .windows(2)
+            .map(|pair| match pair[0].raw() {
+                0 => 0.0,
+                prev => (pair[1].raw() - prev) as f64 / prev as f64,
+            })
+            .collect();
+
+        Ok(returns
+            .windows(window)
+            .map(|window| {
+                let n = window.len() as f64;
+                let mean = window.iter().sum::<f64>() / n;
+                let variance = window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                variance.sqrt()
+            })
+            .collect())
+    }
+}
+
+static GPU_FALLBACK_LOGGED: AtomicBool = AtomicBool::new(false);
+
+/// Backend for this process: CUDA when requested and it initializes, the CPU
+/// otherwise
+pub fn select_backend(gpu_requested: bool) -> Arc<dyn ComputeBackend> {
+    select_backend_with(gpu_requested, init_gpu_backend)
+}
+
+/// `select_backend` with an injectable GPU initializer. A failed init falls
+/// back to `CpuBackend`; the fallback is logged once per process.
+pub fn select_backend_with<F>(gpu_requested: bool, init_gpu: F) -> Arc<dyn ComputeBackend>
+where
+    F: FnOnce() -> Result<Arc<dyn ComputeBackend>, ComputeError>,
+{
+    if gpu_requested {
+        match init_gpu() {
+            Ok(backend) => {
+                info!("Compute backend: {}", backend.name());
+                return backend;
+            }
+            Err(e) => {
+                if !GPU_FALLBACK_LOGGED.swap(true, Ordering::Relaxed) {
+                    warn!("GPU unavailable, falling back to CPU compute: {}", e);
+                }
+            }
+        }
+    }
+    Arc::new(CpuBackend)
+}
+
+#[cfg(feature = "gpu-acceleration")]
+fn init_gpu_backend() -> Result<Arc<dyn ComputeBackend>, ComputeError> {
+    Ok(Arc::new(CudaBackend::init()?))
+}
+
+#[cfg(not(feature = "gpu-acceleration"))]
+fn init_gpu_backend() -> Result<Arc<dyn ComputeBackend>, ComputeError> {
+    Err(ComputeError::Unavailable(
+        "built without the gpu-acceleration feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeGpu;
+
+    impl ComputeBackend for FakeGpu {
+        fn name(&self) -> &'static str {
+            "FakeGpu"
+        }
+
+        fn vwap(&self, prices: &[Px], sizes: &[Qty]) -> Result<Option<Px>, ComputeError> {
+            CpuBackend.vwap(prices, sizes)
+        }
+
+        fn rolling_volatility(
+            &self,
+            prices: &[Px],
+            window: usize,
+        ) -> Result<Vec<f64>, ComputeError> {
+            CpuBackend.rolling_volatility(prices, window)
+        }
+    }
+
+    #[test]
+    fn failed_gpu_init_falls_back_to_the_cpu() {
+        let backend = select_backend_with(true, || {
+            Err(ComputeError::Unavailable("no device".to_string()))
+        });
+        assert_eq!(backend.name(), "CPU");
+
+        // Logged once, but every later failure still falls back
+        let again = select_backend_with(true, || {
+            Err(ComputeError::Unavailable("no device".to_string()))
+        });
+        assert_eq!(again.name(), "CPU");
+    }
+
+    #[test]
+    fn gpu_is_only_initialized_when_requested() {
+        let backend = select_backend_with(false, || panic!("GPU init without --gpu"));
+        assert_eq!(backend.name(), "CPU");
+
+        let backend = select_backend_with(true, || Ok(Arc::new(FakeGpu)));
+        assert_eq!(backend.name(), "FakeGpu");
+    }
+
+    #[cfg(not(feature = "gpu-acceleration"))]
+    #[test]
+    fn gpu_request_without_the_feature_selects_the_cpu() {
+        assert_eq!(select_backend(true).name(), "CPU");
+    }
+}
@@ -0,0 +1,519 @@
+// Runtime configuration for ShrivenQ
+// Typed view of the TOML config file, its layering, validation, and hot reload
+
+pub mod source;
+pub mod watcher;
+
+pub use source::{CliOverrides, ConfigSource, ENV_PREFIX};
+pub use watcher::{ConfigObserver, ConfigWatcher};
+
+use crate::core::journal::{JournalConfig, SnapshotConfig};
+use crate::core::memory::MemoryConfig;
+use crate::core::risk::{RiskLimits, RiskSettings};
+use crate::core::strategy::MeanReversionConfig;
+use crate::core::types::{Qty, SymbolId};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to parse {path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+    #[error("Invalid {field}: {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+impl ConfigError {
+    fn invalid(field: impl Into<String>, reason: impl ToString) -> Self {
+        Self::Invalid {
+            field: field.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// The settings ShrivenQ acts on. Other sections of the file are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub system: SystemConfig,
+    pub gpu: GpuConfig,
+    pub risk_management: RiskConfig,
+    /// Allocator selection; fixed at startup
+    pub memory: MemoryConfig,
+    /// Trade journal; fixed at startup
+    pub journal: JournalSettings,
+    /// Strategies the engine runs; fixed at startup
+    pub strategies: StrategiesConfig,
+    /// Metrics endpoint; fixed at startup
+    pub monitoring: MonitoringConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SystemConfig {
+    /// Tracing filter directive, e.g. `info` or `shriven_q=debug`
+    pub log_level: String,
+    pub worker_threads: usize,
+    /// Memory pool size such as `1GB`; fixed at startup
+    pub max_memory_pool_size: String,
+}
+
+impl Default for SystemConfig {
+    fn default() -> Self {
+        Self {
+            log_level: "info".into(),
+            worker_threads: 16,
+            max_memory_pool_size: "1GB".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GpuConfig {
+    pub enabled: bool,
+    /// Device memory pool size such as `512MB`; fixed at startup
+    pub memory_pool_size: String,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            memory_pool_size: "512MB".into(),
+        }
+    }
+}
+
+/// Where and how order actions are journalled. Journalling is off unless
+/// `dir` is set; the journal there is replayed on startup.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct JournalSettings {
+    pub dir: Option<PathBuf>,
+    /// Longest time a record may sit unsynced; zero syncs every record
+    pub sync_interval_ms: u64,
+    /// Segment size such as `64MB` before starting a new one
+    pub max_segment_size: String,
+    /// Where to snapshot books and positions, so startup replays only the
+    /// journal after the newest snapshot; off while unset
+    pub snapshot_dir: Option<PathBuf>,
+    pub snapshot_interval_secs: u64,
+    /// Snapshots kept on disk
+    pub snapshots_kept: usize,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        let snapshots = SnapshotConfig::default();
+        Self {
+            dir: None,
+            sync_interval_ms: 100,
+            max_segment_size: "64MB".into(),
+            snapshot_dir: None,
+            snapshot_interval_secs: snapshots.interval.as_secs(),
+            snapshots_kept: snapshots.keep,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MonitoringConfig {
+    /// Address the metrics endpoint listens on; loopback unless scraped remotely
+    pub metrics_bind: IpAddr,
+}
+
+impl Default for MonitoringConfig {
+    fn default() -> Self {
+        Self {
+            metrics_bind: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        }
+    }
+}
+
+/// Strategies to register with the engine; each is off while its section is absent
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StrategiesConfig {
+    pub mean_reversion: Option<MeanReversionSettings>,
+}
+
+/// [`MeanReversionStrategy`](crate::core::strategy::MeanReversionStrategy)
+/// on one symbol. Quantities are decimals, written as for risk limits.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MeanReversionSettings {
+    pub symbol: String,
+    pub window: usize,
+    pub entry_z: f64,
+    pub exit_z: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub order_qty: Option<String>,
+    #[serde(deserialize_with = "decimal")]
+    pub max_position: Option<String>,
+}
+
+impl Default for MeanReversionSettings {
+    fn default() -> Self {
+        Self {
+            symbol: String::new(),
+            window: 20,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            order_qty: Some("1".into()),
+            max_position: Some("1".into()),
+        }
+    }
+}
+
+/// Pre-trade limits. Quantities and amounts are decimals, written as TOML
+/// numbers or strings; leaving one out leaves that dimension unchecked.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    #[serde(deserialize_with = "decimal")]
+    pub max_position: Option<String>,
+    #[serde(deserialize_with = "decimal")]
+    pub max_order_size: Option<String>,
+    /// Largest tolerated session loss, as a positive amount
+    #[serde(deserialize_with = "decimal")]
+    pub max_daily_loss: Option<String>,
+    /// Per-symbol overrides; unset fields fall back to the limits above
+    pub symbols: HashMap<String, SymbolRiskConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SymbolRiskConfig {
+    #[serde(deserialize_with = "decimal")]
+    pub max_position: Option<String>,
+    #[serde(deserialize_with = "decimal")]
+    pub max_order_size: Option<String>,
+}
+
+// Accept a decimal as a TOML integer, float or string, keeping its text so
+// it parses into fixed point exactly
+fn decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Int(i64),
+        Float(f64),
+        Text(String),
+    }
+
+    Ok(Some(match Number::deserialize(deserializer)? {
+        Number::Int(value) => value.to_string(),
+        Number::Float(value) => value.to_string(),
+        Number::Text(value) => value,
+    }))
+}
+
+impl Config {
+    /// Parse a config file alone, without environment or command-line
+    /// overrides (see [`ConfigSource`]); call [`Config::validate`] before using it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        ConfigSource::new(path.as_ref()).load()
+    }
+
+    fn from_table(table: toml::Table) -> Result<Self, toml::de::Error> {
+        toml::Value::Table(table).try_into()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.system
+            .log_level
+            .parse::<tracing_subscriber::filter::Directive>()
+            .map_err(|e| ConfigError::invalid("system.log_level", e))?;
+        if self.system.worker_threads == 0 {
+            return Err(ConfigError::invalid(
+                "system.worker_threads",
+                "must be at least 1",
+            ));
+        }
+        parse_byte_size(&self.system.max_memory_pool_size)
+            .map_err(|reason| ConfigError::invalid("system.max_memory_pool_size", reason))?;
+        parse_byte_size(&self.gpu.memory_pool_size)
+            .map_err(|reason| ConfigError::invalid("gpu.memory_pool_size", reason))?;
+        if !self.memory.backend.is_available() {
+            return Err(ConfigError::invalid(
+                "memory.backend",
+                format!(
+                    "'{}' requires a build with the hft-unsafe feature",
+                    self.memory.backend
+                ),
+            ));
+        }
+        self.journal_config()?;
+        self.snapshot_config()?;
+        self.risk_settings()?;
+        self.mean_reversion_config()?;
+        Ok(())
+    }
+
+    /// Journal directory and settings, or `None` when journalling is off
+    pub fn journal_config(&self) -> Result<Option<(PathBuf, JournalConfig)>, ConfigError> {
+        let journal = &self.journal;
+        let max_segment_bytes = parse_byte_size(&journal.max_segment_size)
+            .map_err(|reason| ConfigError::invalid("journal.max_segment_size", reason))?;
+        Ok(journal.dir.clone().map(|dir| {
+            let config = JournalConfig {
+                max_segment_bytes,
+                sync_interval: Duration::from_millis(journal.sync_interval_ms),
+            };
+            (dir, config)
+        }))
+    }
+
+    /// Snapshot directory and settings, or `None` when snapshots are off
+    pub fn snapshot_config(&self) -> Result<Option<(PathBuf, SnapshotConfig)>, ConfigError> {
+        let journal = &self.journal;
+        let Some(dir) = &journal.snapshot_dir else {
+            return Ok(None);
+        };
+        if journal.dir.is_none() {
+            return Err(ConfigError::invalid(
+                "journal.snapshot_dir",
+                "snapshots need journal.dir to resume from",
+            ));
+        }
+        if journal.snapshot_interval_secs == 0 {
+            return Err(ConfigError::invalid(
+                "journal.snapshot_interval_secs",
+                "must be greater than 0",
+            ));
+        }
+        if journal.snapshots_kept == 0 {
+            return Err(ConfigError::invalid(
+                "journal.snapshots_kept",
+                "must be greater than 0",
+            ));
+        }
+        let config = SnapshotConfig {
+            interval: Duration::from_secs(journal.snapshot_interval_secs),
+            keep: journal.snapshots_kept,
+        };
+        Ok(Some((dir.clone(), config)))
+    }
+
+    /// Mean reversion strategy settings, or `None` when it is not configured
+    pub fn mean_reversion_config(&self) -> Result<Option<MeanReversionConfig>, ConfigError> {
+        let Some(settings) = &self.strategies.mean_reversion else {
+            return Ok(None);
+        };
+        let field = |key: &str| format!("strategies.mean_reversion.{key}");
+        if settings.symbol.is_empty() {
+            return Err(ConfigError::invalid(field("symbol"), "must be set"));
+        }
+        let symbol = SymbolId::intern(&settings.symbol)
+            .map_err(|e| ConfigError::invalid(field("symbol"), e))?;
+        if settings.window < 2 {
+            return Err(ConfigError::invalid(field("window"), "must be at least 2"));
+        }
+        if !(settings.entry_z.is_finite() && settings.entry_z > 0.0) {
+            return Err(ConfigError::invalid(field("entry_z"), "must be positive"));
+        }
+        if !(settings.exit_z >= 0.0 && settings.exit_z < settings.entry_z) {
+            return Err(ConfigError::invalid(
+                field("exit_z"),
+                "must be at least 0 and below entry_z",
+            ));
+        }
+        let required = |key: &str, value: &Option<String>| {
+            positive::<Qty>(&field(key), value)?
+                .ok_or_else(|| ConfigError::invalid(field(key), "must be set"))
+        };
+        let order_qty = required("order_qty", &settings.order_qty)?;
+        let max_position = required("max_position", &settings.max_position)?;
+        if max_position < order_qty {
+            return Err(ConfigError::invalid(
+                field("max_position"),
+                "must be at least order_qty",
+            ));
+        }
+
+        Ok(Some(MeanReversionConfig {
+            symbol,
+            window: settings.window,
+            entry_z: settings.entry_z,
+            exit_z: settings.exit_z,
+            order_qty,
+            max_position,
+        }))
+    }
+
+    /// Risk limits as the [`RiskEngine`](crate::core::risk::RiskEngine) enforces them
+    pub fn risk_settings(&self) -> Result<RiskSettings, ConfigError> {
+        let risk = &self.risk_management;
+        let default_limits = RiskLimits {
+            max_position: positive("risk_management.max_position", &risk.max_position)?,
+            max_order_size: positive("risk_management.max_order_size", &risk.max_order_size)?,
+        };
+
+        let mut symbol_limits = HashMap::with_capacity(risk.symbols.len());
+        for (name, limits) in &risk.symbols {
+            let field = |key: &str| format!("risk_management.symbols.{name}.{key}");
+            let symbol =
+                SymbolId::intern(name).map_err(|e| ConfigError::invalid(field("symbol"), e))?;
+            let limits = RiskLimits {
+                max_position: positive(&field("max_position"), &limits.max_position)?
+                    .or(default_limits.max_position),
+                max_order_size: positive(&field("max_order_size"), &limits.max_order_size)?
+                    .or(default_limits.max_order_size),
+            };
+            symbol_limits.insert(symbol, limits);
+        }
+
+        Ok(RiskSettings {
+            default_limits,
+            symbol_limits,
+            max_daily_loss: positive("risk_management.max_daily_loss", &risk.max_daily_loss)?,
+            instruments: HashMap::new(),
+        })
+    }
+
+    /// Startup-only fields that differ in `other`; sizes compare by value
+    pub fn startup_changes(&self, other: &Config) -> Vec<&'static str> {
+        let size = |text: &str| parse_byte_size(text).ok();
+        let mut changed = Vec::new();
+        if self.system.worker_threads != other.system.worker_threads {
+            changed.push("system.worker_threads");
+        }
+        if size(&self.system.max_memory_pool_size) != size(&other.system.max_memory_pool_size) {
+            changed.push("system.max_memory_pool_size");
+        }
+        if size(&self.gpu.memory_pool_size) != size(&other.gpu.memory_pool_size) {
+            changed.push("gpu.memory_pool_size");
+        }
+        if self.memory != other.memory {
+            changed.push("memory");
+        }
+        if self.journal != other.journal {
+            changed.push("journal");
+        }
+        if self.strategies != other.strategies {
+            changed.push("strategies");
+        }
+        if self.monitoring != other.monitoring {
+            changed.push("monitoring");
+        }
+        changed
+    }
+
+    /// Take the startup-only fields from `running`, keeping everything else
+    pub fn keep_startup_fields(&mut self, running: &Config) {
+        self.system.worker_threads = running.system.worker_threads;
+        self.system
+            .max_memory_pool_size
+            .clone_from(&running.system.max_memory_pool_size);
+        self.gpu
+            .memory_pool_size
+            .clone_from(&running.gpu.memory_pool_size);
+        self.memory.clone_from(&running.memory);
+        self.journal.clone_from(&running.journal);
+        self.strategies.clone_from(&running.strategies);
+        self.monitoring.clone_from(&running.monitoring);
+    }
+}
+
+fn positive<T>(field: &str, value: &Option<String>) -> Result<Option<T>, ConfigError>
+where
+    T: FromStr + Ord + Default,
+    T::Err: std::fmt::Display,
+{
+    let Some(text) = value else {
+        return Ok(None);
+    };
+    let value: T = text
+        .parse()
+        .map_err(|e| ConfigError::invalid(field, format!("'{text}': {e}")))?;
+    if value <= T::default() {
+        return Err(ConfigError::invalid(field, "must be positive"));
+    }
+    Ok(Some(value))
+}
+
+/// Parse a size such as `512MB`, `1GB` or `4096` (bytes); units are binary
+pub fn parse_byte_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (digits, unit) = text.split_at(split);
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("'{text}' is not a size"))?;
+    let scale: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "K" | "KIB" => 1 << 10,
+        "MB" | "M" | "MIB" => 1 << 20,
+        "GB" | "G" | "GIB" => 1 << 30,
+        "TB" | "T" | "TIB" => 1 << 40,
+        other => return Err(format!("unknown size unit '{other}'")),
+    };
+    match value.checked_mul(scale) {
+        Some(0) => Err("must be greater than zero".into()),
+        Some(bytes) => Ok(bytes),
+        None => Err(format!("'{text}' is too large")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Config {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn mean_reversion_is_read_from_its_section() {
+        assert_eq!(Config::default().mean_reversion_config().unwrap(), None);
+
+        let config = parse(
+            "[strategies.mean_reversion]\n\
+             symbol = \"CFGMEANREV\"\n\
+             window = 30\n\
+             order_qty = 0.5\n\
+             max_position = \"2\"\n",
+        );
+        let strategy = config.mean_reversion_config().unwrap().unwrap();
+        assert_eq!(strategy.symbol, SymbolId::intern("CFGMEANREV").unwrap());
+        assert_eq!(strategy.window, 30);
+        assert_eq!((strategy.entry_z, strategy.exit_z), (2.0, 0.5));
+        assert_eq!(strategy.order_qty, "0.5".parse().unwrap());
+        assert_eq!(strategy.max_position, Qty::from_int(2).unwrap());
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn bad_mean_reversion_settings_name_the_field() {
+        for (settings, field) in [
+            ("window = 5", "symbol"),
+            ("symbol = \"CFGMEANREV\"\nwindow = 1", "window"),
+            ("symbol = \"CFGMEANREV\"\nexit_z = 3.0", "exit_z"),
+            ("symbol = \"CFGMEANREV\"\norder_qty = 0", "order_qty"),
+            ("symbol = \"CFGMEANREV\"\norder_qty = 2", "max_position"),
+        ] {
+            let config = parse(&format!("[strategies.mean_reversion]\n{settings}\n"));
+            let error = config.validate().unwrap_err().to_string();
+            assert!(
+                error.contains(&format!("strategies.mean_reversion.{field}")),
+                "{settings}: {error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// Config layering
+// Built-in defaults, then the file, then SHRIVENQ_* environment variables, then command-line flags
+
+use super::{Config, ConfigError};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Prefix of the environment variables that override config file settings
+pub const ENV_PREFIX: &str = "SHRIVENQ_";
+
+// Settings that can come from the environment: variable name after the
+// prefix, and the file key it overrides
+const ENV_KEYS: &[(&str, &str)] = &[
+    ("SYSTEM_LOG_LEVEL", "system.log_level"),
+    ("SYSTEM_WORKER_THREADS", "system.worker_threads"),
+    ("SYSTEM_MAX_MEMORY_POOL_SIZE", "system.max_memory_pool_size"),
+    ("GPU_ENABLED", "gpu.enabled"),
+    ("GPU_MEMORY_POOL_SIZE", "gpu.memory_pool_size"),
+    ("MEMORY_BACKEND", "memory.backend"),
+    ("MEMORY_BUDGET_CEILING_BYTES", "memory.budget.ceiling_bytes"),
+    ("MEMORY_BUDGET_WARN_AT", "memory.budget.warn_at"),
+    ("JOURNAL_DIR", "journal.dir"),
+    ("JOURNAL_SNAPSHOT_DIR", "journal.snapshot_dir"),
+    ("RISK_MAX_POSITION", "risk_management.max_position"),
+    ("RISK_MAX_ORDER_SIZE", "risk_management.max_order_size"),
+    ("RISK_MAX_DAILY_LOSS", "risk_management.max_daily_loss"),
+];
+
+/// Settings given as command-line flags; unset ones leave the lower layers alone
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliOverrides {
+    pub log_level: Option<String>,
+}
+
+impl CliOverrides {
+    fn apply(&self, config: &mut Config) {
+        if let Some(level) = &self.log_level {
+            config.system.log_level.clone_from(level);
+        }
+    }
+}
+
+/// Where the config comes from, as layers over the built-in defaults. Later
+/// layers win: the file, then `SHRIVENQ_*` environment variables such as
+/// `SHRIVENQ_RISK_MAX_POSITION=1000`, then command-line flags.
+///
+/// Environment values are written as they would be in the file; ones that
+/// are not a TOML value, such as `info` or `1GB`, are taken as strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigSource {
+    path: PathBuf,
+    env: Vec<(String, String)>,
+    cli: CliOverrides,
+}
+
+impl ConfigSource {
+    /// The file at `path` alone, with no environment or flags over it
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            env: Vec::new(),
+            cli: CliOverrides::default(),
+        }
+    }
+
+    /// Take the `SHRIVENQ_*` variables from this process's environment.
+    /// They are captured now, so reloads see the same values.
+    pub fn with_process_env(self) -> Self {
+        self.with_env(std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }))
+    }
+
+    /// Take the `SHRIVENQ_*` variables from `vars`, ignoring all others.
+    /// Ones that name no setting, e.g. from a newer or older release, are
+    /// logged and ignored.
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .filter(|(name, _)| {
+                let known = env_key(name).is_some();
+                if !known {
+                    warn!("Ignoring {}: not a recognized setting", name);
+                }
+                known
+            })
+            .collect();
+        self.env.sort();
+        self
+    }
+
+    pub fn with_cli(mut self, cli: CliOverrides) -> Self {
+        self.cli = cli;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn cli(&self) -> &CliOverrides {
+        &self.cli
+    }
+
+    /// Names of the environment variables in effect
+    pub fn env_vars(&self) -> impl Iterator<Item = &str> + '_ {
+        self.env.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Parse the file and apply the layers over it; call [`Config::validate`]
+    /// before using the result
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        let text = std::fs::read_to_string(&self.path).map_err(|source| ConfigError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+        let table = toml::from_str(&text).map_err(|e| self.parse_error(e))?;
+        self.build(table)
+    }
+
+    /// The built-in defaults with the environment and flags applied, for
+    /// running without a file
+    pub fn load_defaults(&self) -> Result<Config, ConfigError> {
+        self.build(toml::Table::new())
+    }
+
+    fn build(&self, mut table: toml::Table) -> Result<Config, ConfigError> {
+        // Check the file alone first so its errors are not blamed on the environment
+        if !self.env.is_empty() {
+            Config::from_table(table.clone()).map_err(|e| self.parse_error(e))?;
+        }
+        for (name, value) in &self.env {
+            // `with_env` only keeps recognized names
+            let Some(key) = env_key(name) else {
+                continue;
+            };
+            set_key(&mut table, key, env_value(value))
+                .map_err(|reason| ConfigError::invalid(name, reason))?;
+        }
+
+        let mut config = Config::from_table(table).map_err(|e| {
+            if self.env.is_empty() {
+                self.parse_error(e)
+            } else {
+                let names: Vec<&str> = self.env_vars().collect();
+                ConfigError::invalid(names.join(", "), e)
+            }
+        })?;
+        self.cli.apply(&mut config);
+        Ok(config)
+    }
+
+    fn parse_error(&self, e: toml::de::Error) -> ConfigError {
+        ConfigError::Parse {
+            path: self.path.clone(),
+            reason: e.to_string(),
+        }
+    }
+}
+
+impl From<&str> for ConfigSource {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<String> for ConfigSource {
+    fn from(path: String) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<PathBuf> for ConfigSource {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<&Path> for ConfigSource {
+    fn from(path: &Path) -> Self {
+        Self::new(path)
+    }
+}
+
+// File key a `SHRIVENQ_*` variable overrides
+fn env_key(name: &str) -> Option<&'static str> {
+    let suffix = name.strip_prefix(ENV_PREFIX)?;
+    ENV_KEYS
+        .iter()
+        .find(|(known, _)| *known == suffix)
+        .map(|&(_, key)| key)
+}
+
+// A value as TOML would read it, falling back to the raw text as a string
+fn env_value(text: &str) -> toml::Value {
+    match toml::from_str::<toml::Table>(&format!("value = {text}")) {
+        Ok(mut table) if table.len() == 1 => table
+            .remove("value")
+            .unwrap_or_else(|| toml::Value::String(text.to_string())),
+        _ => toml::Value::String(text.to_string()),
+    }
+}
+
+// Set a dotted key, creating the tables along the way
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) -> Result<(), String> {
+    let mut parts = key.split('.').peekable();
+    let mut table = table;
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), value);
+            break;
+        }
+        table = table
+            .entry(part)
+            .or_insert_with(|| toml::Table::new().into())
+            .as_table_mut()
+            .ok_or_else(|| format!("{part} in the config file is not a table"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Qty;
+
+    fn env(vars: &[(&str, &str)]) -> ConfigSource {
+        ConfigSource::new("shriven.toml").with_env(
+            vars.iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string())),
+        )
+    }
+
+    #[test]
+    fn environment_overrides_the_defaults() {
+        let config = env(&[
+            ("SHRIVENQ_RISK_MAX_POSITION", "1000"),
+            ("SHRIVENQ_SYSTEM_LOG_LEVEL", "warn"),
+            ("SHRIVENQ_GPU_ENABLED", "true"),
+            ("SHRIVENQ_SYSTEM_MAX_MEMORY_POOL_SIZE", "2GB"),
+        ])
+        .load_defaults()
+        .unwrap();
+
+        assert_eq!(config.system.log_level, "warn");
+        assert!(config.gpu.enabled);
+        assert_eq!(config.system.max_memory_pool_size, "2GB");
+        let limits = config.risk_settings().unwrap().default_limits;
+        assert_eq!(limits.max_position, Qty::from_int(1000));
+    }
+
+    #[test]
+    fn unrelated_and_unknown_variables_are_ignored() {
+        let source = env(&[
+            ("PATH", "/usr/bin"),
+            ("SHRIVENQ_NO_SUCH_SETTING", "1"),
+            ("SHRIVENQ_RISK_MAX_ORDER_SIZE", "5"),
+        ]);
+        assert_eq!(
+            source.env_vars().collect::<Vec<_>>(),
+            ["SHRIVENQ_RISK_MAX_ORDER_SIZE"]
+        );
+
+        let config = source.load_defaults().unwrap();
+        let limits = config.risk_settings().unwrap().default_limits;
+        assert_eq!(limits.max_order_size, Qty::from_int(5));
+    }
+
+    #[test]
+    fn bad_environment_values_name_the_variable() {
+        let error = env(&[("SHRIVENQ_SYSTEM_WORKER_THREADS", "many")])
+            .load_defaults()
+            .unwrap_err();
+        assert!(error.to_string().contains("SHRIVENQ_SYSTEM_WORKER_THREADS"));
+    }
+
+    #[test]
+    fn flags_win_over_the_environment_and_the_file() {
+        let dir = std::env::temp_dir().join(format!("shriven-source-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shriven.toml");
+        std::fs::write(
+            &path,
+            "[system]\nlog_level = \"debug\"\nworker_threads = 2\n",
+        )
+        .unwrap();
+
+        let source = ConfigSource::new(&path)
+            .with_env([("SHRIVENQ_SYSTEM_LOG_LEVEL".to_string(), "warn".to_string())])
+            .with_cli(CliOverrides {
+                log_level: Some("error".into()),
+            });
+        let config = source.load().unwrap();
+        assert_eq!(config.system.log_level, "error");
+        assert_eq!(config.system.worker_threads, 2);
+
+        let config = source.with_cli(CliOverrides::default()).load().unwrap();
+        assert_eq!(config.system.log_level, "warn");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+// Config hot reload
+// Watches the config file for changes and pushes validated ones to running subsystems
+
+use super::{Config, ConfigError, ConfigSource};
+use crate::core::risk::RiskEngine;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// A running subsystem that takes the mutable part of a reloaded config
+pub trait ConfigObserver: Send + Sync {
+    /// Apply `config`, which replaces `previous`. Both have been validated
+    /// and share their startup-only fields.
+    fn apply(&self, previous: &Config, config: &Config) -> Result<(), ConfigError>;
+}
+
+impl<F> ConfigObserver for F
+where
+    F: Fn(&Config, &Config) -> Result<(), ConfigError> + Send + Sync,
+{
+    fn apply(&self, previous: &Config, config: &Config) -> Result<(), ConfigError> {
+        self(previous, config)
+    }
+}
+
+/// Reloaded limits reach every gate sharing this engine
+impl ConfigObserver for RiskEngine {
+    fn apply(&self, _previous: &Config, config: &Config) -> Result<(), ConfigError> {
+        let settings = config.risk_settings()?;
+        if settings != self.settings() {
+            self.update(settings);
+            info!("Risk limits reloaded");
+        }
+        Ok(())
+    }
+}
+
+/// Watches a config file and applies each valid edit without a restart.
+///
+/// A reload that fails to parse or validate is logged and the running config
+/// stays in force. Changes to startup-only fields such as pool sizes are
+/// logged and ignored; the rest of the reload still applies.
+pub struct ConfigWatcher {
+    source: ConfigSource,
+    debounce: Duration,
+    current: Mutex<Config>,
+    observers: Vec<Arc<dyn ConfigObserver>>,
+}
+
+impl fmt::Debug for ConfigWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigWatcher")
+            .field("source", &self.source)
+            .field("debounce", &self.debounce)
+            .field("observers", &self.observers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConfigWatcher {
+    /// Watch the file of `source`, which was loaded as `running`. Reloads
+    /// apply the same environment and command-line layers over the file.
+    pub fn new(source: impl Into<ConfigSource>, running: Config) -> Self {
+        Self {
+            source: source.into(),
+            debounce: Duration::from_millis(100),
+            current: Mutex::new(running),
+            observers: Vec::new(),
+        }
+    }
+
+    /// How long `monitor` lets a burst of file events settle before reloading,
+    /// so an editor's truncate-then-write is read once, whole
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    pub fn observe(mut self, observer: Arc<dyn ConfigObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        self.source.path()
+    }
+
+    /// The config in force
+    pub fn current(&self) -> Config {
+        self.current.lock().clone()
+    }
+
+    /// Load, validate and apply the file now. On error nothing is applied.
+    pub fn reload(&self) -> Result<(), ConfigError> {
+        let mut config = self.source.load()?;
+        config.validate()?;
+
+        let mut current = self.current.lock();
+        for field in current.startup_changes(&config) {
+            warn!(
+                "Ignoring change to {} in {}: it only takes effect on restart",
+                field,
+                self.path().display()
+            );
+        }
+        config.keep_startup_fields(&current);
+        if config == *current {
+            debug!("{} reloaded without changes", self.path().display());
+            return Ok(());
+        }
+
+        for observer in &self.observers {
+            if let Err(e) = observer.apply(&current, &config) {
+                warn!("Failed to apply reloaded config: {}", e);
+            }
+        }
+        *current = config;
+        info!("🔧 Reloaded configuration from {}", self.path().display());
+        Ok(())
+    }
+
+    /// Start watching the file and return the task that reloads it on each
+    /// change; run that as its own task. The watch is in place when this
+    /// returns, so edits made after it are not missed.
+    ///
+    /// The file's directory is watched rather than the file, as editors often
+    /// save by writing a new file and renaming it over the old one.
+    pub fn monitor(self: Arc<Self>) -> Result<impl Future<Output = ()> + Send, ConfigError> {
+        let path = self.path().to_path_buf();
+        let watch_error = |e: notify::Error| ConfigError::Io {
+            path: path.clone(),
+            source: std::io::Error::other(e),
+        };
+        let name = path.file_name().map(ToOwned::to_owned);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => ".".into(),
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let _ = tx.send(event);
+        })
+        .map_err(watch_error)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+
+        let touches_file = move |event: &Event| {
+            !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|p| p.file_name() == name.as_deref())
+        };
+        Ok(async move {
+            // Dropping the watcher ends the watch, so the task keeps it
+            let _watcher = watcher;
+            while let Some(event) = rx.recv().await {
+                match event {
+                    Ok(event) if touches_file(&event) => {}
+                    Ok(_) => continue,
+                    Err(e) => {
+                        warn!("Watching {} failed: {}", self.path().display(), e);
+                        continue;
+                    }
+                }
+                tokio::time::sleep(self.debounce).await;
+                while rx.try_recv().is_ok() {}
+                if let Err(e) = self.reload() {
+                    warn!("Config reload rejected, keeping the running config: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Qty;
+    use std::path::PathBuf;
+
+    fn config_file(name: &str, max_position: u32) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-watcher-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shriven.toml");
+        write_limits(&path, max_position);
+        path
+    }
+
+    fn write_limits(path: &Path, max_position: u32) {
+        let text = format!("[risk_management]\nmax_position = {max_position}\n");
+        std::fs::write(path, text).unwrap();
+    }
+
+    fn watched(path: &Path) -> (ConfigWatcher, RiskEngine) {
+        let source = ConfigSource::new(path);
+        let config = source.load().unwrap();
+        config.validate().unwrap();
+        let risk = RiskEngine::from_settings(config.risk_settings().unwrap());
+        let watcher = ConfigWatcher::new(source, config).observe(Arc::new(risk.clone()));
+        (watcher, risk)
+    }
+
+    fn max_position(risk: &RiskEngine) -> Option<Qty> {
+        risk.settings().default_limits.max_position
+    }
+
+    #[test]
+    fn reload_pushes_new_limits_to_the_risk_engine() {
+        let path = config_file("reload", 100);
+        let (watcher, risk) = watched(&path);
+        assert_eq!(max_position(&risk), Qty::from_int(100));
+
+        write_limits(&path, 50);
+        watcher.reload().unwrap();
+        assert_eq!(max_position(&risk), Qty::from_int(50));
+
+        std::fs::write(&path, "[risk_management]\nmax_position = -1\n").unwrap();
+        assert!(watcher.reload().is_err());
+        assert_eq!(max_position(&risk), Qty::from_int(50));
+        assert_eq!(
+            watcher.current().risk_management.max_position.as_deref(),
+            Some("50")
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn monitor_reloads_an_edited_file() {
+        let path = config_file("monitor", 100);
+        let (watcher, risk) = watched(&path);
+        let watcher = Arc::new(watcher.with_debounce(Duration::from_millis(10)));
+        let task = tokio::spawn(watcher.monitor().unwrap());
+
+        write_limits(&path, 25);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while max_position(&risk) != Qty::from_int(25) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "edit was not picked up"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        task.abort();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
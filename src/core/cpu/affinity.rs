@@ -0,0 +1,178 @@
+// CPU pinning for ShrivenQ
+// Dedicated cores for the runtime workers and the market data thread
+#![allow(unsafe_code)] // sched_getaffinity/sched_setaffinity are raw libc calls
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AffinityError {
+    #[error("No CPUs given")]
+    Empty,
+    #[error("CPU {0} is listed twice")]
+    Duplicate(usize),
+    #[error("CPU {cpu} is not available to this process (allowed: {allowed:?})")]
+    Unavailable { cpu: usize, allowed: Vec<usize> },
+    #[error("Thread affinity is only supported on Linux")]
+    Unsupported,
+    #[error("sched_setaffinity failed: {0}")]
+    Os(#[from] std::io::Error),
+}
+
+/// How `--cpu-affinity` splits its cores: the last one is reserved for the
+/// market data thread and the rest run the async runtime. A single core is
+/// shared by both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuAffinity {
+    runtime: Vec<usize>,
+    feed: usize,
+}
+
+impl CpuAffinity {
+    /// Plan pinning onto `cpus`, which must all be usable by this process
+    pub fn new(cpus: &[usize]) -> Result<Self, AffinityError> {
+        let (&feed, rest) = cpus.split_last().ok_or(AffinityError::Empty)?;
+        for (i, cpu) in cpus.iter().enumerate() {
+            if cpus[..i].contains(cpu) {
+                return Err(AffinityError::Duplicate(*cpu));
+            }
+        }
+
+        let allowed = current_thread_affinity()?;
+        if let Some(&cpu) = cpus.iter().find(|cpu| !allowed.contains(cpu)) {
+            return Err(AffinityError::Unavailable { cpu, allowed });
+        }
+
+        let runtime = if rest.is_empty() {
+            vec![feed]
+        } else {
+            rest.to_vec()
+        };
+        Ok(Self { runtime, feed })
+    }
+
+    /// Cores for the main thread and the runtime's workers
+    pub fn runtime_cpus(&self) -> &[usize] {
+        &self.runtime
+    }
+
+    /// Core for the market data thread
+    pub fn feed_cpu(&self) -> usize {
+        self.feed
+    }
+
+    /// Whether the feed thread has a core to itself
+    pub fn isolates_feed(&self) -> bool {
+        !self.runtime.contains(&self.feed)
+    }
+}
+
+/// Restrict the calling thread to `cpus`
+#[cfg(target_os = "linux")]
+pub fn set_current_thread_affinity(cpus: &[usize]) -> Result<(), AffinityError> {
+    use libc::{CPU_SET, CPU_SETSIZE, cpu_set_t, sched_setaffinity};
+
+    if cpus.is_empty() {
+        return Err(AffinityError::Empty);
+    }
+    if let Some(&cpu) = cpus.iter().find(|&&cpu| cpu >= CPU_SETSIZE as usize) {
+        return Err(AffinityError::Unavailable {
+            cpu,
+            allowed: current_thread_affinity()?,
+        });
+    }
+
+    // SAFETY: cpu_set_t is a plain bitmask, so all-zero is a valid empty set;
+    // every CPU index was bounds-checked against CPU_SETSIZE above
+    let result = unsafe {
+        let mut cpu_set: cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            CPU_SET(cpu, &mut cpu_set);
+        }
+        sched_setaffinity(0, std::mem::size_of::<cpu_set_t>(), &cpu_set)
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_current_thread_affinity(_cpus: &[usize]) -> Result<(), AffinityError> {
+    Err(AffinityError::Unsupported)
+}
+
+/// CPUs the calling thread may run on, ascending
+#[cfg(target_os = "linux")]
+pub fn current_thread_affinity() -> Result<Vec<usize>, AffinityError> {
+    use libc::{CPU_ISSET, CPU_SETSIZE, cpu_set_t, sched_getaffinity};
+
+    // SAFETY: the kernel fills at most size_of::<cpu_set_t>() bytes of the
+    // zeroed set, and CPU_ISSET only reads indices below CPU_SETSIZE
+    unsafe {
+        let mut cpu_set: cpu_set_t = std::mem::zeroed();
+        if sched_getaffinity(0, std::mem::size_of::<cpu_set_t>(), &mut cpu_set) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok((0..CPU_SETSIZE as usize)
+            .filter(|&cpu| CPU_ISSET(cpu, &cpu_set))
+            .collect())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_thread_affinity() -> Result<Vec<usize>, AffinityError> {
+    Err(AffinityError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_core_is_reserved_for_market_data() {
+        assert!(matches!(CpuAffinity::new(&[]), Err(AffinityError::Empty)));
+        assert!(matches!(
+            CpuAffinity::new(&[0, 0]),
+            Err(AffinityError::Duplicate(0))
+        ));
+
+        let Ok(allowed) = current_thread_affinity() else {
+            return;
+        };
+        let unusable = allowed.last().unwrap() + 1;
+        assert!(matches!(
+            CpuAffinity::new(&[unusable]),
+            Err(AffinityError::Unavailable { cpu, .. }) if cpu == unusable
+        ));
+
+        let shared = CpuAffinity::new(&allowed[..1]).unwrap();
+        assert_eq!(
+            (shared.runtime_cpus(), shared.feed_cpu()),
+            (&allowed[..1], allowed[0])
+        );
+        assert!(!shared.isolates_feed());
+        if let [runtime @ .., feed] = allowed.as_slice()
+            && !runtime.is_empty()
+        {
+            let split = CpuAffinity::new(&allowed).unwrap();
+            assert_eq!((split.runtime_cpus(), split.feed_cpu()), (runtime, *feed));
+            assert!(split.isolates_feed());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pinning_restricts_the_calling_thread() {
+        std::thread::spawn(|| {
+            let cpu = current_thread_affinity().unwrap()[0];
+            set_current_thread_affinity(&[cpu]).unwrap();
+            assert_eq!(current_thread_affinity().unwrap(), [cpu]);
+            assert!(matches!(
+                set_current_thread_affinity(&[]),
+                Err(AffinityError::Empty)
+            ));
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+// CPU feature detection for ShrivenQ
+// Runtime probing of the SIMD extensions the CPU-only analytics path relies on
+
+pub mod affinity;
+
+use serde::Serialize;
+
+/// SIMD and bit-manipulation extensions available on this machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CpuFeatures {
+    pub arch: &'static str,
+    /// Detected extensions, in probe order
+    pub features: Vec<&'static str>,
+    /// Widest SIMD level usable at runtime
+    pub simd_level: &'static str,
+}
+
+impl CpuFeatures {
+    pub fn detect() -> Self {
+        let features = detect_features();
+        let simd_level = simd_level(&features);
+        Self {
+            arch: std::env::consts::ARCH,
+            features,
+            simd_level,
+        }
+    }
+
+    pub fn has(&self, feature: &str) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Features this binary was compiled to assume (`-C target-feature` /
+    /// `target-cpu`) that the running CPU does not have
+    pub fn missing_compiled_features(&self) -> Vec<&'static str> {
+        compiled_features()
+            .into_iter()
+            .filter(|feature| !self.has(feature))
+            .collect()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn detect_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    macro_rules! probe {
+        ($($feature:tt),*) => {
+            $(if std::arch::is_x86_feature_detected!($feature) {
+                features.push($feature);
+            })*
+        };
+    }
+    probe!(
+        "sse4.2", "popcnt", "avx", "avx2", "fma", "bmi1", "bmi2", "avx512f", "avx512bw", "avx512vl"
+    );
+    features
+}
+
+#[cfg(target_arch = "aarch64")]
+fn detect_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        features.push("neon");
+    }
+    if std::arch::is_aarch64_feature_detected!("sve") {
+        features.push("sve");
+    }
+    features
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn detect_features() -> Vec<&'static str> {
+    Vec::new()
+}
+
+fn compiled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    macro_rules! compiled {
+        ($($feature:tt),*) => {
+            $(if cfg!(target_feature = $feature) {
+                features.push($feature);
+            })*
+        };
+    }
+    compiled!(
+        "sse4.2", "popcnt", "avx", "avx2", "fma", "bmi1", "bmi2", "avx512f", "avx512bw",
+        "avx512vl", "sve"
+    );
+    features
+}
+
+fn simd_level(features: &[&'static str]) -> &'static str {
+    ["avx512f", "avx2", "avx", "sse4.2", "sve", "neon"]
+        .into_iter()
+        .find(|level| features.contains(level))
+        .unwrap_or("scalar")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn detection_reports_this_cpus_features() {
+        let cpu = CpuFeatures::detect();
+
+        assert_eq!(cpu.arch, "x86_64");
+        assert!(!cpu.features.is_empty());
+        assert!(cpu.features.iter().all(|feature| cpu.has(feature)));
+        assert_ne!(cpu.simd_level, "scalar");
+        // The test binary runs on the machine it was built for
+        assert!(cpu.missing_compiled_features().is_empty());
+    }
+
+    #[test]
+    fn simd_level_is_the_widest_detected() {
+        assert_eq!(simd_level(&["sse4.2", "avx", "avx2", "bmi2"]), "avx2");
+        assert_eq!(simd_level(&["avx2", "avx512f"]), "avx512f");
+        assert_eq!(simd_level(&["neon"]), "neon");
+        assert_eq!(simd_level(&["popcnt"]), "scalar");
+    }
+}
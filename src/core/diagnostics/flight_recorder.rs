@@ -0,0 +1,280 @@
+// Flight recorder - the last few significant events, kept in memory
+// Dumped when the process panics or on demand, for post-mortem debugging
+
+#![allow(unsafe_code)] // Slots are seqlocks over plain memory
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use crate::core::execution::ExecutionMode;
+use crate::core::feeds::watchdog::StaleFeed;
+use crate::core::risk::RiskReject;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_FLIGHT_RECORDER_CAPACITY: usize = 4096;
+
+/// Something worth knowing about after the fact. Everything is `Copy` so
+/// recording never allocates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlightEvent {
+    ModeSwitch {
+        from: ExecutionMode,
+        to: ExecutionMode,
+    },
+    RiskReject(RiskReject),
+    /// An allocator returned an error, by `AllocError::kind`
+    AllocFailure {
+        reason: &'static str,
+    },
+    FeedStall(StaleFeed),
+}
+
+impl fmt::Display for FlightEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlightEvent::ModeSwitch { from, to } => write!(f, "mode switch {} -> {}", from, to),
+            FlightEvent::RiskReject(reject) => write!(f, "risk reject: {}", reject),
+            FlightEvent::AllocFailure { reason } => write!(f, "allocation failed: {}", reason),
+            FlightEvent::FeedStall(StaleFeed::Connection { venue, silent_for }) => {
+                write!(f, "feed stall: {} silent for {:?}", venue, silent_for)
+            }
+            FlightEvent::FeedStall(StaleFeed::Symbol { symbol, silent_for }) => {
+                write!(f, "feed stall: {} silent for {:?}", symbol, silent_for)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedEvent {
+    /// Position in the recorder's history, starting at 0
+    pub seq: u64,
+    /// Wall-clock nanoseconds since the epoch
+    pub timestamp_ns: u64,
+    pub event: FlightEvent,
+}
+
+impl fmt::Display for RecordedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} @{}ns {}", self.seq, self.timestamp_ns, self.event)
+    }
+}
+
+// One ring entry guarded by a seqlock: `version` is odd while a write is in
+// progress and zero until the first write completes
+struct Slot {
+    version: AtomicU64,
+    event: UnsafeCell<MaybeUninit<RecordedEvent>>,
+}
+
+impl Slot {
+    // Overwrite the slot, or give up if another writer holds it
+    fn write(&self, event: RecordedEvent) -> bool {
+        let version = self.version.load(Ordering::Relaxed);
+        if version & 1 == 1
+            || self
+                .version
+                .compare_exchange(version, version + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return false;
+        }
+        // Order the odd version before the payload stores
+        fence(Ordering::Release);
+        // SAFETY: The odd version makes this the only writer; readers copy the
+        // payload as `MaybeUninit` and discard it unless the version is stable
+        unsafe { self.event.get().write_volatile(MaybeUninit::new(event)) };
+        self.version.store(version + 2, Ordering::Release);
+        true
+    }
+
+    fn read(&self) -> Option<RecordedEvent> {
+        let before = self.version.load(Ordering::Acquire);
+        if before == 0 || before & 1 == 1 {
+            return None;
+        }
+        // SAFETY: Copying out as `MaybeUninit` is fine even mid-write; the copy
+        // is only used once the version check below shows it was not torn
+        let event = unsafe { self.event.get().read_volatile() };
+        fence(Ordering::Acquire);
+        if self.version.load(Ordering::Relaxed) != before {
+            return None;
+        }
+        // SAFETY: A stable, non-zero even version means a complete write
+        // landed before `before` was read and none started since
+        Some(unsafe { event.assume_init() })
+    }
+}
+
+/// Fixed-size ring of the most recent events.
+///
+/// Recording claims a sequence number with one atomic add and overwrites the
+/// slot it maps to under a per-slot seqlock, so the ring never grows and
+/// nothing ever spins or takes a lock. A dump skips slots that are mid-write
+/// at that instant. Should a writer lap the ring and find its slot still
+/// being written, it drops its event rather than wait.
+pub struct FlightRecorder {
+    slots: Box<[Slot]>,
+    next_seq: AtomicU64,
+}
+
+// SAFETY: Slot payloads are `Copy` and only accessed through the seqlock in
+// `Slot::write`/`Slot::read`, which never hands out a reference into a slot
+unsafe impl Sync for FlightRecorder {}
+
+impl FlightRecorder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity.max(1))
+                .map(|_| Slot {
+                    version: AtomicU64::new(0),
+                    event: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect(),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Process-wide recorder fed by the mode switcher, risk checks,
+    /// allocators and feed watchdogs
+    pub fn global() -> &'static FlightRecorder {
+        static GLOBAL: OnceLock<FlightRecorder> = OnceLock::new();
+        GLOBAL.get_or_init(|| FlightRecorder::new(DEFAULT_FLIGHT_RECORDER_CAPACITY))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Events recorded since creation, including those overwritten
+    pub fn recorded(&self) -> u64 {
+        self.next_seq.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, event: FlightEvent) {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let timestamp_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        self.slots[(seq % self.slots.len() as u64) as usize].write(RecordedEvent {
+            seq,
+            timestamp_ns,
+            event,
+        });
+    }
+
+    /// The retained events, oldest first
+    pub fn dump(&self) -> Vec<RecordedEvent> {
+        let oldest = self
+            .next_seq
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.slots.len() as u64);
+        let mut events: Vec<RecordedEvent> = self
+            .slots
+            .iter()
+            .filter_map(Slot::read)
+            .filter(|recorded| recorded.seq >= oldest)
+            .collect();
+        events.sort_unstable_by_key(|recorded| recorded.seq);
+        events
+    }
+
+    /// Print the global recorder to stderr whenever a thread panics, after
+    /// the usual panic message
+    pub fn install_panic_hook() {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let events = FlightRecorder::global().dump();
+            eprintln!("Flight recorder: last {} events", events.len());
+            for recorded in events {
+                eprintln!("  {}", recorded);
+            }
+        }));
+    }
+}
+
+impl fmt::Debug for FlightRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlightRecorder")
+            .field("capacity", &self.capacity())
+            .field("recorded", &self.recorded())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alloc_failure(index: usize) -> FlightEvent {
+        const REASONS: [&str; 4] = ["exhausted", "too_large", "misaligned", "budget"];
+        FlightEvent::AllocFailure {
+            reason: REASONS[index % REASONS.len()],
+        }
+    }
+
+    #[test]
+    fn dump_returns_the_last_capacity_events_in_order() {
+        let recorder = FlightRecorder::new(8);
+        assert!(recorder.dump().is_empty());
+
+        for index in 0..3 {
+            recorder.record(alloc_failure(index));
+        }
+        let seqs: Vec<_> = recorder
+            .dump()
+            .iter()
+            .map(|recorded| recorded.seq)
+            .collect();
+        assert_eq!(seqs, [0, 1, 2]);
+
+        for index in 3..21 {
+            recorder.record(alloc_failure(index));
+        }
+        let events = recorder.dump();
+        assert_eq!(recorder.recorded(), 21);
+        assert_eq!(events.len(), 8);
+        for (recorded, seq) in events.iter().zip(13..) {
+            assert_eq!(recorded.seq, seq);
+            assert_eq!(recorded.event, alloc_failure(seq as usize));
+        }
+        assert!(
+            events
+                .windows(2)
+                .all(|pair| pair[0].timestamp_ns <= pair[1].timestamp_ns)
+        );
+    }
+
+    #[test]
+    fn concurrent_writers_leave_a_consistent_ring() {
+        let recorder = FlightRecorder::new(64);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for index in 0..500 {
+                        recorder.record(alloc_failure(index));
+                        if index % 32 == 0 {
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    let events = recorder.dump();
+                    assert!(events.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+                    std::thread::yield_now();
+                }
+            });
+        });
+
+        let events = recorder.dump();
+        assert_eq!(recorder.recorded(), 2000);
+        assert!(events.len() <= 64);
+        assert!(events.iter().all(|recorded| recorded.seq >= 2000 - 64));
+    }
+}
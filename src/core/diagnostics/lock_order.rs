@@ -0,0 +1,224 @@
+// Lock ordering for ShrivenQ
+// Every tracked lock has a rank; debug builds panic when a thread takes them out of order
+
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Where a lock sits in the global acquisition order. A thread holding a
+/// lock may only take locks of strictly higher rank, so no two threads can
+/// ever wait on each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockRank {
+    pub rank: u16,
+    pub name: &'static str,
+}
+
+impl LockRank {
+    pub const fn new(rank: u16, name: &'static str) -> Self {
+        Self { rank, name }
+    }
+}
+
+impl fmt::Display for LockRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (rank {})", self.name, self.rank)
+    }
+}
+
+// The order itself. Leave gaps so new locks can slot in between.
+pub const SAFE_POOL_ALLOCATED_CHUNKS: LockRank =
+    LockRank::new(100, "SafeMemoryPool::allocated_chunks");
+pub const SAFE_POOL_CHUNK: LockRank = LockRank::new(110, "SafeMemoryPool chunk");
+pub const NUMA_THREAD_NODE_CACHE: LockRank = LockRank::new(200, "NumaAllocator::thread_node_cache");
+pub const NUMA_ALLOCATION_STATS: LockRank = LockRank::new(210, "NumaAllocator::allocation_stats");
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static HELD: std::cell::RefCell<Vec<LockRank>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Marks `rank` as held by this thread until dropped. Release builds track
+/// nothing and this is zero-sized.
+#[derive(Debug)]
+pub struct HeldRank {
+    #[cfg(debug_assertions)]
+    rank: LockRank,
+}
+
+/// Record that this thread is about to take a lock of `rank`, panicking if it
+/// already holds one of equal or higher rank. Call before blocking on the
+/// lock so a violation is reported instead of deadlocking.
+pub fn acquire(rank: LockRank) -> HeldRank {
+    #[cfg(debug_assertions)]
+    {
+        let violation = HELD.with_borrow_mut(|held| {
+            let holding = held
+                .iter()
+                .find(|holding| holding.rank >= rank.rank)
+                .copied();
+            if holding.is_none() {
+                held.push(rank);
+            }
+            holding
+        });
+        if let Some(holding) = violation {
+            panic!("Lock order violation: acquiring {rank} while holding {holding}");
+        }
+        HeldRank { rank }
+    }
+    #[cfg(not(debug_assertions))]
+    {
+        let _ = rank;
+        HeldRank {}
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for HeldRank {
+    fn drop(&mut self) {
+        // Guards may be dropped in any order, so remove the latest matching entry
+        HELD.with_borrow_mut(|held| {
+            if let Some(index) = held.iter().rposition(|holding| *holding == self.rank) {
+                held.remove(index);
+            }
+        });
+    }
+}
+
+/// A lock guard together with its rank; the guard is released first
+#[derive(Debug)]
+pub struct OrderedGuard<G> {
+    guard: G,
+    _held: HeldRank,
+}
+
+impl<G: Deref> Deref for OrderedGuard<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for OrderedGuard<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<'a, T> OrderedGuard<MutexGuard<'a, T>> {
+    /// `MutexGuard::map` that keeps the rank held
+    pub fn map<U: ?Sized>(
+        this: Self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> OrderedGuard<MappedMutexGuard<'a, U>> {
+        OrderedGuard {
+            guard: MutexGuard::map(this.guard, f),
+            _held: this._held,
+        }
+    }
+}
+
+/// `parking_lot::Mutex` checked against the lock order
+#[derive(Debug)]
+pub struct OrderedMutex<T> {
+    rank: LockRank,
+    inner: Mutex<T>,
+}
+
+impl<T> OrderedMutex<T> {
+    pub fn new(rank: LockRank, value: T) -> Self {
+        Self {
+            rank,
+            inner: Mutex::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> OrderedGuard<MutexGuard<'_, T>> {
+        let held = acquire(self.rank);
+        OrderedGuard {
+            guard: self.inner.lock(),
+            _held: held,
+        }
+    }
+}
+
+/// `parking_lot::RwLock` checked against the lock order. Readers count as
+/// holders too: a read guard blocks writers just as well.
+#[derive(Debug)]
+pub struct OrderedRwLock<T> {
+    rank: LockRank,
+    inner: RwLock<T>,
+}
+
+impl<T> OrderedRwLock<T> {
+    pub fn new(rank: LockRank, value: T) -> Self {
+        Self {
+            rank,
+            inner: RwLock::new(value),
+        }
+    }
+
+    pub fn read(&self) -> OrderedGuard<RwLockReadGuard<'_, T>> {
+        let held = acquire(self.rank);
+        OrderedGuard {
+            guard: self.inner.read(),
+            _held: held,
+        }
+    }
+
+    pub fn write(&self) -> OrderedGuard<RwLockWriteGuard<'_, T>> {
+        let held = acquire(self.rank);
+        OrderedGuard {
+            guard: self.inner.write(),
+            _held: held,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_taken_in_rank_order_are_allowed() {
+        let allocated = OrderedMutex::new(SAFE_POOL_ALLOCATED_CHUNKS, Vec::<u32>::new());
+        let chunk = OrderedRwLock::new(SAFE_POOL_CHUNK, 0u32);
+
+        let mut allocated = allocated.lock();
+        allocated.push(*chunk.read());
+        *chunk.write() += 1;
+        assert_eq!(*allocated, [0]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Lock order violation")]
+    fn taking_a_lower_rank_while_holding_a_higher_one_panics() {
+        let allocated = OrderedMutex::new(SAFE_POOL_ALLOCATED_CHUNKS, ());
+        let chunk = OrderedMutex::new(SAFE_POOL_CHUNK, ());
+
+        let _chunk = chunk.lock();
+        let _allocated = allocated.lock();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn releasing_out_of_order_clears_every_rank() {
+        let allocated = OrderedMutex::new(SAFE_POOL_ALLOCATED_CHUNKS, 0u32);
+        let chunk = OrderedMutex::new(SAFE_POOL_CHUNK, 0u32);
+
+        let outer = allocated.lock();
+        let inner = OrderedGuard::map(chunk.lock(), |value| value);
+        assert_eq!(HELD.with_borrow(Vec::len), 2);
+        drop(outer);
+        assert_eq!(HELD.with_borrow(|held| held.clone()), [SAFE_POOL_CHUNK]);
+        drop(inner);
+        assert!(HELD.with_borrow(Vec::is_empty));
+
+        // Both ranks are free again, in either order
+        drop(chunk.lock());
+        drop(allocated.lock());
+    }
+}
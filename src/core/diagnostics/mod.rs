@@ -0,0 +1,7 @@
+// Diagnostics for ShrivenQ
+// Post-mortem state that outlives the logs: what happened right before a failure
+
+pub mod flight_recorder;
+pub mod lock_order;
+
+pub use flight_recorder::{FlightEvent, FlightRecorder, RecordedEvent};
@@ -0,0 +1,850 @@
+// Trading engine facade for ShrivenQ
+// Single entry point composing memory, event bus, execution mode and timing
+
+use crate::core::book::OrderBook;
+use crate::core::compute::{ComputeBackend, CpuBackend};
+use crate::core::events::bus::DEFAULT_BUS_CAPACITY;
+use crate::core::events::{EventBus, EventPublisher, OverflowPolicy};
+use crate::core::execution::ExecutionMode;
+use crate::core::execution::gateway::{OrderGateway, PaperGateway, RiskGate, SimGateway};
+use crate::core::execution::mode_switcher::ModeSwitcher;
+use crate::core::execution::rng::RngService;
+use crate::core::execution::router::OrderRouter;
+use crate::core::execution::sim_matching::{FillModel, SimMatchingEngine, SimNoise};
+use crate::core::feeds::{FeedError, MarketDataFeed, run_feed};
+use crate::core::journal::{
+    self, Journal, JournalConfig, Recovery, SharedJournal, SnapshotConfig, SnapshotState,
+    Snapshotter,
+};
+use crate::core::memory::{Clock, MemoryBackend, SafePoolConfig, SystemClock};
+use crate::core::risk::RiskEngine;
+use crate::core::strategy::{Strategy, StrategyRunner};
+use crate::core::time::{PrecisionTimer, SimClock, TimeSource};
+use crate::core::types::SymbolId;
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// A feed's run onto the event bus, as handed to the feed spawner
+pub type FeedTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct FeedSpawner(Box<dyn Fn(FeedTask) -> Result<()> + Send + Sync>);
+
+impl fmt::Debug for FeedSpawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FeedSpawner")
+    }
+}
+
+// A feed waiting for the bus and clock it will publish with
+struct PendingFeed(Box<dyn FnOnce(EventPublisher, TimeSource) -> FeedTask + Send>);
+
+impl PendingFeed {
+    fn new<F: MarketDataFeed + 'static>(feed: F, symbols: Vec<String>) -> Self {
+        Self(Box::new(move |publisher, time| {
+            Box::pin(async move {
+                let venue = feed.name();
+                match run_feed(feed, &symbols, publisher, &time).await {
+                    Ok(()) | Err(FeedError::Closed) => info!("{} feed finished", venue),
+                    Err(e) => warn!("{} feed stopped: {}", venue, e),
+                }
+            })
+        }))
+    }
+}
+
+impl fmt::Debug for PendingFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PendingFeed")
+    }
+}
+
+/// Builder for [`Engine`]; every subsystem can be injected, anything left
+/// unset falls back to a safe default.
+#[derive(Debug)]
+pub struct EngineBuilder {
+    mode: ExecutionMode,
+    memory: Option<Arc<MemoryBackend>>,
+    bus_capacity: usize,
+    bus_overflow: OverflowPolicy,
+    risk: RiskEngine,
+    compute: Option<Arc<dyn ComputeBackend>>,
+    rng: Option<RngService>,
+    sim_noise: SimNoise,
+    time: Option<TimeSource>,
+    clock: Option<Arc<dyn Clock>>,
+    sim_clock: Option<SimClock>,
+    strategies: Vec<Box<dyn Strategy>>,
+    feeds: Vec<PendingFeed>,
+    feed_spawner: Option<FeedSpawner>,
+    journal: Option<(PathBuf, JournalConfig)>,
+    snapshots: Option<(PathBuf, SnapshotConfig)>,
+}
+
+impl EngineBuilder {
+    pub fn new(mode: ExecutionMode) -> Self {
+        Self {
+            mode,
+            memory: None,
+            bus_capacity: DEFAULT_BUS_CAPACITY,
+            bus_overflow: OverflowPolicy::DropOldest,
+            risk: RiskEngine::default(),
+            compute: None,
+            rng: None,
+            sim_noise: SimNoise::default(),
+            time: None,
+            clock: None,
+            sim_clock: None,
+            strategies: Vec::new(),
+            feeds: Vec::new(),
+            feed_spawner: None,
+            journal: None,
+            snapshots: None,
+        }
+    }
+
+    /// Use an already-initialized memory backend instead of a default safe pool
+    pub fn memory_backend(mut self, backend: Arc<MemoryBackend>) -> Self {
+        self.memory = Some(backend);
+        self
+    }
+
+    /// Capacity of the market event bus between feeds and strategies
+    pub fn event_bus_capacity(mut self, capacity: usize) -> Self {
+        self.bus_capacity = capacity;
+        self
+    }
+
+    /// What publishing does when the event bus is full. Drops the oldest
+    /// event by default, since fresh market data supersedes stale.
+    pub fn event_bus_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.bus_overflow = policy;
+        self
+    }
+
+    /// Pre-trade limits applied to every routed order
+    pub fn risk_engine(mut self, risk: RiskEngine) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Analytics backend, normally from `compute::select_backend`
+    pub fn compute_backend(mut self, backend: Arc<dyn ComputeBackend>) -> Self {
+        self.compute = Some(backend);
+        self
+    }
+
+    /// Seeded randomness for simulation; a random seed is picked if unset
+    pub fn rng(mut self, rng: RngService) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Random latency and slippage applied by simulated gateways
+    pub fn sim_noise(mut self, noise: SimNoise) -> Self {
+        self.sim_noise = noise;
+        self
+    }
+
+    /// Session clock feeds are normalized to, e.g. one driven by a mock clock
+    pub fn time_source(mut self, time: TimeSource) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Clock for time-dependent components; the system clock by default,
+    /// or a fresh [`SimClock`] in backtest mode
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self.sim_clock = None;
+        self
+    }
+
+    /// Drive time from event timestamps with `clock`, which strategy runs advance
+    pub fn sim_clock(mut self, clock: SimClock) -> Self {
+        self.clock = Some(Arc::new(clock.clone()));
+        self.sim_clock = Some(clock);
+        self
+    }
+
+    /// Strategy driven from the event bus while the engine runs
+    pub fn strategy(mut self, strategy: impl Strategy + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// Market data feed subscribed to `symbols`, publishing onto the event
+    /// bus once the engine runs
+    pub fn feed(mut self, feed: impl MarketDataFeed + 'static, symbols: Vec<String>) -> Self {
+        self.feeds.push(PendingFeed::new(feed, symbols));
+        self
+    }
+
+    /// Where feed tasks run, e.g. a thread pinned to a dedicated core;
+    /// spawned on the current tokio runtime by default
+    pub fn feed_spawner(
+        mut self,
+        spawner: impl Fn(FeedTask) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.feed_spawner = Some(FeedSpawner(Box::new(spawner)));
+        self
+    }
+
+    /// Journal every order action and fill in `dir`. Building replays the
+    /// journal there, so positions and order ids carry on from the last session.
+    pub fn journal(mut self, dir: impl Into<PathBuf>, config: JournalConfig) -> Self {
+        self.journal = Some((dir.into(), config));
+        self
+    }
+
+    /// Snapshot books and journalled state to `dir` while strategies run.
+    /// Building then starts from the newest snapshot there and replays only
+    /// the journal after it. Needs a [`journal`](Self::journal).
+    pub fn snapshots(mut self, dir: impl Into<PathBuf>, config: SnapshotConfig) -> Self {
+        self.snapshots = Some((dir.into(), config));
+        self
+    }
+
+    pub fn build(self) -> Result<Engine> {
+        if self.bus_capacity == 0 {
+            anyhow::bail!("Event bus capacity must be greater than 0");
+        }
+        if self.mode == ExecutionMode::Live {
+            anyhow::bail!(NO_LIVE_GATEWAY);
+        }
+
+        let memory = match self.memory {
+            Some(backend) => backend,
+            None => Arc::new(MemoryBackend::safe(SafePoolConfig::default())?),
+        };
+
+        let (clock, sim_clock): (Arc<dyn Clock>, _) = match (self.clock, self.sim_clock) {
+            (Some(clock), sim_clock) => (clock, sim_clock),
+            (None, _) if self.mode == ExecutionMode::Backtest => {
+                let sim_clock = SimClock::new();
+                (Arc::new(sim_clock.clone()), Some(sim_clock))
+            }
+            (None, _) => (Arc::new(SystemClock), None),
+        };
+        let time = self
+            .time
+            .unwrap_or_else(|| TimeSource::with_clock(Arc::clone(&clock)));
+
+        let (journal, state, snapshotter) = match (self.journal, self.snapshots) {
+            (Some((dir, config)), snapshots) => {
+                // Replay before opening: opening truncates a torn tail
+                let (state, snapshotter) = match snapshots {
+                    Some((snapshot_dir, snapshot_config)) => (
+                        journal::snapshot::restore(&snapshot_dir, &dir)?.state,
+                        Some(Snapshotter::new(&snapshot_dir, snapshot_config)?),
+                    ),
+                    None => {
+                        let recovery = journal::recover(&dir)?;
+                        (
+                            SnapshotState {
+                                recovery,
+                                ..SnapshotState::default()
+                            },
+                            None,
+                        )
+                    }
+                };
+                let journal = SharedJournal::new(Journal::open(&dir, config)?)?
+                    .with_state(state.recovery.clone());
+                info!("📓 Journalling to {}", dir.display());
+                (Some(journal), state, snapshotter)
+            }
+            (None, Some(_)) => anyhow::bail!("Snapshots need a journal to resume from"),
+            (None, None) => (None, SnapshotState::default(), None),
+        };
+
+        Ok(Engine {
+            mode_switcher: Arc::new(Mutex::new(ModeSwitcher::new(self.mode))),
+            memory,
+            event_bus: EventBus::with_policy(self.bus_capacity, self.bus_overflow),
+            risk: self.risk,
+            compute: self.compute.unwrap_or_else(|| Arc::new(CpuBackend)),
+            rng: self.rng.unwrap_or_else(RngService::from_entropy),
+            sim_noise: self.sim_noise,
+            time,
+            clock,
+            sim_clock,
+            strategies: self.strategies,
+            feeds: self.feeds,
+            feed_spawner: self.feed_spawner.unwrap_or_else(|| {
+                FeedSpawner(Box::new(|task| {
+                    tokio::spawn(task);
+                    Ok(())
+                }))
+            }),
+            journal,
+            recovery: state.recovery,
+            books: state.books,
+            snapshotter,
+            session_timer: None,
+        })
+    }
+}
+
+const NO_LIVE_GATEWAY: &str =
+    "Live mode needs a venue gateway, and none is built in yet; use paper mode";
+
+#[derive(Debug)]
+pub struct Engine {
+    mode_switcher: Arc<Mutex<ModeSwitcher>>,
+    memory: Arc<MemoryBackend>,
+    event_bus: EventBus,
+    risk: RiskEngine,
+    compute: Arc<dyn ComputeBackend>,
+    rng: RngService,
+    sim_noise: SimNoise,
+    time: TimeSource,
+    clock: Arc<dyn Clock>,
+    sim_clock: Option<SimClock>,
+    strategies: Vec<Box<dyn Strategy>>,
+    feeds: Vec<PendingFeed>,
+    feed_spawner: FeedSpawner,
+    journal: Option<SharedJournal>,
+    recovery: Recovery,
+    books: HashMap<SymbolId, OrderBook>,
+    snapshotter: Option<Snapshotter>,
+    session_timer: Option<PrecisionTimer>,
+}
+
+/// Summary of a single engine session, produced by [`Engine::shutdown`]
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub mode: ExecutionMode,
+    pub memory_backend: &'static str,
+    pub uptime_micros: u64,
+    pub events_published: u64,
+    pub events_dropped: u64,
+}
+
+impl Engine {
+    pub fn builder(mode: ExecutionMode) -> EngineBuilder {
+        EngineBuilder::new(mode)
+    }
+
+    pub fn mode(&self) -> ExecutionMode {
+        self.mode_switcher.lock().current_mode()
+    }
+
+    pub fn switch_mode(&mut self, new_mode: ExecutionMode) -> Result<()> {
+        self.mode_switcher.lock().switch_mode(new_mode)
+    }
+
+    /// Shared handle for monitors that must change mode from another task,
+    /// such as a feed watchdog forcing a safe mode
+    pub fn mode_switcher(&self) -> Arc<Mutex<ModeSwitcher>> {
+        Arc::clone(&self.mode_switcher)
+    }
+
+    pub fn memory(&self) -> &Arc<MemoryBackend> {
+        &self.memory
+    }
+
+    /// Bus carrying market events from feeds to strategies
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Clock that every feed's timestamps are normalized to
+    pub fn time_source(&self) -> &TimeSource {
+        &self.time
+    }
+
+    /// Clock to hand time-dependent components such as watchdogs and
+    /// rolling windows
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Virtual clock following event time, in backtest mode
+    pub fn sim_clock(&self) -> Option<&SimClock> {
+        self.sim_clock.as_ref()
+    }
+
+    pub fn risk(&self) -> &RiskEngine {
+        &self.risk
+    }
+
+    pub fn compute(&self) -> &Arc<dyn ComputeBackend> {
+        &self.compute
+    }
+
+    /// Seed source for every simulated component; log its seed to replay a session
+    pub fn rng(&self) -> &RngService {
+        &self.rng
+    }
+
+    /// Journal shared by the engine's gateways, if one was configured
+    pub fn journal(&self) -> Option<&SharedJournal> {
+        self.journal.as_ref()
+    }
+
+    /// State replayed from the journal at build; empty without one
+    pub fn recovery(&self) -> &Recovery {
+        &self.recovery
+    }
+
+    /// Books restored from the newest snapshot at build; empty without one
+    pub fn books(&self) -> &HashMap<SymbolId, OrderBook> {
+        &self.books
+    }
+
+    /// Order router for the current mode, gated by the engine's risk limits
+    /// and publishing fills on the event bus
+    pub fn order_router(&self, model: FillModel) -> OrderRouter {
+        OrderRouter::new(self.mode(), self.risk.clone(), self.sim_matcher(model))
+    }
+
+    /// Simulated-fill gateway behind the engine's risk limits
+    pub fn sim_gateway(&self, model: FillModel) -> RiskGate<SimGateway> {
+        self.journalled(SimGateway::new(self.sim_matcher(model), self.risk.clone()))
+    }
+
+    fn sim_matcher(&self, model: FillModel) -> SimMatchingEngine {
+        SimMatchingEngine::new(
+            model,
+            self.event_bus.publisher(),
+            self.rng.stream("sim_matching"),
+        )
+        .with_noise(self.sim_noise)
+    }
+
+    /// Acknowledge-only gateway behind the engine's risk limits
+    pub fn paper_gateway(&self) -> RiskGate<PaperGateway> {
+        self.journalled(PaperGateway::new(self.risk.clone()))
+    }
+
+    // Start `gate` from the recovered positions, writing to the journal
+    fn journalled<G: OrderGateway>(&self, gate: RiskGate<G>) -> RiskGate<G> {
+        let gate = gate.with_portfolio(self.recovery.portfolio.clone());
+        match &self.journal {
+            Some(journal) => gate.with_journal(journal.clone()),
+            None => gate,
+        }
+    }
+
+    // Runner continuing the recovered books, positions and order ids, and
+    // taking over the snapshots
+    fn runner<G: OrderGateway>(
+        &mut self,
+        gateway: G,
+        strategies: Vec<Box<dyn Strategy>>,
+    ) -> StrategyRunner<G> {
+        let first_order_id = self.recovery.last_order_id.map_or(1, |id| id + 1);
+        let runner = StrategyRunner::new(gateway)
+            .with_strategies(strategies)
+            .with_books(self.books.clone())
+            .with_portfolio(self.recovery.portfolio.clone())
+            .with_first_order_id(first_order_id);
+        match (&self.journal, self.snapshotter.take()) {
+            (Some(journal), Some(snapshotter)) => {
+                runner.with_snapshots(journal.clone(), snapshotter)
+            }
+            _ => runner,
+        }
+    }
+
+    /// Add a strategy to drive from the event bus on the next `run`
+    pub fn register_strategy(&mut self, strategy: Box<dyn Strategy>) {
+        self.strategies.push(strategy);
+    }
+
+    pub fn strategies(&self) -> usize {
+        self.strategies.len()
+    }
+
+    /// Add a market data feed to start on the next `run`
+    pub fn add_feed(&mut self, feed: impl MarketDataFeed + 'static, symbols: Vec<String>) {
+        self.feeds.push(PendingFeed::new(feed, symbols));
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.session_timer.is_some()
+    }
+
+    /// Run the engine until `shutdown` resolves: start the added feeds, then
+    /// drive registered strategies from the event bus. Backtest and
+    /// simulation orders go to the simulated gateway, paper orders to the
+    /// paper gateway; live mode is refused until a venue gateway exists.
+    /// Feeds and strategies are consumed by the run; feeds stop once the
+    /// engine is dropped.
+    pub async fn run<F>(&mut self, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        if self.is_running() {
+            anyhow::bail!("Engine is already running");
+        }
+        if self.mode() == ExecutionMode::Live {
+            anyhow::bail!(NO_LIVE_GATEWAY);
+        }
+
+        self.session_timer = Some(PrecisionTimer::start());
+        info!(
+            "Engine running in {} mode ({} memory backend)",
+            self.mode(),
+            self.memory.backend_type()
+        );
+
+        for feed in std::mem::take(&mut self.feeds) {
+            let task = (feed.0)(self.event_bus.publisher(), self.time.clone());
+            (self.feed_spawner.0)(task)?;
+        }
+
+        let strategies = std::mem::take(&mut self.strategies);
+        if strategies.is_empty() {
+            warn!("No strategies registered; events are published but not traded on");
+            shutdown.await;
+            return Ok(());
+        }
+
+        let subscriber = self.event_bus.subscriber();
+        let stats = match self.mode() {
+            ExecutionMode::Backtest | ExecutionMode::Simulation => {
+                let gateway = self.sim_gateway(FillModel::ImmediateAtTouch);
+                let mut runner = self.runner(gateway, strategies);
+                if let Some(clock) = &self.sim_clock {
+                    runner = runner.with_sim_clock(clock.clone());
+                }
+                runner.run(&subscriber, shutdown).await;
+                runner.stats()
+            }
+            ExecutionMode::Paper | ExecutionMode::Live => {
+                let gateway = self.paper_gateway();
+                let mut runner = self.runner(gateway, strategies);
+                runner.run(&subscriber, shutdown).await;
+                runner.stats()
+            }
+        };
+        info!(
+            "🧠 Strategies handled {} events: {} orders placed, {} rejected, {} cancels",
+            stats.events, stats.orders_submitted, stats.orders_rejected, stats.cancels
+        );
+        Ok(())
+    }
+
+    /// Stop the engine and report on the session that just ended
+    pub fn shutdown(&mut self) -> SessionReport {
+        let uptime_micros = self
+            .session_timer
+            .take()
+            .map(|timer| timer.elapsed_micros())
+            .unwrap_or(0);
+
+        let report = SessionReport {
+            mode: self.mode(),
+            memory_backend: self.memory.backend_type(),
+            uptime_micros,
+            events_published: self.event_bus.published(),
+            events_dropped: self.event_bus.dropped(),
+        };
+        info!(
+            "Engine stopped after {}μs in {} mode",
+            report.uptime_micros, report.mode
+        );
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::{MarketEvent, Side};
+    use crate::core::orders::{Fill, Order, OrderKind, OrderSide};
+    use crate::core::strategy::{MeanReversionConfig, MeanReversionStrategy, OrderContext};
+    use crate::core::types::{Px, Qty, SymbolId};
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    // Plays back a fixed list of events, then closes
+    struct ScriptedFeed(VecDeque<MarketEvent>);
+
+    impl MarketDataFeed for ScriptedFeed {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        async fn subscribe(&mut self, _symbols: &[String]) -> Result<(), FeedError> {
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+            self.0.pop_front().ok_or(FeedError::Closed)
+        }
+    }
+
+    #[derive(Debug)]
+    struct CountingStrategy(Arc<AtomicU64>);
+
+    impl Strategy for CountingStrategy {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn on_event(&mut self, _event: &MarketEvent, _ctx: &mut OrderContext<'_>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_feed_events_through_strategies_and_reports_the_session() {
+        let events = (1..=5)
+            .map(|ns| MarketEvent::Heartbeat {
+                timestamp_ns: ns,
+                normalized_ns: ns,
+            })
+            .collect();
+        let seen = Arc::new(AtomicU64::new(0));
+        let mut engine = Engine::builder(ExecutionMode::Backtest)
+            .memory_backend(Arc::new(
+                MemoryBackend::safe(SafePoolConfig::default()).unwrap(),
+            ))
+            .rng(RngService::new(7))
+            .feed(ScriptedFeed(events), Vec::new())
+            .strategy(CountingStrategy(Arc::clone(&seen)))
+            .build()
+            .unwrap();
+
+        let all_seen = {
+            let seen = Arc::clone(&seen);
+            async move {
+                while seen.load(Ordering::Relaxed) < 5 {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), engine.run(all_seen))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(engine.is_running());
+
+        let report = engine.shutdown();
+        assert!(!engine.is_running());
+        assert_eq!(report.mode, ExecutionMode::Backtest);
+        assert_eq!(report.memory_backend, "Safe");
+        assert_eq!((report.events_published, report.events_dropped), (5, 0));
+        assert_eq!(seen.load(Ordering::Relaxed), 5);
+        assert_eq!(engine.sim_clock().map(SimClock::now_ns), Some(5));
+    }
+
+    // Passes events to `inner` and keeps the fills among them
+    #[derive(Debug)]
+    struct FillRecorder<S> {
+        inner: S,
+        fills: Arc<Mutex<Vec<Fill>>>,
+    }
+
+    impl<S: Strategy> Strategy for FillRecorder<S> {
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+
+        fn on_event(&mut self, event: &MarketEvent, ctx: &mut OrderContext<'_>) {
+            if let MarketEvent::Fill(fill) = event {
+                self.fills.lock().push(fill.clone());
+            }
+            self.inner.on_event(event, ctx);
+        }
+    }
+
+    #[tokio::test]
+    async fn mean_reversion_trades_a_scripted_stream() {
+        let symbol = SymbolId::intern("ENGMEANREV").unwrap();
+        let px = |price: i64| Px::from_int(price).unwrap();
+        let level = |side, price| MarketEvent::BookUpdate {
+            symbol,
+            timestamp_ns: 0,
+            normalized_ns: 0,
+            side,
+            price: px(price),
+            size: Qty::from_int(10).unwrap(),
+        };
+        let trades = [100, 101, 100, 101, 90, 99];
+        let events = [level(Side::Bid, 98), level(Side::Ask, 91)]
+            .into_iter()
+            .chain(
+                trades
+                    .iter()
+                    .zip(1..)
+                    .map(|(&price, ns)| MarketEvent::Trade {
+                        symbol,
+                        timestamp_ns: ns,
+                        normalized_ns: ns,
+                        price: px(price),
+                        size: Qty::from_int(1).unwrap(),
+                    }),
+            )
+            .collect();
+
+        let fills = Arc::new(Mutex::new(Vec::new()));
+        let strategy = MeanReversionStrategy::new(MeanReversionConfig {
+            symbol,
+            window: 4,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            order_qty: Qty::from_int(1).unwrap(),
+            max_position: Qty::from_int(1).unwrap(),
+        });
+        let mut engine = Engine::builder(ExecutionMode::Simulation)
+            .rng(RngService::new(7))
+            .feed(ScriptedFeed(events), Vec::new())
+            .strategy(FillRecorder {
+                inner: strategy,
+                fills: Arc::clone(&fills),
+            })
+            .build()
+            .unwrap();
+
+        let both_filled = {
+            let fills = Arc::clone(&fills);
+            async move {
+                while fills.lock().len() < 2 {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), engine.run(both_filled))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The drop to 90 is bought at the ask; the return to 99 flattens at the bid
+        let fills: Vec<_> = fills
+            .lock()
+            .iter()
+            .map(|fill| (fill.side, fill.price, fill.qty, fill.timestamp_ns))
+            .collect();
+        let one = Qty::from_int(1).unwrap();
+        assert_eq!(
+            fills,
+            [
+                (OrderSide::Buy, px(91), one, 5),
+                (OrderSide::Sell, px(98), one, 6)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn live_mode_is_refused_without_a_venue_gateway() {
+        assert!(Engine::builder(ExecutionMode::Live).build().is_err());
+
+        let mut engine = Engine::builder(ExecutionMode::Paper).build().unwrap();
+        engine.switch_mode(ExecutionMode::Live).unwrap();
+        assert!(engine.run(std::future::ready(())).await.is_err());
+        assert!(!engine.is_running());
+    }
+
+    #[tokio::test]
+    async fn journal_is_replayed_when_the_engine_is_rebuilt() {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-engine-journal-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let build = || {
+            Engine::builder(ExecutionMode::Paper)
+                .journal(&dir, JournalConfig::default())
+                .build()
+                .unwrap()
+        };
+
+        let engine = build();
+        assert_eq!(engine.recovery().records, 0);
+        let order = Order {
+            id: 7,
+            symbol: SymbolId::intern("ENGJRNL").unwrap(),
+            side: OrderSide::Buy,
+            kind: OrderKind::Limit(Px::from_int(100).unwrap()),
+            qty: Qty::from_int(1).unwrap(),
+            timestamp_ns: 1,
+        };
+        engine.paper_gateway().submit(order).await.unwrap();
+        drop(engine);
+
+        let engine = build();
+        let recovery = engine.recovery();
+        assert_eq!(recovery.last_order_id, Some(7));
+        assert!(recovery.open_orders.contains_key(&7));
+        drop(engine);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn engine_warm_starts_from_the_snapshot_its_strategies_took() {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-engine-snapshot-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let symbol = SymbolId::intern("ENGSNAP").unwrap();
+        let builder = || {
+            Engine::builder(ExecutionMode::Paper)
+                .journal(dir.join("journal"), JournalConfig::default())
+                .snapshots(dir.join("snapshots"), SnapshotConfig::default())
+        };
+
+        let book_update = MarketEvent::BookUpdate {
+            symbol,
+            timestamp_ns: 1,
+            normalized_ns: 1,
+            side: Side::Bid,
+            price: Px::from_int(99).unwrap(),
+            size: Qty::from_int(4).unwrap(),
+        };
+        let seen = Arc::new(AtomicU64::new(0));
+        let mut engine = builder()
+            .feed(ScriptedFeed(VecDeque::from([book_update])), Vec::new())
+            .strategy(CountingStrategy(Arc::clone(&seen)))
+            .build()
+            .unwrap();
+        assert!(engine.books().is_empty());
+        let book_seen = {
+            let seen = Arc::clone(&seen);
+            async move {
+                while seen.load(Ordering::Relaxed) < 1 {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), engine.run(book_seen))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Journalled after the snapshot the run ended with
+        let order = Order {
+            id: 3,
+            symbol,
+            side: OrderSide::Buy,
+            kind: OrderKind::Limit(Px::from_int(99).unwrap()),
+            qty: Qty::from_int(1).unwrap(),
+            timestamp_ns: 2,
+        };
+        engine.paper_gateway().submit(order).await.unwrap();
+        drop(engine);
+
+        let engine = builder().build().unwrap();
+        assert_eq!(
+            engine.books()[&symbol].best_bid(),
+            Some((Px::from_int(99).unwrap(), Qty::from_int(4).unwrap()))
+        );
+        assert_eq!(engine.recovery().last_order_id, Some(3));
+        drop(engine);
+
+        assert!(
+            Engine::builder(ExecutionMode::Paper)
+                .snapshots(dir.join("snapshots"), SnapshotConfig::default())
+                .build()
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
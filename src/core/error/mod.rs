@@ -0,0 +1,144 @@
+// Top-level errors for ShrivenQ
+// One error per failing subsystem, each with its own process exit code
+
+use crate::core::config::ConfigError;
+use crate::core::execution::gateway::GatewayError;
+use crate::core::feeds::FeedError;
+use crate::core::memory::AllocError;
+use crate::core::risk::RiskReject;
+use std::process::ExitCode;
+use thiserror::Error;
+use tracing::error;
+
+/// Why a ShrivenQ process failed.
+///
+/// Each subsystem maps to its own exit code, following `sysexits.h` where one
+/// fits, so supervisors can tell a bad config from a dead feed without
+/// reading logs. Errors that belong to no subsystem are [`Other`](Self::Other).
+#[derive(Error, Debug)]
+pub enum ShrivenError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Memory(#[from] AllocError),
+    #[error(transparent)]
+    Feed(#[from] FeedError),
+    #[error(transparent)]
+    Gateway(#[from] GatewayError),
+    #[error(transparent)]
+    Risk(#[from] RiskReject),
+    #[error("{0:#}")]
+    Other(anyhow::Error),
+}
+
+pub type Result<T, E = ShrivenError> = std::result::Result<T, E>;
+
+impl ShrivenError {
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // EX_CONFIG
+            Self::Config(_) => 78,
+            // EX_OSERR
+            Self::Memory(_) => 71,
+            // EX_UNAVAILABLE
+            Self::Feed(_) => 69,
+            // EX_PROTOCOL
+            Self::Gateway(_) => 76,
+            // EX_NOPERM
+            Self::Risk(_) => 77,
+            Self::Other(_) => 1,
+        }
+    }
+
+    /// Short subsystem name for structured logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "config",
+            Self::Memory(_) => "memory",
+            Self::Feed(_) => "feed",
+            Self::Gateway(_) => "gateway",
+            Self::Risk(_) => "risk",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Log the error as one line and return the exit code to leave with.
+    /// Goes to stderr if logging was never set up.
+    pub fn report(&self) -> ExitCode {
+        let code = self.exit_code();
+        if tracing::dispatcher::has_been_set() {
+            error!(kind = self.kind(), exit_code = code, "{}", self);
+        } else {
+            eprintln!("error [{}]: {}", self.kind(), self);
+        }
+        ExitCode::from(code)
+    }
+}
+
+/// Recovers the subsystem of errors that crossed an `anyhow` boundary
+impl From<anyhow::Error> for ShrivenError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ConfigError>() {
+            Ok(e) => return Self::Config(e),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<AllocError>() {
+            Ok(e) => return Self::Memory(e),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<FeedError>() {
+            Ok(e) => return Self::Feed(e),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<GatewayError>() {
+            Ok(e) => return Self::Gateway(e),
+            Err(error) => error,
+        };
+        match error.downcast::<RiskReject>() {
+            Ok(e) => Self::Risk(e),
+            Err(error) => Self::Other(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::core::memory::{SafeMemoryPool, SafePoolConfig};
+    use anyhow::Context;
+
+    #[test]
+    fn config_error_exits_with_ex_config() {
+        let missing = std::env::temp_dir().join("shriven-no-such-config.toml");
+        let error = ShrivenError::from(Config::load(&missing).unwrap_err());
+        assert_eq!((error.kind(), error.exit_code()), ("config", 78));
+        assert_eq!(error.report(), ExitCode::from(78));
+    }
+
+    #[test]
+    fn memory_init_error_exits_with_ex_oserr() {
+        let config = SafePoolConfig {
+            chunk_size: 0,
+            ..SafePoolConfig::default()
+        };
+        let error = ShrivenError::from(SafeMemoryPool::new(config).unwrap_err());
+        assert_eq!((error.kind(), error.exit_code()), ("memory", 71));
+        assert_eq!(error.report(), ExitCode::from(71));
+    }
+
+    #[test]
+    fn subsystem_survives_an_anyhow_boundary() {
+        let wrapped = Err::<(), _>(AllocError::PoolExhausted)
+            .context("allocating order slab")
+            .unwrap_err();
+        assert_eq!(ShrivenError::from(wrapped).exit_code(), 71);
+
+        let feed = anyhow::Error::new(FeedError::Closed);
+        assert_eq!(ShrivenError::from(feed).exit_code(), 69);
+
+        let plain = ShrivenError::from(anyhow::anyhow!("something else"));
+        assert_eq!((plain.kind(), plain.exit_code()), ("other", 1));
+    }
+}
@@ -0,0 +1,396 @@
+// Event bus - moves market events from feed threads to strategy threads
+// Bounded crossbeam channel: lock-free on the fast path, no allocation per event
+
+use super::MarketEvent;
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+pub const DEFAULT_BUS_CAPACITY: usize = 65_536;
+
+// After the first, only every this many dropped events is logged
+const DROP_ALERT_EVERY: u64 = 10_000;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BusError {
+    #[error("Event bus is full")]
+    Full,
+    #[error("Event bus is disconnected")]
+    Disconnected,
+}
+
+/// What [`EventPublisher::publish`] does when the bus is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wait for the consumer to make room. Required wherever losing an
+    /// event is not acceptable, e.g. order events.
+    #[default]
+    Block,
+    /// Discard the oldest queued event to make room. Usually right for
+    /// market data, where the latest update supersedes older ones. Its
+    /// publishers hold the queue open, so they never see `Disconnected`.
+    DropOldest,
+    /// Discard the event being published
+    DropNewest,
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OverflowPolicy::Block => "block",
+            OverflowPolicy::DropOldest => "drop-oldest",
+            OverflowPolicy::DropNewest => "drop-newest",
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct BusCounters {
+    published: AtomicU64,
+    rejected: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl BusCounters {
+    fn record_drop(&self, policy: OverflowPolicy) {
+        let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        if dropped == 1 || dropped % DROP_ALERT_EVERY == 0 {
+            warn!(
+                "Event bus full: {} events dropped so far ({})",
+                dropped, policy
+            );
+        }
+    }
+}
+
+/// Bounded multi-producer event bus.
+///
+/// Any number of publishers may feed it. Subscribers compete for events, so
+/// give each stream a single subscriber unless work-sharing is intended.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: Sender<MarketEvent>,
+    receiver: Receiver<MarketEvent>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    counters: Arc<BusCounters>,
+}
+
+impl EventBus {
+    /// Bus whose publishers block while it is full
+    pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, OverflowPolicy::Block)
+    }
+
+    pub fn with_policy(capacity: usize, policy: OverflowPolicy) -> Self {
+        let (sender, receiver) = channel::bounded(capacity);
+        Self {
+            sender,
+            receiver,
+            capacity,
+            policy,
+            counters: Arc::new(BusCounters::default()),
+        }
+    }
+
+    pub fn publisher(&self) -> EventPublisher {
+        EventPublisher {
+            sender: self.sender.clone(),
+            receiver: (self.policy == OverflowPolicy::DropOldest).then(|| self.receiver.clone()),
+            policy: self.policy,
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    pub fn subscriber(&self) -> EventSubscriber {
+        EventSubscriber {
+            receiver: self.receiver.clone(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Events waiting to be consumed
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Move up to `max` queued events into `buf`, oldest first, without
+    /// blocking. Returns how many were moved.
+    pub fn drain_into(&self, buf: &mut Vec<MarketEvent>, max: usize) -> usize {
+        drain_receiver(&self.receiver, buf, max)
+    }
+
+    /// Events accepted since the bus was created
+    pub fn published(&self) -> u64 {
+        self.counters.published.load(Ordering::Relaxed)
+    }
+
+    /// Events turned away by `try_publish` because the bus was full
+    pub fn rejected(&self) -> u64 {
+        self.counters.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Events discarded by the overflow policy
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUS_CAPACITY)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventPublisher {
+    sender: Sender<MarketEvent>,
+    // Only under `DropOldest`, to evict the oldest event
+    receiver: Option<Receiver<MarketEvent>>,
+    policy: OverflowPolicy,
+    counters: Arc<BusCounters>,
+}
+
+impl EventPublisher {
+    /// Publish without blocking; fails with `Full` instead of waiting for space
+    pub fn try_publish(&self, event: MarketEvent) -> Result<(), BusError> {
+        match self.sender.try_send(event) {
+            Ok(()) => {
+                self.counters.published.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                self.counters.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(BusError::Full)
+            }
+            Err(TrySendError::Disconnected(_)) => Err(BusError::Disconnected),
+        }
+    }
+
+    /// Publish, applying the bus's overflow policy if it is full. Events
+    /// discarded by a drop policy still return `Ok` and are counted in
+    /// `EventBus::dropped`.
+    pub fn publish(&self, event: MarketEvent) -> Result<(), BusError> {
+        match self.policy {
+            OverflowPolicy::Block => self
+                .sender
+                .send(event)
+                .map_err(|_| BusError::Disconnected)?,
+            OverflowPolicy::DropNewest => match self.sender.try_send(event) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    self.counters.record_drop(self.policy);
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(BusError::Disconnected),
+            },
+            OverflowPolicy::DropOldest => {
+                let mut event = event;
+                loop {
+                    match self.sender.try_send(event) {
+                        Ok(()) => break,
+                        Err(TrySendError::Full(rejected)) => {
+                            // A consumer may empty the slot first; then nothing is lost
+                            if let Some(receiver) = &self.receiver
+                                && receiver.try_recv().is_ok()
+                            {
+                                self.counters.record_drop(self.policy);
+                            }
+                            event = rejected;
+                        }
+                        Err(TrySendError::Disconnected(_)) => return Err(BusError::Disconnected),
+                    }
+                }
+            }
+        }
+        self.counters.published.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventSubscriber {
+    receiver: Receiver<MarketEvent>,
+}
+
+impl EventSubscriber {
+    /// Next event if one is ready
+    pub fn try_recv(&self) -> Result<Option<MarketEvent>, BusError> {
+        match self.receiver.try_recv() {
+            Ok(event) => Ok(Some(event)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(BusError::Disconnected),
+        }
+    }
+
+    /// Block until an event arrives
+    pub fn recv(&self) -> Result<MarketEvent, BusError> {
+        self.receiver.recv().map_err(|_| BusError::Disconnected)
+    }
+
+    /// Batched [`try_recv`](Self::try_recv): move up to `max` ready events
+    /// into `buf` and return how many were moved
+    pub fn drain_into(&self, buf: &mut Vec<MarketEvent>, max: usize) -> usize {
+        drain_receiver(&self.receiver, buf, max)
+    }
+
+    /// Block for at most `timeout`; `Ok(None)` if nothing arrived in time
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<MarketEvent>, BusError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(BusError::Disconnected),
+        }
+    }
+}
+
+fn drain_receiver(
+    receiver: &Receiver<MarketEvent>,
+    buf: &mut Vec<MarketEvent>,
+    max: usize,
+) -> usize {
+    let before = buf.len();
+    buf.extend(receiver.try_iter().take(max));
+    buf.len() - before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(ns: u64) -> MarketEvent {
+        MarketEvent::Heartbeat {
+            timestamp_ns: ns,
+            normalized_ns: ns,
+        }
+    }
+
+    fn queued(bus: &EventBus) -> Vec<u64> {
+        let mut events = Vec::new();
+        bus.drain_into(&mut events, usize::MAX);
+        events.iter().map(MarketEvent::timestamp_ns).collect()
+    }
+
+    #[test]
+    fn batches_of_32_drain_every_event_in_order() {
+        let bus = EventBus::new(128);
+        let publisher = bus.publisher();
+        for ns in 0..100 {
+            publisher.publish(heartbeat(ns)).unwrap();
+        }
+
+        let mut buf = Vec::new();
+        let mut batches = Vec::new();
+        loop {
+            let moved = bus.drain_into(&mut buf, 32);
+            if moved == 0 {
+                break;
+            }
+            batches.push(moved);
+        }
+        assert_eq!(batches, [32, 32, 32, 4]);
+        let order: Vec<u64> = buf.iter().map(MarketEvent::timestamp_ns).collect();
+        assert_eq!(order, (0..100).collect::<Vec<_>>());
+
+        // Subscribers drain the same way, appending to what is already there
+        publisher.publish(heartbeat(100)).unwrap();
+        assert_eq!(bus.subscriber().drain_into(&mut buf, 32), 1);
+        assert_eq!(buf.len(), 101);
+        assert_eq!(bus.drain_into(&mut buf, 0), 0);
+    }
+
+    #[test]
+    fn block_waits_for_room() {
+        let bus = EventBus::with_policy(1, OverflowPolicy::Block);
+        let publisher = bus.publisher();
+        publisher.publish(heartbeat(1)).unwrap();
+
+        let blocked = std::thread::spawn(move || publisher.publish(heartbeat(2)));
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!blocked.is_finished(), "publish returned on a full bus");
+
+        let subscriber = bus.subscriber();
+        assert_eq!(subscriber.recv().unwrap().timestamp_ns(), 1);
+        blocked.join().unwrap().unwrap();
+        assert_eq!(queued(&bus), [2]);
+        assert_eq!((bus.published(), bus.dropped()), (2, 0));
+    }
+
+    #[test]
+    fn drop_oldest_evicts_queued_events() {
+        let bus = EventBus::with_policy(2, OverflowPolicy::DropOldest);
+        let publisher = bus.publisher();
+        for ns in 1..=5 {
+            publisher.publish(heartbeat(ns)).unwrap();
+        }
+
+        assert_eq!(queued(&bus), [4, 5]);
+        assert_eq!((bus.published(), bus.dropped()), (5, 3));
+    }
+
+    #[test]
+    fn drop_newest_discards_the_published_event() {
+        let bus = EventBus::with_policy(2, OverflowPolicy::DropNewest);
+        let publisher = bus.publisher();
+        for ns in 1..=5 {
+            publisher.publish(heartbeat(ns)).unwrap();
+        }
+
+        assert_eq!(queued(&bus), [1, 2]);
+        assert_eq!((bus.published(), bus.dropped()), (2, 3));
+    }
+
+    #[test]
+    fn try_publish_rejects_without_dropping() {
+        let bus = EventBus::with_policy(1, OverflowPolicy::DropOldest);
+        let publisher = bus.publisher();
+        publisher.try_publish(heartbeat(1)).unwrap();
+
+        assert_eq!(publisher.try_publish(heartbeat(2)), Err(BusError::Full));
+        assert_eq!(queued(&bus), [1]);
+        assert_eq!((bus.rejected(), bus.dropped()), (1, 0));
+    }
+
+    #[test]
+    fn producer_and_consumer_threads_keep_event_order() {
+        const EVENTS: u64 = 100_000;
+        let bus = EventBus::new(1024);
+        let publisher = bus.publisher();
+        let subscriber = bus.subscriber();
+
+        let producer = std::thread::spawn(move || {
+            for ns in 1..=EVENTS {
+                publisher.publish(heartbeat(ns)).unwrap();
+            }
+        });
+        let consumer = std::thread::spawn(move || {
+            let mut last = 0;
+            for _ in 0..EVENTS {
+                let ns = subscriber.recv().unwrap().timestamp_ns();
+                assert_eq!(ns, last + 1, "event out of order");
+                last = ns;
+            }
+            last
+        });
+
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), EVENTS);
+        assert!(bus.is_empty());
+        assert_eq!((bus.published(), bus.dropped()), (EVENTS, 0));
+    }
+}
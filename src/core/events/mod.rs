@@ -0,0 +1,109 @@
+// Market events for ShrivenQ
+// Shared representation of market data across backtest, simulation, paper and live
+
+pub mod bus;
+
+pub use bus::{BusError, EventBus, EventPublisher, EventSubscriber, OverflowPolicy};
+
+use crate::core::orders::{Fill, OrderReject};
+use crate::core::types::{Px, Qty, SymbolId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// A single market data event. Prices and sizes are fixed-point integers;
+/// timestamps are nanoseconds since the epoch.
+///
+/// `timestamp_ns` is the venue's own clock. `normalized_ns` is the same
+/// instant on the session's [`TimeSource`](crate::core::time::TimeSource),
+/// comparable across feeds; it equals `timestamp_ns` until a feed clock
+/// stamps the event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketEvent {
+    Trade {
+        symbol: SymbolId,
+        timestamp_ns: u64,
+        normalized_ns: u64,
+        price: Px,
+        size: Qty,
+    },
+    Quote {
+        symbol: SymbolId,
+        timestamp_ns: u64,
+        normalized_ns: u64,
+        bid_price: Px,
+        bid_size: Qty,
+        ask_price: Px,
+        ask_size: Qty,
+    },
+    /// New aggregate size at one price level; a size of zero removes the level
+    BookUpdate {
+        symbol: SymbolId,
+        timestamp_ns: u64,
+        normalized_ns: u64,
+        side: Side,
+        price: Px,
+        size: Qty,
+    },
+    /// Liveness signal from a feed with no market data to report
+    Heartbeat {
+        timestamp_ns: u64,
+        normalized_ns: u64,
+    },
+    /// Execution of one of our own orders
+    Fill(Fill),
+    /// One of our own orders refused after it was sent
+    Reject(OrderReject),
+}
+
+impl MarketEvent {
+    /// Instrument the event refers to; heartbeats carry none
+    pub fn symbol(&self) -> Option<SymbolId> {
+        match self {
+            MarketEvent::Trade { symbol, .. }
+            | MarketEvent::Quote { symbol, .. }
+            | MarketEvent::BookUpdate { symbol, .. } => Some(*symbol),
+            MarketEvent::Fill(fill) => Some(fill.symbol),
+            MarketEvent::Reject(reject) => Some(reject.symbol),
+            MarketEvent::Heartbeat { .. } => None,
+        }
+    }
+
+    pub fn timestamp_ns(&self) -> u64 {
+        match self {
+            MarketEvent::Trade { timestamp_ns, .. }
+            | MarketEvent::Quote { timestamp_ns, .. }
+            | MarketEvent::BookUpdate { timestamp_ns, .. }
+            | MarketEvent::Heartbeat { timestamp_ns, .. } => *timestamp_ns,
+            MarketEvent::Fill(fill) => fill.timestamp_ns,
+            MarketEvent::Reject(reject) => reject.timestamp_ns,
+        }
+    }
+
+    /// Event time on the session clock. Fills and rejects are stamped
+    /// locally, so their own timestamp already is.
+    pub fn normalized_ns(&self) -> u64 {
+        match self {
+            MarketEvent::Trade { normalized_ns, .. }
+            | MarketEvent::Quote { normalized_ns, .. }
+            | MarketEvent::BookUpdate { normalized_ns, .. }
+            | MarketEvent::Heartbeat { normalized_ns, .. } => *normalized_ns,
+            MarketEvent::Fill(fill) => fill.timestamp_ns,
+            MarketEvent::Reject(reject) => reject.timestamp_ns,
+        }
+    }
+
+    /// Replace the normalized timestamp; a no-op for fills and rejects
+    pub fn set_normalized_ns(&mut self, ns: u64) {
+        match self {
+            MarketEvent::Trade { normalized_ns, .. }
+            | MarketEvent::Quote { normalized_ns, .. }
+            | MarketEvent::BookUpdate { normalized_ns, .. }
+            | MarketEvent::Heartbeat { normalized_ns, .. } => *normalized_ns = ns,
+            MarketEvent::Fill(_) | MarketEvent::Reject(_) => {}
+        }
+    }
+}
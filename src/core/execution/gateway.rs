@@ -0,0 +1,525 @@
+// Order entry gateways for ShrivenQ
+// Venue-agnostic submit/cancel, always behind the pre-trade risk gate
+
+use crate::core::book::OrderBook;
+use crate::core::events::MarketEvent;
+use crate::core::execution::sim_matching::{SimMatchingEngine, SimReject};
+use crate::core::feeds::now_ns;
+use crate::core::journal::{JournalError, JournalRecord, SharedJournal};
+use crate::core::orders::{Fill, Order, OrderId};
+use crate::core::portfolio::Portfolio;
+use crate::core::risk::{RiskEngine, RiskReject, WorkingOrders};
+use crate::core::types::{Qty, SymbolId};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GatewayError {
+    #[error("Risk check failed: {0}")]
+    Risk(#[from] RiskReject),
+    #[error("Execution rejected: {0}")]
+    Execution(#[from] SimReject),
+    #[error("Order {0} is not open")]
+    UnknownOrder(OrderId),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+}
+
+/// Venue acknowledgement of a submitted order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderAck {
+    pub order_id: OrderId,
+    /// Fills produced immediately on arrival; later fills arrive as events
+    pub fills: Vec<Fill>,
+}
+
+impl OrderAck {
+    pub fn filled_qty(&self) -> Qty {
+        self.fills.iter().fold(Qty::ZERO, |acc, fill| {
+            acc.checked_add(fill.qty).unwrap_or(acc)
+        })
+    }
+}
+
+/// Somewhere orders can be sent
+pub trait OrderGateway: Send + Sync {
+    /// Venue name for logs
+    fn name(&self) -> &'static str;
+
+    fn submit(&self, order: Order) -> impl Future<Output = Result<OrderAck, GatewayError>> + Send;
+
+    fn cancel(&self, order_id: OrderId) -> impl Future<Output = Result<(), GatewayError>> + Send;
+
+    /// Feed market data to the venue model; returns fills it triggered.
+    /// Real venues report fills themselves, so the default does nothing.
+    fn on_market_event(&self, _event: &MarketEvent) -> Vec<Fill> {
+        Vec::new()
+    }
+
+    /// Whether the venue may still fill `order_id`. Venues that report every
+    /// fill and cancel keep the default; models that drop unfilled quantity
+    /// on their own say so here.
+    fn is_open(&self, _order_id: OrderId) -> bool {
+        true
+    }
+}
+
+/// Pre-trade risk check in front of a gateway.
+///
+/// Gateways are only constructed wrapped in this gate, so no order reaches a
+/// venue unchecked. It keeps the portfolio the checks run against, applying
+/// fills from acks and market events and marking positions to market data,
+/// and the orders still working at the venue, which count towards limits.
+///
+/// An order is reserved as working in the same step that checks it, before
+/// it is sent, so concurrent submits cannot each pass against a position
+/// that excludes the other. The reservation is released if the order is
+/// rejected downstream.
+///
+/// With a journal attached, every order is journalled before it is sent and
+/// every ack, cancel and fill as it happens; an order that cannot be
+/// journalled is not sent.
+#[derive(Debug)]
+pub struct RiskGate<G> {
+    risk: RiskEngine,
+    state: Mutex<GateState>,
+    journal: Option<SharedJournal>,
+    inner: G,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    portfolio: Portfolio,
+    working: WorkingOrders,
+}
+
+impl<G: OrderGateway> RiskGate<G> {
+    fn new(risk: RiskEngine, inner: G) -> Self {
+        Self {
+            risk,
+            state: Mutex::new(GateState::default()),
+            journal: None,
+            inner,
+        }
+    }
+
+    pub fn with_journal(mut self, journal: SharedJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Start from `portfolio`, e.g. one recovered from the journal
+    pub fn with_portfolio(self, portfolio: Portfolio) -> Self {
+        self.state.lock().portfolio = portfolio;
+        self
+    }
+
+    pub fn gateway(&self) -> &G {
+        &self.inner
+    }
+
+    pub fn risk(&self) -> &RiskEngine {
+        &self.risk
+    }
+
+    pub fn portfolio(&self) -> Portfolio {
+        self.state.lock().portfolio.clone()
+    }
+
+    /// Orders sent and not yet filled or cancelled
+    pub fn working_orders(&self) -> WorkingOrders {
+        self.state.lock().working.clone()
+    }
+
+    fn apply(&self, fills: &[Fill]) {
+        let mut state = self.state.lock();
+        for fill in fills {
+            state.portfolio.apply_fill(fill);
+            state.working.fill(fill);
+        }
+        drop(state);
+        for fill in fills {
+            self.journal_or_warn(&JournalRecord::Fill(fill.clone()));
+        }
+    }
+
+    fn journal(&self, record: &JournalRecord) -> Result<(), JournalError> {
+        match &self.journal {
+            Some(journal) => journal.append(record),
+            None => Ok(()),
+        }
+    }
+
+    // For actions the venue has already taken, which cannot be undone
+    fn journal_or_warn(&self, record: &JournalRecord) {
+        if let Err(e) = self.journal(record) {
+            warn!("[{}] {}", self.inner.name(), e);
+        }
+    }
+}
+
+impl<G: OrderGateway> OrderGateway for RiskGate<G> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn submit(&self, order: Order) -> Result<OrderAck, GatewayError> {
+        let checked = {
+            let mut state = self.state.lock();
+            let GateState { portfolio, working } = &mut *state;
+            working.retain(|order_id| self.inner.is_open(order_id));
+            let checked = self.risk.check(&order, portfolio, working);
+            if checked.is_ok() {
+                working.insert(&order);
+            }
+            checked
+        };
+        if let Err(reject) = checked {
+            warn!("[{}] {}", self.inner.name(), reject);
+            return Err(reject.into());
+        }
+
+        let order_id = order.id;
+        let sent = match self.journal(&JournalRecord::Submit(order.clone())) {
+            Ok(()) => self.inner.submit(order).await,
+            Err(e) => Err(e.into()),
+        };
+        let ack = sent.inspect_err(|_| {
+            self.state.lock().working.remove(order_id);
+        })?;
+        self.journal_or_warn(&JournalRecord::Ack {
+            order_id: ack.order_id,
+            timestamp_ns: now_ns(),
+        });
+        self.apply(&ack.fills);
+        Ok(ack)
+    }
+
+    async fn cancel(&self, order_id: OrderId) -> Result<(), GatewayError> {
+        self.inner.cancel(order_id).await?;
+        self.state.lock().working.remove(order_id);
+        self.journal_or_warn(&JournalRecord::Cancel {
+            order_id,
+            timestamp_ns: now_ns(),
+        });
+        Ok(())
+    }
+
+    /// Our own fill events are skipped: they were applied when produced.
+    /// Rejects release the order's reservation.
+    fn on_market_event(&self, event: &MarketEvent) -> Vec<Fill> {
+        match event {
+            MarketEvent::Fill(_) => return Vec::new(),
+            MarketEvent::Reject(reject) => {
+                self.state.lock().working.remove(reject.order_id);
+                return Vec::new();
+            }
+            _ => {}
+        }
+        self.state.lock().portfolio.on_market_event(event);
+        let fills = self.inner.on_market_event(event);
+        self.apply(&fills);
+        fills
+    }
+}
+
+#[derive(Debug)]
+struct SimState {
+    matcher: SimMatchingEngine,
+    books: HashMap<SymbolId, OrderBook>,
+}
+
+/// Fills orders with the simulated matcher against books it maintains from
+/// `BookUpdate` events. Fills are also published on the event bus.
+#[derive(Debug)]
+pub struct SimGateway {
+    state: Mutex<SimState>,
+}
+
+impl SimGateway {
+    pub fn new(matcher: SimMatchingEngine, risk: RiskEngine) -> RiskGate<Self> {
+        RiskGate::new(
+            risk,
+            Self {
+                state: Mutex::new(SimState {
+                    matcher,
+                    books: HashMap::new(),
+                }),
+            },
+        )
+    }
+
+    /// Orders resting on the simulated books or still in flight
+    pub fn open_orders(&self) -> usize {
+        self.state.lock().matcher.open_orders()
+    }
+}
+
+impl OrderGateway for SimGateway {
+    fn name(&self) -> &'static str {
+        "Sim"
+    }
+
+    async fn submit(&self, order: Order) -> Result<OrderAck, GatewayError> {
+        let order_id = order.id;
+        let mut state = self.state.lock();
+        let SimState { matcher, books } = &mut *state;
+        let book = books
+            .entry(order.symbol)
+            .or_insert_with(|| OrderBook::new(order.symbol));
+        let fills = matcher.submit(order, book)?;
+        Ok(OrderAck { order_id, fills })
+    }
+
+    async fn cancel(&self, order_id: OrderId) -> Result<(), GatewayError> {
+        if self.state.lock().matcher.cancel(order_id) {
+            Ok(())
+        } else {
+            Err(GatewayError::UnknownOrder(order_id))
+        }
+    }
+
+    fn on_market_event(&self, event: &MarketEvent) -> Vec<Fill> {
+        let own = matches!(event, MarketEvent::Fill(_) | MarketEvent::Reject(_));
+        let (Some(symbol), false) = (event.symbol(), own) else {
+            return Vec::new();
+        };
+        let mut state = self.state.lock();
+        let SimState { matcher, books } = &mut *state;
+        let book = books
+            .entry(symbol)
+            .or_insert_with(|| OrderBook::new(symbol));
+        if let MarketEvent::BookUpdate { .. } = event
+            && let Err(e) = book.apply(event)
+        {
+            warn!("Sim gateway dropped book update: {}", e);
+        }
+        matcher.on_market_event(event, book)
+    }
+
+    fn is_open(&self, order_id: OrderId) -> bool {
+        self.state.lock().matcher.is_working(order_id)
+    }
+}
+
+/// Acknowledges every order without executing it; orders stay open until
+/// cancelled. For paper trading where only the decision flow matters.
+#[derive(Debug, Default)]
+pub struct PaperGateway {
+    open: Mutex<HashSet<OrderId>>,
+}
+
+impl PaperGateway {
+    pub fn new(risk: RiskEngine) -> RiskGate<Self> {
+        RiskGate::new(risk, Self::default())
+    }
+
+    pub fn open_orders(&self) -> usize {
+        self.open.lock().len()
+    }
+}
+
+impl OrderGateway for PaperGateway {
+    fn name(&self) -> &'static str {
+        "Paper"
+    }
+
+    async fn submit(&self, order: Order) -> Result<OrderAck, GatewayError> {
+        if order.qty <= Qty::ZERO {
+            return Err(SimReject::InvalidQuantity(order.id).into());
+        }
+        self.open.lock().insert(order.id);
+        Ok(OrderAck {
+            order_id: order.id,
+            fills: Vec::new(),
+        })
+    }
+
+    async fn cancel(&self, order_id: OrderId) -> Result<(), GatewayError> {
+        if self.open.lock().remove(&order_id) {
+            Ok(())
+        } else {
+            Err(GatewayError::UnknownOrder(order_id))
+        }
+    }
+
+    fn is_open(&self, order_id: OrderId) -> bool {
+        self.open.lock().contains(&order_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::{EventBus, Side};
+    use crate::core::execution::rng::RngService;
+    use crate::core::execution::sim_matching::FillModel;
+    use crate::core::orders::{OrderKind, OrderSide};
+    use crate::core::risk::RiskLimits;
+    use crate::core::types::Px;
+    use tokio::sync::Notify;
+
+    fn symbol() -> SymbolId {
+        SymbolId::intern("GATETEST").unwrap()
+    }
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    fn buy(id: OrderId, lots: i64, limit: i64) -> Order {
+        Order {
+            id,
+            symbol: symbol(),
+            side: OrderSide::Buy,
+            kind: OrderKind::Limit(px(limit)),
+            qty: qty(lots),
+            timestamp_ns: id,
+        }
+    }
+
+    fn limits() -> RiskEngine {
+        RiskEngine::new(RiskLimits {
+            max_position: Some(qty(10)),
+            max_order_size: Some(qty(8)),
+        })
+    }
+
+    fn sim_gateway(bus: &EventBus) -> RiskGate<SimGateway> {
+        let matcher = SimMatchingEngine::new(
+            FillModel::ImmediateAtTouch,
+            bus.publisher(),
+            RngService::new(1).stream("gateway_test"),
+        );
+        let gate = SimGateway::new(matcher, limits());
+        gate.on_market_event(&MarketEvent::BookUpdate {
+            symbol: symbol(),
+            timestamp_ns: 0,
+            normalized_ns: 0,
+            side: Side::Ask,
+            price: px(100),
+            size: qty(3),
+        });
+        gate
+    }
+
+    #[tokio::test]
+    async fn sim_fills_marketable_orders_and_rests_the_rest() {
+        let bus = EventBus::new(16);
+        let gate = sim_gateway(&bus);
+
+        let ack = gate.submit(buy(1, 5, 100)).await.unwrap();
+        assert_eq!((ack.order_id, ack.filled_qty()), (1, qty(3)));
+        assert_eq!(gate.portfolio().net_qty(symbol()), qty(3));
+        assert_eq!(gate.gateway().open_orders(), 1);
+        assert_eq!(
+            gate.working_orders().pending(symbol(), OrderSide::Buy),
+            qty(2)
+        );
+
+        gate.cancel(1).await.unwrap();
+        assert_eq!(gate.gateway().open_orders(), 0);
+        assert!(gate.working_orders().is_empty());
+        assert_eq!(gate.cancel(1).await, Err(GatewayError::UnknownOrder(1)));
+    }
+
+    #[tokio::test]
+    async fn paper_acks_without_filling() {
+        let gate = PaperGateway::new(limits());
+
+        let ack = gate.submit(buy(1, 5, 100)).await.unwrap();
+        assert_eq!((ack.order_id, ack.filled_qty()), (1, Qty::ZERO));
+        assert_eq!(gate.gateway().open_orders(), 1);
+        assert_eq!(gate.portfolio().net_qty(symbol()), Qty::ZERO);
+
+        gate.cancel(1).await.unwrap();
+        assert_eq!(gate.gateway().open_orders(), 0);
+        assert_eq!(gate.cancel(1).await, Err(GatewayError::UnknownOrder(1)));
+    }
+
+    #[tokio::test]
+    async fn both_gateways_reject_past_the_limits() {
+        let bus = EventBus::new(16);
+        let sim = sim_gateway(&bus);
+        let paper = PaperGateway::new(limits());
+
+        for result in [
+            sim.submit(buy(1, 9, 100)).await,
+            paper.submit(buy(1, 9, 100)).await,
+        ] {
+            assert!(matches!(
+                result,
+                Err(GatewayError::Risk(RiskReject::OrderSizeLimit { .. }))
+            ));
+        }
+        assert_eq!(sim.gateway().open_orders(), 0);
+        assert_eq!(paper.gateway().open_orders(), 0);
+
+        // Working orders count: 6 resting leaves room for 4 more, not 5
+        paper.submit(buy(2, 6, 100)).await.unwrap();
+        assert!(matches!(
+            paper.submit(buy(3, 5, 100)).await,
+            Err(GatewayError::Risk(RiskReject::PositionLimit { .. }))
+        ));
+        paper.cancel(2).await.unwrap();
+        paper.submit(buy(3, 5, 100)).await.unwrap();
+    }
+
+    // Holds every order until released, like a venue slow to respond
+    #[derive(Debug, Default)]
+    struct SlowGateway {
+        release: Notify,
+    }
+
+    impl OrderGateway for SlowGateway {
+        fn name(&self) -> &'static str {
+            "Slow"
+        }
+
+        async fn submit(&self, order: Order) -> Result<OrderAck, GatewayError> {
+            self.release.notified().await;
+            Ok(OrderAck {
+                order_id: order.id,
+                fills: Vec::new(),
+            })
+        }
+
+        async fn cancel(&self, _order_id: OrderId) -> Result<(), GatewayError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_submits_cannot_both_use_the_same_headroom() {
+        let gate = RiskGate::new(limits(), SlowGateway::default());
+
+        // The first order is still at the venue when the second is checked
+        let (first, second) = tokio::join!(gate.submit(buy(1, 6, 100)), async {
+            let second = gate.submit(buy(2, 6, 100)).await;
+            gate.gateway().release.notify_one();
+            second
+        });
+        assert!(first.is_ok());
+        assert!(matches!(
+            second,
+            Err(GatewayError::Risk(RiskReject::PositionLimit { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn venue_rejects_release_the_reservation() {
+        let gate = PaperGateway::new(limits());
+        assert!(matches!(
+            gate.submit(buy(1, 0, 100)).await,
+            Err(GatewayError::Execution(_))
+        ));
+        assert!(gate.working_orders().is_empty());
+    }
+}
@@ -1,13 +1,18 @@
 // Execution framework for ShrivenQ
 // Handles different execution modes: Backtest, Paper, Live
 
+pub mod gateway;
 pub mod mode_switcher;
+pub mod rng;
+pub mod router;
+pub mod sim_matching;
 
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ExecutionMode {
     Backtest,
+    Simulation,
     Paper,
     Live,
 }
@@ -16,6 +21,7 @@ impl fmt::Display for ExecutionMode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ExecutionMode::Backtest => write!(f, "BACKTEST"),
+            ExecutionMode::Simulation => write!(f, "SIMULATION"),
             ExecutionMode::Paper => write!(f, "PAPER"),
             ExecutionMode::Live => write!(f, "LIVE"),
         }
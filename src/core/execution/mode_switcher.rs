@@ -2,6 +2,7 @@
 // Critical for development to production workflow
 
 use super::ExecutionMode;
+use crate::core::diagnostics::{FlightEvent, FlightRecorder};
 use anyhow::Result;
 
 #[derive(Debug, Copy, Clone)]
@@ -16,6 +17,10 @@ impl ModeSwitcher {
 
     pub fn switch_mode(&mut self, new_mode: ExecutionMode) -> Result<()> {
         tracing::info!("Switching from {} to {}", self.current_mode, new_mode);
+        FlightRecorder::global().record(FlightEvent::ModeSwitch {
+            from: self.current_mode,
+            to: new_mode,
+        });
 
         // TODO: Implement proper mode transition logic
         // - Save current state
@@ -0,0 +1,45 @@
+// Deterministic randomness for simulation
+// One session seed, split into independent per-component streams
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Generator handed to simulation components
+pub type SimRng = ChaCha8Rng;
+
+/// Source of every random draw a simulated session makes. Components take
+/// their own stream keyed by a stable label, so a seed reproduces the session
+/// no matter in which order components are created or how often each draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RngService {
+    seed: u64,
+}
+
+impl RngService {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Pick a fresh seed; log [`RngService::seed`] so the session can be replayed
+    pub fn from_entropy() -> Self {
+        Self::new(rand::thread_rng().r#gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Independent generator for the component named `label`
+    pub fn stream(&self, label: &str) -> SimRng {
+        let mut rng = SimRng::seed_from_u64(self.seed);
+        rng.set_stream(stream_id(label));
+        rng
+    }
+}
+
+// FNV-1a, stable across builds and platforms unlike the std hashers
+fn stream_id(label: &str) -> u64 {
+    label.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,103 @@
+// Order routing for ShrivenQ
+// Single path from strategy orders to fills: risk check, execution, position update
+
+use crate::core::book::OrderBook;
+use crate::core::events::MarketEvent;
+use crate::core::execution::ExecutionMode;
+use crate::core::execution::sim_matching::{SimMatchingEngine, SimReject};
+use crate::core::orders::{Fill, Order, OrderId};
+use crate::core::portfolio::Portfolio;
+use crate::core::risk::{RiskEngine, RiskReject, WorkingOrders};
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    #[error("Risk check failed: {0}")]
+    Risk(#[from] RiskReject),
+    #[error("Execution rejected: {0}")]
+    Execution(#[from] SimReject),
+}
+
+/// Routes orders through the risk engine before they can reach a venue.
+///
+/// Fills are produced by the simulated matcher in every mode until exchange
+/// gateways exist; the risk gate applies regardless of where fills come from.
+#[derive(Debug)]
+pub struct OrderRouter {
+    mode: ExecutionMode,
+    risk: RiskEngine,
+    portfolio: Portfolio,
+    working: WorkingOrders,
+    matcher: SimMatchingEngine,
+}
+
+impl OrderRouter {
+    pub fn new(mode: ExecutionMode, risk: RiskEngine, matcher: SimMatchingEngine) -> Self {
+        Self {
+            mode,
+            risk,
+            portfolio: Portfolio::new(),
+            working: WorkingOrders::new(),
+            matcher,
+        }
+    }
+
+    pub fn mode(&self) -> ExecutionMode {
+        self.mode
+    }
+
+    /// Risk-check `order` against the position and the orders still
+    /// working, then execute it against `book`
+    pub fn route(&mut self, order: Order, book: &OrderBook) -> Result<Vec<Fill>, RouteError> {
+        let matcher = &self.matcher;
+        self.working.retain(|order_id| matcher.is_working(order_id));
+        if let Err(reject) = self.risk.check(&order, &self.portfolio, &self.working) {
+            warn!("[{}] {}", self.mode, reject);
+            return Err(reject.into());
+        }
+
+        let fills = self.matcher.submit(order.clone(), book)?;
+        self.working.insert(&order);
+        self.apply(&fills);
+        Ok(fills)
+    }
+
+    /// Mark positions and let working orders fill against `event`. Fill
+    /// events are ignored: the router already applied its own fills. Rejects
+    /// release the order's reservation.
+    pub fn on_market_event(&mut self, event: &MarketEvent, book: &OrderBook) -> Vec<Fill> {
+        match event {
+            MarketEvent::Fill(_) => return Vec::new(),
+            MarketEvent::Reject(reject) => {
+                self.working.remove(reject.order_id);
+                return Vec::new();
+            }
+            _ => {}
+        }
+        self.portfolio.on_market_event(event);
+        let fills = self.matcher.on_market_event(event, book);
+        self.apply(&fills);
+        fills
+    }
+
+    pub fn cancel(&mut self, order_id: OrderId) -> bool {
+        self.working.remove(order_id);
+        self.matcher.cancel(order_id)
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    pub fn risk(&self) -> &RiskEngine {
+        &self.risk
+    }
+
+    fn apply(&mut self, fills: &[Fill]) {
+        for fill in fills {
+            self.portfolio.apply_fill(fill);
+            self.working.fill(fill);
+        }
+    }
+}
@@ -0,0 +1,585 @@
+// Simulated matching engine - synthetic fills for Simulation mode
+// Matches orders against the local order book without touching an exchange
+
+use crate::core::book::OrderBook;
+use crate::core::events::{EventPublisher, MarketEvent};
+use crate::core::execution::rng::SimRng;
+use crate::core::orders::{Fill, Order, OrderId, OrderKind, OrderReject, OrderSide};
+use crate::core::types::{Px, Qty, SymbolId};
+use rand::Rng;
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::warn;
+
+/// How synthetic fills are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillModel {
+    /// Orders reach the book instantly; resting limits fill as soon as the
+    /// opposite touch reaches their price
+    ImmediateAtTouch,
+    /// Orders reach the book `latency_ns` after submission; resting limits
+    /// queue behind the size already at their level and fill only once trades
+    /// at that price have consumed it
+    QueuePosition { latency_ns: u64 },
+}
+
+/// Random execution effects layered on the fill model; none by default.
+/// Draws come from the engine's [`SimRng`], so a seed replays them exactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimNoise {
+    /// Extra arrival latency, uniform in `0..=latency_jitter_ns`, on top of
+    /// the queue model's fixed latency
+    pub latency_jitter_ns: u64,
+    /// Adverse slippage per aggressive fill, uniform in `0..=max_slippage`,
+    /// never past a limit order's price
+    pub max_slippage: Px,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SimReject {
+    #[error("Order {0} has a non-positive quantity")]
+    InvalidQuantity(OrderId),
+    #[error("Order for {order} sent to the {book} book")]
+    SymbolMismatch { order: SymbolId, book: SymbolId },
+    #[error("No liquidity available for market order {0}")]
+    NoLiquidity(OrderId),
+}
+
+#[derive(Debug, Clone)]
+struct WorkingOrder {
+    order: Order,
+    remaining: Qty,
+    // Still in flight under the latency model until this time
+    active_at_ns: u64,
+    resting: bool,
+    // Size ahead of us at our price level (queue model only)
+    queue_ahead: Qty,
+}
+
+/// Synthetic fill engine. The book is not depleted by simulated fills, but
+/// liquidity consumed while walking levels for one order is accounted for.
+#[derive(Debug)]
+pub struct SimMatchingEngine {
+    model: FillModel,
+    noise: SimNoise,
+    rng: SimRng,
+    publisher: EventPublisher,
+    positions: HashMap<SymbolId, Qty>,
+    working: Vec<WorkingOrder>,
+}
+
+impl SimMatchingEngine {
+    pub fn new(model: FillModel, publisher: EventPublisher, rng: SimRng) -> Self {
+        Self {
+            model,
+            noise: SimNoise::default(),
+            rng,
+            publisher,
+            positions: HashMap::new(),
+            working: Vec::new(),
+        }
+    }
+
+    pub fn with_noise(mut self, noise: SimNoise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Submit an order against `book`. Returns the fills produced right away;
+    /// unfilled limit quantity rests, unfilled market quantity is cancelled.
+    pub fn submit(&mut self, order: Order, book: &OrderBook) -> Result<Vec<Fill>, SimReject> {
+        if order.qty <= Qty::ZERO {
+            return Err(SimReject::InvalidQuantity(order.id));
+        }
+        if order.symbol != book.symbol() {
+            return Err(SimReject::SymbolMismatch {
+                order: order.symbol,
+                book: book.symbol(),
+            });
+        }
+
+        match self.model {
+            FillModel::ImmediateAtTouch => {
+                let now = order.timestamp_ns;
+                let fills = self.execute(order, book, now)?;
+                self.record(&fills);
+                Ok(fills)
+            }
+            FillModel::QueuePosition { latency_ns } => {
+                let remaining = order.qty;
+                let jitter_ns = match self.noise.latency_jitter_ns {
+                    0 => 0,
+                    max => self.rng.gen_range(0..=max),
+                };
+                self.working.push(WorkingOrder {
+                    active_at_ns: order
+                        .timestamp_ns
+                        .saturating_add(latency_ns)
+                        .saturating_add(jitter_ns),
+                    order,
+                    remaining,
+                    resting: false,
+                    queue_ahead: Qty::ZERO,
+                });
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Advance simulated time with a market event for `book`'s symbol:
+    /// in-flight orders arriving by now are matched, resting orders are
+    /// checked for fills. Returns every fill produced; orders rejected on
+    /// arrival are published as `Reject` events.
+    pub fn on_market_event(&mut self, event: &MarketEvent, book: &OrderBook) -> Vec<Fill> {
+        let now = event.timestamp_ns();
+        let symbol = book.symbol();
+        let mut fills = Vec::new();
+
+        // Resting orders first, so orders arriving now do not see this event twice
+        for working in self
+            .working
+            .iter_mut()
+            .filter(|w| w.resting && w.order.symbol == symbol)
+        {
+            let OrderKind::Limit(limit) = working.order.kind else {
+                continue;
+            };
+
+            let available = match self.model {
+                FillModel::ImmediateAtTouch => book
+                    .levels(working.order.side.opposite_book_side())
+                    .next()
+                    .filter(|&(price, _)| working.order.side.crosses(price, limit))
+                    .map(|(_, size)| size),
+                FillModel::QueuePosition { .. } => match event {
+                    MarketEvent::Trade { price, size, .. } if *price == limit => {
+                        // Trades at our level eat the queue ahead of us first
+                        let through = size.checked_sub(working.queue_ahead);
+                        working.queue_ahead = working
+                            .queue_ahead
+                            .checked_sub(*size)
+                            .unwrap_or(Qty::ZERO)
+                            .max(Qty::ZERO);
+                        through.filter(|qty| *qty > Qty::ZERO)
+                    }
+                    MarketEvent::Trade { price, size, .. }
+                        if working.order.side.crosses(*price, limit) =>
+                    {
+                        // Traded through our price: the whole level was swept
+                        working.queue_ahead = Qty::ZERO;
+                        Some(*size)
+                    }
+                    _ => None,
+                },
+            };
+
+            let Some(available) = available else {
+                continue;
+            };
+            let qty = working.remaining.min(available);
+            working.remaining = working.remaining.checked_sub(qty).unwrap_or(Qty::ZERO);
+            fills.push(Fill {
+                order_id: working.order.id,
+                symbol: working.order.symbol,
+                side: working.order.side,
+                price: limit,
+                qty,
+                timestamp_ns: now,
+            });
+        }
+        self.working.retain(|w| w.remaining > Qty::ZERO);
+
+        // Orders whose latency has elapsed reach the book
+        let mut index = 0;
+        while index < self.working.len() {
+            let working = &self.working[index];
+            if working.resting || working.active_at_ns > now || working.order.symbol != symbol {
+                index += 1;
+                continue;
+            }
+
+            let working = self.working.remove(index);
+            let (order_id, symbol) = (working.order.id, working.order.symbol);
+            match self.execute(working.order, book, now) {
+                Ok(mut arrived) => fills.append(&mut arrived),
+                Err(reject) => self.reject(order_id, symbol, &reject, now),
+            }
+        }
+
+        self.record(&fills);
+        fills
+    }
+
+    /// Cancel a working order; false if it is unknown or already complete
+    pub fn cancel(&mut self, order_id: OrderId) -> bool {
+        let before = self.working.len();
+        self.working.retain(|w| w.order.id != order_id);
+        self.working.len() != before
+    }
+
+    /// Whether `order_id` is resting or still in flight
+    pub fn is_working(&self, order_id: OrderId) -> bool {
+        self.working.iter().any(|w| w.order.id == order_id)
+    }
+
+    /// Simulated net position in `symbol`
+    pub fn position(&self, symbol: SymbolId) -> Qty {
+        self.positions.get(&symbol).copied().unwrap_or(Qty::ZERO)
+    }
+
+    /// Orders resting on the book or still in flight
+    pub fn open_orders(&self) -> usize {
+        self.working.len()
+    }
+
+    // Walk the opposite side of the book; rest or drop whatever is left
+    fn execute(
+        &mut self,
+        order: Order,
+        book: &OrderBook,
+        now: u64,
+    ) -> Result<Vec<Fill>, SimReject> {
+        let mut remaining = order.qty;
+        let mut fills = Vec::new();
+
+        for (price, size) in book.levels(order.side.opposite_book_side()) {
+            if remaining.is_zero() {
+                break;
+            }
+            if let OrderKind::Limit(limit) = order.kind
+                && !order.side.crosses(price, limit)
+            {
+                break;
+            }
+
+            let qty = remaining.min(size);
+            remaining = remaining.checked_sub(qty).unwrap_or(Qty::ZERO);
+            let price = self.slipped(&order, price);
+            fills.push(Fill {
+                order_id: order.id,
+                symbol: order.symbol,
+                side: order.side,
+                price,
+                qty,
+                timestamp_ns: now,
+            });
+        }
+
+        match order.kind {
+            OrderKind::Market if fills.is_empty() => Err(SimReject::NoLiquidity(order.id)),
+            OrderKind::Market => Ok(fills),
+            OrderKind::Limit(limit) => {
+                if remaining > Qty::ZERO {
+                    let queue_ahead = match self.model {
+                        FillModel::ImmediateAtTouch => Qty::ZERO,
+                        FillModel::QueuePosition { .. } => {
+                            book.size_at(order.side.book_side(), limit)
+                        }
+                    };
+                    self.working.push(WorkingOrder {
+                        order,
+                        remaining,
+                        active_at_ns: now,
+                        resting: true,
+                        queue_ahead,
+                    });
+                }
+                Ok(fills)
+            }
+        }
+    }
+
+    // Move an aggressive fill price against the order by a random amount
+    fn slipped(&mut self, order: &Order, price: Px) -> Px {
+        let max = self.noise.max_slippage.raw();
+        if max <= 0 {
+            return price;
+        }
+        let slip = self.rng.gen_range(0..=max);
+        let slipped = match order.side {
+            OrderSide::Buy => price.checked_add(Px::from_raw(slip)),
+            OrderSide::Sell => price.checked_sub(Px::from_raw(slip)),
+        }
+        .unwrap_or(price);
+        match (order.kind, order.side) {
+            (OrderKind::Limit(limit), OrderSide::Buy) => slipped.min(limit),
+            (OrderKind::Limit(limit), OrderSide::Sell) => slipped.max(limit),
+            (OrderKind::Market, _) => slipped,
+        }
+    }
+
+    // Publish a reject for an order that was already accepted
+    fn reject(&self, order_id: OrderId, symbol: SymbolId, reject: &SimReject, now: u64) {
+        let event = MarketEvent::Reject(OrderReject {
+            order_id,
+            symbol,
+            reason: reject.to_string(),
+            timestamp_ns: now,
+        });
+        if let Err(e) = self.publisher.try_publish(event) {
+            warn!("Dropped simulated reject for order {}: {}", order_id, e);
+        }
+    }
+
+    // Apply fills to the simulated position and publish them on the bus
+    fn record(&mut self, fills: &[Fill]) {
+        for fill in fills {
+            let position = self.positions.entry(fill.symbol).or_insert(Qty::ZERO);
+            let updated = match fill.side {
+                OrderSide::Buy => position.checked_add(fill.qty),
+                OrderSide::Sell => position.checked_sub(fill.qty),
+            };
+            match updated {
+                Some(updated) => *position = updated,
+                None => warn!("Simulated position overflow in {}", fill.symbol),
+            }
+
+            if let Err(e) = self.publisher.try_publish(MarketEvent::Fill(fill.clone())) {
+                warn!("Dropped simulated fill for order {}: {}", fill.order_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::{EventBus, Side};
+    use crate::core::execution::rng::RngService;
+
+    fn symbol() -> SymbolId {
+        SymbolId::intern("SIMTEST").unwrap()
+    }
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    fn order(id: OrderId, side: OrderSide, kind: OrderKind, lots: i64) -> Order {
+        Order {
+            id,
+            symbol: symbol(),
+            side,
+            kind,
+            qty: qty(lots),
+            timestamp_ns: 0,
+        }
+    }
+
+    fn level(side: Side, price: i64, size: i64, timestamp_ns: u64) -> MarketEvent {
+        MarketEvent::BookUpdate {
+            symbol: symbol(),
+            timestamp_ns,
+            normalized_ns: timestamp_ns,
+            side,
+            price: px(price),
+            size: qty(size),
+        }
+    }
+
+    fn trade(price: i64, size: i64, timestamp_ns: u64) -> MarketEvent {
+        MarketEvent::Trade {
+            symbol: symbol(),
+            timestamp_ns,
+            normalized_ns: timestamp_ns,
+            price: px(price),
+            size: qty(size),
+        }
+    }
+
+    // Bid 99 x 5; asks 100 x 3 and 101 x 4
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new(symbol());
+        for event in [
+            level(Side::Bid, 99, 5, 0),
+            level(Side::Ask, 100, 3, 0),
+            level(Side::Ask, 101, 4, 0),
+        ] {
+            book.apply(&event).unwrap();
+        }
+        book
+    }
+
+    fn engine(model: FillModel, bus: &EventBus) -> SimMatchingEngine {
+        SimMatchingEngine::new(
+            model,
+            bus.publisher(),
+            RngService::new(7).stream("sim_test"),
+        )
+    }
+
+    fn published(bus: &EventBus) -> Vec<MarketEvent> {
+        let mut events = Vec::new();
+        bus.drain_into(&mut events, usize::MAX);
+        events
+    }
+
+    #[test]
+    fn marketable_order_fills_fully_across_levels() {
+        let bus = EventBus::new(16);
+        let mut engine = engine(FillModel::ImmediateAtTouch, &bus);
+
+        let fills = engine
+            .submit(order(1, OrderSide::Buy, OrderKind::Market, 5), &book())
+            .unwrap();
+        let prices: Vec<_> = fills.iter().map(|f| (f.price, f.qty)).collect();
+        assert_eq!(prices, [(px(100), qty(3)), (px(101), qty(2))]);
+        assert_eq!(engine.position(symbol()), qty(5));
+        assert_eq!(engine.open_orders(), 0);
+
+        let events = published(&bus);
+        assert_eq!(events.len(), 2);
+        assert!(
+            events
+                .iter()
+                .all(|e| matches!(e, MarketEvent::Fill(f) if f.order_id == 1))
+        );
+    }
+
+    #[test]
+    fn limit_order_rests_until_the_touch_reaches_it() {
+        let bus = EventBus::new(16);
+        let mut engine = engine(FillModel::ImmediateAtTouch, &bus);
+        let mut book = book();
+
+        let fills = engine
+            .submit(order(1, OrderSide::Buy, OrderKind::Limit(px(98)), 2), &book)
+            .unwrap();
+        assert!(fills.is_empty());
+        assert!(engine.is_working(1));
+
+        let update = level(Side::Ask, 98, 1, 10);
+        book.apply(&update).unwrap();
+        let fills = engine.on_market_event(&update, &book);
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].price, fills[0].qty), (px(98), qty(1)));
+        assert_eq!(engine.position(symbol()), qty(1));
+        assert!(engine.is_working(1));
+
+        assert!(engine.cancel(1));
+        assert!(!engine.is_working(1));
+    }
+
+    #[test]
+    fn queued_limit_fills_once_the_size_ahead_trades() {
+        let bus = EventBus::new(16);
+        let mut engine = engine(FillModel::QueuePosition { latency_ns: 10 }, &bus);
+        let book = book();
+
+        let buy = order(1, OrderSide::Buy, OrderKind::Limit(px(99)), 2);
+        assert!(engine.submit(buy, &book).unwrap().is_empty());
+        // Still in flight, so this trade is not ours to share
+        assert!(engine.on_market_event(&trade(99, 5, 5), &book).is_empty());
+        // Arrives behind the 5 lots bid at 99
+        assert!(engine.on_market_event(&trade(105, 1, 10), &book).is_empty());
+
+        assert!(engine.on_market_event(&trade(99, 4, 20), &book).is_empty());
+        let fills = engine.on_market_event(&trade(99, 2, 30), &book);
+        assert_eq!(fills.iter().map(|f| f.qty).collect::<Vec<_>>(), [qty(1)]);
+        let fills = engine.on_market_event(&trade(98, 5, 40), &book);
+        assert_eq!((fills[0].price, fills[0].qty), (px(99), qty(1)));
+        assert!(!engine.is_working(1));
+        assert_eq!(engine.position(symbol()), qty(2));
+    }
+
+    #[test]
+    fn orders_are_rejected_without_liquidity_or_a_valid_size() {
+        let bus = EventBus::new(16);
+        let mut engine = engine(FillModel::ImmediateAtTouch, &bus);
+        let empty = OrderBook::new(symbol());
+
+        let sell = order(1, OrderSide::Sell, OrderKind::Market, 1);
+        assert_eq!(engine.submit(sell, &empty), Err(SimReject::NoLiquidity(1)));
+        let zero = order(2, OrderSide::Buy, OrderKind::Market, 0);
+        assert_eq!(
+            engine.submit(zero, &book()),
+            Err(SimReject::InvalidQuantity(2))
+        );
+        let other = OrderBook::new(SymbolId::intern("SIMOTHER").unwrap());
+        let misrouted = order(3, OrderSide::Buy, OrderKind::Market, 1);
+        assert!(matches!(
+            engine.submit(misrouted, &other),
+            Err(SimReject::SymbolMismatch { .. })
+        ));
+
+        assert_eq!(engine.open_orders(), 0);
+        assert_eq!(engine.position(symbol()), Qty::ZERO);
+        assert!(published(&bus).is_empty());
+    }
+
+    #[test]
+    fn rejects_on_arrival_are_published() {
+        let bus = EventBus::new(16);
+        let mut engine = engine(FillModel::QueuePosition { latency_ns: 10 }, &bus);
+        let empty = OrderBook::new(symbol());
+
+        let sell = order(1, OrderSide::Sell, OrderKind::Market, 1);
+        assert!(engine.submit(sell, &empty).unwrap().is_empty());
+        assert!(engine.on_market_event(&trade(99, 1, 10), &empty).is_empty());
+        assert!(!engine.is_working(1));
+
+        let events = published(&bus);
+        let [MarketEvent::Reject(reject)] = events.as_slice() else {
+            panic!("expected one reject, got {events:?}");
+        };
+        assert_eq!((reject.order_id, reject.symbol), (1, symbol()));
+        assert_eq!(reject.timestamp_ns, 10);
+        assert_eq!(reject.reason, SimReject::NoLiquidity(1).to_string());
+    }
+
+    // A noisy queue-model session: market orders every 100ns, trades every 10ns
+    fn noisy_session(seed: u64) -> (Vec<Fill>, Vec<MarketEvent>) {
+        let bus = EventBus::new(1024);
+        let mut engine = SimMatchingEngine::new(
+            FillModel::QueuePosition { latency_ns: 10 },
+            bus.publisher(),
+            RngService::new(seed).stream("sim_matching"),
+        )
+        .with_noise(SimNoise {
+            latency_jitter_ns: 50,
+            max_slippage: Px::from_raw(Px::<8>::SCALE / 2),
+        });
+        let book = book();
+
+        let mut fills = Vec::new();
+        for id in 0..100 {
+            let side = if id % 2 == 0 {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            };
+            let mut order = order(id, side, OrderKind::Market, 2);
+            order.timestamp_ns = id * 100;
+            fills.extend(engine.submit(order, &book).unwrap());
+            for step in 1..=10 {
+                let event = trade(105, 1, id * 100 + step * 10);
+                fills.extend(engine.on_market_event(&event, &book));
+            }
+        }
+        (fills, published(&bus))
+    }
+
+    #[test]
+    fn same_seed_reproduces_the_fill_sequence() {
+        let (fills, events) = noisy_session(42);
+        assert_eq!(fills.len(), 100);
+        // The noise is really drawn: arrival times and prices vary
+        assert!(fills.iter().any(|f| f.timestamp_ns % 100 > 20));
+        assert!(
+            fills
+                .iter()
+                .any(|f| f.price != px(100) && f.price != px(99))
+        );
+
+        let (replayed, replayed_events) = noisy_session(42);
+        assert_eq!(format!("{replayed:?}"), format!("{fills:?}"));
+        assert_eq!(replayed_events, events);
+
+        let (reseeded, _) = noisy_session(43);
+        assert_ne!(reseeded, fills);
+    }
+}
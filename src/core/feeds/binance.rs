@@ -0,0 +1,310 @@
+// Binance spot market data over the public combined WebSocket stream
+// Trades and incremental depth, with reconnect and exponential backoff
+
+use super::{FeedError, MarketDataFeed};
+use crate::core::events::{MarketEvent, Side};
+use crate::core::networking::Backoff;
+use crate::core::types::{Px, Qty, SymbolId};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{info, warn};
+
+pub const BINANCE_STREAM_URL: &str = "wss://stream.binance.com:9443/stream";
+const VENUE: &str = "Binance";
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// `<symbol>@trade` and `<symbol>@depth@100ms` for every subscribed symbol
+#[derive(Debug)]
+pub struct BinanceFeed {
+    endpoint: String,
+    streams: Vec<String>,
+    socket: Option<Socket>,
+    pending: VecDeque<MarketEvent>,
+    backoff: Backoff,
+}
+
+impl BinanceFeed {
+    pub fn new() -> Self {
+        Self::with_endpoint(BINANCE_STREAM_URL)
+    }
+
+    /// Connect somewhere other than production, e.g. the testnet
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            streams: Vec::new(),
+            socket: None,
+            pending: VecDeque::new(),
+            backoff: Backoff::default(),
+        }
+    }
+
+    /// Reconnect on this schedule instead of the default jittered backoff
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    fn url(&self) -> String {
+        format!("{}?streams={}", self.endpoint, self.streams.join("/"))
+    }
+
+    // Keep trying until connected, backing off between attempts
+    async fn connect(&mut self) -> Socket {
+        loop {
+            match connect_async(self.url()).await {
+                Ok((socket, _)) => {
+                    info!("Connected to {} ({} streams)", VENUE, self.streams.len());
+                    return socket;
+                }
+                Err(e) => {
+                    let delay = self.backoff.next_delay();
+                    warn!("{} connect failed, retrying in {:?}: {}", VENUE, delay, e);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+impl Default for BinanceFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketDataFeed for BinanceFeed {
+    fn name(&self) -> &'static str {
+        VENUE
+    }
+
+    async fn subscribe(&mut self, symbols: &[String]) -> Result<(), FeedError> {
+        if symbols.is_empty() {
+            return Err(FeedError::NotSubscribed);
+        }
+        self.streams = symbols
+            .iter()
+            .flat_map(|symbol| {
+                let symbol = symbol.to_lowercase();
+                [format!("{symbol}@trade"), format!("{symbol}@depth@100ms")]
+            })
+            .collect();
+        self.pending.clear();
+        self.socket = Some(self.connect().await);
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if self.streams.is_empty() {
+                return Err(FeedError::NotSubscribed);
+            }
+
+            let socket = match &mut self.socket {
+                Some(socket) => socket,
+                None => {
+                    let socket = self.connect().await;
+                    self.socket.insert(socket)
+                }
+            };
+
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => match parse_message(&text) {
+                    Ok(events) => {
+                        self.backoff.reset();
+                        self.pending.extend(events);
+                    }
+                    Err(e) => warn!("{}", e),
+                },
+                // Pings are answered by tungstenite itself
+                Some(Ok(Message::Close(_))) | None => {
+                    warn!("{} stream closed, reconnecting", VENUE);
+                    self.socket = None;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    warn!("{} stream error, reconnecting: {}", VENUE, e);
+                    self.socket = None;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedMessage {
+    data: StreamPayload,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "e")]
+enum StreamPayload {
+    #[serde(rename = "trade")]
+    Trade {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "p")]
+        price: String,
+        #[serde(rename = "q")]
+        qty: String,
+        #[serde(rename = "T")]
+        trade_time_ms: u64,
+    },
+    #[serde(rename = "depthUpdate")]
+    DepthUpdate {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "E")]
+        event_time_ms: u64,
+        #[serde(rename = "b")]
+        bids: Vec<[String; 2]>,
+        #[serde(rename = "a")]
+        asks: Vec<[String; 2]>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// Decode one combined-stream text frame. Subscription acks and event types
+/// we do not handle yield no events; depth updates yield one `BookUpdate`
+/// per level, bids first.
+pub fn parse_message(text: &str) -> Result<Vec<MarketEvent>, FeedError> {
+    let parse_error = |reason: String| FeedError::Parse {
+        venue: VENUE,
+        reason,
+    };
+
+    let Ok(message) = serde_json::from_str::<CombinedMessage>(text) else {
+        // Not a stream event (e.g. `{"result":null,"id":1}`), unless it is not JSON at all
+        return match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(_) => Ok(Vec::new()),
+            Err(e) => Err(parse_error(e.to_string())),
+        };
+    };
+
+    match message.data {
+        StreamPayload::Trade {
+            symbol,
+            price,
+            qty,
+            trade_time_ms,
+        } => Ok(vec![MarketEvent::Trade {
+            symbol: SymbolId::intern(&symbol)?,
+            timestamp_ns: trade_time_ms.saturating_mul(1_000_000),
+            normalized_ns: trade_time_ms.saturating_mul(1_000_000),
+            price: parse_px(&price).map_err(parse_error)?,
+            size: parse_qty(&qty).map_err(parse_error)?,
+        }]),
+        StreamPayload::DepthUpdate {
+            symbol,
+            event_time_ms,
+            bids,
+            asks,
+        } => {
+            let symbol = SymbolId::intern(&symbol)?;
+            let timestamp_ns = event_time_ms.saturating_mul(1_000_000);
+            bids.iter()
+                .map(|level| (Side::Bid, level))
+                .chain(asks.iter().map(|level| (Side::Ask, level)))
+                .map(|(side, [price, size])| {
+                    Ok(MarketEvent::BookUpdate {
+                        symbol,
+                        timestamp_ns,
+                        normalized_ns: timestamp_ns,
+                        side,
+                        price: parse_px(price).map_err(parse_error)?,
+                        size: parse_qty(size).map_err(parse_error)?,
+                    })
+                })
+                .collect()
+        }
+        StreamPayload::Unknown => Ok(Vec::new()),
+    }
+}
+
+fn parse_px(raw: &str) -> Result<Px, String> {
+    raw.parse().map_err(|e| format!("price '{}': {}", raw, e))
+}
+
+fn parse_qty(raw: &str) -> Result<Qty, String> {
+    raw.parse()
+        .map_err(|e| format!("quantity '{}': {}", raw, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Recorded combined-stream frames: a subscription ack, a trade, a depth
+    // update with a removed bid level and an aggregate trade we ignore
+    const MESSAGES: &str = include_str!("fixtures/binance_messages.jsonl");
+
+    fn px(text: &str) -> Px {
+        text.parse().unwrap()
+    }
+
+    fn qty(text: &str) -> Qty {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn decodes_recorded_messages() {
+        let btc = SymbolId::intern("BTCUSDT").unwrap();
+        let events: Vec<Vec<MarketEvent>> = MESSAGES
+            .lines()
+            .map(|line| parse_message(line).unwrap())
+            .collect();
+
+        assert_eq!(events.len(), 4);
+        assert!(events[0].is_empty());
+        assert_eq!(
+            events[1],
+            [MarketEvent::Trade {
+                symbol: btc,
+                timestamp_ns: 1_700_000_000_120_000_000,
+                normalized_ns: 1_700_000_000_120_000_000,
+                price: px("37012.45"),
+                size: qty("0.00135"),
+            }]
+        );
+        let level = |side, price, size| MarketEvent::BookUpdate {
+            symbol: btc,
+            timestamp_ns: 1_700_000_000_200_000_000,
+            normalized_ns: 1_700_000_000_200_000_000,
+            side,
+            price: px(price),
+            size: qty(size),
+        };
+        assert_eq!(
+            events[2],
+            [
+                level(Side::Bid, "37012.44", "1.2031"),
+                level(Side::Bid, "37012", "0"),
+                level(Side::Ask, "37012.45", "0.51"),
+            ]
+        );
+        assert!(events[3].is_empty());
+    }
+
+    #[test]
+    fn malformed_frames_are_parse_errors() {
+        assert!(matches!(
+            parse_message("not json"),
+            Err(FeedError::Parse { venue: VENUE, .. })
+        ));
+        let bad_price =
+            r#"{"stream":"x","data":{"e":"trade","s":"BTCUSDT","p":"abc","q":"1","T":1}}"#;
+        assert!(matches!(
+            parse_message(bad_price),
+            Err(FeedError::Parse { venue: VENUE, .. })
+        ));
+    }
+}
@@ -0,0 +1,532 @@
+// Zerodha Kite Connect ticker feed
+// Binary WebSocket quote packets decoded into MarketEvents via instrument tokens
+
+use super::{FeedError, MarketDataFeed, now_ns};
+use crate::core::events::MarketEvent;
+use crate::core::networking::Backoff;
+use crate::core::types::{Px, Qty, SymbolId};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{info, warn};
+
+pub const KITE_TICKER_URL: &str = "wss://ws.kite.trade";
+const VENUE: &str = "Kite";
+
+// Packet lengths per mode; index instruments use shorter quote/full packets
+const LTP_PACKET_LEN: usize = 8;
+const INDEX_QUOTE_PACKET_LEN: usize = 28;
+const INDEX_FULL_PACKET_LEN: usize = 32;
+const QUOTE_PACKET_LEN: usize = 44;
+const FULL_PACKET_LEN: usize = 184;
+// Full packets carry five bid then five ask depth entries of 12 bytes from here
+const DEPTH_OFFSET: usize = 64;
+const DEPTH_ENTRY_LEN: usize = 12;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Detail level requested per instrument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KiteMode {
+    /// Last traded price only
+    Ltp,
+    /// Price, last quantity and session OHLC/volume
+    Quote,
+    /// Quote plus timestamps and five levels of depth
+    Full,
+}
+
+impl KiteMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            KiteMode::Ltp => "ltp",
+            KiteMode::Quote => "quote",
+            KiteMode::Full => "full",
+        }
+    }
+}
+
+/// Kite ticker subscription. Symbols are resolved to instrument tokens
+/// through the mapping supplied at construction.
+pub struct KiteFeed {
+    endpoint: String,
+    api_key: String,
+    access_token: String,
+    mode: KiteMode,
+    instruments: HashMap<u32, Arc<str>>,
+    subscribed: HashMap<u32, SymbolId>,
+    socket: Option<Socket>,
+    pending: VecDeque<MarketEvent>,
+    backoff: Backoff,
+}
+
+impl KiteFeed {
+    pub fn new(
+        api_key: impl Into<String>,
+        access_token: impl Into<String>,
+        instruments: HashMap<u32, Arc<str>>,
+    ) -> Self {
+        Self {
+            endpoint: KITE_TICKER_URL.to_string(),
+            api_key: api_key.into(),
+            access_token: access_token.into(),
+            mode: KiteMode::Full,
+            instruments,
+            subscribed: HashMap::new(),
+            socket: None,
+            pending: VecDeque::new(),
+            backoff: Backoff::default(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: KiteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Reconnect on this schedule instead of the default jittered backoff
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Connect and subscribe, retrying transient failures. A handshake the
+    /// server refuses with 401 or 403 means the credentials are bad or the
+    /// daily access token has expired, which no retry fixes.
+    async fn connect(&mut self) -> Result<Socket, FeedError> {
+        let url = format!(
+            "{}?api_key={}&access_token={}",
+            self.endpoint, self.api_key, self.access_token
+        );
+        loop {
+            let attempt = match connect_async(url.as_str()).await {
+                Ok((mut socket, _)) => match self.send_subscription(&mut socket).await {
+                    Ok(()) => return Ok(socket),
+                    Err(e) => e,
+                },
+                Err(WsError::Http(response)) if matches!(response.status().as_u16(), 401 | 403) => {
+                    return Err(FeedError::Connect {
+                        venue: VENUE,
+                        reason: format!(
+                            "handshake rejected with HTTP {}; check the API key and access token",
+                            response.status()
+                        ),
+                    });
+                }
+                Err(e) => e.to_string(),
+            };
+            let delay = self.backoff.next_delay();
+            warn!(
+                "{} connect failed, retrying in {:?}: {}",
+                VENUE, delay, attempt
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn send_subscription(&self, socket: &mut Socket) -> Result<(), String> {
+        let tokens: Vec<u32> = self.subscribed.keys().copied().collect();
+        let subscribe = serde_json::json!({ "a": "subscribe", "v": tokens });
+        let mode = serde_json::json!({ "a": "mode", "v": [self.mode.as_str(), tokens] });
+        for request in [subscribe, mode] {
+            socket
+                .send(Message::Text(request.to_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        info!(
+            "Connected to {} ({} instruments, {} mode)",
+            VENUE,
+            self.subscribed.len(),
+            self.mode.as_str()
+        );
+        Ok(())
+    }
+}
+
+// Credentials stay out of logs
+impl fmt::Debug for KiteFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KiteFeed")
+            .field("endpoint", &self.endpoint)
+            .field("api_key", &"<redacted>")
+            .field("access_token", &"<redacted>")
+            .field("mode", &self.mode)
+            .field("instruments", &self.instruments.len())
+            .field("subscribed", &self.subscribed)
+            .field("connected", &self.socket.is_some())
+            .field("pending", &self.pending.len())
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+impl MarketDataFeed for KiteFeed {
+    fn name(&self) -> &'static str {
+        VENUE
+    }
+
+    async fn subscribe(&mut self, symbols: &[String]) -> Result<(), FeedError> {
+        if symbols.is_empty() {
+            return Err(FeedError::NotSubscribed);
+        }
+        self.subscribed = symbols
+            .iter()
+            .map(|symbol| {
+                let token = self
+                    .instruments
+                    .iter()
+                    .find(|(_, known)| known.as_ref() == symbol)
+                    .map(|(&token, _)| token)
+                    .ok_or_else(|| FeedError::UnknownSymbol(symbol.clone()))?;
+                Ok::<_, FeedError>((token, SymbolId::intern(symbol)?))
+            })
+            .collect::<Result<_, _>>()?;
+        self.pending.clear();
+        self.socket = Some(self.connect().await?);
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if self.subscribed.is_empty() {
+                return Err(FeedError::NotSubscribed);
+            }
+
+            let socket = match &mut self.socket {
+                Some(socket) => socket,
+                None => {
+                    let socket = self.connect().await?;
+                    self.socket.insert(socket)
+                }
+            };
+
+            match socket.next().await {
+                Some(Ok(Message::Binary(frame))) => {
+                    match decode_frame(&frame, &self.subscribed, now_ns()) {
+                        Ok(events) => {
+                            self.backoff.reset();
+                            self.pending.extend(events);
+                        }
+                        Err(e) => warn!("{}", e),
+                    }
+                }
+                // Postbacks and errors arrive as JSON text
+                Some(Ok(Message::Text(text))) => info!("{} message: {}", VENUE, text),
+                Some(Ok(Message::Close(_))) | None => {
+                    warn!("{} ticker closed, reconnecting", VENUE);
+                    self.socket = None;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    warn!("{} ticker error, reconnecting: {}", VENUE, e);
+                    self.socket = None;
+                }
+            }
+        }
+    }
+}
+
+/// Load `instrument_token -> tradingsymbol` from Kite's instruments CSV dump
+pub fn load_instruments(path: impl AsRef<Path>) -> Result<HashMap<u32, Arc<str>>, FeedError> {
+    let path = path.as_ref();
+    let error = |reason: String| FeedError::Instruments {
+        path: path.display().to_string(),
+        reason,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| error("empty file".to_string()))?
+        .split(',')
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|&c| c.trim() == name)
+            .ok_or_else(|| error(format!("no '{}' column", name)))
+    };
+    let (token_col, symbol_col) = (column("instrument_token")?, column("tradingsymbol")?);
+
+    let mut instruments = HashMap::new();
+    for (line_no, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').collect();
+        let (Some(token), Some(symbol)) = (fields.get(token_col), fields.get(symbol_col)) else {
+            continue;
+        };
+        let token = token
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| error(format!("line {}: bad instrument_token: {}", line_no + 2, e)))?;
+        instruments.insert(token, Arc::from(symbol.trim()));
+    }
+    Ok(instruments)
+}
+
+/// Decode one binary ticker frame: a big-endian packet count, then each
+/// packet prefixed by its length. A lone byte is a heartbeat.
+///
+/// Packets map to events by mode: LTP packets become zero-size trades (they
+/// carry no quantity but still mark positions), quote packets a trade at the
+/// last price and quantity, and full packets add a top-of-book quote and use
+/// the exchange timestamp instead of `received_ns`. Tokens missing from
+/// `symbols` (not subscribed) are skipped.
+pub fn decode_frame(
+    frame: &[u8],
+    symbols: &HashMap<u32, SymbolId>,
+    received_ns: u64,
+) -> Result<Vec<MarketEvent>, FeedError> {
+    if frame.len() < 2 {
+        return Ok(vec![MarketEvent::Heartbeat {
+            timestamp_ns: received_ns,
+            normalized_ns: received_ns,
+        }]);
+    }
+
+    let truncated = || FeedError::Parse {
+        venue: VENUE,
+        reason: format!("truncated {}-byte frame", frame.len()),
+    };
+    let count = usize::from(read_u16(frame, 0).ok_or_else(truncated)?);
+    let mut offset = 2;
+    let mut events = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let len = usize::from(read_u16(frame, offset).ok_or_else(truncated)?);
+        let packet = frame
+            .get(offset + 2..offset + 2 + len)
+            .ok_or_else(truncated)?;
+        offset += 2 + len;
+
+        let token = read_u32(packet, 0).ok_or_else(truncated)?;
+        let Some(&symbol) = symbols.get(&token) else {
+            continue;
+        };
+        decode_packet(packet, token, symbol, received_ns, &mut events)?;
+    }
+    Ok(events)
+}
+
+fn decode_packet(
+    packet: &[u8],
+    token: u32,
+    symbol: SymbolId,
+    received_ns: u64,
+    events: &mut Vec<MarketEvent>,
+) -> Result<(), FeedError> {
+    let divisor = price_divisor(token);
+    let price_at = |at: usize| read_i32(packet, at).map(|raw| scale_price(raw, divisor));
+    let malformed = || FeedError::Parse {
+        venue: VENUE,
+        reason: format!(
+            "unexpected {}-byte packet for token {}",
+            packet.len(),
+            token
+        ),
+    };
+
+    match packet.len() {
+        LTP_PACKET_LEN | INDEX_QUOTE_PACKET_LEN | INDEX_FULL_PACKET_LEN => {
+            events.push(MarketEvent::Trade {
+                symbol,
+                timestamp_ns: received_ns,
+                normalized_ns: received_ns,
+                price: price_at(4).ok_or_else(malformed)?,
+                size: Qty::ZERO,
+            });
+        }
+        QUOTE_PACKET_LEN | FULL_PACKET_LEN => {
+            let timestamp_ns = if packet.len() == FULL_PACKET_LEN {
+                read_u32(packet, 60)
+                    .filter(|&secs| secs > 0)
+                    .map_or(received_ns, |secs| u64::from(secs) * 1_000_000_000)
+            } else {
+                received_ns
+            };
+            let last_qty = read_i32(packet, 8).ok_or_else(malformed)?;
+            events.push(MarketEvent::Trade {
+                symbol,
+                timestamp_ns,
+                normalized_ns: timestamp_ns,
+                price: price_at(4).ok_or_else(malformed)?,
+                size: Qty::from_int(i64::from(last_qty)).unwrap_or(Qty::ZERO),
+            });
+
+            if packet.len() == FULL_PACKET_LEN {
+                let level = |index: usize| -> Option<(Px, Qty)> {
+                    let at = DEPTH_OFFSET + index * DEPTH_ENTRY_LEN;
+                    let qty = read_i32(packet, at)?;
+                    Some((price_at(at + 4)?, Qty::from_int(i64::from(qty))?))
+                };
+                // Entries 0-4 are bids, 5-9 asks; level 0 of each is the best
+                if let (Some((bid_price, bid_size)), Some((ask_price, ask_size))) =
+                    (level(0), level(5))
+                    && !bid_size.is_zero()
+                    && !ask_size.is_zero()
+                {
+                    events.push(MarketEvent::Quote {
+                        symbol,
+                        timestamp_ns,
+                        normalized_ns: timestamp_ns,
+                        bid_price,
+                        bid_size,
+                        ask_price,
+                        ask_size,
+                    });
+                }
+            }
+        }
+        _ => return Err(malformed()),
+    }
+    Ok(())
+}
+
+// Prices are integers in the segment's smallest unit: paise for most,
+// with finer ticks for currency derivatives (segment 3) and BSE currency (6)
+fn price_divisor(token: u32) -> i64 {
+    match token & 0xff {
+        3 => 10_000_000,
+        6 => 10_000,
+        _ => 100,
+    }
+}
+
+fn scale_price(raw: i32, divisor: i64) -> Px {
+    Px::from_raw(i64::from(raw) * (Px::<8>::SCALE / divisor))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_i32(bytes: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // One ticker frame: an LTP packet for 408065, a quote packet for 738561
+    // and a full packet for 341249, all NSE equities priced in paise
+    const FRAME: &[u8] = include_bytes!("fixtures/kite_frame.bin");
+    const RECEIVED_NS: u64 = 42;
+
+    fn px(text: &str) -> Px {
+        text.parse().unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    #[test]
+    fn decodes_fixture_frame() {
+        let infy = SymbolId::intern("INFY").unwrap();
+        let reliance = SymbolId::intern("RELIANCE").unwrap();
+        let hdfc = SymbolId::intern("HDFCBANK").unwrap();
+        let symbols = HashMap::from([(408065, infy), (738561, reliance), (341249, hdfc)]);
+
+        let events = decode_frame(FRAME, &symbols, RECEIVED_NS).unwrap();
+        let exchange_ns = 1_700_000_000 * 1_000_000_000;
+        assert_eq!(
+            events,
+            [
+                MarketEvent::Trade {
+                    symbol: infy,
+                    timestamp_ns: RECEIVED_NS,
+                    normalized_ns: RECEIVED_NS,
+                    price: px("1500.25"),
+                    size: Qty::ZERO,
+                },
+                MarketEvent::Trade {
+                    symbol: reliance,
+                    timestamp_ns: RECEIVED_NS,
+                    normalized_ns: RECEIVED_NS,
+                    price: px("2450.10"),
+                    size: qty(5),
+                },
+                MarketEvent::Trade {
+                    symbol: hdfc,
+                    timestamp_ns: exchange_ns,
+                    normalized_ns: exchange_ns,
+                    price: px("1600.50"),
+                    size: qty(10),
+                },
+                MarketEvent::Quote {
+                    symbol: hdfc,
+                    timestamp_ns: exchange_ns,
+                    normalized_ns: exchange_ns,
+                    bid_price: px("1600.45"),
+                    bid_size: qty(25),
+                    ask_price: px("1600.55"),
+                    ask_size: qty(40),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unsubscribed_tokens_and_heartbeats() {
+        let symbols = HashMap::from([(408065, SymbolId::intern("INFY").unwrap())]);
+        assert_eq!(decode_frame(FRAME, &symbols, RECEIVED_NS).unwrap().len(), 1);
+        assert!(matches!(
+            decode_frame(&[0], &symbols, RECEIVED_NS).unwrap()[..],
+            [MarketEvent::Heartbeat { .. }]
+        ));
+        assert!(decode_frame(&FRAME[..FRAME.len() - 1], &symbols, RECEIVED_NS).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_credentials() {
+        let feed = KiteFeed::new("the-api-key", "the-access-token", HashMap::new());
+        let debug = format!("{:?}", feed);
+        assert!(!debug.contains("the-api-key"));
+        assert!(!debug.contains("the-access-token"));
+    }
+
+    #[tokio::test]
+    async fn forbidden_handshake_is_fatal() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let instruments = HashMap::from([(408065, Arc::from("INFY"))]);
+        let mut feed = KiteFeed::new("key", "expired", instruments).with_endpoint(endpoint);
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            feed.subscribe(&["INFY".to_string()]),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, Err(FeedError::Connect { .. })));
+    }
+}
@@ -0,0 +1,148 @@
+// Live market data feeds for ShrivenQ
+// Exchange adapters that turn venue wire formats into MarketEvents for the event bus
+
+pub mod recorder;
+pub mod watchdog;
+
+pub use recorder::{FeedPlayer, FeedRecorder};
+pub use watchdog::{FeedWatchdog, StaleFeed, WatchdogConfig, WatchedFeed};
+
+#[cfg(feature = "binance-integration")]
+pub mod binance;
+
+#[cfg(feature = "binance-integration")]
+pub use binance::BinanceFeed;
+
+#[cfg(feature = "zerodha-integration")]
+pub mod kite;
+
+#[cfg(feature = "zerodha-integration")]
+pub use kite::{KiteFeed, KiteMode};
+
+use crate::core::events::{BusError, EventPublisher, MarketEvent};
+use crate::core::time::TimeSource;
+use crate::core::types::SymbolError;
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FeedError {
+    #[error("Connection to {venue} failed: {reason}")]
+    Connect { venue: &'static str, reason: String },
+    #[error("Malformed {venue} message: {reason}")]
+    Parse { venue: &'static str, reason: String },
+    #[error("Unknown symbol: {0}")]
+    UnknownSymbol(String),
+    #[error("Instrument list {path}: {reason}")]
+    Instruments { path: String, reason: String },
+    #[error("Feed recording {path}: {reason}")]
+    Recording { path: String, reason: String },
+    #[error(transparent)]
+    Symbol(#[from] SymbolError),
+    #[error("No symbols subscribed")]
+    NotSubscribed,
+    #[error("Feed closed")]
+    Closed,
+}
+
+/// A venue's streaming market data.
+///
+/// Implementations own their connection and reconnect on transient failures;
+/// `next_event` only errors when the feed cannot continue.
+pub trait MarketDataFeed: Send {
+    /// Venue name for logs
+    fn name(&self) -> &'static str;
+
+    /// Replace the subscription with `symbols`, connecting if needed
+    fn subscribe(
+        &mut self,
+        symbols: &[String],
+    ) -> impl Future<Output = Result<(), FeedError>> + Send;
+
+    /// Next event from the feed, waiting for one to arrive
+    fn next_event(&mut self) -> impl Future<Output = Result<MarketEvent, FeedError>> + Send;
+}
+
+/// Subscribe `feed` to `symbols` and forward its events onto the bus until
+/// the feed fails or the bus is dropped. Each event is stamped with its time
+/// on `time` first. A full bus is handled by its overflow policy: market data
+/// buses drop the oldest events so a slow consumer cannot stall the socket,
+/// counting them in `EventBus::dropped`.
+pub async fn run_feed<F: MarketDataFeed>(
+    mut feed: F,
+    symbols: &[String],
+    publisher: EventPublisher,
+    time: &TimeSource,
+) -> Result<(), FeedError> {
+    feed.subscribe(symbols).await?;
+    info!("{} feed subscribed to {}", feed.name(), symbols.join(", "));
+
+    let clock = time.feed_clock(feed.name());
+    loop {
+        let mut event = feed.next_event().await?;
+        clock.stamp(&mut event);
+        if let Err(BusError::Disconnected) = publisher.publish(event) {
+            return Ok(());
+        }
+    }
+}
+
+/// Local wall-clock time in epoch nanoseconds, for stamping received data
+pub(crate) fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::{EventBus, OverflowPolicy};
+    use std::collections::VecDeque;
+
+    // Plays back a fixed list of events, then closes
+    struct ScriptedFeed(VecDeque<MarketEvent>);
+
+    impl MarketDataFeed for ScriptedFeed {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        async fn subscribe(&mut self, _symbols: &[String]) -> Result<(), FeedError> {
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+            self.0.pop_front().ok_or(FeedError::Closed)
+        }
+    }
+
+    #[tokio::test]
+    async fn full_bus_drops_oldest_feed_events() {
+        let events = (1..=10)
+            .map(|ns| MarketEvent::Heartbeat {
+                timestamp_ns: ns,
+                normalized_ns: ns,
+            })
+            .collect();
+        let bus = EventBus::with_policy(4, OverflowPolicy::DropOldest);
+
+        let result = run_feed(
+            ScriptedFeed(events),
+            &[],
+            bus.publisher(),
+            &TimeSource::default(),
+        )
+        .await;
+        assert_eq!(result, Err(FeedError::Closed));
+
+        let mut queued = Vec::new();
+        bus.drain_into(&mut queued, usize::MAX);
+        let queued: Vec<u64> = queued.iter().map(MarketEvent::timestamp_ns).collect();
+        assert_eq!(queued, [7, 8, 9, 10]);
+        assert_eq!((bus.dropped(), bus.rejected()), (6, 0));
+    }
+}
@@ -0,0 +1,574 @@
+// Market data capture and replay
+// Length-prefixed binary log of received events, played back as a feed
+
+use super::{FeedError, MarketDataFeed, now_ns};
+use crate::core::events::{MarketEvent, Side};
+use crate::core::orders::{Fill, OrderReject, OrderSide};
+use crate::core::replay::ReplayPacer;
+use crate::core::types::{Px, Qty, SymbolId};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+/// Leading bytes of every feed log; the last byte is the format version
+const MAGIC: &[u8; 8] = b"SQFEED\0\x01";
+
+const TAG_TRADE: u8 = 0;
+const TAG_QUOTE: u8 = 1;
+const TAG_BOOK_UPDATE: u8 = 2;
+const TAG_HEARTBEAT: u8 = 3;
+const TAG_FILL: u8 = 4;
+const TAG_REJECT: u8 = 5;
+
+/// Wraps a feed and appends every event it yields to a log file.
+///
+/// Each record is a little-endian `u32` length followed by the local receive
+/// time in nanoseconds and the encoded event. Writes are buffered; call
+/// `flush` to force them out (dropping the recorder also flushes).
+#[derive(Debug)]
+pub struct FeedRecorder<F> {
+    inner: F,
+    path: String,
+    writer: BufWriter<File>,
+    record: Vec<u8>,
+    recorded: u64,
+}
+
+impl<F: MarketDataFeed> FeedRecorder<F> {
+    /// Create (or truncate) the log at `path` and record `inner` into it
+    pub fn new(inner: F, path: impl AsRef<Path>) -> Result<Self, FeedError> {
+        let path = path.as_ref().display().to_string();
+        let mut writer = File::create(&path)
+            .map(BufWriter::new)
+            .map_err(|e| recording_error(&path, e))?;
+        writer
+            .write_all(MAGIC)
+            .map_err(|e| recording_error(&path, e))?;
+        Ok(Self {
+            inner,
+            path,
+            writer,
+            record: Vec::with_capacity(128),
+            recorded: 0,
+        })
+    }
+
+    /// Events written so far
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    pub fn flush(&mut self) -> Result<(), FeedError> {
+        self.writer
+            .flush()
+            .map_err(|e| recording_error(&self.path, e))
+    }
+
+    pub fn into_inner(mut self) -> Result<F, FeedError> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write(&mut self, received_ns: u64, event: &MarketEvent) -> Result<(), FeedError> {
+        self.record.clear();
+        encode(received_ns, event, &mut self.record);
+        let len = u32::try_from(self.record.len()).map_err(|_| FeedError::Recording {
+            path: self.path.clone(),
+            reason: format!("{}-byte record too large", self.record.len()),
+        })?;
+        self.writer
+            .write_all(&len.to_le_bytes())
+            .and_then(|()| self.writer.write_all(&self.record))
+            .map_err(|e| recording_error(&self.path, e))?;
+        self.recorded += 1;
+        Ok(())
+    }
+}
+
+impl<F: MarketDataFeed> MarketDataFeed for FeedRecorder<F> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn subscribe(&mut self, symbols: &[String]) -> Result<(), FeedError> {
+        self.inner.subscribe(symbols).await
+    }
+
+    async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+        let event = self.inner.next_event().await?;
+        self.write(now_ns(), &event)?;
+        Ok(event)
+    }
+}
+
+/// Replays a log written by `FeedRecorder` as a feed.
+///
+/// Without a pacer events are returned as fast as they are read; with one,
+/// the gaps between their original receive times are reproduced at the
+/// pacer's speed. The log is exhausted with `FeedError::Closed`.
+#[derive(Debug)]
+pub struct FeedPlayer {
+    path: String,
+    reader: BufReader<File>,
+    pacer: Option<ReplayPacer>,
+    symbols: Vec<SymbolId>,
+    record: Vec<u8>,
+}
+
+impl FeedPlayer {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FeedError> {
+        let path = path.as_ref().display().to_string();
+        let mut reader = File::open(&path)
+            .map(BufReader::new)
+            .map_err(|e| recording_error(&path, e))?;
+        let mut magic = [0u8; MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| recording_error(&path, e))?;
+        if &magic != MAGIC {
+            return Err(FeedError::Recording {
+                path,
+                reason: "not a feed log".to_string(),
+            });
+        }
+        Ok(Self {
+            path,
+            reader,
+            pacer: None,
+            symbols: Vec::new(),
+            record: Vec::with_capacity(128),
+        })
+    }
+
+    /// Reproduce the recorded inter-event timing through `pacer`
+    pub fn with_pacer(mut self, pacer: ReplayPacer) -> Self {
+        self.pacer = Some(pacer);
+        self
+    }
+
+    /// Next record and its receive time regardless of subscription or
+    /// pacing, or `None` at the end of the log
+    pub fn next_record(&mut self) -> Result<Option<(u64, MarketEvent)>, FeedError> {
+        let mut len = [0u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(recording_error(&self.path, e)),
+        }
+        self.record.resize(u32::from_le_bytes(len) as usize, 0);
+        self.reader
+            .read_exact(&mut self.record)
+            .map_err(|e| recording_error(&self.path, e))?;
+        decode(&self.record)
+            .map(Some)
+            .map_err(|reason| FeedError::Recording {
+                path: self.path.clone(),
+                reason,
+            })
+    }
+
+    fn wanted(&self, event: &MarketEvent) -> bool {
+        event
+            .symbol()
+            .is_none_or(|symbol| self.symbols.contains(&symbol))
+    }
+}
+
+impl MarketDataFeed for FeedPlayer {
+    fn name(&self) -> &'static str {
+        "Replay"
+    }
+
+    async fn subscribe(&mut self, symbols: &[String]) -> Result<(), FeedError> {
+        if symbols.is_empty() {
+            return Err(FeedError::NotSubscribed);
+        }
+        self.symbols = symbols
+            .iter()
+            .map(|symbol| SymbolId::intern(symbol))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+        if self.symbols.is_empty() {
+            return Err(FeedError::NotSubscribed);
+        }
+        loop {
+            let Some((received_ns, event)) = self.next_record()? else {
+                return Err(FeedError::Closed);
+            };
+            if !self.wanted(&event) {
+                continue;
+            }
+            if let Some(pacer) = &mut self.pacer {
+                tokio::time::sleep(pacer.delay_until_due(received_ns)).await;
+            }
+            return Ok(event);
+        }
+    }
+}
+
+fn recording_error(path: &str, error: std::io::Error) -> FeedError {
+    FeedError::Recording {
+        path: path.to_string(),
+        reason: error.to_string(),
+    }
+}
+
+fn encode(received_ns: u64, event: &MarketEvent, out: &mut Vec<u8>) {
+    out.extend_from_slice(&received_ns.to_le_bytes());
+    match event {
+        MarketEvent::Trade {
+            symbol,
+            timestamp_ns,
+            price,
+            size,
+            ..
+        } => {
+            out.push(TAG_TRADE);
+            put_str(out, symbol.as_str());
+            put_u64(out, *timestamp_ns);
+            put_i64(out, price.raw());
+            put_i64(out, size.raw());
+        }
+        MarketEvent::Quote {
+            symbol,
+            timestamp_ns,
+            bid_price,
+            bid_size,
+            ask_price,
+            ask_size,
+            ..
+        } => {
+            out.push(TAG_QUOTE);
+            put_str(out, symbol.as_str());
+            put_u64(out, *timestamp_ns);
+            for value in [
+                bid_price.raw(),
+                bid_size.raw(),
+                ask_price.raw(),
+                ask_size.raw(),
+            ] {
+                put_i64(out, value);
+            }
+        }
+        MarketEvent::BookUpdate {
+            symbol,
+            timestamp_ns,
+            side,
+            price,
+            size,
+            ..
+        } => {
+            out.push(TAG_BOOK_UPDATE);
+            put_str(out, symbol.as_str());
+            put_u64(out, *timestamp_ns);
+            out.push(matches!(side, Side::Ask) as u8);
+            put_i64(out, price.raw());
+            put_i64(out, size.raw());
+        }
+        MarketEvent::Heartbeat { timestamp_ns, .. } => {
+            out.push(TAG_HEARTBEAT);
+            put_u64(out, *timestamp_ns);
+        }
+        MarketEvent::Fill(fill) => {
+            out.push(TAG_FILL);
+            put_str(out, fill.symbol.as_str());
+            put_u64(out, fill.timestamp_ns);
+            put_u64(out, fill.order_id);
+            out.push(matches!(fill.side, OrderSide::Sell) as u8);
+            put_i64(out, fill.price.raw());
+            put_i64(out, fill.qty.raw());
+        }
+        MarketEvent::Reject(reject) => {
+            out.push(TAG_REJECT);
+            put_str(out, reject.symbol.as_str());
+            put_u64(out, reject.timestamp_ns);
+            put_u64(out, reject.order_id);
+            put_str(out, &reject.reason);
+        }
+    }
+}
+
+fn decode(record: &[u8]) -> Result<(u64, MarketEvent), String> {
+    let mut cursor = Cursor {
+        bytes: record,
+        at: 0,
+    };
+    let received_ns = cursor.u64()?;
+    // Logs hold events as the feed produced them, before normalization
+    let event = match cursor.u8()? {
+        TAG_TRADE => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::Trade {
+                symbol,
+                timestamp_ns,
+                normalized_ns: timestamp_ns,
+                price: Px::from_raw(cursor.i64()?),
+                size: Qty::from_raw(cursor.i64()?),
+            }
+        }
+        TAG_QUOTE => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::Quote {
+                symbol,
+                timestamp_ns,
+                normalized_ns: timestamp_ns,
+                bid_price: Px::from_raw(cursor.i64()?),
+                bid_size: Qty::from_raw(cursor.i64()?),
+                ask_price: Px::from_raw(cursor.i64()?),
+                ask_size: Qty::from_raw(cursor.i64()?),
+            }
+        }
+        TAG_BOOK_UPDATE => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::BookUpdate {
+                symbol,
+                timestamp_ns,
+                normalized_ns: timestamp_ns,
+                side: if cursor.u8()? == 0 {
+                    Side::Bid
+                } else {
+                    Side::Ask
+                },
+                price: Px::from_raw(cursor.i64()?),
+                size: Qty::from_raw(cursor.i64()?),
+            }
+        }
+        TAG_HEARTBEAT => {
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::Heartbeat {
+                timestamp_ns,
+                normalized_ns: timestamp_ns,
+            }
+        }
+        TAG_FILL => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::Fill(Fill {
+                order_id: cursor.u64()?,
+                symbol,
+                side: if cursor.u8()? == 0 {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                },
+                price: Px::from_raw(cursor.i64()?),
+                qty: Qty::from_raw(cursor.i64()?),
+                timestamp_ns,
+            })
+        }
+        TAG_REJECT => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            MarketEvent::Reject(OrderReject {
+                order_id: cursor.u64()?,
+                symbol,
+                reason: cursor.str()?.to_string(),
+                timestamp_ns,
+            })
+        }
+        tag => return Err(format!("unknown event tag {}", tag)),
+    };
+    Ok((received_ns, event))
+}
+
+fn put_str(out: &mut Vec<u8>, text: &str) {
+    // Symbols and reject reasons are short; anything longer is truncated
+    let bytes = &text.as_bytes()[..text.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_i64(out: &mut Vec<u8>, value: i64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.at..self.at + len)
+            .ok_or_else(|| format!("record truncated at byte {}", self.at))?;
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        self.array().map(i64::from_le_bytes)
+    }
+
+    fn str(&mut self) -> Result<&'a str, String> {
+        let len = u16::from_le_bytes(self.array()?) as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|e| format!("text is not UTF-8: {}", e))
+    }
+
+    // Logs carry names rather than ids, which are only stable per process
+    fn symbol(&mut self) -> Result<SymbolId, String> {
+        SymbolId::intern(self.str()?).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::path::PathBuf;
+
+    // Plays back a fixed list of events, then closes
+    struct ScriptedFeed(VecDeque<MarketEvent>);
+
+    impl MarketDataFeed for ScriptedFeed {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        async fn subscribe(&mut self, _symbols: &[String]) -> Result<(), FeedError> {
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+            self.0.pop_front().ok_or(FeedError::Closed)
+        }
+    }
+
+    fn log_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("shriven-feed-{}-{}.log", name, std::process::id()))
+    }
+
+    fn heartbeat(ns: u64) -> MarketEvent {
+        MarketEvent::Heartbeat {
+            timestamp_ns: ns,
+            normalized_ns: ns,
+        }
+    }
+
+    fn one_of_each() -> Vec<MarketEvent> {
+        let btc = SymbolId::intern("BTCUSDT").unwrap();
+        let px = |text: &str| text.parse::<Px>().unwrap();
+        let qty = |text: &str| text.parse::<Qty>().unwrap();
+        vec![
+            MarketEvent::Trade {
+                symbol: btc,
+                timestamp_ns: 1,
+                normalized_ns: 1,
+                price: px("37012.45"),
+                size: qty("0.5"),
+            },
+            MarketEvent::Quote {
+                symbol: btc,
+                timestamp_ns: 2,
+                normalized_ns: 2,
+                bid_price: px("37012.44"),
+                bid_size: qty("1.25"),
+                ask_price: px("37012.46"),
+                ask_size: qty("2"),
+            },
+            MarketEvent::BookUpdate {
+                symbol: btc,
+                timestamp_ns: 3,
+                normalized_ns: 3,
+                side: Side::Ask,
+                price: px("37013"),
+                size: qty("0"),
+            },
+            heartbeat(4),
+            MarketEvent::Fill(Fill {
+                order_id: 7,
+                symbol: btc,
+                side: OrderSide::Sell,
+                price: px("37012.45"),
+                qty: qty("0.1"),
+                timestamp_ns: 5,
+            }),
+            MarketEvent::Reject(OrderReject {
+                order_id: 8,
+                symbol: btc,
+                reason: "insufficient margin".to_string(),
+                timestamp_ns: 6,
+            }),
+        ]
+    }
+
+    #[tokio::test]
+    async fn recorded_events_replay_identically() {
+        let path = log_path("round-trip");
+        let events = one_of_each();
+
+        let mut recorder = FeedRecorder::new(ScriptedFeed(events.clone().into()), &path).unwrap();
+        for expected in &events {
+            assert_eq!(&recorder.next_event().await.unwrap(), expected);
+        }
+        assert_eq!(recorder.next_event().await, Err(FeedError::Closed));
+        assert_eq!(recorder.recorded(), events.len() as u64);
+        recorder.into_inner().unwrap();
+
+        let mut player = FeedPlayer::open(&path).unwrap();
+        player.subscribe(&["BTCUSDT".to_string()]).await.unwrap();
+        for expected in &events {
+            assert_eq!(&player.next_event().await.unwrap(), expected);
+        }
+        assert_eq!(player.next_event().await, Err(FeedError::Closed));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn player_reproduces_recorded_gaps_at_the_pacer_speed() {
+        let path = log_path("paced");
+        let mut log = MAGIC.to_vec();
+        for (received_ns, ns) in [(0, 1), (40_000_000, 2), (120_000_000, 3)] {
+            let mut record = Vec::new();
+            encode(received_ns, &heartbeat(ns), &mut record);
+            log.extend_from_slice(&(record.len() as u32).to_le_bytes());
+            log.extend_from_slice(&record);
+        }
+        std::fs::write(&path, log).unwrap();
+
+        let start = std::time::Instant::now();
+        let mut player = FeedPlayer::open(&path)
+            .unwrap()
+            .with_pacer(ReplayPacer::new(2.0).unwrap());
+        player.subscribe(&["BTCUSDT".to_string()]).await.unwrap();
+        let mut due = Vec::new();
+        while let Ok(event) = player.next_event().await {
+            due.push((event.timestamp_ns(), start.elapsed().as_millis()));
+        }
+
+        // Recorded 40ms and 80ms apart, replayed at double speed
+        assert_eq!(due.len(), 3);
+        for ((ns, at_ms), (expected_ns, expected_ms)) in
+            due.into_iter().zip([(1, 0), (2, 20), (3, 60)])
+        {
+            assert_eq!(ns, expected_ns);
+            assert!(at_ms >= expected_ms, "event {ns} at {at_ms}ms");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
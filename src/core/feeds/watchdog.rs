@@ -0,0 +1,365 @@
+// Market data staleness watchdog
+// Detects silent feeds per symbol and per connection, optionally forcing a safe mode
+
+use super::{FeedError, MarketDataFeed};
+use crate::core::diagnostics::{FlightEvent, FlightRecorder};
+use crate::core::events::MarketEvent;
+use crate::core::execution::ExecutionMode;
+use crate::core::execution::mode_switcher::ModeSwitcher;
+use crate::core::memory::{Clock, SystemClock};
+use crate::core::types::{SymbolId, SymbolRegistry};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchdogConfig {
+    /// Silence after which a single symbol is reported stale
+    pub symbol_timeout: Duration,
+    /// Silence across every symbol and heartbeat after which the connection
+    /// is presumed dead
+    pub connection_timeout: Duration,
+    /// How often `monitor` checks the timestamps
+    pub check_interval: Duration,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            symbol_timeout: Duration::from_secs(30),
+            connection_timeout: Duration::from_secs(5),
+            check_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleFeed {
+    /// No events at all, heartbeats included, from the venue
+    Connection {
+        venue: &'static str,
+        silent_for: Duration,
+    },
+    /// The connection is alive but this symbol has stopped updating
+    Symbol {
+        symbol: SymbolId,
+        silent_for: Duration,
+    },
+}
+
+#[derive(Debug)]
+struct WatchdogState {
+    last_event: Instant,
+    last_by_symbol: HashMap<SymbolId, Instant>,
+    connection_stale: bool,
+    stale_symbols: HashSet<SymbolId>,
+}
+
+/// Tracks when a feed last delivered data.
+///
+/// A quiet instrument and a dead connection look the same from one symbol's
+/// point of view, so the connection is judged on every event including
+/// heartbeats: while anything arrives only individual symbols can go stale,
+/// and once nothing does a single `StaleFeed::Connection` is raised instead.
+/// Alerts fire once per stale episode and rearm when data resumes.
+#[derive(Debug)]
+pub struct FeedWatchdog {
+    venue: &'static str,
+    config: WatchdogConfig,
+    state: Mutex<WatchdogState>,
+    safe_mode: Option<(Arc<Mutex<ModeSwitcher>>, ExecutionMode)>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FeedWatchdog {
+    pub fn new(venue: &'static str, config: WatchdogConfig) -> Self {
+        Self {
+            venue,
+            config,
+            state: Mutex::new(WatchdogState {
+                last_event: Instant::now(),
+                last_by_symbol: HashMap::new(),
+                connection_stale: false,
+                stale_symbols: HashSet::new(),
+            }),
+            safe_mode: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Measure silence on `clock`, e.g. a `SimClock` so a backtest judges
+    /// staleness by event time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.state.get_mut().last_event = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Switch `switcher` to `mode` when the connection goes stale
+    pub fn with_safe_mode(
+        mut self,
+        switcher: Arc<Mutex<ModeSwitcher>>,
+        mode: ExecutionMode,
+    ) -> Self {
+        self.safe_mode = Some((switcher, mode));
+        self
+    }
+
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    /// Expect updates for `symbols`, starting their clocks now so a symbol
+    /// that never ticks is reported too
+    pub fn watch(&self, symbols: &[SymbolId]) {
+        let now = self.clock.now();
+        let mut state = self.state.lock();
+        state.last_event = now;
+        state.last_by_symbol = symbols.iter().map(|&symbol| (symbol, now)).collect();
+        state.connection_stale = false;
+        state.stale_symbols.clear();
+    }
+
+    /// Record that `event` just arrived
+    pub fn observe(&self, event: &MarketEvent) {
+        let now = self.clock.now();
+        let mut state = self.state.lock();
+        state.last_event = now;
+        if std::mem::take(&mut state.connection_stale) {
+            info!("{} feed resumed", self.venue);
+        }
+
+        if let Some(symbol) = event.symbol() {
+            if let Some(last) = state.last_by_symbol.get_mut(&symbol) {
+                *last = now;
+            }
+            if state.stale_symbols.remove(&symbol) {
+                info!("{} {} updates resumed", self.venue, symbol);
+            }
+        }
+    }
+
+    /// Alerts for anything that has gone stale since the last check
+    pub fn check(&self) -> Vec<StaleFeed> {
+        let now = self.clock.now();
+        let mut state = self.state.lock();
+        let mut alerts = Vec::new();
+
+        let silent_for = now.duration_since(state.last_event);
+        if silent_for >= self.config.connection_timeout {
+            if !state.connection_stale {
+                state.connection_stale = true;
+                alerts.push(StaleFeed::Connection {
+                    venue: self.venue,
+                    silent_for,
+                });
+            }
+            return alerts;
+        }
+
+        let WatchdogState {
+            last_by_symbol,
+            stale_symbols,
+            ..
+        } = &mut *state;
+        for (&symbol, &last) in last_by_symbol.iter() {
+            let silent_for = now.duration_since(last);
+            if silent_for >= self.config.symbol_timeout && stale_symbols.insert(symbol) {
+                alerts.push(StaleFeed::Symbol { symbol, silent_for });
+            }
+        }
+        alerts
+    }
+
+    /// Check every `check_interval` forever, logging alerts and forcing the
+    /// safe mode on a dead connection. Run it as its own task.
+    pub async fn monitor(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(self.config.check_interval);
+        loop {
+            interval.tick().await;
+            for alert in self.check() {
+                self.handle(&alert);
+            }
+        }
+    }
+
+    fn handle(&self, alert: &StaleFeed) {
+        FlightRecorder::global().record(FlightEvent::FeedStall(*alert));
+        match alert {
+            StaleFeed::Symbol { symbol, silent_for } => {
+                warn!("Stale feed: no {} update for {:?}", symbol, silent_for);
+            }
+            StaleFeed::Connection { venue, silent_for } => {
+                warn!("Stale feed: nothing from {} for {:?}", venue, silent_for);
+                if let Some((switcher, mode)) = &self.safe_mode {
+                    let mut switcher = switcher.lock();
+                    if switcher.current_mode() != *mode
+                        && let Err(e) = switcher.switch_mode(*mode)
+                    {
+                        warn!("Failed to enter {} mode after stale feed: {}", mode, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wrap `feed` so every event it yields is observed
+    pub fn wrap<F: MarketDataFeed>(self: &Arc<Self>, feed: F) -> WatchedFeed<F> {
+        WatchedFeed {
+            inner: feed,
+            watchdog: Arc::clone(self),
+        }
+    }
+}
+
+/// A feed whose events and subscriptions are reported to a `FeedWatchdog`
+#[derive(Debug)]
+pub struct WatchedFeed<F> {
+    inner: F,
+    watchdog: Arc<FeedWatchdog>,
+}
+
+impl<F: MarketDataFeed> MarketDataFeed for WatchedFeed<F> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn subscribe(&mut self, symbols: &[String]) -> Result<(), FeedError> {
+        self.inner.subscribe(symbols).await?;
+        self.watchdog
+            .watch(&SymbolRegistry::global().intern_all(symbols)?);
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+        let event = self.inner.next_event().await?;
+        self.watchdog.observe(&event);
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{Px, Qty};
+    use std::collections::VecDeque;
+
+    // Follows tokio's clock, so paused tests control the watchdog's time too
+    #[derive(Debug)]
+    struct TokioClock;
+
+    impl Clock for TokioClock {
+        fn now(&self) -> Instant {
+            tokio::time::Instant::now().into_std()
+        }
+    }
+
+    // Yields each event after its delay, then goes silent like a half-open socket
+    struct PacedFeed(VecDeque<(Duration, MarketEvent)>);
+
+    impl MarketDataFeed for PacedFeed {
+        fn name(&self) -> &'static str {
+            "paced"
+        }
+
+        async fn subscribe(&mut self, _symbols: &[String]) -> Result<(), FeedError> {
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Result<MarketEvent, FeedError> {
+            match self.0.pop_front() {
+                Some((delay, event)) => {
+                    tokio::time::sleep(delay).await;
+                    Ok(event)
+                }
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn ticks(symbol: &str, seconds: u64) -> PacedFeed {
+        let symbol = SymbolId::intern(symbol).unwrap();
+        PacedFeed(
+            (1..=seconds)
+                .map(|second| {
+                    let event = MarketEvent::BookUpdate {
+                        symbol,
+                        timestamp_ns: second,
+                        normalized_ns: second,
+                        side: crate::core::events::Side::Bid,
+                        price: Px::from_int(100).unwrap(),
+                        size: Qty::from_int(1).unwrap(),
+                    };
+                    (Duration::from_secs(1), event)
+                })
+                .collect(),
+        )
+    }
+
+    fn watchdog() -> Arc<FeedWatchdog> {
+        let config = WatchdogConfig {
+            symbol_timeout: Duration::from_secs(10),
+            connection_timeout: Duration::from_secs(2),
+            check_interval: Duration::from_millis(500),
+        };
+        Arc::new(FeedWatchdog::new("paced", config).with_clock(Arc::new(TokioClock)))
+    }
+
+    async fn run(mut feed: WatchedFeed<PacedFeed>, symbols: &[&str]) {
+        let symbols: Vec<String> = symbols.iter().map(|symbol| symbol.to_string()).collect();
+        feed.subscribe(&symbols).await.unwrap();
+        while feed.next_event().await.is_ok() {}
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quiet_symbols_and_dead_connections_are_told_apart() {
+        let watchdog = watchdog();
+        let feed = watchdog.wrap(ticks("WATCH-ACTIVE", 15));
+        tokio::spawn(run(feed, &["WATCH-ACTIVE", "WATCH-QUIET"]));
+
+        let start = tokio::time::Instant::now();
+        let mut alerts = Vec::new();
+        while start.elapsed() < Duration::from_secs(25) {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            alerts.extend(
+                watchdog
+                    .check()
+                    .into_iter()
+                    .map(|alert| (start.elapsed(), alert)),
+            );
+        }
+
+        // The quiet symbol goes stale while the active one keeps the
+        // connection alive; only once everything stops is the connection
+        let quiet = SymbolId::lookup("WATCH-QUIET").unwrap();
+        assert_eq!(alerts.len(), 2, "{:?}", alerts);
+        let (at, alert) = alerts[0];
+        assert!(matches!(alert, StaleFeed::Symbol { symbol, .. } if symbol == quiet));
+        assert!(at >= Duration::from_secs(10) && at <= Duration::from_millis(10_500));
+        let (at, alert) = alerts[1];
+        assert!(matches!(
+            alert,
+            StaleFeed::Connection { venue: "paced", .. }
+        ));
+        assert!(at >= Duration::from_secs(17) && at <= Duration::from_millis(17_500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dead_connection_switches_to_the_safe_mode() {
+        let switcher = Arc::new(Mutex::new(ModeSwitcher::new(ExecutionMode::Live)));
+        let watchdog = Arc::new(
+            Arc::into_inner(watchdog())
+                .unwrap()
+                .with_safe_mode(Arc::clone(&switcher), ExecutionMode::Paper),
+        );
+        tokio::spawn(run(watchdog.wrap(ticks("WATCH-SAFE", 3)), &["WATCH-SAFE"]));
+        tokio::spawn(Arc::clone(&watchdog).monitor());
+
+        tokio::time::sleep(Duration::from_millis(4_500)).await;
+        assert_eq!(switcher.lock().current_mode(), ExecutionMode::Live);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(switcher.lock().current_mode(), ExecutionMode::Paper);
+    }
+}
@@ -0,0 +1,996 @@
+// Trade journal for ShrivenQ
+// Durable append-only record of every order action and fill, replayed on startup
+
+pub mod snapshot;
+
+pub use snapshot::{SnapshotConfig, SnapshotState, Snapshotter, WarmStart};
+
+use crate::core::book::OrderBook;
+use crate::core::orders::{Fill, Order, OrderId, OrderKind, OrderSide};
+use crate::core::portfolio::Portfolio;
+use crate::core::types::{Px, Qty, SymbolId};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Leading bytes of every segment; the last byte is the format version
+const MAGIC: &[u8; 8] = b"SQJRNL\0\x01";
+const SEGMENT_EXTENSION: &str = "journal";
+// Length and CRC32 of the payload
+const FRAME_HEADER: usize = 8;
+// A length past this is a torn or garbage header, not a record
+const MAX_RECORD_BYTES: u32 = 1 << 20;
+
+const TAG_SUBMIT: u8 = 0;
+const TAG_ACK: u8 = 1;
+const TAG_CANCEL: u8 = 2;
+const TAG_FILL: u8 = 3;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum JournalError {
+    #[error("Journal {path}: {reason}")]
+    Io { path: String, reason: String },
+    #[error("Journal {path} corrupt at byte {offset}: {reason}")]
+    Corrupt {
+        path: String,
+        offset: u64,
+        reason: String,
+    },
+    #[error("Snapshot {path} has format version {version}, this build reads up to {supported}")]
+    UnsupportedVersion {
+        path: String,
+        version: u8,
+        supported: u8,
+    },
+}
+
+/// One journalled order action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalRecord {
+    /// Order about to be sent to the venue
+    Submit(Order),
+    /// Venue accepted the order
+    Ack {
+        order_id: OrderId,
+        timestamp_ns: u64,
+    },
+    /// Venue confirmed the cancel
+    Cancel {
+        order_id: OrderId,
+        timestamp_ns: u64,
+    },
+    Fill(Fill),
+}
+
+/// Point in the journal just past a record: segment index and byte offset
+/// within it. Positions order the same way the records do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JournalPosition {
+    pub segment: u64,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalConfig {
+    /// Start a new segment once the current one would grow past this
+    pub max_segment_bytes: u64,
+    /// Longest time an appended record may sit unsynced; zero syncs every record
+    pub sync_interval: Duration,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            max_segment_bytes: 64 * 1024 * 1024,
+            sync_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Append-only journal in a directory of numbered segments.
+///
+/// Each record is framed as a little-endian `u32` payload length, the
+/// payload's CRC32 and the payload. Records are buffered and fsynced by the
+/// first append or [`Journal::sync_if_due`] call made `sync_interval` or
+/// more after the oldest unsynced one; [`SharedJournal`] makes that call on
+/// a timer. A crash loses at most the unsynced window and can leave the last
+/// record half-written. Opening an existing journal truncates such a torn
+/// tail before appending.
+#[derive(Debug)]
+pub struct Journal {
+    dir: PathBuf,
+    config: JournalConfig,
+    path: String,
+    writer: BufWriter<File>,
+    segment: u64,
+    segment_bytes: u64,
+    // When the oldest record not yet synced was appended
+    unsynced_since: Option<Instant>,
+    frame: Vec<u8>,
+    appended: u64,
+}
+
+impl Journal {
+    /// Open the journal in `dir`, creating it if needed, and continue the
+    /// newest segment
+    pub fn open(dir: impl AsRef<Path>, config: JournalConfig) -> Result<Self, JournalError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        let segments = list_segments(&dir)?;
+
+        let (segment, path, segment_bytes) = match segments.last() {
+            Some((index, path)) => {
+                let mut reader = SegmentReader::open(path, true)?;
+                while reader.next_record()?.is_some() {}
+                let valid = reader.valid_bytes();
+                if reader.torn_bytes() > 0 {
+                    warn!(
+                        "Journal {}: truncating {}-byte torn tail",
+                        path.display(),
+                        reader.torn_bytes()
+                    );
+                }
+                (*index, path.clone(), valid)
+            }
+            None => (1, segment_path(&dir, 1), 0),
+        };
+
+        let mut journal = Self {
+            path: path.display().to_string(),
+            writer: open_segment(&path, segment_bytes)?,
+            dir,
+            config,
+            segment,
+            segment_bytes: segment_bytes.max(MAGIC.len() as u64),
+            unsynced_since: None,
+            frame: Vec::with_capacity(128),
+            appended: 0,
+        };
+        journal.sync()?;
+        Ok(journal)
+    }
+
+    /// Records appended since opening
+    pub fn appended(&self) -> u64 {
+        self.appended
+    }
+
+    /// Segment currently written to
+    pub fn segment(&self) -> u64 {
+        self.segment
+    }
+
+    /// Position just past the last appended record, whether or not it has
+    /// been synced yet
+    pub fn position(&self) -> JournalPosition {
+        JournalPosition {
+            segment: self.segment,
+            offset: self.segment_bytes,
+        }
+    }
+
+    pub fn append(&mut self, record: &JournalRecord) -> Result<(), JournalError> {
+        self.frame.clear();
+        self.frame.extend_from_slice(&[0; FRAME_HEADER]);
+        encode(record, &mut self.frame);
+        let payload = &self.frame[FRAME_HEADER..];
+        let len = payload.len() as u32;
+        let crc = crc32fast::hash(payload);
+        self.frame[..4].copy_from_slice(&len.to_le_bytes());
+        self.frame[4..FRAME_HEADER].copy_from_slice(&crc.to_le_bytes());
+
+        let frame_bytes = self.frame.len() as u64;
+        if self.segment_bytes > MAGIC.len() as u64
+            && self.segment_bytes + frame_bytes > self.config.max_segment_bytes
+        {
+            self.rotate()?;
+        }
+
+        self.writer
+            .write_all(&self.frame)
+            .map_err(|e| io_error(&self.path, e))?;
+        self.segment_bytes += frame_bytes;
+        self.appended += 1;
+        self.unsynced_since.get_or_insert_with(Instant::now);
+        self.sync_if_due().map(|_| ())
+    }
+
+    /// Sync if the oldest unsynced record is `sync_interval` old; true if it did
+    pub fn sync_if_due(&mut self) -> Result<bool, JournalError> {
+        if self.sync_due_in() == Some(Duration::ZERO) {
+            self.sync()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Time until unsynced records are due, or `None` if everything is synced
+    pub fn sync_due_in(&self) -> Option<Duration> {
+        self.unsynced_since
+            .map(|since| self.config.sync_interval.saturating_sub(since.elapsed()))
+    }
+
+    /// Flush buffered records and fsync them to disk
+    pub fn sync(&mut self) -> Result<(), JournalError> {
+        self.writer
+            .flush()
+            .and_then(|()| self.writer.get_ref().sync_data())
+            .map_err(|e| io_error(&self.path, e))?;
+        self.unsynced_since = None;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), JournalError> {
+        self.sync()?;
+        let path = segment_path(&self.dir, self.segment + 1);
+        self.writer = open_segment(&path, 0)?;
+        self.segment += 1;
+        self.segment_bytes = MAGIC.len() as u64;
+        self.path = path.display().to_string();
+        info!("📓 Journal rotated to {}", self.path);
+        Ok(())
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        if self.unsynced_since.is_some()
+            && let Err(e) = self.sync()
+        {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Handle to a [`Journal`] shared by several writers, such as every gateway
+/// of an engine.
+///
+/// Unless `sync_interval` is zero, a background thread syncs records once
+/// they are due, so the interval bounds the unsynced window even when no
+/// further record is appended. The thread stops with the last handle.
+///
+/// It also keeps the state the journalled records add up to, updated under
+/// the journal's lock, so a snapshot taken through it always matches the
+/// journal position it is tagged with.
+#[derive(Debug, Clone)]
+pub struct SharedJournal {
+    journal: Arc<parking_lot::Mutex<Journal>>,
+    // Only locked with `journal` held
+    state: Arc<parking_lot::Mutex<Recovery>>,
+    _syncer: Option<Arc<Syncer>>,
+}
+
+impl SharedJournal {
+    pub fn new(journal: Journal) -> Result<Self, JournalError> {
+        let every_record = journal.config.sync_interval.is_zero();
+        let path = journal.path.clone();
+        let journal = Arc::new(parking_lot::Mutex::new(journal));
+        let syncer = if every_record {
+            None
+        } else {
+            Some(Arc::new(
+                Syncer::spawn(Arc::downgrade(&journal)).map_err(|e| io_error(&path, e))?,
+            ))
+        };
+        Ok(Self {
+            journal,
+            state: Arc::default(),
+            _syncer: syncer,
+        })
+    }
+
+    /// State the records already in the journal add up to, e.g. the one
+    /// recovered at startup; appended records are applied on top of it
+    pub fn with_state(self, state: Recovery) -> Self {
+        *self.state.lock() = state;
+        self
+    }
+
+    /// Exclusive access, e.g. to append or to snapshot at the current position
+    pub fn lock(&self) -> parking_lot::MutexGuard<'_, Journal> {
+        self.journal.lock()
+    }
+
+    pub fn append(&self, record: &JournalRecord) -> Result<(), JournalError> {
+        let mut journal = self.journal.lock();
+        journal.append(record)?;
+        self.state.lock().apply(record);
+        Ok(())
+    }
+
+    /// State as of the last record appended
+    pub fn state(&self) -> Recovery {
+        let _journal = self.journal.lock();
+        self.state.lock().clone()
+    }
+
+    /// Snapshot `books` with the journalled state at the current position
+    pub fn snapshot(
+        &self,
+        snapshotter: &mut Snapshotter,
+        books: &HashMap<SymbolId, OrderBook>,
+    ) -> Result<PathBuf, JournalError> {
+        let mut journal = self.journal.lock();
+        let state = SnapshotState {
+            books: books.clone(),
+            recovery: self.state.lock().clone(),
+        };
+        snapshotter.snapshot(&mut journal, &state)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SyncerSignal {
+    stopped: parking_lot::Mutex<bool>,
+    wake: parking_lot::Condvar,
+}
+
+impl SyncerSignal {
+    // Sleep for `timeout` or until stopped; true once stopped
+    fn wait(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut stopped = self.stopped.lock();
+        while !*stopped {
+            if self.wake.wait_until(&mut stopped, deadline).timed_out() {
+                break;
+            }
+        }
+        *stopped
+    }
+
+    fn stop(&self) {
+        *self.stopped.lock() = true;
+        self.wake.notify_all();
+    }
+}
+
+#[derive(Debug)]
+struct Syncer {
+    signal: Arc<SyncerSignal>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Syncer {
+    fn spawn(journal: Weak<parking_lot::Mutex<Journal>>) -> std::io::Result<Self> {
+        let signal = Arc::new(SyncerSignal::default());
+        let handle = std::thread::Builder::new()
+            .name("shriven-journal-sync".into())
+            .spawn({
+                let signal = Arc::clone(&signal);
+                move || {
+                    loop {
+                        let Some(journal) = journal.upgrade() else {
+                            break;
+                        };
+                        let wait = {
+                            let mut journal = journal.lock();
+                            if let Err(e) = journal.sync_if_due() {
+                                warn!("{}", e);
+                            }
+                            journal
+                                .sync_due_in()
+                                .unwrap_or(journal.config.sync_interval)
+                        };
+                        drop(journal);
+                        if signal.wait(wait) {
+                            break;
+                        }
+                    }
+                }
+            })?;
+        Ok(Self {
+            signal,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Syncer {
+    fn drop(&mut self) {
+        self.signal.stop();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// State rebuilt by replaying a journal
+#[derive(Debug, Clone, Default)]
+pub struct Recovery {
+    pub portfolio: Portfolio,
+    /// Submitted orders not yet cancelled or fully filled, with their
+    /// unfilled quantity
+    pub open_orders: HashMap<OrderId, Qty>,
+    /// Highest order id seen, so new ids can continue past it
+    pub last_order_id: Option<OrderId>,
+    pub records: u64,
+    /// Bytes of a half-written final record that were ignored
+    pub torn_bytes: u64,
+}
+
+impl Recovery {
+    pub fn apply(&mut self, record: &JournalRecord) {
+        self.records += 1;
+        match record {
+            JournalRecord::Submit(order) => {
+                self.open_orders.insert(order.id, order.qty);
+                self.last_order_id = self.last_order_id.max(Some(order.id));
+            }
+            JournalRecord::Ack { .. } => {}
+            JournalRecord::Cancel { order_id, .. } => {
+                self.open_orders.remove(order_id);
+            }
+            JournalRecord::Fill(fill) => {
+                self.portfolio.apply_fill(fill);
+                if let Some(remaining) = self.open_orders.get_mut(&fill.order_id) {
+                    *remaining = remaining.checked_sub(fill.qty).unwrap_or(Qty::ZERO);
+                    if *remaining <= Qty::ZERO {
+                        self.open_orders.remove(&fill.order_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replay every intact record in `dir`, oldest first. A missing directory
+/// recovers to an empty state.
+pub fn recover(dir: impl AsRef<Path>) -> Result<Recovery, JournalError> {
+    let mut recovery = Recovery::default();
+    let mut reader = JournalReader::open(dir)?;
+    while let Some(record) = reader.next_record()? {
+        recovery.apply(&record);
+    }
+    recovery.torn_bytes = reader.torn_bytes();
+    if recovery.records > 0 {
+        info!(
+            "📓 Recovered {} journal records ({} open orders)",
+            recovery.records,
+            recovery.open_orders.len()
+        );
+    }
+    if recovery.torn_bytes > 0 {
+        warn!(
+            "Journal ended with a {}-byte torn record; ignored",
+            recovery.torn_bytes
+        );
+    }
+    Ok(recovery)
+}
+
+/// Reads a journal's records across all of its segments.
+///
+/// Only the newest segment may end in a torn record, which is skipped and
+/// counted; damage anywhere else is reported as corruption.
+#[derive(Debug)]
+pub struct JournalReader {
+    segments: std::vec::IntoIter<(u64, PathBuf)>,
+    current: Option<SegmentReader>,
+    torn_bytes: u64,
+}
+
+impl JournalReader {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, JournalError> {
+        let dir = dir.as_ref();
+        let segments = if dir.exists() {
+            list_segments(dir)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            segments: segments.into_iter(),
+            current: None,
+            torn_bytes: 0,
+        })
+    }
+
+    /// Reader over the records after `position`, as returned by
+    /// [`Journal::position`]. Segments before it may already be gone; the
+    /// segment it points into must still hold at least `offset` bytes.
+    pub fn open_from(
+        dir: impl AsRef<Path>,
+        position: JournalPosition,
+    ) -> Result<Self, JournalError> {
+        let dir = dir.as_ref();
+        let mut reader = Self::open(dir)?;
+        let mut segments: Vec<_> = std::mem::take(&mut reader.segments)
+            .skip_while(|(index, _)| *index < position.segment)
+            .collect();
+
+        // Nothing but the header written yet: the position is the journal start
+        let at_start = position.offset <= MAGIC.len() as u64;
+        match segments.first() {
+            Some((index, path)) if *index == position.segment => {
+                let mut segment = SegmentReader::open(path, segments.len() == 1)?;
+                segment.skip_to(position.offset)?;
+                reader.current = Some(segment);
+                let _ = segments.remove(0);
+            }
+            _ if at_start => {}
+            _ => {
+                return Err(JournalError::Corrupt {
+                    path: segment_path(dir, position.segment).display().to_string(),
+                    offset: position.offset,
+                    reason: "segment holding the resume position is missing".to_string(),
+                });
+            }
+        }
+        reader.segments = segments.into_iter();
+        Ok(reader)
+    }
+
+    /// Next intact record, or `None` once every segment is exhausted
+    pub fn next_record(&mut self) -> Result<Option<JournalRecord>, JournalError> {
+        loop {
+            if let Some(segment) = &mut self.current {
+                if let Some(record) = segment.next_record()? {
+                    return Ok(Some(record));
+                }
+                self.torn_bytes += segment.torn_bytes();
+                self.current = None;
+            }
+            let Some((_, path)) = self.segments.next() else {
+                return Ok(None);
+            };
+            let last = self.segments.len() == 0;
+            self.current = Some(SegmentReader::open(&path, last)?);
+        }
+    }
+
+    /// Size of the torn final record skipped so far
+    pub fn torn_bytes(&self) -> u64 {
+        self.torn_bytes
+    }
+}
+
+#[derive(Debug)]
+struct SegmentReader {
+    path: String,
+    reader: BufReader<File>,
+    len: u64,
+    offset: u64,
+    // Only the newest segment may legitimately end mid-record
+    allow_torn: bool,
+    torn_bytes: u64,
+    payload: Vec<u8>,
+}
+
+impl SegmentReader {
+    fn open(path: &Path, allow_torn: bool) -> Result<Self, JournalError> {
+        let file = File::open(path).map_err(|e| io_error(path, e))?;
+        let len = file.metadata().map_err(|e| io_error(path, e))?.len();
+        let mut segment = Self {
+            path: path.display().to_string(),
+            reader: BufReader::new(file),
+            len,
+            offset: 0,
+            allow_torn,
+            torn_bytes: 0,
+            payload: Vec::with_capacity(128),
+        };
+
+        if len < MAGIC.len() as u64 {
+            // Crashed right after creating the segment
+            segment.torn(0, "segment header incomplete")?;
+            return Ok(segment);
+        }
+        let mut magic = [0u8; MAGIC.len()];
+        segment
+            .reader
+            .read_exact(&mut magic)
+            .map_err(|e| io_error(path, e))?;
+        if &magic != MAGIC {
+            return Err(segment.corrupt(0, "not a journal segment"));
+        }
+        segment.offset = MAGIC.len() as u64;
+        Ok(segment)
+    }
+
+    fn next_record(&mut self) -> Result<Option<JournalRecord>, JournalError> {
+        let start = self.offset;
+        let remaining = self.len.saturating_sub(start);
+        if remaining == 0 || self.torn_bytes > 0 {
+            return Ok(None);
+        }
+        if remaining < FRAME_HEADER as u64 {
+            self.torn(start, "record header incomplete")?;
+            return Ok(None);
+        }
+
+        let mut header = [0u8; FRAME_HEADER];
+        self.read(&mut header)?;
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let end = start + FRAME_HEADER as u64 + u64::from(len);
+        if len > MAX_RECORD_BYTES || end > self.len {
+            self.torn(start, "record extends past end of segment")?;
+            return Ok(None);
+        }
+
+        let mut payload = std::mem::take(&mut self.payload);
+        payload.resize(len as usize, 0);
+        let read = self.read(&mut payload);
+        self.payload = payload;
+        read?;
+        if crc32fast::hash(&self.payload) != crc {
+            if end == self.len {
+                self.torn(start, "checksum mismatch in final record")?;
+                return Ok(None);
+            }
+            return Err(self.corrupt(start, "checksum mismatch"));
+        }
+
+        self.offset = end;
+        decode(&self.payload)
+            .map(Some)
+            .map_err(|reason| self.corrupt(start, &reason))
+    }
+
+    // Resume at `offset`, the end of a record this segment is known to hold
+    fn skip_to(&mut self, offset: u64) -> Result<(), JournalError> {
+        if offset <= self.offset {
+            return Ok(());
+        }
+        if offset > self.len {
+            return Err(self.corrupt(self.len, "segment ends before the resume position"));
+        }
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| io_error(&self.path, e))?;
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Bytes up to the end of the last intact record
+    fn valid_bytes(&self) -> u64 {
+        self.len - self.torn_bytes
+    }
+
+    fn torn_bytes(&self) -> u64 {
+        self.torn_bytes
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), JournalError> {
+        self.reader
+            .read_exact(buf)
+            .map_err(|e| io_error(&self.path, e))
+    }
+
+    fn torn(&mut self, offset: u64, reason: &str) -> Result<(), JournalError> {
+        if !self.allow_torn {
+            return Err(self.corrupt(offset, reason));
+        }
+        self.torn_bytes = self.len - offset;
+        Ok(())
+    }
+
+    fn corrupt(&self, offset: u64, reason: &str) -> JournalError {
+        JournalError::Corrupt {
+            path: self.path.clone(),
+            offset,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+fn segment_path(dir: &Path, index: u64) -> PathBuf {
+    dir.join(format!("{:010}.{}", index, SEGMENT_EXTENSION))
+}
+
+// Segments in `dir` in index order
+fn list_segments(dir: &Path) -> Result<Vec<(u64, PathBuf)>, JournalError> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        let path = entry.map_err(|e| io_error(dir, e))?.path();
+        if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION)
+            && let Some(index) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+        {
+            segments.push((index, path));
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+// Open `path` for appending after its first `valid_bytes`, dropping anything
+// past them; a new or headerless segment gets a fresh header
+fn open_segment(path: &Path, valid_bytes: u64) -> Result<BufWriter<File>, JournalError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| io_error(path, e))?;
+    let header_ok = valid_bytes >= MAGIC.len() as u64;
+    file.set_len(if header_ok { valid_bytes } else { 0 })
+        .map_err(|e| io_error(path, e))?;
+    let mut writer = BufWriter::new(file);
+    if !header_ok {
+        writer.write_all(MAGIC).map_err(|e| io_error(path, e))?;
+    }
+    Ok(writer)
+}
+
+fn io_error(path: impl AsRef<Path>, error: std::io::Error) -> JournalError {
+    let reason = match error.kind() {
+        ErrorKind::UnexpectedEof => "unexpected end of file".to_string(),
+        _ => error.to_string(),
+    };
+    JournalError::Io {
+        path: path.as_ref().display().to_string(),
+        reason,
+    }
+}
+
+fn encode(record: &JournalRecord, out: &mut Vec<u8>) {
+    match record {
+        JournalRecord::Submit(order) => {
+            out.push(TAG_SUBMIT);
+            put_symbol(out, order.symbol.as_str());
+            put_u64(out, order.timestamp_ns);
+            put_u64(out, order.id);
+            out.push(matches!(order.side, OrderSide::Sell) as u8);
+            match order.kind {
+                OrderKind::Market => out.push(0),
+                OrderKind::Limit(price) => {
+                    out.push(1);
+                    put_i64(out, price.raw());
+                }
+            }
+            put_i64(out, order.qty.raw());
+        }
+        JournalRecord::Ack {
+            order_id,
+            timestamp_ns,
+        } => {
+            out.push(TAG_ACK);
+            put_u64(out, *timestamp_ns);
+            put_u64(out, *order_id);
+        }
+        JournalRecord::Cancel {
+            order_id,
+            timestamp_ns,
+        } => {
+            out.push(TAG_CANCEL);
+            put_u64(out, *timestamp_ns);
+            put_u64(out, *order_id);
+        }
+        JournalRecord::Fill(fill) => {
+            out.push(TAG_FILL);
+            put_symbol(out, fill.symbol.as_str());
+            put_u64(out, fill.timestamp_ns);
+            put_u64(out, fill.order_id);
+            out.push(matches!(fill.side, OrderSide::Sell) as u8);
+            put_i64(out, fill.price.raw());
+            put_i64(out, fill.qty.raw());
+        }
+    }
+}
+
+fn decode(payload: &[u8]) -> Result<JournalRecord, String> {
+    let mut cursor = Cursor {
+        bytes: payload,
+        at: 0,
+    };
+    let record = match cursor.u8()? {
+        TAG_SUBMIT => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            let id = cursor.u64()?;
+            let side = cursor.side()?;
+            let kind = match cursor.u8()? {
+                0 => OrderKind::Market,
+                _ => OrderKind::Limit(Px::from_raw(cursor.i64()?)),
+            };
+            JournalRecord::Submit(Order {
+                id,
+                symbol,
+                side,
+                kind,
+                qty: Qty::from_raw(cursor.i64()?),
+                timestamp_ns,
+            })
+        }
+        TAG_ACK => {
+            let timestamp_ns = cursor.u64()?;
+            JournalRecord::Ack {
+                order_id: cursor.u64()?,
+                timestamp_ns,
+            }
+        }
+        TAG_CANCEL => {
+            let timestamp_ns = cursor.u64()?;
+            JournalRecord::Cancel {
+                order_id: cursor.u64()?,
+                timestamp_ns,
+            }
+        }
+        TAG_FILL => {
+            let symbol = cursor.symbol()?;
+            let timestamp_ns = cursor.u64()?;
+            JournalRecord::Fill(Fill {
+                order_id: cursor.u64()?,
+                symbol,
+                side: cursor.side()?,
+                price: Px::from_raw(cursor.i64()?),
+                qty: Qty::from_raw(cursor.i64()?),
+                timestamp_ns,
+            })
+        }
+        tag => return Err(format!("unknown record tag {}", tag)),
+    };
+    Ok(record)
+}
+
+fn put_symbol(out: &mut Vec<u8>, symbol: &str) {
+    let bytes = &symbol.as_bytes()[..symbol.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_i64(out: &mut Vec<u8>, value: i64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.at..self.at + len)
+            .ok_or_else(|| format!("record truncated at byte {}", self.at))?;
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        self.array().map(i64::from_le_bytes)
+    }
+
+    fn side(&mut self) -> Result<OrderSide, String> {
+        Ok(if self.u8()? == 0 {
+            OrderSide::Buy
+        } else {
+            OrderSide::Sell
+        })
+    }
+
+    fn symbol(&mut self) -> Result<SymbolId, String> {
+        let len = u16::from_le_bytes(self.array()?) as usize;
+        let name = std::str::from_utf8(self.take(len)?)
+            .map_err(|e| format!("symbol is not UTF-8: {}", e))?;
+        SymbolId::intern(name).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-journal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn fill(order_id: OrderId, qty: i64) -> JournalRecord {
+        JournalRecord::Fill(Fill {
+            order_id,
+            symbol: SymbolId::intern("JRNLTEST").unwrap(),
+            side: OrderSide::Buy,
+            price: Px::from_int(100).unwrap(),
+            qty: Qty::from_int(qty).unwrap(),
+            timestamp_ns: order_id,
+        })
+    }
+
+    #[test]
+    fn torn_tail_is_skipped_then_truncated() {
+        let dir = temp_dir("torn");
+        let mut journal = Journal::open(&dir, JournalConfig::default()).unwrap();
+        for id in 1..=3 {
+            journal.append(&fill(id, 1)).unwrap();
+        }
+        drop(journal);
+
+        // Crash halfway through writing the third record
+        let segment = segment_path(&dir, 1);
+        let len = fs::metadata(&segment).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&segment).unwrap();
+        file.set_len(len - 5).unwrap();
+        drop(file);
+
+        let recovery = recover(&dir).unwrap();
+        assert_eq!(recovery.records, 2);
+        assert!(recovery.torn_bytes > 0);
+        let symbol = SymbolId::intern("JRNLTEST").unwrap();
+        assert_eq!(
+            recovery.portfolio.net_qty(symbol),
+            Qty::from_int(2).unwrap()
+        );
+
+        // Reopening drops the torn bytes, so new records follow intact ones
+        let mut journal = Journal::open(&dir, JournalConfig::default()).unwrap();
+        journal.append(&fill(4, 1)).unwrap();
+        drop(journal);
+        let recovery = recover(&dir).unwrap();
+        assert_eq!(recovery.records, 3);
+        assert_eq!(recovery.torn_bytes, 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn idle_journal_syncs_once_interval_passes() {
+        let dir = temp_dir("idle");
+        let config = JournalConfig {
+            sync_interval: Duration::from_millis(20),
+            ..JournalConfig::default()
+        };
+        let journal = SharedJournal::new(Journal::open(&dir, config).unwrap()).unwrap();
+        journal.append(&fill(1, 1)).unwrap();
+        assert!(journal.lock().sync_due_in().is_some());
+
+        // No further appends: the background thread has to sync it
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while journal.lock().sync_due_in().is_some() {
+            assert!(Instant::now() < deadline, "record never synced");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(recover(&dir).unwrap().records, 1);
+        drop(journal);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn append_syncs_when_interval_is_zero() {
+        let dir = temp_dir("zero");
+        let config = JournalConfig {
+            sync_interval: Duration::ZERO,
+            ..JournalConfig::default()
+        };
+        let journal = SharedJournal::new(Journal::open(&dir, config).unwrap()).unwrap();
+        journal.append(&fill(1, 1)).unwrap();
+        assert_eq!(journal.lock().sync_due_in(), None);
+        drop(journal);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
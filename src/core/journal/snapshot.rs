@@ -0,0 +1,564 @@
+// Warm start snapshots for ShrivenQ
+// Periodic binary images of books, positions and symbols, resumed from the journal tail
+
+use super::{
+    Cursor, Journal, JournalError, JournalPosition, JournalReader, Recovery, io_error, put_i64,
+    put_symbol, put_u32, put_u64,
+};
+use crate::core::book::OrderBook;
+use crate::core::events::Side;
+use crate::core::portfolio::Position;
+use crate::core::types::{Px, Qty, SymbolId, SymbolRegistry};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Leading bytes of every snapshot; the last byte is the format version
+const MAGIC: &[u8; 8] = b"SQSNAP\0\x01";
+/// Newest format this build reads and the one it writes
+pub const SNAPSHOT_VERSION: u8 = 1;
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+// Payload length and CRC32 after the magic
+const HEADER: usize = MAGIC.len() + 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotConfig {
+    /// Time between snapshots taken by [`Snapshotter::maybe_snapshot`]
+    pub interval: Duration,
+    /// Snapshots kept on disk; older ones are deleted after each write
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            keep: 2,
+        }
+    }
+}
+
+/// Everything a snapshot restores: the local books and the state replayed
+/// from the journal
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotState {
+    pub books: HashMap<SymbolId, OrderBook>,
+    pub recovery: Recovery,
+}
+
+/// Outcome of [`restore`]
+#[derive(Debug, Clone, Default)]
+pub struct WarmStart {
+    pub state: SnapshotState,
+    /// Snapshot the state started from; `None` means the whole journal was replayed
+    pub snapshot: Option<PathBuf>,
+    /// Journal records replayed on top of the snapshot
+    pub replayed: u64,
+}
+
+/// Writes a snapshot every `interval`, tagged with the journal position it
+/// corresponds to.
+///
+/// The state passed in must reflect exactly the records journalled so far:
+/// take the snapshot from the thread that applies fills and appends them,
+/// between records, so a restore neither loses nor replays one twice.
+#[derive(Debug)]
+pub struct Snapshotter {
+    dir: PathBuf,
+    config: SnapshotConfig,
+    last: Instant,
+}
+
+impl Snapshotter {
+    pub fn new(dir: impl AsRef<Path>, config: SnapshotConfig) -> Result<Self, JournalError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        Ok(Self {
+            dir,
+            config,
+            last: Instant::now(),
+        })
+    }
+
+    /// Whether `interval` has passed since the last snapshot
+    pub fn due(&self) -> bool {
+        self.last.elapsed() >= self.config.interval
+    }
+
+    /// Snapshot if one is due
+    pub fn maybe_snapshot(
+        &mut self,
+        journal: &mut Journal,
+        state: &SnapshotState,
+    ) -> Result<Option<PathBuf>, JournalError> {
+        if !self.due() {
+            return Ok(None);
+        }
+        self.snapshot(journal, state).map(Some)
+    }
+
+    /// Sync the journal and write `state` as of its current position
+    pub fn snapshot(
+        &mut self,
+        journal: &mut Journal,
+        state: &SnapshotState,
+    ) -> Result<PathBuf, JournalError> {
+        journal.sync()?;
+        let path = write(&self.dir, journal.position(), state)?;
+        self.last = Instant::now();
+        prune(&self.dir, self.config.keep.max(1))?;
+        Ok(path)
+    }
+}
+
+/// Write `state` to a new snapshot in `dir` for journal `position`. The file
+/// appears under its final name only once fully written and synced.
+pub fn write(
+    dir: impl AsRef<Path>,
+    position: JournalPosition,
+    state: &SnapshotState,
+) -> Result<PathBuf, JournalError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+    let payload = encode(position, state);
+    let mut bytes = Vec::with_capacity(HEADER + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    put_u64(&mut bytes, payload.len() as u64);
+    put_u32(&mut bytes, crc32fast::hash(&payload));
+    bytes.extend_from_slice(&payload);
+
+    let path = snapshot_path(dir, position);
+    let partial = path.with_extension("partial");
+    let mut file = File::create(&partial).map_err(|e| io_error(&partial, e))?;
+    file.write_all(&bytes)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| io_error(&partial, e))?;
+    fs::rename(&partial, &path).map_err(|e| io_error(&path, e))?;
+    // The rename itself must reach disk before older snapshots are pruned
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| io_error(dir, e))?;
+
+    info!(
+        "📸 Snapshot {} ({} books, {} positions, {} bytes)",
+        path.display(),
+        state.books.len(),
+        state.recovery.portfolio.positions().count(),
+        bytes.len()
+    );
+    Ok(path)
+}
+
+/// Newest snapshot in `dir` that reads back intact, with its journal
+/// position. Damaged snapshots are skipped with a warning, falling back to
+/// older ones; a snapshot from a newer format version is an error.
+pub fn load_latest(
+    dir: impl AsRef<Path>,
+) -> Result<Option<(PathBuf, JournalPosition, SnapshotState)>, JournalError> {
+    for path in list_snapshots(dir.as_ref())?.into_iter().rev() {
+        match load(&path) {
+            Ok((position, state)) => return Ok(Some((path, position, state))),
+            Err(e @ JournalError::UnsupportedVersion { .. }) => return Err(e),
+            Err(e) => warn!("Skipping snapshot: {}", e),
+        }
+    }
+    Ok(None)
+}
+
+/// Read one snapshot file
+pub fn load(path: impl AsRef<Path>) -> Result<(JournalPosition, SnapshotState), JournalError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| io_error(path, e))?;
+    let corrupt = |offset: usize, reason: &str| JournalError::Corrupt {
+        path: path.display().to_string(),
+        offset: offset as u64,
+        reason: reason.to_string(),
+    };
+
+    if bytes.len() < HEADER || bytes[..MAGIC.len() - 1] != MAGIC[..MAGIC.len() - 1] {
+        return Err(corrupt(0, "not a snapshot"));
+    }
+    let version = bytes[MAGIC.len() - 1];
+    if version > SNAPSHOT_VERSION {
+        return Err(JournalError::UnsupportedVersion {
+            path: path.display().to_string(),
+            version,
+            supported: SNAPSHOT_VERSION,
+        });
+    }
+
+    let mut header = Cursor {
+        bytes: &bytes[MAGIC.len()..HEADER],
+        at: 0,
+    };
+    let len = header.u64().map_err(|e| corrupt(MAGIC.len(), &e))?;
+    let crc = header.u32().map_err(|e| corrupt(MAGIC.len(), &e))?;
+    let payload = &bytes[HEADER..];
+    if payload.len() as u64 != len {
+        return Err(corrupt(HEADER, "payload length does not match header"));
+    }
+    if crc32fast::hash(payload) != crc {
+        return Err(corrupt(HEADER, "checksum mismatch"));
+    }
+    decode(payload).map_err(|reason| corrupt(HEADER, &reason))
+}
+
+/// Rebuild state from the newest snapshot in `snapshot_dir`, then replay
+/// the journal records written after it. Without a usable snapshot the whole
+/// journal is replayed.
+///
+/// The journal holds order actions and fills only, so books come back as
+/// they were at the snapshot; the live feed brings them current.
+pub fn restore(
+    snapshot_dir: impl AsRef<Path>,
+    journal_dir: impl AsRef<Path>,
+) -> Result<WarmStart, JournalError> {
+    let journal_dir = journal_dir.as_ref();
+    let snapshot_dir = snapshot_dir.as_ref();
+    let latest = if snapshot_dir.exists() {
+        load_latest(snapshot_dir)?
+    } else {
+        None
+    };
+
+    let (snapshot, mut state, mut reader) = match latest {
+        Some((path, position, state)) => {
+            let reader = JournalReader::open_from(journal_dir, position)?;
+            (Some(path), state, reader)
+        }
+        None => (
+            None,
+            SnapshotState::default(),
+            JournalReader::open(journal_dir)?,
+        ),
+    };
+
+    let mut replayed = 0;
+    while let Some(record) = reader.next_record()? {
+        state.recovery.apply(&record);
+        replayed += 1;
+    }
+    state.recovery.torn_bytes = reader.torn_bytes();
+
+    match &snapshot {
+        Some(path) => info!(
+            "📸 Warm start from {} plus {} journal records",
+            path.display(),
+            replayed
+        ),
+        None if replayed > 0 => info!("📓 Cold start replayed {} journal records", replayed),
+        None => {}
+    }
+    Ok(WarmStart {
+        state,
+        snapshot,
+        replayed,
+    })
+}
+
+fn snapshot_path(dir: &Path, position: JournalPosition) -> PathBuf {
+    dir.join(format!(
+        "{:010}-{:020}.{}",
+        position.segment, position.offset, SNAPSHOT_EXTENSION
+    ))
+}
+
+// Snapshots in `dir`, oldest first; names sort in journal position order
+fn list_snapshots(dir: &Path) -> Result<Vec<PathBuf>, JournalError> {
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        let path = entry.map_err(|e| io_error(dir, e))?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == SNAPSHOT_EXTENSION)
+        {
+            snapshots.push(path);
+        }
+    }
+    snapshots.sort_unstable();
+    Ok(snapshots)
+}
+
+fn prune(dir: &Path, keep: usize) -> Result<(), JournalError> {
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        fs::remove_file(path).map_err(|e| io_error(path, e))?;
+    }
+    Ok(())
+}
+
+// Payload layout, all integers little-endian. Every collection is written in
+// a fixed order, so equal states encode to identical bytes.
+//   journal position    segment u64, offset u64
+//   symbols             count u32, then each name in id order
+//   last order id       present u8, id u64
+//   records             u64
+//   open orders         count u32, then (id u64, remaining qty i64) by id
+//   positions           count u32, then (symbol u32, qty, avg price,
+//                       realized pnl i64, mark present u8, mark i64) by symbol
+//   books               count u32, then (symbol u32, last update u64,
+//                       bid count u32, bids (px, qty i64) best first,
+//                       ask count u32, asks likewise) by symbol
+// Symbols are referenced by their index in the symbol table, so a restore
+// into a registry that already assigned other ids still lines up.
+fn encode(position: JournalPosition, state: &SnapshotState) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4096);
+    put_u64(&mut out, position.segment);
+    put_u64(&mut out, position.offset);
+
+    let symbols: Vec<_> = SymbolRegistry::global().iter().collect();
+    put_u32(&mut out, symbols.len() as u32);
+    for (_, name) in symbols {
+        put_symbol(&mut out, name);
+    }
+
+    let recovery = &state.recovery;
+    out.push(u8::from(recovery.last_order_id.is_some()));
+    put_u64(&mut out, recovery.last_order_id.unwrap_or(0));
+    put_u64(&mut out, recovery.records);
+
+    let mut open_orders: Vec<_> = recovery.open_orders.iter().collect();
+    open_orders.sort_unstable_by_key(|(id, _)| **id);
+    put_u32(&mut out, open_orders.len() as u32);
+    for (id, remaining) in open_orders {
+        put_u64(&mut out, *id);
+        put_i64(&mut out, remaining.raw());
+    }
+
+    let mut positions: Vec<_> = recovery.portfolio.positions().collect();
+    positions.sort_unstable_by_key(|(symbol, _)| *symbol);
+    put_u32(&mut out, positions.len() as u32);
+    for (symbol, position) in positions {
+        put_u32(&mut out, symbol.index() as u32);
+        put_i64(&mut out, position.qty.raw());
+        put_i64(&mut out, position.avg_price.raw());
+        put_i64(&mut out, position.realized_pnl.raw());
+        out.push(u8::from(position.mark.is_some()));
+        put_i64(&mut out, position.mark.map_or(0, Px::raw));
+    }
+
+    let mut books: Vec<_> = state.books.values().collect();
+    books.sort_unstable_by_key(|book| book.symbol());
+    put_u32(&mut out, books.len() as u32);
+    for book in books {
+        put_u32(&mut out, book.symbol().index() as u32);
+        put_u64(&mut out, book.last_update_ns());
+        for side in [Side::Bid, Side::Ask] {
+            put_u32(&mut out, book.level_count(side) as u32);
+            for (px, qty) in book.levels(side) {
+                put_i64(&mut out, px.raw());
+                put_i64(&mut out, qty.raw());
+            }
+        }
+    }
+    out
+}
+
+fn decode(payload: &[u8]) -> Result<(JournalPosition, SnapshotState), String> {
+    let mut cursor = Cursor {
+        bytes: payload,
+        at: 0,
+    };
+    let position = JournalPosition {
+        segment: cursor.u64()?,
+        offset: cursor.u64()?,
+    };
+
+    // Interning in the saved order gives every symbol its old id in a fresh process
+    let symbols = (0..cursor.u32()?)
+        .map(|_| cursor.symbol())
+        .collect::<Result<Vec<_>, _>>()?;
+    let symbol = |index: u32| {
+        symbols
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| format!("symbol index {} past the symbol table", index))
+    };
+
+    let mut state = SnapshotState::default();
+    let recovery = &mut state.recovery;
+    let has_last_order = cursor.u8()? != 0;
+    let last_order_id = cursor.u64()?;
+    recovery.last_order_id = has_last_order.then_some(last_order_id);
+    recovery.records = cursor.u64()?;
+
+    for _ in 0..cursor.u32()? {
+        let id = cursor.u64()?;
+        recovery
+            .open_orders
+            .insert(id, Qty::from_raw(cursor.i64()?));
+    }
+
+    for _ in 0..cursor.u32()? {
+        let symbol = symbol(cursor.u32()?)?;
+        let qty = Qty::from_raw(cursor.i64()?);
+        let avg_price = Px::from_raw(cursor.i64()?);
+        let realized_pnl = Px::from_raw(cursor.i64()?);
+        let has_mark = cursor.u8()? != 0;
+        let mark = Px::from_raw(cursor.i64()?);
+        recovery.portfolio.restore_position(
+            symbol,
+            Position {
+                qty,
+                avg_price,
+                realized_pnl,
+                mark: has_mark.then_some(mark),
+            },
+        );
+    }
+
+    for _ in 0..cursor.u32()? {
+        let symbol = symbol(cursor.u32()?)?;
+        let mut book = OrderBook::new(symbol);
+        let last_update_ns = cursor.u64()?;
+        for side in [Side::Bid, Side::Ask] {
+            for _ in 0..cursor.u32()? {
+                let px = Px::from_raw(cursor.i64()?);
+                let qty = Qty::from_raw(cursor.i64()?);
+                book.apply_level(side, px, qty)
+                    .map_err(|e| format!("{} book: {}", symbol, e))?;
+            }
+        }
+        book.set_last_update_ns(last_update_ns);
+        state.books.insert(symbol, book);
+    }
+
+    if cursor.at != payload.len() {
+        return Err(format!(
+            "{} trailing bytes after the snapshot",
+            payload.len() - cursor.at
+        ));
+    }
+    Ok((position, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::journal::{JournalConfig, JournalRecord, SharedJournal};
+    use crate::core::orders::{Fill, Order, OrderKind, OrderSide};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-snapshot-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn px(value: i64) -> Px {
+        Px::from_int(value).unwrap()
+    }
+
+    fn qty(value: i64) -> Qty {
+        Qty::from_int(value).unwrap()
+    }
+
+    fn submit(id: u64, symbol: SymbolId) -> JournalRecord {
+        JournalRecord::Submit(Order {
+            id,
+            symbol,
+            side: OrderSide::Buy,
+            kind: OrderKind::Limit(px(100)),
+            qty: qty(5),
+            timestamp_ns: id,
+        })
+    }
+
+    fn state() -> SnapshotState {
+        let symbol = SymbolId::intern("SNAPTEST").unwrap();
+        let mut book = OrderBook::new(symbol);
+        book.apply_level(Side::Bid, px(99), qty(3)).unwrap();
+        book.apply_level(Side::Bid, px(98), qty(7)).unwrap();
+        book.apply_level(Side::Ask, px(101), qty(2)).unwrap();
+
+        let mut recovery = Recovery::default();
+        recovery.apply(&submit(4, symbol));
+        recovery.apply(&submit(9, symbol));
+        recovery.apply(&JournalRecord::Fill(Fill {
+            order_id: 4,
+            symbol,
+            side: OrderSide::Buy,
+            price: px(100),
+            qty: qty(2),
+            timestamp_ns: 10,
+        }));
+        recovery.portfolio.mark(symbol, px(101));
+        SnapshotState {
+            books: HashMap::from([(symbol, book)]),
+            recovery,
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_bit_for_bit() {
+        let dir = temp_dir("round-trip");
+        let position = JournalPosition {
+            segment: 3,
+            offset: 1234,
+        };
+        let original = state();
+
+        // The symbol table is global, so retry if a concurrent test interns
+        // a symbol between the two writes
+        let (first, second) = loop {
+            let symbols = SymbolRegistry::global().len();
+            let first = write(dir.join("first"), position, &original).unwrap();
+            let (loaded_position, loaded) = load(&first).unwrap();
+            assert_eq!(loaded_position, position);
+            let second = write(dir.join("second"), loaded_position, &loaded).unwrap();
+            if SymbolRegistry::global().len() == symbols {
+                break (first, second);
+            }
+        };
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let (_, loaded) = load(&second).unwrap();
+        let recovery = &loaded.recovery;
+        assert_eq!(recovery.last_order_id, original.recovery.last_order_id);
+        assert_eq!(recovery.open_orders, original.recovery.open_orders);
+        assert_eq!(recovery.records, 3);
+        let symbol = SymbolId::lookup("SNAPTEST").unwrap();
+        let position = recovery.portfolio.position(symbol).unwrap();
+        assert_eq!((position.qty, position.mark), (qty(2), Some(px(101))));
+        let book = &loaded.books[&symbol];
+        assert_eq!(
+            book.levels(Side::Bid).collect::<Vec<_>>(),
+            vec![(px(99), qty(3)), (px(98), qty(7))]
+        );
+        assert_eq!(book.best_ask(), Some((px(101), qty(2))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restore_replays_only_the_journal_after_the_snapshot() {
+        let dir = temp_dir("restore");
+        let (journal_dir, snapshot_dir) = (dir.join("journal"), dir.join("snapshots"));
+        let symbol = SymbolId::intern("SNAPTEST").unwrap();
+        let original = state();
+
+        let journal =
+            SharedJournal::new(Journal::open(&journal_dir, JournalConfig::default()).unwrap())
+                .unwrap()
+                .with_state(original.recovery.clone());
+        journal.append(&submit(11, symbol)).unwrap();
+        let mut snapshotter = Snapshotter::new(&snapshot_dir, SnapshotConfig::default()).unwrap();
+        journal.snapshot(&mut snapshotter, &original.books).unwrap();
+        journal.append(&submit(12, symbol)).unwrap();
+        drop(journal);
+
+        let warm = restore(&snapshot_dir, &journal_dir).unwrap();
+        assert!(warm.snapshot.is_some());
+        assert_eq!(warm.replayed, 1);
+        let recovery = &warm.state.recovery;
+        assert_eq!(recovery.last_order_id, Some(12));
+        assert!(recovery.open_orders.contains_key(&9));
+        assert!(recovery.open_orders.contains_key(&11));
+        assert!(recovery.open_orders.contains_key(&12));
+        assert_eq!(warm.state.books[&symbol].best_bid(), Some((px(99), qty(3))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -25,6 +25,58 @@ pub enum AllocError {
     NotInitialized,
     #[error("Unsupported operation: {0}")]
     UnsupportedOperation(String),
+    #[error("{region} canary corrupted for chunk {address:#x} (generation {generation})")]
+    CanaryCorrupted {
+        address: usize,
+        generation: u64,
+        region: &'static str,
+    },
+    #[error("Pointer {address:#x} was not allocated by this allocator")]
+    ForeignPointer { address: usize },
+    #[error("Layout of {requested} bytes does not match the {recorded}-byte block at {address:#x}")]
+    LayoutMismatch {
+        address: usize,
+        requested: usize,
+        recorded: usize,
+    },
+    #[error(
+        "Growing by {requested} bytes would exceed the {ceiling}-byte memory budget ({used} in use)"
+    )]
+    BudgetExceeded {
+        requested: usize,
+        used: usize,
+        ceiling: usize,
+    },
+}
+
+impl AllocError {
+    /// Stable variant name, used to break down failures in statistics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AllocError::OutOfMemory => "OutOfMemory",
+            AllocError::InvalidLayout(_) => "InvalidLayout",
+            AllocError::NumaNodeUnavailable(_) => "NumaNodeUnavailable",
+            AllocError::SizeExceeded { .. } => "SizeExceeded",
+            AllocError::PoolExhausted => "PoolExhausted",
+            AllocError::AlignmentNotSupported { .. } => "AlignmentNotSupported",
+            AllocError::AlreadyInitialized => "AlreadyInitialized",
+            AllocError::NotInitialized => "NotInitialized",
+            AllocError::UnsupportedOperation(_) => "UnsupportedOperation",
+            AllocError::CanaryCorrupted { .. } => "CanaryCorrupted",
+            AllocError::ForeignPointer { .. } => "ForeignPointer",
+            AllocError::LayoutMismatch { .. } => "LayoutMismatch",
+            AllocError::BudgetExceeded { .. } => "BudgetExceeded",
+        }
+    }
+}
+
+/// Discrepancy between a pool's running counters and its actual contents
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AccountingError {
+    #[error("total_memory is {recorded} bytes but pool contents account for {computed} bytes")]
+    TotalMemoryMismatch { recorded: usize, computed: usize },
+    #[error("free_count is {recorded} but the free list holds {actual} chunks")]
+    FreeCountMismatch { recorded: usize, actual: usize },
 }
 
 pub trait MemoryAllocator: Send + Sync {
@@ -32,6 +84,29 @@ pub trait MemoryAllocator: Send + Sync {
 
     fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
 
+    /// `deallocate` that, in debug builds, refuses pointers this allocator
+    /// does not own and layouts larger than the block recorded at allocation,
+    /// instead of corrupting pool state. Release builds skip the checks.
+    fn deallocate_checked(&self, ptr: NonNull<u8>, layout: Layout) -> Result<(), AllocError> {
+        if cfg!(debug_assertions) {
+            let address = ptr.as_ptr() as usize;
+            if !self.owns(ptr) {
+                return Err(AllocError::ForeignPointer { address });
+            }
+            if let Some(recorded) = self.allocation_size(ptr)
+                && layout.size() > recorded
+            {
+                return Err(AllocError::LayoutMismatch {
+                    address,
+                    requested: layout.size(),
+                    recorded,
+                });
+            }
+        }
+        self.deallocate(ptr, layout);
+        Ok(())
+    }
+
     fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
         #[cfg(feature = "hft-unsafe")]
         {
@@ -94,7 +169,60 @@ pub trait MemoryAllocator: Send + Sync {
         64
     }
 
+    /// Whether `ptr` was handed out by this allocator. Use it to route a free
+    /// when several allocators are in play; defaults to `false` (unknown).
+    fn owns(&self, _ptr: NonNull<u8>) -> bool {
+        false
+    }
+
+    /// Size of the block at `ptr`, fixed when it was allocated; `None` when
+    /// the pointer is foreign or the allocator does not track it.
+    fn allocation_size(&self, _ptr: NonNull<u8>) -> Option<usize> {
+        None
+    }
+
     fn available_memory(&self) -> usize;
 
     fn total_memory(&self) -> usize;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::safe_pool::{SafeMemoryPool, SafePoolConfig};
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn checked_free_rejects_foreign_pointers_and_oversized_layouts() {
+        let pool = SafeMemoryPool::new(SafePoolConfig {
+            chunk_size: 256,
+            initial_chunks: 2,
+            max_chunks: 2,
+            ..SafePoolConfig::default()
+        })
+        .unwrap();
+        let layout = Layout::from_size_align(256, 8).unwrap();
+        let ptr = pool.allocate(layout).unwrap();
+
+        let oversized = Layout::from_size_align(512, 8).unwrap();
+        assert!(matches!(
+            pool.deallocate_checked(ptr, oversized),
+            Err(AllocError::LayoutMismatch {
+                requested: 512,
+                recorded: 256,
+                ..
+            })
+        ));
+        let mut on_stack = [0u8; 256];
+        let foreign = NonNull::from(&mut on_stack).cast::<u8>();
+        assert!(matches!(
+            pool.deallocate_checked(foreign, layout),
+            Err(AllocError::ForeignPointer { .. })
+        ));
+
+        // Refused frees left the chunk allocated; the matching one releases it
+        assert_eq!(pool.available_memory(), 256);
+        pool.deallocate_checked(ptr, layout).unwrap();
+        assert_eq!(pool.available_memory(), 512);
+    }
+}
@@ -0,0 +1,191 @@
+//! Bump arena for short-lived scratch allocations
+//!
+//! Everything allocated while handling one event dies together, so the arena
+//! carves sub-allocations out of pool chunks with a bump pointer and hands the
+//! chunks back in one `reset()`.
+//!
+//! # Safety
+//! This module uses unsafe code to hand out references into pool chunks.
+//! All unsafe operations are documented with SAFETY comments.
+
+#![allow(unsafe_code)] // Handing out references into raw chunks requires unsafe
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use crate::core::memory::allocator::AllocError;
+use crate::core::memory::lock_free_pool::LockFreeMemoryPool;
+use std::alloc::Layout;
+use std::cell::{Cell, RefCell};
+use std::ptr::NonNull;
+
+/// Bump allocator layered on a [`LockFreeMemoryPool`].
+///
+/// Values are never dropped: the arena only reclaims their memory, so store
+/// plain data (or types whose destructor does not matter) in it. Borrows are
+/// tied to `&self`, and `reset` takes `&mut self`, so no reference can outlive
+/// the memory behind it.
+#[derive(Debug)]
+pub struct Arena<'a> {
+    pool: &'a LockFreeMemoryPool,
+    // Chunks taken from the pool; the last one is being bumped
+    chunks: RefCell<Vec<NonNull<u8>>>,
+    // Bytes used in the current chunk
+    offset: Cell<usize>,
+}
+
+impl<'a> Arena<'a> {
+    /// Create an empty arena; the first chunk is taken on first allocation
+    pub fn new(pool: &'a LockFreeMemoryPool) -> Self {
+        Self {
+            pool,
+            chunks: RefCell::new(Vec::new()),
+            offset: Cell::new(0),
+        }
+    }
+
+    /// Move `value` into the arena
+    #[allow(clippy::mut_from_ref)] // Each call hands out a distinct, fresh region
+    pub fn alloc<T>(&self, value: T) -> Result<&mut T, AllocError> {
+        let ptr = self.alloc_layout(Layout::new::<T>())?.cast::<T>();
+        // SAFETY: `ptr` is aligned for T, points to size_of::<T>() unused bytes
+        // inside a chunk this arena owns, and no other reference aliases it
+        unsafe {
+            ptr.as_ptr().write(value);
+            Ok(&mut *ptr.as_ptr())
+        }
+    }
+
+    /// Copy `src` into the arena
+    #[allow(clippy::mut_from_ref)] // Each call hands out a distinct, fresh region
+    pub fn alloc_slice<T: Copy>(&self, src: &[T]) -> Result<&mut [T], AllocError> {
+        let layout =
+            Layout::array::<T>(src.len()).map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
+        let ptr = self.alloc_layout(layout)?.cast::<T>();
+        // SAFETY: `ptr` is aligned for T with room for `src.len()` elements in a
+        // chunk this arena owns; `src` cannot overlap memory not yet handed out
+        unsafe {
+            std::ptr::copy_nonoverlapping(src.as_ptr(), ptr.as_ptr(), src.len());
+            Ok(std::slice::from_raw_parts_mut(ptr.as_ptr(), src.len()))
+        }
+    }
+
+    /// Return every chunk to the pool, invalidating all allocations
+    pub fn reset(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..) {
+            self.pool.deallocate_chunk(chunk);
+        }
+        self.offset.set(0);
+    }
+
+    /// Number of pool chunks currently held
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            // Zero-sized values need an aligned address but no storage
+            return NonNull::new(layout.align() as *mut u8)
+                .ok_or_else(|| AllocError::InvalidLayout("zero alignment".to_string()));
+        }
+
+        if let Some(ptr) = self.bump(layout) {
+            return Ok(ptr);
+        }
+
+        // Current chunk is full (or there is none yet): start a fresh one
+        let chunk = self.pool.allocate_chunk()?;
+        self.chunks.borrow_mut().push(chunk);
+        self.offset.set(0);
+
+        self.bump(layout).ok_or(AllocError::SizeExceeded {
+            size: layout.size(),
+            max: self.pool.chunk_size(),
+        })
+    }
+
+    fn bump(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let base = *self.chunks.borrow().last()?;
+        let start = base.as_ptr() as usize + self.offset.get();
+        let aligned = start.checked_next_multiple_of(layout.align())?;
+        let end = aligned.checked_add(layout.size())?;
+        let used = end - base.as_ptr() as usize;
+        if used > self.pool.chunk_size() {
+            return None;
+        }
+
+        self.offset.set(used);
+        // SAFETY: `aligned - base` is within the chunk (checked above), so the
+        // offset pointer stays inside the same allocation and is non-null
+        Some(unsafe { base.add(aligned - base.as_ptr() as usize) })
+    }
+}
+
+impl Drop for Arena<'_> {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::lock_free_pool::PoolConfig;
+
+    fn pool() -> LockFreeMemoryPool {
+        LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 4,
+            max_chunks: 4,
+            thread_cache_size: 0,
+            ..PoolConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn allocations_are_aligned_for_their_type() {
+        let pool = pool();
+        let arena = Arena::new(&pool);
+
+        let byte = arena.alloc(7u8).unwrap();
+        let word = arena.alloc(0xDEAD_BEEF_u64).unwrap();
+        let wide = arena.alloc(u128::MAX).unwrap();
+        let slice = arena.alloc_slice(&[1u32, 2, 3]).unwrap();
+
+        assert_eq!(*byte, 7);
+        assert_eq!(*word, 0xDEAD_BEEF);
+        assert_eq!(*wide, u128::MAX);
+        assert_eq!(slice, &[1, 2, 3]);
+        assert_eq!(word as *mut u64 as usize % align_of::<u64>(), 0);
+        assert_eq!(wide as *mut u128 as usize % align_of::<u128>(), 0);
+        assert_eq!(slice.as_ptr() as usize % align_of::<u32>(), 0);
+    }
+
+    #[test]
+    fn full_chunk_rolls_over_and_reset_returns_every_chunk() {
+        let pool = pool();
+        let mut arena = Arena::new(&pool);
+
+        arena.alloc([0u8; 48]).unwrap();
+        assert_eq!(arena.chunk_count(), 1);
+        arena.alloc([0u8; 48]).unwrap();
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(pool.get_stats().allocated_chunks, 2);
+
+        arena.reset();
+        assert_eq!(arena.chunk_count(), 0);
+        assert_eq!(pool.get_stats().allocated_chunks, 0);
+
+        // The returned chunks serve the next event
+        assert_eq!(*arena.alloc(42u64).unwrap(), 42);
+        assert_eq!(arena.chunk_count(), 1);
+        assert_eq!(pool.get_stats().allocated_chunks, 1);
+
+        assert!(matches!(
+            arena.alloc([0u8; 65]),
+            Err(AllocError::SizeExceeded { size: 65, max: 64 })
+        ));
+        drop(arena);
+        assert_eq!(pool.get_stats().allocated_chunks, 0);
+    }
+}
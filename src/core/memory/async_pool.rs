@@ -0,0 +1,151 @@
+// Async allocation front-end for the lock-free pool
+// Tasks waiting on an exhausted pool park on a Notify instead of spinning the runtime
+
+use crate::core::memory::allocator::{AllocError, MemoryAllocator};
+use crate::core::memory::lock_free_pool::LockFreeMemoryPool;
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Wraps a `LockFreeMemoryPool` so tokio tasks can await a free chunk.
+///
+/// Waiters are woken by `deallocate` on this wrapper; chunks returned to the
+/// inner pool directly do not wake anyone until the next wrapper free.
+#[derive(Debug)]
+pub struct AsyncPool {
+    pool: Arc<LockFreeMemoryPool>,
+    freed: Notify,
+}
+
+impl AsyncPool {
+    pub fn new(pool: Arc<LockFreeMemoryPool>) -> Self {
+        Self {
+            pool,
+            freed: Notify::new(),
+        }
+    }
+
+    pub fn pool(&self) -> &Arc<LockFreeMemoryPool> {
+        &self.pool
+    }
+
+    /// Allocate a chunk for `layout`, yielding to the runtime while the pool
+    /// is exhausted. Errors other than exhaustion are returned immediately.
+    pub async fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.pool
+            .check_layout(layout)
+            .map_err(|e| self.pool.record_failure(e))?;
+
+        let started = Instant::now();
+        let mut waited = false;
+        loop {
+            // Register before trying so a free between the attempt and the
+            // await is not missed
+            let freed = self.freed.notified();
+            tokio::pin!(freed);
+            freed.as_mut().enable();
+
+            match self.pool.try_allocate() {
+                Ok(ptr) => {
+                    if waited {
+                        self.record_wait(started);
+                    }
+                    return Ok(ptr);
+                }
+                Err(AllocError::PoolExhausted) => {}
+                Err(e) => return Err(self.pool.record_failure(e)),
+            }
+            // Outside the match so the non-Send result is not held across the await
+            waited = true;
+            freed.await;
+        }
+    }
+
+    /// Like [`allocate`](Self::allocate), but give up with `PoolExhausted`
+    /// once `timeout` has passed without a chunk becoming free
+    pub async fn allocate_timeout(
+        &self,
+        layout: Layout,
+        timeout: Duration,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, self.allocate(layout)).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.record_wait(started);
+                Err(self.pool.record_failure(AllocError::PoolExhausted))
+            }
+        }
+    }
+
+    /// Return a chunk to the pool and wake one waiting task
+    pub fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.pool.deallocate(ptr, layout);
+        self.freed.notify_one();
+    }
+
+    fn record_wait(&self, started: Instant) {
+        self.pool
+            .get_allocation_stats()
+            .record_allocation_wait(started.elapsed().as_nanos() as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::lock_free_pool::PoolConfig;
+
+    fn single_chunk_pool() -> AsyncPool {
+        AsyncPool::new(Arc::new(
+            LockFreeMemoryPool::new(PoolConfig {
+                chunk_size: 64,
+                initial_chunks: 1,
+                max_chunks: 1,
+                thread_cache_size: 0,
+                ..PoolConfig::default()
+            })
+            .unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn parked_task_wakes_when_a_chunk_is_freed() {
+        let pool = single_chunk_pool();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let held = pool.allocate(layout).await.unwrap();
+
+        let (woken, ()) = tokio::join!(pool.allocate(layout), async {
+            // The waiter has found the pool empty and parked by now
+            tokio::task::yield_now().await;
+            pool.deallocate(held, layout);
+        });
+
+        let ptr = woken.unwrap();
+        assert_eq!(ptr, held);
+        assert_eq!(
+            pool.pool()
+                .get_allocation_stats()
+                .get_snapshot()
+                .blocked_allocations,
+            1
+        );
+        pool.deallocate(ptr, layout);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deadline_gives_up_on_an_exhausted_pool() {
+        let pool = single_chunk_pool();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let held = pool.allocate(layout).await.unwrap();
+
+        let result = pool
+            .allocate_timeout(layout, Duration::from_millis(50))
+            .await;
+
+        assert!(matches!(result, Err(AllocError::PoolExhausted)));
+        pool.deallocate(held, layout);
+    }
+}
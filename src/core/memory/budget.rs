@@ -0,0 +1,374 @@
+// Process-wide memory ceiling for ShrivenQ
+// Caps the combined footprint of every registered backend and refuses growth past it
+
+use crate::core::memory::MemoryBackend;
+use crate::core::memory::allocator::{AllocError, MemoryAllocator};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use tracing::{info, warn};
+
+static GLOBAL: OnceLock<Arc<MemoryBudget>> = OnceLock::new();
+
+const DEFAULT_WARN_AT: f64 = 0.9;
+
+#[cfg(test)]
+std::thread_local! {
+    // Checked instead of the global budget, so a test can use a low ceiling
+    // without starving the pools of tests running alongside it
+    static SCOPED: std::cell::RefCell<Option<Arc<MemoryBudget>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// The `[memory.budget]` section. Without a ceiling there is no budget and
+/// pools grow up to their own `max_chunks`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    pub ceiling_bytes: Option<usize>,
+    /// Fraction of the ceiling (0 to 1] at which usage is reported and idle
+    /// pools are shrunk
+    pub warn_at: f64,
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        Self {
+            ceiling_bytes: None,
+            warn_at: DEFAULT_WARN_AT,
+        }
+    }
+}
+
+/// Called with the budget's usage when it crosses the warning level
+pub type BudgetAlert = Box<dyn Fn(BudgetSnapshot) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetSnapshot {
+    pub used_bytes: usize,
+    pub ceiling_bytes: usize,
+    pub refused: u64,
+}
+
+/// Ceiling on the summed `total_memory` of every registered backend.
+///
+/// Pools ask the installed budget before taking a new chunk from the OS.
+/// Past the warning level the budget shrinks the free lists of idle pools
+/// (those with nothing handed out) to make room and reports the pressure once
+/// per crossing; a growth that would still take usage over the ceiling fails
+/// with [`AllocError::BudgetExceeded`]. Reuse of chunks a pool already holds
+/// is never refused.
+///
+/// The check reads the pools' counters rather than reserving, so threads
+/// growing at the same moment can overshoot by a chunk each.
+pub struct MemoryBudget {
+    ceiling: usize,
+    warn_at: usize,
+    backends: RwLock<Vec<Weak<MemoryBackend>>>,
+    warned: AtomicBool,
+    refused: AtomicU64,
+    alert: RwLock<Option<BudgetAlert>>,
+}
+
+impl std::fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryBudget")
+            .field("ceiling", &self.ceiling)
+            .field("warn_at", &self.warn_at)
+            .field("backends", &self.backends.read().len())
+            .field("refused", &self.refused.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+impl MemoryBudget {
+    pub fn new(ceiling_bytes: usize) -> Self {
+        Self {
+            ceiling: ceiling_bytes,
+            warn_at: fraction_of(ceiling_bytes, DEFAULT_WARN_AT),
+            backends: RwLock::new(Vec::new()),
+            warned: AtomicBool::new(false),
+            refused: AtomicU64::new(0),
+            alert: RwLock::new(None),
+        }
+    }
+
+    /// Budget for `config`, or `None` when it sets no ceiling
+    pub fn from_config(config: &BudgetConfig) -> Result<Option<Self>, AllocError> {
+        let Some(ceiling) = config.ceiling_bytes else {
+            return Ok(None);
+        };
+        Self::new(ceiling).with_warn_at(config.warn_at).map(Some)
+    }
+
+    /// Usage, as a fraction of the ceiling in (0, 1], that counts as pressure
+    pub fn with_warn_at(mut self, fraction: f64) -> Result<Self, AllocError> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(AllocError::InvalidLayout(format!(
+                "Budget warning level {} must be in (0, 1]",
+                fraction
+            )));
+        }
+        self.warn_at = fraction_of(self.ceiling, fraction);
+        Ok(self)
+    }
+
+    /// Call `alert` each time usage crosses the warning level. Replaces any
+    /// earlier alert. Runs on the allocating thread, so keep it short.
+    pub fn with_alert(self, alert: BudgetAlert) -> Self {
+        *self.alert.write() = Some(alert);
+        self
+    }
+
+    /// Make this the budget every pool checks. Only one can be installed.
+    pub fn install(self: Arc<Self>) -> Result<Arc<Self>, AllocError> {
+        GLOBAL
+            .set(Arc::clone(&self))
+            .map_err(|_| AllocError::AlreadyInitialized)?;
+        info!(
+            ceiling_mb = self.ceiling / (1024 * 1024),
+            "Memory budget installed"
+        );
+        Ok(self)
+    }
+
+    /// The installed budget, if any
+    pub fn global() -> Option<&'static Arc<MemoryBudget>> {
+        GLOBAL.get()
+    }
+
+    /// Count `backend` against the ceiling for as long as it lives
+    pub fn register(&self, backend: &Arc<MemoryBackend>) {
+        let mut backends = self.backends.write();
+        backends.retain(|b| b.strong_count() > 0);
+        backends.push(Arc::downgrade(backend));
+    }
+
+    pub fn ceiling(&self) -> usize {
+        self.ceiling
+    }
+
+    /// Bytes held by the registered backends, free chunks included
+    pub fn used(&self) -> usize {
+        self.backends
+            .read()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|backend| backend.total_memory())
+            .sum()
+    }
+
+    /// Growths refused so far
+    pub fn refused(&self) -> u64 {
+        self.refused.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> BudgetSnapshot {
+        BudgetSnapshot {
+            used_bytes: self.used(),
+            ceiling_bytes: self.ceiling,
+            refused: self.refused(),
+        }
+    }
+
+    /// Release the free chunks of every idle pool; returns the bytes freed
+    pub fn shrink_idle(&self) -> usize {
+        self.backends
+            .read()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|backend| backend.shrink_idle())
+            .sum()
+    }
+
+    /// Whether a pool may take `bytes` more from the OS
+    pub fn admit(&self, bytes: usize) -> Result<(), AllocError> {
+        let mut used = self.used();
+        if used.saturating_add(bytes) <= self.warn_at {
+            self.warned.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        if !self.warned.swap(true, Ordering::Relaxed) {
+            warn!(
+                used_mb = used / (1024 * 1024),
+                ceiling_mb = self.ceiling / (1024 * 1024),
+                "Memory budget under pressure, shrinking idle pools"
+            );
+            if let Some(alert) = self.alert.read().as_ref() {
+                alert(BudgetSnapshot {
+                    used_bytes: used,
+                    ceiling_bytes: self.ceiling,
+                    refused: self.refused(),
+                });
+            }
+        }
+        if self.shrink_idle() > 0 {
+            used = self.used();
+        }
+        if used.saturating_add(bytes) <= self.ceiling {
+            return Ok(());
+        }
+
+        let prev_refused = self.refused.fetch_add(1, Ordering::Relaxed);
+        if prev_refused % 1000 == 0 {
+            warn!(
+                requested = bytes,
+                used_bytes = used,
+                ceiling_bytes = self.ceiling,
+                refused = prev_refused + 1,
+                "Memory budget refused pool growth"
+            );
+        }
+        Err(AllocError::BudgetExceeded {
+            requested: bytes,
+            used,
+            ceiling: self.ceiling,
+        })
+    }
+}
+
+/// Check a pool growth of `bytes` against the installed budget; always
+/// admitted when none is installed
+pub fn admit_growth(bytes: usize) -> Result<(), AllocError> {
+    #[cfg(test)]
+    if let Some(budget) = SCOPED.with_borrow(Option::clone) {
+        return budget.admit(bytes);
+    }
+    match MemoryBudget::global() {
+        Some(budget) => budget.admit(bytes),
+        None => Ok(()),
+    }
+}
+
+fn fraction_of(bytes: usize, fraction: f64) -> usize {
+    (bytes as f64 * fraction) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::{SafeMemoryPool, SafePoolConfig};
+
+    const CHUNK: usize = 256;
+
+    fn safe_backend(initial_chunks: usize) -> Arc<MemoryBackend> {
+        let pool = SafeMemoryPool::new(SafePoolConfig {
+            chunk_size: CHUNK,
+            initial_chunks,
+            max_chunks: 64,
+            ..SafePoolConfig::default()
+        })
+        .unwrap();
+        Arc::new(MemoryBackend::Safe(pool))
+    }
+
+    fn safe_pool(backend: &MemoryBackend) -> &SafeMemoryPool {
+        match backend {
+            MemoryBackend::Safe(pool) => pool,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("test backends are safe pools"),
+        }
+    }
+
+    // Run `f` with `budget` as the one pools on this thread check
+    fn with_scoped<R>(budget: &Arc<MemoryBudget>, f: impl FnOnce() -> R) -> R {
+        SCOPED.set(Some(Arc::clone(budget)));
+        let result = f();
+        SCOPED.set(None);
+        result
+    }
+
+    #[test]
+    fn growth_past_the_ceiling_is_refused() {
+        let alerts = Arc::new(AtomicU64::new(0));
+        let counted = Arc::clone(&alerts);
+        let budget = Arc::new(MemoryBudget::new(8 * CHUNK).with_alert(Box::new(move |_| {
+            counted.fetch_add(1, Ordering::Relaxed);
+        })));
+        let backend = safe_backend(2);
+        budget.register(&backend);
+        let pool = safe_pool(&backend);
+
+        let mut handles = Vec::new();
+        let err = with_scoped(&budget, || {
+            loop {
+                match pool.allocate_chunk() {
+                    Ok(handle) => handles.push(handle),
+                    Err(err) => break err,
+                }
+            }
+        });
+
+        assert_eq!(handles.len(), 8);
+        assert!(matches!(
+            err,
+            AllocError::BudgetExceeded {
+                requested: CHUNK,
+                used,
+                ceiling,
+            } if used == 8 * CHUNK && ceiling == 8 * CHUNK
+        ));
+        assert_eq!(budget.used(), 8 * CHUNK);
+        assert_eq!(budget.refused(), 1);
+        assert_eq!(alerts.load(Ordering::Relaxed), 1);
+
+        // Reusing a chunk the pool already holds is never refused
+        pool.deallocate_chunk(handles.pop().unwrap());
+        let reused = with_scoped(&budget, || pool.allocate_chunk());
+        assert!(reused.is_ok());
+        assert_eq!(budget.used(), 8 * CHUNK);
+    }
+
+    #[test]
+    fn idle_pools_are_shrunk_to_make_room() {
+        let budget = Arc::new(MemoryBudget::new(8 * CHUNK));
+        let idle = safe_backend(4);
+        let busy = safe_backend(0);
+        budget.register(&idle);
+        budget.register(&busy);
+
+        let handles: Vec<_> = with_scoped(&budget, || {
+            (0..8)
+                .map_while(|_| safe_pool(&busy).allocate_chunk().ok())
+                .collect()
+        });
+
+        assert_eq!(handles.len(), 8);
+        assert_eq!(idle.total_memory(), 0);
+        assert_eq!(budget.used(), 8 * CHUNK);
+        assert_eq!(budget.refused(), 0);
+    }
+
+    #[test]
+    fn dropped_backends_stop_counting() {
+        let budget = MemoryBudget::new(8 * CHUNK);
+        let backend = safe_backend(4);
+        budget.register(&backend);
+        assert_eq!(budget.used(), 4 * CHUNK);
+
+        drop(backend);
+        assert_eq!(budget.used(), 0);
+        assert!(budget.admit(8 * CHUNK).is_ok());
+    }
+
+    #[test]
+    fn warning_level_must_be_a_fraction() {
+        for warn_at in [0.0, 1.5, f64::NAN] {
+            let config = BudgetConfig {
+                ceiling_bytes: Some(1024),
+                warn_at,
+            };
+            assert!(matches!(
+                MemoryBudget::from_config(&config),
+                Err(AllocError::InvalidLayout(_))
+            ));
+        }
+        assert!(
+            MemoryBudget::from_config(&BudgetConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// Memory configuration for ShrivenQ
+// Which allocator backs the engine, and how each one is sized, from the config file
+
+use crate::core::memory::budget::BudgetConfig;
+use crate::core::memory::safe_pool::SafePoolConfig;
+#[cfg(feature = "hft-unsafe")]
+use crate::core::memory::{NumaConfig, PoolConfig, SlabConfig};
+use serde::Deserialize;
+use std::fmt;
+
+/// Allocator behind [`MemoryBackend`](super::MemoryBackend). Every kind but
+/// `Safe` needs the `hft-unsafe` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    Safe,
+    LockFree,
+    Numa,
+    Slab,
+}
+
+impl BackendKind {
+    pub fn is_unsafe(self) -> bool {
+        self != BackendKind::Safe
+    }
+
+    /// Whether this build can construct the backend
+    pub fn is_available(self) -> bool {
+        cfg!(feature = "hft-unsafe") || !self.is_unsafe()
+    }
+}
+
+/// The fastest backend this build has
+impl Default for BackendKind {
+    fn default() -> Self {
+        if cfg!(feature = "hft-unsafe") {
+            BackendKind::LockFree
+        } else {
+            BackendKind::Safe
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendKind::Safe => write!(f, "safe"),
+            BackendKind::LockFree => write!(f, "lock_free"),
+            BackendKind::Numa => write!(f, "numa"),
+            BackendKind::Slab => write!(f, "slab"),
+        }
+    }
+}
+
+/// The `[memory]` section: the backend to build and the settings of each.
+/// Only the section for the chosen backend is used. Sections for the
+/// `hft-unsafe` backends are ignored by builds without it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    pub backend: BackendKind,
+    /// Ceiling on the combined size of every pool; unlimited by default
+    pub budget: BudgetConfig,
+    pub safe: SafePoolConfig,
+    #[cfg(feature = "hft-unsafe")]
+    pub lock_free: PoolConfig,
+    #[cfg(feature = "hft-unsafe")]
+    pub numa: NumaConfig,
+    #[cfg(feature = "hft-unsafe")]
+    pub slab: SlabConfig,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::default(),
+            budget: BudgetConfig::default(),
+            safe: SafePoolConfig {
+                max_chunks: 100_000,
+                ..SafePoolConfig::default()
+            },
+            #[cfg(feature = "hft-unsafe")]
+            lock_free: PoolConfig {
+                max_chunks: 100_000,
+                ..PoolConfig::default()
+            },
+            #[cfg(feature = "hft-unsafe")]
+            numa: NumaConfig::default(),
+            #[cfg(feature = "hft-unsafe")]
+            slab: SlabConfig::default(),
+        }
+    }
+}
@@ -0,0 +1,798 @@
+// Allocator fuzzing for ShrivenQ
+// Random allocate/free sequences checked against a shadow model of the live blocks.
+// Test-only: run with `cargo test --features "hft-unsafe testing" memory::fuzz`,
+// setting SHRIVEN_FUZZ_SEED to replay a failure.
+
+use crate::core::memory::allocator::{AllocError, MemoryAllocator};
+use crate::core::memory::lock_free_pool::{LockFreeMemoryPool, PoolConfig, ReaperConfig};
+use crate::core::memory::slab_allocator::{SlabAllocator, SlabConfig};
+use parking_lot::Mutex;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestCaseError, TestError, TestRng, TestRunner};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::alloc::Layout;
+use std::collections::BTreeMap;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Blocks a stress thread holds at most, so the run cycles through reuse
+// rather than sitting at exhaustion
+const MAX_HELD_PER_THREAD: usize = 32;
+
+// Proptest's automatic limit of four shrink steps per case stops long before
+// a few hundred operations are pared down to the ones that matter
+const MAX_SHRINK_ITERS: u32 = 50_000;
+
+// Reaper passes `check_reaper` waits through before giving up
+const REAPER_PASSES: u32 = 50;
+
+/// One step of a generated sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Allocate room for this many bytes
+    Allocate(usize),
+    /// Free a live block, picked by index modulo the number live
+    Free(usize),
+    /// Return free chunks to the OS down to this many; lock-free pool only
+    Shrink(usize),
+}
+
+#[derive(Debug, Clone)]
+struct FuzzConfig {
+    /// Sequences to generate
+    pub cases: u32,
+    /// Longest sequence
+    pub max_ops: usize,
+    /// Seed for the generator; random when `None`
+    pub seed: Option<u64>,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            cases: 256,
+            max_ops: 512,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+enum FuzzError {
+    #[error("{allocator}: {reason}\nMinimal failing sequence: {ops:?}")]
+    Falsified {
+        allocator: &'static str,
+        reason: String,
+        ops: Vec<Op>,
+    },
+    #[error("{allocator}: run aborted: {reason}")]
+    Aborted {
+        allocator: &'static str,
+        reason: String,
+    },
+    #[error("{allocator}: concurrent run failed: {reason}")]
+    Concurrent {
+        allocator: &'static str,
+        reason: String,
+    },
+    #[error("{allocator}: reaper check failed: {reason}")]
+    Reaper {
+        allocator: &'static str,
+        reason: String,
+    },
+    #[error("{allocator}: could not be built: {source}")]
+    Setup {
+        allocator: &'static str,
+        source: AllocError,
+    },
+}
+
+/// Fresh pool per sequence, built from `config`. Checks that no two live
+/// chunks overlap, every live chunk keeps what was written to it,
+/// `allocated + free` matches `total_memory`, and the pool only reports
+/// exhaustion with `max_chunks` handed out.
+fn fuzz_lock_free(pool: &PoolConfig, config: &FuzzConfig) -> Result<(), FuzzError> {
+    let allocator = "LockFreeMemoryPool";
+    pool.validate()
+        .map_err(|source| FuzzError::Setup { allocator, source })?;
+    run_cases(allocator, config, pool.chunk_size, |ops| {
+        let target = PoolUnderTest {
+            pool: LockFreeMemoryPool::new(pool.clone())
+                .map_err(|e| TestCaseError::fail(e.to_string()))?,
+            max_chunks: pool.max_chunks,
+        };
+        run_sequence(&target, ops)
+    })
+}
+
+/// Fresh slab per sequence, built from `config`. Checks as for
+/// [`fuzz_lock_free`], plus that every block comes from the smallest class
+/// that fits and each class keeps its pre-allocated block count.
+fn fuzz_slab(slab: &SlabConfig, config: &FuzzConfig) -> Result<(), FuzzError> {
+    let allocator = "SlabAllocator";
+    let largest = slab
+        .resolve_size_classes()
+        .map_err(|source| FuzzError::Setup { allocator, source })?
+        .last()
+        .copied()
+        .unwrap_or(slab.min_object_size);
+    run_cases(allocator, config, largest, |ops| {
+        let target = SlabUnderTest {
+            slab: SlabAllocator::new(slab.clone())
+                .map_err(|e| TestCaseError::fail(e.to_string()))?,
+            config: slab,
+        };
+        run_sequence(&target, ops)
+    })
+}
+
+fn run_cases(
+    allocator: &'static str,
+    config: &FuzzConfig,
+    max_size: usize,
+    case: impl Fn(&[Op]) -> Result<(), TestCaseError>,
+) -> Result<(), FuzzError> {
+    let runner_config = Config {
+        cases: config.cases,
+        max_shrink_iters: MAX_SHRINK_ITERS,
+        failure_persistence: None,
+        ..Config::default()
+    };
+    let mut runner = match config.seed {
+        Some(seed) => {
+            let mut bytes = [0u8; 32];
+            bytes[..8].copy_from_slice(&seed.to_le_bytes());
+            TestRunner::new_with_rng(
+                runner_config,
+                TestRng::from_seed(RngAlgorithm::ChaCha, &bytes),
+            )
+        }
+        None => TestRunner::new(runner_config),
+    };
+
+    let op = prop_oneof![
+        4 => (1..=max_size.max(1)).prop_map(Op::Allocate),
+        3 => any::<usize>().prop_map(Op::Free),
+        1 => (0..8usize).prop_map(Op::Shrink),
+    ];
+    let ops = prop::collection::vec(op, 1..=config.max_ops.max(1));
+
+    runner.run(&ops, |ops| case(&ops)).map_err(|err| match err {
+        TestError::Fail(reason, ops) => FuzzError::Falsified {
+            allocator,
+            reason: reason.to_string(),
+            ops,
+        },
+        TestError::Abort(reason) => FuzzError::Aborted {
+            allocator,
+            reason: reason.to_string(),
+        },
+    })
+}
+
+// An allocator under test, with what the harness needs to know about it
+trait Target {
+    fn allocator(&self) -> &dyn MemoryAllocator;
+
+    /// Usable bytes of the block handed out for `size`
+    fn block_size(&self, size: usize) -> usize;
+
+    /// Whether running out while allocating `size` is legitimate
+    fn may_exhaust(&self, shadow: &Shadow, size: usize) -> bool;
+
+    fn shrink(&self, _target_free: usize) {}
+
+    /// The allocator's own counters against the shadow model
+    fn check(&self, shadow: &Shadow) -> Result<(), String>;
+}
+
+struct PoolUnderTest {
+    pool: LockFreeMemoryPool,
+    max_chunks: usize,
+}
+
+impl Target for PoolUnderTest {
+    fn allocator(&self) -> &dyn MemoryAllocator {
+        &self.pool
+    }
+
+    fn block_size(&self, _size: usize) -> usize {
+        self.pool.chunk_size()
+    }
+
+    fn may_exhaust(&self, shadow: &Shadow, _size: usize) -> bool {
+        shadow.len() >= self.max_chunks
+    }
+
+    fn shrink(&self, target_free: usize) {
+        self.pool.shrink(target_free);
+    }
+
+    fn check(&self, shadow: &Shadow) -> Result<(), String> {
+        self.pool.verify_accounting().map_err(|e| e.to_string())?;
+        let stats = self.pool.get_stats();
+        if stats.allocated_chunks != shadow.len() {
+            return Err(format!(
+                "pool reports {} chunks allocated, {} are live",
+                stats.allocated_chunks,
+                shadow.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+struct SlabUnderTest<'a> {
+    slab: SlabAllocator,
+    config: &'a SlabConfig,
+}
+
+impl Target for SlabUnderTest<'_> {
+    fn allocator(&self) -> &dyn MemoryAllocator {
+        &self.slab
+    }
+
+    fn block_size(&self, size: usize) -> usize {
+        let classes = self.slab.size_classes();
+        classes
+            .get(classes.partition_point(|&class| class < size))
+            .copied()
+            .unwrap_or(size)
+    }
+
+    fn may_exhaust(&self, shadow: &Shadow, size: usize) -> bool {
+        let class = self.block_size(size);
+        shadow.count_of_size(class) >= self.config.preallocation_for(class)
+    }
+
+    fn check(&self, shadow: &Shadow) -> Result<(), String> {
+        let stats = self.slab.get_stats();
+        let outstanding = stats.allocated_objects.saturating_sub(stats.freed_objects);
+        if outstanding != shadow.len() {
+            return Err(format!(
+                "slab reports {} objects outstanding, {} are live",
+                outstanding,
+                shadow.len()
+            ));
+        }
+        let mut expected_total = 0;
+        for (class, free) in self.slab.free_blocks_per_class() {
+            let blocks = self.config.preallocation_for(class);
+            expected_total += blocks * class;
+            let live = shadow.count_of_size(class);
+            if free + live != blocks {
+                return Err(format!(
+                    "class {}: {} free + {} live != {} pre-allocated",
+                    class, free, live, blocks
+                ));
+            }
+        }
+        if stats.total_memory != expected_total {
+            return Err(format!(
+                "total_memory {} != {} pre-allocated",
+                stats.total_memory, expected_total
+            ));
+        }
+        Ok(())
+    }
+}
+
+// A block the model believes is live
+#[derive(Debug)]
+struct Live {
+    ptr: NonNull<u8>,
+    len: usize,
+    requested: usize,
+    tag: u8,
+}
+
+// Live blocks by start address
+#[derive(Debug, Default)]
+struct Shadow {
+    live: BTreeMap<usize, Live>,
+}
+
+impl Shadow {
+    fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    fn count_of_size(&self, len: usize) -> usize {
+        self.live.values().filter(|block| block.len == len).count()
+    }
+
+    /// Record a block after checking it overlaps nothing live, then fill it
+    /// with `tag`
+    fn insert(&mut self, block: Live) -> Result<(), String> {
+        let start = block.ptr.as_ptr() as usize;
+        let end = start + block.len;
+        if let Some((&prev, prev_block)) = self.live.range(..=start).next_back()
+            && prev + prev_block.len > start
+        {
+            return Err(format!(
+                "block {:#x}+{} overlaps live block {:#x}+{}",
+                start, block.len, prev, prev_block.len
+            ));
+        }
+        if let Some((&next, next_block)) = self.live.range(start..).next()
+            && next < end
+        {
+            return Err(format!(
+                "block {:#x}+{} overlaps live block {:#x}+{}",
+                start, block.len, next, next_block.len
+            ));
+        }
+
+        // SAFETY: The allocator handed out `len` usable bytes at `ptr` and the
+        // model holds no other reference to them
+        unsafe { std::ptr::write_bytes(block.ptr.as_ptr(), block.tag, block.len) };
+        self.live.insert(start, block);
+        Ok(())
+    }
+
+    /// Remove the `slot`-th live block, modulo the number live, after
+    /// checking its contents survived
+    fn take(&mut self, slot: usize) -> Result<Option<Live>, String> {
+        if self.live.is_empty() {
+            return Ok(None);
+        }
+        let Some(&start) = self.live.keys().nth(slot % self.live.len()) else {
+            return Ok(None);
+        };
+        let Some(block) = self.live.remove(&start) else {
+            return Ok(None);
+        };
+        block.verify()?;
+        Ok(Some(block))
+    }
+}
+
+impl Live {
+    fn verify(&self) -> Result<(), String> {
+        // SAFETY: The block is live and was filled with `tag` over `len` bytes
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) };
+        match bytes.iter().position(|&b| b != self.tag) {
+            None => Ok(()),
+            Some(offset) => Err(format!(
+                "block {:#x} byte {} is {:#04x}, expected {:#04x}: written through another block",
+                self.ptr.as_ptr() as usize,
+                offset,
+                bytes[offset],
+                self.tag
+            )),
+        }
+    }
+}
+
+fn run_sequence<T: Target>(target: &T, ops: &[Op]) -> Result<(), TestCaseError> {
+    let mut shadow = Shadow::default();
+    let result = apply_ops(target, &mut shadow, ops);
+    // Hand everything back so the allocator drops cleanly whatever happened
+    for block in std::mem::take(&mut shadow.live).into_values() {
+        target
+            .allocator()
+            .deallocate(block.ptr, layout_of(block.requested));
+    }
+    result.map_err(TestCaseError::fail)?;
+    target.check(&shadow).map_err(TestCaseError::fail)
+}
+
+fn apply_ops<T: Target>(target: &T, shadow: &mut Shadow, ops: &[Op]) -> Result<(), String> {
+    for (step, &op) in ops.iter().enumerate() {
+        match op {
+            Op::Allocate(size) => match target.allocator().allocate(layout_of(size)) {
+                Ok(ptr) => {
+                    let len = target.block_size(size);
+                    if len < size {
+                        return Err(format!(
+                            "step {}: {} bytes for a {}-byte request",
+                            step, len, size
+                        ));
+                    }
+                    if let Some(recorded) = target.allocator().allocation_size(ptr)
+                        && recorded < len
+                    {
+                        return Err(format!(
+                            "step {}: block {:#x} records {} bytes, expected {}",
+                            step,
+                            ptr.as_ptr() as usize,
+                            recorded,
+                            len
+                        ));
+                    }
+                    shadow
+                        .insert(Live {
+                            ptr,
+                            len,
+                            requested: size,
+                            tag: (step % 255) as u8 + 1,
+                        })
+                        .map_err(|e| format!("step {}: {}", step, e))?;
+                }
+                Err(AllocError::PoolExhausted) if target.may_exhaust(shadow, size) => {}
+                Err(e) => {
+                    return Err(format!(
+                        "step {}: allocating {} bytes with {} live failed: {}",
+                        step,
+                        size,
+                        shadow.len(),
+                        e
+                    ));
+                }
+            },
+            Op::Free(slot) => {
+                if let Some(block) = shadow
+                    .take(slot)
+                    .map_err(|e| format!("step {}: {}", step, e))?
+                {
+                    target
+                        .allocator()
+                        .deallocate(block.ptr, layout_of(block.requested));
+                }
+            }
+            Op::Shrink(target_free) => target.shrink(target_free),
+        }
+        target
+            .check(shadow)
+            .map_err(|e| format!("step {} ({:?}): {}", step, op, e))?;
+    }
+    Ok(())
+}
+
+/// Run `threads` threads of `ops_per_thread` random allocations and frees
+/// against one shared allocator. Live blocks from every thread go into one
+/// registry, so a block handed to two threads at once, or overlapping
+/// another thread's, is caught, as is a write landing in another thread's
+/// block. Real threads on real hardware: this finds races by volume, it does
+/// not enumerate interleavings.
+fn stress_concurrent(
+    allocator: &'static str,
+    target: &dyn MemoryAllocator,
+    sizes: &[usize],
+    threads: usize,
+    ops_per_thread: usize,
+    seed: u64,
+) -> Result<(), FuzzError> {
+    let fail = |reason: String| FuzzError::Concurrent { allocator, reason };
+    if sizes.is_empty() {
+        return Err(fail("no sizes to allocate".to_string()));
+    }
+    // Start address -> (length, owning thread)
+    let registry: Mutex<BTreeMap<usize, (usize, usize)>> = Mutex::new(BTreeMap::new());
+
+    let results: Vec<Result<(), String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                let registry = &registry;
+                scope.spawn(move || {
+                    stress_thread(target, sizes, registry, thread, ops_per_thread, seed)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("stress thread panicked".to_string()))
+            })
+            .collect()
+    });
+    for result in results {
+        result.map_err(fail)?;
+    }
+
+    let leftover = registry.lock().len();
+    if leftover != 0 {
+        return Err(fail(format!(
+            "{} blocks still registered after the run",
+            leftover
+        )));
+    }
+    Ok(())
+}
+
+fn stress_thread(
+    target: &dyn MemoryAllocator,
+    sizes: &[usize],
+    registry: &Mutex<BTreeMap<usize, (usize, usize)>>,
+    thread: usize,
+    ops: usize,
+    seed: u64,
+) -> Result<(), String> {
+    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(thread as u64));
+    let tag = (thread % 255) as u8 + 1;
+    let mut held: Vec<(NonNull<u8>, usize, usize)> = Vec::new();
+    let mut outcome = Ok(());
+
+    for _ in 0..ops {
+        if held.is_empty() || (held.len() < MAX_HELD_PER_THREAD && rng.gen_bool(0.5)) {
+            let size = sizes[rng.gen_range(0..sizes.len())];
+            let ptr = match target.allocate(layout_of(size)) {
+                Ok(ptr) => ptr,
+                Err(AllocError::PoolExhausted) => continue,
+                Err(e) => {
+                    outcome = Err(format!(
+                        "thread {}: allocating {} bytes: {}",
+                        thread, size, e
+                    ));
+                    break;
+                }
+            };
+            let start = ptr.as_ptr() as usize;
+            {
+                let mut live = registry.lock();
+                if let Some((&prev, &(len, owner))) = live.range(..start + size).next_back()
+                    && prev + len > start
+                {
+                    outcome = Err(format!(
+                        "thread {}: block {:#x}+{} overlaps {:#x}+{} held by thread {}",
+                        thread, start, size, prev, len, owner
+                    ));
+                    target.deallocate(ptr, layout_of(size));
+                    break;
+                }
+                live.insert(start, (size, thread));
+            }
+            // SAFETY: The block was just handed to this thread and registered
+            // as its own; `size` bytes fit in it
+            unsafe { std::ptr::write_bytes(ptr.as_ptr(), tag, size) };
+            held.push((ptr, size, start));
+        } else {
+            let (ptr, size, start) = held.swap_remove(rng.gen_range(0..held.len()));
+            let check = verify_fill(ptr, size, tag, thread);
+            registry.lock().remove(&start);
+            target.deallocate(ptr, layout_of(size));
+            if let Err(e) = check {
+                outcome = Err(e);
+                break;
+            }
+        }
+    }
+
+    for (ptr, size, start) in held {
+        if outcome.is_ok() {
+            outcome = verify_fill(ptr, size, tag, thread);
+        }
+        registry.lock().remove(&start);
+        target.deallocate(ptr, layout_of(size));
+    }
+    outcome
+}
+
+fn verify_fill(ptr: NonNull<u8>, size: usize, tag: u8, thread: usize) -> Result<(), String> {
+    // SAFETY: The block is still held by this thread, which filled `size`
+    // bytes of it with `tag`
+    let bytes = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), size) };
+    match bytes.iter().position(|&b| b != tag) {
+        None => Ok(()),
+        Some(offset) => Err(format!(
+            "thread {}: block {:#x} byte {} is {:#04x}, expected {:#04x}",
+            thread,
+            ptr.as_ptr() as usize,
+            offset,
+            bytes[offset],
+            tag
+        )),
+    }
+}
+
+fn layout_of(size: usize) -> Layout {
+    // Every size here is non-zero and far below isize::MAX
+    Layout::from_size_align(size.max(1), 1).unwrap_or(Layout::new::<u8>())
+}
+
+/// Grow a pool built from `pool` by `burst` chunks past its reserve, free them
+/// all and let a reaper running every `interval` return the idle chunks.
+/// Checks the pool comes back down to exactly `min_reserve` free chunks plus
+/// the one chunk held throughout. Thread caches are disabled so every freed
+/// chunk is within the reaper's reach.
+fn check_reaper(
+    pool: &PoolConfig,
+    burst: usize,
+    min_reserve: usize,
+    interval: Duration,
+) -> Result<(), FuzzError> {
+    let allocator = "LockFreeMemoryPool";
+    let fail = |reason: String| FuzzError::Reaper { allocator, reason };
+    let config = PoolConfig {
+        initial_chunks: 0,
+        max_chunks: pool.max_chunks.max(burst + min_reserve),
+        thread_cache_size: 0,
+        ..pool.clone()
+    };
+    let target = Arc::new(
+        LockFreeMemoryPool::new(config).map_err(|source| FuzzError::Setup { allocator, source })?,
+    );
+
+    let mut held = Vec::with_capacity(burst + min_reserve);
+    for _ in 0..burst + min_reserve {
+        held.push(target.allocate_chunk().map_err(|e| fail(e.to_string()))?);
+    }
+    // Keep one chunk out while the rest go idle
+    let kept = held.pop();
+    for ptr in held {
+        target.deallocate_chunk(ptr);
+    }
+
+    target
+        .start_reaper(ReaperConfig {
+            interval,
+            min_reserve,
+        })
+        .map_err(|e| fail(format!("starting the reaper: {}", e)))?;
+
+    let chunk_size = target.chunk_size();
+    let deadline = Instant::now() + interval * REAPER_PASSES;
+    let settled = loop {
+        let stats = target.get_stats();
+        if stats.free_chunks <= min_reserve || Instant::now() >= deadline {
+            break stats;
+        }
+        std::thread::sleep(interval / 4);
+    };
+    target.stop_reaper();
+
+    let outcome = if settled.free_chunks != min_reserve {
+        Err(fail(format!(
+            "{} free chunks after {} passes, expected the reserve of {}",
+            settled.free_chunks, REAPER_PASSES, min_reserve
+        )))
+    } else if settled.total_memory_bytes != (min_reserve + settled.allocated_chunks) * chunk_size {
+        Err(fail(format!(
+            "{} bytes held after reaping, expected {}",
+            settled.total_memory_bytes,
+            (min_reserve + settled.allocated_chunks) * chunk_size
+        )))
+    } else {
+        target.verify_accounting().map_err(|e| fail(e.to_string()))
+    };
+
+    if let Some(ptr) = kept {
+        target.deallocate_chunk(ptr);
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Seed from SHRIVEN_FUZZ_SEED to replay a failure, random otherwise
+    fn seed() -> u64 {
+        std::env::var("SHRIVEN_FUZZ_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(rand::random)
+    }
+
+    fn fuzz_config(seed: u64) -> FuzzConfig {
+        FuzzConfig {
+            seed: Some(seed),
+            ..FuzzConfig::default()
+        }
+    }
+
+    fn check(seed: u64, result: Result<(), FuzzError>) {
+        if let Err(e) = result {
+            panic!("{e}\nReplay with SHRIVEN_FUZZ_SEED={seed}");
+        }
+    }
+
+    // Small pools so sequences reach exhaustion, reuse and shrinking
+    fn small_pool() -> PoolConfig {
+        PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 4,
+            max_chunks: 32,
+            thread_cache_size: 4,
+            ..PoolConfig::default()
+        }
+    }
+
+    #[test]
+    fn lock_free_pool_sequences() {
+        let seed = seed();
+        check(seed, fuzz_lock_free(&small_pool(), &fuzz_config(seed)));
+    }
+
+    #[test]
+    fn lock_free_pool_sequences_with_canaries() {
+        let seed = seed();
+        let pool = PoolConfig {
+            chunk_size: 128,
+            initial_chunks: 0,
+            max_chunks: 16,
+            thread_cache_size: 0,
+            canary: true,
+            zero_on_alloc: true,
+            check_accounting: true,
+            ..PoolConfig::default()
+        };
+        check(seed, fuzz_lock_free(&pool, &fuzz_config(seed)));
+    }
+
+    #[test]
+    fn slab_sequences() {
+        let seed = seed();
+        let slab = SlabConfig {
+            min_object_size: 64,
+            max_object_size: 1024,
+            pre_allocate_slabs: 8,
+            ..SlabConfig::default()
+        };
+        check(seed, fuzz_slab(&slab, &fuzz_config(seed)));
+    }
+
+    #[test]
+    fn slab_sequences_with_explicit_classes() {
+        let seed = seed();
+        let slab = SlabConfig {
+            min_object_size: 16,
+            max_object_size: 512,
+            pre_allocate_slabs: 4,
+            cache_align: false,
+            size_classes: Some(vec![24, 40, 96, 512]),
+            pre_allocate_per_class: Some(HashMap::from([(40, 12)])),
+            ..SlabConfig::default()
+        };
+        check(seed, fuzz_slab(&slab, &fuzz_config(seed)));
+    }
+
+    #[test]
+    fn reaper_returns_idle_chunks() {
+        check(
+            0,
+            check_reaper(&small_pool(), 64, 8, Duration::from_millis(20)),
+        );
+    }
+
+    #[test]
+    fn lock_free_pool_under_concurrent_use() {
+        let seed = seed();
+        let threads = 4;
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 256,
+            initial_chunks: 64,
+            max_chunks: 64 * threads,
+            thread_cache_size: 8,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        let result = stress_concurrent(
+            "LockFreeMemoryPool",
+            &pool,
+            &[8, 64, 256],
+            threads,
+            100_000,
+            seed,
+        );
+        check(seed, result);
+    }
+
+    #[test]
+    fn slab_under_concurrent_use() {
+        let seed = seed();
+        let threads = 4;
+        let slab = SlabAllocator::new(SlabConfig {
+            min_object_size: 64,
+            max_object_size: 512,
+            pre_allocate_slabs: 32 * threads,
+            ..SlabConfig::default()
+        })
+        .unwrap();
+        let result = stress_concurrent(
+            "SlabAllocator",
+            &slab,
+            &[1, 64, 100, 300, 512],
+            threads,
+            100_000,
+            seed,
+        );
+        check(seed, result);
+    }
+}
@@ -0,0 +1,80 @@
+// Process-wide allocator selection for ShrivenQ
+// `jemalloc` / `mimalloc` features swap the global allocator for A/B runs against our pools
+//
+// Everything outside the custom pools (Vec, HashMap, Arc, tokio) goes through the
+// global allocator, as do the backing regions the `hft-unsafe` pools carve their
+// chunks from. Pool fast paths never call it, so with `hft-unsafe` the comparison
+// is pool-vs-allocator on the hot path with identical cold-path behaviour.
+
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("features `jemalloc` and `mimalloc` both set a global allocator; enable only one");
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: jemalloc_global::Jemalloc = jemalloc_global::Jemalloc;
+
+/// Name of the allocator serving the process heap
+pub const fn global_allocator_name() -> &'static str {
+    if cfg!(feature = "jemalloc") {
+        "jemalloc"
+    } else if cfg!(feature = "mimalloc") {
+        "mimalloc"
+    } else {
+        "system"
+    }
+}
+
+// Thin `GlobalAlloc` over jemalloc's sized/aligned `*allocx` API
+#[cfg(feature = "jemalloc")]
+#[allow(unsafe_code)]
+mod jemalloc_global {
+    use jemalloc as sys;
+    use std::alloc::{GlobalAlloc, Layout};
+    use std::os::raw::{c_int, c_void};
+
+    // jemalloc's minimum alignment; smaller requests need no MALLOCX_ALIGN flag
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    const MIN_ALIGN: usize = 16;
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    const MIN_ALIGN: usize = 8;
+
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct Jemalloc;
+
+    fn flags(layout: &Layout) -> c_int {
+        if layout.align() <= MIN_ALIGN && layout.align() <= layout.size() {
+            0
+        } else {
+            sys::MALLOCX_ALIGN(layout.align())
+        }
+    }
+
+    // SAFETY: every call forwards a non-zero size (GlobalAlloc contract) and the
+    // same alignment flags on free/realloc as on allocation, as jemalloc requires.
+    unsafe impl GlobalAlloc for Jemalloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // SAFETY: layout.size() is non-zero per the GlobalAlloc contract
+            unsafe { sys::mallocx(layout.size(), flags(&layout)) as *mut u8 }
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let flags = flags(&layout) | sys::MALLOCX_ZERO;
+            // SAFETY: as for `alloc`
+            unsafe { sys::mallocx(layout.size(), flags) as *mut u8 }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            // SAFETY: ptr came from this allocator with this layout
+            unsafe { sys::sdallocx(ptr as *mut c_void, layout.size(), flags(&layout)) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            // SAFETY: ptr came from this allocator with this layout; alignment is unchanged
+            unsafe { sys::rallocx(ptr as *mut c_void, new_size, flags(&layout)) as *mut u8 }
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use crossbeam::queue::SegQueue;
-use parking_lot::Mutex;
-use std::cell::UnsafeCell;
+use crate::core::memory::TypeLayout;
+use crate::core::memory::sync::{
+    self, AtomicBool, AtomicPtr, AtomicUsize, Mutex, SegQueue, UnsafeCell, fence,
+};
+use std::cell::Cell;
 use std::collections::HashSet;
-use std::mem::MaybeUninit;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::atomic::Ordering;
+
+#[cfg(all(test, shriven_loom))]
+mod models;
 
 const MAX_HAZARD_POINTERS_PER_THREAD: usize = 8;
 const RETIRE_THRESHOLD: usize = 32;
@@ -62,13 +66,31 @@ impl HazardPointerSlot {
     }
 }
 
+// The published pointer owns a cache line; `active` and the owner spill
+// into a second one, keeping slots from sharing lines with each other.
+// Loom's instrumented atomics are larger, so the sizes only hold without it.
+#[cfg(not(shriven_loom))]
+const _: () = {
+    assert!(std::mem::size_of::<CacheAligned<AtomicPtr<u8>>>() == CACHE_LINE_SIZE);
+    assert!(std::mem::align_of::<CacheAligned<AtomicPtr<u8>>>() == CACHE_LINE_SIZE);
+    assert!(std::mem::size_of::<HazardPointerSlot>() == 2 * CACHE_LINE_SIZE);
+    assert!(std::mem::align_of::<HazardPointerSlot>() == CACHE_LINE_SIZE);
+};
+
+pub(super) fn type_layouts() -> [TypeLayout; 2] {
+    [
+        TypeLayout::of::<HazardPointerSlot>("HazardPointerSlot"),
+        TypeLayout::of::<CacheAligned<AtomicPtr<u8>>>("CacheAligned<AtomicPtr<u8>>"),
+    ]
+}
+
 unsafe impl Send for HazardPointerDomainInner {}
 unsafe impl Sync for HazardPointerDomainInner {}
 
 struct ThreadData {
     thread_id: usize,
     local_retire_list: UnsafeCell<Vec<RetiredNode>>,
-    hazard_indices: parking_lot::Mutex<Vec<usize>>,
+    hazard_indices: Mutex<Vec<usize>>,
 }
 
 struct RetiredNode {
@@ -140,39 +162,36 @@ impl HazardPointerDomain {
         panic!("No free hazard pointer slots available");
     }
 
+    // loom's `thread_local!` has no `const` initializer form
+    #[allow(clippy::missing_const_for_thread_local)]
     fn get_or_create_thread_id(&self) -> usize {
-        thread_local! {
-            static THREAD_ID: UnsafeCell<MaybeUninit<usize>> = UnsafeCell::new(MaybeUninit::uninit());
+        sync::thread_local! {
+            // 0 until the thread first touches a domain
+            static THREAD_ID: Cell<usize> = Cell::new(0);
         }
 
-        THREAD_ID.with(|id| unsafe {
-            let id_ptr = id.get();
-            // Check if thread ID is already initialized
-            if (*id_ptr).as_ptr().read() == 0 {
-                let new_id = self.inner.active_threads.fetch_add(1, Ordering::Relaxed) + 1;
-                (*id_ptr).write(new_id);
-
-                // Create thread data for this new thread
-                let thread_data = Arc::new(ThreadData {
-                    thread_id: new_id,
-                    local_retire_list: UnsafeCell::new(Vec::new()),
-                    hazard_indices: parking_lot::Mutex::new(Vec::with_capacity(
-                        MAX_HAZARD_POINTERS_PER_THREAD,
-                    )),
-                });
-
-                self.inner.thread_data.lock().push(thread_data);
-                new_id
-            } else {
-                (*id_ptr).assume_init()
+        THREAD_ID.with(|id| {
+            if id.get() != 0 {
+                return id.get();
             }
+            let new_id = self.inner.active_threads.fetch_add(1, Ordering::Relaxed) + 1;
+            id.set(new_id);
+
+            // Create thread data for this new thread
+            let thread_data = Arc::new(ThreadData {
+                thread_id: new_id,
+                local_retire_list: UnsafeCell::new(Vec::new()),
+                hazard_indices: Mutex::new(Vec::with_capacity(MAX_HAZARD_POINTERS_PER_THREAD)),
+            });
+
+            self.inner.thread_data.lock().push(thread_data);
+            new_id
         })
     }
 
     pub fn retire_ptr(&self, ptr: NonNull<u8>, size: usize, align: usize) {
-        let thread_id = self.get_or_create_thread_id();
         let ptr_addr = ptr.as_ptr() as usize;
-        let deleter: Box<dyn FnOnce() + Send + 'static> = Box::new(move || unsafe {
+        self.retire_with(ptr, move || unsafe {
             if let Ok(layout) = std::alloc::Layout::from_size_align(size, align) {
                 let ptr_to_dealloc = ptr_addr as *mut u8;
                 if !ptr_to_dealloc.is_null() {
@@ -180,22 +199,29 @@ impl HazardPointerDomain {
                 }
             }
         });
+    }
 
-        let retired = RetiredNode { ptr, deleter };
+    /// Retire `ptr`, running `deleter` once no hazard pointer protects it.
+    /// Reclamation is batched; [`reclaim`](Self::reclaim) forces a pass.
+    pub fn retire_with(&self, ptr: NonNull<u8>, deleter: impl FnOnce() + Send + 'static) {
+        let thread_id = self.get_or_create_thread_id();
+        let retired = RetiredNode {
+            ptr,
+            deleter: Box::new(deleter),
+        };
 
         // Try to find this thread's local retire list first
         if let Some(thread_data) = self.find_thread_data(thread_id) {
-            unsafe {
-                let local_list = &mut *thread_data.local_retire_list.get();
-                local_list.push(retired);
-
-                // If local list gets too big, move to global list
-                if local_list.len() >= RETIRE_THRESHOLD / 2 {
-                    for node in local_list.drain(..) {
-                        self.inner.global_retire_list.push(node);
-                    }
-                    self.try_reclaim();
-                }
+            // SAFETY: Only the owning thread touches its local retire list
+            let full = thread_data.local_retire_list.with_mut(|list| unsafe {
+                (*list).push(retired);
+                (*list).len() >= RETIRE_THRESHOLD / 2
+            });
+
+            // If local list gets too big, move to global list
+            if full {
+                self.flush_local(&thread_data);
+                self.try_reclaim();
             }
         } else {
             // Fallback to global list if thread data not found
@@ -206,6 +232,35 @@ impl HazardPointerDomain {
         }
     }
 
+    /// Returns true if any active hazard pointer currently protects `ptr`
+    pub fn is_protected(&self, ptr: NonNull<u8>) -> bool {
+        self.inner.hazard_pointers.iter().any(|slot| {
+            slot.active.load(Ordering::Acquire)
+                && slot.pointer.0.load(Ordering::Acquire) == ptr.as_ptr()
+        })
+    }
+
+    /// Free everything this thread has retired that no hazard pointer
+    /// protects, along with anything other threads left unreclaimed
+    pub fn reclaim(&self) {
+        let thread_id = self.get_or_create_thread_id();
+        if let Some(thread_data) = self.find_thread_data(thread_id) {
+            self.flush_local(&thread_data);
+        }
+        self.try_reclaim();
+    }
+
+    // Move a thread's retired nodes onto the global list; owner thread only
+    fn flush_local(&self, thread_data: &ThreadData) {
+        // SAFETY: Only the owning thread touches its local retire list
+        let local = thread_data
+            .local_retire_list
+            .with_mut(|list| unsafe { std::mem::take(&mut *list) });
+        for node in local {
+            self.inner.global_retire_list.push(node);
+        }
+    }
+
     fn find_thread_data(&self, thread_id: usize) -> Option<Arc<ThreadData>> {
         let thread_data_list = self.inner.thread_data.lock();
         thread_data_list
@@ -215,6 +270,10 @@ impl HazardPointerDomain {
     }
 
     fn try_reclaim(&self) {
+        // Pairs with the fence in `protect`: a reader whose hazard this scan
+        // misses is guaranteed to see the pointer already unlinked
+        fence(Ordering::SeqCst);
+
         let mut hazard_set = HashSet::new();
 
         for slot in &self.inner.hazard_pointers {
@@ -242,6 +301,25 @@ impl HazardPointerDomain {
     }
 }
 
+impl Drop for HazardPointerDomainInner {
+    // No hazard pointer outlives the domain, so everything still retired is
+    // unreachable and can go
+    fn drop(&mut self) {
+        for thread_data in self.thread_data.lock().drain(..) {
+            // SAFETY: The domain is being dropped, so no thread is using its lists
+            let local = thread_data
+                .local_retire_list
+                .with_mut(|list| unsafe { std::mem::take(&mut *list) });
+            for node in local {
+                (node.deleter)();
+            }
+        }
+        while let Some(node) = self.global_retire_list.pop() {
+            (node.deleter)();
+        }
+    }
+}
+
 pub struct HazardPointer<'a> {
     domain: &'a HazardPointerDomain,
     slot_index: usize,
@@ -253,7 +331,7 @@ impl<'a> HazardPointer<'a> {
         let slot = &self.domain.inner.hazard_pointers[self.slot_index];
         slot.pointer.0.store(ptr as *mut u8, Ordering::Release);
 
-        std::sync::atomic::fence(Ordering::SeqCst);
+        fence(Ordering::SeqCst);
 
         true
     }
@@ -0,0 +1,184 @@
+// Loom models of the hazard pointer domain
+// Every interleaving of protect, retire and slot claiming across two threads.
+// Run with `RUSTFLAGS="--cfg shriven_loom" cargo test --features hft-unsafe hazard_pointer::models`
+// in the dev profile; loom's coroutines need unwinding, which release aborts on.
+
+use super::HazardPointerDomain;
+use crate::core::memory::sync::{AtomicBool, AtomicPtr, AtomicUsize};
+use loom::model::Builder;
+use loom::thread;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+// Retired object that records its reclamation instead of freeing itself, so
+// touching it after reclamation fails a check rather than being undefined
+struct Node {
+    reclaimed: AtomicBool,
+}
+
+impl Node {
+    fn leak() -> NonNull<Node> {
+        NonNull::from(Box::leak(Box::new(Node {
+            reclaimed: AtomicBool::new(false),
+        })))
+    }
+
+    fn is_reclaimed(node: NonNull<Node>) -> bool {
+        // SAFETY: Nodes stay allocated until the end of the model
+        unsafe { node.as_ref() }.reclaimed.load(Ordering::Acquire)
+    }
+
+    fn retire(domain: &HazardPointerDomain, node: NonNull<Node>) {
+        let addr = node.as_ptr() as usize;
+        domain.retire_with(node.cast(), move || {
+            // SAFETY: Nodes stay allocated until the end of the model
+            unsafe { &*(addr as *const Node) }
+                .reclaimed
+                .store(true, Ordering::Release);
+        });
+    }
+
+    fn free(node: NonNull<Node>) {
+        // SAFETY: Leaked by `Node::leak`, freed once after every thread joined
+        drop(unsafe { Box::from_raw(node.as_ptr()) });
+    }
+}
+
+// LOOM_MAX_PREEMPTIONS overrides the bound; unbounded runs do not finish
+fn builder() -> Builder {
+    let mut builder = Builder::new();
+    if builder.preemption_bound.is_none() {
+        builder.preemption_bound = Some(3);
+    }
+    builder
+}
+
+/// Two threads each read the shared pointer under a hazard pointer, then
+/// swap in a fresh node and retire the old one. No node may be reclaimed
+/// while a reader holds it, which also drives `protect_ptr`'s retry when the
+/// pointer changes between the load and the re-check. Once both threads are
+/// done every retired node must be reclaimed.
+#[test]
+fn protect_while_retiring() {
+    builder().check(|| {
+        let domain = Arc::new(HazardPointerDomain::new(2));
+        let first = Node::leak();
+        let shared = Arc::new(AtomicPtr::new(first.as_ptr()));
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let domain = Arc::clone(&domain);
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    {
+                        let hazard = domain.acquire();
+                        if let Some(node) = hazard.protect_ptr(&shared) {
+                            assert!(!Node::is_reclaimed(node), "node reclaimed while protected");
+                        }
+                    }
+
+                    let fresh = Node::leak();
+                    let old = shared.swap(fresh.as_ptr(), Ordering::AcqRel);
+                    if let Some(old) = NonNull::new(old) {
+                        Node::retire(&domain, old);
+                    }
+                    domain.reclaim();
+                    fresh.as_ptr() as usize
+                })
+            })
+            .collect();
+
+        let mut nodes = vec![first];
+        for worker in workers {
+            let fresh = worker.join().unwrap_or_else(|_| panic!("worker panicked"));
+            nodes.extend(NonNull::new(fresh as *mut Node));
+        }
+
+        domain.reclaim();
+        let current = shared.load(Ordering::Acquire);
+        for &node in &nodes {
+            let retired = node.as_ptr() != current;
+            assert_eq!(
+                Node::is_reclaimed(node),
+                retired,
+                "retired node left unreclaimed, or the live node reclaimed"
+            );
+        }
+
+        drop(domain);
+        nodes.into_iter().for_each(Node::free);
+    });
+}
+
+/// Two threads claim hazard slots at once from a domain whose slot scan
+/// starts at the same index for both; the compare-exchange in
+/// `find_free_slot` must never hand one slot to both.
+#[test]
+fn slot_claims_are_exclusive() {
+    builder().check(|| {
+        let domain = Arc::new(HazardPointerDomain::new(1));
+        let slots = domain.inner.hazard_pointers.len();
+        let holders: Arc<Vec<AtomicUsize>> =
+            Arc::new((0..slots).map(|_| AtomicUsize::new(0)).collect());
+
+        let claimers: Vec<_> = (0..2)
+            .map(|_| {
+                let domain = Arc::clone(&domain);
+                let holders = Arc::clone(&holders);
+                thread::spawn(move || {
+                    let hazard = domain.acquire();
+                    let slot = &domain.inner.hazard_pointers[hazard.slot_index];
+                    assert_eq!(
+                        slot.owner_thread_id.load(Ordering::Acquire),
+                        hazard.thread_id(),
+                        "slot owned by another thread"
+                    );
+                    let held = holders[hazard.slot_index].fetch_add(1, Ordering::AcqRel);
+                    assert_eq!(held, 0, "slot {} claimed twice", hazard.slot_index);
+                    holders[hazard.slot_index].fetch_sub(1, Ordering::AcqRel);
+                })
+            })
+            .collect();
+        for claimer in claimers {
+            claimer
+                .join()
+                .unwrap_or_else(|_| panic!("claimer panicked"));
+        }
+    });
+}
+
+/// Nodes retired without a reclamation pass, some while another thread
+/// protects them, are all reclaimed when the domain is dropped.
+#[test]
+fn drop_reclaims_everything() {
+    builder().check(|| {
+        let domain = Arc::new(HazardPointerDomain::new(2));
+        let nodes = [Node::leak(), Node::leak()];
+        let shared = Arc::new(AtomicPtr::new(nodes[0].as_ptr()));
+
+        let reader = {
+            let domain = Arc::clone(&domain);
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let hazard = domain.acquire();
+                if let Some(node) = hazard.protect_ptr(&shared) {
+                    assert!(!Node::is_reclaimed(node), "node reclaimed while protected");
+                }
+            })
+        };
+
+        let old = shared.swap(nodes[1].as_ptr(), Ordering::AcqRel);
+        if let Some(old) = NonNull::new(old) {
+            Node::retire(&domain, old);
+        }
+        Node::retire(&domain, nodes[1]);
+        reader.join().unwrap_or_else(|_| panic!("reader panicked"));
+
+        drop(domain);
+        for &node in &nodes {
+            assert!(Node::is_reclaimed(node), "retired node leaked by drop");
+        }
+        nodes.into_iter().for_each(Node::free);
+    });
+}
@@ -7,27 +7,85 @@
 #![allow(unsafe_code)] // This module requires unsafe for performance
 #![deny(unsafe_op_in_unsafe_fn)] // But every unsafe op must be justified
 
-use crate::core::memory::allocator::{AllocError, MemoryAllocator};
+use crate::core::memory::TypeLayout;
+use crate::core::memory::allocator::{AccountingError, AllocError, MemoryAllocator};
+use crate::core::memory::budget;
 use crate::core::memory::hazard_pointer::HazardPointerDomain;
-use crate::core::memory::stats::{AllocationTimer, MemoryStats};
+use crate::core::memory::stats::{AllocationTimer, MemoryStats, saturating_fetch_sub};
 use crossbeam::queue::SegQueue;
-use std::alloc::{Layout, alloc, dealloc};
+use crossbeam::utils::CachePadded;
+use serde::Deserialize;
+use std::alloc::{Layout, alloc_zeroed, dealloc};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 const CACHE_LINE_SIZE: usize = 64;
 const DEFAULT_CHUNK_SIZE: usize = 4096;
 const DEFAULT_INITIAL_CHUNKS: usize = 1024;
+// allocate_chunk_timeout spins up to 2^MAX_SPIN_BACKOFF iterations, then yields
+const MAX_SPIN_BACKOFF: u32 = 6;
 
-#[derive(Clone, Debug)]
+// Canary mode layout: [padding][generation: u64][front canary] chunk [back canary]
+const CANARY_PATTERN: u64 = 0xC0DE_CAFE_F00D_BEEF;
+const CANARY_LEN: usize = std::mem::size_of::<u64>();
+const CANARY_HEADER_LEN: usize = 2 * CANARY_LEN;
+
+// Huge page size requested for `use_huge_pages` regions (x86_64 and aarch64)
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+// Threads past this many share the last per-thread stats slot
+const MAX_TRACKED_THREADS: usize = 256;
+
+// How far usage, as a share of `max_chunks`, must fall below the high-water
+// threshold before the alert can fire again
+const HIGH_WATER_HYSTERESIS: f64 = 0.05;
+
+const DEFAULT_REAPER_INTERVAL: Duration = Duration::from_secs(10);
+
+static NEXT_THREAD_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Cache slots this thread owns, flushed back to their free lists on exit
+    static CACHE_OWNER: CacheOwner = const { CacheOwner(RefCell::new(Vec::new())) };
+    // Dense process-wide index of this thread, assigned on first use
+    static THREAD_INDEX: usize = NEXT_THREAD_INDEX.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
 pub struct PoolConfig {
     pub chunk_size: usize,
     pub initial_chunks: usize,
     pub max_chunks: usize,
     pub alignment: usize,
     pub zero_on_dealloc: bool,
+    /// Zero every chunk as it is handed out, so reused chunks never carry
+    /// bytes left behind by their previous owner. New chunks always start zeroed.
+    pub zero_on_alloc: bool,
+    /// Surround every chunk with canary words and verify them on deallocate,
+    /// aborting on damage. Debugging aid for overruns; costs extra memory per chunk.
+    pub canary: bool,
+    /// Chunks each thread may keep locally before spilling back to the shared
+    /// free list. 0 disables the per-thread cache. Threads past the 256th
+    /// share one cache.
     pub thread_cache_size: usize,
+    /// Re-verify `total_memory` against the free list after every pool operation
+    /// (debug builds only). Only meaningful while the pool is used from one thread.
+    pub check_accounting: bool,
+    /// Keep allocation counters per thread, see `per_thread_stats`
+    pub track_per_thread: bool,
+    /// Back the preallocated chunks with one region of 2MB huge pages (Linux).
+    /// Falls back to regular pages with a warning when none are available;
+    /// `PoolStats::huge_pages` reports the outcome. Chunks in the region are
+    /// never returned to the OS by `shrink`.
+    pub use_huge_pages: bool,
 }
 
 impl Default for PoolConfig {
@@ -38,8 +96,153 @@ impl Default for PoolConfig {
             max_chunks: 1_000_000,
             alignment: CACHE_LINE_SIZE,
             zero_on_dealloc: false,
+            zero_on_alloc: false,
+            canary: false,
             thread_cache_size: 32,
+            check_accounting: false,
+            track_per_thread: false,
+            use_huge_pages: false,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Reject configurations `LockFreeMemoryPool::new` cannot honour.
+    /// `thread_cache_size` is not checked: 0 is valid and disables the cache.
+    pub fn validate(&self) -> Result<(), AllocError> {
+        if !self.alignment.is_power_of_two() {
+            return Err(AllocError::InvalidLayout(format!(
+                "Alignment {} must be a power of 2",
+                self.alignment
+            )));
+        }
+        if !self.chunk_size.is_power_of_two() {
+            return Err(AllocError::InvalidLayout(format!(
+                "Chunk size {} must be a power of 2",
+                self.chunk_size
+            )));
+        }
+        if self.chunk_size < CACHE_LINE_SIZE {
+            return Err(AllocError::InvalidLayout(format!(
+                "Chunk size {} is below the {}-byte cache line",
+                self.chunk_size, CACHE_LINE_SIZE
+            )));
+        }
+        if self.chunk_size < self.alignment {
+            return Err(AllocError::InvalidLayout(format!(
+                "Chunk size {} must be >= alignment {}",
+                self.chunk_size, self.alignment
+            )));
+        }
+        if self.initial_chunks > self.max_chunks {
+            return Err(AllocError::InvalidLayout(format!(
+                "Initial chunks {} exceed max chunks {}",
+                self.initial_chunks, self.max_chunks
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Settings for the background thread started by
+/// [`LockFreeMemoryPool::start_reaper`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaperConfig {
+    /// Time between passes; also the window a chunk must sit unused to count as idle
+    pub interval: Duration,
+    /// Free chunks the reaper always leaves on the free list
+    pub min_reserve: usize,
+}
+
+impl Default for ReaperConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_REAPER_INTERVAL,
+            min_reserve: 0,
+        }
+    }
+}
+
+// Wakes the reaper early when it is told to stop
+#[derive(Debug, Default)]
+struct ReaperSignal {
+    stopped: parking_lot::Mutex<bool>,
+    wake: parking_lot::Condvar,
+}
+
+impl ReaperSignal {
+    // Sleep for `interval` or until stopped; true once stopped
+    fn wait(&self, interval: Duration) -> bool {
+        let deadline = Instant::now() + interval;
+        let mut stopped = self.stopped.lock();
+        while !*stopped {
+            if self.wake.wait_until(&mut stopped, deadline).timed_out() {
+                break;
+            }
         }
+        *stopped
+    }
+
+    fn stop(&self) {
+        *self.stopped.lock() = true;
+        self.wake.notify_all();
+    }
+}
+
+#[derive(Debug)]
+struct Reaper {
+    signal: Arc<ReaperSignal>,
+    handle: JoinHandle<()>,
+}
+
+/// Allocation activity of one thread against one pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadAllocStats {
+    pub thread_index: usize,
+    pub thread_name: Option<String>,
+    pub allocations: u64,
+    pub deallocations: u64,
+    pub bytes_allocated: u64,
+    pub bytes_deallocated: u64,
+}
+
+#[derive(Debug, Default)]
+struct ThreadCounters {
+    used: AtomicBool,
+    allocations: AtomicU64,
+    deallocations: AtomicU64,
+    bytes_allocated: AtomicU64,
+    bytes_deallocated: AtomicU64,
+}
+
+#[derive(Debug)]
+struct PerThreadStats {
+    slots: Box<[ThreadCounters]>,
+    // Names captured the first time each thread touches the pool
+    names: parking_lot::Mutex<Vec<(usize, Option<String>)>>,
+}
+
+impl PerThreadStats {
+    fn new() -> Self {
+        Self {
+            slots: (0..MAX_TRACKED_THREADS)
+                .map(|_| ThreadCounters::default())
+                .collect(),
+            names: parking_lot::Mutex::new(Vec::new()),
+        }
+    }
+
+    fn current(&self) -> &ThreadCounters {
+        let index = THREAD_INDEX
+            .try_with(|index| *index)
+            .unwrap_or(MAX_TRACKED_THREADS - 1)
+            .min(MAX_TRACKED_THREADS - 1);
+        let slot = &self.slots[index];
+        if !slot.used.swap(true, Ordering::Relaxed) {
+            let name = std::thread::current().name().map(str::to_string);
+            self.names.lock().push((index, name));
+        }
+        slot
     }
 }
 
@@ -52,35 +255,382 @@ pub struct MemoryChunk {
 unsafe impl Send for MemoryChunk {}
 unsafe impl Sync for MemoryChunk {}
 
+// A chunk descriptor is three words, so free list nodes stay compact
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(std::mem::size_of::<MemoryChunk>() == 24);
+    assert!(std::mem::align_of::<MemoryChunk>() == 8);
+};
+
+pub(super) fn type_layouts() -> [TypeLayout; 1] {
+    [TypeLayout::of::<MemoryChunk>("MemoryChunk")]
+}
+
+// Free chunks of one pool: the shared queue plus a front cache per thread.
+// The caches live here rather than in thread-locals, so the pool can see and
+// reclaim every chunk it owns, and dropping it releases them all. Threads
+// hold only weak references, to flush their own cache when they exit.
+struct FreeList {
+    chunks: SegQueue<MemoryChunk>,
+    // Chunks parked in thread caches; they still count towards `free_count`
+    cached: AtomicUsize,
+    // Front caches indexed by thread index, the last shared by every thread
+    // past it; empty when `thread_cache_size` is 0
+    caches: Box<[CachePadded<parking_lot::Mutex<CacheSlot>>]>,
+    layout: Layout,
+    // Backing of the preallocated chunks when `use_huge_pages` is set
+    huge_region: Option<HugePageRegion>,
+    // Pops from the shared queue, so tests can see what the caches save
+    #[cfg(test)]
+    shared_pops: AtomicUsize,
+}
+
+impl FreeList {
+    fn in_huge_region(&self, ptr: NonNull<u8>) -> bool {
+        self.huge_region
+            .as_ref()
+            .is_some_and(|region| region.contains(ptr))
+    }
+
+    fn pop_shared(&self) -> Option<MemoryChunk> {
+        let chunk = self.chunks.pop();
+        #[cfg(test)]
+        if chunk.is_some() {
+            self.shared_pops.fetch_add(1, Ordering::Relaxed);
+        }
+        chunk
+    }
+
+    // Move one cache's chunks back to the shared queue
+    fn flush_slot(&self, index: usize) -> usize {
+        let mut slot = self.caches[index].lock();
+        let flushed = slot.chunks.len();
+        for chunk in slot.chunks.drain(..) {
+            self.chunks.push(chunk);
+        }
+        self.cached.fetch_sub(flushed, Ordering::Relaxed);
+        flushed
+    }
+
+    // Move every cached chunk back to the shared queue, waiting for threads
+    // that are using their cache
+    fn flush_caches(&self) -> usize {
+        (0..self.caches.len())
+            .map(|index| self.flush_slot(index))
+            .sum()
+    }
+}
+
+impl std::fmt::Debug for FreeList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FreeList")
+            .field("shared", &self.chunks.len())
+            .field("cached", &self.cached.load(Ordering::Relaxed))
+            .field("layout", &self.layout)
+            .field("huge_region", &self.huge_region)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for FreeList {
+    fn drop(&mut self) {
+        self.flush_caches();
+
+        let mut region_chunks = 0;
+        while let Some(chunk) = self.chunks.pop() {
+            if self.in_huge_region(chunk.ptr) {
+                region_chunks += 1;
+                continue;
+            }
+            // SAFETY: Every chunk in the free list outside the huge page region
+            // was allocated with `layout` and is not handed out to any caller
+            unsafe {
+                dealloc(chunk.ptr.as_ptr(), self.layout);
+            }
+        }
+
+        if let Some(region) = self.huge_region.take()
+            && region_chunks < region.chunks
+        {
+            // Chunks still handed out point into the region; keep it mapped
+            tracing::warn!(
+                outstanding = region.chunks - region_chunks,
+                "Leaking huge page region with chunks still in use"
+            );
+            std::mem::forget(region);
+        }
+    }
+}
+
+// Where a huge page region's memory came from, and so how to release it
+#[derive(Debug)]
+enum RegionBacking {
+    // mmap(MAP_HUGETLB), released with munmap
+    #[cfg(target_os = "linux")]
+    HugeTlb,
+    // Global allocator with this layout, released with dealloc
+    Heap(Layout),
+}
+
+// One contiguous block carved into the pool's preallocated chunks
+#[derive(Debug)]
+struct HugePageRegion {
+    ptr: NonNull<u8>,
+    len: usize,
+    // Chunks carved from the region
+    chunks: usize,
+    backing: RegionBacking,
+    // Whether the kernel backs the region with huge pages
+    huge_pages: bool,
+}
+
+// SAFETY: The region is plain memory owned by the pool; chunks carved from it
+// are handed out under the same rules as individually allocated chunks
+unsafe impl Send for HugePageRegion {}
+unsafe impl Sync for HugePageRegion {}
+
+impl HugePageRegion {
+    /// Reserve room for `chunks` chunks of `layout`: explicit huge pages
+    /// first, then a transparent-huge-page hint on a heap block. `None` if
+    /// neither can be had and plain per-chunk allocation should be used.
+    fn reserve(layout: Layout, chunks: usize) -> Option<Self> {
+        if chunks == 0 {
+            return None;
+        }
+        if layout.align() > HUGE_PAGE_SIZE {
+            tracing::warn!(
+                alignment = layout.align(),
+                "Alignment exceeds the huge page size; using regular pages"
+            );
+            return None;
+        }
+        let len = layout
+            .pad_to_align()
+            .size()
+            .checked_mul(chunks)?
+            .checked_next_multiple_of(HUGE_PAGE_SIZE)?;
+
+        Self::map_huge_tlb(len, chunks).or_else(|| Self::advise_heap(len, chunks))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn map_huge_tlb(len: usize, chunks: usize) -> Option<Self> {
+        // SAFETY: Anonymous private mapping with no address hint; the result is
+        // checked against MAP_FAILED before use
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            tracing::debug!(
+                "MAP_HUGETLB unavailable ({}); trying transparent huge pages",
+                std::io::Error::last_os_error()
+            );
+            return None;
+        }
+        Some(Self {
+            ptr: NonNull::new(ptr.cast())?,
+            len,
+            chunks,
+            backing: RegionBacking::HugeTlb,
+            huge_pages: true,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn map_huge_tlb(_len: usize, _chunks: usize) -> Option<Self> {
+        None
+    }
+
+    fn advise_heap(len: usize, chunks: usize) -> Option<Self> {
+        let layout = Layout::from_size_align(len, HUGE_PAGE_SIZE).ok()?;
+        // SAFETY: `layout` has a non-zero size and a power-of-two alignment.
+        // Zeroed, like every chunk, so handles can expose it as `[u8]`
+        let ptr = NonNull::new(unsafe { alloc_zeroed(layout) })?;
+
+        #[cfg(target_os = "linux")]
+        // SAFETY: The range is exactly the block just allocated, aligned to the
+        // huge page size; MADV_HUGEPAGE only changes how it is backed
+        let huge_pages =
+            unsafe { libc::madvise(ptr.as_ptr().cast(), len, libc::MADV_HUGEPAGE) } == 0;
+        #[cfg(not(target_os = "linux"))]
+        let huge_pages = false;
+
+        if !huge_pages {
+            tracing::warn!("Huge pages unavailable; the pool uses regular pages");
+        }
+        Some(Self {
+            ptr,
+            len,
+            chunks,
+            backing: RegionBacking::Heap(layout),
+            huge_pages,
+        })
+    }
+
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let start = self.ptr.as_ptr() as usize;
+        (start..start + self.len).contains(&(ptr.as_ptr() as usize))
+    }
+}
+
+impl Drop for HugePageRegion {
+    fn drop(&mut self) {
+        match self.backing {
+            #[cfg(target_os = "linux")]
+            // SAFETY: The region was mapped with exactly this address and length
+            RegionBacking::HugeTlb => unsafe {
+                libc::munmap(self.ptr.as_ptr().cast(), self.len);
+            },
+            // SAFETY: The region was allocated with exactly this layout
+            RegionBacking::Heap(layout) => unsafe {
+                dealloc(self.ptr.as_ptr(), layout);
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheSlot {
+    chunks: Vec<MemoryChunk>,
+    // Whether a thread has registered to flush this slot when it exits
+    owned: bool,
+}
+
+// The free lists and slot indexes whose caches this thread owns. Weak, so a
+// pool dropped before the thread exits is freed then rather than kept alive.
+struct CacheOwner(RefCell<Vec<(Weak<FreeList>, usize)>>);
+
+impl Drop for CacheOwner {
+    fn drop(&mut self) {
+        // Thread exit: hand cached chunks of live pools back to their shared lists
+        for (free_list, index) in self.0.get_mut().drain(..) {
+            if let Some(free_list) = free_list.upgrade() {
+                free_list.flush_slot(index);
+            }
+        }
+    }
+}
+
+/// Called with the pool's stats when usage crosses the high-water mark
+pub type HighWaterCallback = Box<dyn Fn(PoolStats) + Send + Sync>;
+
+// Utilization alert. The allocate path only compares the new allocated count
+// against `mark`; everything else happens once the mark is crossed.
+struct HighWater {
+    // Allocated chunks that trigger the alert; usize::MAX while unset
+    mark: AtomicUsize,
+    // Allocated chunks the pool must drop below before the alert rearms
+    rearm_below: AtomicUsize,
+    armed: AtomicBool,
+    callback: parking_lot::RwLock<Option<HighWaterCallback>>,
+}
+
+impl HighWater {
+    fn new() -> Self {
+        Self {
+            mark: AtomicUsize::new(usize::MAX),
+            rearm_below: AtomicUsize::new(0),
+            armed: AtomicBool::new(false),
+            callback: parking_lot::RwLock::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for HighWater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HighWater")
+            .field("mark", &self.mark.load(Ordering::Relaxed))
+            .field("rearm_below", &self.rearm_below.load(Ordering::Relaxed))
+            .field("armed", &self.armed.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 pub struct LockFreeMemoryPool {
     config: PoolConfig,
-    free_chunks: Arc<SegQueue<MemoryChunk>>,
+    free_list: Arc<FreeList>,
+    // Distance from the start of an allocation to the pointer handed out;
+    // non-zero only in canary mode
+    canary_offset: usize,
+    // Every live backing allocation: start address -> length in bytes
+    regions: parking_lot::RwLock<BTreeMap<usize, usize>>,
     allocated_count: AtomicUsize,
     free_count: AtomicUsize,
     total_memory: AtomicUsize,
     generation: AtomicUsize,
     hazard_domain: Arc<HazardPointerDomain>,
     stats: Arc<MemoryStats>,
+    per_thread: Option<PerThreadStats>,
+    // `try_allocate_chunk` calls that found the free list empty
+    fast_path_misses: AtomicU64,
+    high_water: HighWater,
+    // Most chunks handed out at once since the reaper's last pass
+    peak_allocated: AtomicUsize,
+    reaper: parking_lot::Mutex<Option<Reaper>>,
 }
 
 impl LockFreeMemoryPool {
     pub fn new(config: PoolConfig) -> Result<Self, AllocError> {
-        if !config.chunk_size.is_power_of_two() && config.chunk_size < CACHE_LINE_SIZE {
-            return Err(AllocError::InvalidLayout(
-                "Chunk size must be power of 2 and >= cache line size".to_string(),
-            ));
-        }
+        config.validate()?;
+
+        // Keep the handed-out pointer aligned by padding the header to the alignment
+        let canary_offset = if config.canary {
+            CANARY_HEADER_LEN.max(config.alignment)
+        } else {
+            0
+        };
+        let trailer = if config.canary { CANARY_LEN } else { 0 };
+
+        let layout = Layout::from_size_align(
+            config.chunk_size + canary_offset + trailer,
+            config.alignment,
+        )
+        .map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
+
+        let huge_region = if config.use_huge_pages {
+            HugePageRegion::reserve(layout, config.initial_chunks)
+        } else {
+            None
+        };
 
         let pool = Self {
             config: config.clone(),
-            free_chunks: Arc::new(SegQueue::new()),
+            free_list: Arc::new(FreeList {
+                chunks: SegQueue::new(),
+                cached: AtomicUsize::new(0),
+                caches: if config.thread_cache_size == 0 {
+                    Box::default()
+                } else {
+                    (0..MAX_TRACKED_THREADS)
+                        .map(|_| CachePadded::default())
+                        .collect()
+                },
+                layout,
+                huge_region,
+                #[cfg(test)]
+                shared_pops: AtomicUsize::new(0),
+            }),
+            canary_offset,
+            regions: parking_lot::RwLock::new(BTreeMap::new()),
             allocated_count: AtomicUsize::new(0),
             free_count: AtomicUsize::new(0),
             total_memory: AtomicUsize::new(0),
             generation: AtomicUsize::new(0),
             hazard_domain: Arc::new(HazardPointerDomain::new(128)),
             stats: Arc::new(MemoryStats::new()),
+            per_thread: config.track_per_thread.then(PerThreadStats::new),
+            fast_path_misses: AtomicU64::new(0),
+            high_water: HighWater::new(),
+            peak_allocated: AtomicUsize::new(0),
+            reaper: parking_lot::Mutex::new(None),
         };
 
         pool.preallocate_chunks(config.initial_chunks)?;
@@ -88,14 +638,39 @@ impl LockFreeMemoryPool {
         Ok(pool)
     }
 
+    /// Allocate a chunk wrapped in a handle that returns it to the pool on drop
+    pub fn acquire(&self) -> Result<PooledChunk<'_>, AllocError> {
+        let ptr = self.allocate_chunk()?;
+        Ok(PooledChunk { pool: self, ptr })
+    }
+
     fn preallocate_chunks(&self, count: usize) -> Result<(), AllocError> {
-        let layout = Layout::from_size_align(self.config.chunk_size, self.config.alignment)
-            .map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
+        let layout = self.free_list.layout;
+        let region = self
+            .free_list
+            .huge_region
+            .as_ref()
+            .filter(|region| region.chunks == count);
+        if let Some(region) = region {
+            self.regions
+                .write()
+                .insert(region.ptr.as_ptr() as usize, region.len);
+        }
 
-        for _ in 0..count {
-            // SAFETY: Layout is valid (checked above), alignment is power of 2
-            // The allocated memory is immediately wrapped in MemoryChunk
-            let ptr = unsafe { alloc(layout) };
+        for index in 0..count {
+            let ptr = match region {
+                // SAFETY: The region holds `count` padded chunks, so this offset
+                // stays inside it and keeps the chunk alignment
+                Some(region) => unsafe {
+                    region
+                        .ptr
+                        .as_ptr()
+                        .add(index * layout.pad_to_align().size())
+                },
+                // SAFETY: Layout is valid (checked above), alignment is power of 2
+                // The allocated memory is immediately wrapped in MemoryChunk
+                None => unsafe { alloc_zeroed(layout) },
+            };
             if ptr.is_null() {
                 return Err(AllocError::OutOfMemory);
             }
@@ -107,30 +682,94 @@ impl LockFreeMemoryPool {
                 generation: self.generation.fetch_add(1, Ordering::Relaxed) as u64,
             };
 
-            self.free_chunks.push(chunk);
+            if region.is_none() {
+                self.track_region(chunk.ptr);
+            }
+            self.free_list.chunks.push(chunk);
             self.free_count.fetch_add(1, Ordering::Relaxed);
             self.total_memory
                 .fetch_add(self.config.chunk_size, Ordering::Relaxed);
         }
 
+        self.debug_check_accounting();
         Ok(())
     }
 
     pub fn allocate_chunk(&self) -> Result<NonNull<u8>, AllocError> {
-        let timer = AllocationTimer::start();
+        self.try_allocate().map_err(|e| self.record_failure(e))
+    }
 
-        // Use hazard pointer to safely access the free list
-        let hazard = self.hazard_domain.acquire();
+    /// Like [`allocate_chunk`](Self::allocate_chunk), but when the pool is
+    /// exhausted keep retrying with exponential backoff until `timeout` elapses.
+    /// Time spent waiting is recorded in the pool's stats.
+    pub fn allocate_chunk_timeout(&self, timeout: Duration) -> Result<NonNull<u8>, AllocError> {
+        let started = Instant::now();
+        let deadline = started + timeout;
+        let mut backoff = 0u32;
 
-        if let Some(chunk) = self.free_chunks.pop() {
-            // Protect the chunk with hazard pointer during access
-            hazard.protect(chunk.ptr.as_ptr() as *const u8);
+        loop {
+            match self.try_allocate() {
+                Ok(ptr) => {
+                    if backoff > 0 {
+                        self.stats
+                            .record_allocation_wait(started.elapsed().as_nanos() as u64);
+                    }
+                    return Ok(ptr);
+                }
+                Err(AllocError::PoolExhausted) if Instant::now() < deadline => {
+                    if backoff < MAX_SPIN_BACKOFF {
+                        for _ in 0..(1u32 << backoff) {
+                            std::hint::spin_loop();
+                        }
+                    } else {
+                        std::thread::yield_now();
+                    }
+                    backoff += 1;
+                }
+                Err(e) => {
+                    if backoff > 0 {
+                        self.stats
+                            .record_allocation_wait(started.elapsed().as_nanos() as u64);
+                    }
+                    return Err(self.record_failure(e));
+                }
+            }
+        }
+    }
 
-            self.free_count.fetch_sub(1, Ordering::Relaxed);
-            self.allocated_count.fetch_add(1, Ordering::Relaxed);
-            self.stats
-                .record_allocation(self.config.chunk_size, timer.elapsed_ns());
-            return Ok(chunk.ptr);
+    // Whether a chunk can satisfy `layout`
+    pub(crate) fn check_layout(&self, layout: Layout) -> Result<(), AllocError> {
+        if layout.size() > self.config.chunk_size {
+            return Err(AllocError::SizeExceeded {
+                size: layout.size(),
+                max: self.config.chunk_size,
+            });
+        }
+        if layout.align() > self.config.alignment {
+            return Err(AllocError::AlignmentNotSupported {
+                required: layout.align(),
+                supported: self.config.alignment,
+            });
+        }
+        Ok(())
+    }
+
+    /// Take a chunk from the preallocated free list only, never asking the
+    /// OS for more memory. Returns `None` when the free list is empty, which
+    /// is counted in `PoolStats::fast_path_misses`.
+    pub fn try_allocate_chunk(&self) -> Option<NonNull<u8>> {
+        let chunk = self.pop_free_chunk(&AllocationTimer::start());
+        if chunk.is_none() {
+            self.fast_path_misses.fetch_add(1, Ordering::Relaxed);
+        }
+        chunk
+    }
+
+    // Single allocation attempt; callers decide whether a failure is recorded
+    pub(crate) fn try_allocate(&self) -> Result<NonNull<u8>, AllocError> {
+        let timer = AllocationTimer::start();
+        if let Some(ptr) = self.pop_free_chunk(&timer) {
+            return Ok(ptr);
         }
 
         let current_total =
@@ -139,25 +778,118 @@ impl LockFreeMemoryPool {
         if current_total >= self.config.max_chunks {
             return Err(AllocError::PoolExhausted);
         }
+        budget::admit_growth(self.config.chunk_size)?;
 
-        let layout = Layout::from_size_align(self.config.chunk_size, self.config.alignment)
-            .map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
-
-        let ptr = unsafe { alloc(layout) };
+        // SAFETY: The free list layout has a non-zero size. Zeroed so the
+        // chunk is initialized before anyone reads it through a `PooledChunk`
+        let ptr = unsafe { alloc_zeroed(self.free_list.layout) };
         if ptr.is_null() {
             return Err(AllocError::OutOfMemory);
         }
+        // SAFETY: We just checked ptr is not null
+        let ptr = unsafe { NonNull::new_unchecked(ptr) };
+        self.track_region(ptr);
 
-        self.allocated_count.fetch_add(1, Ordering::Relaxed);
+        self.count_allocation();
         self.total_memory
             .fetch_add(self.config.chunk_size, Ordering::Relaxed);
         self.stats
             .record_allocation(self.config.chunk_size, timer.elapsed_ns());
+        self.record_thread_allocation();
+        self.debug_check_accounting();
+
+        Ok(self.stamp_canaries(ptr))
+    }
 
-        Ok(unsafe { NonNull::new_unchecked(ptr) })
+    /// Call `callback` once usage, allocated chunks over `max_chunks`,
+    /// reaches `threshold` (0 to 1]. It fires once per crossing: usage has to
+    /// drop 5 points below the threshold before it can fire again. Replaces
+    /// any earlier callback. Runs on the allocating thread, so keep it short.
+    pub fn set_high_water_callback(
+        &self,
+        threshold: f64,
+        callback: HighWaterCallback,
+    ) -> Result<(), AllocError> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(AllocError::InvalidLayout(format!(
+                "High-water threshold {} must be in (0, 1]",
+                threshold
+            )));
+        }
+        let max = self.config.max_chunks as f64;
+        let mark = ((threshold * max).ceil() as usize).max(1);
+        // An empty pool always rearms, however low the threshold
+        let rearm_below = (((threshold - HIGH_WATER_HYSTERESIS).max(0.0) * max) as usize).max(1);
+
+        *self.high_water.callback.write() = Some(callback);
+        self.high_water
+            .rearm_below
+            .store(rearm_below, Ordering::Relaxed);
+        self.high_water.armed.store(true, Ordering::Relaxed);
+        self.high_water.mark.store(mark, Ordering::Relaxed);
+        // Already past the mark: alert now rather than on the next allocation
+        self.check_high_water(self.allocated_count.load(Ordering::Relaxed));
+        Ok(())
+    }
+
+    fn count_allocation(&self) {
+        let allocated = self.allocated_count.fetch_add(1, Ordering::Relaxed) + 1;
+        if allocated > self.peak_allocated.load(Ordering::Relaxed) {
+            self.peak_allocated.fetch_max(allocated, Ordering::Relaxed);
+        }
+        self.check_high_water(allocated);
+    }
+
+    fn check_high_water(&self, allocated: usize) {
+        if allocated < self.high_water.mark.load(Ordering::Relaxed) {
+            return;
+        }
+        // Only the thread that disarms the alert reports it
+        if self
+            .high_water
+            .armed
+            .compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+            && let Some(callback) = &*self.high_water.callback.read()
+        {
+            callback(self.get_stats());
+        }
+    }
+
+    // Hand out a chunk from the thread cache or the shared free list
+    fn pop_free_chunk(&self, timer: &AllocationTimer) -> Option<NonNull<u8>> {
+        let mut _hazard = None;
+        let chunk = match self.pop_cached_chunk() {
+            Some(chunk) => chunk,
+            None => {
+                // Use hazard pointer to safely access the free list
+                let hazard = self.hazard_domain.acquire();
+                let chunk = self.free_list.pop_shared()?;
+                // Protect the chunk with hazard pointer during access
+                hazard.protect(chunk.ptr.as_ptr() as *const u8);
+                _hazard = Some(hazard);
+                chunk
+            }
+        };
+
+        self.free_count.fetch_sub(1, Ordering::Relaxed);
+        self.count_allocation();
+        self.stats
+            .record_allocation(self.config.chunk_size, timer.elapsed_ns());
+        self.record_thread_allocation();
+        self.debug_check_accounting();
+        Some(self.zero_if_requested(self.stamp_canaries(chunk.ptr)))
     }
 
     pub fn deallocate_chunk(&self, ptr: NonNull<u8>) {
+        let timer = AllocationTimer::start();
+
+        if let Err(e) = self.check_canaries(ptr) {
+            // The heap around this chunk can no longer be trusted
+            tracing::error!("LockFreeMemoryPool: {}", e);
+            std::process::abort();
+        }
+
         if self.config.zero_on_dealloc {
             unsafe {
                 std::ptr::write_bytes(ptr.as_ptr(), 0, self.config.chunk_size);
@@ -165,84 +897,1525 @@ impl LockFreeMemoryPool {
         }
 
         let chunk = MemoryChunk {
-            ptr,
+            ptr: self.allocation_start(ptr),
             size: self.config.chunk_size,
             generation: self.generation.fetch_add(1, Ordering::Relaxed) as u64,
         };
 
-        self.free_chunks.push(chunk);
-        self.allocated_count.fetch_sub(1, Ordering::Relaxed);
+        self.push_cached_chunk(chunk);
+        let allocated = saturating_fetch_sub(&self.allocated_count, 1).saturating_sub(1);
+        if allocated < self.high_water.rearm_below.load(Ordering::Relaxed) {
+            self.high_water.armed.store(true, Ordering::Relaxed);
+        }
         self.free_count.fetch_add(1, Ordering::Relaxed);
-        self.stats.record_deallocation(self.config.chunk_size);
+        self.stats
+            .record_deallocation(self.config.chunk_size, timer.elapsed_ns());
+        if let Some(per_thread) = &self.per_thread {
+            let counters = per_thread.current();
+            counters.deallocations.fetch_add(1, Ordering::Relaxed);
+            counters
+                .bytes_deallocated
+                .fetch_add(self.config.chunk_size as u64, Ordering::Relaxed);
+        }
+        self.debug_check_accounting();
     }
 
-    pub fn get_stats(&self) -> PoolStats {
-        PoolStats {
-            allocated_chunks: self.allocated_count.load(Ordering::Relaxed),
-            free_chunks: self.free_count.load(Ordering::Relaxed),
-            total_memory_bytes: self.total_memory.load(Ordering::Relaxed),
-            chunk_size: self.config.chunk_size,
+    /// Verify the canary words around a chunk handed out by this pool.
+    /// Always succeeds when canary mode is off.
+    pub fn check_canaries(&self, ptr: NonNull<u8>) -> Result<(), AllocError> {
+        if self.canary_offset == 0 {
+            return Ok(());
         }
-    }
 
-    pub fn get_allocation_stats(&self) -> Arc<MemoryStats> {
-        Arc::clone(&self.stats)
+        let user = ptr.as_ptr();
+        // SAFETY: `ptr` came from `allocate_chunk`, so the header sits in the
+        // `canary_offset` bytes before it and the trailer right after the chunk
+        let (generation, front, back) = unsafe {
+            (
+                user.sub(CANARY_HEADER_LEN).cast::<u64>().read_unaligned(),
+                user.sub(CANARY_LEN).cast::<u64>().read_unaligned(),
+                user.add(self.config.chunk_size)
+                    .cast::<u64>()
+                    .read_unaligned(),
+            )
+        };
+
+        let region = if front != CANARY_PATTERN {
+            "front"
+        } else if back != CANARY_PATTERN {
+            "back"
+        } else {
+            return Ok(());
+        };
+
+        Err(AllocError::CanaryCorrupted {
+            address: user as usize,
+            generation,
+            region,
+        })
     }
-}
 
-impl MemoryAllocator for LockFreeMemoryPool {
-    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
-        if layout.size() > self.config.chunk_size {
-            return Err(AllocError::SizeExceeded {
-                size: layout.size(),
-                max: self.config.chunk_size,
-            });
+    // Write the header/trailer for a raw allocation and return the pointer to hand out
+    fn stamp_canaries(&self, start: NonNull<u8>) -> NonNull<u8> {
+        if self.canary_offset == 0 {
+            return start;
         }
 
-        if layout.align() > self.config.alignment {
-            return Err(AllocError::AlignmentNotSupported {
-                required: layout.align(),
-                supported: self.config.alignment,
-            });
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) as u64;
+        // SAFETY: The allocation is `canary_offset + chunk_size + CANARY_LEN`
+        // bytes long, so every write below stays inside it
+        unsafe {
+            let user = start.as_ptr().add(self.canary_offset);
+            user.sub(CANARY_HEADER_LEN)
+                .cast::<u64>()
+                .write_unaligned(generation);
+            user.sub(CANARY_LEN)
+                .cast::<u64>()
+                .write_unaligned(CANARY_PATTERN);
+            user.add(self.config.chunk_size)
+                .cast::<u64>()
+                .write_unaligned(CANARY_PATTERN);
+            NonNull::new_unchecked(user)
         }
+    }
 
-        self.allocate_chunk()
+    // Inverse of `stamp_canaries`: recover the raw allocation from a handed-out pointer
+    fn allocation_start(&self, ptr: NonNull<u8>) -> NonNull<u8> {
+        // SAFETY: Handed-out pointers sit `canary_offset` bytes into their allocation
+        unsafe { NonNull::new_unchecked(ptr.as_ptr().sub(self.canary_offset)) }
     }
 
-    fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
-        self.deallocate_chunk(ptr);
+    fn track_region(&self, start: NonNull<u8>) {
+        self.regions
+            .write()
+            .insert(start.as_ptr() as usize, self.free_list.layout.size());
     }
 
-    fn available_memory(&self) -> usize {
-        self.free_count.load(Ordering::Relaxed) * self.config.chunk_size
+    fn zero_if_requested(&self, ptr: NonNull<u8>) -> NonNull<u8> {
+        if self.config.zero_on_alloc {
+            unsafe {
+                std::ptr::write_bytes(ptr.as_ptr(), 0, self.config.chunk_size);
+            }
+        }
+        ptr
     }
 
-    fn total_memory(&self) -> usize {
-        self.total_memory.load(Ordering::Relaxed)
+    fn with_thread_cache<R>(&self, f: impl FnOnce(&mut Vec<MemoryChunk>) -> R) -> Option<R> {
+        let caches = &self.free_list.caches;
+        if caches.is_empty() {
+            return None;
+        }
+
+        // During thread teardown, or while the slot is shared with another
+        // thread or being flushed, fall back to the shared list
+        let index = THREAD_INDEX.try_with(|index| *index).ok()?;
+        let index = index.min(caches.len() - 1);
+        let mut slot = caches[index].try_lock()?;
+        if !slot.owned {
+            slot.owned = CACHE_OWNER
+                .try_with(|owner| {
+                    let Ok(mut owned) = owner.0.try_borrow_mut() else {
+                        return false;
+                    };
+                    owned.retain(|(free_list, _)| free_list.strong_count() > 0);
+                    owned.push((Arc::downgrade(&self.free_list), index));
+                    true
+                })
+                .unwrap_or(false);
+        }
+        if slot.chunks.capacity() == 0 {
+            slot.chunks.reserve(self.config.thread_cache_size + 1);
+        }
+        Some(f(&mut slot.chunks))
     }
 
-    fn max_alignment(&self) -> usize {
-        self.config.alignment
+    fn pop_cached_chunk(&self) -> Option<MemoryChunk> {
+        self.with_thread_cache(|cache| {
+            if cache.is_empty() {
+                // Refill half the cache in one batch so the next allocations stay local
+                let batch = (self.config.thread_cache_size / 2).max(1);
+                while cache.len() < batch {
+                    match self.free_list.pop_shared() {
+                        Some(chunk) => cache.push(chunk),
+                        None => break,
+                    }
+                }
+                self.free_list
+                    .cached
+                    .fetch_add(cache.len(), Ordering::Relaxed);
+            }
+
+            let chunk = cache.pop()?;
+            self.free_list.cached.fetch_sub(1, Ordering::Relaxed);
+            Some(chunk)
+        })
+        .flatten()
     }
-}
 
-impl Drop for LockFreeMemoryPool {
-    fn drop(&mut self) {
-        let layout = Layout::from_size_align(self.config.chunk_size, self.config.alignment)
-            .expect("Invalid layout in drop");
+    fn push_cached_chunk(&self, chunk: MemoryChunk) {
+        let mut chunk = Some(chunk);
 
-        while let Some(chunk) = self.free_chunks.pop() {
-            unsafe {
-                dealloc(chunk.ptr.as_ptr(), layout);
+        self.with_thread_cache(|cache| {
+            if let Some(chunk) = chunk.take() {
+                cache.push(chunk);
+                self.free_list.cached.fetch_add(1, Ordering::Relaxed);
+            }
+
+            // Overflow: spill down to half capacity in one batch
+            if cache.len() > self.config.thread_cache_size {
+                let keep = self.config.thread_cache_size / 2;
+                let spilled = cache.len() - keep;
+                for chunk in cache.drain(keep..) {
+                    self.free_list.chunks.push(chunk);
+                }
+                self.free_list.cached.fetch_sub(spilled, Ordering::Relaxed);
             }
+        });
+
+        if let Some(chunk) = chunk {
+            self.free_list.chunks.push(chunk);
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct PoolStats {
-    pub allocated_chunks: usize,
+    /// Touch every page of the free chunks so the kernel backs them now
+    /// rather than on first use in the hot path. Returns the number of chunks
+    /// touched.
+    ///
+    /// Thread caches are flushed to the shared free list first, and chunks are
+    /// taken off it while being touched, so call this at startup before the
+    /// pool is shared.
+    pub fn prefault(&self) -> usize {
+        self.free_list.flush_caches();
+        let page = page_size();
+        let len = self.free_list.layout.size();
+        let mut chunks = Vec::with_capacity(self.free_count.load(Ordering::Relaxed));
+        while let Some(chunk) = self.free_list.chunks.pop() {
+            // SAFETY: The chunk is free and owned by the pool, so nothing reads
+            // it; every offset below is inside its `len`-byte allocation
+            unsafe {
+                let start = chunk.ptr.as_ptr();
+                for offset in (0..len).step_by(page) {
+                    start.add(offset).write_volatile(0);
+                }
+                start.add(len - 1).write_volatile(0);
+            }
+            chunks.push(chunk);
+        }
+
+        let touched = chunks.len();
+        for chunk in chunks {
+            self.free_list.chunks.push(chunk);
+        }
+        tracing::debug!(chunks = touched, "LockFreeMemoryPool prefaulted");
+        touched
+    }
+
+    /// Release free chunks back to the OS until at most `target_free` remain.
+    /// Thread caches are flushed to the shared free list first, so chunks
+    /// parked in them are released too.
+    ///
+    /// Chunks still protected by a hazard pointer are retired through the hazard
+    /// domain and freed once no reader holds them. Returns the number of chunks
+    /// released.
+    pub fn shrink(&self, target_free: usize) -> usize {
+        self.free_list.flush_caches();
+        let mut released = 0;
+        // Huge page chunks cannot be released one by one; they go back at the end
+        let mut pinned = Vec::new();
+        while self.free_count.load(Ordering::Acquire) > target_free + pinned.len() {
+            let Some(chunk) = self.free_list.chunks.pop() else {
+                break;
+            };
+            if self.free_list.in_huge_region(chunk.ptr) {
+                pinned.push(chunk);
+                continue;
+            }
+            self.free_count.fetch_sub(1, Ordering::Relaxed);
+            self.total_memory
+                .fetch_sub(self.config.chunk_size, Ordering::Relaxed);
+            self.regions.write().remove(&(chunk.ptr.as_ptr() as usize));
+
+            if self.hazard_domain.is_protected(chunk.ptr) {
+                self.hazard_domain.retire_ptr(
+                    chunk.ptr,
+                    self.free_list.layout.size(),
+                    self.config.alignment,
+                );
+            } else {
+                // SAFETY: The chunk was allocated with this layout, has been removed
+                // from the free list and no hazard pointer protects it
+                unsafe {
+                    dealloc(chunk.ptr.as_ptr(), self.free_list.layout);
+                }
+            }
+            released += 1;
+        }
+        for chunk in pinned {
+            self.free_list.chunks.push(chunk);
+        }
+
+        if released > 0 {
+            tracing::debug!(
+                released,
+                free_chunks = self.free_count.load(Ordering::Relaxed),
+                "LockFreeMemoryPool shrunk"
+            );
+        }
+        self.debug_check_accounting();
+        released
+    }
+
+    /// Release every free chunk if nothing is handed out; returns the bytes freed
+    pub fn shrink_idle(&self) -> usize {
+        if self.allocated_count.load(Ordering::Acquire) > 0 {
+            return 0;
+        }
+        self.shrink(0) * self.config.chunk_size
+    }
+
+    /// Start a background thread that returns idle free chunks to the OS every
+    /// `config.interval`, replacing any reaper already running.
+    ///
+    /// A chunk is idle when the pool never needed it during the last interval:
+    /// each pass keeps as many free chunks as the peak number handed out since
+    /// the previous pass required, and never fewer than `config.min_reserve`.
+    /// The thread holds only a weak reference and exits once the pool is dropped.
+    pub fn start_reaper(self: &Arc<Self>, config: ReaperConfig) -> std::io::Result<()> {
+        if config.interval.is_zero() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "reaper interval must be non-zero",
+            ));
+        }
+
+        let mut reaper = self.reaper.lock();
+        if let Some(previous) = reaper.take() {
+            previous.stop();
+        }
+        self.peak_allocated.store(
+            self.allocated_count.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+
+        let signal = Arc::new(ReaperSignal::default());
+        let pool: Weak<Self> = Arc::downgrade(self);
+        let handle = std::thread::Builder::new()
+            .name("shriven-pool-reaper".into())
+            .spawn({
+                let signal = Arc::clone(&signal);
+                move || {
+                    while !signal.wait(config.interval) {
+                        let Some(pool) = pool.upgrade() else {
+                            break;
+                        };
+                        pool.reap(config.min_reserve);
+                    }
+                }
+            })?;
+        *reaper = Some(Reaper { signal, handle });
+
+        tracing::debug!(
+            interval = ?config.interval,
+            min_reserve = config.min_reserve,
+            "LockFreeMemoryPool reaper started"
+        );
+        Ok(())
+    }
+
+    /// Stop the reaper started by `start_reaper` and wait for its thread to
+    /// exit. Does nothing when no reaper is running.
+    pub fn stop_reaper(&self) {
+        if let Some(reaper) = self.reaper.lock().take() {
+            reaper.stop();
+        }
+    }
+
+    // One reaper pass: release the free chunks the last interval did not need
+    fn reap(&self, min_reserve: usize) -> usize {
+        let allocated = self.allocated_count.load(Ordering::Acquire);
+        let peak = self
+            .peak_allocated
+            .swap(allocated, Ordering::Relaxed)
+            .max(allocated);
+        self.shrink((peak - allocated).max(min_reserve))
+    }
+
+    /// Recompute the pool's true footprint from the free list and allocated count
+    /// and compare it against the running `total_memory` counter.
+    ///
+    /// Concurrent allocations race with the counters, so call this while the pool
+    /// is quiescent.
+    pub fn verify_accounting(&self) -> Result<(), AccountingError> {
+        let recorded_free = self.free_count.load(Ordering::Acquire);
+        let actual_free =
+            self.free_list.chunks.len() + self.free_list.cached.load(Ordering::Acquire);
+        if recorded_free != actual_free {
+            return Err(AccountingError::FreeCountMismatch {
+                recorded: recorded_free,
+                actual: actual_free,
+            });
+        }
+
+        let allocated = self.allocated_count.load(Ordering::Acquire);
+        let computed = (actual_free + allocated) * self.config.chunk_size;
+        let recorded = self.total_memory.load(Ordering::Acquire);
+        if recorded != computed {
+            return Err(AccountingError::TotalMemoryMismatch { recorded, computed });
+        }
+
+        Ok(())
+    }
+
+    fn debug_check_accounting(&self) {
+        if cfg!(debug_assertions) && self.config.check_accounting {
+            let result = self.verify_accounting();
+            debug_assert!(
+                result.is_ok(),
+                "LockFreeMemoryPool accounting desync: {:?}",
+                result
+            );
+        }
+    }
+
+    fn record_thread_allocation(&self) {
+        if let Some(per_thread) = &self.per_thread {
+            let counters = per_thread.current();
+            counters.allocations.fetch_add(1, Ordering::Relaxed);
+            counters
+                .bytes_allocated
+                .fetch_add(self.config.chunk_size as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Counters for every thread that has used this pool, by thread index.
+    /// Empty unless `PoolConfig::track_per_thread` is set.
+    pub fn per_thread_stats(&self) -> Vec<ThreadAllocStats> {
+        let Some(per_thread) = &self.per_thread else {
+            return Vec::new();
+        };
+
+        let mut stats: Vec<ThreadAllocStats> = per_thread
+            .names
+            .lock()
+            .iter()
+            .map(|(index, name)| {
+                let counters = &per_thread.slots[*index];
+                ThreadAllocStats {
+                    thread_index: *index,
+                    thread_name: name.clone(),
+                    allocations: counters.allocations.load(Ordering::Relaxed),
+                    deallocations: counters.deallocations.load(Ordering::Relaxed),
+                    bytes_allocated: counters.bytes_allocated.load(Ordering::Relaxed),
+                    bytes_deallocated: counters.bytes_deallocated.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        stats.sort_by_key(|s| s.thread_index);
+        stats
+    }
+
+    pub(crate) fn record_failure(&self, err: AllocError) -> AllocError {
+        self.stats.record_failed_allocation(&err);
+        err
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.config.chunk_size
+    }
+
+    pub fn get_stats(&self) -> PoolStats {
+        let allocated_chunks = self.allocated_count.load(Ordering::Relaxed);
+        let free_chunks = self.free_count.load(Ordering::Relaxed);
+
+        PoolStats {
+            allocated_chunks,
+            free_chunks,
+            total_memory_bytes: self.total_memory.load(Ordering::Relaxed),
+            chunk_size: self.config.chunk_size,
+            huge_pages: self.huge_pages(),
+            fast_path_misses: self.fast_path_misses.load(Ordering::Relaxed),
+            size_classes: vec![SizeClassStats {
+                chunk_size: self.config.chunk_size,
+                allocated_chunks,
+                free_chunks,
+            }],
+        }
+    }
+
+    pub fn get_allocation_stats(&self) -> Arc<MemoryStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Whether the preallocated chunks are backed by huge pages
+    pub fn huge_pages(&self) -> bool {
+        self.free_list
+            .huge_region
+            .as_ref()
+            .is_some_and(|region| region.huge_pages)
+    }
+}
+
+impl MemoryAllocator for LockFreeMemoryPool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.check_layout(layout)
+            .map_err(|e| self.record_failure(e))?;
+        self.allocate_chunk()
+    }
+
+    fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        self.deallocate_chunk(ptr);
+    }
+
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        let addr = ptr.as_ptr() as usize;
+        self.regions
+            .read()
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(&start, &len)| addr < start + len)
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        self.owns(ptr).then_some(self.config.chunk_size)
+    }
+
+    fn available_memory(&self) -> usize {
+        self.free_count.load(Ordering::Relaxed) * self.config.chunk_size
+    }
+
+    fn total_memory(&self) -> usize {
+        self.total_memory.load(Ordering::Relaxed)
+    }
+
+    fn max_alignment(&self) -> usize {
+        self.config.alignment
+    }
+}
+
+impl Reaper {
+    fn stop(self) {
+        self.signal.stop();
+        if self.handle.join().is_err() {
+            tracing::warn!("LockFreeMemoryPool reaper thread panicked");
+        }
+    }
+}
+
+impl Drop for LockFreeMemoryPool {
+    fn drop(&mut self) {
+        // The drop may run on the reaper thread itself, so signal without joining
+        if let Some(reaper) = self.reaper.get_mut().take() {
+            reaper.signal.stop();
+        }
+    }
+}
+
+/// Chunk borrowed from a [`LockFreeMemoryPool`], returned automatically on drop.
+///
+/// The handle owns the chunk exclusively, so moving it never double-frees;
+/// `std::mem::forget` leaks the chunk until the pool itself is dropped.
+pub struct PooledChunk<'a> {
+    pool: &'a LockFreeMemoryPool,
+    ptr: NonNull<u8>,
+}
+
+impl PooledChunk<'_> {
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+}
+
+impl Deref for PooledChunk<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: The chunk is `chunk_size` bytes, exclusively owned by this
+        // handle. Chunks are zeroed when first allocated (`alloc_zeroed`, or
+        // anonymous mmap for huge pages) and only ever hold bytes written since,
+        // so every byte is initialized
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.pool.config.chunk_size) }
+    }
+}
+
+impl DerefMut for PooledChunk<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: As for `deref`; `&mut self` guarantees a unique borrow
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.pool.config.chunk_size) }
+    }
+}
+
+impl Drop for PooledChunk<'_> {
+    fn drop(&mut self) {
+        self.pool.deallocate_chunk(self.ptr);
+    }
+}
+
+impl std::fmt::Debug for PooledChunk<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledChunk")
+            .field("ptr", &self.ptr)
+            .field("len", &self.pool.config.chunk_size)
+            .finish()
+    }
+}
+
+/// Pool serving several chunk sizes, backed by one lock-free pool per size class.
+/// Each allocation is served from the smallest class that fits the layout.
+#[derive(Debug)]
+pub struct MultiClassPool {
+    // Ascending chunk sizes, parallel to `pools`
+    size_classes: Vec<usize>,
+    pools: Vec<LockFreeMemoryPool>,
+}
+
+impl MultiClassPool {
+    /// Build one sub-pool per entry of `size_classes` (ascending, each a valid
+    /// `chunk_size`), sharing every other setting of `config`.
+    pub fn new(config: PoolConfig, size_classes: &[usize]) -> Result<Self, AllocError> {
+        if size_classes.is_empty() {
+            return Err(AllocError::InvalidLayout(
+                "At least one size class is required".to_string(),
+            ));
+        }
+        if size_classes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(AllocError::InvalidLayout(
+                "Size classes must be strictly ascending".to_string(),
+            ));
+        }
+
+        let pools = size_classes
+            .iter()
+            .map(|&chunk_size| {
+                LockFreeMemoryPool::new(PoolConfig {
+                    chunk_size,
+                    ..config.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            size_classes: size_classes.to_vec(),
+            pools,
+        })
+    }
+
+    fn class_index(&self, size: usize) -> Option<usize> {
+        let index = self.size_classes.partition_point(|&class| class < size);
+        (index < self.size_classes.len()).then_some(index)
+    }
+
+    /// Pool serving allocations of `size` bytes
+    fn pool_for(&self, size: usize) -> Result<&LockFreeMemoryPool, AllocError> {
+        // Single class: skip the search entirely
+        if self.pools.len() == 1 {
+            return Ok(&self.pools[0]);
+        }
+
+        self.class_index(size)
+            .map(|index| &self.pools[index])
+            .ok_or(AllocError::SizeExceeded {
+                size,
+                max: self.size_classes[self.size_classes.len() - 1],
+            })
+    }
+
+    pub fn size_classes(&self) -> &[usize] {
+        &self.size_classes
+    }
+
+    /// Prefault every class, see [`LockFreeMemoryPool::prefault`]
+    pub fn prefault(&self) -> usize {
+        self.pools.iter().map(LockFreeMemoryPool::prefault).sum()
+    }
+
+    /// Aggregate statistics; `chunk_size` reports the largest class
+    pub fn get_stats(&self) -> PoolStats {
+        let mut stats = PoolStats {
+            allocated_chunks: 0,
+            free_chunks: 0,
+            total_memory_bytes: 0,
+            chunk_size: self.size_classes[self.size_classes.len() - 1],
+            huge_pages: self.pools.iter().all(LockFreeMemoryPool::huge_pages),
+            fast_path_misses: 0,
+            size_classes: Vec::with_capacity(self.pools.len()),
+        };
+
+        for pool in &self.pools {
+            let pool_stats = pool.get_stats();
+            stats.allocated_chunks += pool_stats.allocated_chunks;
+            stats.free_chunks += pool_stats.free_chunks;
+            stats.total_memory_bytes += pool_stats.total_memory_bytes;
+            stats.fast_path_misses += pool_stats.fast_path_misses;
+            stats.size_classes.extend(pool_stats.size_classes);
+        }
+
+        stats
+    }
+}
+
+impl MemoryAllocator for MultiClassPool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.pool_for(layout.size())?.allocate(layout)
+    }
+
+    fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        match self.pool_for(layout.size()) {
+            Ok(pool) => pool.deallocate(ptr, layout),
+            Err(e) => tracing::error!("MultiClassPool deallocate with foreign layout: {}", e),
+        }
+    }
+
+    /// `layout` must route back to the class that served `ptr`, otherwise the
+    /// chunk would be pushed onto another class's free list
+    fn deallocate_checked(&self, ptr: NonNull<u8>, layout: Layout) -> Result<(), AllocError> {
+        if cfg!(debug_assertions) {
+            let address = ptr.as_ptr() as usize;
+            let recorded = self
+                .allocation_size(ptr)
+                .ok_or(AllocError::ForeignPointer { address })?;
+            let routed = self.pool_for(layout.size())?.config.chunk_size;
+            if routed != recorded {
+                return Err(AllocError::LayoutMismatch {
+                    address,
+                    requested: layout.size(),
+                    recorded,
+                });
+            }
+        }
+        self.deallocate(ptr, layout);
+        Ok(())
+    }
+
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        self.pools.iter().any(|pool| pool.owns(ptr))
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        self.pools.iter().find_map(|pool| pool.allocation_size(ptr))
+    }
+
+    fn available_memory(&self) -> usize {
+        self.pools.iter().map(|pool| pool.available_memory()).sum()
+    }
+
+    fn total_memory(&self) -> usize {
+        self.pools.iter().map(|pool| pool.total_memory()).sum()
+    }
+
+    fn max_alignment(&self) -> usize {
+        self.pools[0].max_alignment()
+    }
+}
+
+// Granularity at which the kernel backs memory on first touch
+fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        // SAFETY: sysconf has no preconditions
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if let Ok(size) = usize::try_from(size)
+            && size > 0
+        {
+            return size;
+        }
+    }
+    4096
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolStats {
+    pub allocated_chunks: usize,
     pub free_chunks: usize,
     pub total_memory_bytes: usize,
     pub chunk_size: usize,
+    /// Whether the preallocated chunks got huge pages; for a multi-class
+    /// pool, whether every class did
+    pub huge_pages: bool,
+    /// `try_allocate_chunk` calls that found no free chunk
+    pub fast_path_misses: u64,
+    /// Utilization per size class, one entry for a single-class pool
+    pub size_classes: Vec<SizeClassStats>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SizeClassStats {
+    pub chunk_size: usize,
+    pub allocated_chunks: usize,
+    pub free_chunks: usize,
+}
+
+impl SizeClassStats {
+    /// Fraction of this class's chunks currently handed out
+    pub fn utilization(&self) -> f64 {
+        let total = self.allocated_chunks + self.free_chunks;
+        if total == 0 {
+            0.0
+        } else {
+            self.allocated_chunks as f64 / total as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn pool(thread_cache_size: usize) -> LockFreeMemoryPool {
+        LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 64,
+            max_chunks: 1024,
+            thread_cache_size,
+            ..PoolConfig::default()
+        })
+        .expect("pool")
+    }
+
+    fn churn(pool: &LockFreeMemoryPool, rounds: usize) {
+        for _ in 0..rounds {
+            let chunks: Vec<_> = (0..4).map(|_| pool.allocate_chunk().unwrap()).collect();
+            for chunk in chunks {
+                pool.deallocate_chunk(chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn thread_cache_cuts_shared_pops() {
+        let uncached = pool(0);
+        churn(&uncached, 1000);
+        let cached = pool(32);
+        churn(&cached, 1000);
+
+        let before = uncached.free_list.shared_pops.load(Ordering::Relaxed);
+        let after = cached.free_list.shared_pops.load(Ordering::Relaxed);
+        assert_eq!(before, 4000);
+        assert!(after <= 16, "{after} shared pops with a thread cache");
+        cached.verify_accounting().unwrap();
+    }
+
+    fn checked_pool() -> LockFreeMemoryPool {
+        LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 8,
+            max_chunks: 64,
+            thread_cache_size: 4,
+            check_accounting: true,
+            ..PoolConfig::default()
+        })
+        .expect("pool")
+    }
+
+    #[test]
+    fn accounting_holds_across_allocate_grow_and_free() {
+        let pool = checked_pool();
+        // Past the 8 preallocated chunks, so the pool grows
+        let mut chunks: Vec<_> = (0..20).map(|_| pool.allocate_chunk().unwrap()).collect();
+        pool.verify_accounting().unwrap();
+        assert_eq!(pool.get_stats().total_memory_bytes, 20 * 64);
+
+        for chunk in chunks.drain(..12) {
+            pool.deallocate_chunk(chunk);
+        }
+        assert_eq!(pool.shrink(2), 10);
+        chunks.extend((0..5).map(|_| pool.allocate_chunk().unwrap()));
+        pool.verify_accounting().unwrap();
+
+        for chunk in chunks {
+            pool.deallocate_chunk(chunk);
+        }
+        pool.verify_accounting().unwrap();
+        let stats = pool.get_stats();
+        assert_eq!(stats.allocated_chunks, 0);
+        assert_eq!(stats.total_memory_bytes, stats.free_chunks * 64);
+    }
+
+    #[test]
+    fn injected_desync_is_reported() {
+        let pool = pool(0);
+        pool.total_memory.fetch_sub(64, Ordering::Relaxed);
+        assert_eq!(
+            pool.verify_accounting(),
+            Err(AccountingError::TotalMemoryMismatch {
+                recorded: 63 * 64,
+                computed: 64 * 64
+            })
+        );
+
+        pool.total_memory.fetch_add(64, Ordering::Relaxed);
+        pool.free_count.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(
+            pool.verify_accounting(),
+            Err(AccountingError::FreeCountMismatch {
+                recorded: 65,
+                actual: 64
+            })
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "LockFreeMemoryPool accounting desync")]
+    fn checked_pool_asserts_on_desync() {
+        let pool = checked_pool();
+        pool.total_memory.fetch_add(1, Ordering::Relaxed);
+        let _ = pool.allocate_chunk();
+    }
+
+    #[test]
+    fn pooled_chunks_return_to_the_pool_on_drop() {
+        let pool = pool(4);
+        let baseline = pool.get_stats().free_chunks;
+
+        let mut guards: Vec<_> = (0..80).map(|_| pool.acquire().unwrap()).collect();
+        // Past the preallocated chunks the pool grows; new chunks read as zero
+        assert!(guards.iter().all(|chunk| chunk.iter().all(|&b| b == 0)));
+        guards[0].fill(0xAB);
+        assert_eq!(guards[0].len(), 64);
+        assert_eq!(pool.get_stats().allocated_chunks, 80);
+
+        // Moving a handle hands over the chunk without returning it
+        let moved = guards.pop().unwrap();
+        let elsewhere = vec![moved];
+        assert_eq!(pool.get_stats().allocated_chunks, 80);
+        drop(elsewhere);
+        drop(guards);
+        let stats = pool.get_stats();
+        assert_eq!(stats.allocated_chunks, 0);
+        assert_eq!(stats.free_chunks, baseline + 16);
+        pool.verify_accounting().unwrap();
+
+        std::mem::forget(pool.acquire().unwrap());
+        assert_eq!(pool.get_stats().allocated_chunks, 1);
+    }
+
+    // Runs `work` on a thread that stays alive until the returned sender is used
+    fn parked_thread(
+        pool: &Arc<LockFreeMemoryPool>,
+        work: fn(&LockFreeMemoryPool),
+    ) -> (mpsc::Sender<()>, std::thread::JoinHandle<()>) {
+        let (done_tx, done_rx) = mpsc::channel();
+        let (exit_tx, exit_rx) = mpsc::channel::<()>();
+        let pool = Arc::clone(pool);
+        let handle = std::thread::spawn(move || {
+            work(&pool);
+            drop(pool);
+            done_tx.send(()).unwrap();
+            let _ = exit_rx.recv();
+        });
+        done_rx.recv().unwrap();
+        (exit_tx, handle)
+    }
+
+    #[test]
+    fn shrink_reclaims_chunks_cached_by_live_threads() {
+        let pool = Arc::new(pool(32));
+        let (exit, handle) = parked_thread(&pool, |pool| churn(pool, 10));
+        assert!(pool.free_list.cached.load(Ordering::Relaxed) > 0);
+
+        assert_eq!(pool.shrink(0), 64);
+        assert_eq!(pool.get_stats().free_chunks, 0);
+        assert_eq!(pool.free_list.cached.load(Ordering::Relaxed), 0);
+        pool.verify_accounting().unwrap();
+
+        exit.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn thread_exit_returns_cached_chunks() {
+        let pool = Arc::new(pool(32));
+        let (exit, handle) = parked_thread(&pool, |pool| churn(pool, 10));
+        exit.send(()).unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(pool.free_list.cached.load(Ordering::Relaxed), 0);
+        assert_eq!(pool.free_list.chunks.len(), 64);
+        pool.verify_accounting().unwrap();
+    }
+
+    #[test]
+    fn dropped_pool_is_freed_while_threads_live() {
+        let pool = Arc::new(pool(32));
+        let (exit, handle) = parked_thread(&pool, |pool| churn(pool, 10));
+        let free_list = Arc::downgrade(&pool.free_list);
+
+        drop(pool);
+        // Nothing but the pool held the chunks, though the thread still runs
+        assert!(free_list.upgrade().is_none());
+
+        exit.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn repeated_pools_do_not_accumulate_in_thread_locals() {
+        for _ in 0..100 {
+            churn(&pool(32), 1);
+        }
+        let owned = CACHE_OWNER.with(|owner| owner.0.borrow().len());
+        assert!(owned <= 1, "{owned} cache registrations kept");
+    }
+
+    #[test]
+    fn writing_outside_a_chunk_trips_its_canary() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 2,
+            max_chunks: 2,
+            canary: true,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        let ptr = pool.allocate_chunk().unwrap();
+        let user = ptr.as_ptr();
+
+        // SAFETY: The whole chunk belongs to the caller
+        unsafe { user.write_bytes(0x11, 64) };
+        pool.check_canaries(ptr).unwrap();
+
+        // SAFETY: Canary mode puts a trailer word right after the chunk
+        unsafe { user.add(64).write(0x11) };
+        let err = pool.check_canaries(ptr).unwrap_err();
+        assert!(matches!(
+            err,
+            AllocError::CanaryCorrupted { address, region: "back", .. } if address == user as usize
+        ));
+        assert!(err.to_string().contains(&format!("{:#x}", user as usize)));
+        assert!(err.to_string().contains("generation"));
+
+        // SAFETY: Restores the trailer, then clobbers the word before the
+        // chunk, which canary mode also reserves
+        unsafe {
+            user.add(64).cast::<u64>().write_unaligned(CANARY_PATTERN);
+            user.sub(1).write(0x11);
+        }
+        assert!(matches!(
+            pool.check_canaries(ptr),
+            Err(AllocError::CanaryCorrupted {
+                region: "front",
+                ..
+            })
+        ));
+
+        // SAFETY: As above; with both words intact the chunk can go back
+        unsafe {
+            user.sub(CANARY_LEN)
+                .cast::<u64>()
+                .write_unaligned(CANARY_PATTERN)
+        };
+        pool.check_canaries(ptr).unwrap();
+        pool.deallocate_chunk(ptr);
+        pool.verify_accounting().unwrap();
+    }
+
+    #[test]
+    fn timed_allocation_waits_for_a_chunk_to_be_freed() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 1,
+            max_chunks: 1,
+            thread_cache_size: 0,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        let held = pool.allocate_chunk().unwrap();
+        assert!(matches!(
+            pool.allocate_chunk(),
+            Err(AllocError::PoolExhausted)
+        ));
+        assert!(matches!(
+            pool.allocate_chunk_timeout(Duration::from_millis(5)),
+            Err(AllocError::PoolExhausted)
+        ));
+
+        // NonNull is not Send, so the address crosses threads
+        let address = held.as_ptr() as usize;
+        let waited = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                pool.deallocate_chunk(NonNull::new(address as *mut u8).unwrap());
+            });
+            pool.allocate_chunk_timeout(Duration::from_secs(5))
+        })
+        .unwrap();
+        assert_eq!(waited.as_ptr() as usize, address);
+
+        // Both the timed-out wait and the successful one are counted
+        let stats = pool.get_allocation_stats().get_snapshot();
+        assert_eq!(stats.blocked_allocations, 2);
+        assert!(stats.total_wait_ns >= 20_000_000, "{}", stats.total_wait_ns);
+        pool.deallocate_chunk(waited);
+    }
+
+    #[test]
+    fn reused_chunks_are_zeroed_only_when_asked() {
+        for zero_on_alloc in [false, true] {
+            let pool = LockFreeMemoryPool::new(PoolConfig {
+                chunk_size: 64,
+                initial_chunks: 1,
+                max_chunks: 1,
+                zero_on_alloc,
+                ..PoolConfig::default()
+            })
+            .unwrap();
+            pool.acquire().unwrap().fill(0xCD);
+
+            let reused = pool.acquire().unwrap();
+            let expected = if zero_on_alloc { 0 } else { 0xCD };
+            assert!(reused.iter().all(|&byte| byte == expected));
+        }
+    }
+
+    #[test]
+    fn multi_class_pool_serves_each_size_from_the_smallest_fitting_class() {
+        let pool = MultiClassPool::new(
+            PoolConfig {
+                initial_chunks: 8,
+                max_chunks: 64,
+                ..PoolConfig::default()
+            },
+            &[64, 256, 1024],
+        )
+        .unwrap();
+
+        let layouts: Vec<_> = [32, 64, 65, 200, 1000]
+            .into_iter()
+            .map(|size| Layout::from_size_align(size, 8).unwrap())
+            .collect();
+        let ptrs: Vec<_> = layouts
+            .iter()
+            .map(|&layout| pool.allocate(layout).unwrap())
+            .collect();
+        let classes: Vec<_> = ptrs
+            .iter()
+            .map(|&ptr| pool.allocation_size(ptr).unwrap())
+            .collect();
+        assert_eq!(classes, [64, 64, 256, 256, 1024]);
+
+        let stats = pool.get_stats();
+        let allocated: Vec<_> = stats
+            .size_classes
+            .iter()
+            .map(|class| (class.chunk_size, class.allocated_chunks))
+            .collect();
+        assert_eq!(allocated, [(64, 2), (256, 2), (1024, 1)]);
+        assert_eq!(stats.allocated_chunks, 5);
+        assert!((stats.size_classes[2].utilization() - 1.0 / 8.0).abs() < 1e-9);
+
+        assert!(matches!(
+            pool.allocate(Layout::from_size_align(2000, 8).unwrap()),
+            Err(AllocError::SizeExceeded {
+                size: 2000,
+                max: 1024
+            })
+        ));
+        if cfg!(debug_assertions) {
+            assert!(matches!(
+                pool.deallocate_checked(ptrs[4], layouts[0]),
+                Err(AllocError::LayoutMismatch { recorded: 1024, .. })
+            ));
+        }
+
+        for (ptr, layout) in ptrs.into_iter().zip(layouts) {
+            pool.deallocate_checked(ptr, layout).unwrap();
+        }
+        assert_eq!(pool.get_stats().allocated_chunks, 0);
+    }
+
+    #[test]
+    fn single_class_pool_skips_the_class_search() {
+        let pool = MultiClassPool::new(PoolConfig::default(), &[128]).unwrap();
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let ptr = pool.allocate(layout).unwrap();
+        assert_eq!(pool.allocation_size(ptr), Some(128));
+        pool.deallocate(ptr, layout);
+
+        assert!(MultiClassPool::new(PoolConfig::default(), &[]).is_err());
+        assert!(MultiClassPool::new(PoolConfig::default(), &[256, 64]).is_err());
+    }
+
+    #[test]
+    fn per_thread_stats_attribute_each_threads_allocations() {
+        let tracked = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 64,
+            max_chunks: 1024,
+            track_per_thread: true,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            for worker in 1..=3 {
+                let tracked = &tracked;
+                std::thread::Builder::new()
+                    .name(format!("worker-{worker}"))
+                    .spawn_scoped(scope, move || churn(tracked, worker))
+                    .unwrap();
+            }
+        });
+
+        let stats = tracked.per_thread_stats();
+        assert_eq!(stats.len(), 3);
+        for worker in 1..=3u64 {
+            let name = format!("worker-{worker}");
+            let thread = stats
+                .iter()
+                .find(|s| s.thread_name.as_deref() == Some(name.as_str()))
+                .unwrap();
+            assert_eq!(
+                (thread.allocations, thread.deallocations),
+                (4 * worker, 4 * worker)
+            );
+            assert_eq!(thread.bytes_allocated, 4 * worker * 64);
+            assert_eq!(thread.bytes_deallocated, 4 * worker * 64);
+        }
+
+        let untracked = pool(32);
+        churn(&untracked, 1);
+        assert!(untracked.per_thread_stats().is_empty());
+    }
+
+    #[test]
+    fn fast_path_never_grows_a_drained_pool() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 8,
+            max_chunks: 64,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        let preallocated = pool.get_stats().total_memory_bytes;
+
+        let mut chunks: Vec<_> = (0..8).map(|_| pool.try_allocate_chunk().unwrap()).collect();
+        assert!(pool.try_allocate_chunk().is_none());
+        assert!(pool.try_allocate_chunk().is_none());
+        let stats = pool.get_stats();
+        assert_eq!(stats.total_memory_bytes, preallocated);
+        assert_eq!((stats.allocated_chunks, stats.fast_path_misses), (8, 2));
+
+        // The regular path still grows, and a freed chunk serves the fast path again
+        chunks.push(pool.allocate_chunk().unwrap());
+        assert_eq!(pool.get_stats().total_memory_bytes, preallocated + 64);
+        pool.deallocate_chunk(chunks.pop().unwrap());
+        chunks.push(pool.try_allocate_chunk().unwrap());
+        assert_eq!(pool.get_stats().fast_path_misses, 2);
+
+        for chunk in chunks {
+            pool.deallocate_chunk(chunk);
+        }
+        pool.verify_accounting().unwrap();
+    }
+
+    #[test]
+    fn high_water_callback_fires_once_per_crossing() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64,
+            initial_chunks: 20,
+            max_chunks: 20,
+            thread_cache_size: 0,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        assert!(pool.set_high_water_callback(1.5, Box::new(|_| {})).is_err());
+        let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        pool.set_high_water_callback(
+            0.8,
+            Box::new(move |stats| sink.lock().push(stats.allocated_chunks)),
+        )
+        .unwrap();
+
+        let mut chunks: Vec<_> = (0..19).map(|_| pool.allocate_chunk().unwrap()).collect();
+        assert_eq!(*seen.lock(), [16]);
+
+        // Dipping to 16 is within the hysteresis band, so no second alert
+        for chunk in chunks.drain(16..) {
+            pool.deallocate_chunk(chunk);
+        }
+        chunks.extend((0..3).map(|_| pool.allocate_chunk().unwrap()));
+        assert_eq!(*seen.lock(), [16]);
+
+        // Falling below 75% rearms it
+        for chunk in chunks.drain(14..) {
+            pool.deallocate_chunk(chunk);
+        }
+        chunks.extend((0..2).map(|_| pool.allocate_chunk().unwrap()));
+        assert_eq!(*seen.lock(), [16, 16]);
+
+        for chunk in chunks {
+            pool.deallocate_chunk(chunk);
+        }
+    }
+
+    // Minor page faults taken by the calling thread so far
+    #[cfg(target_os = "linux")]
+    fn minor_faults() -> i64 {
+        // SAFETY: getrusage only writes the zeroed struct it is handed
+        unsafe {
+            let mut usage: libc::rusage = std::mem::zeroed();
+            assert_eq!(libc::getrusage(libc::RUSAGE_THREAD, &mut usage), 0);
+            usage.ru_minflt
+        }
+    }
+
+    // Allocate `count` chunks and write each of their pages, as a first use would
+    #[cfg(target_os = "linux")]
+    fn first_use(
+        pool: &LockFreeMemoryPool,
+        count: usize,
+        chunks: &mut Vec<NonNull<u8>>,
+    ) -> Duration {
+        let start = Instant::now();
+        for _ in 0..count {
+            let chunk = pool.allocate_chunk().unwrap();
+            // SAFETY: the chunk is `chunk_size` writable bytes owned by this test
+            unsafe {
+                for offset in (0..pool.chunk_size()).step_by(page_size()) {
+                    chunk.as_ptr().add(offset).write_volatile(1);
+                }
+            }
+            chunks.push(chunk);
+        }
+        start.elapsed()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn prefaulted_chunks_take_no_page_faults_on_first_use() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 64 * 1024,
+            initial_chunks: 64,
+            max_chunks: 64,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        assert_eq!(pool.prefault(), 64);
+        pool.verify_accounting().unwrap();
+
+        let mut chunks = Vec::with_capacity(64);
+        let faults = minor_faults();
+        let first = first_use(&pool, 64, &mut chunks);
+        let first_faults = minor_faults() - faults;
+        // 1024 pages were touched; none of them should fault in
+        assert!(
+            first_faults < 16,
+            "{first_faults} page faults after prefault"
+        );
+
+        for chunk in chunks.drain(..) {
+            pool.deallocate_chunk(chunk);
+        }
+        let steady = first_use(&pool, 64, &mut chunks);
+        assert!(
+            first < steady * 4 + Duration::from_millis(1),
+            "first use took {first:?}, steady state {steady:?}"
+        );
+        for chunk in chunks {
+            pool.deallocate_chunk(chunk);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn huge_page_pool_allocates_with_or_without_huge_pages() {
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 4096,
+            initial_chunks: 64,
+            max_chunks: 128,
+            use_huge_pages: true,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        assert_eq!(pool.get_stats().huge_pages, pool.huge_pages());
+
+        // Past the preallocated region the pool grows from the heap as usual
+        let chunks: Vec<_> = (0..72).map(|_| pool.allocate_chunk().unwrap()).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            // SAFETY: each chunk is 4096 writable bytes owned by this test
+            unsafe { std::ptr::write_bytes(chunk.as_ptr(), i as u8, 4096) };
+        }
+        for chunk in chunks {
+            pool.deallocate_chunk(chunk);
+        }
+        pool.verify_accounting().unwrap();
+
+        // Region chunks stay put until the pool is dropped
+        let region = pool.free_list.huge_region.is_some();
+        assert_eq!(pool.shrink(0), if region { 8 } else { 72 });
+        pool.verify_accounting().unwrap();
+
+        let plain = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 4096,
+            initial_chunks: 4,
+            max_chunks: 8,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        assert!(!plain.get_stats().huge_pages);
+    }
+
+    #[test]
+    fn each_invalid_config_is_rejected() {
+        let valid = PoolConfig {
+            chunk_size: 128,
+            alignment: 64,
+            initial_chunks: 4,
+            max_chunks: 8,
+            ..PoolConfig::default()
+        };
+        assert!(valid.validate().is_ok());
+
+        let cases = [
+            (
+                "alignment",
+                PoolConfig {
+                    alignment: 48,
+                    ..valid.clone()
+                },
+            ),
+            // At least a cache line, so this slipped past the old `&&` check
+            (
+                "chunk size 96",
+                PoolConfig {
+                    chunk_size: 96,
+                    ..valid.clone()
+                },
+            ),
+            (
+                "cache line",
+                PoolConfig {
+                    chunk_size: 32,
+                    alignment: 16,
+                    ..valid.clone()
+                },
+            ),
+            (
+                "alignment 256",
+                PoolConfig {
+                    alignment: 256,
+                    ..valid.clone()
+                },
+            ),
+            (
+                "initial chunks",
+                PoolConfig {
+                    initial_chunks: 9,
+                    ..valid.clone()
+                },
+            ),
+        ];
+        for (case, config) in cases {
+            assert!(config.validate().is_err(), "{case} accepted");
+            assert!(
+                matches!(
+                    LockFreeMemoryPool::new(config),
+                    Err(AllocError::InvalidLayout(_))
+                ),
+                "{case} built a pool"
+            );
+        }
+    }
+
+    #[test]
+    fn chunk_sizes_must_be_powers_of_two_and_span_a_cache_line() {
+        let with_chunk = |chunk_size| {
+            LockFreeMemoryPool::new(PoolConfig {
+                chunk_size,
+                alignment: 8,
+                initial_chunks: 1,
+                max_chunks: 4,
+                ..PoolConfig::default()
+            })
+        };
+
+        let Err(AllocError::InvalidLayout(odd)) = with_chunk(5000) else {
+            panic!("5000-byte chunks accepted");
+        };
+        assert!(odd.contains("power of 2"), "{odd}");
+        let Err(AllocError::InvalidLayout(small)) = with_chunk(32) else {
+            panic!("32-byte chunks accepted");
+        };
+        assert!(small.contains("cache line"), "{small}");
+        assert_eq!(with_chunk(4096).unwrap().chunk_size(), 4096);
+    }
+
+    fn reaped_pool() -> Arc<LockFreeMemoryPool> {
+        Arc::new(
+            LockFreeMemoryPool::new(PoolConfig {
+                chunk_size: 64,
+                initial_chunks: 4,
+                max_chunks: 256,
+                thread_cache_size: 0,
+                ..PoolConfig::default()
+            })
+            .unwrap(),
+        )
+    }
+
+    fn grow_and_release(pool: &LockFreeMemoryPool, chunks: usize) {
+        let held: Vec<_> = (0..chunks)
+            .map(|_| pool.allocate_chunk().unwrap())
+            .collect();
+        for chunk in held {
+            pool.deallocate_chunk(chunk);
+        }
+    }
+
+    #[test]
+    fn reaper_reclaims_idle_chunks_down_to_the_reserve() {
+        let pool = reaped_pool();
+        grow_and_release(&pool, 100);
+        assert_eq!(pool.total_memory(), 100 * 64);
+
+        let config = ReaperConfig {
+            interval: Duration::from_millis(10),
+            min_reserve: 8,
+        };
+        pool.start_reaper(config).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pool.total_memory() > 8 * 64 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(pool.total_memory(), 8 * 64);
+
+        // Later passes leave the reserve alone
+        std::thread::sleep(config.interval * 5);
+        assert_eq!(pool.total_memory(), 8 * 64);
+        pool.verify_accounting().unwrap();
+
+        pool.stop_reaper();
+        grow_and_release(&pool, 50);
+        std::thread::sleep(config.interval * 5);
+        assert_eq!(pool.total_memory(), 50 * 64);
+        assert!(
+            pool.start_reaper(ReaperConfig {
+                interval: Duration::ZERO,
+                ..config
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn reaping_keeps_what_the_last_interval_needed() {
+        let pool = reaped_pool();
+        let mut held: Vec<_> = (0..30).map(|_| pool.allocate_chunk().unwrap()).collect();
+        for chunk in held.drain(10..) {
+            pool.deallocate_chunk(chunk);
+        }
+
+        // The interval peaked at 30 handed out, so the 20 free chunks stay
+        assert_eq!(pool.reap(4), 0);
+        assert_eq!(pool.total_memory(), 30 * 64);
+        // A quiet interval needs none of them beyond the reserve
+        assert_eq!(pool.reap(4), 16);
+        assert_eq!(pool.total_memory(), (10 + 4) * 64);
+
+        for chunk in held {
+            pool.deallocate_chunk(chunk);
+        }
+    }
 }
@@ -9,11 +9,22 @@
 #![deny(clippy::missing_safety_doc)] // Every unsafe fn must explain invariants
 
 pub mod allocator;
+pub mod budget;
+pub mod clock;
+pub mod config;
+pub mod global_alloc;
 pub mod safe_pool;
 pub mod stats;
+pub mod topology;
 
 // Conditionally compile unsafe modules only with hft-unsafe feature
 #[cfg(feature = "hft-unsafe")]
+pub mod arena;
+#[cfg(feature = "hft-unsafe")]
+pub mod async_pool;
+#[cfg(all(test, feature = "hft-unsafe", feature = "testing"))]
+mod fuzz;
+#[cfg(feature = "hft-unsafe")]
 pub mod hazard_pointer;
 #[cfg(feature = "hft-unsafe")]
 pub mod lock_free_pool;
@@ -21,21 +32,74 @@ pub mod lock_free_pool;
 pub mod numa_allocator;
 #[cfg(feature = "hft-unsafe")]
 pub mod slab_allocator;
+#[cfg(feature = "hft-unsafe")]
+pub mod spsc_ring;
+#[cfg(feature = "hft-unsafe")]
+pub(crate) mod sync;
 
 // Always export safe interfaces
-pub use allocator::{AllocError, MemoryAllocator};
+pub use allocator::{AccountingError, AllocError, MemoryAllocator};
+pub use budget::{BudgetConfig, MemoryBudget};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use config::{BackendKind, MemoryConfig};
+pub use global_alloc::global_allocator_name;
 pub use safe_pool::{SafeMemoryPool, SafePoolConfig};
-pub use stats::MemoryStats;
+pub use stats::{AllocationCounters, HistogramConfig, MemoryStats};
+pub use topology::{NumaNode, NumaTopology, TopologySource};
 
 // Conditionally export unsafe module interfaces
 #[cfg(feature = "hft-unsafe")]
+pub use arena::Arena;
+#[cfg(feature = "hft-unsafe")]
+pub use async_pool::AsyncPool;
+#[cfg(feature = "hft-unsafe")]
 pub use hazard_pointer::HazardPointerDomain;
 #[cfg(feature = "hft-unsafe")]
-pub use lock_free_pool::{LockFreeMemoryPool, PoolConfig};
+pub use lock_free_pool::{
+    HighWaterCallback, LockFreeMemoryPool, MultiClassPool, PoolConfig, PooledChunk, ReaperConfig,
+    ThreadAllocStats,
+};
 #[cfg(feature = "hft-unsafe")]
 pub use numa_allocator::{NumaAllocator, NumaConfig};
 #[cfg(feature = "hft-unsafe")]
 pub use slab_allocator::{SlabAllocator, SlabConfig};
+#[cfg(feature = "hft-unsafe")]
+pub use spsc_ring::{SpscConsumer, SpscProducer, SpscRing};
+
+use serde::Serialize;
+use std::alloc::Layout;
+use std::ptr::NonNull;
+
+/// Size and alignment of one type on this target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TypeLayout {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+}
+
+impl TypeLayout {
+    pub const fn of<T>(name: &'static str) -> Self {
+        Self {
+            name,
+            size: std::mem::size_of::<T>(),
+            align: std::mem::align_of::<T>(),
+        }
+    }
+}
+
+/// Layout of the allocator structures whose size matters for cache
+/// behaviour. Empty without the `hft-unsafe` feature.
+pub fn layout_report() -> Vec<TypeLayout> {
+    #[cfg(feature = "hft-unsafe")]
+    {
+        let mut report = lock_free_pool::type_layouts().to_vec();
+        report.extend(hazard_pointer::type_layouts());
+        report
+    }
+    #[cfg(not(feature = "hft-unsafe"))]
+    Vec::new()
+}
 
 /// Unified memory backend that can switch between safe and high-performance implementations
 #[derive(Debug)]
@@ -62,6 +126,26 @@ impl MemoryBackend {
         Ok(MemoryBackend::Safe(SafeMemoryPool::new(config)?))
     }
 
+    /// Build the backend `config.backend` names from its section of `config`.
+    /// Fails with `UnsupportedOperation` for an `hft-unsafe` backend in a
+    /// build without that feature.
+    pub fn from_config(config: &MemoryConfig) -> Result<Self, AllocError> {
+        match config.backend {
+            BackendKind::Safe => Self::safe(config.safe),
+            #[cfg(feature = "hft-unsafe")]
+            BackendKind::LockFree => Self::lock_free(config.lock_free.clone()),
+            #[cfg(feature = "hft-unsafe")]
+            BackendKind::Numa => Self::numa(config.numa.clone()),
+            #[cfg(feature = "hft-unsafe")]
+            BackendKind::Slab => Self::slab(config.slab.clone()),
+            #[cfg(not(feature = "hft-unsafe"))]
+            kind => Err(AllocError::UnsupportedOperation(format!(
+                "The {} memory backend requires the hft-unsafe feature",
+                kind
+            ))),
+        }
+    }
+
     /// Create a lock-free memory backend (requires hft-unsafe feature)
     #[cfg(feature = "hft-unsafe")]
     pub fn lock_free(config: PoolConfig) -> Result<Self, AllocError> {
@@ -101,4 +185,221 @@ impl MemoryBackend {
             MemoryBackend::Slab(_) => "Slab",
         }
     }
+
+    /// Release the free chunks of pools with nothing handed out, for
+    /// [`MemoryBudget`]; returns the bytes freed. Slab memory is fixed.
+    pub fn shrink_idle(&self) -> usize {
+        match self {
+            MemoryBackend::Safe(pool) => pool.shrink_idle(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.shrink_idle(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.shrink_idle(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(_) => 0,
+        }
+    }
+
+    /// Pin the calling thread to `cpus`. The NUMA backend also binds the
+    /// thread to the node owning them so it allocates locally.
+    pub fn pin_current_thread(&self, cpus: &[usize]) -> Result<(), AllocError> {
+        #[cfg(feature = "hft-unsafe")]
+        if let MemoryBackend::Numa(allocator) = self {
+            return allocator.bind_current_thread_to_cpus(cpus).map(|_| ());
+        }
+        crate::core::cpu::affinity::set_current_thread_affinity(cpus).map_err(|e| {
+            AllocError::UnsupportedOperation(format!(
+                "Pinning thread to CPUs {:?} failed: {}",
+                cpus, e
+            ))
+        })
+    }
+}
+
+impl MemoryAllocator for MemoryBackend {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        match self {
+            MemoryBackend::Safe(pool) => pool.allocate(layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.allocate(layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.allocate(layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.allocate(layout),
+        }
+    }
+
+    fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        match self {
+            MemoryBackend::Safe(pool) => pool.deallocate(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.deallocate(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.deallocate(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.deallocate(ptr, layout),
+        }
+    }
+
+    fn deallocate_checked(&self, ptr: NonNull<u8>, layout: Layout) -> Result<(), AllocError> {
+        match self {
+            MemoryBackend::Safe(pool) => pool.deallocate_checked(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.deallocate_checked(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.deallocate_checked(ptr, layout),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.deallocate_checked(ptr, layout),
+        }
+    }
+
+    fn max_alignment(&self) -> usize {
+        match self {
+            MemoryBackend::Safe(pool) => pool.max_alignment(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.max_alignment(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.max_alignment(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.max_alignment(),
+        }
+    }
+
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        match self {
+            MemoryBackend::Safe(pool) => pool.owns(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.owns(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.owns(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.owns(ptr),
+        }
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        match self {
+            MemoryBackend::Safe(pool) => pool.allocation_size(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.allocation_size(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.allocation_size(ptr),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.allocation_size(ptr),
+        }
+    }
+
+    fn available_memory(&self) -> usize {
+        match self {
+            MemoryBackend::Safe(pool) => pool.available_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.available_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.available_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.available_memory(),
+        }
+    }
+
+    fn total_memory(&self) -> usize {
+        match self {
+            MemoryBackend::Safe(pool) => pool.total_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::LockFree(pool) => pool.total_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Numa(allocator) => allocator.total_memory(),
+            #[cfg(feature = "hft-unsafe")]
+            MemoryBackend::Slab(allocator) => allocator.total_memory(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "hft-unsafe", target_arch = "x86_64"))]
+    #[test]
+    fn layout_report_matches_the_x86_64_layouts() {
+        let layout = |name, size, align| TypeLayout { name, size, align };
+        assert_eq!(
+            layout_report(),
+            [
+                layout("MemoryChunk", 24, 8),
+                layout("HazardPointerSlot", 128, 64),
+                layout("CacheAligned<AtomicPtr<u8>>", 64, 64),
+            ]
+        );
+    }
+
+    #[cfg(not(feature = "hft-unsafe"))]
+    #[test]
+    fn layout_report_is_empty_without_hft_unsafe() {
+        assert!(layout_report().is_empty());
+    }
+
+    // A `[memory]` section selecting `backend`, with every pool kept small
+    fn memory_config(backend: &str) -> MemoryConfig {
+        toml::from_str(&format!(
+            r#"
+            backend = "{backend}"
+
+            [safe]
+            initial_chunks = 4
+            max_chunks = 16
+
+            [lock_free]
+            initial_chunks = 4
+            max_chunks = 16
+
+            [numa.pool_config]
+            initial_chunks = 4
+
+            [[numa.nodes]]
+            id = 0
+            cpu_mask = [0]
+            memory_size = 65536
+            distance_map = {{}}
+
+            [slab]
+            max_object_size = 256
+            pre_allocate_slabs = 4
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn safe_backend_from_config() {
+        let backend = MemoryBackend::from_config(&memory_config("safe")).unwrap();
+        assert_eq!(backend.backend_type(), "Safe");
+        assert!(toml::from_str::<MemoryConfig>(r#"backend = "jemalloc""#).is_err());
+    }
+
+    #[cfg(feature = "hft-unsafe")]
+    #[test]
+    fn unsafe_backends_from_config() {
+        for (kind, name) in [
+            ("lock_free", "LockFree"),
+            ("numa", "NUMA-aware"),
+            ("slab", "Slab"),
+        ] {
+            let backend = MemoryBackend::from_config(&memory_config(kind)).unwrap();
+            assert_eq!(backend.backend_type(), name);
+        }
+    }
+
+    #[cfg(not(feature = "hft-unsafe"))]
+    #[test]
+    fn unsafe_backends_need_the_hft_unsafe_feature() {
+        for kind in ["lock_free", "numa", "slab"] {
+            let config = memory_config(kind);
+            assert!(!config.backend.is_available());
+            assert!(matches!(
+                MemoryBackend::from_config(&config),
+                Err(AllocError::UnsupportedOperation(message))
+                    if message == format!("The {kind} memory backend requires the hft-unsafe feature")
+            ));
+        }
+    }
 }
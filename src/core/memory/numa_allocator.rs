@@ -1,163 +1,40 @@
+use crate::core::cpu::affinity::set_current_thread_affinity;
+use crate::core::diagnostics::lock_order::{self, OrderedRwLock};
 use crate::core::memory::allocator::{AllocError, MemoryAllocator};
 use crate::core::memory::lock_free_pool::{LockFreeMemoryPool, PoolConfig};
-use parking_lot::RwLock;
+use crate::core::memory::stats::{AllocationStats, MemoryStats};
+pub use crate::core::memory::topology::NumaNode;
+use crate::core::memory::topology::NumaTopology;
+use serde::Deserialize;
 use std::alloc::Layout;
 use std::collections::HashMap;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 #[cfg(target_os = "linux")]
 use libc::{CPU_ISSET, CPU_SETSIZE, cpu_set_t, sched_getaffinity};
 
-const DEFAULT_NUMA_NODES: usize = 2;
 const CACHE_LINE_SIZE: usize = 64;
+const DEFAULT_REVALIDATE_INTERVAL: u64 = 1024;
 
-#[derive(Clone, Debug)]
-pub struct NumaNode {
-    pub id: usize,
-    pub cpu_mask: Vec<usize>,
-    pub memory_size: usize,
-    pub distance_map: HashMap<usize, u8>,
-}
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
 pub struct NumaConfig {
     pub nodes: Vec<NumaNode>,
     pub interleave: bool,
     pub local_alloc_preference: bool,
     pub migration_threshold: usize,
+    /// Cached node lookups per thread between checks of which node the
+    /// thread actually runs on; 0 trusts the cache forever
+    pub revalidate_interval: u64,
     pub pool_config: PoolConfig,
 }
 
-impl NumaConfig {
-    #[cfg(target_os = "linux")]
-    fn discover_numa_topology() -> Option<Vec<NumaNode>> {
-        // Try to discover NUMA topology from /sys/devices/system/node/
-        let node_dir = std::path::Path::new("/sys/devices/system/node");
-        if !node_dir.exists() {
-            return None;
-        }
-
-        let mut nodes = Vec::new();
-
-        // Read available nodes
-        let mut node_ids = Vec::new();
-        if let Ok(entries) = std::fs::read_dir(node_dir) {
-            for entry in entries.flatten() {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-                if name_str.starts_with("node") {
-                    if let Ok(node_id) = name_str[4..].parse::<usize>() {
-                        node_ids.push(node_id);
-                    }
-                }
-            }
-        }
-
-        if node_ids.is_empty() {
-            return None;
-        }
-
-        node_ids.sort();
-
-        for &node_id in &node_ids {
-            if let Some(node) = Self::read_numa_node_info(node_id) {
-                nodes.push(node);
-            }
-        }
-
-        if nodes.is_empty() { None } else { Some(nodes) }
-    }
-
-    #[cfg(target_os = "linux")]
-    fn read_numa_node_info(node_id: usize) -> Option<NumaNode> {
-        let node_path = format!("/sys/devices/system/node/node{}", node_id);
-
-        // Read CPU list for this node
-        let cpu_mask = Self::read_cpu_list(&format!("{}/cpulist", node_path))?;
-
-        // Read memory size (try meminfo first, fallback to estimate)
-        let memory_size = Self::read_memory_size(&format!("{}/meminfo", node_path))
-            .unwrap_or(16 * 1024 * 1024 * 1024); // 16GB default
-
-        // Create distance map - simplified for now
-        let mut distance_map = HashMap::new();
-        distance_map.insert(node_id, 10); // Local access
-
-        Some(NumaNode {
-            id: node_id,
-            cpu_mask,
-            memory_size,
-            distance_map,
-        })
-    }
-
-    #[cfg(target_os = "linux")]
-    fn read_cpu_list(path: &str) -> Option<Vec<usize>> {
-        let content = std::fs::read_to_string(path).ok()?;
-        let mut cpus = Vec::new();
-
-        for part in content.trim().split(',') {
-            if let Some(range_pos) = part.find('-') {
-                // Range like "0-7"
-                let start: usize = part[..range_pos].parse().ok()?;
-                let end: usize = part[range_pos + 1..].parse().ok()?;
-                for cpu in start..=end {
-                    cpus.push(cpu);
-                }
-            } else {
-                // Single CPU
-                cpus.push(part.parse().ok()?);
-            }
-        }
-
-        Some(cpus)
-    }
-
-    #[cfg(target_os = "linux")]
-    fn read_memory_size(path: &str) -> Option<usize> {
-        let content = std::fs::read_to_string(path).ok()?;
-        for line in content.lines() {
-            if line.starts_with("Node") && line.contains("MemTotal:") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    let kb: usize = parts[2].parse().ok()?;
-                    return Some(kb * 1024); // Convert KB to bytes
-                }
-            }
-        }
-        None
-    }
-
-    #[cfg(not(target_os = "linux"))]
-    fn discover_numa_topology() -> Option<Vec<NumaNode>> {
-        None
-    }
-}
-
 impl Default for NumaConfig {
     fn default() -> Self {
-        let nodes = if let Some(discovered_nodes) = Self::discover_numa_topology() {
-            discovered_nodes
-        } else {
-            // Fallback to default configuration
-            let mut nodes = Vec::new();
-            for i in 0..DEFAULT_NUMA_NODES {
-                let mut distance_map = HashMap::new();
-                for j in 0..DEFAULT_NUMA_NODES {
-                    distance_map.insert(j, if i == j { 10 } else { 20 });
-                }
-
-                nodes.push(NumaNode {
-                    id: i,
-                    cpu_mask: (i * 8..(i + 1) * 8).collect(),
-                    memory_size: 32 * 1024 * 1024 * 1024,
-                    distance_map,
-                });
-            }
-            nodes
-        };
+        // The kernel's nodes, or a simulated two-node layout without NUMA
+        let nodes = NumaTopology::discover().nodes;
 
         let mut pool_config = PoolConfig::default();
         // Ensure alignment is at least cache line size for NUMA
@@ -168,6 +45,7 @@ impl Default for NumaConfig {
             interleave: false,
             local_alloc_preference: true,
             migration_threshold: 1000,
+            revalidate_interval: DEFAULT_REVALIDATE_INTERVAL,
             pool_config,
         }
     }
@@ -177,9 +55,18 @@ impl Default for NumaConfig {
 pub struct NumaAllocator {
     config: NumaConfig,
     node_pools: Vec<Arc<LockFreeMemoryPool>>,
+    // Per preferred node: the other nodes, nearest first
+    fallback_order: Vec<Vec<usize>>,
     current_node: AtomicUsize,
-    allocation_stats: Arc<RwLock<NumaStats>>,
-    thread_node_cache: Arc<RwLock<HashMap<std::thread::ThreadId, usize>>>,
+    allocation_stats: Arc<OrderedRwLock<NumaStats>>,
+    thread_node_cache: Arc<OrderedRwLock<HashMap<std::thread::ThreadId, CachedNode>>>,
+}
+
+// A thread's node as last seen, and how often it was looked up since
+#[derive(Debug)]
+struct CachedNode {
+    node: usize,
+    lookups: AtomicU64,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -188,6 +75,10 @@ pub struct NumaStats {
     pub cross_node_allocations: usize,
     pub local_allocations: usize,
     pub total_bytes_allocated: usize,
+    /// Cached thread nodes found stale on revalidation and corrected
+    pub thread_migrations: usize,
+    /// Cross-node fallbacks keyed by the distance of the node that served them
+    pub fallback_distances: HashMap<u8, usize>,
     pub node_stats: Vec<NodeStats>,
 }
 
@@ -209,7 +100,10 @@ pub struct NumaStatsSnapshot {
     pub cross_node_allocations: usize,
     pub local_allocations: usize,
     pub total_bytes_allocated: usize,
+    pub thread_migrations: usize,
     pub node_summaries: Vec<(usize, usize, usize, usize)>, // (node_id, allocated, free, total)
+    /// Allocation stats of every node pool merged into one view
+    pub combined: AllocationStats,
 }
 
 impl NumaAllocator {
@@ -245,37 +139,85 @@ impl NumaAllocator {
             });
         }
 
-        Ok(Self {
+        let mut allocator = Self {
             config,
             node_pools,
+            fallback_order: Vec::new(),
             current_node: AtomicUsize::new(0),
-            allocation_stats: Arc::new(RwLock::new(initial_stats)),
-            thread_node_cache: Arc::new(RwLock::new(HashMap::new())),
-        })
+            allocation_stats: Arc::new(OrderedRwLock::new(
+                lock_order::NUMA_ALLOCATION_STATS,
+                initial_stats,
+            )),
+            thread_node_cache: Arc::new(OrderedRwLock::new(
+                lock_order::NUMA_THREAD_NODE_CACHE,
+                HashMap::new(),
+            )),
+        };
+        allocator.fallback_order = (0..allocator.node_pools.len())
+            .map(|node| allocator.nodes_by_distance(node))
+            .collect();
+
+        Ok(allocator)
     }
 
-    pub fn get_current_numa_node(&self) -> usize {
-        #[cfg(target_os = "linux")]
-        {
-            if let Some(node) = self.get_linux_numa_node() {
-                return node;
-            }
-        }
+    // Every node except `from`, ordered by increasing distance; unknown
+    // distances sort last and ties keep index order
+    fn nodes_by_distance(&self, from: usize) -> Vec<usize> {
+        let mut candidates: Vec<usize> = (0..self.node_pools.len())
+            .filter(|&node| node != from)
+            .collect();
+        candidates.sort_by_key(|&node| self.get_node_distance(from, node).unwrap_or(u8::MAX));
+        candidates
+    }
 
+    pub fn get_current_numa_node(&self) -> usize {
         if let Some(node) = self.get_cached_thread_node() {
             return node;
         }
 
         let thread_id = std::thread::current().id();
-        let hash = self.hash_thread_id(thread_id);
-        let node = hash % self.config.nodes.len();
+        let node = self
+            .get_linux_numa_node()
+            .unwrap_or_else(|| self.hash_thread_id(thread_id) % self.config.nodes.len());
 
         self.cache_thread_node(thread_id, node);
         node
     }
 
+    /// Re-read the node the calling thread runs on and correct its cached
+    /// node if the thread has moved. Returns the node it moved to, if any.
+    pub fn revalidate_thread_node(&self) -> Option<usize> {
+        let thread_id = std::thread::current().id();
+        let actual = self.get_linux_numa_node()?;
+        let cached = self
+            .thread_node_cache
+            .read()
+            .get(&thread_id)
+            .map(|entry| entry.node);
+        if cached == Some(actual) {
+            return None;
+        }
+
+        self.cache_thread_node(thread_id, actual);
+        if cached.is_some() {
+            self.allocation_stats.write().thread_migrations += 1;
+        }
+        Some(actual)
+    }
+
+    // The node of the CPU the thread is running on, else of the first CPU it
+    // may run on
     #[cfg(target_os = "linux")]
     fn get_linux_numa_node(&self) -> Option<usize> {
+        // SAFETY: sched_getcpu takes no arguments and only reports the CPU
+        let cpu = unsafe { libc::sched_getcpu() };
+        if let Some(node) = usize::try_from(cpu)
+            .ok()
+            .and_then(|cpu| self.node_of_cpu(cpu))
+        {
+            return Some(node);
+        }
+
         unsafe {
             let mut cpu_set: cpu_set_t = std::mem::zeroed();
 
@@ -299,16 +241,88 @@ impl NumaAllocator {
         None
     }
 
+    /// Pin the calling thread to the CPUs of `node_id` so its allocations stay
+    /// node-local, and remember the binding for later node lookups.
+    pub fn bind_current_thread_to_node(&self, node_id: usize) -> Result<(), AllocError> {
+        let node = self
+            .config
+            .nodes
+            .iter()
+            .find(|node| node.id == node_id)
+            .ok_or(AllocError::NumaNodeUnavailable(node_id))?;
+        let cpus = node.cpu_mask.clone();
+        if cpus.is_empty() {
+            return Err(AllocError::NumaNodeUnavailable(node_id));
+        }
+
+        set_current_thread_affinity(&cpus).map_err(|e| {
+            AllocError::UnsupportedOperation(format!(
+                "Binding thread to NUMA node {} failed: {}",
+                node_id, e
+            ))
+        })?;
+        self.cache_thread_node(std::thread::current().id(), node_id);
+        Ok(())
+    }
+
+    /// Pin the calling thread to `cpus` and allocate for it from the node
+    /// owning the first of them. Returns that node.
+    pub fn bind_current_thread_to_cpus(&self, cpus: &[usize]) -> Result<usize, AllocError> {
+        let node_id = cpus
+            .first()
+            .and_then(|cpu| self.node_of_cpu(*cpu))
+            .unwrap_or_else(|| self.get_current_numa_node());
+
+        set_current_thread_affinity(cpus).map_err(|e| {
+            AllocError::UnsupportedOperation(format!(
+                "Pinning thread to CPUs {:?} failed: {}",
+                cpus, e
+            ))
+        })?;
+        self.cache_thread_node(std::thread::current().id(), node_id);
+        Ok(node_id)
+    }
+
+    /// The node whose CPU mask contains `cpu`
+    pub fn node_of_cpu(&self, cpu: usize) -> Option<usize> {
+        self.config
+            .nodes
+            .iter()
+            .find(|node| node.cpu_mask.contains(&cpu))
+            .map(|node| node.id)
+    }
+
+    // Every `revalidate_interval` lookups the thread's real node is checked,
+    // since the scheduler may have moved an unpinned thread since it was cached
     fn get_cached_thread_node(&self) -> Option<usize> {
         let thread_id = std::thread::current().id();
-        self.thread_node_cache.read().get(&thread_id).copied()
+        let (node, lookups) = {
+            let cache = self.thread_node_cache.read();
+            let entry = cache.get(&thread_id)?;
+            (
+                entry.node,
+                entry.lookups.fetch_add(1, Ordering::Relaxed) + 1,
+            )
+        };
+
+        let interval = self.config.revalidate_interval;
+        if interval != 0 && lookups % interval == 0 {
+            return Some(self.revalidate_thread_node().unwrap_or(node));
+        }
+        Some(node)
     }
 
     fn cache_thread_node(&self, thread_id: std::thread::ThreadId, node: usize) {
         // Cache the node for this thread - update if already exists
-        if let Some(prev_node) = self.thread_node_cache.write().insert(thread_id, node) {
-            tracing::debug!(thread_id = ?thread_id, prev_node, new_node = node,
-                          "Updated thread NUMA node assignment");
+        let entry = CachedNode {
+            node,
+            lookups: AtomicU64::new(0),
+        };
+        if let Some(prev) = self.thread_node_cache.write().insert(thread_id, entry) {
+            if prev.node != node {
+                tracing::debug!(thread_id = ?thread_id, prev_node = prev.node, new_node = node,
+                              "Updated thread NUMA node assignment");
+            }
         }
     }
 
@@ -377,6 +391,11 @@ impl NumaAllocator {
             .copied()
     }
 
+    /// Shrink the pools of nodes with nothing handed out; returns the bytes freed
+    pub fn shrink_idle(&self) -> usize {
+        self.node_pools.iter().map(|pool| pool.shrink_idle()).sum()
+    }
+
     pub fn with_stats<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&NumaStats) -> R,
@@ -405,9 +424,20 @@ impl NumaAllocator {
             cross_node_allocations: stats_guard.cross_node_allocations,
             local_allocations: stats_guard.local_allocations,
             total_bytes_allocated: stats_guard.total_bytes_allocated,
+            thread_migrations: stats_guard.thread_migrations,
             node_summaries,
+            combined: self.combined_allocation_stats(),
         }
     }
+
+    /// Allocation stats across all node pools, see `AllocationStats::merge`
+    pub fn combined_allocation_stats(&self) -> AllocationStats {
+        self.node_pools
+            .iter()
+            .map(|pool| pool.get_allocation_stats().get_snapshot())
+            .reduce(|combined, node| combined.merge(&node))
+            .unwrap_or_else(|| MemoryStats::new().get_snapshot())
+    }
 }
 
 impl MemoryAllocator for NumaAllocator {
@@ -420,12 +450,23 @@ impl MemoryAllocator for NumaAllocator {
                 Ok(ptr)
             }
             Err(_) => {
-                for (node_id, pool) in self.node_pools.iter().enumerate() {
-                    if node_id != preferred_node {
-                        if let Ok(ptr) = pool.allocate(layout) {
-                            self.update_stats(node_id, layout.size(), false);
-                            return Ok(ptr);
-                        }
+                let Some(candidates) = self.fallback_order.get(preferred_node) else {
+                    return Err(AllocError::NumaNodeUnavailable(preferred_node));
+                };
+
+                for &node_id in candidates {
+                    if let Ok(ptr) = self.node_pools[node_id].allocate(layout) {
+                        self.update_stats(node_id, layout.size(), false);
+                        let distance = self
+                            .get_node_distance(preferred_node, node_id)
+                            .unwrap_or(u8::MAX);
+                        *self
+                            .allocation_stats
+                            .write()
+                            .fallback_distances
+                            .entry(distance)
+                            .or_insert(0) += 1;
+                        return Ok(ptr);
                     }
                 }
                 Err(AllocError::OutOfMemory)
@@ -434,12 +475,25 @@ impl MemoryAllocator for NumaAllocator {
     }
 
     fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        for pool in &self.node_pools {
-            pool.deallocate(ptr, layout);
-            return;
+        match self.node_pools.iter().find(|pool| pool.owns(ptr)) {
+            Some(pool) => pool.deallocate(ptr, layout),
+            None => tracing::error!(
+                "NumaAllocator: deallocate called with foreign pointer {:p}",
+                ptr
+            ),
         }
     }
 
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        self.node_pools.iter().any(|pool| pool.owns(ptr))
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        self.node_pools
+            .iter()
+            .find_map(|pool| pool.allocation_size(ptr))
+    }
+
     fn available_memory(&self) -> usize {
         self.node_pools
             .iter()
@@ -451,3 +505,186 @@ impl MemoryAllocator for NumaAllocator {
         self.node_pools.iter().map(|pool| pool.total_memory()).sum()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cpu::affinity::current_thread_affinity;
+
+    fn single_node_allocator(cpu_mask: Vec<usize>) -> NumaAllocator {
+        let pool_config = PoolConfig {
+            initial_chunks: 4,
+            ..PoolConfig::default()
+        };
+        NumaAllocator::new(NumaConfig {
+            nodes: vec![NumaNode {
+                id: 0,
+                cpu_mask,
+                memory_size: 16 * pool_config.chunk_size,
+                distance_map: HashMap::new(),
+            }],
+            pool_config,
+            ..NumaConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn binding_to_a_node_pins_the_thread_to_its_cpus() {
+        // Bind a fresh thread so the test runner's own affinity is untouched
+        std::thread::spawn(|| {
+            let cpu = current_thread_affinity().unwrap()[0];
+            let allocator = single_node_allocator(vec![cpu]);
+
+            allocator.bind_current_thread_to_node(0).unwrap();
+
+            assert_eq!(current_thread_affinity().unwrap(), vec![cpu]);
+            assert_eq!(allocator.get_current_numa_node(), 0);
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn binding_to_an_unknown_or_cpuless_node_fails() {
+        let allocator = single_node_allocator(Vec::new());
+
+        assert!(matches!(
+            allocator.bind_current_thread_to_node(7),
+            Err(AllocError::NumaNodeUnavailable(7))
+        ));
+        assert!(matches!(
+            allocator.bind_current_thread_to_node(0),
+            Err(AllocError::NumaNodeUnavailable(0))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stale_thread_node_is_corrected_on_revalidation() {
+        let pool_config = PoolConfig {
+            initial_chunks: 4,
+            ..PoolConfig::default()
+        };
+        let node = |id, cpu_mask| NumaNode {
+            id,
+            cpu_mask,
+            memory_size: 16 * pool_config.chunk_size,
+            distance_map: HashMap::new(),
+        };
+        // Every CPU this thread can land on belongs to node 0
+        let allocator = NumaAllocator::new(NumaConfig {
+            nodes: vec![node(0, (0..1024).collect()), node(1, Vec::new())],
+            revalidate_interval: 4,
+            pool_config,
+            ..NumaConfig::default()
+        })
+        .unwrap();
+
+        // As if the thread had been cached while running on node 1
+        allocator.cache_thread_node(std::thread::current().id(), 1);
+        for _ in 0..3 {
+            assert_eq!(allocator.get_current_numa_node(), 1);
+        }
+        // The fourth lookup checks where the thread really is
+        assert_eq!(allocator.get_current_numa_node(), 0);
+        assert_eq!(allocator.get_current_numa_node(), 0);
+        assert_eq!(allocator.get_stats_snapshot().thread_migrations, 1);
+
+        // Already correct: nothing to migrate
+        assert_eq!(allocator.revalidate_thread_node(), None);
+        assert_eq!(allocator.get_stats_snapshot().thread_migrations, 1);
+    }
+
+    #[test]
+    fn exhausted_node_falls_back_to_the_nearest_node_first() {
+        let pool_config = PoolConfig {
+            initial_chunks: 1,
+            thread_cache_size: 0,
+            ..PoolConfig::default()
+        };
+        let chunk = pool_config.chunk_size;
+        let node = |id, chunks, distances: &[(usize, u8)]| NumaNode {
+            id,
+            cpu_mask: Vec::new(),
+            memory_size: chunks * chunk,
+            distance_map: distances.iter().copied().collect(),
+        };
+        // Node 2 sits closer to node 0 than node 1 does, but not the reverse
+        let allocator = NumaAllocator::new(NumaConfig {
+            nodes: vec![
+                node(0, 1, &[(0, 10), (1, 40), (2, 20)]),
+                node(1, 2, &[(0, 20), (1, 10), (2, 20)]),
+                node(2, 1, &[(0, 40), (1, 20), (2, 10)]),
+            ],
+            local_alloc_preference: false,
+            pool_config,
+            ..NumaConfig::default()
+        })
+        .unwrap();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+
+        let local = allocator.allocate(layout).unwrap();
+        let nearest = allocator.allocate(layout).unwrap();
+        let farther = allocator.allocate(layout).unwrap();
+
+        assert!(allocator.node_pools[0].owns(local));
+        assert!(allocator.node_pools[2].owns(nearest));
+        assert!(allocator.node_pools[1].owns(farther));
+        allocator.with_stats(|stats| {
+            assert_eq!(stats.fallback_distances.get(&20), Some(&1));
+            assert_eq!(stats.fallback_distances.get(&40), Some(&1));
+        });
+
+        for ptr in [local, nearest, farther] {
+            allocator.deallocate(ptr, layout);
+        }
+    }
+
+    #[test]
+    fn combined_stats_sum_every_node_pool() {
+        let pool_config = PoolConfig {
+            initial_chunks: 4,
+            ..PoolConfig::default()
+        };
+        let node = |id| NumaNode {
+            id,
+            cpu_mask: Vec::new(),
+            memory_size: 16 * pool_config.chunk_size,
+            distance_map: HashMap::new(),
+        };
+        let allocator = NumaAllocator::new(NumaConfig {
+            nodes: vec![node(0), node(1)],
+            pool_config: pool_config.clone(),
+            ..NumaConfig::default()
+        })
+        .unwrap();
+        let layout = Layout::from_size_align(64, 8).unwrap();
+
+        let on_first: Vec<_> = (0..3)
+            .map(|_| allocator.allocate_on_node(0, layout).unwrap())
+            .collect();
+        let on_second = allocator.allocate_on_node(1, layout).unwrap();
+
+        let combined = allocator.combined_allocation_stats();
+        let per_node: Vec<_> = allocator
+            .node_pools
+            .iter()
+            .map(|pool| pool.get_allocation_stats().get_snapshot())
+            .collect();
+        assert_eq!(combined.total_allocations, 4);
+        assert_eq!(
+            combined.current_allocated_bytes,
+            per_node
+                .iter()
+                .map(|s| s.current_allocated_bytes)
+                .sum::<usize>()
+        );
+
+        for ptr in on_first.into_iter().chain([on_second]) {
+            allocator.deallocate(ptr, layout);
+        }
+        assert_eq!(allocator.combined_allocation_stats().total_deallocations, 4);
+    }
+}
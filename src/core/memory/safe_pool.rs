@@ -1,70 +1,142 @@
 // Safe memory pool implementation using only safe Rust
 // No unsafe code - uses Vec for memory management
 
-use crate::core::memory::allocator::AllocError;
-use crate::core::memory::stats::{AllocationTimer, MemoryStats};
+use crate::core::diagnostics::lock_order::{self, OrderedGuard, OrderedMutex, OrderedRwLock};
+use crate::core::memory::allocator::{AccountingError, AllocError, MemoryAllocator};
+use crate::core::memory::budget;
+use crate::core::memory::stats::{AllocationTimer, MemoryStats, saturating_fetch_sub};
 use crossbeam::queue::SegQueue;
+use serde::Deserialize;
+use std::alloc::Layout;
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, warn};
 const DEFAULT_CHUNK_SIZE: usize = 4096;
 const DEFAULT_INITIAL_CHUNKS: usize = 1024;
+const DEFAULT_ALIGNMENT: usize = 64;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
 pub struct SafePoolConfig {
     pub chunk_size: usize,
+    /// Address alignment of every chunk, a power of two; cache-line by default
+    pub alignment: usize,
     pub initial_chunks: usize,
     pub max_chunks: usize,
     pub zero_on_dealloc: bool,
+    /// Re-zero reused chunks before handing them out. Fresh chunks start zeroed.
+    pub zero_on_alloc: bool,
+    /// Re-verify `total_memory` against the free list after every pool operation
+    /// (debug builds only). Only meaningful while the pool is used from one thread.
+    pub check_accounting: bool,
 }
 
 impl Default for SafePoolConfig {
     fn default() -> Self {
         Self {
             chunk_size: DEFAULT_CHUNK_SIZE,
+            alignment: DEFAULT_ALIGNMENT,
             initial_chunks: DEFAULT_INITIAL_CHUNKS,
             max_chunks: 1_000_000,
             zero_on_dealloc: false,
+            zero_on_alloc: false,
+            check_accounting: false,
         }
     }
 }
 
-// Safe memory chunk using Box<[u8]>
+impl SafePoolConfig {
+    /// Reject configurations `SafeMemoryPool::new` cannot honour
+    pub fn validate(&self) -> Result<(), AllocError> {
+        if self.chunk_size == 0 {
+            return Err(AllocError::InvalidLayout(
+                "Chunk size must be greater than 0".to_string(),
+            ));
+        }
+        if !self.alignment.is_power_of_two() {
+            return Err(AllocError::InvalidLayout(format!(
+                "Alignment {} is not a power of two",
+                self.alignment
+            )));
+        }
+        if self.initial_chunks > self.max_chunks {
+            return Err(AllocError::InvalidLayout(format!(
+                "Initial chunks {} exceed max chunks {}",
+                self.initial_chunks, self.max_chunks
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Safe memory chunk using Box<[u8]>. The buffer is over-allocated by
+// `alignment - 1` bytes and the chunk is the aligned window inside it, which
+// never moves since the boxed buffer does not.
 #[derive(Debug)]
 pub struct SafeMemoryChunk {
-    data: Box<[u8]>,
+    buffer: Box<[u8]>,
+    offset: usize,
+    len: usize,
     _generation: u64,
+    // Where the chunk was last handed out, reported if it leaks
+    #[cfg(debug_assertions)]
+    allocation_site: Option<std::backtrace::Backtrace>,
 }
 
 impl SafeMemoryChunk {
-    fn new(size: usize, generation: u64) -> Self {
+    fn new(size: usize, alignment: usize, generation: u64) -> Self {
+        let buffer = vec![0u8; size + alignment - 1].into_boxed_slice();
+        let misalignment = buffer.as_ptr() as usize % alignment;
         Self {
-            data: vec![0u8; size].into_boxed_slice(),
+            offset: (alignment - misalignment) % alignment,
+            len: size,
+            buffer,
             _generation: generation,
+            #[cfg(debug_assertions)]
+            allocation_site: None,
+        }
+    }
+
+    fn record_allocation_site(&mut self) {
+        // Honours RUST_BACKTRACE, so this is cheap unless explicitly enabled
+        #[cfg(debug_assertions)]
+        {
+            self.allocation_site = Some(std::backtrace::Backtrace::capture());
         }
     }
 
+    fn data(&self) -> &[u8] {
+        &self.buffer[self.offset..self.offset + self.len]
+    }
+
+    fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.offset..self.offset + self.len]
+    }
+
     pub fn as_ptr(&self) -> *const u8 {
-        self.data.as_ptr()
+        self.data().as_ptr()
     }
 
     pub fn as_mut_ptr(&mut self) -> *mut u8 {
-        self.data.as_mut_ptr()
+        self.data_mut().as_mut_ptr()
     }
 
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.len
     }
 
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.len == 0
     }
 }
 
 // Wrapper to provide NonNull interface while keeping memory safe
 #[derive(Debug)]
 pub struct SafeMemoryHandle {
-    chunk: Arc<parking_lot::Mutex<SafeMemoryChunk>>,
+    chunk: Arc<OrderedMutex<SafeMemoryChunk>>,
 }
 
 impl SafeMemoryHandle {
@@ -75,13 +147,56 @@ impl SafeMemoryHandle {
     pub fn as_mut_ptr(&self) -> *mut u8 {
         self.chunk.lock().as_mut_ptr()
     }
+
+    /// Borrow the chunk's bytes without any unsafe at the call site.
+    ///
+    /// The returned guard holds the chunk's lock, so it borrows the handle
+    /// and cannot outlive it. Drop the guard before calling another accessor
+    /// on the same handle, or the second call will deadlock; debug builds
+    /// panic with a lock order violation instead.
+    pub fn as_slice(&self) -> impl Deref<Target = [u8]> + '_ {
+        OrderedGuard::map(self.chunk.lock(), |chunk| chunk.data_mut())
+    }
+
+    /// Mutable counterpart of [`as_slice`](Self::as_slice); same locking rules apply
+    pub fn as_mut_slice(&self) -> impl DerefMut<Target = [u8]> + '_ {
+        OrderedGuard::map(self.chunk.lock(), |chunk| chunk.data_mut())
+    }
+
+    /// Copy the first `len` bytes into an owned `Vec` that can outlive the
+    /// pool, e.g. to hand across an async boundary.
+    pub fn copy_to_vec(&self, len: usize) -> Result<Vec<u8>, AllocError> {
+        let chunk = self.chunk.lock();
+        let bytes = chunk.data().get(..len).ok_or(AllocError::SizeExceeded {
+            size: len,
+            max: chunk.len(),
+        })?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Copy `src` into the start of the chunk, leaving the rest untouched
+    pub fn copy_from_slice(&self, src: &[u8]) -> Result<(), AllocError> {
+        let mut chunk = self.chunk.lock();
+        let max = chunk.len();
+        let dst = chunk
+            .data_mut()
+            .get_mut(..src.len())
+            .ok_or(AllocError::SizeExceeded {
+                size: src.len(),
+                max,
+            })?;
+        dst.copy_from_slice(src);
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub struct SafeMemoryPool {
     config: SafePoolConfig,
-    free_chunks: Arc<SegQueue<Arc<parking_lot::Mutex<SafeMemoryChunk>>>>,
-    allocated_chunks: Arc<parking_lot::RwLock<Vec<Arc<parking_lot::Mutex<SafeMemoryChunk>>>>>,
+    free_chunks: Arc<SegQueue<Arc<OrderedMutex<SafeMemoryChunk>>>>,
+    // Handed-out chunks by start address, so a pointer finds its chunk in
+    // O(log n)
+    allocated_chunks: Arc<OrderedRwLock<BTreeMap<usize, Arc<OrderedMutex<SafeMemoryChunk>>>>>,
     allocated_count: AtomicUsize,
     free_count: AtomicUsize,
     total_memory: AtomicUsize,
@@ -91,16 +206,15 @@ pub struct SafeMemoryPool {
 
 impl SafeMemoryPool {
     pub fn new(config: SafePoolConfig) -> Result<Self, AllocError> {
-        if config.chunk_size == 0 {
-            return Err(AllocError::InvalidLayout(
-                "Chunk size must be greater than 0".to_string(),
-            ));
-        }
+        config.validate()?;
 
         let pool = Self {
             config,
             free_chunks: Arc::new(SegQueue::new()),
-            allocated_chunks: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            allocated_chunks: Arc::new(OrderedRwLock::new(
+                lock_order::SAFE_POOL_ALLOCATED_CHUNKS,
+                BTreeMap::new(),
+            )),
             allocated_count: AtomicUsize::new(0),
             free_count: AtomicUsize::new(0),
             total_memory: AtomicUsize::new(0),
@@ -116,8 +230,12 @@ impl SafeMemoryPool {
     fn preallocate_chunks(&self, count: usize) -> Result<(), AllocError> {
         for _ in 0..count {
             let generation = self.generation.fetch_add(1, Ordering::Relaxed);
-            let chunk = SafeMemoryChunk::new(self.config.chunk_size, generation as u64);
-            let chunk_arc = Arc::new(parking_lot::Mutex::new(chunk));
+            let chunk = SafeMemoryChunk::new(
+                self.config.chunk_size,
+                self.config.alignment,
+                generation as u64,
+            );
+            let chunk_arc = Arc::new(OrderedMutex::new(lock_order::SAFE_POOL_CHUNK, chunk));
 
             self.free_chunks.push(chunk_arc);
             let free_count = self.free_count.fetch_add(1, Ordering::Relaxed);
@@ -135,6 +253,7 @@ impl SafeMemoryPool {
             }
         }
 
+        self.debug_check_accounting();
         Ok(())
     }
 
@@ -159,10 +278,21 @@ impl SafeMemoryPool {
             }
 
             // Track allocated chunk
-            self.allocated_chunks.write().push(Arc::clone(&chunk));
+            let start = {
+                let mut guard = chunk.lock();
+                if self.config.zero_on_alloc {
+                    guard.data_mut().fill(0);
+                }
+                guard.record_allocation_site();
+                guard.as_ptr() as usize
+            };
+            self.allocated_chunks
+                .write()
+                .insert(start, Arc::clone(&chunk));
 
             self.stats
                 .record_allocation(self.config.chunk_size, timer.elapsed_ns());
+            self.debug_check_accounting();
 
             return Ok(SafeMemoryHandle { chunk });
         }
@@ -172,15 +302,29 @@ impl SafeMemoryPool {
             self.allocated_count.load(Ordering::Relaxed) + self.free_count.load(Ordering::Relaxed);
 
         if current_total >= self.config.max_chunks {
-            return Err(AllocError::PoolExhausted);
+            let err = AllocError::PoolExhausted;
+            self.stats.record_failed_allocation(&err);
+            return Err(err);
+        }
+        if let Err(err) = budget::admit_growth(self.config.chunk_size) {
+            self.stats.record_failed_allocation(&err);
+            return Err(err);
         }
 
         // Allocate a new chunk
         let generation = self.generation.fetch_add(1, Ordering::Relaxed);
-        let chunk = SafeMemoryChunk::new(self.config.chunk_size, generation as u64);
-        let chunk_arc = Arc::new(parking_lot::Mutex::new(chunk));
+        let mut chunk = SafeMemoryChunk::new(
+            self.config.chunk_size,
+            self.config.alignment,
+            generation as u64,
+        );
+        chunk.record_allocation_site();
+        let start = chunk.as_ptr() as usize;
+        let chunk_arc = Arc::new(OrderedMutex::new(lock_order::SAFE_POOL_CHUNK, chunk));
 
-        self.allocated_chunks.write().push(Arc::clone(&chunk_arc));
+        self.allocated_chunks
+            .write()
+            .insert(start, Arc::clone(&chunk_arc));
         let allocated_count = self.allocated_count.fetch_add(1, Ordering::Relaxed);
         let total_memory = self
             .total_memory
@@ -196,26 +340,28 @@ impl SafeMemoryPool {
         }
         self.stats
             .record_allocation(self.config.chunk_size, timer.elapsed_ns());
+        self.debug_check_accounting();
 
         Ok(SafeMemoryHandle { chunk: chunk_arc })
     }
 
     pub fn deallocate_chunk(&self, handle: SafeMemoryHandle) {
-        if self.config.zero_on_dealloc {
+        let timer = AllocationTimer::start();
+
+        let start = {
             let mut chunk = handle.chunk.lock();
-            for byte in chunk.data.iter_mut() {
-                *byte = 0;
+            if self.config.zero_on_dealloc {
+                chunk.data_mut().fill(0);
             }
-        }
+            chunk.as_ptr() as usize
+        };
 
         // Remove from allocated list
-        self.allocated_chunks
-            .write()
-            .retain(|c| !Arc::ptr_eq(c, &handle.chunk));
+        self.allocated_chunks.write().remove(&start);
 
         // Add back to free list
         self.free_chunks.push(handle.chunk);
-        let prev_allocated = self.allocated_count.fetch_sub(1, Ordering::Relaxed);
+        let prev_allocated = saturating_fetch_sub(&self.allocated_count, 1);
         let prev_free = self.free_count.fetch_add(1, Ordering::Relaxed);
 
         // Track deallocation patterns for memory leak detection
@@ -228,7 +374,82 @@ impl SafeMemoryPool {
                 "SafeMemoryPool deallocation milestone"
             );
         }
-        self.stats.record_deallocation(self.config.chunk_size);
+        self.stats
+            .record_deallocation(self.config.chunk_size, timer.elapsed_ns());
+        self.debug_check_accounting();
+    }
+
+    /// Number of chunks handed out and not yet returned
+    pub fn outstanding_allocations(&self) -> usize {
+        self.allocated_count.load(Ordering::Acquire)
+    }
+
+    /// Drop free chunks until at most `target_free` remain; returns the
+    /// number released
+    pub fn shrink(&self, target_free: usize) -> usize {
+        let mut released = 0;
+        while self.free_count.load(Ordering::Acquire) > target_free {
+            if self.free_chunks.pop().is_none() {
+                break;
+            }
+            saturating_fetch_sub(&self.free_count, 1);
+            saturating_fetch_sub(&self.total_memory, self.config.chunk_size);
+            released += 1;
+        }
+
+        if released > 0 {
+            debug!(
+                released,
+                free_chunks = self.free_count.load(Ordering::Relaxed),
+                "SafeMemoryPool shrunk"
+            );
+        }
+        self.debug_check_accounting();
+        released
+    }
+
+    /// Release every free chunk if nothing is handed out; returns the bytes freed
+    pub fn shrink_idle(&self) -> usize {
+        if self.outstanding_allocations() > 0 {
+            return 0;
+        }
+        self.shrink(0) * self.config.chunk_size
+    }
+
+    /// Recompute the pool's true footprint from the free list and tracked
+    /// allocations and compare it against the running `total_memory` counter.
+    ///
+    /// Concurrent allocations race with the counters, so call this while the pool
+    /// is quiescent.
+    pub fn verify_accounting(&self) -> Result<(), AccountingError> {
+        let recorded_free = self.free_count.load(Ordering::Acquire);
+        let actual_free = self.free_chunks.len();
+        if recorded_free != actual_free {
+            return Err(AccountingError::FreeCountMismatch {
+                recorded: recorded_free,
+                actual: actual_free,
+            });
+        }
+
+        let allocated = self.allocated_count.load(Ordering::Acquire);
+        let computed = (actual_free + allocated) * self.config.chunk_size;
+        let recorded = self.total_memory.load(Ordering::Acquire);
+        if recorded != computed {
+            return Err(AccountingError::TotalMemoryMismatch { recorded, computed });
+        }
+
+        Ok(())
+    }
+
+    fn debug_check_accounting(&self) {
+        if cfg!(debug_assertions) && self.config.check_accounting {
+            let result = self.verify_accounting();
+            debug_assert!(
+                result.is_ok(),
+                "SafeMemoryPool accounting desync: {:?}",
+                result
+            );
+        }
     }
 
     pub fn get_stats(&self) -> SafePoolStats {
@@ -239,6 +460,107 @@ impl SafeMemoryPool {
             chunk_size: self.config.chunk_size,
         }
     }
+
+    pub fn get_allocation_stats(&self) -> Arc<MemoryStats> {
+        Arc::clone(&self.stats)
+    }
+}
+
+// Trait allocations hand out the chunk's data pointer; the chunk itself stays
+// owned by `allocated_chunks` until the pointer comes back through `deallocate`.
+impl MemoryAllocator for SafeMemoryPool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() > self.config.chunk_size {
+            let err = AllocError::SizeExceeded {
+                size: layout.size(),
+                max: self.config.chunk_size,
+            };
+            self.stats.record_failed_allocation(&err);
+            return Err(err);
+        }
+        if layout.align() > self.config.alignment {
+            let err = AllocError::AlignmentNotSupported {
+                required: layout.align(),
+                supported: self.config.alignment,
+            };
+            self.stats.record_failed_allocation(&err);
+            return Err(err);
+        }
+
+        let handle = self.allocate_chunk()?;
+        match NonNull::new(handle.as_mut_ptr()) {
+            Some(ptr) => Ok(ptr),
+            None => {
+                self.deallocate_chunk(handle);
+                Err(AllocError::OutOfMemory)
+            }
+        }
+    }
+
+    fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        let chunk = self
+            .allocated_chunks
+            .read()
+            .get(&(ptr.as_ptr() as usize))
+            .map(Arc::clone);
+
+        match chunk {
+            Some(chunk) => self.deallocate_chunk(SafeMemoryHandle { chunk }),
+            None => warn!(
+                "SafeMemoryPool: deallocate called with unknown pointer {:p}",
+                ptr
+            ),
+        }
+    }
+
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        let addr = ptr.as_ptr() as usize;
+        self.allocated_chunks
+            .read()
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(&start, _)| addr < start + self.config.chunk_size)
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        self.owns(ptr).then_some(self.config.chunk_size)
+    }
+
+    fn max_alignment(&self) -> usize {
+        self.config.alignment
+    }
+
+    fn available_memory(&self) -> usize {
+        self.free_count.load(Ordering::Relaxed) * self.config.chunk_size
+    }
+
+    fn total_memory(&self) -> usize {
+        self.total_memory.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for SafeMemoryPool {
+    fn drop(&mut self) {
+        let leaked = self.allocated_count.load(Ordering::Acquire);
+        if leaked == 0 {
+            return;
+        }
+
+        warn!(
+            leaked_chunks = leaked,
+            leaked_bytes = leaked * self.config.chunk_size,
+            "SafeMemoryPool dropped with outstanding allocations"
+        );
+
+        #[cfg(debug_assertions)]
+        for (index, chunk) in self.allocated_chunks.read().values().enumerate() {
+            if let Some(site) = &chunk.lock().allocation_site
+                && site.status() == std::backtrace::BacktraceStatus::Captured
+            {
+                warn!(chunk = index, "Leaked chunk allocated at:\n{}", site);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -248,3 +570,249 @@ pub struct SafePoolStats {
     pub total_memory_bytes: usize,
     pub chunk_size: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(alignment: usize) -> SafeMemoryPool {
+        SafeMemoryPool::new(SafePoolConfig {
+            chunk_size: 256,
+            alignment,
+            initial_chunks: 4,
+            max_chunks: 16,
+            ..SafePoolConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn chunks_honour_the_configured_alignment() {
+        for alignment in [1, 8, 64, 4096] {
+            let pool = pool(alignment);
+            assert_eq!(pool.max_alignment(), alignment);
+            let layout = Layout::from_size_align(256, alignment).unwrap();
+            let ptrs: Vec<_> = (0..8).map(|_| pool.allocate(layout).unwrap()).collect();
+            for ptr in ptrs {
+                assert_eq!(ptr.as_ptr() as usize % alignment, 0);
+                pool.deallocate(ptr, layout);
+            }
+            assert_eq!(pool.outstanding_allocations(), 0);
+        }
+    }
+
+    #[test]
+    fn stricter_alignment_is_rejected_without_taking_a_chunk() {
+        let pool = pool(64);
+        let layout = Layout::from_size_align(64, 128).unwrap();
+        assert!(matches!(
+            pool.allocate(layout),
+            Err(AllocError::AlignmentNotSupported {
+                required: 128,
+                supported: 64
+            })
+        ));
+        assert_eq!(pool.get_stats().free_chunks, 4);
+    }
+
+    #[test]
+    fn pointers_map_back_to_their_chunks() {
+        let pool = pool(64);
+        let layout = Layout::from_size_align(256, 8).unwrap();
+        let first = pool.allocate(layout).unwrap();
+        let second = pool.allocate(layout).unwrap();
+
+        let inside = NonNull::new(first.as_ptr().wrapping_add(255)).unwrap();
+        assert!(pool.owns(inside));
+        assert_eq!(pool.allocation_size(inside), Some(256));
+
+        pool.deallocate(first, layout);
+        assert!(!pool.owns(first));
+        assert!(pool.owns(second));
+        assert_eq!(pool.outstanding_allocations(), 1);
+        pool.deallocate(second, layout);
+        assert_eq!(pool.outstanding_allocations(), 0);
+        pool.verify_accounting().unwrap();
+    }
+
+    #[test]
+    fn bytes_written_through_the_slice_read_back() {
+        let pool = pool(64);
+        let handle = pool.allocate_chunk().unwrap();
+        {
+            let mut bytes = handle.as_mut_slice();
+            assert_eq!(bytes.len(), 256);
+            for (index, byte) in bytes.iter_mut().enumerate() {
+                *byte = index as u8 ^ 0xA5;
+            }
+        }
+
+        let bytes = handle.as_slice();
+        assert!(
+            bytes
+                .iter()
+                .enumerate()
+                .all(|(index, &byte)| byte == index as u8 ^ 0xA5)
+        );
+        drop(bytes);
+        assert_eq!(handle.copy_to_vec(4).unwrap(), [0xA5, 0xA4, 0xA7, 0xA6]);
+        assert!(handle.copy_to_vec(257).is_err());
+        pool.deallocate_chunk(handle);
+    }
+
+    #[test]
+    fn byte_pattern_round_trips_through_copies() {
+        let pool = pool(64);
+        let handle = pool.allocate_chunk().unwrap();
+        let pattern: Vec<u8> = (0..=255u8).rev().collect();
+
+        handle.copy_from_slice(&pattern).unwrap();
+        let copied = handle.copy_to_vec(256).unwrap();
+        // The copy is owned: it outlives the chunk going back to the pool
+        pool.deallocate_chunk(handle);
+        assert_eq!(copied, pattern);
+
+        // A shorter write leaves the rest of the chunk as it was
+        let handle = pool.allocate_chunk().unwrap();
+        handle.copy_from_slice(&pattern).unwrap();
+        handle.copy_from_slice(b"order").unwrap();
+        let head = handle.copy_to_vec(8).unwrap();
+        assert_eq!(head, [b'o', b'r', b'd', b'e', b'r', 250, 249, 248]);
+        assert!(handle.copy_to_vec(0).unwrap().is_empty());
+
+        assert!(matches!(
+            handle.copy_from_slice(&[0; 257]),
+            Err(AllocError::SizeExceeded {
+                size: 257,
+                max: 256
+            })
+        ));
+        assert!(matches!(
+            handle.copy_to_vec(257),
+            Err(AllocError::SizeExceeded {
+                size: 257,
+                max: 256
+            })
+        ));
+        pool.deallocate_chunk(handle);
+    }
+
+    #[test]
+    fn reused_chunks_are_zeroed_only_when_asked() {
+        for zero_on_alloc in [false, true] {
+            let pool = SafeMemoryPool::new(SafePoolConfig {
+                chunk_size: 256,
+                initial_chunks: 1,
+                max_chunks: 1,
+                zero_on_alloc,
+                ..SafePoolConfig::default()
+            })
+            .unwrap();
+            let dirty = pool.allocate_chunk().unwrap();
+            dirty.as_mut_slice().fill(0xCD);
+            pool.deallocate_chunk(dirty);
+
+            let reused = pool.allocate_chunk().unwrap();
+            let expected = if zero_on_alloc { 0 } else { 0xCD };
+            assert!(reused.as_slice().iter().all(|&byte| byte == expected));
+            pool.deallocate_chunk(reused);
+        }
+    }
+
+    fn checked_pool() -> SafeMemoryPool {
+        SafeMemoryPool::new(SafePoolConfig {
+            chunk_size: 256,
+            initial_chunks: 4,
+            max_chunks: 16,
+            check_accounting: true,
+            ..SafePoolConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn accounting_holds_across_allocate_grow_and_free() {
+        let pool = checked_pool();
+        // Past the 4 preallocated chunks, so the pool grows
+        let mut handles: Vec<_> = (0..10).map(|_| pool.allocate_chunk().unwrap()).collect();
+        pool.verify_accounting().unwrap();
+        assert_eq!(pool.get_stats().total_memory_bytes, 10 * 256);
+
+        for handle in handles.drain(..6) {
+            pool.deallocate_chunk(handle);
+        }
+        assert_eq!(pool.shrink(2), 4);
+        handles.extend((0..5).map(|_| pool.allocate_chunk().unwrap()));
+        pool.verify_accounting().unwrap();
+
+        for handle in handles {
+            pool.deallocate_chunk(handle);
+        }
+        pool.verify_accounting().unwrap();
+        let stats = pool.get_stats();
+        assert_eq!(stats.allocated_chunks, 0);
+        assert_eq!(stats.total_memory_bytes, stats.free_chunks * 256);
+    }
+
+    #[test]
+    fn injected_desync_is_reported() {
+        let pool = pool(64);
+        pool.total_memory.fetch_add(256, Ordering::Relaxed);
+        assert_eq!(
+            pool.verify_accounting(),
+            Err(AccountingError::TotalMemoryMismatch {
+                recorded: 5 * 256,
+                computed: 4 * 256
+            })
+        );
+
+        pool.total_memory.fetch_sub(256, Ordering::Relaxed);
+        pool.free_count.fetch_sub(1, Ordering::Relaxed);
+        assert_eq!(
+            pool.verify_accounting(),
+            Err(AccountingError::FreeCountMismatch {
+                recorded: 3,
+                actual: 4
+            })
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "SafeMemoryPool accounting desync")]
+    fn checked_pool_asserts_on_desync() {
+        let pool = checked_pool();
+        pool.total_memory.fetch_add(1, Ordering::Relaxed);
+        let _ = pool.allocate_chunk();
+    }
+
+    #[test]
+    fn alignment_must_be_a_power_of_two() {
+        let config = SafePoolConfig {
+            alignment: 48,
+            ..SafePoolConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn empty_chunks_and_oversized_initial_counts_are_rejected() {
+        assert!(SafePoolConfig::default().validate().is_ok());
+        for config in [
+            SafePoolConfig {
+                chunk_size: 0,
+                ..SafePoolConfig::default()
+            },
+            SafePoolConfig {
+                initial_chunks: 17,
+                max_chunks: 16,
+                ..SafePoolConfig::default()
+            },
+        ] {
+            assert!(matches!(
+                SafeMemoryPool::new(config),
+                Err(AllocError::InvalidLayout(_))
+            ));
+        }
+    }
+}
@@ -4,20 +4,31 @@
 
 use crate::core::memory::allocator::{AllocError, MemoryAllocator};
 use crossbeam::queue::SegQueue;
+use serde::{Deserialize, Deserializer};
 use std::alloc::{Layout, alloc};
+use std::collections::HashMap;
 use std::ptr::NonNull;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 const CACHE_LINE_SIZE: usize = 64;
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
 pub struct SlabConfig {
     pub min_object_size: usize,
     pub max_object_size: usize,
     pub objects_per_slab: usize,
     pub pre_allocate_slabs: usize,
     pub cache_align: bool,
+    /// Explicit object sizes, strictly ascending and within
+    /// `[min_object_size, max_object_size]`. `None` doubles from
+    /// `min_object_size`, which suits sizes that do not cluster.
+    pub size_classes: Option<Vec<usize>>,
+    /// Blocks to pre-allocate keyed by size class; classes left out get
+    /// `pre_allocate_slabs`
+    #[serde(deserialize_with = "size_keyed")]
+    pub pre_allocate_per_class: Option<HashMap<usize, usize>>,
 }
 
 impl Default for SlabConfig {
@@ -28,10 +39,95 @@ impl Default for SlabConfig {
             objects_per_slab: 1024,
             pre_allocate_slabs: 100,
             cache_align: true,
+            size_classes: None,
+            pre_allocate_per_class: None,
         }
     }
 }
 
+impl SlabConfig {
+    /// The size classes to carve, ascending
+    pub fn resolve_size_classes(&self) -> Result<Vec<usize>, AllocError> {
+        let Some(classes) = &self.size_classes else {
+            let mut size_classes = Vec::new();
+            let mut size = self.min_object_size;
+            while size <= self.max_object_size {
+                size_classes.push(size);
+                size *= 2; // Double each time for simplicity
+            }
+            return Ok(size_classes);
+        };
+
+        if classes.is_empty() {
+            return Err(AllocError::InvalidLayout(
+                "Size class list is empty".to_string(),
+            ));
+        }
+        if let Some(pair) = classes.windows(2).find(|pair| pair[0] >= pair[1]) {
+            return Err(AllocError::InvalidLayout(format!(
+                "Size classes must be strictly ascending, found {} before {}",
+                pair[0], pair[1]
+            )));
+        }
+        if let Some(&size) = classes
+            .iter()
+            .find(|&&size| size < self.min_object_size || size > self.max_object_size)
+        {
+            return Err(AllocError::InvalidLayout(format!(
+                "Size class {} is outside [{}, {}]",
+                size, self.min_object_size, self.max_object_size
+            )));
+        }
+        Ok(classes.clone())
+    }
+
+    /// Blocks pre-allocated for `size_class`
+    pub fn preallocation_for(&self, size_class: usize) -> usize {
+        self.pre_allocate_per_class
+            .as_ref()
+            .and_then(|counts| counts.get(&size_class))
+            .copied()
+            .unwrap_or(self.pre_allocate_slabs)
+    }
+
+    // Every per-class count must name one of `size_classes`
+    fn validate_preallocation(&self, size_classes: &[usize]) -> Result<(), AllocError> {
+        let Some(counts) = &self.pre_allocate_per_class else {
+            return Ok(());
+        };
+        let mut unknown: Vec<usize> = counts
+            .keys()
+            .copied()
+            .filter(|size| size_classes.binary_search(size).is_err())
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort_unstable();
+        Err(AllocError::InvalidLayout(format!(
+            "Pre-allocation given for sizes {:?} that are not size classes {:?}",
+            unknown, size_classes
+        )))
+    }
+}
+
+// TOML table keys are strings, so sizes arrive as "64" rather than 64
+fn size_keyed<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<HashMap<usize, usize>>, D::Error> {
+    let counts = HashMap::<String, usize>::deserialize(deserializer)?;
+    counts
+        .into_iter()
+        .map(|(size, count)| {
+            size.trim()
+                .parse()
+                .map(|size| (size, count))
+                .map_err(|_| serde::de::Error::custom(format!("'{size}' is not a size class")))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
 // Pre-allocated memory block
 struct MemoryBlock {
     ptr: usize,  // Store as usize to avoid Send/Sync issues
@@ -45,6 +141,8 @@ pub struct SlabAllocator {
     // Lock-free queues for each size class
     free_blocks: Arc<Vec<Arc<SegQueue<MemoryBlock>>>>,
     size_classes: Vec<usize>,
+    // Start address and size of every pre-allocated block, sorted by address
+    regions: Vec<(usize, usize)>,
     allocated_count: AtomicUsize,
     freed_count: AtomicUsize,
     total_memory: AtomicUsize,
@@ -52,16 +150,12 @@ pub struct SlabAllocator {
 
 impl SlabAllocator {
     pub fn new(config: SlabConfig) -> Result<Self, AllocError> {
-        // Calculate size classes
-        let mut size_classes = Vec::new();
-        let mut size = config.min_object_size;
-        while size <= config.max_object_size {
-            size_classes.push(size);
-            size = size * 2; // Double each time for simplicity
-        }
+        let size_classes = config.resolve_size_classes()?;
+        config.validate_preallocation(&size_classes)?;
 
         // Pre-allocate all memory blocks
         let mut free_blocks = Vec::new();
+        let mut regions = Vec::new();
         let mut total_memory = 0;
 
         for &size_class in &size_classes {
@@ -76,7 +170,7 @@ impl SlabAllocator {
                     .map_err(|e| AllocError::InvalidLayout(e.to_string()))?
             };
 
-            for _ in 0..config.pre_allocate_slabs {
+            for _ in 0..config.preallocation_for(size_class) {
                 let ptr = unsafe { alloc(layout) };
                 if ptr.is_null() {
                     return Err(AllocError::OutOfMemory);
@@ -86,6 +180,7 @@ impl SlabAllocator {
                     ptr: ptr as usize,
                     size: size_class,
                 });
+                regions.push((ptr as usize, size_class));
 
                 total_memory += size_class;
             }
@@ -93,20 +188,30 @@ impl SlabAllocator {
             free_blocks.push(queue);
         }
 
+        regions.sort_unstable();
+
         Ok(Self {
             config,
             free_blocks: Arc::new(free_blocks),
             size_classes,
+            regions,
             allocated_count: AtomicUsize::new(0),
             freed_count: AtomicUsize::new(0),
             total_memory: AtomicUsize::new(total_memory),
         })
     }
 
+    // Smallest class that fits `size`
     fn get_size_class_index(&self, size: usize) -> Option<usize> {
-        self.size_classes
-            .iter()
-            .position(|&class_size| class_size >= size)
+        let index = self
+            .size_classes
+            .partition_point(|&class_size| class_size < size);
+        (index < self.size_classes.len()).then_some(index)
+    }
+
+    /// Object size of each class, ascending
+    pub fn size_classes(&self) -> &[usize] {
+        &self.size_classes
     }
 
     pub fn allocate_object(&self, size: usize) -> Result<NonNull<u8>, AllocError> {
@@ -114,7 +219,7 @@ impl SlabAllocator {
             self.get_size_class_index(size)
                 .ok_or_else(|| AllocError::SizeExceeded {
                     size,
-                    max: self.config.max_object_size,
+                    max: self.size_classes.last().copied().unwrap_or(0),
                 })?;
 
         if let Some(block) = self.free_blocks[class_idx].pop() {
@@ -166,6 +271,54 @@ impl SlabAllocator {
         }
     }
 
+    /// Take back every block at once, e.g. at session close, instead of
+    /// freeing objects one by one. The free lists are rebuilt from the
+    /// pre-allocated regions and the allocation counters start over.
+    /// Returns how many blocks were still outstanding.
+    ///
+    /// # Safety
+    ///
+    /// The allocator must be quiescent: no pointer it handed out may be used
+    /// afterwards, and no other thread may allocate or free during the reset.
+    /// Any pointer still held would alias a block that gets issued again.
+    pub unsafe fn reset_epoch(&self) -> usize {
+        let mut queued = 0;
+        for queue in self.free_blocks.iter() {
+            while queue.pop().is_some() {
+                queued += 1;
+            }
+        }
+
+        for &(ptr, size) in &self.regions {
+            if let Some(class_idx) = self.get_size_class_index(size) {
+                self.free_blocks[class_idx].push(MemoryBlock { ptr, size });
+            }
+        }
+        debug_assert!(
+            self.free_blocks
+                .iter()
+                .zip(&self.size_classes)
+                .all(|(queue, &size)| queue.len() <= self.config.preallocation_for(size)),
+            "Slab class queue holds more blocks than were pre-allocated"
+        );
+
+        self.allocated_count.store(0, Ordering::Relaxed);
+        self.freed_count.store(0, Ordering::Relaxed);
+
+        let outstanding = self.regions.len().saturating_sub(queued);
+        tracing::debug!(outstanding, "SlabAllocator epoch reset");
+        outstanding
+    }
+
+    /// Free blocks of each size class, ascending by size
+    pub fn free_blocks_per_class(&self) -> Vec<(usize, usize)> {
+        self.size_classes
+            .iter()
+            .zip(self.free_blocks.iter())
+            .map(|(&size, queue)| (size, queue.len()))
+            .collect()
+    }
+
     pub fn get_stats(&self) -> SlabStats {
         SlabStats {
             allocated_objects: self.allocated_count.load(Ordering::Relaxed),
@@ -185,6 +338,40 @@ impl MemoryAllocator for SlabAllocator {
         self.deallocate_object(ptr, layout.size());
     }
 
+    /// `layout` must map to the size class of the block at `ptr`, otherwise
+    /// the block would be queued under another class
+    fn deallocate_checked(&self, ptr: NonNull<u8>, layout: Layout) -> Result<(), AllocError> {
+        if cfg!(debug_assertions) {
+            let address = ptr.as_ptr() as usize;
+            let recorded = self
+                .allocation_size(ptr)
+                .ok_or(AllocError::ForeignPointer { address })?;
+            let routed = self
+                .get_size_class_index(layout.size())
+                .map(|index| self.size_classes[index]);
+            if routed != Some(recorded) {
+                return Err(AllocError::LayoutMismatch {
+                    address,
+                    requested: layout.size(),
+                    recorded,
+                });
+            }
+        }
+        self.deallocate(ptr, layout);
+        Ok(())
+    }
+
+    fn owns(&self, ptr: NonNull<u8>) -> bool {
+        self.allocation_size(ptr).is_some()
+    }
+
+    fn allocation_size(&self, ptr: NonNull<u8>) -> Option<usize> {
+        let addr = ptr.as_ptr() as usize;
+        let index = self.regions.partition_point(|&(start, _)| start <= addr);
+        let (start, size) = *self.regions.get(index.checked_sub(1)?)?;
+        (addr < start + size).then_some(size)
+    }
+
     fn available_memory(&self) -> usize {
         // Approximate - count free blocks
         self.free_blocks
@@ -231,3 +418,154 @@ impl Drop for SlabAllocator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_land_in_the_smallest_class_that_fits() {
+        let doubling = SlabConfig::default().resolve_size_classes().unwrap();
+        assert_eq!(doubling, [64, 128, 256, 512, 1024, 2048, 4096, 8192]);
+
+        let slab = SlabAllocator::new(SlabConfig {
+            min_object_size: 64,
+            max_object_size: 256,
+            pre_allocate_slabs: 2,
+            size_classes: Some(vec![64, 96, 128, 256]),
+            ..SlabConfig::default()
+        })
+        .unwrap();
+
+        let order = slab.allocate_object(80).unwrap();
+        assert_eq!(
+            slab.free_blocks_per_class(),
+            [(64, 2), (96, 1), (128, 2), (256, 2)]
+        );
+        slab.deallocate_object(order, 80);
+        assert_eq!(
+            slab.free_blocks_per_class(),
+            [(64, 2), (96, 2), (128, 2), (256, 2)]
+        );
+
+        assert!(matches!(
+            slab.allocate_object(257),
+            Err(AllocError::SizeExceeded {
+                size: 257,
+                max: 256
+            })
+        ));
+    }
+
+    #[test]
+    fn each_class_preallocates_its_configured_count() {
+        let config: SlabConfig = toml::from_str(
+            r#"
+            min_object_size = 64
+            max_object_size = 1024
+            pre_allocate_slabs = 10
+
+            [pre_allocate_per_class]
+            "64" = 500
+            "1024" = 2
+            "#,
+        )
+        .unwrap();
+        let slab = SlabAllocator::new(config.clone()).unwrap();
+
+        assert_eq!(
+            slab.free_blocks_per_class(),
+            [(64, 500), (128, 10), (256, 10), (512, 10), (1024, 2)]
+        );
+        let expected_memory = 64 * 500 + (128 + 256 + 512) * 10 + 1024 * 2;
+        assert_eq!(slab.get_stats().total_memory, expected_memory);
+
+        let mut unknown = config;
+        unknown
+            .pre_allocate_per_class
+            .as_mut()
+            .unwrap()
+            .insert(100, 1);
+        assert!(matches!(
+            SlabAllocator::new(unknown),
+            Err(AllocError::InvalidLayout(message)) if message.contains("[100]")
+        ));
+    }
+
+    #[test]
+    fn epoch_reset_reclaims_every_block_for_reuse() {
+        let slab = SlabAllocator::new(SlabConfig {
+            min_object_size: 64,
+            max_object_size: 128,
+            pre_allocate_slabs: 8,
+            size_classes: Some(vec![64, 128]),
+            ..SlabConfig::default()
+        })
+        .unwrap();
+        // Every block of both classes, as (address, size)
+        let drain = |slab: &SlabAllocator| {
+            let mut issued: Vec<_> = (0..8)
+                .flat_map(|_| [64, 128])
+                .map(|size| (slab.allocate_object(size).unwrap().as_ptr() as usize, size))
+                .collect();
+            assert!(slab.allocate_object(64).is_err());
+            assert!(slab.allocate_object(128).is_err());
+            issued.sort_unstable();
+            issued
+        };
+
+        let mut first = drain(&slab);
+        // A few objects are freed individually; the rest are still out at close
+        for (ptr, size) in first.drain(..3) {
+            slab.deallocate_object(NonNull::new(ptr as *mut u8).unwrap(), size);
+        }
+
+        // SAFETY: no pointer from `first` is used after the reset
+        assert_eq!(unsafe { slab.reset_epoch() }, 13);
+        let stats = slab.get_stats();
+        assert_eq!((stats.allocated_objects, stats.freed_objects), (0, 0));
+        assert_eq!(slab.free_blocks_per_class(), [(64, 8), (128, 8)]);
+
+        let second = drain(&slab);
+        assert_eq!(second.len(), 16);
+        assert!(first.iter().all(|block| second.contains(block)));
+        assert_eq!(slab.get_stats().allocated_objects, 16);
+    }
+
+    #[test]
+    fn each_allocator_owns_only_its_own_pointers() {
+        use crate::core::memory::lock_free_pool::{LockFreeMemoryPool, PoolConfig};
+
+        let slab = SlabAllocator::new(SlabConfig {
+            max_object_size: 256,
+            pre_allocate_slabs: 2,
+            ..SlabConfig::default()
+        })
+        .unwrap();
+        let pool = LockFreeMemoryPool::new(PoolConfig {
+            chunk_size: 1024,
+            initial_chunks: 4,
+            max_chunks: 4,
+            ..PoolConfig::default()
+        })
+        .unwrap();
+        let small = Layout::from_size_align(64, 8).unwrap();
+        let large = Layout::from_size_align(1024, 8).unwrap();
+
+        let from_slab = slab.allocate(small).unwrap();
+        let from_pool = pool.allocate(large).unwrap();
+
+        assert!(slab.owns(from_slab));
+        assert!(!slab.owns(from_pool));
+        assert!(pool.owns(from_pool));
+        assert!(!pool.owns(from_slab));
+
+        let mut on_stack = 0u64;
+        let foreign = NonNull::from(&mut on_stack).cast::<u8>();
+        assert!(!slab.owns(foreign));
+        assert!(!pool.owns(foreign));
+
+        slab.deallocate(from_slab, small);
+        pool.deallocate(from_pool, large);
+    }
+}
@@ -0,0 +1,300 @@
+//! Single-producer single-consumer ring buffer in a pool chunk
+//!
+//! Carries values from one thread to exactly one other without a heap
+//! allocation per message. The slots live in one chunk of a
+//! [`LockFreeMemoryPool`] and the indices are atomics, so neither side ever
+//! takes a lock.
+//!
+//! The engine's event bus does not use it: several feeds and the simulated
+//! venue publish to the bus, and its drop-oldest policy pops from the
+//! publishing side, so the bus stays on a bounded crossbeam channel. The
+//! ring is for pipeline stages with exactly one thread on each end.
+//!
+//! # Safety
+//! This module uses unsafe code to move values in and out of the raw chunk.
+//! All unsafe operations are documented with SAFETY comments.
+
+#![allow(unsafe_code)] // Slots are uninitialized memory inside a pool chunk
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use crate::core::memory::allocator::{AllocError, MemoryAllocator};
+use crate::core::memory::lock_free_pool::LockFreeMemoryPool;
+use crossbeam::utils::CachePadded;
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Shared state behind an [`SpscProducer`] / [`SpscConsumer`] pair.
+///
+/// `head` and `tail` count pops and pushes since creation and wrap freely;
+/// the slot of index `i` is `i & mask`. Each sits on its own cache line so
+/// the producer's writes to `tail` do not evict the consumer's `head`.
+pub struct SpscRing<T> {
+    pool: Arc<LockFreeMemoryPool>,
+    slots: NonNull<T>,
+    layout: Layout,
+    mask: usize,
+    // Next slot to pop; written only by the consumer
+    head: CachePadded<AtomicUsize>,
+    // Next slot to push; written only by the producer
+    tail: CachePadded<AtomicUsize>,
+}
+
+// SAFETY: Values of T are only moved between threads, never shared: a slot is
+// written by the producer before `tail` is released and read by the consumer
+// after acquiring it, so T: Send is enough
+unsafe impl<T: Send> Send for SpscRing<T> {}
+// SAFETY: The only shared access is through the atomics; each slot is touched
+// by one side at a time as described above
+unsafe impl<T: Send> Sync for SpscRing<T> {}
+
+impl<T> SpscRing<T> {
+    /// Create a ring of `capacity` slots in one chunk of `pool`.
+    ///
+    /// `capacity` must be a power of two and `capacity * size_of::<T>()` must
+    /// fit in the pool's chunk size. The chunk is returned when both halves
+    /// are dropped.
+    #[allow(clippy::new_ret_no_self)] // The ring is only reachable through its two halves
+    pub fn new(
+        pool: Arc<LockFreeMemoryPool>,
+        capacity: usize,
+    ) -> Result<(SpscProducer<T>, SpscConsumer<T>), AllocError> {
+        if !capacity.is_power_of_two() {
+            return Err(AllocError::InvalidLayout(format!(
+                "Ring capacity {} must be a power of two",
+                capacity
+            )));
+        }
+        if size_of::<T>() == 0 {
+            return Err(AllocError::InvalidLayout(
+                "Ring element type must not be zero-sized".to_string(),
+            ));
+        }
+        let layout =
+            Layout::array::<T>(capacity).map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
+        let slots = pool.allocate(layout)?.cast::<T>();
+
+        let ring = Arc::new(Self {
+            pool,
+            slots,
+            layout,
+            mask: capacity - 1,
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+        });
+        Ok((
+            SpscProducer {
+                ring: Arc::clone(&ring),
+                cached_head: 0,
+            },
+            SpscConsumer {
+                ring,
+                cached_tail: 0,
+            },
+        ))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Values waiting to be popped; only a snapshot while the other side runs
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        // SAFETY: `index & mask` is below the capacity the chunk was sized for
+        unsafe { self.slots.as_ptr().add(index & self.mask) }
+    }
+}
+
+impl<T> Drop for SpscRing<T> {
+    fn drop(&mut self) {
+        // Both halves are gone, so nothing else touches the indices
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            // SAFETY: Slots in head..tail hold values pushed and never popped
+            unsafe { self.slot(head).drop_in_place() };
+            head = head.wrapping_add(1);
+        }
+        self.pool.deallocate(self.slots.cast(), self.layout);
+    }
+}
+
+impl<T> std::fmt::Debug for SpscRing<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpscRing")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Pushing half of an [`SpscRing`]; not `Clone`, so there is one producer
+#[derive(Debug)]
+pub struct SpscProducer<T> {
+    ring: Arc<SpscRing<T>>,
+    // Last `head` seen; the ring has at least this much free space
+    cached_head: usize,
+}
+
+impl<T> SpscProducer<T> {
+    /// Push without blocking, handing `value` back if the ring is full
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.cached_head) > ring.mask {
+            self.cached_head = ring.head.load(Ordering::Acquire);
+            if tail.wrapping_sub(self.cached_head) > ring.mask {
+                return Err(value);
+            }
+        }
+        // SAFETY: The slot is outside head..tail, so the consumer has finished
+        // with it (Acquire on `head`) and will not read it before the Release below
+        unsafe { ring.slot(tail).write(value) };
+        ring.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn ring(&self) -> &SpscRing<T> {
+        &self.ring
+    }
+}
+
+/// Popping half of an [`SpscRing`]; not `Clone`, so there is one consumer
+#[derive(Debug)]
+pub struct SpscConsumer<T> {
+    ring: Arc<SpscRing<T>>,
+    // Last `tail` seen; at least this many values are ready
+    cached_tail: usize,
+}
+
+impl<T> SpscConsumer<T> {
+    /// Oldest value, if any
+    pub fn pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        if head == self.cached_tail {
+            self.cached_tail = ring.tail.load(Ordering::Acquire);
+            if head == self.cached_tail {
+                return None;
+            }
+        }
+        // SAFETY: The slot is inside head..tail, so the producer wrote it
+        // before releasing `tail`, and will not reuse it until `head` moves on
+        let value = unsafe { ring.slot(head).read() };
+        ring.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    pub fn ring(&self) -> &SpscRing<T> {
+        &self.ring
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::lock_free_pool::PoolConfig;
+
+    fn pool() -> Arc<LockFreeMemoryPool> {
+        Arc::new(
+            LockFreeMemoryPool::new(PoolConfig {
+                chunk_size: 256,
+                initial_chunks: 2,
+                max_chunks: 2,
+                thread_cache_size: 0,
+                ..PoolConfig::default()
+            })
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn fills_to_capacity_then_drains_in_order() {
+        let (mut producer, mut consumer) = SpscRing::<u64>::new(pool(), 8).unwrap();
+        for value in 0..8 {
+            producer.push(value).unwrap();
+        }
+        assert_eq!(producer.push(8), Err(8));
+        assert_eq!(producer.ring().len(), 8);
+
+        let drained: Vec<u64> = std::iter::from_fn(|| consumer.pop()).collect();
+        assert_eq!(drained, (0..8).collect::<Vec<_>>());
+        assert!(consumer.ring().is_empty());
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn indices_wrap_around_the_slots() {
+        let (mut producer, mut consumer) = SpscRing::<u64>::new(pool(), 4).unwrap();
+        // Keep the ring partly full so head and tail straddle the wrap point
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+        for value in 2..1_000 {
+            producer.push(value).unwrap();
+            assert_eq!(consumer.pop(), Some(value - 2));
+            assert_eq!(consumer.ring().len(), 2);
+        }
+        assert_eq!(consumer.pop(), Some(998));
+        assert_eq!(consumer.pop(), Some(999));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn values_cross_threads_in_order() {
+        let (mut producer, mut consumer) = SpscRing::<u64>::new(pool(), 16).unwrap();
+        const COUNT: u64 = 100_000;
+        let sender = std::thread::spawn(move || {
+            for value in 0..COUNT {
+                let mut pending = value;
+                while let Err(back) = producer.push(pending) {
+                    pending = back;
+                    std::thread::yield_now();
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < COUNT {
+            match consumer.pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => std::thread::yield_now(),
+            }
+        }
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn dropping_the_ring_drops_unpopped_values_and_returns_the_chunk() {
+        let pool = pool();
+        let value = Arc::new(());
+        let (mut producer, consumer) = SpscRing::<Arc<()>>::new(Arc::clone(&pool), 4).unwrap();
+        producer.push(Arc::clone(&value)).unwrap();
+        producer.push(Arc::clone(&value)).unwrap();
+        assert_eq!(pool.get_stats().allocated_chunks, 1);
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        drop((producer, consumer));
+        assert_eq!(Arc::strong_count(&value), 1);
+        assert_eq!(pool.get_stats().allocated_chunks, 0);
+    }
+
+    #[test]
+    fn rejects_capacities_that_cannot_be_masked_or_do_not_fit() {
+        assert!(SpscRing::<u64>::new(pool(), 6).is_err());
+        assert!(SpscRing::<u64>::new(pool(), 64).is_err());
+        assert!(SpscRing::<()>::new(pool(), 4).is_err());
+    }
+}
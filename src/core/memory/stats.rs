@@ -1,21 +1,179 @@
+use crate::core::diagnostics::{FlightEvent, FlightRecorder};
+use crate::core::memory::allocator::AllocError;
+use crate::core::memory::clock::{Clock, SystemClock};
+use hdrhistogram::{CreationError, Histogram};
 use parking_lot::RwLock;
-use std::collections::VecDeque;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 const HISTORY_SIZE: usize = 1000;
+// Record a fragmentation sample every Nth allocation/deallocation to keep the lock off the hot path
+const FRAGMENTATION_SAMPLE_INTERVAL: u64 = 64;
 const PERCENTILES: &[f64] = &[0.5, 0.9, 0.95, 0.99, 0.999];
 
+// Accounting helpers that clamp instead of wrapping, so a mismatched free
+// reads as zero bytes outstanding rather than terabytes. Each returns the
+// previous value, like the `fetch_*` methods they replace.
+pub(crate) fn saturating_fetch_add(counter: &AtomicUsize, n: usize) -> usize {
+    match counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+        Some(v.saturating_add(n))
+    }) {
+        Ok(prev) | Err(prev) => prev,
+    }
+}
+
+pub(crate) fn saturating_fetch_sub(counter: &AtomicUsize, n: usize) -> usize {
+    match counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+        Some(v.saturating_sub(n))
+    }) {
+        Ok(prev) | Err(prev) => prev,
+    }
+}
+
+pub(crate) fn saturating_fetch_add_u64(counter: &AtomicU64, n: u64) -> u64 {
+    match counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+        Some(v.saturating_add(n))
+    }) {
+        Ok(prev) | Err(prev) => prev,
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct AllocationStats {
     pub total_allocations: u64,
     pub total_deallocations: u64,
+    pub failed_allocations: u64,
+    /// Most frequent failure reason and its count, if any allocation failed
+    pub dominant_failure: Option<(&'static str, u64)>,
     pub current_allocated_bytes: usize,
     pub peak_allocated_bytes: usize,
     pub allocation_rate: f64,
     pub deallocation_rate: f64,
     pub fragmentation_ratio: f64,
+    /// Allocations that had to wait for a chunk to be freed
+    pub blocked_allocations: u64,
+    /// Total time spent waiting in blocked allocations
+    pub total_wait_ns: u64,
     pub latency_stats: LatencyStats,
+    pub dealloc_latency_stats: LatencyStats,
+    /// Window the rates were computed over
+    pub uptime: Duration,
+}
+
+impl AllocationStats {
+    /// Combine snapshots from independent pools into one view.
+    ///
+    /// Counters, bytes and waits are summed and rates recomputed over the
+    /// longer of the two windows (both end now, so that is their union).
+    /// Approximations, since raw samples are not retained:
+    /// - `peak_allocated_bytes` is the sum of the peaks, an upper bound as the
+    ///   pools need not have peaked together
+    /// - latency means are weighted by operation count and exact; percentiles
+    ///   are count-weighted averages of the inputs' percentiles, exact only
+    ///   when the pools have similar distributions; min and max are exact
+    /// - `dominant_failure` is the more frequent of the two inputs' reasons
+    pub fn merge(&self, other: &AllocationStats) -> AllocationStats {
+        let total_allocations = self
+            .total_allocations
+            .saturating_add(other.total_allocations);
+        let total_deallocations = self
+            .total_deallocations
+            .saturating_add(other.total_deallocations);
+        let current = self
+            .current_allocated_bytes
+            .saturating_add(other.current_allocated_bytes);
+        let peak = self
+            .peak_allocated_bytes
+            .saturating_add(other.peak_allocated_bytes);
+        let uptime = self.uptime.max(other.uptime);
+        let secs = uptime.as_secs_f64();
+        let rate = |count: u64| if secs > 0.0 { count as f64 / secs } else { 0.0 };
+
+        let dominant_failure = match (self.dominant_failure, other.dominant_failure) {
+            (Some((a, a_count)), Some((b, b_count))) if a == b => {
+                Some((a, a_count.saturating_add(b_count)))
+            }
+            (Some(a), Some(b)) => Some(if b.1 > a.1 { b } else { a }),
+            (a, b) => a.or(b),
+        };
+
+        AllocationStats {
+            total_allocations,
+            total_deallocations,
+            failed_allocations: self
+                .failed_allocations
+                .saturating_add(other.failed_allocations),
+            dominant_failure,
+            current_allocated_bytes: current,
+            peak_allocated_bytes: peak,
+            allocation_rate: rate(total_allocations),
+            deallocation_rate: rate(total_deallocations),
+            fragmentation_ratio: if peak == 0 {
+                0.0
+            } else {
+                1.0 - (current as f64 / peak as f64)
+            },
+            blocked_allocations: self
+                .blocked_allocations
+                .saturating_add(other.blocked_allocations),
+            total_wait_ns: self.total_wait_ns.saturating_add(other.total_wait_ns),
+            latency_stats: self.latency_stats.merge(
+                self.total_allocations,
+                &other.latency_stats,
+                other.total_allocations,
+            ),
+            dealloc_latency_stats: self.dealloc_latency_stats.merge(
+                self.total_deallocations,
+                &other.dealloc_latency_stats,
+                other.total_deallocations,
+            ),
+            uptime,
+        }
+    }
+}
+
+/// The atomic counters of a [`MemoryStats`], read without taking any lock.
+/// Cheap enough to poll from a monitoring thread while allocators run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AllocationCounters {
+    pub total_allocations: u64,
+    pub total_deallocations: u64,
+    pub failed_allocations: u64,
+    pub current_allocated_bytes: usize,
+    pub peak_allocated_bytes: usize,
+    pub blocked_allocations: u64,
+    pub total_wait_ns: u64,
+    /// Time since the stats started or were reset
+    pub uptime: Duration,
+}
+
+impl AllocationCounters {
+    /// Allocations per second over `uptime`
+    pub fn allocation_rate(&self) -> f64 {
+        Self::rate(self.total_allocations, self.uptime)
+    }
+
+    /// Deallocations per second over `uptime`
+    pub fn deallocation_rate(&self) -> f64 {
+        Self::rate(self.total_deallocations, self.uptime)
+    }
+
+    /// 1 - current/peak allocated bytes
+    pub fn fragmentation_ratio(&self) -> f64 {
+        if self.peak_allocated_bytes == 0 {
+            0.0
+        } else {
+            1.0 - (self.current_allocated_bytes as f64 / self.peak_allocated_bytes as f64)
+        }
+    }
+
+    fn rate(count: u64, uptime: Duration) -> f64 {
+        let secs = uptime.as_secs_f64();
+        if secs > 0.0 { count as f64 / secs } else { 0.0 }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -30,6 +188,81 @@ pub struct LatencyStats {
     pub max_ns: u64,
 }
 
+impl LatencyStats {
+    const EMPTY: LatencyStats = LatencyStats {
+        mean_ns: 0.0,
+        median_ns: 0.0,
+        p90_ns: 0.0,
+        p95_ns: 0.0,
+        p99_ns: 0.0,
+        p999_ns: 0.0,
+        min_ns: 0,
+        max_ns: 0,
+    };
+
+    /// Summary of an HDR histogram of nanosecond latencies
+    pub fn from_histogram(histogram: &Histogram<u64>) -> LatencyStats {
+        if histogram.is_empty() {
+            return LatencyStats::EMPTY;
+        }
+        LatencyStats {
+            mean_ns: histogram.mean(),
+            median_ns: histogram.value_at_quantile(PERCENTILES[0]) as f64,
+            p90_ns: histogram.value_at_quantile(PERCENTILES[1]) as f64,
+            p95_ns: histogram.value_at_quantile(PERCENTILES[2]) as f64,
+            p99_ns: histogram.value_at_quantile(PERCENTILES[3]) as f64,
+            p999_ns: histogram.value_at_quantile(PERCENTILES[4]) as f64,
+            min_ns: histogram.min(),
+            max_ns: histogram.max(),
+        }
+    }
+
+    // Count-weighted combination, see `AllocationStats::merge`
+    fn merge(&self, weight: u64, other: &LatencyStats, other_weight: u64) -> LatencyStats {
+        match (weight, other_weight) {
+            (0, 0) => return LatencyStats::EMPTY,
+            (_, 0) => return *self,
+            (0, _) => return *other,
+            _ => {}
+        }
+
+        let (w, ow) = (weight as f64, other_weight as f64);
+        let min_ns = self.min_ns.min(other.min_ns);
+        let max_ns = self.max_ns.max(other.max_ns);
+        let blend =
+            |a: f64, b: f64| ((a * w + b * ow) / (w + ow)).clamp(min_ns as f64, max_ns as f64);
+
+        LatencyStats {
+            mean_ns: (self.mean_ns * w + other.mean_ns * ow) / (w + ow),
+            median_ns: blend(self.median_ns, other.median_ns),
+            p90_ns: blend(self.p90_ns, other.p90_ns),
+            p95_ns: blend(self.p95_ns, other.p95_ns),
+            p99_ns: blend(self.p99_ns, other.p99_ns),
+            p999_ns: blend(self.p999_ns, other.p999_ns),
+            min_ns,
+            max_ns,
+        }
+    }
+}
+
+/// Bounds for the optional HDR histogram latency recorder
+#[derive(Debug, Clone, Copy)]
+pub struct HistogramConfig {
+    /// Decimal digits of precision kept for every recorded value (1-5)
+    pub significant_digits: u8,
+    /// Largest latency the histogram can represent; larger samples are clamped
+    pub max_trackable_ns: u64,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self {
+            significant_digits: 3,
+            max_trackable_ns: 60_000_000_000, // 60 seconds
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MemoryStats {
     allocations: AtomicU64,
@@ -37,12 +270,25 @@ pub struct MemoryStats {
     allocated_bytes: AtomicUsize,
     peak_bytes: AtomicUsize,
     failed_allocations: AtomicU64,
+    failure_reasons: RwLock<HashMap<&'static str, u64>>,
+    blocked_allocations: AtomicU64,
+    allocation_wait_ns: AtomicU64,
 
     latency_history: RwLock<LatencyTracker>,
+    dealloc_latency_history: RwLock<LatencyTracker>,
+    // Lifetime latency distribution, only present when built via `with_histogram`
+    latency_histogram: Option<RwLock<Histogram<u64>>>,
     allocation_sizes: RwLock<SizeDistribution>,
+    fragmentation_events: AtomicU64,
+    fragmentation_history: RwLock<VecDeque<(Duration, f64, usize)>>,
+
+    // Latency and size distributions record one in this many operations
+    sample_every: u64,
 
+    clock: Arc<dyn Clock>,
     start_time: Instant,
-    last_update: RwLock<Instant>,
+    // Nanoseconds after `start_time`; atomic so recording takes no lock
+    last_update_ns: AtomicU64,
 }
 
 #[derive(Debug)]
@@ -77,6 +323,10 @@ impl LatencyTracker {
         self.cache_valid = false;
     }
 
+    /// Nearest-rank percentile: the smallest sample such that at least
+    /// `percentile` of all samples are less than or equal to it, i.e. the
+    /// element at 1-based rank `ceil(percentile * len)`. `percentile` is
+    /// taken to the nearest millionth.
     fn get_percentile(&mut self, percentile: f64) -> u64 {
         if self.samples.is_empty() {
             return 0;
@@ -89,22 +339,17 @@ impl LatencyTracker {
             self.cache_valid = true;
         }
 
-        let index = ((self.sorted_cache.len() as f64 - 1.0) * percentile) as usize;
-        self.sorted_cache[index]
+        let len = self.sorted_cache.len();
+        // In integers, as 0.55 * 100 is 55.00000000000001 in floating point
+        // and would ceil to rank 56
+        let millionths = (percentile.clamp(0.0, 1.0) * 1e6).round() as usize;
+        let rank = (millionths * len).div_ceil(1_000_000);
+        self.sorted_cache[rank.clamp(1, len) - 1]
     }
 
     fn get_stats(&mut self) -> LatencyStats {
         if self.samples.is_empty() {
-            return LatencyStats {
-                mean_ns: 0.0,
-                median_ns: 0.0,
-                p90_ns: 0.0,
-                p95_ns: 0.0,
-                p99_ns: 0.0,
-                p999_ns: 0.0,
-                min_ns: 0,
-                max_ns: 0,
-            };
+            return LatencyStats::EMPTY;
         }
 
         let sum: u64 = self.samples.iter().sum();
@@ -135,6 +380,78 @@ struct SizeBucket {
     max_size: usize,
     count: u64,
     total_bytes: u64,
+    // Sum of squared sizes, for the byte-weighted mean
+    total_bytes_sq: u128,
+}
+
+/// Allocation counts by size range, in ascending bucket order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeHistogram {
+    pub buckets: Vec<SizeHistogramBucket>,
+    pub total_count: u64,
+    pub total_bytes: u64,
+    // Sum of squared sizes, for the byte-weighted mean
+    #[serde(skip)]
+    total_bytes_sq: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeHistogramBucket {
+    /// Human-readable range, e.g. "65B-256B"
+    pub range: String,
+    pub min_size: usize,
+    /// Inclusive upper bound; `None` for the open-ended last bucket
+    pub max_size: Option<usize>,
+    pub count: u64,
+    /// Share of all allocations, 0-100
+    pub percentage: f64,
+    pub bytes: u64,
+}
+
+impl SizeHistogram {
+    /// Bucket holding the most allocations; ties go to the smaller sizes
+    pub fn modal_bucket(&self) -> Option<&SizeHistogramBucket> {
+        self.buckets
+            .iter()
+            .filter(|bucket| bucket.count > 0)
+            .reduce(|modal, bucket| {
+                if bucket.count > modal.count {
+                    bucket
+                } else {
+                    modal
+                }
+            })
+    }
+
+    fn scale(&mut self, factor: u64) {
+        for bucket in &mut self.buckets {
+            bucket.count = bucket.count.saturating_mul(factor);
+            bucket.bytes = bucket.bytes.saturating_mul(factor);
+        }
+        self.total_count = self.total_count.saturating_mul(factor);
+        self.total_bytes = self.total_bytes.saturating_mul(factor);
+        self.total_bytes_sq = self.total_bytes_sq.saturating_mul(u128::from(factor));
+    }
+
+    /// Mean allocation size
+    pub fn mean_size(&self) -> f64 {
+        if self.total_count == 0 {
+            0.0
+        } else {
+            self.total_bytes as f64 / self.total_count as f64
+        }
+    }
+
+    /// Mean size weighted by bytes: the size of the allocation an average
+    /// allocated byte lives in. Well above `mean_size` when a few large
+    /// allocations dominate memory even though small ones dominate counts.
+    pub fn byte_weighted_mean_size(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            self.total_bytes_sq as f64 / self.total_bytes as f64
+        }
+    }
 }
 
 impl SizeDistribution {
@@ -161,6 +478,7 @@ impl SizeDistribution {
             if size >= bucket.min_size && size <= bucket.max_size {
                 bucket.count += 1;
                 bucket.total_bytes += size as u64;
+                bucket.total_bytes_sq += (size as u128) * (size as u128);
                 break;
             }
         }
@@ -172,9 +490,18 @@ impl SizeDistribution {
             return Vec::new();
         }
 
-        self.buckets
-            .iter()
+        self.histogram()
+            .buckets
+            .into_iter()
             .filter(|b| b.count > 0)
+            .map(|bucket| (bucket.range, bucket.percentage, bucket.bytes))
+            .collect()
+    }
+
+    fn histogram(&self) -> SizeHistogram {
+        let buckets = self
+            .buckets
+            .iter()
             .map(|bucket| {
                 let range = if bucket.max_size == usize::MAX {
                     format!("{}+", Self::format_size(bucket.min_size))
@@ -186,11 +513,29 @@ impl SizeDistribution {
                     )
                 };
 
-                let percentage = (bucket.count as f64 / self.total_count as f64) * 100.0;
+                let percentage = if self.total_count == 0 {
+                    0.0
+                } else {
+                    (bucket.count as f64 / self.total_count as f64) * 100.0
+                };
 
-                (range, percentage, bucket.total_bytes)
+                SizeHistogramBucket {
+                    range,
+                    min_size: bucket.min_size,
+                    max_size: (bucket.max_size != usize::MAX).then_some(bucket.max_size),
+                    count: bucket.count,
+                    percentage,
+                    bytes: bucket.total_bytes,
+                }
             })
-            .collect()
+            .collect();
+
+        SizeHistogram {
+            buckets,
+            total_count: self.total_count,
+            total_bytes: self.buckets.iter().map(|b| b.total_bytes).sum(),
+            total_bytes_sq: self.buckets.iter().map(|b| b.total_bytes_sq).sum(),
+        }
     }
 
     fn format_size(size: usize) -> String {
@@ -218,41 +563,93 @@ impl SizeBucket {
             max_size: max,
             count: 0,
             total_bytes: 0,
+            total_bytes_sq: 0,
         }
     }
 }
 
 impl MemoryStats {
     pub fn new() -> Self {
-        let now = Instant::now();
+        Self::new_with_clock(SystemClock)
+    }
+
+    /// Stats whose uptime and rates follow `clock`, e.g. a `MockClock`
+    pub fn new_with_clock(clock: impl Clock + 'static) -> Self {
+        let now = clock.now();
         Self {
             allocations: AtomicU64::new(0),
             deallocations: AtomicU64::new(0),
             allocated_bytes: AtomicUsize::new(0),
             peak_bytes: AtomicUsize::new(0),
             failed_allocations: AtomicU64::new(0),
+            failure_reasons: RwLock::new(HashMap::new()),
+            blocked_allocations: AtomicU64::new(0),
+            allocation_wait_ns: AtomicU64::new(0),
             latency_history: RwLock::new(LatencyTracker::new()),
+            dealloc_latency_history: RwLock::new(LatencyTracker::new()),
+            latency_histogram: None,
             allocation_sizes: RwLock::new(SizeDistribution::new()),
+            fragmentation_events: AtomicU64::new(0),
+            fragmentation_history: RwLock::new(VecDeque::with_capacity(HISTORY_SIZE)),
+            sample_every: 1,
+            clock: Arc::new(clock),
             start_time: now,
-            last_update: RwLock::new(now),
+            last_update_ns: AtomicU64::new(0),
         }
     }
 
+    /// Record latency and size distributions for only one in `sample_every`
+    /// operations, taking the distribution locks off most of the hot path.
+    /// Counters and byte totals stay exact; exported size counts are scaled
+    /// back up by the sampling rate. Sampling is every Nth operation, so a
+    /// workload whose sizes repeat with a period sharing factors with N skews
+    /// the distributions.
+    pub fn with_sampling(sample_every: u64) -> Self {
+        Self {
+            sample_every: sample_every.max(1),
+            ..Self::new()
+        }
+    }
+
+    pub fn sample_every(&self) -> u64 {
+        self.sample_every
+    }
+
+    /// Record latencies into an HDR histogram as well as the recent-sample window,
+    /// so snapshot percentiles cover the whole session with bounded memory.
+    pub fn with_histogram(config: HistogramConfig) -> Result<Self, CreationError> {
+        let histogram =
+            Histogram::new_with_bounds(1, config.max_trackable_ns, config.significant_digits)?;
+        Ok(Self {
+            latency_histogram: Some(RwLock::new(histogram)),
+            ..Self::new()
+        })
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
     pub fn uptime(&self) -> Duration {
-        self.start_time.elapsed()
+        self.clock.now().saturating_duration_since(self.start_time)
     }
 
     pub fn time_since_last_update(&self) -> Duration {
-        self.last_update.read().elapsed()
+        let last_update = Duration::from_nanos(self.last_update_ns.load(Ordering::Relaxed));
+        self.uptime().saturating_sub(last_update)
+    }
+
+    fn touch(&self) {
+        let now = self.uptime().as_nanos() as u64;
+        self.last_update_ns.store(now, Ordering::Relaxed);
     }
 
     pub fn record_allocation(&self, size: usize, latency_ns: u64) {
-        let prev_allocations = self.allocations.fetch_add(1, Ordering::Relaxed);
-        let current = self.allocated_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        let prev_allocations = saturating_fetch_add_u64(&self.allocations, 1);
+        let current = saturating_fetch_add(&self.allocated_bytes, size).saturating_add(size);
 
-        // Track allocation count for potential overflow detection
         if prev_allocations == u64::MAX {
-            tracing::warn!("Allocation counter overflow detected");
+            tracing::warn!("Allocation counter saturated");
         }
 
         let mut peak = self.peak_bytes.load(Ordering::Relaxed);
@@ -268,16 +665,24 @@ impl MemoryStats {
             }
         }
 
-        self.latency_history.write().record(latency_ns);
-        self.allocation_sizes.write().record(size);
-        *self.last_update.write() = Instant::now();
+        if prev_allocations % self.sample_every == 0 {
+            self.latency_history.write().record(latency_ns);
+            if let Some(histogram) = &self.latency_histogram {
+                histogram
+                    .write()
+                    .saturating_record_n(latency_ns, self.sample_every);
+            }
+            self.allocation_sizes.write().record(size);
+        }
+        self.maybe_sample_fragmentation();
+        self.touch();
     }
 
-    pub fn record_deallocation(&self, size: usize) {
-        let prev_deallocations = self.deallocations.fetch_add(1, Ordering::Relaxed);
-        let prev_bytes = self.allocated_bytes.fetch_sub(size, Ordering::Relaxed);
+    pub fn record_deallocation(&self, size: usize, latency_ns: u64) {
+        let prev_deallocations = saturating_fetch_add_u64(&self.deallocations, 1);
+        let prev_bytes = saturating_fetch_sub(&self.allocated_bytes, size);
 
-        // Detect potential underflow or mismatched deallocation
+        // Mismatched deallocation: the byte count clamps at zero instead of wrapping
         if prev_bytes < size {
             tracing::error!(
                 "Memory deallocation underflow: tried to deallocate {} bytes but only {} were allocated",
@@ -286,13 +691,44 @@ impl MemoryStats {
             );
         }
         if prev_deallocations == u64::MAX {
-            tracing::warn!("Deallocation counter overflow detected");
+            tracing::warn!("Deallocation counter saturated");
+        }
+        if prev_deallocations % self.sample_every == 0 {
+            self.dealloc_latency_history.write().record(latency_ns);
         }
-        *self.last_update.write() = Instant::now();
+        self.maybe_sample_fragmentation();
+        self.touch();
     }
 
-    pub fn record_failed_allocation(&self) {
-        let prev_failures = self.failed_allocations.fetch_add(1, Ordering::Relaxed);
+    fn maybe_sample_fragmentation(&self) {
+        let events = self.fragmentation_events.fetch_add(1, Ordering::Relaxed);
+        if events % FRAGMENTATION_SAMPLE_INTERVAL != 0 {
+            return;
+        }
+
+        let sample = (
+            self.uptime(),
+            self.calculate_fragmentation(),
+            self.allocated_bytes.load(Ordering::Relaxed),
+        );
+
+        let mut history = self.fragmentation_history.write();
+        if history.len() >= HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(sample);
+    }
+
+    /// Sampled `(time since start, fragmentation ratio, current bytes)` history,
+    /// oldest first. One sample is taken every `FRAGMENTATION_SAMPLE_INTERVAL` events.
+    pub fn fragmentation_series(&self) -> Vec<(Duration, f64, usize)> {
+        self.fragmentation_history.read().iter().copied().collect()
+    }
+
+    pub fn record_failed_allocation(&self, err: &AllocError) {
+        let prev_failures = saturating_fetch_add_u64(&self.failed_allocations, 1);
+        *self.failure_reasons.write().entry(err.kind()).or_insert(0) += 1;
+        FlightRecorder::global().record(FlightEvent::AllocFailure { reason: err.kind() });
 
         // Alert on high failure rate
         if prev_failures > 0 && prev_failures % 1000 == 0 {
@@ -303,21 +739,82 @@ impl MemoryStats {
         }
     }
 
-    pub fn get_snapshot(&self) -> AllocationStats {
-        let elapsed = self.start_time.elapsed().as_secs_f64();
-        let allocations = self.allocations.load(Ordering::Relaxed);
-        let deallocations = self.deallocations.load(Ordering::Relaxed);
+    /// Record time an allocation spent waiting for the pool to free up
+    pub fn record_allocation_wait(&self, wait_ns: u64) {
+        saturating_fetch_add_u64(&self.blocked_allocations, 1);
+        saturating_fetch_add_u64(&self.allocation_wait_ns, wait_ns);
+    }
 
-        AllocationStats {
-            total_allocations: allocations,
-            total_deallocations: deallocations,
+    /// Failure counts per `AllocError` variant, most frequent first
+    pub fn failure_breakdown(&self) -> Vec<(&'static str, u64)> {
+        let mut breakdown: Vec<_> = self
+            .failure_reasons
+            .read()
+            .iter()
+            .map(|(&kind, &count)| (kind, count))
+            .collect();
+        breakdown.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        breakdown
+    }
+
+    /// Counters only, from atomic loads: never blocks and never delays a
+    /// recording thread. Use this to poll; `get_snapshot` and
+    /// `get_latency_stats` lock the latency window to sort it.
+    pub fn get_counters(&self) -> AllocationCounters {
+        AllocationCounters {
+            total_allocations: self.allocations.load(Ordering::Relaxed),
+            total_deallocations: self.deallocations.load(Ordering::Relaxed),
+            failed_allocations: self.failed_allocations.load(Ordering::Relaxed),
             current_allocated_bytes: self.allocated_bytes.load(Ordering::Relaxed),
             peak_allocated_bytes: self.peak_bytes.load(Ordering::Relaxed),
-            allocation_rate: allocations as f64 / elapsed,
-            deallocation_rate: deallocations as f64 / elapsed,
-            fragmentation_ratio: self.calculate_fragmentation(),
-            latency_stats: self.latency_history.write().get_stats(),
+            blocked_allocations: self.blocked_allocations.load(Ordering::Relaxed),
+            total_wait_ns: self.allocation_wait_ns.load(Ordering::Relaxed),
+            uptime: self.uptime(),
+        }
+    }
+
+    /// Allocation and deallocation latency percentiles. Sorts the sample
+    /// window under its lock, stalling any allocation that samples meanwhile.
+    pub fn get_latency_stats(&self) -> (LatencyStats, LatencyStats) {
+        (
+            self.latency_stats(),
+            self.dealloc_latency_history.write().get_stats(),
+        )
+    }
+
+    /// Counters plus latency percentiles, see `get_latency_stats`
+    pub fn get_snapshot(&self) -> AllocationStats {
+        let counters = self.get_counters();
+        let (latency_stats, dealloc_latency_stats) = self.get_latency_stats();
+
+        AllocationStats {
+            total_allocations: counters.total_allocations,
+            total_deallocations: counters.total_deallocations,
+            failed_allocations: counters.failed_allocations,
+            dominant_failure: self.failure_breakdown().first().copied(),
+            current_allocated_bytes: counters.current_allocated_bytes,
+            peak_allocated_bytes: counters.peak_allocated_bytes,
+            allocation_rate: counters.allocation_rate(),
+            deallocation_rate: counters.deallocation_rate(),
+            fragmentation_ratio: counters.fragmentation_ratio(),
+            blocked_allocations: counters.blocked_allocations,
+            total_wait_ns: counters.total_wait_ns,
+            latency_stats,
+            dealloc_latency_stats,
+            uptime: counters.uptime,
+        }
+    }
+
+    fn latency_stats(&self) -> LatencyStats {
+        let Some(histogram) = &self.latency_histogram else {
+            return self.latency_history.write().get_stats();
+        };
+
+        let histogram = histogram.read();
+        if histogram.is_empty() {
+            return self.latency_history.write().get_stats();
         }
+        LatencyStats::from_histogram(&histogram)
     }
 
     fn calculate_fragmentation(&self) -> f64 {
@@ -335,16 +832,35 @@ impl MemoryStats {
         self.allocation_sizes.read().get_distribution()
     }
 
+    /// Every size bucket, including empty ones, for export. Under sampling
+    /// the counts and bytes are estimates scaled by the sampling rate.
+    pub fn size_histogram(&self) -> SizeHistogram {
+        let mut histogram = self.allocation_sizes.read().histogram();
+        if self.sample_every > 1 {
+            histogram.scale(self.sample_every);
+        }
+        histogram
+    }
+
     pub fn reset(&self) {
         self.allocations.store(0, Ordering::Relaxed);
         self.deallocations.store(0, Ordering::Relaxed);
         self.allocated_bytes.store(0, Ordering::Relaxed);
         self.peak_bytes.store(0, Ordering::Relaxed);
         self.failed_allocations.store(0, Ordering::Relaxed);
+        self.failure_reasons.write().clear();
+        self.blocked_allocations.store(0, Ordering::Relaxed);
+        self.allocation_wait_ns.store(0, Ordering::Relaxed);
 
         *self.latency_history.write() = LatencyTracker::new();
+        *self.dealloc_latency_history.write() = LatencyTracker::new();
+        if let Some(histogram) = &self.latency_histogram {
+            histogram.write().reset();
+        }
         *self.allocation_sizes.write() = SizeDistribution::new();
-        *self.last_update.write() = Instant::now();
+        self.fragmentation_events.store(0, Ordering::Relaxed);
+        self.fragmentation_history.write().clear();
+        self.touch();
     }
 }
 
@@ -366,7 +882,337 @@ impl AllocationTimer {
         }
     }
 
+    /// Start timing against `clock` rather than the system clock
+    pub fn start_with(clock: &dyn Clock) -> Self {
+        Self { start: clock.now() }
+    }
+
     pub fn elapsed_ns(&self) -> u64 {
         self.start.elapsed().as_nanos() as u64
     }
+
+    pub fn elapsed_ns_with(&self, clock: &dyn Clock) -> u64 {
+        clock.now().saturating_duration_since(self.start).as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::clock::MockClock;
+
+    fn recorded(samples: impl IntoIterator<Item = u64>) -> LatencyTracker {
+        let mut tracker = LatencyTracker::new();
+        for sample in samples {
+            tracker.record(sample);
+        }
+        tracker
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        // Recorded out of order; sorted, the sample at rank r is r
+        let mut tracker = recorded((1..=HISTORY_SIZE as u64).rev());
+        assert_eq!(tracker.get_percentile(0.5), 500);
+        assert_eq!(tracker.get_percentile(0.9), 900);
+        assert_eq!(tracker.get_percentile(0.99), 990);
+        assert_eq!(tracker.get_percentile(0.999), 999);
+        assert_eq!(tracker.get_percentile(1.0), 1000);
+        assert_eq!(tracker.get_percentile(0.0), 1);
+        assert_eq!(tracker.get_percentile(0.55), 550);
+
+        let mut tracker = recorded(1..=100);
+        assert_eq!(tracker.get_percentile(0.55), 55);
+        assert_eq!(tracker.get_percentile(0.999), 100);
+        assert_eq!(tracker.get_percentile(0.901), 91);
+    }
+
+    #[test]
+    fn percentiles_of_tiny_histories() {
+        assert_eq!(recorded([]).get_percentile(0.99), 0);
+        let mut single = recorded([42]);
+        for percentile in [0.0, 0.5, 0.999, 1.0] {
+            assert_eq!(single.get_percentile(percentile), 42);
+        }
+        let mut pair = recorded([7, 3]);
+        assert_eq!(pair.get_percentile(0.5), 3);
+        assert_eq!(pair.get_percentile(0.51), 7);
+    }
+
+    #[test]
+    fn percentiles_cover_only_the_retained_history() {
+        let mut tracker = recorded(1..=HISTORY_SIZE as u64 + 500);
+        assert_eq!(tracker.get_percentile(0.0), 501);
+        assert_eq!(tracker.get_percentile(0.5), 1000);
+        assert_eq!(tracker.get_percentile(0.999), 1499);
+    }
+
+    fn snapshot(
+        allocations: u64,
+        bytes: usize,
+        latency: LatencyStats,
+        secs: u64,
+    ) -> AllocationStats {
+        AllocationStats {
+            total_allocations: allocations,
+            total_deallocations: allocations / 2,
+            failed_allocations: 1,
+            dominant_failure: Some(("pool exhausted", 1)),
+            current_allocated_bytes: bytes,
+            peak_allocated_bytes: bytes * 2,
+            allocation_rate: 0.0,
+            deallocation_rate: 0.0,
+            fragmentation_ratio: 0.0,
+            blocked_allocations: 2,
+            total_wait_ns: 1_000,
+            latency_stats: latency,
+            dealloc_latency_stats: latency,
+            uptime: Duration::from_secs(secs),
+        }
+    }
+
+    fn latency(mean_ns: f64, p99_ns: f64, min_ns: u64, max_ns: u64) -> LatencyStats {
+        LatencyStats {
+            mean_ns,
+            median_ns: mean_ns,
+            p90_ns: p99_ns,
+            p95_ns: p99_ns,
+            p99_ns,
+            p999_ns: p99_ns,
+            min_ns,
+            max_ns,
+        }
+    }
+
+    #[test]
+    fn merged_totals_are_the_sum_of_the_inputs() {
+        let a = snapshot(300, 4096, latency(100.0, 400.0, 50, 500), 10);
+        let b = snapshot(100, 1024, latency(200.0, 800.0, 20, 900), 20);
+
+        let merged = a.merge(&b);
+
+        assert_eq!(merged.total_allocations, 400);
+        assert_eq!(merged.total_deallocations, 200);
+        assert_eq!(merged.failed_allocations, 2);
+        assert_eq!(merged.dominant_failure, Some(("pool exhausted", 2)));
+        assert_eq!(merged.current_allocated_bytes, 5120);
+        assert_eq!(merged.peak_allocated_bytes, 10240);
+        assert_eq!(
+            (merged.blocked_allocations, merged.total_wait_ns),
+            (4, 2_000)
+        );
+        // Rates over the longer window
+        assert_eq!(merged.uptime, Duration::from_secs(20));
+        assert_eq!(merged.allocation_rate, 20.0);
+        assert_eq!(merged.deallocation_rate, 10.0);
+        assert_eq!(merged.fragmentation_ratio, 0.5);
+        // Weighted 3:1 by allocation count
+        assert_eq!(merged.latency_stats.mean_ns, 125.0);
+        assert_eq!(merged.latency_stats.p99_ns, 500.0);
+        assert_eq!(
+            (merged.latency_stats.min_ns, merged.latency_stats.max_ns),
+            (20, 900)
+        );
+    }
+
+    #[test]
+    fn merging_an_idle_pool_keeps_the_busy_pools_latencies() {
+        let busy = snapshot(10, 640, latency(150.0, 300.0, 100, 400), 5);
+        let idle = snapshot(0, 0, LatencyStats::EMPTY, 5);
+
+        let merged = busy.merge(&idle);
+
+        let latency = merged.latency_stats;
+        assert_eq!(merged.total_allocations, 10);
+        assert_eq!((latency.mean_ns, latency.p99_ns), (150.0, 300.0));
+        assert_eq!((latency.min_ns, latency.max_ns), (100, 400));
+        let both_idle = idle.merge(&idle).latency_stats;
+        assert_eq!((both_idle.mean_ns, both_idle.max_ns), (0.0, 0));
+    }
+
+    #[test]
+    fn size_histogram_reports_bucket_shares_and_means() {
+        let stats = MemoryStats::new();
+        let sizes = [32; 6].into_iter().chain([128; 3]).chain([2048]);
+        for size in sizes {
+            stats.record_allocation(size, 100);
+        }
+
+        let histogram = stats.size_histogram();
+
+        let used: Vec<_> = histogram
+            .buckets
+            .iter()
+            .filter(|bucket| bucket.count > 0)
+            .map(|bucket| (bucket.range.as_str(), bucket.percentage, bucket.bytes))
+            .collect();
+        assert_eq!(
+            used,
+            [
+                ("0B-64B", 60.0, 192),
+                ("65B-256B", 30.0, 384),
+                ("1.0KB-4KB", 10.0, 2048),
+            ]
+        );
+        assert_eq!(histogram.buckets.len(), 8);
+        assert_eq!(histogram.buckets[7].max_size, None);
+        assert_eq!((histogram.total_count, histogram.total_bytes), (10, 2624));
+        assert_eq!(histogram.modal_bucket().unwrap().range, "0B-64B");
+        assert_eq!(histogram.mean_size(), 262.4);
+        let squares = 6.0 * 32.0 * 32.0 + 3.0 * 128.0 * 128.0 + 2048.0 * 2048.0;
+        assert_eq!(histogram.byte_weighted_mean_size(), squares / 2624.0);
+    }
+
+    #[test]
+    fn sampling_keeps_counters_exact_and_distributions_close() {
+        let stats = MemoryStats::with_sampling(10);
+        // Period 7 shares no factor with the sampling rate
+        let size_of = |i: u64| match i % 7 {
+            0..=3 => 32,
+            4 | 5 => 128,
+            _ => 2048,
+        };
+        let mut total_bytes = 0;
+        for i in 0..1000 {
+            stats.record_allocation(size_of(i), i);
+            total_bytes += size_of(i);
+        }
+        for i in 0..400 {
+            stats.record_deallocation(size_of(i), 50);
+        }
+
+        let snapshot = stats.get_snapshot();
+        assert_eq!(snapshot.total_allocations, 1000);
+        assert_eq!(snapshot.total_deallocations, 400);
+        let freed: usize = (0..400).map(size_of).sum();
+        assert_eq!(snapshot.current_allocated_bytes, total_bytes - freed);
+        assert!((snapshot.latency_stats.mean_ns - 499.5).abs() < 10.0);
+
+        let histogram = stats.size_histogram();
+        assert_eq!(histogram.total_count, 1000);
+        let share = |range: &str| {
+            histogram
+                .buckets
+                .iter()
+                .find(|bucket| bucket.range == range)
+                .unwrap()
+                .percentage
+        };
+        for (range, expected) in [
+            ("0B-64B", 400.0 / 7.0),
+            ("65B-256B", 200.0 / 7.0),
+            ("1.0KB-4KB", 100.0 / 7.0),
+        ] {
+            assert!(
+                (share(range) - expected).abs() < 3.0,
+                "{range}: {}",
+                share(range)
+            );
+        }
+    }
+
+    #[test]
+    fn mock_clock_drives_uptime_rates_and_timers() {
+        let clock = MockClock::new();
+        let stats = MemoryStats::new_with_clock(clock.clone());
+        for _ in 0..250 {
+            stats.record_allocation(64, 100);
+        }
+        for _ in 0..100 {
+            stats.record_deallocation(64, 100);
+        }
+        assert_eq!(stats.get_snapshot().allocation_rate, 0.0);
+
+        clock.advance(Duration::from_secs(1));
+        let snapshot = stats.get_snapshot();
+        assert_eq!(snapshot.uptime, Duration::from_secs(1));
+        assert_eq!(snapshot.allocation_rate, 250.0);
+        assert_eq!(snapshot.deallocation_rate, 100.0);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(stats.get_snapshot().allocation_rate, 125.0);
+
+        let timer = AllocationTimer::start_with(&clock);
+        clock.advance(Duration::from_nanos(1_500));
+        assert_eq!(timer.elapsed_ns_with(&clock), 1_500);
+    }
+
+    #[test]
+    fn over_freeing_clamps_allocated_bytes_at_zero() {
+        let stats = MemoryStats::new();
+        stats.record_allocation(100, 10);
+        stats.record_deallocation(100, 10);
+        stats.record_deallocation(4096, 10);
+
+        let snapshot = stats.get_snapshot();
+        assert_eq!(snapshot.current_allocated_bytes, 0);
+        assert_eq!(snapshot.peak_allocated_bytes, 100);
+        assert_eq!(snapshot.total_deallocations, 2);
+        assert_eq!(snapshot.fragmentation_ratio, 1.0);
+
+        // Accounting carries on from zero once frees match again
+        stats.record_allocation(64, 10);
+        assert_eq!(stats.get_snapshot().current_allocated_bytes, 64);
+
+        let counter = AtomicUsize::new(usize::MAX - 1);
+        assert_eq!(saturating_fetch_add(&counter, 5), usize::MAX - 1);
+        assert_eq!(counter.load(Ordering::Relaxed), usize::MAX);
+    }
+
+    #[test]
+    fn counters_poll_while_allocators_run() {
+        let stats = Arc::new(MemoryStats::new());
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let poller = {
+            let (stats, done) = (Arc::clone(&stats), Arc::clone(&done));
+            std::thread::spawn(move || {
+                let mut last = 0;
+                loop {
+                    let finished = done.load(Ordering::Acquire);
+                    let counters = stats.get_counters();
+                    assert!(
+                        counters.total_allocations >= last,
+                        "counters went backwards"
+                    );
+                    last = counters.total_allocations;
+                    if finished {
+                        return last;
+                    }
+                }
+            })
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..50_000 {
+                        stats.record_allocation(64, 100);
+                        stats.record_deallocation(64, 100);
+                    }
+                });
+            }
+        });
+        done.store(true, Ordering::Release);
+        // Its last poll started after every allocator finished
+        assert_eq!(poller.join().unwrap(), 200_000);
+
+        let counters = stats.get_counters();
+        assert_eq!(counters.total_allocations, 200_000);
+        assert_eq!(counters.total_deallocations, 200_000);
+        assert_eq!(counters.current_allocated_bytes, 0);
+    }
+
+    #[test]
+    fn counters_never_wait_on_the_latency_locks() {
+        let stats = Arc::new(MemoryStats::new());
+        stats.record_allocation(64, 100);
+
+        // Hold both locks the percentile path needs, as a long sort would
+        let _history = stats.latency_history.write();
+        let _dealloc_history = stats.dealloc_latency_history.write();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let poller = Arc::clone(&stats);
+        std::thread::spawn(move || tx.send(poller.get_counters().total_allocations).unwrap());
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(1));
+    }
 }
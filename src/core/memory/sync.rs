@@ -0,0 +1,92 @@
+// Synchronization primitives for the lock-free memory code
+// std, parking_lot and crossbeam normally; loom's model-checked stand-ins under `--cfg shriven_loom`
+
+#[cfg(shriven_loom)]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
+#[cfg(shriven_loom)]
+pub(crate) use loom::thread_local;
+#[cfg(shriven_loom)]
+pub(crate) use model::{Mutex, SegQueue};
+
+#[cfg(not(shriven_loom))]
+pub(crate) use crossbeam::queue::SegQueue;
+#[cfg(not(shriven_loom))]
+pub(crate) use parking_lot::Mutex;
+#[cfg(not(shriven_loom))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
+#[cfg(not(shriven_loom))]
+pub(crate) use std::thread_local;
+
+/// `UnsafeCell` with loom's closure-based access, so code using it runs
+/// unchanged under the model checker, which then tracks every access
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T> {
+    #[cfg(not(shriven_loom))]
+    inner: std::cell::UnsafeCell<T>,
+    #[cfg(shriven_loom)]
+    inner: loom::cell::UnsafeCell<T>,
+}
+
+impl<T> UnsafeCell<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            #[cfg(not(shriven_loom))]
+            inner: std::cell::UnsafeCell::new(value),
+            #[cfg(shriven_loom)]
+            inner: loom::cell::UnsafeCell::new(value),
+        }
+    }
+
+    /// Call `f` with a pointer to the contents. Dereferencing it has the
+    /// same obligations as `std::cell::UnsafeCell::get`.
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        #[cfg(not(shriven_loom))]
+        return f(self.inner.get());
+        #[cfg(shriven_loom)]
+        return self.inner.with_mut(f);
+    }
+}
+
+#[cfg(shriven_loom)]
+mod model {
+    use std::collections::VecDeque;
+
+    /// `parking_lot::Mutex` surface over loom's mutex
+    #[derive(Debug)]
+    pub(crate) struct Mutex<T>(loom::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self(loom::sync::Mutex::new(value))
+        }
+
+        pub(crate) fn lock(&self) -> loom::sync::MutexGuard<'_, T> {
+            self.0
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+        }
+    }
+
+    /// `SegQueue` surface over a locked deque. Crossbeam's queue spins on
+    /// atomics loom cannot see, which would hang the model.
+    #[derive(Debug)]
+    pub(crate) struct SegQueue<T>(Mutex<VecDeque<T>>);
+
+    impl<T> SegQueue<T> {
+        pub(crate) fn new() -> Self {
+            Self(Mutex::new(VecDeque::new()))
+        }
+
+        pub(crate) fn push(&self, value: T) {
+            self.0.lock().push_back(value);
+        }
+
+        pub(crate) fn pop(&self) -> Option<T> {
+            self.0.lock().pop_front()
+        }
+
+        pub(crate) fn len(&self) -> usize {
+            self.0.lock().len()
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// NUMA topology for ShrivenQ
+// Nodes, CPUs, memory and distances as the kernel reports them, or a stand-in
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+const SYSFS_NODE_DIR: &str = "/sys/devices/system/node";
+const SIMULATED_NODES: usize = 2;
+// Memory assumed for a node whose meminfo cannot be read
+const DEFAULT_NODE_MEMORY: usize = 16 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpu_mask: Vec<usize>,
+    pub memory_size: usize,
+    pub distance_map: HashMap<usize, u8>,
+}
+
+/// Where a [`NumaTopology`] came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologySource {
+    /// Read from the kernel's sysfs node directory
+    Sysfs,
+    /// Made up because the kernel reports no NUMA nodes; placement on it is
+    /// bookkeeping only
+    Simulated,
+}
+
+impl fmt::Display for TopologySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologySource::Sysfs => write!(f, "sysfs"),
+            TopologySource::Simulated => write!(f, "simulated"),
+        }
+    }
+}
+
+/// The machine's NUMA nodes, sorted by id
+#[derive(Debug, Clone)]
+pub struct NumaTopology {
+    pub nodes: Vec<NumaNode>,
+    pub source: TopologySource,
+}
+
+impl NumaTopology {
+    /// The kernel's topology, or a simulated two-node one where there is none
+    pub fn discover() -> Self {
+        match Self::from_sysfs(Path::new(SYSFS_NODE_DIR)) {
+            Some(nodes) => Self {
+                nodes,
+                source: TopologySource::Sysfs,
+            },
+            None => Self::simulated(),
+        }
+    }
+
+    /// Two nodes of eight CPUs and 32GB each, ten apart from each other
+    pub fn simulated() -> Self {
+        let nodes = (0..SIMULATED_NODES)
+            .map(|i| NumaNode {
+                id: i,
+                cpu_mask: (i * 8..(i + 1) * 8).collect(),
+                memory_size: 32 * 1024 * 1024 * 1024,
+                distance_map: (0..SIMULATED_NODES)
+                    .map(|j| (j, if i == j { 10 } else { 20 }))
+                    .collect(),
+            })
+            .collect();
+        Self {
+            nodes,
+            source: TopologySource::Simulated,
+        }
+    }
+
+    /// Read the nodes under `dir`, laid out like `/sys/devices/system/node`.
+    /// `None` if it holds no readable node.
+    pub fn from_sysfs(dir: &Path) -> Option<Vec<NumaNode>> {
+        let mut node_ids: Vec<usize> = std::fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                name.to_str()?.strip_prefix("node")?.parse().ok()
+            })
+            .collect();
+        node_ids.sort_unstable();
+
+        let nodes: Vec<NumaNode> = node_ids
+            .iter()
+            .filter_map(|&id| read_node(&dir.join(format!("node{id}")), id, &node_ids))
+            .collect();
+        (!nodes.is_empty()).then_some(nodes)
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.source == TopologySource::Simulated
+    }
+
+    /// One line for startup logs
+    pub fn summary(&self) -> String {
+        let cpus: usize = self.nodes.iter().map(|node| node.cpu_mask.len()).sum();
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        match self.source {
+            TopologySource::Sysfs => format!(
+                "{} node{}, {} CPU{}",
+                self.nodes.len(),
+                plural(self.nodes.len()),
+                cpus,
+                plural(cpus)
+            ),
+            TopologySource::Simulated => format!(
+                "unavailable, simulating {} node{} (no real placement)",
+                self.nodes.len(),
+                plural(self.nodes.len())
+            ),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "source": self.source.to_string(),
+            "nodes": self.nodes.iter().map(|node| json!({
+                "id": node.id,
+                "cpus": cpu_ranges(&node.cpu_mask),
+                "memory_bytes": node.memory_size,
+                "distances": self.nodes.iter()
+                    .map(|to| node.distance_map.get(&to.id))
+                    .collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Node list followed by the distance matrix, one row per node
+impl fmt::Display for NumaTopology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            writeln!(
+                f,
+                "node {}: CPUs {}, {:.1} GiB",
+                node.id,
+                cpu_ranges(&node.cpu_mask),
+                node.memory_size as f64 / (1024.0 * 1024.0 * 1024.0)
+            )?;
+        }
+
+        write!(f, "distances:")?;
+        for node in &self.nodes {
+            write!(f, " {:>4}", node.id)?;
+        }
+        for node in &self.nodes {
+            write!(f, "\n{:>10}", node.id)?;
+            for to in &self.nodes {
+                match node.distance_map.get(&to.id) {
+                    Some(distance) => write!(f, " {distance:>4}")?,
+                    None => write!(f, " {:>4}", "-")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_node(dir: &Path, id: usize, node_ids: &[usize]) -> Option<NumaNode> {
+    let cpu_mask = parse_cpu_list(&std::fs::read_to_string(dir.join("cpulist")).ok()?)?;
+    let memory_size = std::fs::read_to_string(dir.join("meminfo"))
+        .ok()
+        .and_then(|meminfo| parse_mem_total(&meminfo))
+        .unwrap_or(DEFAULT_NODE_MEMORY);
+
+    // `distance` lists this node's distance to every node, in node id order
+    let mut distance_map: HashMap<usize, u8> = std::fs::read_to_string(dir.join("distance"))
+        .ok()
+        .map(|distances| {
+            node_ids
+                .iter()
+                .copied()
+                .zip(distances.split_whitespace().map_while(|d| d.parse().ok()))
+                .collect()
+        })
+        .unwrap_or_default();
+    distance_map.entry(id).or_insert(10);
+
+    Some(NumaNode {
+        id,
+        cpu_mask,
+        memory_size,
+        distance_map,
+    })
+}
+
+// Kernel CPU list such as "0-7,16-23"; empty for a memory-only node
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+// "Node 0 MemTotal:  32827484 kB" in bytes
+fn parse_mem_total(meminfo: &str) -> Option<usize> {
+    let line = meminfo.lines().find(|line| line.contains("MemTotal:"))?;
+    let kb: usize = line.split_whitespace().nth(3)?.parse().ok()?;
+    kb.checked_mul(1024)
+}
+
+// Inverse of `parse_cpu_list`, collapsing runs into ranges
+fn cpu_ranges(cpus: &[usize]) -> String {
+    let mut sorted = cpus.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = sorted.into_iter().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end += 1;
+            iter.next();
+        }
+        ranges.push(if start == end {
+            start.to_string()
+        } else {
+            format!("{start}-{end}")
+        });
+    }
+    if ranges.is_empty() {
+        "none".to_string()
+    } else {
+        ranges.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A two-node sysfs tree: node0 with CPUs 0-3,8, node1 memory-only
+    fn mock_sysfs() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("shriven-numa-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (node, cpulist, mem_kb, distance) in [
+            (0, "0-3,8\n", 8 * 1024 * 1024, "10 21\n"),
+            (1, "\n", 4 * 1024 * 1024, "21 10\n"),
+        ] {
+            let node_dir = dir.join(format!("node{node}"));
+            std::fs::create_dir_all(&node_dir).unwrap();
+            std::fs::write(node_dir.join("cpulist"), cpulist).unwrap();
+            let meminfo =
+                format!("Node {node} MemTotal:       {mem_kb} kB\nNode {node} MemFree: 1 kB\n");
+            std::fs::write(node_dir.join("meminfo"), meminfo).unwrap();
+            std::fs::write(node_dir.join("distance"), distance).unwrap();
+        }
+        // Other entries in the real directory are not nodes
+        std::fs::write(dir.join("possible"), "0-1\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn rendered_topology_lists_each_node() {
+        let dir = mock_sysfs();
+        let topology = NumaTopology {
+            nodes: NumaTopology::from_sysfs(&dir).unwrap(),
+            source: TopologySource::Sysfs,
+        };
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            topology.to_string(),
+            "node 0: CPUs 0-3,8, 8.0 GiB\n\
+             node 1: CPUs none, 4.0 GiB\n\
+             distances:    0    1\n         0   10   21\n         1   21   10"
+        );
+        assert_eq!(topology.summary(), "2 nodes, 5 CPUs");
+
+        let json = topology.to_json();
+        assert_eq!(json["source"], "sysfs");
+        assert_eq!(json["nodes"][0]["cpus"], "0-3,8");
+        assert_eq!(json["nodes"][1]["memory_bytes"], 4u64 << 30);
+        assert_eq!(json["nodes"][1]["distances"], json!([21, 10]));
+    }
+
+    #[test]
+    fn simulated_topology_says_so() {
+        let topology = NumaTopology::simulated();
+        assert!(topology.is_simulated());
+        assert_eq!(
+            topology.summary(),
+            "unavailable, simulating 2 nodes (no real placement)"
+        );
+        assert!(
+            topology
+                .to_string()
+                .starts_with("node 0: CPUs 0-7, 32.0 GiB\nnode 1: CPUs 8-15")
+        );
+        assert_eq!(topology.to_json()["source"], "simulated");
+        assert!(NumaTopology::from_sysfs(Path::new("/nonexistent/shriven")).is_none());
+    }
+}
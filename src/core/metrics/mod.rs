@@ -0,0 +1,453 @@
+// Prometheus exposition for ShrivenQ
+// Renders allocator statistics in the Prometheus text format (version 0.0.4)
+
+use crate::core::memory::MemoryBackend;
+use crate::core::memory::stats::{AllocationStats, LatencyStats, SizeHistogram};
+use std::fmt::Write;
+
+#[cfg(feature = "hft-unsafe")]
+use crate::core::memory::numa_allocator::NumaStatsSnapshot;
+#[cfg(feature = "hft-unsafe")]
+use crate::core::memory::slab_allocator::SlabStats;
+
+const METRIC_PREFIX: &str = "shrivenq_memory";
+
+/// Minimal writer for the Prometheus text exposition format
+#[derive(Debug, Default)]
+pub struct PrometheusEncoder {
+    buffer: String,
+}
+
+impl PrometheusEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, help, "gauge");
+        self.sample(name, &[], value);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.header(name, help, "counter");
+        self.sample(name, &[], value as f64);
+    }
+
+    /// Summary with pre-computed `(quantile, value)` pairs
+    pub fn summary(
+        &mut self,
+        name: &str,
+        help: &str,
+        quantiles: &[(f64, f64)],
+        sum: f64,
+        count: u64,
+    ) {
+        self.header(name, help, "summary");
+        for &(quantile, value) in quantiles {
+            self.sample(name, &[("quantile", &quantile.to_string())], value);
+        }
+        self.sample(&format!("{}_sum", name), &[], sum);
+        self.sample(&format!("{}_count", name), &[], count as f64);
+    }
+
+    /// Gauge family with one labelled sample per entry
+    pub fn labelled_gauge(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        samples: &[(String, f64)],
+    ) {
+        self.header(name, help, "gauge");
+        for (label_value, value) in samples {
+            self.sample(name, &[(label, label_value)], *value);
+        }
+    }
+
+    /// Histogram from `(upper_bound, cumulative_count)` pairs; `None` is `+Inf`
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        buckets: &[(Option<f64>, u64)],
+        sum: f64,
+        count: u64,
+    ) {
+        self.header(name, help, "histogram");
+        let bucket_name = format!("{}_bucket", name);
+        for &(upper, cumulative) in buckets {
+            let le = upper.map_or_else(|| "+Inf".to_string(), |upper| upper.to_string());
+            self.sample(&bucket_name, &[("le", &le)], cumulative as f64);
+        }
+        if !buckets.iter().any(|(upper, _)| upper.is_none()) {
+            self.sample(&bucket_name, &[("le", "+Inf")], count as f64);
+        }
+        self.sample(&format!("{}_sum", name), &[], sum);
+        self.sample(&format!("{}_count", name), &[], count as f64);
+    }
+
+    pub fn finish(self) -> String {
+        self.buffer
+    }
+
+    fn header(&mut self, name: &str, help: &str, metric_type: &str) {
+        let _ = writeln!(self.buffer, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+        let _ = writeln!(
+            self.buffer,
+            "# TYPE {}_{} {}",
+            METRIC_PREFIX, name, metric_type
+        );
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.buffer, "{}_{}", METRIC_PREFIX, name);
+        if !labels.is_empty() {
+            let rendered: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            let _ = write!(self.buffer, "{{{}}}", rendered.join(","));
+        }
+        let _ = writeln!(self.buffer, " {}", value);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn encode_allocation_stats(encoder: &mut PrometheusEncoder, stats: &AllocationStats) {
+    encoder.gauge(
+        "allocated_bytes",
+        "Bytes currently allocated from the pool",
+        stats.current_allocated_bytes as f64,
+    );
+    encoder.gauge(
+        "peak_allocated_bytes",
+        "Highest number of bytes allocated at once",
+        stats.peak_allocated_bytes as f64,
+    );
+    encoder.counter(
+        "allocations_total",
+        "Successful allocations",
+        stats.total_allocations,
+    );
+    encoder.counter(
+        "deallocations_total",
+        "Deallocations",
+        stats.total_deallocations,
+    );
+    encoder.counter(
+        "failed_allocations_total",
+        "Allocations that returned an error",
+        stats.failed_allocations,
+    );
+    encoder.counter(
+        "blocked_allocations_total",
+        "Allocations that waited for a chunk to be freed",
+        stats.blocked_allocations,
+    );
+    encoder.counter(
+        "allocation_wait_ns_total",
+        "Nanoseconds spent waiting in blocked allocations",
+        stats.total_wait_ns,
+    );
+    encoder.gauge(
+        "fragmentation_ratio",
+        "1 - current/peak allocated bytes",
+        stats.fragmentation_ratio,
+    );
+
+    encode_latency(
+        encoder,
+        "allocation_latency_ns",
+        "Allocation latency in nanoseconds",
+        &stats.latency_stats,
+        stats.total_allocations,
+    );
+    encode_latency(
+        encoder,
+        "deallocation_latency_ns",
+        "Deallocation latency in nanoseconds",
+        &stats.dealloc_latency_stats,
+        stats.total_deallocations,
+    );
+}
+
+// Only recent samples are kept, so `_sum` is estimated as their mean times `count`
+fn encode_latency(
+    encoder: &mut PrometheusEncoder,
+    name: &str,
+    help: &str,
+    latency: &LatencyStats,
+    count: u64,
+) {
+    encoder.summary(
+        name,
+        help,
+        &[
+            (0.5, latency.median_ns),
+            (0.9, latency.p90_ns),
+            (0.95, latency.p95_ns),
+            (0.99, latency.p99_ns),
+            (0.999, latency.p999_ns),
+        ],
+        latency.mean_ns * count as f64,
+        count,
+    );
+}
+
+pub fn encode_size_histogram(encoder: &mut PrometheusEncoder, histogram: &SizeHistogram) {
+    let mut cumulative = 0;
+    let buckets: Vec<(Option<f64>, u64)> = histogram
+        .buckets
+        .iter()
+        .map(|bucket| {
+            cumulative += bucket.count;
+            (bucket.max_size.map(|max| max as f64), cumulative)
+        })
+        .collect();
+    encoder.histogram(
+        "allocation_size_bytes",
+        "Requested allocation sizes in bytes",
+        &buckets,
+        histogram.total_bytes as f64,
+        histogram.total_count,
+    );
+    encoder.gauge(
+        "allocation_size_byte_weighted_mean_bytes",
+        "Allocation size weighted by bytes allocated",
+        histogram.byte_weighted_mean_size(),
+    );
+}
+
+#[cfg(feature = "hft-unsafe")]
+pub fn encode_numa_stats(encoder: &mut PrometheusEncoder, stats: &NumaStatsSnapshot) {
+    encoder.counter(
+        "numa_local_allocations_total",
+        "Allocations served from the caller's NUMA node",
+        stats.local_allocations as u64,
+    );
+    encoder.counter(
+        "numa_cross_node_allocations_total",
+        "Allocations served from a remote NUMA node",
+        stats.cross_node_allocations as u64,
+    );
+    encoder.counter(
+        "numa_allocated_bytes_total",
+        "Bytes allocated across all NUMA nodes",
+        stats.total_bytes_allocated as u64,
+    );
+
+    let per_node = |select: fn(&(usize, usize, usize, usize)) -> usize| -> Vec<(String, f64)> {
+        stats
+            .node_summaries
+            .iter()
+            .map(|summary| (summary.0.to_string(), select(summary) as f64))
+            .collect()
+    };
+    encoder.labelled_gauge(
+        "numa_node_allocated_chunks",
+        "Chunks currently allocated per NUMA node",
+        "node",
+        &per_node(|s| s.1),
+    );
+    encoder.labelled_gauge(
+        "numa_node_free_chunks",
+        "Free chunks per NUMA node",
+        "node",
+        &per_node(|s| s.2),
+    );
+    encoder.labelled_gauge(
+        "numa_node_total_bytes",
+        "Bytes reserved per NUMA node",
+        "node",
+        &per_node(|s| s.3),
+    );
+}
+
+#[cfg(feature = "hft-unsafe")]
+pub fn encode_slab_stats(encoder: &mut PrometheusEncoder, stats: &SlabStats) {
+    encoder.counter(
+        "slab_allocated_objects_total",
+        "Objects handed out by the slab allocator",
+        stats.allocated_objects as u64,
+    );
+    encoder.counter(
+        "slab_freed_objects_total",
+        "Objects returned to the slab allocator",
+        stats.freed_objects as u64,
+    );
+    encoder.gauge(
+        "slab_total_bytes",
+        "Bytes reserved by the slab allocator",
+        stats.total_memory as f64,
+    );
+    encoder.gauge(
+        "slab_size_classes",
+        "Number of slab size classes",
+        stats.size_classes as f64,
+    );
+}
+
+/// Render everything the given backend exposes as a Prometheus scrape body
+pub fn render_memory_backend(backend: &MemoryBackend) -> String {
+    let mut encoder = PrometheusEncoder::new();
+
+    match backend {
+        MemoryBackend::Safe(pool) => {
+            let stats = pool.get_allocation_stats();
+            encode_allocation_stats(&mut encoder, &stats.get_snapshot());
+            encode_size_histogram(&mut encoder, &stats.size_histogram());
+        }
+        #[cfg(feature = "hft-unsafe")]
+        MemoryBackend::LockFree(pool) => {
+            let stats = pool.get_allocation_stats();
+            encode_allocation_stats(&mut encoder, &stats.get_snapshot());
+            encode_size_histogram(&mut encoder, &stats.size_histogram());
+        }
+        #[cfg(feature = "hft-unsafe")]
+        MemoryBackend::Numa(allocator) => {
+            encode_numa_stats(&mut encoder, &allocator.get_stats_snapshot());
+        }
+        #[cfg(feature = "hft-unsafe")]
+        MemoryBackend::Slab(allocator) => {
+            encode_slab_stats(&mut encoder, &allocator.get_stats());
+        }
+    }
+
+    encoder.finish()
+}
+
+/// Serve `GET /metrics` on `addr` until the listener fails
+#[cfg(feature = "metrics-http")]
+pub async fn serve(
+    addr: std::net::SocketAddr,
+    backend: std::sync::Arc<MemoryBackend>,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind(addr).await?;
+
+    loop {
+        let (mut socket, peer) = listener.accept().await?;
+        let backend = std::sync::Arc::clone(&backend);
+
+        tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let read = match socket.read(&mut request).await {
+                Ok(read) => read,
+                Err(e) => {
+                    tracing::debug!(%peer, "Metrics request read failed: {}", e);
+                    return;
+                }
+            };
+
+            let request_line = String::from_utf8_lossy(&request[..read]);
+            let response = if request_line.starts_with("GET /metrics ") {
+                let body = render_memory_backend(&backend);
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+
+            if let Err(e) = socket.write_all(response.as_bytes()).await {
+                tracing::debug!(%peer, "Metrics response write failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::safe_pool::{SafeMemoryPool, SafePoolConfig};
+    use std::collections::HashMap;
+
+    // Families by name with their type, and samples by series in file order
+    fn parse(body: &str) -> (HashMap<String, String>, Vec<(String, f64)>) {
+        let mut types = HashMap::new();
+        let mut samples = Vec::new();
+        for line in body.lines() {
+            if let Some(declaration) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = declaration.split_once(' ').unwrap();
+                types.insert(name.to_string(), kind.to_string());
+            } else if !line.starts_with('#') {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                samples.push((series.to_string(), value.parse().unwrap()));
+            }
+        }
+        (types, samples)
+    }
+
+    fn value(samples: &[(String, f64)], series: &str) -> f64 {
+        samples
+            .iter()
+            .find(|(name, _)| name == series)
+            .unwrap_or_else(|| panic!("{} missing", series))
+            .1
+    }
+
+    #[test]
+    fn rendered_scrape_parses_back() {
+        let pool = SafeMemoryPool::new(SafePoolConfig {
+            chunk_size: 256,
+            initial_chunks: 4,
+            max_chunks: 16,
+            ..SafePoolConfig::default()
+        })
+        .unwrap();
+        let handles: Vec<_> = (0..3).map(|_| pool.allocate_chunk().unwrap()).collect();
+        for handle in handles.into_iter().take(2) {
+            pool.deallocate_chunk(handle);
+        }
+
+        let backend = MemoryBackend::Safe(pool);
+        let (types, samples) = parse(&render_memory_backend(&backend));
+        for (series, _) in &samples {
+            let name = series.split('{').next().unwrap();
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| {
+                    let family = name.strip_suffix(suffix)?;
+                    matches!(
+                        types.get(family).map(String::as_str),
+                        Some("summary" | "histogram")
+                    )
+                    .then_some(family)
+                })
+                .unwrap_or(name);
+            assert!(types.contains_key(family), "{} has no TYPE", series);
+        }
+
+        let latency = "shrivenq_memory_allocation_latency_ns";
+        assert_eq!(types[latency], "summary");
+        assert_eq!(value(&samples, &format!("{}_count", latency)), 3.0);
+        assert!(value(&samples, &format!("{}_sum", latency)) >= 0.0);
+        value(&samples, &format!("{}{{quantile=\"0.99\"}}", latency));
+        let dealloc = "shrivenq_memory_deallocation_latency_ns";
+        assert_eq!(value(&samples, &format!("{}_count", dealloc)), 2.0);
+        assert_eq!(
+            value(&samples, "shrivenq_memory_allocations_total"),
+            value(&samples, &format!("{}_count", latency))
+        );
+
+        let sizes = "shrivenq_memory_allocation_size_bytes";
+        assert_eq!(types[sizes], "histogram");
+        let count = value(&samples, &format!("{}_count", sizes));
+        assert_eq!(
+            value(&samples, &format!("{}_bucket{{le=\"+Inf\"}}", sizes)),
+            count
+        );
+        assert_eq!(value(&samples, &format!("{}_sum", sizes)), count * 256.0);
+    }
+}
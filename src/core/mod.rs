@@ -1,4 +1,22 @@
+pub mod analytics;
+pub mod book;
+pub mod compute;
+pub mod config;
+pub mod cpu;
+pub mod diagnostics;
+pub mod engine;
+pub mod error;
+pub mod events;
 pub mod execution;
+pub mod feeds;
+pub mod journal;
 pub mod memory;
+pub mod metrics;
 pub mod networking;
+pub mod orders;
+pub mod portfolio;
+pub mod replay;
+pub mod risk;
+pub mod strategy;
 pub mod time;
+pub mod types;
@@ -0,0 +1,260 @@
+// Reconnection backoff for ShrivenQ
+// Exponential delays with optional jitter, shared by every network integration
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BackoffError {
+    #[error("Backoff multiplier must be at least 1, got {0}")]
+    InvalidMultiplier(f64),
+    #[error("Backoff base delay {base:?} exceeds the maximum {max:?}")]
+    BaseExceedsMax { base: Duration, max: Duration },
+    #[error("Backoff base delay must be non-zero")]
+    ZeroBase,
+}
+
+/// How randomness is mixed into the exponential delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Jitter {
+    /// Exactly `base * multiplier^attempt`
+    None,
+    /// Uniform in `[0, base * multiplier^attempt]`; spreads out clients that
+    /// all lost the same connection at once
+    #[default]
+    Full,
+    /// Uniform in `[base, previous * multiplier]`, so each delay depends on
+    /// the last rather than on the attempt count
+    Decorrelated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackoffConfig {
+    pub base: Duration,
+    pub multiplier: f64,
+    pub max: Duration,
+    pub jitter: Jitter,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(250),
+            multiplier: 2.0,
+            max: Duration::from_secs(30),
+            jitter: Jitter::Full,
+        }
+    }
+}
+
+impl BackoffConfig {
+    pub fn validate(&self) -> Result<(), BackoffError> {
+        if !(self.multiplier >= 1.0 && self.multiplier.is_finite()) {
+            return Err(BackoffError::InvalidMultiplier(self.multiplier));
+        }
+        if self.base.is_zero() {
+            return Err(BackoffError::ZeroBase);
+        }
+        if self.base > self.max {
+            return Err(BackoffError::BaseExceedsMax {
+                base: self.base,
+                max: self.max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Delay generator for retrying a failed connection.
+///
+/// Call `next_delay` after each failure and `reset` once connected. Delays
+/// never exceed `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    config: BackoffConfig,
+    attempt: u32,
+    previous: Duration,
+    rng: StdRng,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::from_valid(BackoffConfig::default(), StdRng::from_entropy())
+    }
+}
+
+impl Backoff {
+    pub fn new(config: BackoffConfig) -> Result<Self, BackoffError> {
+        config.validate()?;
+        Ok(Self::from_valid(config, StdRng::from_entropy()))
+    }
+
+    /// Reproducible jitter from a fixed seed
+    pub fn with_seed(config: BackoffConfig, seed: u64) -> Result<Self, BackoffError> {
+        config.validate()?;
+        Ok(Self::from_valid(config, StdRng::seed_from_u64(seed)))
+    }
+
+    fn from_valid(config: BackoffConfig, rng: StdRng) -> Self {
+        Self {
+            config,
+            attempt: 0,
+            previous: config.base,
+            rng,
+        }
+    }
+
+    pub fn config(&self) -> &BackoffConfig {
+        &self.config
+    }
+
+    /// Failures since the last reset
+    pub fn attempts(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let BackoffConfig {
+            base,
+            multiplier,
+            max,
+            jitter,
+        } = self.config;
+
+        let delay = match jitter {
+            Jitter::None => self.ceiling(),
+            Jitter::Full => self.ceiling().mul_f64(self.rng.r#gen::<f64>()),
+            Jitter::Decorrelated => {
+                // `mul_f64` would panic once the product overflows a Duration
+                let upper = Duration::try_from_secs_f64(self.previous.as_secs_f64() * multiplier)
+                    .map_or(max, |upper| upper.min(max))
+                    .max(base);
+                base + (upper - base).mul_f64(self.rng.r#gen::<f64>())
+            }
+        };
+
+        self.attempt = self.attempt.saturating_add(1);
+        self.previous = delay;
+        delay
+    }
+
+    /// Start again from the base delay, e.g. after connecting successfully
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+        self.previous = self.config.base;
+    }
+
+    // Un-jittered exponential delay for the current attempt, capped at max
+    fn ceiling(&self) -> Duration {
+        let factor = self
+            .config
+            .multiplier
+            .powi(self.attempt.min(i32::MAX as u32) as i32);
+        let secs = self.config.base.as_secs_f64() * factor;
+        if secs.is_finite() && secs < self.config.max.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.config.max
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoff(jitter: Jitter) -> Backoff {
+        Backoff::with_seed(
+            BackoffConfig {
+                base: Duration::from_millis(100),
+                multiplier: 2.0,
+                max: Duration::from_secs(2),
+                jitter,
+            },
+            7,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn delays_grow_cap_at_max_and_reset() {
+        let mut backoff = backoff(Jitter::None);
+        let delays: Vec<_> = (0..8).map(|_| backoff.next_delay().as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1600, 2000, 2000, 2000]);
+        assert_eq!(backoff.attempts(), 8);
+
+        backoff.reset();
+        assert_eq!(backoff.attempts(), 0);
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let mut full = backoff(Jitter::Full);
+        for attempt in 0..20 {
+            let ceiling =
+                Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(2));
+            assert!(full.next_delay() <= ceiling);
+        }
+
+        let mut decorrelated = backoff(Jitter::Decorrelated);
+        let mut previous = Duration::from_millis(100);
+        for _ in 0..50 {
+            let delay = decorrelated.next_delay();
+            let upper = (previous * 2).min(Duration::from_secs(2));
+            assert!(delay >= Duration::from_millis(100) && delay <= upper);
+            previous = delay;
+        }
+        decorrelated.reset();
+        assert!(decorrelated.next_delay() <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn huge_multipliers_cap_instead_of_overflowing() {
+        for jitter in [Jitter::None, Jitter::Full, Jitter::Decorrelated] {
+            let mut backoff = Backoff::with_seed(
+                BackoffConfig {
+                    base: Duration::from_secs(1),
+                    multiplier: 1e300,
+                    max: Duration::MAX,
+                    jitter,
+                },
+                7,
+            )
+            .unwrap();
+            for _ in 0..5 {
+                backoff.next_delay();
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let config = BackoffConfig::default();
+        assert_eq!(
+            BackoffConfig {
+                multiplier: 0.5,
+                ..config
+            }
+            .validate(),
+            Err(BackoffError::InvalidMultiplier(0.5))
+        );
+        assert_eq!(
+            BackoffConfig {
+                base: Duration::ZERO,
+                ..config
+            }
+            .validate(),
+            Err(BackoffError::ZeroBase)
+        );
+        assert!(matches!(
+            Backoff::new(BackoffConfig {
+                base: Duration::from_secs(60),
+                ..config
+            }),
+            Err(BackoffError::BaseExceedsMax { .. })
+        ));
+    }
+}
@@ -1,4 +1,8 @@
 // Ultra-low latency networking for ShrivenQ
 // io_uring, kernel bypass, zero-copy
 
+pub mod backoff;
+
+pub use backoff::{Backoff, BackoffConfig, BackoffError, Jitter};
+
 // TODO: Implement kernel bypass networking
@@ -0,0 +1,198 @@
+// Instrument trading rules for ShrivenQ
+// Tick size, lot size and minimum notional that every order must respect
+
+use super::{Order, OrderKind, OrderSide};
+use crate::core::types::{Px, Qty};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentReject {
+    #[error("price {price} is not a multiple of tick size {tick}")]
+    OffTick { price: Px, tick: Px },
+    #[error("quantity {qty} is not a multiple of lot size {lot}")]
+    OffLot { qty: Qty, lot: Qty },
+    #[error("quantity must be positive, got {qty}")]
+    NonPositiveQty { qty: Qty },
+    #[error("notional {notional} is below the minimum {min}")]
+    BelowMinNotional { notional: Px, min: Px },
+}
+
+/// Price and size increments an exchange accepts for one instrument.
+/// A zero tick or lot size leaves that dimension unconstrained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InstrumentSpec {
+    pub tick_size: Px,
+    pub lot_size: Qty,
+    /// Smallest accepted price × quantity, in price units
+    pub min_notional: Option<Px>,
+}
+
+impl InstrumentSpec {
+    pub fn new(tick_size: Px, lot_size: Qty) -> Self {
+        Self {
+            tick_size,
+            lot_size,
+            min_notional: None,
+        }
+    }
+
+    pub fn with_min_notional(mut self, min_notional: Px) -> Self {
+        self.min_notional = Some(min_notional);
+        self
+    }
+
+    /// Snap `price` onto the tick grid in the order's favour: buys round down
+    /// so they never pay more than asked, sells round up so they never
+    /// receive less. A sell too close to the top of the range to round up
+    /// rounds down instead.
+    pub fn round_price(&self, price: Px, side: OrderSide) -> Px {
+        let tick = self.tick_size.raw();
+        if tick <= 0 {
+            return price;
+        }
+        let down = price.raw().div_euclid(tick) * tick;
+        let rounded = match side {
+            OrderSide::Buy => down,
+            OrderSide::Sell if down == price.raw() => down,
+            OrderSide::Sell => down.checked_add(tick).unwrap_or(down),
+        };
+        Px::from_raw(rounded)
+    }
+
+    /// Round `qty` toward zero to a whole number of lots, so the order is
+    /// never larger than intended. May return zero.
+    pub fn round_qty(&self, qty: Qty) -> Qty {
+        let lot = self.lot_size.raw();
+        if lot <= 0 {
+            return qty;
+        }
+        Qty::from_raw(qty.raw() / lot * lot)
+    }
+
+    /// Check `order` against the increments and minimum notional. Market
+    /// orders are valued at `reference_price`; without one their notional
+    /// is not checked.
+    pub fn validate_order(
+        &self,
+        order: &Order,
+        reference_price: Option<Px>,
+    ) -> Result<(), InstrumentReject> {
+        if order.qty <= Qty::ZERO {
+            return Err(InstrumentReject::NonPositiveQty { qty: order.qty });
+        }
+        if self.round_qty(order.qty) != order.qty {
+            return Err(InstrumentReject::OffLot {
+                qty: order.qty,
+                lot: self.lot_size,
+            });
+        }
+
+        let price = match order.kind {
+            OrderKind::Limit(price) => {
+                if self.round_price(price, OrderSide::Buy) != price {
+                    return Err(InstrumentReject::OffTick {
+                        price,
+                        tick: self.tick_size,
+                    });
+                }
+                Some(price)
+            }
+            OrderKind::Market => reference_price,
+        };
+
+        if let (Some(min), Some(price)) = (self.min_notional, price) {
+            let notional = price.checked_notional(order.qty).unwrap_or(Px::MAX).abs();
+            if notional < min {
+                return Err(InstrumentReject::BelowMinNotional { notional, min });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::SymbolId;
+
+    fn px(text: &str) -> Px {
+        text.parse().unwrap()
+    }
+
+    fn qty(text: &str) -> Qty {
+        text.parse().unwrap()
+    }
+
+    fn spec() -> InstrumentSpec {
+        InstrumentSpec::new(px("0.05"), qty("10")).with_min_notional(px("1000"))
+    }
+
+    fn limit(side: OrderSide, price: &str, lots: &str) -> Order {
+        Order {
+            id: 1,
+            symbol: SymbolId::intern("TICKTEST").unwrap(),
+            side,
+            kind: OrderKind::Limit(px(price)),
+            qty: qty(lots),
+            timestamp_ns: 0,
+        }
+    }
+
+    #[test]
+    fn prices_round_in_the_orders_favour() {
+        let spec = spec();
+        assert_eq!(spec.round_price(px("100.07"), OrderSide::Buy), px("100.05"));
+        assert_eq!(
+            spec.round_price(px("100.07"), OrderSide::Sell),
+            px("100.10")
+        );
+        assert_eq!(
+            spec.round_price(px("100.05"), OrderSide::Sell),
+            px("100.05")
+        );
+        assert_eq!(spec.round_price(px("-0.02"), OrderSide::Buy), px("-0.05"));
+
+        assert_eq!(spec.round_qty(qty("37")), qty("30"));
+        assert_eq!(spec.round_qty(qty("9")), Qty::ZERO);
+        assert_eq!(InstrumentSpec::default().round_qty(qty("3.7")), qty("3.7"));
+    }
+
+    #[test]
+    fn orders_off_the_grid_or_below_min_notional_are_rejected() {
+        let spec = spec();
+        assert_eq!(
+            spec.validate_order(&limit(OrderSide::Buy, "100.05", "10"), None),
+            Ok(())
+        );
+        assert_eq!(
+            spec.validate_order(&limit(OrderSide::Buy, "100.07", "10"), None),
+            Err(InstrumentReject::OffTick {
+                price: px("100.07"),
+                tick: px("0.05"),
+            })
+        );
+        assert_eq!(
+            spec.validate_order(&limit(OrderSide::Sell, "100", "15"), None),
+            Err(InstrumentReject::OffLot {
+                qty: qty("15"),
+                lot: qty("10"),
+            })
+        );
+        assert_eq!(
+            spec.validate_order(&limit(OrderSide::Sell, "99.95", "10"), None),
+            Err(InstrumentReject::BelowMinNotional {
+                notional: px("999.5"),
+                min: px("1000"),
+            })
+        );
+
+        // Market orders are valued at the reference price when there is one
+        let mut market = limit(OrderSide::Buy, "0", "10");
+        market.kind = OrderKind::Market;
+        assert_eq!(spec.validate_order(&market, None), Ok(()));
+        assert!(matches!(
+            spec.validate_order(&market, Some(px("50"))),
+            Err(InstrumentReject::BelowMinNotional { .. })
+        ));
+    }
+}
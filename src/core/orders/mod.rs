@@ -0,0 +1,80 @@
+// Orders and fills for ShrivenQ
+// Mode-independent order representation shared by simulation, paper and live
+
+pub mod instrument;
+
+pub use instrument::{InstrumentReject, InstrumentSpec};
+
+use crate::core::events::Side;
+use crate::core::types::{Px, Qty, SymbolId};
+
+pub type OrderId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    /// Book side this order trades against
+    pub fn opposite_book_side(self) -> Side {
+        match self {
+            OrderSide::Buy => Side::Ask,
+            OrderSide::Sell => Side::Bid,
+        }
+    }
+
+    /// Book side this order rests on
+    pub fn book_side(self) -> Side {
+        match self {
+            OrderSide::Buy => Side::Bid,
+            OrderSide::Sell => Side::Ask,
+        }
+    }
+
+    /// Whether `price` is at least as good as `limit` for this side
+    pub fn crosses(self, price: Px, limit: Px) -> bool {
+        match self {
+            OrderSide::Buy => price <= limit,
+            OrderSide::Sell => price >= limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderKind {
+    Market,
+    Limit(Px),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    /// Client order id, unique per session
+    pub id: OrderId,
+    pub symbol: SymbolId,
+    pub side: OrderSide,
+    pub kind: OrderKind,
+    pub qty: Qty,
+    pub timestamp_ns: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub side: OrderSide,
+    pub price: Px,
+    pub qty: Qty,
+    pub timestamp_ns: u64,
+}
+
+/// One of our orders refused after it was sent, e.g. a market order that
+/// found no liquidity once it reached the book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderReject {
+    pub order_id: OrderId,
+    pub symbol: SymbolId,
+    pub reason: String,
+    pub timestamp_ns: u64,
+}
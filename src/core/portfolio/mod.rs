@@ -0,0 +1,316 @@
+// Position and PnL tracking for ShrivenQ
+// Shared by backtest, simulation and paper trading; integer math throughout
+
+use crate::core::events::MarketEvent;
+use crate::core::orders::{Fill, OrderSide};
+use crate::core::types::{Px, Qty, SymbolId};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Net position in one symbol. PnL figures are money in price units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    /// Signed net quantity: positive long, negative short
+    pub qty: Qty,
+    /// Average entry price of the open quantity; zero when flat
+    pub avg_price: Px,
+    pub realized_pnl: Px,
+    /// Latest mark price, if any market data has been seen
+    pub mark: Option<Px>,
+}
+
+impl Position {
+    /// PnL of the open quantity against the latest mark
+    pub fn unrealized_pnl(&self) -> Px {
+        match self.mark {
+            Some(mark) if !self.qty.is_zero() => mark
+                .checked_sub(self.avg_price)
+                .and_then(|diff| pnl(diff.raw(), self.qty))
+                .unwrap_or(Px::ZERO),
+            _ => Px::ZERO,
+        }
+    }
+
+    fn apply(&mut self, side: OrderSide, price: Px, qty: Qty) -> Option<()> {
+        let signed = match side {
+            OrderSide::Buy => qty,
+            OrderSide::Sell => qty.checked_neg()?,
+        };
+        let old = self.qty.raw();
+        let new = old.checked_add(signed.raw())?;
+
+        if old == 0 || old.signum() == signed.raw().signum() {
+            // Opening or adding: volume-weighted average entry
+            let cost = i128::from(self.avg_price.raw()) * i128::from(old.checked_abs()?)
+                + i128::from(price.raw()) * i128::from(qty.raw());
+            let avg = cost / i128::from(new.checked_abs()?);
+            self.avg_price = Px::from_raw(i64::try_from(avg).ok()?);
+        } else {
+            // Reducing, closing or flipping: realize PnL on the closed part
+            let closed = old.checked_abs()?.min(qty.raw());
+            let direction = old.signum();
+            let diff = price
+                .checked_sub(self.avg_price)?
+                .checked_mul_int(direction)?;
+            let realized = pnl(diff.raw(), Qty::from_raw(closed))?;
+            self.realized_pnl = self.realized_pnl.checked_add(realized)?;
+
+            if new == 0 {
+                self.avg_price = Px::ZERO;
+            } else if new.signum() != direction {
+                // Flipped through flat: the remainder opens at the fill price
+                self.avg_price = price;
+            }
+        }
+
+        self.qty = Qty::from_raw(new);
+        Some(())
+    }
+}
+
+// Money value of a per-unit price difference over `qty`
+fn pnl(price_diff_raw: i64, qty: Qty) -> Option<Px> {
+    let value = i128::from(price_diff_raw) * i128::from(qty.raw()) / i128::from(Qty::<8>::SCALE);
+    i64::try_from(value).ok().map(Px::from_raw)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionSnapshot {
+    pub symbol: SymbolId,
+    pub qty: Qty,
+    pub avg_price: Px,
+    pub mark: Option<Px>,
+    pub realized_pnl: Px,
+    pub unrealized_pnl: Px,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortfolioSnapshot {
+    /// Sorted by symbol name
+    pub positions: Vec<PositionSnapshot>,
+    pub realized_pnl: Px,
+    pub unrealized_pnl: Px,
+}
+
+impl PortfolioSnapshot {
+    pub fn total_pnl(&self) -> Px {
+        self.realized_pnl
+            .checked_add(self.unrealized_pnl)
+            .unwrap_or(Px::ZERO)
+    }
+}
+
+/// Per-symbol positions updated from fills and marked to market data
+#[derive(Debug, Clone, Default)]
+pub struct Portfolio {
+    positions: HashMap<SymbolId, Position>,
+}
+
+impl Portfolio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply_fill(&mut self, fill: &Fill) {
+        let position = self.positions.entry(fill.symbol).or_default();
+        if position.apply(fill.side, fill.price, fill.qty).is_none() {
+            warn!(
+                "Portfolio overflow applying fill for order {} in {}",
+                fill.order_id, fill.symbol
+            );
+        }
+    }
+
+    /// Apply fills and re-mark positions from trades and quote midpoints
+    pub fn on_market_event(&mut self, event: &MarketEvent) {
+        match event {
+            MarketEvent::Fill(fill) => self.apply_fill(fill),
+            MarketEvent::Trade { symbol, price, .. } => self.mark(*symbol, *price),
+            MarketEvent::Quote {
+                symbol,
+                bid_price,
+                ask_price,
+                ..
+            } => {
+                let mid = (i128::from(bid_price.raw()) + i128::from(ask_price.raw())) / 2;
+                if let Ok(mid) = i64::try_from(mid) {
+                    self.mark(*symbol, Px::from_raw(mid));
+                }
+            }
+            MarketEvent::BookUpdate { .. }
+            | MarketEvent::Heartbeat { .. }
+            | MarketEvent::Reject(_) => {}
+        }
+    }
+
+    /// Mark an existing position; symbols we hold nothing in are ignored
+    pub fn mark(&mut self, symbol: SymbolId, price: Px) {
+        if let Some(position) = self.positions.get_mut(&symbol) {
+            position.mark = Some(price);
+        }
+    }
+
+    pub fn position(&self, symbol: SymbolId) -> Option<&Position> {
+        self.positions.get(&symbol)
+    }
+
+    /// Every symbol ever traded, in no particular order
+    pub fn positions(&self) -> impl Iterator<Item = (SymbolId, &Position)> + '_ {
+        self.positions
+            .iter()
+            .map(|(&symbol, position)| (symbol, position))
+    }
+
+    // Snapshot restore puts positions back exactly as they were saved
+    pub(crate) fn restore_position(&mut self, symbol: SymbolId, position: Position) {
+        self.positions.insert(symbol, position);
+    }
+
+    /// Net quantity in `symbol`, zero if never traded
+    pub fn net_qty(&self, symbol: SymbolId) -> Qty {
+        self.positions
+            .get(&symbol)
+            .map(|p| p.qty)
+            .unwrap_or(Qty::ZERO)
+    }
+
+    pub fn realized_pnl(&self) -> Px {
+        self.sum(|p| p.realized_pnl)
+    }
+
+    pub fn unrealized_pnl(&self) -> Px {
+        self.sum(Position::unrealized_pnl)
+    }
+
+    pub fn total_pnl(&self) -> Px {
+        self.realized_pnl()
+            .checked_add(self.unrealized_pnl())
+            .unwrap_or(Px::ZERO)
+    }
+
+    pub fn snapshot(&self) -> PortfolioSnapshot {
+        let mut positions: Vec<PositionSnapshot> = self
+            .positions
+            .iter()
+            .map(|(&symbol, p)| PositionSnapshot {
+                symbol,
+                qty: p.qty,
+                avg_price: p.avg_price,
+                mark: p.mark,
+                realized_pnl: p.realized_pnl,
+                unrealized_pnl: p.unrealized_pnl(),
+            })
+            .collect();
+        positions.sort_by_key(|p| p.symbol.as_str());
+
+        PortfolioSnapshot {
+            positions,
+            realized_pnl: self.realized_pnl(),
+            unrealized_pnl: self.unrealized_pnl(),
+        }
+    }
+
+    fn sum(&self, value: impl Fn(&Position) -> Px) -> Px {
+        self.positions
+            .values()
+            .map(value)
+            .fold(Px::ZERO, |acc, v| acc.checked_add(v).unwrap_or(acc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol() -> SymbolId {
+        SymbolId::intern("PNLTEST").unwrap()
+    }
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    fn fill(side: OrderSide, price: i64, lots: i64) -> Fill {
+        Fill {
+            order_id: 1,
+            symbol: symbol(),
+            side,
+            price: px(price),
+            qty: qty(lots),
+            timestamp_ns: 0,
+        }
+    }
+
+    #[test]
+    fn round_trip_through_open_add_partial_close_and_flip() {
+        let mut portfolio = Portfolio::new();
+
+        portfolio.apply_fill(&fill(OrderSide::Buy, 100, 2));
+        portfolio.apply_fill(&fill(OrderSide::Buy, 106, 1));
+        let position = *portfolio.position(symbol()).unwrap();
+        assert_eq!((position.qty, position.avg_price), (qty(3), px(102)));
+        assert_eq!(position.realized_pnl, Px::ZERO);
+
+        // Sell 1 of 3 at 110: +8 realized, the rest keeps its entry
+        portfolio.apply_fill(&fill(OrderSide::Sell, 110, 1));
+        let position = *portfolio.position(symbol()).unwrap();
+        assert_eq!((position.qty, position.avg_price), (qty(2), px(102)));
+        assert_eq!(position.realized_pnl, px(8));
+
+        // Sell 5 at 99: closes 2 for -6, opens 3 short at 99
+        portfolio.apply_fill(&fill(OrderSide::Sell, 99, 5));
+        let position = *portfolio.position(symbol()).unwrap();
+        assert_eq!((position.qty, position.avg_price), (qty(-3), px(99)));
+        assert_eq!(position.realized_pnl, px(2));
+
+        // Buy back at 97: +6 on the short
+        portfolio.apply_fill(&fill(OrderSide::Buy, 97, 3));
+        let position = *portfolio.position(symbol()).unwrap();
+        assert_eq!((position.qty, position.avg_price), (Qty::ZERO, Px::ZERO));
+        assert_eq!(portfolio.realized_pnl(), px(8));
+        assert_eq!(portfolio.unrealized_pnl(), Px::ZERO);
+    }
+
+    #[test]
+    fn unrealized_pnl_follows_the_mark() {
+        let mut portfolio = Portfolio::new();
+        portfolio.apply_fill(&fill(OrderSide::Buy, 100, 4));
+        assert_eq!(portfolio.unrealized_pnl(), Px::ZERO);
+
+        portfolio.mark(symbol(), px(103));
+        assert_eq!(portfolio.unrealized_pnl(), px(12));
+        portfolio.on_market_event(&MarketEvent::Quote {
+            symbol: symbol(),
+            timestamp_ns: 1,
+            normalized_ns: 1,
+            bid_price: px(97),
+            bid_size: qty(1),
+            ask_price: px(99),
+            ask_size: qty(1),
+        });
+        assert_eq!(portfolio.unrealized_pnl(), px(-8));
+
+        let snapshot = portfolio.snapshot();
+        assert_eq!(snapshot.positions[0].mark, Some(px(98)));
+        assert_eq!(snapshot.total_pnl(), px(-8));
+    }
+
+    #[test]
+    fn overflow_leaves_the_position_unchanged() {
+        let mut position = Position {
+            qty: qty(1),
+            avg_price: Px::from_raw(i64::MIN + 1),
+            ..Position::default()
+        };
+        let before = position;
+        assert_eq!(position.apply(OrderSide::Sell, Px::MAX, qty(1)), None);
+        assert_eq!(position, before);
+
+        position.mark = Some(Px::MAX);
+        assert_eq!(position.unrealized_pnl(), Px::ZERO);
+    }
+}
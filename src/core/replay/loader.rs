@@ -0,0 +1,460 @@
+// Historical data loaders for ShrivenQ
+// File formats behind one trait; gzip-compressed inputs are unpacked transparently
+
+use crate::core::events::MarketEvent;
+use crate::core::replay::DateRange;
+use crate::core::types::{Px, Qty, SymbolId};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDateTime};
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+// First two bytes of every gzip member
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Reads one historical data file into market events
+pub trait HistoricalLoader: Send + Sync {
+    /// Format name for logs
+    fn format(&self) -> &'static str;
+
+    /// Events inside `range`, in timestamp order. Fails if the file's own
+    /// timestamps ever go backwards.
+    fn load(&self, path: &Path, range: DateRange) -> Result<Vec<MarketEvent>>;
+}
+
+/// Loader for `path` chosen from its extension, looking through a `.gz`
+/// suffix: `.csv` and `.csv.gz` load as CSV, `.parquet` as Parquet when
+/// built with the `parquet` feature
+pub fn loader_for(path: &Path) -> Result<Box<dyn HistoricalLoader>> {
+    match data_extension(path).as_deref() {
+        Some("csv") => Ok(Box::new(CsvLoader)),
+        #[cfg(feature = "parquet")]
+        Some("parquet") => Ok(Box::new(ParquetLoader)),
+        #[cfg(not(feature = "parquet"))]
+        Some("parquet") => bail!(
+            "{}: Parquet input needs a build with the `parquet` feature",
+            path.display()
+        ),
+        _ => bail!("{}: unrecognised historical data format", path.display()),
+    }
+}
+
+/// Whether `path` names a file some loader claims, by extension
+pub fn is_historical_data(path: &Path) -> bool {
+    matches!(data_extension(path).as_deref(), Some("csv" | "parquet"))
+}
+
+/// File contents, gunzipped when they start with the gzip magic bytes
+pub fn read_input(path: &Path) -> Result<Vec<u8>> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+    let mut unpacked = Vec::new();
+    MultiGzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut unpacked)
+        .with_context(|| format!("decompressing {}", path.display()))?;
+    Ok(unpacked)
+}
+// File name without a trailing `.gz`
+fn data_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    Some(name.strip_suffix(".gz").map(str::to_owned).unwrap_or(name))
+}
+
+fn data_extension(path: &Path) -> Option<String> {
+    let name = data_name(path)?;
+    name.rsplit_once('.').map(|(_, ext)| ext.to_owned())
+}
+
+// File stem ignoring a `.gz` suffix, in its original case
+fn data_stem(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    let name = match name.len().checked_sub(3) {
+        Some(cut) if name.is_char_boundary(cut) && name[cut..].eq_ignore_ascii_case(".gz") => {
+            name[..cut].to_owned()
+        }
+        _ => name,
+    };
+    Some(match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem.to_owned(),
+        _ => name,
+    })
+}
+
+/// Tick or OHLCV CSV.
+///
+/// The header selects the format: `timestamp,symbol,price,size` for ticks or
+/// `timestamp,symbol,open,high,low,close,volume` for bars (column order is
+/// free, the `symbol` column is optional and defaults to the file stem). Bars
+/// are replayed as a trade at the close price for the bar's volume.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvLoader;
+
+impl HistoricalLoader for CsvLoader {
+    fn format(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn load(&self, path: &Path, range: DateRange) -> Result<Vec<MarketEvent>> {
+        let content = String::from_utf8(read_input(path)?)
+            .with_context(|| format!("{}: not UTF-8 text", path.display()))?;
+        let mut lines = content.lines().enumerate();
+        let Some((_, header)) = lines.next() else {
+            return Ok(Vec::new());
+        };
+        let columns: Vec<&str> = header.split(',').collect();
+        let rows = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| Ok((index + 1, line.split(',').collect::<Vec<_>>())));
+        parse_rows(path, &columns, rows, range)
+    }
+}
+
+/// Tick or OHLCV Parquet, with the same columns as [`CsvLoader`] reads.
+///
+/// Columns may be strings or numbers; numbers are read as their decimal text,
+/// so a `DOUBLE` price of `101.25` loads exactly as the CSV text `101.25`.
+/// Parquet timestamp columns are taken in their own unit.
+#[cfg(feature = "parquet")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParquetLoader;
+
+#[cfg(feature = "parquet")]
+impl HistoricalLoader for ParquetLoader {
+    fn format(&self) -> &'static str {
+        "Parquet"
+    }
+
+    fn load(&self, path: &Path, range: DateRange) -> Result<Vec<MarketEvent>> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(read_input(path)?))
+            .with_context(|| format!("{}: not a Parquet file", path.display()))?;
+        let columns: Vec<String> = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .root_schema()
+            .get_fields()
+            .iter()
+            .map(|field| field.name().to_owned())
+            .collect();
+        let rows = reader
+            .get_row_iter(None)
+            .with_context(|| format!("{}: reading rows", path.display()))?
+            .enumerate()
+            .map(|(index, row)| {
+                let row = row.with_context(|| format!("{}: row {}", path.display(), index + 1))?;
+                let fields = row
+                    .get_column_iter()
+                    .map(|(_, field)| parquet_text(field))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "{}: row {}: unsupported column type",
+                            path.display(),
+                            index + 1
+                        )
+                    })?;
+                Ok((index + 1, fields))
+            });
+        parse_rows(path, &columns, rows, range)
+    }
+}
+
+// A Parquet value as the text a CSV cell would hold; timestamps become epoch
+// nanoseconds so their unit is not guessed from the digit count
+#[cfg(feature = "parquet")]
+fn parquet_text(field: &parquet::record::Field) -> Option<String> {
+    use parquet::record::Field;
+
+    Some(match field {
+        Field::Str(text) => text.clone(),
+        Field::Int(value) => value.to_string(),
+        Field::Long(value) => value.to_string(),
+        Field::UInt(value) => value.to_string(),
+        Field::ULong(value) => value.to_string(),
+        Field::Float(value) => value.to_string(),
+        Field::Double(value) => value.to_string(),
+        Field::TimestampMillis(ms) => format!("{ms}000000"),
+        Field::TimestampMicros(us) => format!("{us}000"),
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RowFormat {
+    Tick { price: usize, size: usize },
+    Ohlcv { close: usize, volume: usize },
+}
+
+// Turn rows under `columns` into events inside `range`, failing if
+// timestamps go backwards. Each row comes with its line or row number.
+fn parse_rows<S: AsRef<str>>(
+    path: &Path,
+    columns: &[impl AsRef<str>],
+    rows: impl Iterator<Item = Result<(usize, Vec<S>)>>,
+    range: DateRange,
+) -> Result<Vec<MarketEvent>> {
+    let mut events = Vec::new();
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|c| c.as_ref().trim().to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+
+    let timestamp_col = column(&["timestamp", "time", "ts"])
+        .ok_or_else(|| anyhow!("{}: missing timestamp column", path.display()))?;
+    let symbol_col = column(&["symbol"]);
+    let format = match (
+        column(&["price"]),
+        column(&["size", "qty", "quantity"]),
+        column(&["close"]),
+        column(&["volume"]),
+    ) {
+        (Some(price), Some(size), _, _) => RowFormat::Tick { price, size },
+        (_, _, Some(close), Some(volume)) => RowFormat::Ohlcv { close, volume },
+        _ => bail!(
+            "{}: header must contain price,size or close,volume columns",
+            path.display()
+        ),
+    };
+
+    let default_symbol = SymbolId::intern(data_stem(path).as_deref().unwrap_or("UNKNOWN"))?;
+    let mut last_timestamp_ns = 0;
+
+    for row in rows {
+        let (line_no, fields) = row?;
+        let field = |col: usize| {
+            fields
+                .get(col)
+                .map(|f| f.as_ref().trim())
+                .ok_or_else(|| anyhow!("{}:{}: missing column {}", path.display(), line_no, col))
+        };
+
+        let timestamp_ns = parse_timestamp_ns(field(timestamp_col)?)
+            .with_context(|| format!("{}:{}: bad timestamp", path.display(), line_no))?;
+        if timestamp_ns < last_timestamp_ns {
+            bail!(
+                "{}:{}: timestamp {} is earlier than the row before it ({})",
+                path.display(),
+                line_no,
+                timestamp_ns,
+                last_timestamp_ns
+            );
+        }
+        last_timestamp_ns = timestamp_ns;
+        if !range.contains(timestamp_ns) {
+            continue;
+        }
+
+        let symbol = match symbol_col {
+            Some(col) => SymbolId::intern(field(col)?)
+                .with_context(|| format!("{}:{}: bad symbol", path.display(), line_no))?,
+            None => default_symbol,
+        };
+        let (price_col, size_col) = match format {
+            RowFormat::Tick { price, size } => (price, size),
+            RowFormat::Ohlcv { close, volume } => (close, volume),
+        };
+        let price_raw = field(price_col)?;
+        let size_raw = field(size_col)?;
+
+        events.push(MarketEvent::Trade {
+            symbol,
+            timestamp_ns,
+            normalized_ns: timestamp_ns,
+            price: price_raw.parse::<Px>().with_context(|| {
+                format!(
+                    "{}:{}: invalid price '{}'",
+                    path.display(),
+                    line_no,
+                    price_raw
+                )
+            })?,
+            size: size_raw.parse::<Qty>().with_context(|| {
+                format!(
+                    "{}:{}: invalid size '{}'",
+                    path.display(),
+                    line_no,
+                    size_raw
+                )
+            })?,
+        });
+    }
+
+    Ok(events)
+}
+
+/// Accepts RFC 3339, `YYYY-MM-DD HH:MM:SS[.fff]` (UTC), or an integer epoch
+/// timestamp whose unit is inferred from its digit count (s, ms, us or ns)
+fn parse_timestamp_ns(raw: &str) -> Result<u64> {
+    if let Ok(value) = raw.parse::<u64>() {
+        let scale = match raw.len() {
+            0..=10 => 1_000_000_000,
+            11..=13 => 1_000_000,
+            14..=16 => 1_000,
+            _ => 1,
+        };
+        return value
+            .checked_mul(scale)
+            .ok_or_else(|| anyhow!("timestamp {} overflows", raw));
+    }
+
+    let datetime = DateTime::parse_from_rfc3339(raw)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f"))
+        .with_context(|| format!("unrecognised timestamp '{}'", raw))?;
+    datetime
+        .and_utc()
+        .timestamp_nanos_opt()
+        .and_then(|ns| u64::try_from(ns).ok())
+        .ok_or_else(|| anyhow!("timestamp '{}' out of range", raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    const TICKS: &str = "timestamp,symbol,price,size\n\
+        1700000000000000000,BTCUSDT,101.25,3\n\
+        1700000000500000000,ETHUSDT,2000.5,10\n\
+        1700000001000000000,BTCUSDT,101.5,1\n";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("shriven-loader-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn everything() -> DateRange {
+        DateRange {
+            start_ns: 0,
+            end_ns: u64::MAX,
+        }
+    }
+
+    fn load(path: &Path) -> Vec<MarketEvent> {
+        loader_for(path).unwrap().load(path, everything()).unwrap()
+    }
+
+    #[test]
+    fn gzipped_csv_loads_like_plain_csv() {
+        let dir = temp_dir("gzip");
+        let plain = dir.join("ticks.csv");
+        std::fs::write(&plain, TICKS).unwrap();
+        let packed = dir.join("ticks.csv.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(TICKS.as_bytes()).unwrap();
+        std::fs::write(&packed, encoder.finish().unwrap()).unwrap();
+
+        let events = load(&plain);
+        assert_eq!(events.len(), 3);
+        assert_eq!(load(&packed), events);
+        let MarketEvent::Trade { price, size, .. } = events[0] else {
+            panic!("expected a trade, got {:?}", events[0]);
+        };
+        assert_eq!(price, "101.25".parse::<Px>().unwrap());
+        assert_eq!(size, "3".parse::<Qty>().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_gzip_is_an_error() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("ticks.csv.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(TICKS.as_bytes()).unwrap();
+        let mut bytes = encoder.finish().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(CsvLoader.load(&path, everything()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn parquet_needs_the_feature() {
+        let error = loader_for(Path::new("ticks.parquet")).err().unwrap();
+        assert!(error.to_string().contains("`parquet` feature"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_loads_like_csv() {
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let dir = temp_dir("parquet");
+        let csv = dir.join("ticks.csv");
+        std::fs::write(&csv, TICKS).unwrap();
+
+        let schema = parse_message_type(
+            "message ticks {
+                REQUIRED INT64 timestamp;
+                REQUIRED BYTE_ARRAY symbol (UTF8);
+                REQUIRED DOUBLE price;
+                REQUIRED INT64 size;
+            }",
+        )
+        .unwrap();
+        let parquet = dir.join("ticks.parquet");
+        let file = std::fs::File::create(&parquet).unwrap();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), properties).unwrap();
+        let mut group = writer.next_row_group().unwrap();
+        let timestamps = [
+            1700000000000000000,
+            1700000000500000000,
+            1700000001000000000,
+        ];
+        let symbols: Vec<ByteArray> = ["BTCUSDT", "ETHUSDT", "BTCUSDT"]
+            .into_iter()
+            .map(ByteArray::from)
+            .collect();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&timestamps, None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&symbols, None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[101.25, 2000.5, 101.5], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[3, 10, 1], None, None)
+            .unwrap();
+        column.close().unwrap();
+        group.close().unwrap();
+        writer.close().unwrap();
+
+        let events = load(&csv);
+        assert_eq!(events.len(), 3);
+        assert_eq!(load(&parquet), events);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+// Historical data replay for ShrivenQ
+// Feeds recorded market data through the same event types as live trading
+
+pub mod loader;
+pub mod parallel;
+pub mod report;
+
+#[cfg(feature = "parquet")]
+pub use loader::ParquetLoader;
+pub use loader::{CsvLoader, HistoricalLoader, loader_for};
+pub use parallel::{BacktestShard, ParallelBacktest};
+pub use report::{BacktestRecorder, BacktestReport, SymbolReport};
+
+use crate::core::events::MarketEvent;
+use crate::core::time::PrecisionTimer;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::NaiveDate;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+/// Source of market events in timestamp order
+pub trait DataSource {
+    fn next_event(&mut self) -> Option<MarketEvent>;
+}
+
+/// Half-open `[start, end)` window in epoch nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub start_ns: u64,
+    pub end_ns: u64,
+}
+
+impl DateRange {
+    /// Parse inclusive `YYYY-MM-DD` bounds; the end date covers its whole day
+    pub fn parse(start_date: &str, end_date: &str) -> Result<Self> {
+        let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+            .with_context(|| format!("invalid start date '{}'", start_date))?;
+        let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+            .with_context(|| format!("invalid end date '{}'", end_date))?;
+        if end < start {
+            bail!("end date {} is before start date {}", end_date, start_date);
+        }
+
+        let end = end
+            .succ_opt()
+            .ok_or_else(|| anyhow!("end date {} out of range", end_date))?;
+        Ok(Self {
+            start_ns: day_start_ns(start)?,
+            end_ns: day_start_ns(end)?,
+        })
+    }
+
+    pub fn contains(&self, timestamp_ns: u64) -> bool {
+        timestamp_ns >= self.start_ns && timestamp_ns < self.end_ns
+    }
+}
+
+fn day_start_ns(date: NaiveDate) -> Result<u64> {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_utc().timestamp_nanos_opt())
+        .and_then(|ns| u64::try_from(ns).ok())
+        .ok_or_else(|| anyhow!("date {} not representable in epoch nanoseconds", date))
+}
+
+/// Replays historical data files of any format [`loader_for`] recognises.
+///
+/// All matching files are loaded up front and merged by timestamp; rows with
+/// equal timestamps keep their file order.
+#[derive(Debug)]
+pub struct HistoricalDataSource {
+    events: std::vec::IntoIter<MarketEvent>,
+}
+
+impl HistoricalDataSource {
+    /// Load a single file, or every recognised file in a directory
+    pub fn open(path: impl AsRef<Path>, range: DateRange) -> Result<Self> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .with_context(|| format!("reading data directory {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| loader::is_historical_data(p))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        let mut events = Vec::new();
+        for file in &files {
+            let loader = loader_for(file)?;
+            let loaded = loader.load(file, range)?;
+            debug!(
+                "Loaded {} {} events from {}",
+                loaded.len(),
+                loader.format(),
+                file.display()
+            );
+            events.extend(loaded);
+        }
+        events.sort_by_key(MarketEvent::timestamp_ns);
+
+        Ok(Self {
+            events: events.into_iter(),
+        })
+    }
+
+    /// Events left to replay
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Take the events left to replay, in timestamp order
+    pub fn into_events(self) -> Vec<MarketEvent> {
+        self.events.collect()
+    }
+}
+
+impl DataSource for HistoricalDataSource {
+    fn next_event(&mut self) -> Option<MarketEvent> {
+        self.events.next()
+    }
+}
+
+/// Paces replay so event timestamps map onto wall-clock time at `speed`x
+#[derive(Debug)]
+pub struct ReplayPacer {
+    speed: f64,
+    timer: PrecisionTimer,
+    first_timestamp_ns: Option<u64>,
+}
+
+impl ReplayPacer {
+    pub fn new(speed: f64) -> Result<Self> {
+        if !(speed.is_finite() && speed > 0.0) {
+            bail!("replay speed must be a positive number, got {}", speed);
+        }
+        Ok(Self {
+            speed,
+            timer: PrecisionTimer::start(),
+            first_timestamp_ns: None,
+        })
+    }
+
+    /// Block until the event at `timestamp_ns` is due
+    pub fn pace(&mut self, timestamp_ns: u64) {
+        let due_ns = self.due_nanos(timestamp_ns);
+        self.timer.wait_until_nanos(due_ns);
+    }
+
+    /// Time left until the event at `timestamp_ns` is due, for callers that
+    /// must not block (e.g. async tasks sleeping on the runtime timer)
+    pub fn delay_until_due(&mut self, timestamp_ns: u64) -> Duration {
+        let due_ns = self.due_nanos(timestamp_ns);
+        Duration::from_nanos(due_ns.saturating_sub(self.timer.elapsed_nanos()))
+    }
+
+    /// Wall-clock offset from the first paced event at which this one is due
+    fn due_nanos(&mut self, timestamp_ns: u64) -> u64 {
+        let first = *self.first_timestamp_ns.get_or_insert(timestamp_ns);
+        let offset_ns = timestamp_ns.saturating_sub(first);
+        (offset_ns as f64 / self.speed) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directory_replays_in_the_date_range_in_timestamp_order() {
+        let dir = std::env::temp_dir().join(format!("shriven-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // 2023-11-14 spans 1699920000 to 1700006400 seconds
+        std::fs::write(
+            dir.join("btc.csv"),
+            "timestamp,symbol,price,size\n\
+             1699919999000000000,BTCUSDT,99.0,1\n\
+             1700000000000000000,BTCUSDT,101.25,3\n\
+             1700000002000000000,BTCUSDT,101.5,1\n\
+             1700006400000000000,BTCUSDT,102.0,2\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("eth.csv"),
+            "timestamp,symbol,price,size\n\
+             1699920000000000000,ETHUSDT,1990.0,4\n\
+             1700000001000000000,ETHUSDT,2000.5,10\n",
+        )
+        .unwrap();
+
+        let range = DateRange::parse("2023-11-14", "2023-11-14").unwrap();
+        let mut source = HistoricalDataSource::open(&dir, range).unwrap();
+        assert_eq!(source.remaining(), 4);
+
+        let mut replayed = Vec::new();
+        while let Some(event) = source.next_event() {
+            let symbol = event.symbol().unwrap().as_str();
+            replayed.push((event.timestamp_ns() / 1_000_000_000, symbol));
+        }
+        assert_eq!(
+            replayed,
+            [
+                (1_699_920_000, "ETHUSDT"),
+                (1_700_000_000, "BTCUSDT"),
+                (1_700_000_001, "ETHUSDT"),
+                (1_700_000_002, "BTCUSDT"),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,296 @@
+// Parallel backtesting for ShrivenQ
+// Shards independent symbols across worker threads over one shared event slice
+
+use crate::core::book::OrderBook;
+use crate::core::events::MarketEvent;
+use crate::core::replay::report::{BacktestRecorder, BacktestReport};
+use crate::core::types::SymbolId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{debug, warn};
+
+/// State private to one shard: its books and its portfolio
+#[derive(Debug, Default)]
+pub struct BacktestShard {
+    books: HashMap<SymbolId, OrderBook>,
+    recorder: BacktestRecorder,
+}
+
+impl BacktestShard {
+    fn on_event(&mut self, seq: u64, event: &MarketEvent) {
+        if let MarketEvent::BookUpdate { symbol, .. } = event {
+            let book = self
+                .books
+                .entry(*symbol)
+                .or_insert_with(|| OrderBook::new(*symbol));
+            if let Err(e) = book.apply(event) {
+                warn!("Skipping book update for {}: {}", symbol, e);
+            }
+        }
+        self.recorder.on_event_at(seq, event);
+    }
+
+    pub fn book(&self, symbol: SymbolId) -> Option<&OrderBook> {
+        self.books.get(&symbol)
+    }
+
+    pub fn recorder(&self) -> &BacktestRecorder {
+        &self.recorder
+    }
+}
+
+// What decides which shard an event belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ShardKey {
+    Group(usize),
+    Symbol(SymbolId),
+    // Events without a symbol, such as heartbeats
+    Unkeyed,
+}
+
+/// Backtests independent symbols concurrently.
+///
+/// Each symbol gets its own shard unless it was [grouped](Self::group) with
+/// others, for strategies such as pairs that must see several symbols
+/// together. Workers take whole shards from a shared queue, largest first,
+/// and read events straight from the caller's slice.
+#[derive(Debug, Clone)]
+pub struct ParallelBacktest {
+    workers: usize,
+    groups: HashMap<SymbolId, usize>,
+    group_count: usize,
+}
+
+impl ParallelBacktest {
+    /// Run on up to `workers` threads; zero is treated as one
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            groups: HashMap::new(),
+            group_count: 0,
+        }
+    }
+
+    /// Keep `symbols` in one shard. A symbol named in several groups stays
+    /// in the last one.
+    pub fn group(mut self, symbols: impl IntoIterator<Item = SymbolId>) -> Self {
+        let group = self.group_count;
+        self.group_count += 1;
+        for symbol in symbols {
+            self.groups.insert(symbol, group);
+        }
+        self
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Backtest `events` (in timestamp order) and merge every shard into one
+    /// report, identical to a serial run over the same events
+    pub fn run(&self, events: &[MarketEvent]) -> BacktestReport {
+        let shards = self.run_shards(events);
+        let recorders: Vec<BacktestRecorder> = shards.into_iter().map(|s| s.recorder).collect();
+        BacktestRecorder::combine(&recorders)
+    }
+
+    /// Backtest `events` and return the finished shards
+    pub fn run_shards(&self, events: &[MarketEvent]) -> Vec<BacktestShard> {
+        let mut plan = self.plan(events);
+        // Largest first so one big shard is not left to run alone at the end
+        plan.sort_by_key(|indices| std::cmp::Reverse(indices.len()));
+
+        let next = AtomicUsize::new(0);
+        let workers = self.workers.min(plan.len());
+        debug!(
+            "Backtesting {} events in {} shards on {} workers",
+            events.len(),
+            plan.len(),
+            workers
+        );
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let shard = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(indices) = plan.get(shard) else {
+                                break;
+                            };
+                            let mut state = BacktestShard::default();
+                            for &index in indices {
+                                state.on_event(index as u64, &events[index]);
+                            }
+                            done.push(state);
+                        }
+                        done
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| match handle.join() {
+                    Ok(done) => done,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+                .collect()
+        })
+    }
+
+    // Indices into `events` for each shard, each in stream order
+    fn plan(&self, events: &[MarketEvent]) -> Vec<Vec<usize>> {
+        let mut shard_of: HashMap<ShardKey, usize> = HashMap::new();
+        let mut plan: Vec<Vec<usize>> = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            let key = match event.symbol() {
+                Some(symbol) => match self.groups.get(&symbol) {
+                    Some(&group) => ShardKey::Group(group),
+                    None => ShardKey::Symbol(symbol),
+                },
+                None => ShardKey::Unkeyed,
+            };
+            let shard = *shard_of.entry(key).or_insert_with(|| {
+                plan.push(Vec::new());
+                plan.len() - 1
+            });
+            plan[shard].push(index);
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::Side;
+    use crate::core::orders::{Fill, OrderSide};
+    use crate::core::types::{Px, Qty};
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    // Interleaved book, trade and fill events for two unrelated symbols
+    fn two_symbol_stream() -> (SymbolId, SymbolId, Vec<MarketEvent>) {
+        let (a, b) = (
+            SymbolId::intern("PARAAA").unwrap(),
+            SymbolId::intern("PARBBB").unwrap(),
+        );
+        let mut events = Vec::new();
+        for step in 0..50i64 {
+            let ts = step as u64;
+            for (symbol, base) in [(a, 100), (b, 2_000)] {
+                let price = base + (step * 7 % 11) - 5;
+                events.push(MarketEvent::BookUpdate {
+                    symbol,
+                    timestamp_ns: ts,
+                    normalized_ns: ts,
+                    side: Side::Bid,
+                    price: px(price - 1),
+                    size: qty(3),
+                });
+                events.push(MarketEvent::Trade {
+                    symbol,
+                    timestamp_ns: ts,
+                    normalized_ns: ts,
+                    price: px(price),
+                    size: qty(1),
+                });
+                if step % 5 == 0 {
+                    let side = if step % 10 == 0 {
+                        OrderSide::Buy
+                    } else {
+                        OrderSide::Sell
+                    };
+                    events.push(MarketEvent::Fill(Fill {
+                        order_id: step as u64,
+                        symbol,
+                        side,
+                        price: px(price),
+                        qty: qty(2),
+                        timestamp_ns: ts,
+                    }));
+                }
+            }
+            if step % 10 == 0 {
+                events.push(MarketEvent::Heartbeat {
+                    timestamp_ns: ts,
+                    normalized_ns: ts,
+                });
+            }
+        }
+        (a, b, events)
+    }
+
+    fn serial(events: &[MarketEvent]) -> BacktestReport {
+        let mut recorder = BacktestRecorder::new();
+        for event in events {
+            recorder.on_event(event);
+        }
+        recorder.report()
+    }
+
+    #[test]
+    fn merged_report_matches_serial_runs() {
+        let (a, b, events) = two_symbol_stream();
+        let merged = ParallelBacktest::new(2).run(&events);
+
+        assert_eq!(merged, serial(&events));
+        assert!(merged.closed_trades > 0 && !merged.realized_pnl.is_zero());
+
+        // Each symbol alone, then summed
+        let only = |symbol| -> Vec<MarketEvent> {
+            events
+                .iter()
+                .filter(|e| e.symbol() == Some(symbol))
+                .cloned()
+                .collect()
+        };
+        let (ra, rb) = (serial(&only(a)), serial(&only(b)));
+        assert_eq!(
+            merged.total_pnl,
+            ra.total_pnl.checked_add(rb.total_pnl).unwrap()
+        );
+        assert_eq!(
+            merged.realized_pnl,
+            ra.realized_pnl.checked_add(rb.realized_pnl).unwrap()
+        );
+        assert_eq!(merged.trades, ra.trades + rb.trades);
+        assert_eq!(merged.closed_trades, ra.closed_trades + rb.closed_trades);
+        assert_eq!(
+            merged.symbols,
+            [ra.symbols[0].clone(), rb.symbols[0].clone()]
+        );
+    }
+
+    #[test]
+    fn grouped_symbols_share_one_shard() {
+        let (a, b, events) = two_symbol_stream();
+
+        let apart = ParallelBacktest::new(4).run_shards(&events);
+        // One shard per symbol plus one for heartbeats
+        assert_eq!(apart.len(), 3);
+        assert!(
+            apart
+                .iter()
+                .all(|s| s.book(a).is_none() || s.book(b).is_none())
+        );
+
+        let backtest = ParallelBacktest::new(4).group([a, b]);
+        let together = backtest.run_shards(&events);
+        assert_eq!(together.len(), 2);
+        assert!(
+            together
+                .iter()
+                .any(|s| s.book(a).is_some() && s.book(b).is_some())
+        );
+        assert_eq!(backtest.run(&events), serial(&events));
+    }
+}
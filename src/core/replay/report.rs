@@ -0,0 +1,477 @@
+// Backtest results for ShrivenQ
+// Summary metrics built from the portfolio, fill history and equity curve
+
+use crate::core::events::MarketEvent;
+use crate::core::orders::{Fill, OrderSide};
+use crate::core::portfolio::Portfolio;
+use crate::core::types::{Px, Qty, SymbolId};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SymbolTally {
+    events: u64,
+    trades: u64,
+    closed_trades: u64,
+    wins: u64,
+}
+
+impl SymbolTally {
+    fn add(&mut self, other: &SymbolTally) {
+        self.events += other.events;
+        self.trades += other.trades;
+        self.closed_trades += other.closed_trades;
+        self.wins += other.wins;
+    }
+}
+
+/// Follows a backtest event by event and produces a [`BacktestReport`].
+/// Equity is total PnL, sampled after every event.
+///
+/// A backtest sharded across workers gives each shard its own recorder fed
+/// with [`BacktestRecorder::on_event_at`]; [`BacktestRecorder::combine`] then
+/// rebuilds the equity curve of the whole stream from the shards.
+#[derive(Debug, Clone, Default)]
+pub struct BacktestRecorder {
+    portfolio: Portfolio,
+    tallies: HashMap<SymbolId, SymbolTally>,
+    events: u64,
+    last_equity: i64,
+    // Equity changes as (stream sequence, change); unchanged events are implied
+    equity_changes: Vec<(u64, i64)>,
+}
+
+impl BacktestRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the next event of an unsharded stream
+    pub fn on_event(&mut self, event: &MarketEvent) {
+        self.on_event_at(self.events, event);
+    }
+
+    /// Record an event at position `seq` of the full stream; sequences must
+    /// increase across calls
+    pub fn on_event_at(&mut self, seq: u64, event: &MarketEvent) {
+        self.events += 1;
+        if let Some(symbol) = event.symbol() {
+            self.tallies.entry(symbol).or_default().events += 1;
+        }
+
+        match event {
+            MarketEvent::Fill(fill) => self.record_fill(fill),
+            _ => self.portfolio.on_market_event(event),
+        }
+
+        let equity = self.portfolio.total_pnl().raw();
+        if equity != self.last_equity {
+            self.equity_changes
+                .push((seq, equity.saturating_sub(self.last_equity)));
+            self.last_equity = equity;
+        }
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    // A fill that reduces an open position closes a trade; it wins if it
+    // realizes a profit
+    fn record_fill(&mut self, fill: &Fill) {
+        let before = self.portfolio.position(fill.symbol).copied();
+        self.portfolio.apply_fill(fill);
+
+        let tally = self.tallies.entry(fill.symbol).or_default();
+        tally.trades += 1;
+
+        let Some(before) = before else {
+            return;
+        };
+        let closing = match fill.side {
+            OrderSide::Buy => before.qty < Qty::ZERO,
+            OrderSide::Sell => before.qty > Qty::ZERO,
+        };
+        if closing {
+            tally.closed_trades += 1;
+            let realized = self
+                .portfolio
+                .position(fill.symbol)
+                .map_or(before.realized_pnl, |after| after.realized_pnl);
+            if realized > before.realized_pnl {
+                tally.wins += 1;
+            }
+        }
+    }
+
+    pub fn report(&self) -> BacktestReport {
+        Self::combine(std::slice::from_ref(self))
+    }
+
+    /// Report on the union of shards that each saw a disjoint part of one
+    /// stream. Identical to recording the whole stream in one recorder.
+    pub fn combine(shards: &[BacktestRecorder]) -> BacktestReport {
+        let events: u64 = shards.iter().map(|s| s.events).sum();
+
+        let mut tallies: HashMap<SymbolId, SymbolTally> = HashMap::new();
+        let mut positions = Vec::new();
+        let (mut realized_pnl, mut unrealized_pnl) = (Px::ZERO, Px::ZERO);
+        for shard in shards {
+            for (&symbol, tally) in &shard.tallies {
+                tallies.entry(symbol).or_default().add(tally);
+            }
+            let snapshot = shard.portfolio.snapshot();
+            realized_pnl = realized_pnl
+                .checked_add(snapshot.realized_pnl)
+                .unwrap_or(realized_pnl);
+            unrealized_pnl = unrealized_pnl
+                .checked_add(snapshot.unrealized_pnl)
+                .unwrap_or(unrealized_pnl);
+            positions.extend(snapshot.positions);
+        }
+
+        // Walk the merged equity curve; events between changes moved it by zero
+        let mut changes: Vec<(u64, i64)> = shards
+            .iter()
+            .flat_map(|s| s.equity_changes.iter().copied())
+            .collect();
+        changes.sort_unstable_by_key(|&(seq, _)| seq);
+        let (mut equity, mut peak, mut max_drawdown) = (0i64, 0i64, 0i64);
+        let mut moments = Moments::default();
+        for &(_, change) in &changes {
+            equity = equity.saturating_add(change);
+            peak = peak.max(equity);
+            max_drawdown = max_drawdown.max(peak.saturating_sub(equity));
+            moments.push(money(Px::from_raw(change)));
+        }
+        moments.push_zeros(events.saturating_sub(changes.len() as u64));
+
+        let mut symbols: Vec<SymbolReport> = tallies
+            .iter()
+            .map(|(&symbol, tally)| {
+                let position = positions.iter().find(|p| p.symbol == symbol);
+                SymbolReport {
+                    symbol,
+                    events: tally.events,
+                    trades: tally.trades,
+                    closed_trades: tally.closed_trades,
+                    win_rate: win_rate(tally.wins, tally.closed_trades),
+                    net_qty: position.map_or(Qty::ZERO, |p| p.qty),
+                    realized_pnl: position.map_or(Px::ZERO, |p| p.realized_pnl),
+                    unrealized_pnl: position.map_or(Px::ZERO, |p| p.unrealized_pnl),
+                }
+            })
+            .collect();
+        symbols.sort_by_key(|s| s.symbol.as_str());
+
+        let closed_trades = symbols.iter().map(|s| s.closed_trades).sum();
+        let wins = tallies.values().map(|t| t.wins).sum();
+
+        BacktestReport {
+            events,
+            total_pnl: realized_pnl.checked_add(unrealized_pnl).unwrap_or(Px::ZERO),
+            realized_pnl,
+            unrealized_pnl,
+            max_drawdown: Px::from_raw(max_drawdown),
+            sharpe: moments.sharpe(),
+            trades: symbols.iter().map(|s| s.trades).sum(),
+            closed_trades,
+            win_rate: win_rate(wins, closed_trades),
+            symbols,
+        }
+    }
+}
+
+// Welford moments of per-event equity changes, in price units
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    // Fold in a batch of zero samples at once (Chan et al. pairwise update)
+    fn push_zeros(&mut self, zeros: u64) {
+        if zeros == 0 {
+            return;
+        }
+        let count = self.count + zeros;
+        let delta = -self.mean;
+        let weight = zeros as f64 / count as f64;
+        self.mean += delta * weight;
+        self.m2 += delta * delta * self.count as f64 * weight;
+        self.count = count;
+    }
+
+    fn sharpe(&self) -> Option<f64> {
+        (self.count >= 2)
+            .then(|| (self.m2 / (self.count - 1) as f64).sqrt())
+            .filter(|stddev| *stddev > 0.0)
+            .map(|stddev| self.mean / stddev)
+    }
+}
+
+fn win_rate(wins: u64, closed: u64) -> Option<f64> {
+    (closed > 0).then(|| wins as f64 / closed as f64)
+}
+
+fn money(value: Px) -> f64 {
+    value.raw() as f64 / Px::<8>::SCALE as f64
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolReport {
+    pub symbol: SymbolId,
+    pub events: u64,
+    /// Fills, opening and closing
+    pub trades: u64,
+    /// Fills that reduced an open position
+    pub closed_trades: u64,
+    pub win_rate: Option<f64>,
+    pub net_qty: Qty,
+    pub realized_pnl: Px,
+    pub unrealized_pnl: Px,
+}
+
+/// Outcome of one backtest run. PnL and drawdown are money in price units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestReport {
+    pub events: u64,
+    pub total_pnl: Px,
+    pub realized_pnl: Px,
+    pub unrealized_pnl: Px,
+    /// Largest fall of equity from a previous peak
+    pub max_drawdown: Px,
+    /// Mean over standard deviation of per-event equity changes; not
+    /// annualized. `None` until equity has moved.
+    pub sharpe: Option<f64>,
+    pub trades: u64,
+    pub closed_trades: u64,
+    /// Share of closed trades that realized a profit
+    pub win_rate: Option<f64>,
+    /// Sorted by symbol name
+    pub symbols: Vec<SymbolReport>,
+}
+
+impl BacktestReport {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "events": self.events,
+            "total_pnl": money(self.total_pnl),
+            "realized_pnl": money(self.realized_pnl),
+            "unrealized_pnl": money(self.unrealized_pnl),
+            "max_drawdown": money(self.max_drawdown),
+            "sharpe": self.sharpe,
+            "trades": self.trades,
+            "closed_trades": self.closed_trades,
+            "win_rate": self.win_rate,
+            "symbols": self.symbols.iter().map(|s| json!({
+                "symbol": s.symbol.as_str(),
+                "events": s.events,
+                "trades": s.trades,
+                "closed_trades": s.closed_trades,
+                "win_rate": s.win_rate,
+                "net_qty": s.net_qty.raw() as f64 / Qty::<8>::SCALE as f64,
+                "realized_pnl": money(s.realized_pnl),
+                "unrealized_pnl": money(s.unrealized_pnl),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+struct Percent(Option<f64>);
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(rate) => write!(f, "{:.1}%", rate * 100.0),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// Human-readable summary table
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Events          {}", self.events)?;
+        writeln!(f, "Total PnL       {}", self.total_pnl)?;
+        writeln!(f, "  Realized      {}", self.realized_pnl)?;
+        writeln!(f, "  Unrealized    {}", self.unrealized_pnl)?;
+        writeln!(f, "Max drawdown    {}", self.max_drawdown)?;
+        match self.sharpe {
+            Some(sharpe) => writeln!(f, "Sharpe (event)  {:.3}", sharpe)?,
+            None => writeln!(f, "Sharpe (event)  -")?,
+        }
+        writeln!(
+            f,
+            "Trades          {} ({} closed)",
+            self.trades, self.closed_trades
+        )?;
+        writeln!(f, "Win rate        {}", Percent(self.win_rate))?;
+
+        if self.symbols.is_empty() {
+            return Ok(());
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<12} {:>10} {:>8} {:>8} {:>18} {:>18} {:>18}",
+            "Symbol", "Events", "Trades", "Win", "Net qty", "Realized", "Unrealized"
+        )?;
+        for s in &self.symbols {
+            writeln!(
+                f,
+                "{:<12} {:>10} {:>8} {:>8} {:>18} {:>18} {:>18}",
+                s.symbol.as_str(),
+                s.events,
+                s.trades,
+                Percent(s.win_rate).to_string(),
+                s.net_qty.to_string(),
+                s.realized_pnl.to_string(),
+                s.unrealized_pnl.to_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aaa() -> SymbolId {
+        SymbolId::intern("RPTAAA").unwrap()
+    }
+
+    fn bbb() -> SymbolId {
+        SymbolId::intern("RPTBBB").unwrap()
+    }
+
+    fn fill(symbol: SymbolId, side: OrderSide, price: i64, lots: i64) -> MarketEvent {
+        MarketEvent::Fill(Fill {
+            order_id: 1,
+            symbol,
+            side,
+            price: Px::from_int(price).unwrap(),
+            qty: Qty::from_int(lots).unwrap(),
+            timestamp_ns: 0,
+        })
+    }
+
+    fn trade(symbol: SymbolId, price: i64) -> MarketEvent {
+        MarketEvent::Trade {
+            symbol,
+            timestamp_ns: 0,
+            normalized_ns: 0,
+            price: Px::from_int(price).unwrap(),
+            size: Qty::from_int(1).unwrap(),
+        }
+    }
+
+    fn scripted_report() -> BacktestReport {
+        let mut recorder = BacktestRecorder::new();
+        for event in [
+            fill(aaa(), OrderSide::Buy, 100, 2),  // equity 0
+            trade(aaa(), 110),                    // +20 -> 20, the peak
+            trade(aaa(), 90),                     // -40 -> -20, the trough
+            fill(aaa(), OrderSide::Sell, 95, 1),  // loss of 5 -> -15
+            fill(aaa(), OrderSide::Sell, 120, 1), // win of 20 -> 15
+            fill(bbb(), OrderSide::Sell, 50, 1),  // opens a short, 15
+            trade(bbb(), 40),                     // +10 -> 25
+            fill(bbb(), OrderSide::Buy, 45, 1),   // win of 5 -> 20
+            MarketEvent::Heartbeat {
+                timestamp_ns: 0,
+                normalized_ns: 0,
+            },
+        ] {
+            recorder.on_event(&event);
+        }
+        recorder.report()
+    }
+
+    #[test]
+    fn scripted_fills_give_the_expected_pnl_drawdown_and_win_rate() {
+        let report = scripted_report();
+
+        assert_eq!(report.events, 9);
+        assert_eq!(report.total_pnl, Px::from_int(20).unwrap());
+        assert_eq!(report.realized_pnl, Px::from_int(20).unwrap());
+        assert_eq!(report.unrealized_pnl, Px::ZERO);
+        assert_eq!(report.max_drawdown, Px::from_int(40).unwrap());
+        assert_eq!((report.trades, report.closed_trades), (5, 3));
+        assert_eq!(report.win_rate, Some(2.0 / 3.0));
+
+        let [a, b] = report.symbols.as_slice() else {
+            panic!("expected two symbols, got {:?}", report.symbols);
+        };
+        assert_eq!(
+            (a.symbol, a.events, a.trades, a.closed_trades),
+            (aaa(), 5, 3, 2)
+        );
+        assert_eq!(a.win_rate, Some(0.5));
+        assert_eq!(
+            (a.net_qty, a.realized_pnl),
+            (Qty::ZERO, Px::from_int(15).unwrap())
+        );
+        assert_eq!(
+            (b.symbol, b.events, b.trades, b.closed_trades),
+            (bbb(), 3, 2, 1)
+        );
+        assert_eq!(b.win_rate, Some(1.0));
+        assert_eq!(
+            (b.net_qty, b.realized_pnl),
+            (Qty::ZERO, Px::from_int(5).unwrap())
+        );
+    }
+
+    #[test]
+    fn sharpe_is_mean_over_stddev_of_every_events_equity_change() {
+        let changes = [0.0, 20.0, -40.0, 5.0, 30.0, 0.0, 10.0, -5.0, 0.0];
+        let n = changes.len() as f64;
+        let mean = changes.iter().sum::<f64>() / n;
+        let stddev = (changes.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+
+        let sharpe = scripted_report().sharpe.unwrap();
+        assert!((sharpe - mean / stddev).abs() < 1e-12, "{sharpe}");
+
+        let mut idle = BacktestRecorder::new();
+        idle.on_event(&trade(aaa(), 100));
+        idle.on_event(&trade(aaa(), 101));
+        let report = idle.report();
+        assert_eq!((report.sharpe, report.win_rate), (None, None));
+        assert_eq!(report.max_drawdown, Px::ZERO);
+    }
+
+    #[test]
+    fn report_renders_as_json_and_a_table() {
+        let report = scripted_report();
+
+        let json = report.to_json();
+        assert_eq!(json["events"], 9);
+        assert_eq!(json["total_pnl"], 20.0);
+        assert_eq!(json["max_drawdown"], 40.0);
+        assert_eq!(json["closed_trades"], 3);
+        assert_eq!(json["symbols"][0]["symbol"], "RPTAAA");
+        assert_eq!(json["symbols"][0]["realized_pnl"], 15.0);
+        assert_eq!(json["symbols"][1]["win_rate"], 1.0);
+
+        let table = report.to_string();
+        assert!(table.contains("Max drawdown    40.00000000"), "{table}");
+        assert!(table.contains("Trades          5 (3 closed)"), "{table}");
+        assert!(table.contains("Win rate        66.7%"), "{table}");
+        assert!(
+            table
+                .lines()
+                .any(|l| l.starts_with("RPTBBB") && l.contains("100.0%"))
+        );
+    }
+}
@@ -0,0 +1,481 @@
+// Pre-trade risk checks for ShrivenQ
+// Every order passes through here before it can fill, whatever the execution mode
+
+use crate::core::diagnostics::{FlightEvent, FlightRecorder};
+use crate::core::orders::{
+    Fill, InstrumentReject, InstrumentSpec, Order, OrderId, OrderKind, OrderSide,
+};
+use crate::core::portfolio::Portfolio;
+use crate::core::types::{Px, Qty, SymbolId};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskReject {
+    #[error("Order {order_id} would take {symbol} to {projected}, limit {limit}")]
+    PositionLimit {
+        order_id: OrderId,
+        symbol: SymbolId,
+        projected: Qty,
+        limit: Qty,
+    },
+    #[error("Order {order_id} size {qty} exceeds limit {limit}")]
+    OrderSizeLimit {
+        order_id: OrderId,
+        qty: Qty,
+        limit: Qty,
+    },
+    #[error("Order {order_id} blocked: session PnL {pnl} breaches daily loss limit {limit}")]
+    DailyLossLimit {
+        order_id: OrderId,
+        pnl: Px,
+        limit: Px,
+    },
+    #[error("Order {order_id} rejected by instrument rules: {reason}")]
+    Instrument {
+        order_id: OrderId,
+        reason: InstrumentReject,
+    },
+}
+
+/// Per-symbol limits; `None` leaves that dimension unchecked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    /// Largest absolute net position
+    pub max_position: Option<Qty>,
+    /// Largest single order quantity
+    pub max_order_size: Option<Qty>,
+}
+
+/// Everything a [`RiskEngine`] enforces
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskSettings {
+    pub default_limits: RiskLimits,
+    /// Overrides of the default limits per symbol
+    pub symbol_limits: HashMap<SymbolId, RiskLimits>,
+    /// Largest tolerated loss across the portfolio, as a positive amount
+    pub max_daily_loss: Option<Px>,
+    /// Tick, lot and minimum-notional rules per symbol; symbols without a
+    /// spec accept any price and size
+    pub instruments: HashMap<SymbolId, InstrumentSpec>,
+}
+
+impl RiskSettings {
+    /// Limits in force for `symbol`
+    pub fn limits(&self, symbol: SymbolId) -> RiskLimits {
+        self.symbol_limits
+            .get(&symbol)
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WorkingOrder {
+    symbol: SymbolId,
+    side: OrderSide,
+    remaining: Qty,
+}
+
+/// Orders sent to a venue and not yet filled or cancelled. Position limits
+/// count them as if they will fill, so a burst of orders cannot overshoot
+/// a limit before any of them executes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkingOrders {
+    orders: HashMap<OrderId, WorkingOrder>,
+}
+
+impl WorkingOrders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, order: &Order) {
+        self.orders.insert(
+            order.id,
+            WorkingOrder {
+                symbol: order.symbol,
+                side: order.side,
+                remaining: order.qty,
+            },
+        );
+    }
+
+    /// Reduce the order `fill` belongs to, dropping it once complete
+    pub fn fill(&mut self, fill: &Fill) {
+        if let Some(working) = self.orders.get_mut(&fill.order_id) {
+            working.remaining = working.remaining.checked_sub(fill.qty).unwrap_or(Qty::ZERO);
+            if working.remaining <= Qty::ZERO {
+                self.orders.remove(&fill.order_id);
+            }
+        }
+    }
+
+    /// Forget a cancelled or rejected order; false if it was not working
+    pub fn remove(&mut self, order_id: OrderId) -> bool {
+        self.orders.remove(&order_id).is_some()
+    }
+
+    /// Keep only the orders `open` says the venue still holds
+    pub fn retain(&mut self, mut open: impl FnMut(OrderId) -> bool) {
+        self.orders.retain(|&order_id, _| open(order_id));
+    }
+
+    /// Unfilled quantity working on `side` of `symbol`
+    pub fn pending(&self, symbol: SymbolId, side: OrderSide) -> Qty {
+        self.orders
+            .values()
+            .filter(|working| working.symbol == symbol && working.side == side)
+            .fold(Qty::ZERO, |total, working| {
+                total.checked_add(working.remaining).unwrap_or(Qty::MAX)
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+/// Checks orders against instrument rules and position, order-size and
+/// daily-loss limits.
+///
+/// Orders that strictly reduce exposure are always allowed through the
+/// position and loss checks so a breached book can still be flattened.
+///
+/// Clones share one set of limits, so [`RiskEngine::update`] takes effect in
+/// every gate and router holding a clone.
+#[derive(Debug, Clone, Default)]
+pub struct RiskEngine {
+    settings: Arc<RwLock<RiskSettings>>,
+}
+
+impl RiskEngine {
+    pub fn new(default_limits: RiskLimits) -> Self {
+        Self::from_settings(RiskSettings {
+            default_limits,
+            ..RiskSettings::default()
+        })
+    }
+
+    pub fn from_settings(settings: RiskSettings) -> Self {
+        let engine = Self::default();
+        engine.update(settings);
+        engine
+    }
+
+    /// Override the default limits for one symbol
+    pub fn with_symbol_limits(self, symbol: SymbolId, limits: RiskLimits) -> Self {
+        self.settings.write().symbol_limits.insert(symbol, limits);
+        self
+    }
+
+    /// Enforce `spec` on every order for `symbol`
+    pub fn with_instrument(self, symbol: SymbolId, spec: InstrumentSpec) -> Self {
+        self.settings.write().instruments.insert(symbol, spec);
+        self
+    }
+
+    pub fn with_max_daily_loss(self, max_loss: Px) -> Self {
+        self.settings.write().max_daily_loss = Some(max_loss.abs());
+        self
+    }
+
+    /// Replace every limit at once; orders checked afterwards see the new
+    /// limits, including through clones
+    pub fn update(&self, mut settings: RiskSettings) {
+        settings.max_daily_loss = settings.max_daily_loss.map(Px::abs);
+        *self.settings.write() = settings;
+    }
+
+    pub fn settings(&self) -> RiskSettings {
+        self.settings.read().clone()
+    }
+
+    /// Limits in force for `symbol`
+    pub fn limits(&self, symbol: SymbolId) -> RiskLimits {
+        self.settings.read().limits(symbol)
+    }
+
+    pub fn max_daily_loss(&self) -> Option<Px> {
+        self.settings.read().max_daily_loss
+    }
+
+    pub fn instrument(&self, symbol: SymbolId) -> Option<InstrumentSpec> {
+        self.settings.read().instruments.get(&symbol).copied()
+    }
+
+    /// Check `order` against `portfolio` with `working` still outstanding.
+    /// Rejections are also kept in the flight recorder.
+    pub fn check(
+        &self,
+        order: &Order,
+        portfolio: &Portfolio,
+        working: &WorkingOrders,
+    ) -> Result<(), RiskReject> {
+        self.evaluate(order, portfolio, working)
+            .inspect_err(|reject| {
+                FlightRecorder::global().record(FlightEvent::RiskReject(*reject));
+            })
+    }
+
+    fn evaluate(
+        &self,
+        order: &Order,
+        portfolio: &Portfolio,
+        working: &WorkingOrders,
+    ) -> Result<(), RiskReject> {
+        let settings = self.settings.read();
+        let limits = settings.limits(order.symbol);
+
+        if let Some(spec) = settings.instruments.get(&order.symbol) {
+            // Market orders are valued at the last mark, when there is one
+            let reference = match order.kind {
+                OrderKind::Limit(price) => Some(price),
+                OrderKind::Market => portfolio.position(order.symbol).and_then(|p| p.mark),
+            };
+            spec.validate_order(order, reference)
+                .map_err(|reason| RiskReject::Instrument {
+                    order_id: order.id,
+                    reason,
+                })?;
+        }
+
+        if let Some(limit) = limits.max_order_size
+            && order.qty > limit
+        {
+            return Err(RiskReject::OrderSizeLimit {
+                order_id: order.id,
+                qty: order.qty,
+                limit,
+            });
+        }
+
+        // Where the position ends up if every working order on this side
+        // fills, before and after this one
+        let pending = working.pending(order.symbol, order.side);
+        let held = portfolio.net_qty(order.symbol);
+        let project = |from: Qty, qty: Qty| {
+            match order.side {
+                OrderSide::Buy => from.checked_add(qty),
+                OrderSide::Sell => from.checked_sub(qty),
+            }
+            .unwrap_or(Qty::MAX)
+        };
+        let current = project(held, pending);
+        let projected = project(current, order.qty);
+        let reduces = projected.abs() < current.abs();
+
+        if let Some(limit) = limits.max_position
+            && projected.abs() > limit
+            && !reduces
+        {
+            return Err(RiskReject::PositionLimit {
+                order_id: order.id,
+                symbol: order.symbol,
+                projected,
+                limit,
+            });
+        }
+
+        if let Some(limit) = settings.max_daily_loss
+            && !reduces
+        {
+            let pnl = portfolio.total_pnl();
+            if pnl
+                .checked_add(limit)
+                .is_none_or(|headroom| headroom <= Px::ZERO)
+            {
+                return Err(RiskReject::DailyLossLimit {
+                    order_id: order.id,
+                    pnl,
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol() -> SymbolId {
+        SymbolId::intern("RISKTEST").unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    fn order(id: OrderId, side: OrderSide, lots: i64) -> Order {
+        Order {
+            id,
+            symbol: symbol(),
+            side,
+            kind: OrderKind::Limit(Px::from_int(100).unwrap()),
+            qty: qty(lots),
+            timestamp_ns: id,
+        }
+    }
+
+    fn fill(order: &Order, price: i64) -> Fill {
+        Fill {
+            order_id: order.id,
+            symbol: order.symbol,
+            side: order.side,
+            price: Px::from_int(price).unwrap(),
+            qty: order.qty,
+            timestamp_ns: order.timestamp_ns,
+        }
+    }
+
+    fn limited() -> RiskEngine {
+        RiskEngine::new(RiskLimits {
+            max_position: Some(qty(10)),
+            max_order_size: Some(qty(5)),
+        })
+    }
+
+    #[test]
+    fn oversized_order_is_rejected() {
+        let reject = limited()
+            .check(
+                &order(1, OrderSide::Buy, 6),
+                &Portfolio::new(),
+                &WorkingOrders::new(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            reject,
+            RiskReject::OrderSizeLimit { order_id: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn order_within_limits_passes() {
+        let mut portfolio = Portfolio::new();
+        portfolio.apply_fill(&fill(&order(1, OrderSide::Buy, 5), 100));
+        let next = order(2, OrderSide::Buy, 5);
+        assert_eq!(
+            limited().check(&next, &portfolio, &WorkingOrders::new()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn position_limit_rejects_past_the_held_position() {
+        let mut portfolio = Portfolio::new();
+        portfolio.apply_fill(&fill(&order(1, OrderSide::Buy, 5), 100));
+        portfolio.apply_fill(&fill(&order(2, OrderSide::Buy, 4), 100));
+        let reject = limited()
+            .check(
+                &order(3, OrderSide::Buy, 2),
+                &portfolio,
+                &WorkingOrders::new(),
+            )
+            .unwrap_err();
+        assert!(
+            matches!(reject, RiskReject::PositionLimit { projected, .. } if projected == qty(11))
+        );
+    }
+
+    #[test]
+    fn working_orders_count_towards_the_position_limit() {
+        let risk = limited();
+        let portfolio = Portfolio::new();
+        let mut working = WorkingOrders::new();
+        for id in 1..=2 {
+            let resting = order(id, OrderSide::Buy, 5);
+            risk.check(&resting, &portfolio, &working).unwrap();
+            working.insert(&resting);
+        }
+
+        // Nothing has filled, but 10 lots are already working
+        let reject = risk
+            .check(&order(3, OrderSide::Buy, 1), &portfolio, &working)
+            .unwrap_err();
+        assert!(
+            matches!(reject, RiskReject::PositionLimit { projected, .. } if projected == qty(11))
+        );
+        // Working sells do not offset working buys
+        assert_eq!(
+            risk.check(&order(4, OrderSide::Sell, 5), &portfolio, &working),
+            Ok(())
+        );
+
+        working.remove(1);
+        assert_eq!(
+            risk.check(&order(5, OrderSide::Buy, 5), &portfolio, &working),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn resting_orders_count_until_cancelled() {
+        let risk = limited();
+        let portfolio = Portfolio::new();
+        let mut working = WorkingOrders::new();
+        let resting = order(1, OrderSide::Sell, 5);
+        working.insert(&resting);
+        working.insert(&order(2, OrderSide::Sell, 5));
+        assert_eq!(working.pending(symbol(), OrderSide::Sell), qty(10));
+
+        let next = order(3, OrderSide::Sell, 1);
+        assert!(risk.check(&next, &portfolio, &working).is_err());
+
+        // Cancelled by us, or no longer held by the venue
+        assert!(working.remove(resting.id));
+        assert!(!working.remove(resting.id));
+        assert_eq!(risk.check(&next, &portfolio, &working), Ok(()));
+        working.retain(|_| false);
+        assert_eq!(working.pending(symbol(), OrderSide::Sell), Qty::ZERO);
+    }
+
+    #[test]
+    fn fills_move_working_quantity_into_the_position() {
+        let mut working = WorkingOrders::new();
+        let resting = order(1, OrderSide::Buy, 5);
+        working.insert(&resting);
+        let mut partial = fill(&resting, 100);
+        partial.qty = qty(2);
+        working.fill(&partial);
+        assert_eq!(working.pending(symbol(), OrderSide::Buy), qty(3));
+        working.fill(&partial);
+        working.fill(&partial);
+        assert!(working.is_empty());
+    }
+
+    #[test]
+    fn reducing_orders_pass_a_breached_limit() {
+        let risk = limited().with_max_daily_loss(Px::from_int(50).unwrap());
+        let mut portfolio = Portfolio::new();
+        let bought = order(1, OrderSide::Buy, 5);
+        portfolio.apply_fill(&fill(&bought, 100));
+        portfolio.mark(symbol(), Px::from_int(80).unwrap());
+
+        let reject = risk
+            .check(
+                &order(2, OrderSide::Buy, 1),
+                &portfolio,
+                &WorkingOrders::new(),
+            )
+            .unwrap_err();
+        assert!(matches!(reject, RiskReject::DailyLossLimit { .. }));
+        assert_eq!(
+            risk.check(
+                &order(3, OrderSide::Sell, 5),
+                &portfolio,
+                &WorkingOrders::new()
+            ),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,284 @@
+// Hot-swappable strategy slot
+// Replaces strategy logic between events while books, positions and feeds carry on
+
+use super::{OrderContext, Strategy};
+use crate::core::events::MarketEvent;
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+// Replacement waiting for the next event boundary
+type SwapPoint = Mutex<Option<Box<dyn Strategy>>>;
+
+/// Runs one strategy and lets it be replaced while the session keeps going.
+///
+/// A reload only queues the new strategy. The swap happens at the start of
+/// the next event, so the old instance always finishes the event it is
+/// handling and the new one sees every event after it; nothing is skipped or
+/// handled twice. Books and positions live in the runner's context rather
+/// than the strategy, so they survive the swap, and the new instance gets
+/// them through [`Strategy::on_attach`] before its first event.
+pub struct StrategyHost {
+    current: Box<dyn Strategy>,
+    pending: Arc<SwapPoint>,
+    reloads: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for StrategyHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrategyHost")
+            .field("current", &self.current.name())
+            .field("reload_pending", &self.pending.lock().is_some())
+            .field("reloads", &self.reloads.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl StrategyHost {
+    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+        Self {
+            current: strategy,
+            pending: Arc::new(Mutex::new(None)),
+            reloads: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Handle for reloading from another task once the host has been handed
+    /// to a runner
+    pub fn handle(&self) -> ReloadHandle {
+        ReloadHandle {
+            pending: Arc::clone(&self.pending),
+            reloads: Arc::clone(&self.reloads),
+        }
+    }
+
+    /// Replace the strategy from the next event on
+    pub fn reload(&self, strategy: Box<dyn Strategy>) {
+        self.handle().reload(strategy);
+    }
+
+    /// Strategy handling events now; a queued reload is not yet in effect
+    pub fn current(&self) -> &dyn Strategy {
+        self.current.as_ref()
+    }
+
+    /// Swaps completed so far
+    pub fn reloads(&self) -> u64 {
+        self.reloads.load(Ordering::Relaxed)
+    }
+
+    fn swap_pending(&mut self, ctx: &mut OrderContext<'_>) {
+        let Some(mut next) = self.pending.lock().take() else {
+            return;
+        };
+        next.on_attach(ctx);
+        info!(
+            "🔁 Strategy {} replaced by {}",
+            self.current.name(),
+            next.name()
+        );
+        self.current = next;
+        self.reloads.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Strategy for StrategyHost {
+    fn name(&self) -> &str {
+        self.current.name()
+    }
+
+    fn on_attach(&mut self, ctx: &mut OrderContext<'_>) {
+        self.swap_pending(ctx);
+        self.current.on_attach(ctx);
+    }
+
+    fn on_event(&mut self, event: &MarketEvent, ctx: &mut OrderContext<'_>) {
+        // Only between events, never part-way through one
+        self.swap_pending(ctx);
+        self.current.on_event(event, ctx);
+    }
+}
+
+/// Queues a replacement strategy for a [`StrategyHost`]
+#[derive(Clone)]
+pub struct ReloadHandle {
+    pending: Arc<SwapPoint>,
+    reloads: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for ReloadHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadHandle")
+            .field("reload_pending", &self.pending.lock().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReloadHandle {
+    /// Swap in `strategy` at the next event boundary. A reload queued before
+    /// the previous one took effect replaces it.
+    pub fn reload(&self, strategy: Box<dyn Strategy>) {
+        if let Some(superseded) = self.pending.lock().replace(strategy) {
+            info!("Queued strategy reload {} superseded", superseded.name());
+        }
+    }
+
+    /// Whether a reload is waiting for the next event
+    pub fn is_pending(&self) -> bool {
+        self.pending.lock().is_some()
+    }
+
+    /// Swaps completed so far
+    pub fn reloads(&self) -> u64 {
+        self.reloads.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::Side;
+    use crate::core::execution::gateway::PaperGateway;
+    use crate::core::orders::{Fill, OrderSide};
+    use crate::core::risk::{RiskEngine, RiskLimits};
+    use crate::core::strategy::StrategyRunner;
+    use crate::core::types::{Px, Qty, SymbolId};
+
+    // (strategy, event timestamp, best bid, net position) as a strategy saw it
+    type Seen = Arc<Mutex<Vec<(&'static str, u64, Option<Px>, Qty)>>>;
+
+    fn symbol() -> SymbolId {
+        SymbolId::intern("HOSTTEST").unwrap()
+    }
+
+    fn px(price: i64) -> Px {
+        Px::from_int(price).unwrap()
+    }
+
+    fn qty(lots: i64) -> Qty {
+        Qty::from_int(lots).unwrap()
+    }
+
+    fn bid(ts: u64) -> MarketEvent {
+        MarketEvent::BookUpdate {
+            symbol: symbol(),
+            timestamp_ns: ts,
+            normalized_ns: ts,
+            side: Side::Bid,
+            price: px(100 + ts as i64),
+            size: qty(1),
+        }
+    }
+
+    fn view(name: &'static str, ctx: &OrderContext<'_>) -> (&'static str, u64, Option<Px>, Qty) {
+        let best_bid = ctx
+            .book(symbol())
+            .and_then(|book| book.best_bid())
+            .map(|(price, _)| price);
+        (
+            name,
+            ctx.timestamp_ns(),
+            best_bid,
+            ctx.portfolio().net_qty(symbol()),
+        )
+    }
+
+    /// Logs every event it handles; queues `successor` from inside the
+    /// event at `reload_at`
+    #[derive(Debug)]
+    struct Recorder {
+        name: &'static str,
+        seen: Seen,
+        attached: Seen,
+        reload_at: u64,
+        successor: Option<(ReloadHandle, Box<dyn Strategy>)>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, seen: &Seen, attached: &Seen) -> Self {
+            Self {
+                name,
+                seen: Arc::clone(seen),
+                attached: Arc::clone(attached),
+                reload_at: 0,
+                successor: None,
+            }
+        }
+    }
+
+    impl Strategy for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn on_attach(&mut self, ctx: &mut OrderContext<'_>) {
+            self.attached.lock().push(view(self.name, ctx));
+        }
+
+        fn on_event(&mut self, _event: &MarketEvent, ctx: &mut OrderContext<'_>) {
+            if ctx.timestamp_ns() == self.reload_at
+                && let Some((handle, next)) = self.successor.take()
+            {
+                handle.reload(next);
+                assert!(handle.is_pending());
+            }
+            self.seen.lock().push(view(self.name, ctx));
+        }
+    }
+
+    #[tokio::test]
+    async fn swap_mid_stream_keeps_state_and_every_event() {
+        let seen = Seen::default();
+        let attached = Seen::default();
+        let mut old = Recorder::new("old", &seen, &attached);
+        // `old` needs the host's handle, so it is swapped in before event 1
+        let host = StrategyHost::new(Box::new(Recorder::new("placeholder", &seen, &attached)));
+        let handle = host.handle();
+        // Reloaded part-way through event 4, so old must still finish it
+        old.reload_at = 4;
+        old.successor = Some((
+            handle.clone(),
+            Box::new(Recorder::new("new", &seen, &attached)),
+        ));
+        host.reload(Box::new(old));
+
+        let gateway = PaperGateway::new(RiskEngine::new(RiskLimits::default()));
+        let mut runner = StrategyRunner::new(gateway).with_strategy(host);
+        let mut events: Vec<MarketEvent> = (1..=8).map(bid).collect();
+        events.insert(
+            3,
+            MarketEvent::Fill(Fill {
+                order_id: 1,
+                symbol: symbol(),
+                side: OrderSide::Buy,
+                price: px(103),
+                qty: qty(2),
+                timestamp_ns: 3,
+            }),
+        );
+        for event in &events {
+            runner.on_event(event).await;
+        }
+
+        let seen = seen.lock();
+        let timestamps: Vec<u64> = seen.iter().map(|&(_, ts, _, _)| ts).collect();
+        assert_eq!(timestamps, [1, 2, 3, 3, 4, 5, 6, 7, 8]);
+        let names: Vec<&str> = seen.iter().map(|&(name, ..)| name).collect();
+        assert_eq!(names[..5], ["old"; 5]);
+        assert_eq!(names[5..], ["new"; 4]);
+
+        // The new strategy takes over the book and position built so far
+        let attached = attached.lock();
+        assert_eq!(attached.len(), 2);
+        assert_eq!(attached[0], ("old", 1, Some(px(101)), Qty::ZERO));
+        assert_eq!(attached[1], ("new", 5, Some(px(105)), qty(2)));
+        assert_eq!(seen[5], ("new", 5, Some(px(105)), qty(2)));
+        assert_eq!(seen[8], ("new", 8, Some(px(108)), qty(2)));
+
+        assert_eq!(handle.reloads(), 2);
+        assert!(!handle.is_pending());
+        assert_eq!(runner.stats().events, 9);
+    }
+}
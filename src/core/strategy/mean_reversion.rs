@@ -0,0 +1,121 @@
+// Mean reversion reference strategy
+// Fades trade prices that stray from their rolling mean, flattens once they return
+
+use super::{OrderContext, Strategy};
+use crate::core::analytics::RollingStats;
+use crate::core::events::MarketEvent;
+use crate::core::orders::{OrderKind, OrderSide};
+use crate::core::types::{Px, Qty, SymbolId};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeanReversionConfig {
+    pub symbol: SymbolId,
+    /// Trades in the rolling window; no orders until it is full
+    pub window: usize,
+    /// Z-score beyond which to fade the move
+    pub entry_z: f64,
+    /// Z-score inside which an open position is closed
+    pub exit_z: f64,
+    pub order_qty: Qty,
+    /// Largest absolute position the strategy builds
+    pub max_position: Qty,
+}
+
+/// Buys when the last trade is `entry_z` standard deviations below the
+/// rolling mean and sells when it is as far above, adding `order_qty` per
+/// signal up to `max_position`; closes the whole position once price is back
+/// within `exit_z` of the mean.
+///
+/// Orders are market orders and the strategy assumes they fill: it tracks the
+/// position it has asked for rather than waiting for fills.
+#[derive(Debug, Clone)]
+pub struct MeanReversionStrategy {
+    config: MeanReversionConfig,
+    stats: RollingStats,
+    position: Qty,
+}
+
+impl MeanReversionStrategy {
+    pub fn new(config: MeanReversionConfig) -> Self {
+        Self {
+            stats: RollingStats::new(config.window),
+            config,
+            position: Qty::ZERO,
+        }
+    }
+
+    /// Position the strategy has ordered so far
+    pub fn target_position(&self) -> Qty {
+        self.position
+    }
+
+    fn place(&mut self, ctx: &mut OrderContext<'_>, side: OrderSide, qty: Qty) {
+        let signed = match side {
+            OrderSide::Buy => self.position.checked_add(qty),
+            OrderSide::Sell => self.position.checked_sub(qty),
+        };
+        let Some(position) = signed else {
+            return;
+        };
+        ctx.submit(self.config.symbol, side, OrderKind::Market, qty);
+        self.position = position;
+    }
+}
+
+impl Strategy for MeanReversionStrategy {
+    fn name(&self) -> &str {
+        "mean_reversion"
+    }
+
+    /// Carry on from the position already held
+    fn on_attach(&mut self, ctx: &mut OrderContext<'_>) {
+        self.position = ctx.portfolio().net_qty(self.config.symbol);
+    }
+
+    fn on_event(&mut self, event: &MarketEvent, ctx: &mut OrderContext<'_>) {
+        let MarketEvent::Trade { symbol, price, .. } = event else {
+            return;
+        };
+        if *symbol != self.config.symbol {
+            return;
+        }
+
+        // Score against the window before this trade joins it
+        let signal = match (self.stats.is_full(), self.stats.mean(), self.stats.stddev()) {
+            (true, Some(mean), Some(stddev)) if stddev > 0.0 => {
+                let last = price.raw() as f64 / Px::<8>::SCALE as f64;
+                Some((last - mean) / stddev)
+            }
+            _ => None,
+        };
+        self.stats.on_event(event);
+        let Some(z) = signal else {
+            return;
+        };
+
+        let config = self.config;
+        if z <= -config.entry_z
+            && self
+                .position
+                .checked_add(config.order_qty)
+                .is_some_and(|p| p <= config.max_position)
+        {
+            self.place(ctx, OrderSide::Buy, config.order_qty);
+        } else if z >= config.entry_z
+            && self
+                .position
+                .checked_sub(config.order_qty)
+                .and_then(Qty::checked_neg)
+                .is_some_and(|p| p <= config.max_position)
+        {
+            self.place(ctx, OrderSide::Sell, config.order_qty);
+        } else if z.abs() <= config.exit_z && !self.position.is_zero() {
+            let side = if self.position > Qty::ZERO {
+                OrderSide::Sell
+            } else {
+                OrderSide::Buy
+            };
+            self.place(ctx, side, self.position.abs());
+        }
+    }
+}
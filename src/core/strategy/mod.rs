@@ -0,0 +1,333 @@
+// Strategy framework for ShrivenQ
+// Trading logic reacting to market events and placing orders through a gateway
+
+pub mod host;
+pub mod mean_reversion;
+
+pub use host::{ReloadHandle, StrategyHost};
+pub use mean_reversion::{MeanReversionConfig, MeanReversionStrategy};
+
+use crate::core::book::OrderBook;
+use crate::core::events::{EventSubscriber, MarketEvent};
+use crate::core::execution::gateway::OrderGateway;
+use crate::core::journal::{SharedJournal, Snapshotter};
+use crate::core::orders::{Order, OrderId, OrderKind, OrderSide};
+use crate::core::portfolio::Portfolio;
+use crate::core::time::SimClock;
+use crate::core::types::{Qty, SymbolId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Events handled per bus drain before checking for shutdown
+const BATCH_SIZE: usize = 256;
+/// Wait before polling an empty bus again
+const IDLE_POLL: Duration = Duration::from_millis(1);
+
+/// Trading logic driven by market events.
+///
+/// `on_event` runs synchronously on the event path; orders placed through
+/// the context are sent to the gateway once it returns.
+pub trait Strategy: Debug + Send {
+    /// Name for logs
+    fn name(&self) -> &str;
+
+    /// Called when the strategy takes over mid-session, e.g. through a
+    /// [`StrategyHost`] reload, with the books and positions built so far
+    fn on_attach(&mut self, _ctx: &mut OrderContext<'_>) {}
+
+    fn on_event(&mut self, event: &MarketEvent, ctx: &mut OrderContext<'_>);
+}
+
+/// Order instruction queued by a strategy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderAction {
+    Submit(Order),
+    Cancel(OrderId),
+}
+
+/// A strategy's view of the market and its handle for placing orders
+/// while handling one event
+#[derive(Debug)]
+pub struct OrderContext<'a> {
+    books: &'a HashMap<SymbolId, OrderBook>,
+    portfolio: &'a Portfolio,
+    timestamp_ns: u64,
+    next_order_id: &'a mut OrderId,
+    actions: &'a mut Vec<OrderAction>,
+}
+
+impl<'a> OrderContext<'a> {
+    /// Orders get ids counting up from `next_order_id` and are queued in
+    /// `actions` for the caller to send
+    pub fn new(
+        books: &'a HashMap<SymbolId, OrderBook>,
+        portfolio: &'a Portfolio,
+        timestamp_ns: u64,
+        next_order_id: &'a mut OrderId,
+        actions: &'a mut Vec<OrderAction>,
+    ) -> Self {
+        Self {
+            books,
+            portfolio,
+            timestamp_ns,
+            next_order_id,
+            actions,
+        }
+    }
+
+    /// Exchange timestamp of the event being handled
+    pub fn timestamp_ns(&self) -> u64 {
+        self.timestamp_ns
+    }
+
+    /// Book built from `BookUpdate` events, if any arrived for `symbol`
+    pub fn book(&self, symbol: SymbolId) -> Option<&OrderBook> {
+        self.books.get(&symbol)
+    }
+
+    /// Positions from fills seen so far; orders still in flight are not included
+    pub fn portfolio(&self) -> &Portfolio {
+        self.portfolio
+    }
+
+    /// Queue a new order and return its id
+    pub fn submit(
+        &mut self,
+        symbol: SymbolId,
+        side: OrderSide,
+        kind: OrderKind,
+        qty: Qty,
+    ) -> OrderId {
+        let id = *self.next_order_id;
+        *self.next_order_id += 1;
+        self.actions.push(OrderAction::Submit(Order {
+            id,
+            symbol,
+            side,
+            kind,
+            qty,
+            timestamp_ns: self.timestamp_ns,
+        }));
+        id
+    }
+
+    pub fn cancel(&mut self, order_id: OrderId) {
+        self.actions.push(OrderAction::Cancel(order_id));
+    }
+}
+
+/// Counts of what a [`StrategyRunner`] did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunnerStats {
+    pub events: u64,
+    pub orders_submitted: u64,
+    pub orders_rejected: u64,
+    pub cancels: u64,
+}
+
+/// Feeds market events to strategies and sends their orders through a gateway.
+///
+/// Books are built from `BookUpdate` events and positions from `Fill`
+/// events, so fills reach strategies the same way in every mode: as events
+/// on the bus.
+#[derive(Debug)]
+pub struct StrategyRunner<G> {
+    gateway: G,
+    strategies: Vec<Box<dyn Strategy>>,
+    books: HashMap<SymbolId, OrderBook>,
+    portfolio: Portfolio,
+    next_order_id: OrderId,
+    actions: Vec<OrderAction>,
+    stats: RunnerStats,
+    sim_clock: Option<SimClock>,
+    snapshots: Option<(SharedJournal, Snapshotter)>,
+}
+
+impl<G: OrderGateway> StrategyRunner<G> {
+    pub fn new(gateway: G) -> Self {
+        Self {
+            gateway,
+            strategies: Vec::new(),
+            books: HashMap::new(),
+            portfolio: Portfolio::new(),
+            next_order_id: 1,
+            actions: Vec::new(),
+            stats: RunnerStats::default(),
+            sim_clock: None,
+            snapshots: None,
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Strategy + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    pub fn with_strategies(mut self, strategies: Vec<Box<dyn Strategy>>) -> Self {
+        self.strategies.extend(strategies);
+        self
+    }
+
+    /// First order id handed out, e.g. past the ids recovered from a journal
+    pub fn with_first_order_id(mut self, id: OrderId) -> Self {
+        self.next_order_id = id;
+        self
+    }
+
+    /// Start from `portfolio`, e.g. one recovered from a journal
+    pub fn with_portfolio(mut self, portfolio: Portfolio) -> Self {
+        self.portfolio = portfolio;
+        self
+    }
+
+    /// Start from `books`, e.g. ones restored from a snapshot
+    pub fn with_books(mut self, books: HashMap<SymbolId, OrderBook>) -> Self {
+        self.books = books;
+        self
+    }
+
+    /// Snapshot the books and `journal`'s state between event batches
+    /// whenever `snapshotter` is due, and once more when the run ends
+    pub fn with_snapshots(mut self, journal: SharedJournal, snapshotter: Snapshotter) -> Self {
+        self.snapshots = Some((journal, snapshotter));
+        self
+    }
+
+    /// Advance `clock` to each event's timestamp before handling it
+    pub fn with_sim_clock(mut self, clock: SimClock) -> Self {
+        self.sim_clock = Some(clock);
+        self
+    }
+
+    pub fn gateway(&self) -> &G {
+        &self.gateway
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    pub fn stats(&self) -> RunnerStats {
+        self.stats
+    }
+
+    /// Update books and positions from `event`, let the venue model react to
+    /// it, then run every strategy and send the orders they placed
+    pub async fn on_event(&mut self, event: &MarketEvent) {
+        self.stats.events += 1;
+        if let Some(clock) = &self.sim_clock {
+            clock.advance_to(event.timestamp_ns());
+        }
+        if let (MarketEvent::BookUpdate { .. }, Some(symbol)) = (event, event.symbol()) {
+            let book = self
+                .books
+                .entry(symbol)
+                .or_insert_with(|| OrderBook::new(symbol));
+            if let Err(e) = book.apply(event) {
+                warn!("Strategy runner dropped book update: {}", e);
+            }
+        }
+        self.portfolio.on_market_event(event);
+        // Fills triggered here come back as events on the bus
+        self.gateway.on_market_event(event);
+
+        for strategy in &mut self.strategies {
+            let mut ctx = OrderContext::new(
+                &self.books,
+                &self.portfolio,
+                event.timestamp_ns(),
+                &mut self.next_order_id,
+                &mut self.actions,
+            );
+            strategy.on_event(event, &mut ctx);
+        }
+        self.send_actions().await;
+    }
+
+    /// Handle events from `subscriber` until `shutdown` resolves or the bus
+    /// disconnects
+    pub async fn run<F>(&mut self, subscriber: &EventSubscriber, shutdown: F)
+    where
+        F: Future<Output = ()>,
+    {
+        let names: Vec<&str> = self.strategies.iter().map(|s| s.name()).collect();
+        info!(
+            "🧠 Running {} strategies via {} gateway: {}",
+            names.len(),
+            self.gateway.name(),
+            names.join(", ")
+        );
+
+        self.drive(subscriber, shutdown).await;
+        self.snapshot(true);
+    }
+
+    async fn drive<F>(&mut self, subscriber: &EventSubscriber, shutdown: F)
+    where
+        F: Future<Output = ()>,
+    {
+        let mut shutdown = pin!(shutdown);
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        loop {
+            batch.clear();
+            if subscriber.drain_into(&mut batch, BATCH_SIZE) == 0 {
+                if let Err(e) = subscriber.try_recv().map(|event| batch.extend(event)) {
+                    warn!("Strategy runner stopping: {}", e);
+                    return;
+                }
+                if batch.is_empty() {
+                    tokio::select! {
+                        _ = &mut shutdown => return,
+                        _ = tokio::time::sleep(IDLE_POLL) => continue,
+                    }
+                }
+            }
+            for event in &batch {
+                self.on_event(event).await;
+            }
+            self.snapshot(false);
+            if futures_util::poll!(&mut shutdown).is_ready() {
+                return;
+            }
+        }
+    }
+
+    // Snapshot if one is due, or regardless when `force`d
+    fn snapshot(&mut self, force: bool) {
+        let Some((journal, snapshotter)) = &mut self.snapshots else {
+            return;
+        };
+        if (force || snapshotter.due())
+            && let Err(e) = journal.snapshot(snapshotter, &self.books)
+        {
+            warn!("Strategy runner could not snapshot: {}", e);
+        }
+    }
+
+    async fn send_actions(&mut self) {
+        for action in std::mem::take(&mut self.actions) {
+            match action {
+                OrderAction::Submit(order) => {
+                    let order_id = order.id;
+                    match self.gateway.submit(order).await {
+                        Ok(_) => self.stats.orders_submitted += 1,
+                        Err(e) => {
+                            self.stats.orders_rejected += 1;
+                            warn!("Strategy order {} not placed: {}", order_id, e);
+                        }
+                    }
+                }
+                OrderAction::Cancel(order_id) => {
+                    self.stats.cancels += 1;
+                    if let Err(e) = self.gateway.cancel(order_id).await {
+                        warn!("Strategy cancel of {} failed: {}", order_id, e);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,14 @@
 // Precision timing for ShrivenQ
 // TSC-based timing, hardware timestamps
 
+pub mod sim;
+pub mod source;
+pub mod span;
+
+pub use sim::SimClock;
+pub use source::{FeedClock, TimeSource};
+pub use span::{LatencyAttribution, LatencySpan, Stage, StageBreakdown};
+
 use std::time::Instant;
 
 #[derive(Debug, Clone, Copy)]
@@ -22,4 +30,26 @@ impl PrecisionTimer {
     pub fn elapsed_micros(&self) -> u64 {
         self.start.elapsed().as_micros() as u64
     }
+
+    /// Block until `nanos` have passed since `start`: sleep while far off,
+    /// then spin for the last stretch to avoid scheduler jitter
+    pub fn wait_until_nanos(&self, nanos: u64) {
+        const SPIN_THRESHOLD_NS: u64 = 200_000;
+
+        loop {
+            let elapsed = self.elapsed_nanos();
+            if elapsed >= nanos {
+                return;
+            }
+
+            let remaining = nanos - elapsed;
+            if remaining > SPIN_THRESHOLD_NS {
+                std::thread::sleep(std::time::Duration::from_nanos(
+                    remaining - SPIN_THRESHOLD_NS,
+                ));
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+    }
 }
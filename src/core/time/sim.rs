@@ -0,0 +1,177 @@
+// Virtual clock for backtests
+// Time that moves with the timestamps of replayed events instead of the wall clock
+
+use crate::core::memory::Clock;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Event time of the first advance, before which the clock reads its base
+const UNSET: u64 = u64::MAX;
+
+/// [`Clock`] that reads the timestamp of the event being processed.
+///
+/// The driver calls [`advance_to`](Self::advance_to) with each event's
+/// timestamp before handling it, so anything timed through the clock sees
+/// the market's time and a year of data runs as fast as it can be read.
+/// Time never steps backwards: an out-of-order event leaves the clock where
+/// it is. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct SimClock {
+    base: Instant,
+    shared: Arc<SimTime>,
+}
+
+#[derive(Debug)]
+struct SimTime {
+    origin_ns: AtomicU64,
+    now_ns: AtomicU64,
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            shared: Arc::new(SimTime {
+                origin_ns: AtomicU64::new(UNSET),
+                now_ns: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Move to event time `timestamp_ns`
+    pub fn advance_to(&self, timestamp_ns: u64) {
+        let _ = self.shared.origin_ns.compare_exchange(
+            UNSET,
+            timestamp_ns,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        self.shared
+            .now_ns
+            .fetch_max(timestamp_ns, Ordering::Relaxed);
+    }
+
+    /// Event time in nanoseconds; zero before the first advance
+    pub fn now_ns(&self) -> u64 {
+        self.shared.now_ns.load(Ordering::Relaxed)
+    }
+
+    /// Event time covered since the first advance
+    pub fn elapsed(&self) -> Duration {
+        match self.shared.origin_ns.load(Ordering::Relaxed) {
+            UNSET => Duration::ZERO,
+            origin => Duration::from_nanos(self.now_ns().saturating_sub(origin)),
+        }
+    }
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SimClock {
+    /// The clock's creation instant plus the event time covered so far
+    fn now(&self) -> Instant {
+        self.base + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::analytics::RollingStats;
+    use crate::core::events::MarketEvent;
+    use crate::core::execution::gateway::PaperGateway;
+    use crate::core::risk::{RiskEngine, RiskLimits};
+    use crate::core::strategy::{OrderContext, Strategy, StrategyRunner};
+    use crate::core::types::{Px, Qty, SymbolId};
+    use parking_lot::Mutex;
+
+    const SECOND: u64 = 1_000_000_000;
+    const SESSION_START_NS: u64 = 1_700_000_000 * SECOND;
+
+    fn trade(at_s: u64) -> MarketEvent {
+        MarketEvent::Trade {
+            symbol: SymbolId::intern("SIMTEST").unwrap(),
+            timestamp_ns: SESSION_START_NS + at_s * SECOND,
+            normalized_ns: SESSION_START_NS + at_s * SECOND,
+            price: Px::from_int(100 + at_s as i64).unwrap(),
+            size: Qty::from_int(1).unwrap(),
+        }
+    }
+
+    /// Keeps a 10 second trade window on the runner's clock and logs what
+    /// the clock read and how many trades the window held at each event
+    #[derive(Debug)]
+    struct Windowed {
+        clock: SimClock,
+        window: RollingStats,
+        log: Arc<Mutex<Vec<(u64, u64, usize)>>>,
+    }
+
+    impl Strategy for Windowed {
+        fn name(&self) -> &str {
+            "windowed"
+        }
+
+        fn on_event(&mut self, event: &MarketEvent, _ctx: &mut OrderContext<'_>) {
+            self.window.on_event(event);
+            self.log
+                .lock()
+                .push((event.timestamp_ns(), self.clock.now_ns(), self.window.len()));
+        }
+    }
+
+    #[tokio::test]
+    async fn clock_follows_events_and_expires_the_window_by_event_time() {
+        let clock = SimClock::new();
+        let log = Arc::default();
+        let strategy = Windowed {
+            clock: clock.clone(),
+            window: RollingStats::new(100)
+                .with_max_age(Duration::from_secs(10), Arc::new(clock.clone())),
+            log: Arc::clone(&log),
+        };
+        let gateway = PaperGateway::new(RiskEngine::new(RiskLimits::default()));
+        let mut runner = StrategyRunner::new(gateway)
+            .with_sim_clock(clock.clone())
+            .with_strategy(strategy);
+
+        let started = Instant::now();
+        for at_s in [0, 3, 6, 12, 30] {
+            runner.on_event(&trade(at_s)).await;
+        }
+        // 30 seconds of market time pass without waiting for them
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let log = log.lock();
+        for &(event_ns, clock_ns, _) in log.iter() {
+            assert_eq!(clock_ns, event_ns);
+        }
+        // The trade at 0s leaves once 12s is reached; only the 30s trade
+        // is left after the 18 second gap
+        let sizes: Vec<usize> = log.iter().map(|&(_, _, len)| len).collect();
+        assert_eq!(sizes, [1, 2, 3, 3, 1]);
+        assert_eq!(clock.elapsed(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn late_events_never_move_the_clock_back() {
+        let clock = SimClock::new();
+        assert_eq!(clock.now_ns(), 0);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+        let base = clock.now();
+
+        clock.advance_to(SESSION_START_NS + 5 * SECOND);
+        clock.advance_to(SESSION_START_NS + 2 * SECOND);
+        assert_eq!(clock.now_ns(), SESSION_START_NS + 5 * SECOND);
+
+        // Elapsed time counts from the first event, not from zero
+        clock.advance_to(SESSION_START_NS + 9 * SECOND);
+        assert_eq!(clock.elapsed(), Duration::from_secs(4));
+        assert_eq!(clock.now() - base, Duration::from_secs(4));
+    }
+}
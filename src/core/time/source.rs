@@ -0,0 +1,241 @@
+// Session clock shared by every feed
+// Maps each venue's native timestamps onto one monotonic nanosecond domain
+
+use crate::core::events::MarketEvent;
+use crate::core::memory::{Clock, SystemClock};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Offset of a feed that has not yet produced an event
+const NO_OFFSET: i64 = i64::MAX;
+
+/// One nanosecond clock for a multi-feed session.
+///
+/// Local time is the wall clock read once at creation, advanced by a
+/// monotonic clock after that, so it reads as epoch nanoseconds but never
+/// steps backwards. Each feed's offset from it is the smallest
+/// `received - exchange` gap seen so far: network delay only ever widens that
+/// gap, so the minimum is the best estimate of the venue's clock skew.
+///
+/// Clones share offsets, so hand one to each feed task.
+#[derive(Debug, Clone)]
+pub struct TimeSource {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    clock: Arc<dyn Clock>,
+    origin: Instant,
+    origin_epoch_ns: u64,
+    offsets: RwLock<HashMap<&'static str, Arc<AtomicI64>>>,
+}
+
+impl TimeSource {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// Advance local time with `clock` instead of the system's monotonic clock
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        let origin_epoch_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        Self {
+            shared: Arc::new(Shared {
+                origin: clock.now(),
+                clock,
+                origin_epoch_ns,
+                offsets: RwLock::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Current local time in nanoseconds
+    pub fn now_ns(&self) -> u64 {
+        let elapsed = self
+            .shared
+            .clock
+            .now()
+            .saturating_duration_since(self.shared.origin);
+        self.shared
+            .origin_epoch_ns
+            .saturating_add(elapsed.as_nanos() as u64)
+    }
+
+    /// Clock for the feed called `feed`. A feed that reconnects under the
+    /// same name keeps its offset estimate.
+    pub fn feed_clock(&self, feed: &'static str) -> FeedClock {
+        let offset = Arc::clone(
+            self.shared
+                .offsets
+                .write()
+                .entry(feed)
+                .or_insert_with(|| Arc::new(AtomicI64::new(NO_OFFSET))),
+        );
+        FeedClock {
+            source: self.clone(),
+            offset,
+        }
+    }
+
+    /// Estimated `local - exchange` offset of `feed`, once it has produced an event
+    pub fn offset_ns(&self, feed: &str) -> Option<i64> {
+        let offset = self
+            .shared
+            .offsets
+            .read()
+            .get(feed)?
+            .load(Ordering::Relaxed);
+        (offset != NO_OFFSET).then_some(offset)
+    }
+}
+
+impl Default for TimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One feed's view of a [`TimeSource`]; taking events never locks
+#[derive(Debug, Clone)]
+pub struct FeedClock {
+    source: TimeSource,
+    offset: Arc<AtomicI64>,
+}
+
+impl FeedClock {
+    /// Map `exchange_ns`, received at local time `received_ns`, onto the
+    /// session clock, refining the offset estimate on the way. The result
+    /// is never later than `received_ns`.
+    pub fn normalize(&self, exchange_ns: u64, received_ns: u64) -> u64 {
+        let gap = i128::from(received_ns) - i128::from(exchange_ns);
+        let gap = i64::try_from(gap).unwrap_or(if gap < 0 { i64::MIN } else { NO_OFFSET - 1 });
+        let offset = self.offset.fetch_min(gap, Ordering::Relaxed).min(gap);
+        let normalized = i128::from(exchange_ns) + i128::from(offset);
+        u64::try_from(normalized.clamp(0, i128::from(received_ns))).unwrap_or(received_ns)
+    }
+
+    /// Set `event.normalized_ns` from its exchange timestamp as received now
+    pub fn stamp(&self, event: &mut MarketEvent) {
+        let normalized = self.normalize(event.timestamp_ns(), self.source.now_ns());
+        event.set_normalized_ns(normalized);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::MockClock;
+    use std::time::Duration;
+
+    const MS: u64 = 1_000_000;
+    const LOCAL_EPOCH_NS: u64 = 1_700_000_000_000 * MS;
+
+    // Venue clocks: binance runs 2s ahead of local time, zerodha 3s behind
+    fn exchange_ns(feed: &str, true_ns: u64) -> u64 {
+        match feed {
+            "binance" => true_ns + 2_000 * MS,
+            _ => true_ns - 3_000 * MS,
+        }
+    }
+
+    #[test]
+    fn skewed_feeds_normalize_into_true_event_order() {
+        let source = TimeSource::new();
+        let clocks = [
+            ("binance", source.feed_clock("binance")),
+            ("zerodha", source.feed_clock("zerodha")),
+        ];
+        // (feed, true time, network latency); each feed's first event
+        // arrives with the minimum latency of 1ms
+        let sent = [
+            ("binance", 10, 1),
+            ("zerodha", 12, 1),
+            ("binance", 14, 20),
+            ("zerodha", 16, 2),
+            ("binance", 20, 3),
+            ("zerodha", 22, 15),
+            ("binance", 25, 1),
+        ];
+        let mut arrivals: Vec<_> = sent
+            .iter()
+            .map(|&(feed, true_ms, latency_ms)| {
+                let true_ns = LOCAL_EPOCH_NS + true_ms * MS;
+                (
+                    feed,
+                    true_ms,
+                    exchange_ns(feed, true_ns),
+                    true_ns + latency_ms * MS,
+                )
+            })
+            .collect();
+        arrivals.sort_by_key(|&(_, _, _, received_ns)| received_ns);
+
+        let mut stream: Vec<(u64, u64, u64)> = arrivals
+            .iter()
+            .map(|&(feed, true_ms, exchange, received)| {
+                let (_, clock) = clocks.iter().find(|(name, _)| *name == feed).unwrap();
+                (clock.normalize(exchange, received), exchange, true_ms)
+            })
+            .collect();
+
+        // Raw venue timestamps put every zerodha event before any binance one
+        stream.sort_by_key(|&(_, exchange, _)| exchange);
+        let by_exchange: Vec<u64> = stream.iter().map(|&(_, _, true_ms)| true_ms).collect();
+        assert_eq!(by_exchange, [12, 16, 22, 10, 14, 20, 25]);
+
+        stream.sort_by_key(|&(normalized, _, _)| normalized);
+        let by_normalized: Vec<u64> = stream.iter().map(|&(_, _, true_ms)| true_ms).collect();
+        assert_eq!(by_normalized, [10, 12, 14, 16, 20, 22, 25]);
+        for &(normalized, _, true_ms) in &stream {
+            assert_eq!(normalized, LOCAL_EPOCH_NS + (true_ms + 1) * MS);
+        }
+
+        assert_eq!(source.offset_ns("binance"), Some(-1_999_000_000));
+        assert_eq!(source.offset_ns("zerodha"), Some(3_001_000_000));
+        assert_eq!(source.offset_ns("kraken"), None);
+    }
+
+    #[test]
+    fn normalized_time_never_passes_receipt() {
+        let clock = TimeSource::new().feed_clock("binance");
+        let received = LOCAL_EPOCH_NS + 100 * MS;
+        assert_eq!(clock.normalize(received - 5 * MS, received), received);
+
+        // A later event claiming a smaller gap tightens the offset but is
+        // still stamped no later than it arrived
+        let received = LOCAL_EPOCH_NS + 200 * MS;
+        assert_eq!(clock.normalize(received - MS, received), received);
+    }
+
+    #[test]
+    fn stamp_reads_receipt_time_from_the_session_clock() {
+        let mock = MockClock::new();
+        let source = TimeSource::with_clock(Arc::new(mock.clone()));
+        let clock = source.feed_clock("zerodha");
+        let venue_skew_ns = 3_000 * MS;
+
+        mock.advance(Duration::from_millis(5));
+        let mut first = MarketEvent::Heartbeat {
+            timestamp_ns: source.now_ns() - venue_skew_ns - MS,
+            normalized_ns: 0,
+        };
+        clock.stamp(&mut first);
+        assert_eq!(first.normalized_ns(), source.now_ns());
+
+        // Sent 4ms ago and delayed 3ms longer than the first event
+        mock.advance(Duration::from_millis(10));
+        let mut second = MarketEvent::Heartbeat {
+            timestamp_ns: source.now_ns() - venue_skew_ns - 4 * MS,
+            normalized_ns: 0,
+        };
+        clock.stamp(&mut second);
+        assert_eq!(second.normalized_ns(), source.now_ns() - 3 * MS);
+        assert_eq!(source.offset_ns("zerodha"), Some(3_001_000_000));
+    }
+}
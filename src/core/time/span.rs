@@ -0,0 +1,319 @@
+// Latency attribution for ShrivenQ
+// Times each pipeline stage of one event's journey and aggregates per-stage percentiles
+
+use crate::core::memory::stats::{HistogramConfig, LatencyStats};
+use hdrhistogram::{CreationError, Histogram};
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+#[cfg(feature = "latency-spans")]
+use super::PrecisionTimer;
+
+/// Step of the tick-to-order path that a span attributes time to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    FeedParse,
+    BookUpdate,
+    StrategyDecision,
+    RiskCheck,
+    GatewaySubmit,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::FeedParse,
+        Stage::BookUpdate,
+        Stage::StrategyDecision,
+        Stage::RiskCheck,
+        Stage::GatewaySubmit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::FeedParse => "feed_parse",
+            Stage::BookUpdate => "book_update",
+            Stage::StrategyDecision => "strategy_decision",
+            Stage::RiskCheck => "risk_check",
+            Stage::GatewaySubmit => "gateway_submit",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Stage timings of a single event, from `start` to `finish`.
+///
+/// Each `checkpoint` charges the time since the previous one (or since
+/// `start`) to a stage; a stage checkpointed twice accumulates. Without the
+/// `latency-spans` feature the span is zero-sized and every call compiles
+/// to nothing.
+#[derive(Debug)]
+pub struct LatencySpan {
+    #[cfg(feature = "latency-spans")]
+    timer: PrecisionTimer,
+    #[cfg(feature = "latency-spans")]
+    last_ns: u64,
+    #[cfg(feature = "latency-spans")]
+    stages: [Option<u64>; Stage::ALL.len()],
+}
+
+impl LatencySpan {
+    #[inline]
+    pub fn start() -> Self {
+        Self {
+            #[cfg(feature = "latency-spans")]
+            timer: PrecisionTimer::start(),
+            #[cfg(feature = "latency-spans")]
+            last_ns: 0,
+            #[cfg(feature = "latency-spans")]
+            stages: [None; Stage::ALL.len()],
+        }
+    }
+
+    /// Charge the time since the previous checkpoint to `stage`
+    #[inline]
+    pub fn checkpoint(&mut self, stage: Stage) {
+        #[cfg(feature = "latency-spans")]
+        {
+            let now = self.timer.elapsed_nanos();
+            let delta = now.saturating_sub(self.last_ns);
+            self.last_ns = now;
+            let slot = &mut self.stages[stage.index()];
+            *slot = Some(slot.unwrap_or(0) + delta);
+        }
+        #[cfg(not(feature = "latency-spans"))]
+        let _ = stage;
+    }
+
+    /// Nanoseconds charged to `stage` so far; always `None` without the feature
+    pub fn stage_ns(&self, stage: Stage) -> Option<u64> {
+        #[cfg(feature = "latency-spans")]
+        {
+            self.stages[stage.index()]
+        }
+        #[cfg(not(feature = "latency-spans"))]
+        {
+            let _ = stage;
+            None
+        }
+    }
+
+    /// Record every checkpointed stage, and the span's total, into `tracker`
+    #[inline]
+    pub fn finish(self, tracker: &LatencyAttribution) {
+        #[cfg(feature = "latency-spans")]
+        {
+            for stage in Stage::ALL {
+                if let Some(ns) = self.stages[stage.index()] {
+                    tracker.record(stage, ns);
+                }
+            }
+            tracker.record_total(self.last_ns);
+        }
+        #[cfg(not(feature = "latency-spans"))]
+        let _ = tracker;
+    }
+}
+
+/// Per-stage latency distributions of every finished span
+#[derive(Debug)]
+pub struct LatencyAttribution {
+    stages: [Mutex<Histogram<u64>>; Stage::ALL.len()],
+    total: Mutex<Histogram<u64>>,
+}
+
+/// One stage's share of the pipeline
+#[derive(Debug, Clone, Copy)]
+pub struct StageBreakdown {
+    pub stage: Stage,
+    pub samples: u64,
+    pub latency: LatencyStats,
+    /// Mean stage time over mean end-to-end time
+    pub share_of_total: f64,
+}
+
+impl LatencyAttribution {
+    pub fn new(config: HistogramConfig) -> Result<Self, CreationError> {
+        let histogram = || {
+            Histogram::new_with_bounds(1, config.max_trackable_ns, config.significant_digits)
+                .map(Mutex::new)
+        };
+        Ok(Self {
+            stages: [
+                histogram()?,
+                histogram()?,
+                histogram()?,
+                histogram()?,
+                histogram()?,
+            ],
+            total: histogram()?,
+        })
+    }
+
+    /// Add one sample for `stage`; values past the histogram's range are clamped
+    pub fn record(&self, stage: Stage, latency_ns: u64) {
+        self.stages[stage.index()]
+            .lock()
+            .saturating_record(latency_ns.max(1));
+    }
+
+    /// Add one end-to-end sample
+    pub fn record_total(&self, latency_ns: u64) {
+        self.total.lock().saturating_record(latency_ns.max(1));
+    }
+
+    pub fn stage_stats(&self, stage: Stage) -> LatencyStats {
+        LatencyStats::from_histogram(&self.stages[stage.index()].lock())
+    }
+
+    /// End-to-end latency of finished spans
+    pub fn total_stats(&self) -> LatencyStats {
+        LatencyStats::from_histogram(&self.total.lock())
+    }
+
+    /// Every stage with at least one sample, in pipeline order
+    pub fn breakdown(&self) -> Vec<StageBreakdown> {
+        let total_mean = self.total_stats().mean_ns;
+        Stage::ALL
+            .iter()
+            .filter_map(|&stage| {
+                let histogram = self.stages[stage.index()].lock();
+                if histogram.is_empty() {
+                    return None;
+                }
+                let latency = LatencyStats::from_histogram(&histogram);
+                Some(StageBreakdown {
+                    stage,
+                    samples: histogram.len(),
+                    latency,
+                    share_of_total: if total_mean > 0.0 {
+                        latency.mean_ns / total_mean
+                    } else {
+                        0.0
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Log the current breakdown, one line per stage
+    pub fn log_breakdown(&self) {
+        let total = self.total_stats();
+        let spans = self.total.lock().len();
+        if spans == 0 {
+            return;
+        }
+        info!(
+            "⏱️  Latency over {} events: p50 {:.0}ns, p99 {:.0}ns",
+            spans, total.median_ns, total.p99_ns
+        );
+        for entry in self.breakdown() {
+            info!(
+                "   {}: p50 {:.0}ns, p99 {:.0}ns, max {}ns ({:.1}% of total)",
+                entry.stage,
+                entry.latency.median_ns,
+                entry.latency.p99_ns,
+                entry.latency.max_ns,
+                entry.share_of_total * 100.0
+            );
+        }
+    }
+
+    /// Log the breakdown every `every` forever. Run it as its own task.
+    pub async fn report(self: Arc<Self>, every: Duration) {
+        let mut interval = tokio::time::interval(every);
+        // The first tick fires immediately, before anything was recorded
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            self.log_breakdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> LatencyAttribution {
+        LatencyAttribution::new(HistogramConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn breakdown_lists_recorded_stages_in_pipeline_order() {
+        let tracker = tracker();
+        for _ in 0..4 {
+            tracker.record(Stage::RiskCheck, 3_000);
+            tracker.record(Stage::FeedParse, 1_000);
+            tracker.record_total(4_000);
+        }
+
+        let breakdown = tracker.breakdown();
+        let stages: Vec<Stage> = breakdown.iter().map(|entry| entry.stage).collect();
+        assert_eq!(stages, [Stage::FeedParse, Stage::RiskCheck]);
+        assert_eq!(breakdown[0].samples, 4);
+        assert!((breakdown[0].share_of_total - 0.25).abs() < 0.01);
+        assert!((breakdown[1].share_of_total - 0.75).abs() < 0.01);
+    }
+
+    #[cfg(not(feature = "latency-spans"))]
+    #[test]
+    fn spans_record_nothing_without_the_feature() {
+        assert_eq!(std::mem::size_of::<LatencySpan>(), 0);
+
+        let tracker = tracker();
+        let mut span = LatencySpan::start();
+        span.checkpoint(Stage::FeedParse);
+        assert_eq!(span.stage_ns(Stage::FeedParse), None);
+        span.finish(&tracker);
+        assert!(tracker.breakdown().is_empty());
+    }
+
+    #[cfg(feature = "latency-spans")]
+    #[test]
+    fn stage_latencies_match_injected_sleeps() {
+        const SLACK_NS: f64 = 5_000_000.0;
+        let script = [
+            (Stage::FeedParse, 2),
+            (Stage::BookUpdate, 6),
+            (Stage::StrategyDecision, 12),
+            (Stage::RiskCheck, 4),
+            (Stage::GatewaySubmit, 8),
+        ];
+
+        let tracker = tracker();
+        for _ in 0..5 {
+            let mut span = LatencySpan::start();
+            for (stage, ms) in script {
+                std::thread::sleep(Duration::from_millis(ms));
+                span.checkpoint(stage);
+                assert!(span.stage_ns(stage).unwrap() >= ms * 1_000_000);
+            }
+            span.finish(&tracker);
+        }
+
+        for (stage, ms) in script {
+            let injected = (ms * 1_000_000) as f64;
+            let stats = tracker.stage_stats(stage);
+            assert!(
+                stats.min_ns as f64 >= injected * 0.999,
+                "{stage}: {stats:?}"
+            );
+            assert!(stats.median_ns < injected + SLACK_NS, "{stage}: {stats:?}");
+        }
+        let total = tracker.total_stats();
+        assert!(total.min_ns as f64 >= 32_000_000.0 * 0.999);
+        assert_eq!(tracker.breakdown().len(), script.len());
+    }
+}
@@ -0,0 +1,353 @@
+// Fixed-point price and quantity types
+// i64 newtypes with a compile-time number of decimal places; no floats, no allocation
+
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedPointError {
+    #[error("empty number")]
+    Empty,
+    #[error("invalid number")]
+    Invalid,
+    #[error("number out of range")]
+    Overflow,
+}
+
+// Divide by `divisor`, rounding half away from zero
+fn div_round(value: i64, divisor: i64) -> i64 {
+    let quotient = value / divisor;
+    let remainder = value % divisor;
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        quotient + value.signum()
+    } else {
+        quotient
+    }
+}
+
+// Parse `[-]digits[.digits]` into units of 10^-decimals. Extra fractional
+// digits are rounded half away from zero.
+fn parse_scaled(raw: &str, decimals: u32) -> Result<i64, FixedPointError> {
+    let raw = raw.trim();
+    let (negative, digits) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw.strip_prefix('+').unwrap_or(raw)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(FixedPointError::Empty);
+    }
+    if !whole
+        .bytes()
+        .chain(fraction.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return Err(FixedPointError::Invalid);
+    }
+
+    // Accumulated as a negative number, which has room for i64::MIN
+    let mut value: i64 = 0;
+    let mut push_digit = |digit: u8| -> Result<(), FixedPointError> {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_sub(i64::from(digit - b'0')))
+            .ok_or(FixedPointError::Overflow)?;
+        Ok(())
+    };
+
+    for digit in whole.bytes() {
+        push_digit(digit)?;
+    }
+    let kept = fraction.len().min(decimals as usize);
+    for digit in fraction.bytes().take(kept) {
+        push_digit(digit)?;
+    }
+    for _ in kept..decimals as usize {
+        push_digit(b'0')?;
+    }
+    // First dropped digit decides rounding
+    if fraction.as_bytes().get(kept).is_some_and(|&d| d >= b'5') {
+        value = value.checked_sub(1).ok_or(FixedPointError::Overflow)?;
+    }
+
+    if negative {
+        Ok(value)
+    } else {
+        value.checked_neg().ok_or(FixedPointError::Overflow)
+    }
+}
+
+fn write_scaled(f: &mut fmt::Formatter<'_>, raw: i64, decimals: u32) -> fmt::Result {
+    let scale = 10u64.pow(decimals);
+    let magnitude = raw.unsigned_abs();
+    let sign = if raw < 0 { "-" } else { "" };
+    if decimals == 0 {
+        write!(f, "{}{}", sign, magnitude)
+    } else {
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            magnitude / scale,
+            magnitude % scale,
+            width = decimals as usize
+        )
+    }
+}
+
+macro_rules! fixed_point_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        pub struct $name<const D: u32 = 8>(i64);
+
+        impl<const D: u32> $name<D> {
+            /// Units per whole number, `10^D`
+            pub const SCALE: i64 = {
+                assert!(D <= 18, "at most 18 decimal places fit in i64");
+                10i64.pow(D)
+            };
+            pub const DECIMALS: u32 = D;
+            pub const ZERO: Self = Self(0);
+            pub const MAX: Self = Self(i64::MAX);
+
+            /// Wrap a value already expressed in `10^-D` units
+            pub const fn from_raw(raw: i64) -> Self {
+                Self(raw)
+            }
+
+            pub const fn raw(self) -> i64 {
+                self.0
+            }
+
+            pub fn from_int(whole: i64) -> Option<Self> {
+                whole.checked_mul(Self::SCALE).map(Self)
+            }
+
+            pub const fn is_zero(self) -> bool {
+                self.0 == 0
+            }
+
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            pub fn checked_mul_int(self, factor: i64) -> Option<Self> {
+                self.0.checked_mul(factor).map(Self)
+            }
+
+            pub fn checked_neg(self) -> Option<Self> {
+                self.0.checked_neg().map(Self)
+            }
+
+            pub fn abs(self) -> Self {
+                Self(self.0.saturating_abs())
+            }
+
+            /// Convert to another number of decimal places, rounding half
+            /// away from zero when precision is lost
+            pub fn rescale<const E: u32>(self) -> Option<$name<E>> {
+                let raw = if E >= D {
+                    self.0.checked_mul(10i64.checked_pow(E - D)?)?
+                } else {
+                    div_round(self.0, 10i64.checked_pow(D - E)?)
+                };
+                Some($name::<E>::from_raw(raw))
+            }
+
+            /// Round to a multiple of `step` (e.g. a tick size), half away from zero
+            pub fn round_to(self, step: Self) -> Option<Self> {
+                if step.0 <= 0 {
+                    return None;
+                }
+                div_round(self.0, step.0).checked_mul(step.0).map(Self)
+            }
+        }
+
+        impl<const D: u32> FromStr for $name<D> {
+            type Err = FixedPointError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_scaled(s, D).map(Self)
+            }
+        }
+
+        impl<const D: u32> fmt::Display for $name<D> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_scaled(f, self.0, D)
+            }
+        }
+    };
+}
+
+fixed_point_type!(
+    /// Price in units of `10^-D`
+    Px
+);
+fixed_point_type!(
+    /// Quantity in units of `10^-D`
+    Qty
+);
+
+impl<const D: u32> Px<D> {
+    /// Notional value of `qty` at this price, in this price's scale
+    pub fn checked_notional<const Q: u32>(self, qty: Qty<Q>) -> Option<Px<D>> {
+        let product = i128::from(self.0) * i128::from(qty.raw());
+        let scaled = product / i128::from(10i64.checked_pow(Q)?);
+        i64::try_from(scaled).ok().map(Self)
+    }
+}
+
+/// Crypto venues quote to 1e-8
+pub type CryptoPx = Px<8>;
+pub type CryptoQty = Qty<8>;
+/// Cash equities quote to 1e-2 and trade whole shares
+pub type EquityPx = Px<2>;
+pub type EquityQty = Qty<0>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extra_digits_round_half_away_from_zero() {
+        assert_eq!("1.005".parse::<EquityPx>(), Ok(Px::from_raw(101)));
+        assert_eq!("1.0049".parse::<EquityPx>(), Ok(Px::from_raw(100)));
+        assert_eq!("-1.005".parse::<EquityPx>(), Ok(Px::from_raw(-101)));
+        assert_eq!(
+            "0.123456785".parse::<CryptoPx>(),
+            Ok(Px::from_raw(12_345_679))
+        );
+        assert_eq!("2.5".parse::<EquityQty>(), Ok(Qty::from_raw(3)));
+        assert_eq!("-2.4".parse::<EquityQty>(), Ok(Qty::from_raw(-2)));
+
+        let px = CryptoPx::from_raw(123_456_789);
+        assert_eq!(px.rescale::<2>(), Some(EquityPx::from_raw(123)));
+        assert_eq!(
+            CryptoPx::from_raw(-123_500_000).rescale::<2>(),
+            Some(EquityPx::from_raw(-124))
+        );
+        assert_eq!(
+            EquityPx::from_raw(123).rescale::<8>(),
+            Some(CryptoPx::from_raw(123_000_000))
+        );
+
+        let tick = EquityPx::from_raw(5);
+        assert_eq!(
+            EquityPx::from_raw(10_137).round_to(tick),
+            Some(Px::from_raw(10_135))
+        );
+        assert_eq!(
+            "101.375"
+                .parse::<CryptoPx>()
+                .unwrap()
+                .round_to("0.05".parse().unwrap()),
+            Some("101.4".parse().unwrap())
+        );
+        assert_eq!(EquityPx::from_raw(10_137).round_to(Px::ZERO), None);
+    }
+
+    #[test]
+    fn overflow_is_detected() {
+        assert_eq!(
+            "92233720368547758.07".parse::<EquityPx>(),
+            Ok(EquityPx::MAX)
+        );
+        assert_eq!(
+            "-92233720368547758.08".parse::<EquityPx>(),
+            Ok(EquityPx::from_raw(i64::MIN))
+        );
+        for raw in [
+            "92233720368547758.08",
+            "92233720368547758.075",
+            "-92233720368547758.09",
+            "-92233720368547758.085",
+            "100000000000000000",
+        ] {
+            assert_eq!(
+                raw.parse::<EquityPx>(),
+                Err(FixedPointError::Overflow),
+                "{raw}"
+            );
+        }
+        assert_eq!(
+            "92233720368.54775808".parse::<CryptoQty>(),
+            Err(FixedPointError::Overflow)
+        );
+
+        assert_eq!(EquityPx::MAX.checked_add(Px::from_raw(1)), None);
+        assert_eq!(
+            EquityPx::from_raw(i64::MIN).checked_sub(Px::from_raw(1)),
+            None
+        );
+        assert_eq!(EquityPx::from_raw(i64::MIN).checked_neg(), None);
+        assert_eq!(EquityPx::MAX.checked_mul_int(2), None);
+        assert_eq!(CryptoPx::from_int(i64::MAX / 10), None);
+        assert_eq!(EquityPx::MAX.rescale::<8>(), None);
+        assert_eq!(EquityPx::MAX.checked_notional(EquityQty::from_raw(2)), None);
+        assert_eq!(
+            EquityPx::from_raw(10_050).checked_notional(EquityQty::from_raw(3)),
+            Some(EquityPx::from_raw(30_150))
+        );
+    }
+
+    #[test]
+    fn strings_round_trip_at_both_scales() {
+        for text in [
+            "0.00",
+            "0.05",
+            "-0.05",
+            "101.37",
+            "-101.37",
+            "92233720368547758.07",
+            "-92233720368547758.08",
+        ] {
+            let px: EquityPx = text.parse().unwrap();
+            assert_eq!(px.to_string(), text);
+            assert_eq!(px.to_string().parse(), Ok(px));
+        }
+        for text in [
+            "0.00000001",
+            "-0.00000001",
+            "65000.12345678",
+            "92233720368.54775807",
+            "-92233720368.54775808",
+        ] {
+            let px: CryptoPx = text.parse().unwrap();
+            assert_eq!(px.to_string(), text);
+            assert_eq!(px.to_string().parse(), Ok(px));
+        }
+        for raw in [0, 1, -1, 250, i64::MAX, i64::MIN] {
+            let qty = EquityQty::from_raw(raw);
+            assert_eq!(qty.to_string().parse(), Ok(qty));
+        }
+
+        // Shorter and signed inputs print at the full scale
+        assert_eq!(
+            "+1.5".parse::<CryptoQty>().unwrap().to_string(),
+            "1.50000000"
+        );
+        assert_eq!(" 7 ".parse::<EquityPx>().unwrap().to_string(), "7.00");
+        assert_eq!(".5".parse::<EquityPx>().unwrap().to_string(), "0.50");
+    }
+
+    #[test]
+    fn malformed_strings_are_rejected() {
+        for (text, error) in [
+            ("", FixedPointError::Empty),
+            ("-", FixedPointError::Empty),
+            (".", FixedPointError::Empty),
+            ("1.2.3", FixedPointError::Invalid),
+            ("1e5", FixedPointError::Invalid),
+            ("--1", FixedPointError::Invalid),
+            ("12 34", FixedPointError::Invalid),
+        ] {
+            assert_eq!(text.parse::<EquityPx>(), Err(error), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,8 @@
+// Core value types for ShrivenQ
+// Integer-only representations used on the hot path
+
+pub mod fixed_point;
+pub mod symbol;
+
+pub use fixed_point::{CryptoPx, CryptoQty, EquityPx, EquityQty, FixedPointError, Px, Qty};
+pub use symbol::{SymbolError, SymbolId, SymbolRegistry};
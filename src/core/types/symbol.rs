@@ -0,0 +1,223 @@
+// Symbol interning for ShrivenQ
+// Compact SymbolId handles in place of exchange symbol strings on the hot path
+
+use parking_lot::Mutex;
+use rustc_hash::FxHasher;
+use std::fmt;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
+
+/// Symbols the global registry can hold
+pub const DEFAULT_SYMBOL_CAPACITY: usize = 16_384;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SymbolError {
+    #[error("Symbol registry is full ({capacity} symbols)")]
+    RegistryFull { capacity: usize },
+    #[error("Symbol must not be empty")]
+    Empty,
+}
+
+/// Interned exchange symbol. Ids are dense, assigned in registration order
+/// and stable for the life of the process, but not across processes: persist
+/// the name, not the id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SymbolId(u32);
+
+impl SymbolId {
+    /// Intern `name` in the global registry
+    pub fn intern(name: &str) -> Result<Self, SymbolError> {
+        SymbolRegistry::global().intern(name)
+    }
+
+    /// Id of `name` if it is already in the global registry
+    pub fn lookup(name: &str) -> Option<Self> {
+        SymbolRegistry::global().lookup(name)
+    }
+
+    /// Name from the global registry
+    pub fn as_str(self) -> &'static str {
+        SymbolRegistry::global()
+            .resolve(self)
+            .map_or("<unregistered>", |name| name.as_ref())
+    }
+
+    /// Dense index, usable to address per-symbol arrays
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Fixed-capacity string interner.
+///
+/// There is one per process, reached through [`SymbolRegistry::global`]:
+/// `SymbolId` carries no reference to its registry, so `as_str` and
+/// `Display` can only ever resolve against the global one.
+///
+/// Everything is preallocated up front. Reads (`lookup`, `resolve`) never
+/// lock: names sit in write-once slots indexed by id, and the name-to-id
+/// index is an open-addressed table of atomics kept at most half full.
+/// Registering a new symbol takes a mutex, so do it during startup where
+/// possible; interning an already-known symbol is a read.
+#[derive(Debug)]
+pub struct SymbolRegistry {
+    names: Box<[OnceLock<Arc<str>>]>,
+    /// `id + 1` per slot, zero when empty
+    index: Box<[AtomicU32]>,
+    len: AtomicU32,
+    writer: Mutex<()>,
+}
+
+impl SymbolRegistry {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.min(u32::MAX as usize - 1);
+        let slots = (capacity * 2).next_power_of_two().max(2);
+        Self {
+            names: (0..capacity).map(|_| OnceLock::new()).collect(),
+            index: (0..slots).map(|_| AtomicU32::new(0)).collect(),
+            len: AtomicU32::new(0),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Process-wide registry used by market events, books and orders
+    pub fn global() -> &'static SymbolRegistry {
+        static GLOBAL: OnceLock<SymbolRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(|| SymbolRegistry::new(DEFAULT_SYMBOL_CAPACITY))
+    }
+
+    /// Id for `name`, registering it if new
+    pub fn intern(&self, name: &str) -> Result<SymbolId, SymbolError> {
+        if let Some(id) = self.lookup(name) {
+            return Ok(id);
+        }
+        if name.is_empty() {
+            return Err(SymbolError::Empty);
+        }
+
+        let _writer = self.writer.lock();
+        if let Some(id) = self.lookup(name) {
+            return Ok(id);
+        }
+        let id = self.len.load(Ordering::Relaxed);
+        let slot = self
+            .names
+            .get(id as usize)
+            .ok_or(SymbolError::RegistryFull {
+                capacity: self.capacity(),
+            })?;
+        // Only the writer fills slots, in order, so this one is still empty
+        let _ = slot.set(Arc::from(name));
+
+        let mut probe = self.home_slot(name);
+        while self.index[probe].load(Ordering::Relaxed) != 0 {
+            probe = (probe + 1) & self.mask();
+        }
+        self.index[probe].store(id + 1, Ordering::Release);
+        self.len.store(id + 1, Ordering::Release);
+        Ok(SymbolId(id))
+    }
+
+    /// Intern every symbol in `names`, e.g. a subscription list at startup
+    pub fn intern_all<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<SymbolId>, SymbolError> {
+        names
+            .iter()
+            .map(|name| self.intern(name.as_ref()))
+            .collect()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<SymbolId> {
+        let mut probe = self.home_slot(name);
+        loop {
+            let entry = self.index[probe].load(Ordering::Acquire);
+            if entry == 0 {
+                return None;
+            }
+            let id = SymbolId(entry - 1);
+            if self.resolve(id).is_some_and(|known| known.as_ref() == name) {
+                return Some(id);
+            }
+            probe = (probe + 1) & self.mask();
+        }
+    }
+
+    pub fn resolve(&self, id: SymbolId) -> Option<&Arc<str>> {
+        self.names.get(id.index())?.get()
+    }
+
+    /// Every registered symbol with its name, in id order
+    pub fn iter(&self) -> impl Iterator<Item = (SymbolId, &Arc<str>)> + '_ {
+        (0..self.len.load(Ordering::Acquire))
+            .filter_map(|id| Some((SymbolId(id), self.names[id as usize].get()?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.names.len()
+    }
+
+    fn mask(&self) -> usize {
+        self.index.len() - 1
+    }
+
+    fn home_slot(&self, name: &str) -> usize {
+        let mut hasher = FxHasher::default();
+        hasher.write(name.as_bytes());
+        hasher.finish() as usize & self.mask()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_is_deduplicated_and_resolves_back() {
+        let first = SymbolId::intern("SYMTEST-A").unwrap();
+        let second = SymbolId::intern("SYMTEST-B").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(SymbolId::intern("SYMTEST-A").unwrap(), first);
+        assert_eq!(SymbolId::lookup("SYMTEST-B"), Some(second));
+        assert_eq!(SymbolId::lookup("SYMTEST-MISSING"), None);
+
+        assert_eq!(first.as_str(), "SYMTEST-A");
+        assert_eq!(second.to_string(), "SYMTEST-B");
+        assert_eq!(
+            SymbolRegistry::global()
+                .resolve(first)
+                .map(|name| name.as_ref()),
+            Some("SYMTEST-A")
+        );
+    }
+
+    #[test]
+    fn empty_names_and_full_registries_are_rejected() {
+        assert_eq!(SymbolId::intern(""), Err(SymbolError::Empty));
+
+        let registry = SymbolRegistry::new(2);
+        let ids = registry.intern_all(&["X", "Y", "X"]).unwrap();
+        assert_eq!(ids[0], ids[2]);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry.intern("Z"),
+            Err(SymbolError::RegistryFull { capacity: 2 })
+        );
+        let names: Vec<_> = registry.iter().map(|(_, name)| name.to_string()).collect();
+        assert_eq!(names, ["X", "Y"]);
+    }
+}
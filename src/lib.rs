@@ -0,0 +1,5 @@
+//! ShrivenQ Nexus core library
+//!
+//! Shared by the `shriven-q` binary and the auxiliary binaries under `src/bin`.
+
+pub mod core;
@@ -7,9 +7,8 @@
 //! - Multi-asset class support
 //! - Local exchange simulation
 
-pub mod core;
+use shriven_q::core;
 
-use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing::{info, warn};
 
@@ -27,18 +26,46 @@ struct Cli {
     #[arg(long, default_value = "config/default.toml")]
     config: String,
 
-    /// Log level
-    #[arg(long, default_value = "info")]
-    log_level: String,
+    /// Log level; overrides system.log_level from the config file and
+    /// SHRIVENQ_SYSTEM_LOG_LEVEL [default: info]
+    #[arg(long)]
+    log_level: Option<String>,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
 
     /// Enable GPU acceleration
     #[arg(long)]
     gpu: bool,
 
+    /// RNG seed for simulated fills; overrides the config file, random if unset
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Pin to these cores (Linux): the last runs market data, the others the
+    /// async runtime
+    #[arg(long, value_delimiter = ',')]
+    cpu_affinity: Vec<usize>,
+
+    /// Shut down gracefully after this long (e.g. 500ms, 30s, 5m, 1h), as if
+    /// Ctrl+C had been pressed; for soak tests and CI smoke runs
+    #[arg(long, value_parser = parse_duration)]
+    max_runtime: Option<Duration>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Log output formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines with the startup banner
+    Pretty,
+    /// One JSON object per line for log aggregation; no banner
+    Json,
+}
+
 /// Execution modes
 #[derive(Clone, Copy, Debug, clap::ValueEnum)]
 enum ExecutionMode {
@@ -52,6 +79,44 @@ enum ExecutionMode {
     Live,
 }
 
+/// Market data venues for paper mode
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum FeedVenue {
+    /// Binance spot WebSocket streams (binance-integration)
+    Binance,
+    /// Zerodha Kite ticker; reads KITE_API_KEY, KITE_ACCESS_TOKEN and the
+    /// KITE_INSTRUMENTS csv path (zerodha-integration)
+    Kite,
+}
+
+/// Paper mode market data source
+#[derive(Clone, Debug, clap::Args)]
+struct FeedArgs {
+    /// Symbols to stream market data for
+    #[arg(long, value_delimiter = ',', default_value = "BTCUSDT")]
+    symbols: Vec<String>,
+    /// Venue to stream market data from
+    #[arg(long, value_enum, default_value = "binance")]
+    feed: FeedVenue,
+    /// Capture the received events to this feed log
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Replay a feed log at its original pace instead of connecting to a venue
+    #[arg(long, conflicts_with = "record")]
+    replay: Option<PathBuf>,
+}
+
+impl Default for FeedArgs {
+    fn default() -> Self {
+        Self {
+            symbols: vec!["BTCUSDT".to_string()],
+            feed: FeedVenue::Binance,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start the trading engine
@@ -59,6 +124,11 @@ enum Commands {
         /// Port to bind services
         #[arg(long, default_value = "8080")]
         port: u16,
+        /// Serve Prometheus metrics on this port (requires metrics-http feature)
+        #[arg(long)]
+        metrics_port: Option<u16>,
+        #[command(flatten)]
+        feed: FeedArgs,
     },
     /// Run system benchmarks
     Benchmark {
@@ -68,8 +138,14 @@ enum Commands {
     },
     /// Validate system configuration
     Validate,
+    /// Run the full initialization path, then exit without starting the engine
+    Preflight,
     /// Show system information
-    Info,
+    Info {
+        /// Print the information as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 impl std::fmt::Display for ExecutionMode {
@@ -83,51 +159,163 @@ impl std::fmt::Display for ExecutionMode {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+impl From<ExecutionMode> for crate::core::execution::ExecutionMode {
+    fn from(mode: ExecutionMode) -> Self {
+        match mode {
+            ExecutionMode::Backtest => Self::Backtest,
+            ExecutionMode::Simulation => Self::Simulation,
+            ExecutionMode::Paper => Self::Paper,
+            ExecutionMode::Live => Self::Live,
+        }
+    }
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    FlightRecorder::install_panic_hook();
+    let result = init_tracing(&cli)
+        .and_then(|()| build_runtime(&cli.cpu_affinity))
+        .and_then(|runtime| runtime.block_on(run(cli)));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => e.report(),
+    }
+}
 
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(cli.log_level.parse()?)
-                .add_directive("shriven_q=info".parse()?),
-        )
+fn init_tracing(cli: &Cli) -> Result<()> {
+    // The filter stays reloadable for config hot reload
+    let subscriber = log_subscriber(cli.log_level.as_deref().unwrap_or(DEFAULT_LOG_LEVEL))?;
+    match cli.log_format {
+        LogFormat::Pretty => {
+            let subscriber = subscriber.with_filter_reloading();
+            let handle = subscriber.reload_handle();
+            set_log_reloader(move |filter| handle.reload(filter));
+            subscriber.init();
+        }
+        LogFormat::Json => {
+            let subscriber = json_logs(subscriber).with_filter_reloading();
+            let handle = subscriber.reload_handle();
+            set_log_reloader(move |filter| handle.reload(filter));
+            subscriber.init();
+        }
+    }
+    Ok(())
+}
+
+// Human-readable subscriber at `level`, tagging thread ids and line numbers
+fn log_subscriber(level: &str) -> Result<SubscriberBuilder<DefaultFields, Format, EnvFilter>> {
+    Ok(tracing_subscriber::fmt()
+        .with_env_filter(log_filter(level)?)
         .with_target(false)
         .with_thread_ids(true)
-        .with_line_number(true)
-        .init();
+        .with_line_number(true))
+}
 
-    // ASCII Art Banner
-    print_banner();
+// One JSON object per line, event fields at the top level
+fn json_logs<W>(
+    subscriber: SubscriberBuilder<DefaultFields, Format, EnvFilter, W>,
+) -> SubscriberBuilder<JsonFields, Format<Json>, EnvFilter, W> {
+    subscriber
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+}
 
-    // Show system information
-    info!("🚀 ShrivenQ Nexus - Ultra-Low Latency Trading Platform");
-    info!("├─ Mode: {}", cli.mode);
-    info!("├─ Config: {}", cli.config);
-    info!(
-        "├─ GPU Acceleration: {}",
-        if cli.gpu { "ENABLED" } else { "DISABLED" }
-    );
-    info!("└─ Version: {}", env!("CARGO_PKG_VERSION"));
+/// The multi-threaded runtime, with the main thread and one worker per
+/// runtime core pinned when `--cpu-affinity` is given
+fn build_runtime(cpus: &[usize]) -> Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+
+    if !cpus.is_empty() {
+        let affinity = CpuAffinity::new(cpus).map_err(|e| ConfigError::Invalid {
+            field: "--cpu-affinity".into(),
+            reason: e.to_string(),
+        })?;
+        if !affinity.isolates_feed() {
+            warn!("--cpu-affinity lists one core; market data shares it with the runtime");
+        }
+        pin_current_thread("main", affinity.runtime_cpus());
+        let runtime_cpus = affinity.runtime_cpus().to_vec();
+        builder
+            .worker_threads(runtime_cpus.len())
+            .on_thread_start(move || pin_current_thread("runtime", &runtime_cpus));
+        let _ = CPU_AFFINITY.set(affinity);
+    }
+
+    builder
+        .build()
+        .map_err(|e| ShrivenError::Other(anyhow::Error::new(e).context("failed to start runtime")))
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    if cli.log_format == LogFormat::Pretty {
+        // ASCII Art Banner
+        print_banner();
+
+        // Show system information
+        info!("🚀 ShrivenQ Nexus - Ultra-Low Latency Trading Platform");
+        info!("├─ Mode: {}", cli.mode);
+        info!("├─ Config: {}", cli.config);
+        info!(
+            "├─ GPU Acceleration: {}",
+            if cli.gpu { "ENABLED" } else { "DISABLED" }
+        );
+        info!("└─ Version: {}", env!("CARGO_PKG_VERSION"));
+    } else {
+        info!(
+            mode = %cli.mode,
+            config = %cli.config,
+            gpu = cli.gpu,
+            version = env!("CARGO_PKG_VERSION"),
+            "ShrivenQ Nexus starting"
+        );
+    }
 
     // Check system capabilities
     check_system_capabilities(cli.gpu).await?;
 
+    let config_source = ConfigSource::new(&cli.config)
+        .with_process_env()
+        .with_cli(CliOverrides {
+            log_level: cli.log_level.clone(),
+        });
+
     // Execute command
-    match cli.command.unwrap_or(Commands::Start { port: 8080 }) {
-        Commands::Start { port } => {
-            start_trading_engine(cli.mode, &cli.config, port, cli.gpu).await?;
+    match cli.command.unwrap_or(Commands::Start {
+        port: 8080,
+        metrics_port: None,
+        feed: FeedArgs::default(),
+    }) {
+        Commands::Start {
+            port,
+            metrics_port,
+            feed,
+        } => {
+            let rng = session_rng(cli.seed, &cli.config)?;
+            start_trading_engine(
+                cli.mode,
+                &config_source,
+                port,
+                metrics_port,
+                &feed,
+                cli.gpu,
+                rng,
+                cli.max_runtime,
+            )
+            .await?;
         }
         Commands::Benchmark { iterations } => {
             run_benchmarks(iterations).await?;
         }
         Commands::Validate => {
-            validate_configuration(&cli.config).await?;
+            validate_configuration(&config_source).await?;
+        }
+        Commands::Preflight => {
+            run_preflight(cli.mode, &config_source, cli.gpu).await?;
         }
-        Commands::Info => {
-            show_system_info().await?;
+        Commands::Info { json } => {
+            show_system_info(json).await?;
         }
     }
 
@@ -160,7 +348,15 @@ async fn check_system_capabilities(gpu_enabled: bool) -> Result<()> {
     info!("🔍 Checking system capabilities...");
 
     // Check CPU features
-    info!("├─ CPU: {}", std::env::consts::ARCH);
+    let cpu = CpuFeatures::detect();
+    info!("├─ CPU: {} (SIMD: {})", cpu.arch, cpu.simd_level);
+    info!("├─ CPU features: {}", cpu.features.join(", "));
+    for feature in cpu.missing_compiled_features() {
+        warn!(
+            "├─ CPU: built assuming {} but this CPU lacks it; expect illegal instructions",
+            feature
+        );
+    }
 
     // Check available memory
     // TODO: Implement proper memory check
@@ -168,13 +364,8 @@ async fn check_system_capabilities(gpu_enabled: bool) -> Result<()> {
 
     // Check GPU availability if requested
     if gpu_enabled {
-        match check_gpu_availability().await {
-            Ok(_) => info!("├─ GPU: CUDA device detected and ready"),
-            Err(e) => {
-                warn!("├─ GPU: CUDA not available: {}", e);
-                warn!("├─ GPU: Falling back to CPU-only mode");
-            }
-        }
+        let backend = core::compute::select_backend(true);
+        info!("├─ GPU: using the {} compute backend", backend.name());
     } else {
         info!("├─ GPU: Disabled (CPU-only mode)");
     }
@@ -187,29 +378,36 @@ async fn check_system_capabilities(gpu_enabled: bool) -> Result<()> {
     Ok(())
 }
 
-async fn check_gpu_availability() -> Result<()> {
-    // TODO: Implement actual CUDA device detection
-    // For now, just return an error to simulate GPU not being available
-    anyhow::bail!("CUDA runtime not initialized (placeholder)")
-}
-
+#[allow(clippy::too_many_arguments)]
 async fn start_trading_engine(
     mode: ExecutionMode,
-    config_path: &str,
+    config_source: &ConfigSource,
     port: u16,
+    metrics_port: Option<u16>,
+    feed: &FeedArgs,
     gpu_enabled: bool,
+    rng: RngService,
+    max_runtime: Option<Duration>,
 ) -> Result<()> {
     info!("🚀 Starting ShrivenQ Nexus Trading Engine");
     info!("├─ Execution Mode: {}", mode);
-    info!("├─ Configuration: {}", config_path);
+    info!("├─ Configuration: {}", config_source.path().display());
     info!("├─ Port: {}", port);
     info!(
         "└─ GPU Acceleration: {}",
         if gpu_enabled { "ON" } else { "OFF" }
     );
 
-    // TODO: Initialize core systems
-    initialize_core_systems(mode, config_path, gpu_enabled).await?;
+    let config = initialize_core_systems(mode, config_source, gpu_enabled).await?;
+
+    if let Some(metrics_port) = metrics_port {
+        let bind = config
+            .as_ref()
+            .map(|config| config.monitoring.clone())
+            .unwrap_or_default()
+            .metrics_bind;
+        start_metrics_server(SocketAddr::new(bind, metrics_port))?;
+    }
 
     // TODO: Start trading engine based on mode
     match mode {
@@ -232,30 +430,416 @@ async fn start_trading_engine(
         }
     }
 
+    let mut builder = Engine::builder(mode.into())
+        .memory_backend(Arc::clone(&memory_system()?.backend))
+        .compute_backend(core::compute::select_backend(gpu_enabled))
+        .rng(rng)
+        .feed_spawner(|task| launch_feed(task).map_err(anyhow::Error::from))
+        .risk_engine(match &config {
+            Some(config) => RiskEngine::from_settings(config.risk_settings()?),
+            None => RiskEngine::default(),
+        });
+    let (journal, snapshots, mean_reversion) = match &config {
+        Some(config) => (
+            config.journal_config()?,
+            config.snapshot_config()?,
+            config.mean_reversion_config()?,
+        ),
+        None => (None, None, None),
+    };
+    if let Some((dir, journal)) = journal {
+        builder = builder.journal(dir, journal);
+    }
+    if let Some((dir, snapshots)) = snapshots {
+        builder = builder.snapshots(dir, snapshots);
+    }
+    if let Some(mean_reversion) = mean_reversion {
+        info!("🧠 Mean reversion on {}", mean_reversion.symbol);
+        builder = builder.strategy(MeanReversionStrategy::new(mean_reversion));
+    }
+    let mut engine = builder.build()?;
+    info!("🎲 RNG seed: {} (replay with --seed)", engine.rng().seed());
+    info!(
+        "📨 Event bus ready (capacity {}, {} when full)",
+        engine.event_bus().capacity(),
+        engine.event_bus().policy()
+    );
+    info!("🧮 Compute backend: {}", engine.compute().name());
+
+    if let Some(config) = config {
+        let watcher = ConfigWatcher::new(config_source.clone(), config)
+            .observe(Arc::new(engine.risk().clone()))
+            .observe(Arc::new(apply_log_level));
+        match Arc::new(watcher).monitor() {
+            Ok(task) => {
+                info!("👀 Watching {} for changes", config_source.path().display());
+                tokio::spawn(task);
+            }
+            Err(e) => warn!("Config hot reload disabled: {}", e),
+        }
+    }
+
+    if matches!(mode, ExecutionMode::Paper) {
+        start_market_data_feed(feed, &mut engine)?;
+    }
+
     // Keep the application running
     info!("✅ ShrivenQ Nexus is running on port {}", port);
     info!("Press Ctrl+C to stop...");
+    if let Some(max_runtime) = max_runtime {
+        info!("⏱️  Stopping automatically after {:?}", max_runtime);
+    }
 
-    // TODO: Implement proper signal handling and graceful shutdown
-    tokio::signal::ctrl_c().await?;
+    engine.run(shutdown_signal(max_runtime)).await?;
     info!("🛑 Shutting down ShrivenQ Nexus...");
 
+    let report = engine.shutdown();
+    info!(
+        "└─ Session: {} mode, {} memory, uptime {}μs, {} events ({} dropped)",
+        report.mode,
+        report.memory_backend,
+        report.uptime_micros,
+        report.events_published,
+        report.events_dropped
+    );
+
     Ok(())
 }
 
+/// Resolves on Ctrl+C, or once `max_runtime` has elapsed if one is set
+async fn shutdown_signal(max_runtime: Option<Duration>) {
+    let deadline = async {
+        match max_runtime {
+            Some(max_runtime) => tokio::time::sleep(max_runtime).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if let Err(e) = result {
+                warn!("Failed to listen for Ctrl+C: {}", e);
+            }
+        }
+        () = deadline => info!("⏱️  Max runtime reached"),
+    }
+}
+
+/// Parse `--max-runtime`: a whole number with an `ms`, `s`, `m` or `h` suffix
+fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("expected a number followed by ms, s, m or h, got '{s}'"))?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        "m" => value
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or("duration too long")?,
+        "h" => value
+            .checked_mul(3600)
+            .map(Duration::from_secs)
+            .ok_or("duration too long")?,
+        "" => return Err(format!("missing unit in '{s}', expected ms, s, m or h")),
+        other => return Err(format!("unknown unit '{other}', expected ms, s, m or h")),
+    };
+    if duration.is_zero() {
+        return Err("duration must be greater than zero".to_string());
+    }
+    Ok(duration)
+}
+
+/// The validated, layered config, or `None` when there is no file and no
+/// `SHRIVENQ_*` variable to build one from
+fn load_config(config_source: &ConfigSource) -> Result<Option<Config>> {
+    let path = config_source.path().display();
+    let config = match config_source.load() {
+        Ok(config) => config,
+        Err(ConfigError::Io { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => {
+            let vars: Vec<&str> = config_source.env_vars().collect();
+            if vars.is_empty() {
+                warn!("Config file {} not found; running with defaults", path);
+                return Ok(None);
+            }
+            warn!(
+                "Config file {} not found; running with defaults and {}",
+                path,
+                vars.join(", ")
+            );
+            config_source.load_defaults()?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    config.validate()?;
+    for var in config_source.env_vars() {
+        info!("├─ {} overrides the config file", var);
+    }
+    Ok(Some(config))
+}
+
+// RUST_LOG, then `level`, keeping our own logs at info or finer
+fn log_filter(level: &str) -> Result<EnvFilter, ConfigError> {
+    let directive = |directive: &str| {
+        directive.parse().map_err(|e| ConfigError::Invalid {
+            field: "log level".into(),
+            reason: format!("{directive}: {e}"),
+        })
+    };
+    Ok(EnvFilter::from_default_env()
+        .add_directive(directive(level)?)
+        .add_directive(directive("shriven_q=info")?))
+}
+
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+type LogReloader = Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
+static LOG_RELOADER: OnceCell<LogReloader> = OnceCell::new();
+
+fn set_log_reloader(
+    reload: impl Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync + 'static,
+) {
+    let _ = LOG_RELOADER.set(Box::new(reload));
+}
+
+// Config observer switching the log filter when `system.log_level` changes
+fn apply_log_level(previous: &Config, config: &Config) -> Result<(), ConfigError> {
+    let level = &config.system.log_level;
+    if previous.system.log_level == *level {
+        return Ok(());
+    }
+    set_log_level(level)
+}
+
+fn set_log_level(level: &str) -> Result<(), ConfigError> {
+    let Some(reload) = LOG_RELOADER.get() else {
+        return Ok(());
+    };
+    let filter = log_filter(level)?;
+    reload(filter).map_err(|e| ConfigError::Invalid {
+        field: "system.log_level".into(),
+        reason: e.to_string(),
+    })?;
+    info!("Log level set to {}", level);
+    Ok(())
+}
+
+/// Seed from `--seed`, else `execution_modes.simulation.seed` in the config
+/// file, else a random one
+fn session_rng(cli_seed: Option<u64>, config_path: &str) -> Result<RngService> {
+    if let Some(seed) = cli_seed {
+        return Ok(RngService::new(seed));
+    }
+
+    let config = match std::fs::read_to_string(config_path) {
+        Ok(text) => match text.parse::<toml::Table>() {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Ignoring unparseable config {}: {}", config_path, e);
+                return Ok(RngService::from_entropy());
+            }
+        },
+        Err(_) => return Ok(RngService::from_entropy()),
+    };
+    let seed = config
+        .get("execution_modes")
+        .and_then(|modes| modes.get("simulation"))
+        .and_then(|simulation| simulation.get("seed"));
+    match seed {
+        None => Ok(RngService::from_entropy()),
+        Some(value) => value
+            .as_integer()
+            .and_then(|seed| u64::try_from(seed).ok())
+            .map(RngService::new)
+            .ok_or_else(|| {
+                ConfigError::Invalid {
+                    field: "execution_modes.simulation.seed".into(),
+                    reason: format!("{config_path}: must be a non-negative integer"),
+                }
+                .into()
+            }),
+    }
+}
+
+fn start_market_data_feed(args: &FeedArgs, engine: &mut Engine) -> Result<()> {
+    // Register subscribed symbols up front so the feed only ever reads the registry
+    core::types::SymbolRegistry::global()
+        .intern_all(&args.symbols)
+        .map_err(core::feeds::FeedError::from)?;
+
+    if let Some(path) = &args.replay {
+        let pacer = core::replay::ReplayPacer::new(1.0)?;
+        let player = core::feeds::FeedPlayer::open(path)?.with_pacer(pacer);
+        info!("⏪ Replaying feed log {}", path.display());
+        return spawn_feed(player, args, engine);
+    }
+
+    match args.feed {
+        #[cfg(feature = "binance-integration")]
+        FeedVenue::Binance => spawn_feed(core::feeds::BinanceFeed::new(), args, engine),
+        #[cfg(feature = "zerodha-integration")]
+        FeedVenue::Kite => spawn_feed(kite_feed_from_env()?, args, engine),
+        #[allow(unreachable_patterns)]
+        venue => {
+            warn!(
+                "No market data for {}: built without the {:?} feed",
+                args.symbols.join(", "),
+                venue
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Hand `feed` to the engine under a staleness watchdog that drops the
+/// engine back to paper trading if the feed dies, capturing the feed first
+/// if asked
+fn spawn_feed<F: core::feeds::MarketDataFeed + 'static>(
+    feed: F,
+    args: &FeedArgs,
+    engine: &mut Engine,
+) -> Result<()> {
+    use core::feeds::{FeedWatchdog, WatchdogConfig};
+
+    info!("📡 Streaming {} market data", feed.name());
+    let watchdog = Arc::new(
+        FeedWatchdog::new(feed.name(), WatchdogConfig::default()).with_safe_mode(
+            engine.mode_switcher(),
+            core::execution::ExecutionMode::Paper,
+        ),
+    );
+    let feed = watchdog.wrap(feed);
+    tokio::spawn(watchdog.monitor());
+
+    let symbols = args.symbols.clone();
+    match &args.record {
+        Some(path) => {
+            let recorder = core::feeds::FeedRecorder::new(feed, path)?;
+            info!("⏺️  Recording feed to {}", path.display());
+            engine.add_feed(recorder, symbols);
+        }
+        None => engine.add_feed(feed, symbols),
+    }
+    Ok(())
+}
+
+/// Run a feed task on the runtime, or under `--cpu-affinity` on a thread of
+/// its own pinned to the feed core
+fn launch_feed(task: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+    let Some(affinity) = CPU_AFFINITY.get() else {
+        tokio::spawn(task);
+        return Ok(());
+    };
+
+    let cpu = affinity.feed_cpu();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| {
+            ShrivenError::Other(anyhow::Error::new(e).context("failed to start feed runtime"))
+        })?;
+    std::thread::Builder::new()
+        .name("shriven-feed".into())
+        .spawn(move || {
+            pin_current_thread("feed", &[cpu]);
+            runtime.block_on(task);
+        })
+        .map_err(|e| {
+            ShrivenError::Other(anyhow::Error::new(e).context("failed to start feed thread"))
+        })?;
+    Ok(())
+}
+
+static CPU_AFFINITY: OnceCell<CpuAffinity> = OnceCell::new();
+
+/// Pin the calling thread to `cpus` and log its resulting mask. Once memory
+/// is up this goes through the backend, so a NUMA backend also binds the
+/// thread to the node it runs on.
+fn pin_current_thread(role: &str, cpus: &[usize]) {
+    let pinned = match memory_system() {
+        Ok(memory) => memory
+            .backend
+            .pin_current_thread(cpus)
+            .map_err(|e| e.to_string()),
+        Err(_) => affinity::set_current_thread_affinity(cpus).map_err(|e| e.to_string()),
+    };
+    match pinned.and_then(|()| affinity::current_thread_affinity().map_err(|e| e.to_string())) {
+        Ok(mask) => info!("📌 {} thread pinned to CPUs {:?}", role, mask),
+        Err(e) => warn!("Failed to pin {} thread to CPUs {:?}: {}", role, cpus, e),
+    }
+}
+
+#[cfg(feature = "zerodha-integration")]
+fn kite_feed_from_env() -> Result<core::feeds::KiteFeed> {
+    let var = |name: &str| {
+        std::env::var(name).map_err(|_| ConfigError::Invalid {
+            field: name.into(),
+            reason: "must be set for the Kite feed".into(),
+        })
+    };
+    let instruments = core::feeds::kite::load_instruments(var("KITE_INSTRUMENTS")?)?;
+    Ok(core::feeds::KiteFeed::new(
+        var("KITE_API_KEY")?,
+        var("KITE_ACCESS_TOKEN")?,
+        instruments,
+    ))
+}
+
+#[cfg(feature = "metrics-http")]
+fn start_metrics_server(addr: SocketAddr) -> Result<()> {
+    let backend = Arc::clone(&memory_system()?.backend);
+    tokio::spawn(async move {
+        if let Err(e) = core::metrics::serve(addr, backend).await {
+            warn!("Metrics endpoint stopped: {}", e);
+        }
+    });
+    info!("📈 Prometheus metrics at http://{}/metrics", addr);
+    Ok(())
+}
+
+#[cfg(not(feature = "metrics-http"))]
+fn start_metrics_server(addr: SocketAddr) -> Result<()> {
+    warn!(
+        "Metrics port {} ignored: built without the metrics-http feature",
+        addr.port()
+    );
+    Ok(())
+}
+
+/// Returns the validated config file, `None` if there is none
 async fn initialize_core_systems(
     mode: ExecutionMode,
-    config_path: &str,
+    config_source: &ConfigSource,
     gpu_enabled: bool,
-) -> Result<()> {
+) -> Result<Option<Config>> {
     info!("⚙️  Initializing core systems...");
 
-    // TODO: Load configuration
-    info!("├─ Loading configuration from: {}", config_path);
+    info!(
+        "├─ Loading configuration from: {}",
+        config_source.path().display()
+    );
+    let config = load_config(config_source)?;
+
+    // Logging started before the config was read, at --log-level or the default
+    if let Some(config) = &config
+        && config_source.cli().log_level.is_none()
+        && config.system.log_level != DEFAULT_LOG_LEVEL
+    {
+        set_log_level(&config.system.log_level)?;
+    }
 
     // Initialize memory pools
-    info!("├─ Initializing lock-free memory pools...");
-    initialize_memory_system().await?;
+    info!("├─ Initializing memory pools...");
+    let memory = config
+        .as_ref()
+        .map(|config| config.memory.clone())
+        .unwrap_or_default();
+    initialize_memory_system(&memory).await?;
+    log_numa_topology(&NumaTopology::discover());
 
     // TODO: Initialize networking
     info!("├─ Setting up ultra-low latency networking...");
@@ -278,12 +862,14 @@ async fn initialize_core_systems(
         }
         ExecutionMode::Live => {
             info!("├─ Establishing exchange connections...");
-            info!("├─ Initializing risk management systems...");
         }
     }
 
+    // Risk checks gate order routing in every mode, not just live
+    info!("├─ Initializing risk management systems...");
+
     info!("└─ Core systems initialized successfully");
-    Ok(())
+    Ok(config)
 }
 
 async fn run_benchmarks(iterations: u32) -> Result<()> {
@@ -294,6 +880,14 @@ async fn run_benchmarks(iterations: u32) -> Result<()> {
 
     // TODO: Implement comprehensive benchmarks
     info!("├─ Order book insertion latency...");
+    let book_ns = bench_order_book(iterations)?;
+    info!("│  └─ {:.1}ns per level update", book_ns);
+    info!("├─ Sharded order book parallel updates...");
+    let (threads, sharded_ns) = bench_sharded_book(iterations)?;
+    info!(
+        "│  └─ {:.1}ns per level update across {} threads, no updates lost",
+        sharded_ns, threads
+    );
     info!("├─ Market data processing throughput...");
     info!("├─ GPU computation performance...");
     info!("├─ Risk calculation speed...");
@@ -308,23 +902,251 @@ async fn run_benchmarks(iterations: u32) -> Result<()> {
     Ok(())
 }
 
-async fn validate_configuration(config_path: &str) -> Result<()> {
-    info!("🔧 Validating configuration: {}", config_path);
+/// Average nanoseconds per `OrderBook::apply_level` over a book that keeps
+/// inserting, updating and removing levels around a moving mid
+fn bench_order_book(iterations: u32) -> Result<f64> {
+    use crate::core::book::OrderBook;
+    use crate::core::events::Side;
+    use crate::core::time::PrecisionTimer;
+    use crate::core::types::{Px, Qty, SymbolId};
+
+    let symbol = SymbolId::intern("BENCH").map_err(anyhow::Error::from)?;
+    let mut book = OrderBook::new(symbol);
+    let timer = PrecisionTimer::start();
+    for i in 0..iterations {
+        let offset = i64::from(i % 64);
+        let side = if i % 2 == 0 { Side::Bid } else { Side::Ask };
+        let price = match side {
+            Side::Bid => Px::from_raw(10_000_000_000 - offset * 1_000_000),
+            Side::Ask => Px::from_raw(10_001_000_000 + offset * 1_000_000),
+        };
+        // Every fourth update clears its level so removals are measured too
+        let size = if i % 4 == 3 {
+            Qty::ZERO
+        } else {
+            Qty::from_raw(i64::from(i) + 1)
+        };
+        let _ = book.apply_level(side, price, size);
+    }
+
+    Ok(timer.elapsed_nanos() as f64 / f64::from(iterations.max(1)))
+}
+
+/// Wall-clock nanoseconds per `ShardedBook::apply_level` with one thread per
+/// core all updating the same symbols. Each thread owns its own price levels,
+/// so the final books are known exactly; any lost or misplaced update fails
+/// the run. Returns the thread count and the average.
+fn bench_sharded_book(iterations: u32) -> Result<(usize, f64)> {
+    use crate::core::book::{ShardedBook, ShardedBookConfig, TopOfBook};
+    use crate::core::events::Side;
+    use crate::core::time::PrecisionTimer;
+    use crate::core::types::{Px, Qty, SymbolId};
+
+    const SYMBOLS: usize = 64;
+    const LEVELS: u32 = 16;
+    // Raw price gap between threads, so their levels never collide
+    const THREAD_STRIDE: i64 = 1_000_000_000;
+
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get().clamp(2, 16));
+    let symbols = (0..SYMBOLS)
+        .map(|i| SymbolId::intern(&format!("BENCH{}", i)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::from)?;
+    let book = ShardedBook::new(ShardedBookConfig::default());
+    // Enough for every thread to finish at least one full cycle of levels
+    let per_thread = (iterations / threads as u32).max(LEVELS * SYMBOLS as u32);
+
+    // Level `j` of thread `t`: bids below 10_000, asks above, stride apart
+    let price = |side: Side, thread: usize, j: u32| {
+        let offset = thread as i64 * THREAD_STRIDE + i64::from(j) * 1_000_000;
+        match side {
+            Side::Bid => Px::from_raw(10_000_000_000_000 - offset),
+            Side::Ask => Px::from_raw(10_001_000_000_000 + offset),
+        }
+    };
+    // Thread `t`'s last write to level `j` of any symbol is always `t + 1`
+    let size = |thread: usize, i: u32| {
+        let last = per_thread - 1 - i < LEVELS * SYMBOLS as u32;
+        Qty::from_raw(if last {
+            thread as i64 + 1
+        } else {
+            i64::from(i) + 100
+        })
+    };
 
-    // TODO: Implement configuration validation
-    info!("├─ Checking execution mode settings...");
-    info!("├─ Validating exchange configurations...");
-    info!("├─ Verifying GPU settings...");
-    info!("├─ Testing network connectivity...");
-    info!("└─ Validating risk limits...");
+    let timer = PrecisionTimer::start();
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let (book, symbols) = (&book, &symbols);
+            let _ = scope.spawn(move || {
+                for i in 0..per_thread {
+                    let symbol = symbols[i as usize % SYMBOLS];
+                    let j = i / SYMBOLS as u32 % LEVELS;
+                    let side = if j % 2 == 0 { Side::Bid } else { Side::Ask };
+                    let _ = book.apply_level(symbol, side, price(side, thread, j), size(thread, i));
+                    let _ = book.best_bid(symbol);
+                }
+            });
+        }
+    });
+    let elapsed = timer.elapsed_nanos() as f64;
+
+    // Every symbol saw each thread's full cycle of levels
+    for &symbol in &symbols {
+        let expected = TopOfBook {
+            bid: Some((price(Side::Bid, 0, 0), Qty::from_raw(1))),
+            ask: Some((price(Side::Ask, 0, 1), Qty::from_raw(1))),
+        };
+        if book.top_of_book(symbol) != expected {
+            return Err(anyhow::anyhow!(
+                "sharded book top of {} is {:?}, expected {:?}",
+                symbol,
+                book.top_of_book(symbol),
+                expected
+            )
+            .into());
+        }
+        for side in [Side::Bid, Side::Ask] {
+            let levels: Vec<_> = book
+                .with_book(symbol, |b| b.levels(side).collect())
+                .unwrap_or_default();
+            let expected = threads * LEVELS as usize / 2;
+            if levels.len() != expected {
+                return Err(anyhow::anyhow!(
+                    "sharded book {} has {} {:?} levels, expected {}",
+                    symbol,
+                    levels.len(),
+                    side,
+                    expected
+                )
+                .into());
+            }
+            for thread in 0..threads {
+                for j in (0..LEVELS).filter(|j| (j % 2 == 0) == (side == Side::Bid)) {
+                    let want = (price(side, thread, j), Qty::from_raw(thread as i64 + 1));
+                    if !levels.contains(&want) {
+                        return Err(anyhow::anyhow!(
+                            "sharded book {} lost thread {}'s last update to {:?} level {}",
+                            symbol,
+                            thread,
+                            side,
+                            j
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((
+        threads,
+        elapsed / f64::from((per_thread * threads as u32).max(1)),
+    ))
+}
+
+/// Initialize everything a real start would - memory, configuration, engine -
+/// without running the engine or connecting to exchanges. Stops at the first
+/// failing step.
+async fn run_preflight(
+    mode: ExecutionMode,
+    config_source: &ConfigSource,
+    gpu_enabled: bool,
+) -> Result<()> {
+    use crate::core::time::PrecisionTimer;
+
+    info!("🛫 Preflight check in {} mode", mode);
+    let timer = PrecisionTimer::start();
+    let mut passed = Vec::new();
+    let failed = |step: &str| warn!("✗ Preflight failed at {}", step);
+
+    initialize_core_systems(mode, config_source, gpu_enabled)
+        .await
+        .inspect_err(|_| failed("core system initialization"))?;
+    passed.push("core systems");
+
+    validate_configuration(config_source)
+        .await
+        .inspect_err(|_| failed("configuration validation"))?;
+    passed.push("configuration");
+
+    // Exercise real allocation rather than trusting the pool's construction
+    let backend = Arc::clone(&memory_system()?.backend);
+    let layout =
+        Layout::from_size_align(256, 1).map_err(|e| AllocError::InvalidLayout(e.to_string()))?;
+    let ptr = backend
+        .allocate(layout)
+        .inspect_err(|_| failed("test allocation from the memory backend"))?;
+    backend.deallocate(ptr, layout);
+    passed.push("memory allocation");
+
+    let engine = Engine::builder(mode.into())
+        .memory_backend(backend)
+        .compute_backend(core::compute::select_backend(gpu_enabled))
+        .build()
+        .inspect_err(|_| failed("engine construction"))?;
+    passed.push("engine");
+
+    info!("✅ Preflight passed in {}μs", timer.elapsed_micros());
+    info!(
+        "└─ {} mode ready: {} (event bus capacity {})",
+        engine.mode(),
+        passed.join(", "),
+        engine.event_bus().capacity()
+    );
+    Ok(())
+}
+
+async fn validate_configuration(config_source: &ConfigSource) -> Result<()> {
+    info!(
+        "🔧 Validating configuration: {}",
+        config_source.path().display()
+    );
+
+    let config = config_source.load()?;
+    config.validate()?;
+    let risk = config.risk_settings()?;
+    info!("├─ Log level: {}", config.system.log_level);
+    info!("├─ Worker threads: {}", config.system.worker_threads);
+    info!(
+        "├─ Memory pools: {} host, {} GPU",
+        config.system.max_memory_pool_size, config.gpu.memory_pool_size
+    );
+    info!("├─ Memory backend: {}", config.memory.backend);
+    info!(
+        "└─ Risk limits: position {:?}, order size {:?}, daily loss {:?}, {} symbol overrides",
+        risk.default_limits.max_position,
+        risk.default_limits.max_order_size,
+        risk.max_daily_loss,
+        risk.symbol_limits.len()
+    );
 
     info!("✅ Configuration is valid and ready for use");
     Ok(())
 }
 
-use crate::core::memory::{AllocError, MemoryBackend, SafePoolConfig};
+use crate::core::config::{CliOverrides, Config, ConfigError, ConfigSource, ConfigWatcher};
+use crate::core::cpu::CpuFeatures;
+use crate::core::cpu::affinity::{self, CpuAffinity};
+use crate::core::diagnostics::FlightRecorder;
+use crate::core::engine::Engine;
+use crate::core::error::{Result, ShrivenError};
+use crate::core::execution::rng::RngService;
+use crate::core::memory::{
+    AllocError, MemoryAllocator, MemoryBackend, MemoryBudget, MemoryConfig, NumaTopology,
+};
+use crate::core::risk::RiskEngine;
+use crate::core::strategy::MeanReversionStrategy;
 use once_cell::sync::OnceCell;
+use std::alloc::Layout;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::fmt::SubscriberBuilder;
+use tracing_subscriber::fmt::format::{DefaultFields, Format, Json, JsonFields};
+use tracing_subscriber::{EnvFilter, reload};
 
 #[derive(Debug)]
 pub struct MemorySystem {
@@ -337,39 +1159,28 @@ pub fn memory_system() -> Result<&'static MemorySystem, AllocError> {
     MEMORY_SYSTEM.get().ok_or(AllocError::NotInitialized)
 }
 
-async fn initialize_memory_system() -> Result<()> {
-    // Choose memory backend based on feature flags
-    let backend = if cfg!(feature = "hft-unsafe") {
-        // Use high-performance lock-free pool when hft-unsafe is enabled
-        #[cfg(feature = "hft-unsafe")]
-        {
-            use crate::core::memory::PoolConfig;
-            let config = PoolConfig {
-                chunk_size: 4096,
-                initial_chunks: 1024,
-                max_chunks: 100_000,
-                alignment: 64,
-                zero_on_dealloc: false,
-                thread_cache_size: 32,
-            };
-            let backend = MemoryBackend::lock_free(config)?;
-            info!("   ├─ Lock-free memory pool initialized (HIGH PERFORMANCE MODE)");
-            backend
-        }
-        #[cfg(not(feature = "hft-unsafe"))]
-        unreachable!()
+async fn initialize_memory_system(config: &MemoryConfig) -> Result<()> {
+    let backend = MemoryBackend::from_config(config)?;
+    if backend.is_unsafe() {
+        info!(
+            "   ├─ {} memory pool initialized (HIGH PERFORMANCE MODE)",
+            config.backend
+        );
     } else {
-        // Default to safe memory pool
-        let config = SafePoolConfig {
-            chunk_size: 4096,
-            initial_chunks: 1024,
-            max_chunks: 100_000,
-            zero_on_dealloc: false,
-        };
-        let backend = MemoryBackend::safe(config)?;
         info!("   ├─ Safe memory pool initialized (SAFE MODE)");
-        backend
-    };
+    }
+
+    // Fault the pool's pages in now instead of during the session
+    #[cfg(feature = "hft-unsafe")]
+    if let MemoryBackend::LockFree(pool) = &backend {
+        let timer = crate::core::time::PrecisionTimer::start();
+        let chunks = pool.prefault();
+        info!(
+            "   ├─ Prefaulted {} chunks in {}μs",
+            chunks,
+            timer.elapsed_micros()
+        );
+    }
 
     info!("   ├─ Memory backend: {}", backend.backend_type());
     info!(
@@ -381,10 +1192,19 @@ async fn initialize_memory_system() -> Result<()> {
         }
     );
 
+    let backend = Arc::new(backend);
+    if let Some(budget) = MemoryBudget::from_config(&config.budget)? {
+        let budget = Arc::new(budget).install()?;
+        budget.register(&backend);
+        info!(
+            "   ├─ Memory budget: {} MB ({} MB in use)",
+            budget.ceiling() / (1024 * 1024),
+            budget.used() / (1024 * 1024)
+        );
+    }
+
     // Store the memory system globally
-    let memory_system = MemorySystem {
-        backend: Arc::new(backend),
-    };
+    let memory_system = MemorySystem { backend };
 
     MEMORY_SYSTEM
         .set(memory_system)
@@ -393,7 +1213,52 @@ async fn initialize_memory_system() -> Result<()> {
     Ok(())
 }
 
-async fn show_system_info() -> Result<()> {
+/// Nodes and distances, flagging a simulated topology so operators know
+/// NUMA placement is not real
+fn log_numa_topology(topology: &NumaTopology) {
+    if topology.is_simulated() {
+        warn!("├─ NUMA: {}", topology.summary());
+    } else {
+        info!("├─ NUMA: {}", topology.summary());
+    }
+    for line in topology.to_string().lines() {
+        info!("│  {}", line);
+    }
+}
+
+async fn show_system_info(json: bool) -> Result<()> {
+    let cpu = CpuFeatures::detect();
+    let mut features = Vec::new();
+    if cfg!(feature = "gpu-acceleration") {
+        features.push("GPU");
+    }
+    if cfg!(feature = "high-performance") {
+        features.push("High-Performance");
+    }
+    if cfg!(feature = "zerodha-integration") {
+        features.push("Zerodha");
+    }
+    if cfg!(feature = "binance-integration") {
+        features.push("Binance");
+    }
+
+    if json {
+        let info = serde_json::json!({
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "rust_version": env!("CARGO_PKG_RUST_VERSION"),
+            "build_profile": if cfg!(debug_assertions) { "debug" } else { "release" },
+            "enabled_features": features,
+            "global_allocator": core::memory::global_allocator_name(),
+            "cpu": cpu,
+            "memory_layout": core::memory::layout_report(),
+            "numa": NumaTopology::discover().to_json(),
+        });
+        let info = serde_json::to_string_pretty(&info).map_err(anyhow::Error::from)?;
+        println!("{}", info);
+        return Ok(());
+    }
+
     info!("ℹ️  ShrivenQ Nexus System Information");
 
     // System information
@@ -412,22 +1277,24 @@ async fn show_system_info() -> Result<()> {
         }
     );
 
-    // Feature flags
-    let mut features = Vec::new();
-    if cfg!(feature = "gpu-acceleration") {
-        features.push("GPU");
-    }
-    if cfg!(feature = "high-performance") {
-        features.push("High-Performance");
-    }
-    if cfg!(feature = "zerodha-integration") {
-        features.push("Zerodha");
-    }
-    if cfg!(feature = "binance-integration") {
-        features.push("Binance");
-    }
-
     info!("├─ Enabled Features: {}", features.join(", "));
+    info!(
+        "├─ Global Allocator: {}",
+        core::memory::global_allocator_name()
+    );
+    info!(
+        "├─ CPU Features: {} (SIMD: {})",
+        cpu.features.join(", "),
+        cpu.simd_level
+    );
+
+    log_numa_topology(&NumaTopology::discover());
+    for layout in core::memory::layout_report() {
+        info!(
+            "├─ Layout {}: {} bytes, align {}",
+            layout.name, layout.size, layout.align
+        );
+    }
 
     // Performance capabilities
     info!("├─ Expected Latency: < 100 microseconds");
@@ -437,3 +1304,121 @@ async fn show_system_info() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_fires_once_max_runtime_elapses() {
+        let start = Instant::now();
+        shutdown_signal(Some(Duration::from_secs(30))).await;
+        let elapsed = start.elapsed();
+        let expected = Duration::from_secs(30)..Duration::from_secs(31);
+        assert!(expected.contains(&elapsed), "stopped after {elapsed:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_waits_without_max_runtime() {
+        let waited = tokio::time::timeout(Duration::from_secs(24 * 3600), shutdown_signal(None));
+        assert!(waited.await.is_err());
+    }
+
+    #[test]
+    fn durations_parse_with_each_unit() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 5m "), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+    }
+
+    #[test]
+    fn bad_durations_are_rejected() {
+        for bad in ["", "30", "s", "1.5s", "-1s", "10d", "0s", "30 s"] {
+            assert!(parse_duration(bad).is_err(), "accepted '{bad}'");
+        }
+        assert_eq!(
+            parse_duration(&format!("{}h", u64::MAX)),
+            Err("duration too long".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn preflight_passes_in_paper_mode_with_the_default_config() {
+        let config = ConfigSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/config/default.toml"));
+
+        run_preflight(ExecutionMode::Paper, &config, false)
+            .await
+            .unwrap();
+
+        // The allocator it exercised stays installed for the real start
+        assert!(memory_system().is_ok());
+    }
+
+    #[test]
+    fn json_logs_are_one_object_per_line() {
+        #[derive(Clone, Default)]
+        struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = json_logs(
+            log_subscriber("info")
+                .unwrap()
+                .with_writer(move || writer.clone()),
+        )
+        .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            info!(mode = "paper", gpu = false, "ShrivenQ Nexus starting");
+            warn!("├─ NUMA: simulated");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "ShrivenQ Nexus starting");
+        assert_eq!(lines[0]["mode"], "paper");
+        assert_eq!(lines[0]["gpu"], false);
+        assert_eq!(lines[1]["level"], "WARN");
+        for line in &lines {
+            assert!(line["threadId"].is_string(), "{line}");
+            assert!(line["line_number"].is_u64(), "{line}");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cpu_affinity_pins_the_main_and_runtime_threads() {
+        use crate::core::cpu::affinity::current_thread_affinity;
+
+        // Stands in for the process main thread, so the test harness stays unpinned
+        std::thread::spawn(|| {
+            let cpu = current_thread_affinity().unwrap()[0];
+            let runtime = build_runtime(&[cpu]).unwrap();
+            assert_eq!(current_thread_affinity().unwrap(), [cpu]);
+
+            let worker = runtime
+                .block_on(runtime.spawn(async { current_thread_affinity().unwrap() }))
+                .unwrap();
+            assert_eq!(worker, [cpu]);
+            assert_eq!(CPU_AFFINITY.get().unwrap().feed_cpu(), cpu);
+        })
+        .join()
+        .unwrap();
+    }
+}